@@ -0,0 +1,127 @@
+//! `#[command]`: `tauri::command` with every command body run under the app's panic guard.
+//!
+//! The annotated function is left as written, so Rust callers are unaffected. Next to it the
+//! attribute emits a hidden `__guarded_<name>` tauri command taking the same arguments, which
+//! calls the function through `crate::guard_command` (`crate::guard_async_command` for async
+//! functions) and rejects with `crate::crash::CommandError`. `generate_handler![<name>]` is
+//! pointed at that wrapper, so a panicking command rejects with `AppError::Internal` instead of
+//! leaving the frontend's promise pending.
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, FnArg, ItemFn, Pat, ReturnType, Type, Visibility};
+
+#[proc_macro_attribute]
+pub fn command(attributes: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+    if !attributes.is_empty() {
+        return syn::Error::new_spanned(
+            proc_macro2::TokenStream::from(attributes),
+            "#[command] takes no arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+    match guarded(&function) {
+        Ok(tokens) => quote!(#function #tokens).into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn guarded(function: &ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let sig = &function.sig;
+    let name = &sig.ident;
+    let command = name.to_string();
+    let guarded = format_ident!("__guarded_{}", name);
+    let forward = format_ident!("__cmd__{}", name);
+    let tauri_forward = format_ident!("__cmd__{}", guarded);
+    let visibility = &function.vis;
+    let macro_export = match visibility {
+        Visibility::Public(_) => quote!(#[macro_export]),
+        _ => quote!(),
+    };
+    let attrs = function
+        .attrs
+        .iter()
+        .filter(|attr| !attr.path().is_ident("doc"));
+
+    // Same argument names, so tauri reads the same keys from the invoke payload; `mut` only
+    // matters to the original body.
+    let mut inputs = Vec::new();
+    let mut args = Vec::new();
+    for input in &sig.inputs {
+        let FnArg::Typed(typed) = input else {
+            return Err(syn::Error::new_spanned(
+                input,
+                "commands cannot take `self`",
+            ));
+        };
+        let Pat::Ident(pat) = &*typed.pat else {
+            return Err(syn::Error::new_spanned(
+                &typed.pat,
+                "command arguments must be plain identifiers",
+            ));
+        };
+        let ident = &pat.ident;
+        let ty = &typed.ty;
+        inputs.push(quote!(#ident: #ty));
+        args.push(ident.clone());
+    }
+
+    let (ok, fallible) = match &sig.output {
+        ReturnType::Default => (quote!(()), false),
+        ReturnType::Type(_, ty) => match result_ok_type(ty) {
+            Some(ok) => (quote!(#ok), true),
+            None => (quote!(#ty), false),
+        },
+    };
+    let call = quote!(#name(#(#args),*));
+    let body = match (sig.asyncness.is_some(), fallible) {
+        (false, true) => quote!(crate::guard_command(#command, move || #call)),
+        (false, false) => quote!(crate::guard_command(#command, move || Ok(#call))),
+        (true, true) => quote!(crate::guard_async_command(#command, #call).await),
+        (true, false) => {
+            quote!(crate::guard_async_command(#command, async move { Ok(#call.await) }).await)
+        }
+    };
+    let asyncness = &sig.asyncness;
+
+    Ok(quote! {
+        #[doc(hidden)]
+        #(#attrs)*
+        #[::tauri::command]
+        #visibility #asyncness fn #guarded(#(#inputs),*)
+            -> ::std::result::Result<#ok, crate::crash::CommandError>
+        {
+            #body
+        }
+
+        #[doc(hidden)]
+        #macro_export
+        macro_rules! #forward {
+            ($path:path, $invoke:ident) => {
+                #tauri_forward!(#guarded, $invoke)
+            };
+        }
+        #[allow(unused_imports)]
+        #visibility use #forward;
+    })
+}
+
+/// `T` for a `Result<T, E>` return type.
+fn result_ok_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let last = path.path.segments.last()?;
+    if last.ident != "Result" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(generics) = &last.arguments else {
+        return None;
+    };
+    match generics.args.first()? {
+        syn::GenericArgument::Type(ok) => Some(ok),
+        _ => None,
+    }
+}
@@ -0,0 +1,270 @@
+//! Agent activity timeline built from session transcripts and gateway logs.
+//!
+//! Transcripts give the messages an agent handled and the tools it called; the gateway log
+//! adds scheduled task runs (cron, heartbeat) and errors. Events are ordered newest first by
+//! `(time, id)`; times are the ISO-8601 strings OpenClaw writes, which sort as text. A page's
+//! `next_cursor` is the position of its last event, so later writes never shift older pages.
+
+const SUMMARY_CHARS: usize = 160;
+const FILE_HEADER: (&str, &str) = ("==> ", " <==");
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct ActivityEvent {
+    pub id: String,
+    pub time: String,
+    /// `message`, `tool_call`, `task` or `error`.
+    pub kind: String,
+    /// Agent id, or `gateway` for events from the gateway log.
+    pub agent: String,
+    pub channel: Option<String>,
+    pub session_id: Option<String>,
+    pub summary: String,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ActivityPage {
+    pub events: Vec<ActivityEvent>,
+    /// Pass back as `cursor` for the next, older page; `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+fn summarize(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= SUMMARY_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(SUMMARY_CHARS - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+fn message_text(message: &serde_json::Value) -> String {
+    match message.get("content") {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(serde_json::Value::Array(parts)) => parts
+            .iter()
+            .filter(|p| p.get("type").and_then(|t| t.as_str()).unwrap_or("text") == "text")
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => String::new(),
+    }
+}
+
+/// Splits output of the form `==> path <==\n<content>` repeated, as `tail` prints it.
+pub fn split_files(output: &str) -> Vec<(String, String)> {
+    let mut files: Vec<(String, String)> = Vec::new();
+    for line in output.lines() {
+        let header = line
+            .strip_prefix(FILE_HEADER.0)
+            .and_then(|rest| rest.strip_suffix(FILE_HEADER.1));
+        match (header, files.last_mut()) {
+            (Some(path), _) => files.push((path.to_string(), String::new())),
+            (None, Some((_, content))) => {
+                content.push_str(line);
+                content.push('\n');
+            }
+            (None, None) => {}
+        }
+    }
+    files
+}
+
+/// Events in one session transcript (`.jsonl`).
+pub fn transcript_events(
+    agent: &str,
+    session_id: &str,
+    channel: Option<&str>,
+    transcript: &str,
+) -> Vec<ActivityEvent> {
+    let mut events = Vec::new();
+    for (index, line) in transcript.lines().enumerate() {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(line.trim()) else {
+            continue;
+        };
+        if entry.get("type").and_then(|v| v.as_str()) != Some("message") {
+            continue;
+        }
+        let (Some(time), Some(message)) = (
+            entry.get("timestamp").and_then(|v| v.as_str()),
+            entry.get("message"),
+        ) else {
+            continue;
+        };
+        let event = |n: usize, kind: &str, summary: String| ActivityEvent {
+            id: format!("{}:{}:{}:{}", agent, session_id, index, n),
+            time: time.to_string(),
+            kind: kind.to_string(),
+            agent: agent.to_string(),
+            channel: channel.map(str::to_string),
+            session_id: Some(session_id.to_string()),
+            summary,
+        };
+        match message.get("role").and_then(|v| v.as_str()) {
+            Some("user") => events.push(event(0, "message", summarize(&message_text(message)))),
+            Some("assistant") => {
+                let tools = message
+                    .get("content")
+                    .and_then(|c| c.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter(|p| {
+                        matches!(
+                            p.get("type").and_then(|t| t.as_str()),
+                            Some("toolCall" | "tool_use")
+                        )
+                    })
+                    .filter_map(|p| p.get("name").and_then(|n| n.as_str()));
+                for (n, name) in tools.enumerate() {
+                    events.push(event(n + 1, "tool_call", name.to_string()));
+                }
+                let error = message
+                    .get("errorMessage")
+                    .and_then(|v| v.as_str())
+                    .or_else(|| {
+                        (message.get("stopReason").and_then(|v| v.as_str()) == Some("error"))
+                            .then_some("The model request failed")
+                    });
+                if let Some(error) = error {
+                    events.push(event(0, "error", summarize(error)));
+                }
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Task runs and errors from JSON gateway log lines.
+pub fn log_events(log: &str) -> Vec<ActivityEvent> {
+    let mut events: Vec<ActivityEvent> = Vec::new();
+    for line in log.lines() {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(line.trim()) else {
+            continue;
+        };
+        let Some(time) = entry
+            .get("time")
+            .or_else(|| entry.get("_meta").and_then(|m| m.get("date")))
+            .and_then(|v| v.as_str())
+        else {
+            continue;
+        };
+        let Some(message) = ["msg", "message", "1", "0"]
+            .iter()
+            .find_map(|key| entry.get(*key).and_then(|v| v.as_str()))
+        else {
+            continue;
+        };
+        let level = entry
+            .get("_meta")
+            .and_then(|m| m.get("logLevelName"))
+            .or_else(|| entry.get("level"))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let lower = message.to_lowercase();
+        let kind = if level == "error" || level == "fatal" {
+            "error"
+        } else if (lower.contains("cron") || lower.contains("heartbeat"))
+            && (lower.contains("run") || lower.contains("finished"))
+        {
+            "task"
+        } else {
+            continue;
+        };
+        // Numbered within the timestamp so ids stay stable as the log tail moves.
+        let n = events.iter().filter(|e| e.time == time).count();
+        events.push(ActivityEvent {
+            id: format!("gateway:{}:{}", time, n),
+            time: time.to_string(),
+            kind: kind.to_string(),
+            agent: "gateway".to_string(),
+            channel: None,
+            session_id: None,
+            summary: summarize(message),
+        });
+    }
+    events
+}
+
+fn cursor_of(event: &ActivityEvent) -> String {
+    format!("{}|{}", event.time, event.id)
+}
+
+/// Newest-first page of at most `limit` events older than `cursor`.
+pub fn page(mut events: Vec<ActivityEvent>, limit: usize, cursor: Option<&str>) -> ActivityPage {
+    events.sort_by(|a, b| (&b.time, &b.id).cmp(&(&a.time, &a.id)));
+    let older: Vec<ActivityEvent> = events
+        .into_iter()
+        .filter(|e| cursor.is_none_or(|c| cursor_of(e).as_str() < c))
+        .collect();
+    let more = older.len() > limit;
+    let events: Vec<ActivityEvent> = older.into_iter().take(limit).collect();
+    ActivityPage {
+        next_cursor: events.last().filter(|_| more).map(cursor_of),
+        events,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcript_and_log_events() {
+        let transcript = [
+            r#"{"type":"session","id":"s1"}"#,
+            r#"{"type":"message","timestamp":"2025-10-09T08:00:00.000Z","message":{"role":"user","content":[{"type":"text","text":"What's   on my calendar?"}]}}"#,
+            r#"{"type":"message","timestamp":"2025-10-09T08:00:02.000Z","message":{"role":"assistant","content":[{"type":"toolCall","name":"calendar_list"},{"type":"toolCall","name":"web_search"}]}}"#,
+            r#"{"type":"message","timestamp":"2025-10-09T08:00:05.000Z","message":{"role":"assistant","content":[],"stopReason":"error","errorMessage":"429 rate limited"}}"#,
+        ]
+        .join("\n");
+        let events = transcript_events("main", "s1", Some("telegram"), &transcript);
+        let kinds: Vec<&str> = events.iter().map(|e| e.kind.as_str()).collect();
+        assert_eq!(kinds, ["message", "tool_call", "tool_call", "error"]);
+        assert_eq!(events[0].summary, "What's on my calendar?");
+        assert_eq!(events[0].channel.as_deref(), Some("telegram"));
+        assert_eq!(events[2].summary, "web_search");
+
+        let log = [
+            r#"{"0":"cron: job daily-report run finished","_meta":{"date":"2025-10-09T09:00:00.000Z","logLevelName":"INFO"}}"#,
+            r#"{"0":"gateway listening","_meta":{"date":"2025-10-09T09:00:01.000Z","logLevelName":"INFO"}}"#,
+            r#"{"msg":"telegram: send failed","level":"error","time":"2025-10-09T09:00:02.000Z"}"#,
+        ]
+        .join("\n");
+        let kinds: Vec<String> = log_events(&log).into_iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, ["task", "error"]);
+
+        let files = split_files("==> main/sessions/a.jsonl <==\nline1\nline2\n==> x <==\n");
+        assert_eq!(
+            files[0],
+            (
+                "main/sessions/a.jsonl".to_string(),
+                "line1\nline2\n".to_string()
+            )
+        );
+        assert_eq!(files[1].1, "");
+    }
+
+    #[test]
+    fn test_page_with_cursor() {
+        let events: Vec<ActivityEvent> = (0..5)
+            .map(|i| ActivityEvent {
+                id: format!("e{}", i),
+                time: format!("2025-10-09T08:00:0{}.000Z", i),
+                kind: "message".to_string(),
+                agent: "main".to_string(),
+                channel: None,
+                session_id: None,
+                summary: String::new(),
+            })
+            .collect();
+        let first = page(events.clone(), 2, None);
+        assert_eq!(first.events[0].id, "e4");
+        assert_eq!(first.events[1].id, "e3");
+        let second = page(events.clone(), 2, first.next_cursor.as_deref());
+        assert_eq!(second.events[0].id, "e2");
+        let last = page(events, 2, Some("2025-10-09T08:00:01.000Z|e1"));
+        assert_eq!(last.events.len(), 1);
+        assert_eq!(last.next_cursor, None);
+    }
+}
@@ -0,0 +1,410 @@
+//! Encrypted off-machine backups of the OpenClaw config and workspace.
+//!
+//! An archive is sealed with AES-256-GCM under a key derived from the user's passphrase
+//! (PBKDF2-HMAC-SHA256), so the storage provider only ever sees ciphertext. Targets implement
+//! `BackupTarget`; `TargetConfig` lists the kinds that can be configured, currently WebDAV
+//! (Nextcloud, ownCloud, most NAS boxes). Network calls block, so callers run them off the
+//! main thread. Scheduled local archives share the naming and use `archives_to_prune` for
+//! retention.
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::Rng;
+use std::time::Duration;
+
+const MAGIC: &[u8] = b"CLAWBAK1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+pub const PBKDF2_ROUNDS: u32 = 200_000;
+pub const EXTENSION: &str = ".clawbak";
+const TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct RemoteBackup {
+    pub id: String,
+    pub size: Option<u64>,
+    /// As the server reports it, e.g. an HTTP date.
+    pub modified: Option<String>,
+}
+
+pub trait BackupTarget {
+    /// Verifies access and creates the destination if it is missing.
+    fn prepare(&self) -> Result<(), String>;
+    fn upload(&self, id: &str, data: &[u8]) -> Result<(), String>;
+    fn list(&self) -> Result<Vec<RemoteBackup>, String>;
+    fn download(&self, id: &str) -> Result<Vec<u8>, String>;
+    fn delete(&self, id: &str) -> Result<(), String>;
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum TargetConfig {
+    WebDav(WebDavTarget),
+}
+
+impl TargetConfig {
+    pub fn target(&self) -> &dyn BackupTarget {
+        match self {
+            TargetConfig::WebDav(target) => target,
+        }
+    }
+}
+
+pub fn pbkdf2_sha256(passphrase: &str, salt: &[u8], rounds: u32) -> [u8; 32] {
+    let hmac = |data: &[u8]| crate::bedrock::hmac_sha256(passphrase.as_bytes(), data);
+    let mut block = hmac(&[salt, &1u32.to_be_bytes()[..]].concat());
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&block);
+    for _ in 1..rounds {
+        block = hmac(&block);
+        key.iter_mut().zip(&block).for_each(|(k, b)| *k ^= b);
+    }
+    key
+}
+
+/// `nonce || ciphertext` of `plaintext` under `key`.
+pub fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| e.to_string())?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|e| format!("Encryption failed: {}", e))?;
+    Ok([&nonce[..], &ciphertext].concat())
+}
+
+pub fn open(key: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN {
+        return Err("Encrypted data is truncated".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    Aes256Gcm::new_from_slice(key)
+        .map_err(|e| e.to_string())?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Wrong passphrase or damaged backup".to_string())
+}
+
+/// `MAGIC || salt || seal(pbkdf2(passphrase, salt), archive)`.
+pub fn encrypt_archive(passphrase: &str, archive: &[u8]) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill(&mut salt);
+    let key = pbkdf2_sha256(passphrase, &salt, PBKDF2_ROUNDS);
+    Ok([MAGIC, &salt[..], &seal(&key, archive)?].concat())
+}
+
+pub fn decrypt_archive(passphrase: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    let body = data
+        .strip_prefix(MAGIC)
+        .filter(|body| body.len() > SALT_LEN)
+        .ok_or("Not an OpenClaw backup")?;
+    let (salt, sealed) = body.split_at(SALT_LEN);
+    open(&pbkdf2_sha256(passphrase, salt, PBKDF2_ROUNDS), sealed)
+}
+
+/// Names sort chronologically: `openclaw-YYYYMMDD-HHMMSS<extension>`.
+pub fn archive_name(now: u64, extension: &str) -> String {
+    let secs = now % 86_400;
+    format!(
+        "openclaw-{}-{:02}{:02}{:02}{}",
+        crate::civil_date_from_unix_days((now / 86_400) as i64).replace('-', ""),
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        extension
+    )
+}
+
+pub fn backup_id(now: u64) -> String {
+    archive_name(now, EXTENSION)
+}
+
+/// Days since the unix epoch of the date in an `archive_name`.
+fn archive_day(name: &str) -> Option<i64> {
+    let date = name.strip_prefix("openclaw-")?.get(..8)?;
+    if !date.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (y, m, d): (i64, i64, i64) = (
+        date[..4].parse().ok()?,
+        date[4..6].parse().ok()?,
+        date[6..].parse().ok()?,
+    );
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    Some(era * 146_097 + yoe * 365 + yoe / 4 - yoe / 100 + doy - 719_468)
+}
+
+/// Archives to delete so that only the newest archive of each of the last `keep_daily` days
+/// and of each of the last `keep_weekly` weeks remains. Names that are not archives are
+/// left alone.
+pub fn archives_to_prune(names: &[String], keep_daily: usize, keep_weekly: usize) -> Vec<String> {
+    let mut dated: Vec<(&String, i64)> = names
+        .iter()
+        .filter_map(|name| archive_day(name).map(|day| (name, day)))
+        .collect();
+    dated.sort_by(|a, b| b.0.cmp(a.0));
+    let (mut days, mut weeks) = (Vec::new(), Vec::new());
+    dated
+        .into_iter()
+        .filter(|(_, day)| {
+            let daily = days.len() < keep_daily && !days.contains(day);
+            if daily {
+                days.push(*day);
+            }
+            // Weeks start on Monday; day 0 was a Thursday.
+            let week = (day + 3).div_euclid(7);
+            let weekly = weeks.len() < keep_weekly && !weeks.contains(&week);
+            if weekly {
+                weeks.push(week);
+            }
+            !daily && !weekly
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+pub fn is_backup_id(id: &str) -> bool {
+    id.ends_with(EXTENSION)
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Inner text of each element whose local name is `name`, ignoring namespace prefixes.
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = rest[..end].split_whitespace().next().unwrap_or_default();
+        let local = tag.rsplit(':').next().unwrap_or(tag);
+        if tag.starts_with('/') || local != name || rest[..end].ends_with('/') {
+            continue;
+        }
+        let body = &rest[end + 1..];
+        let Some(close) = body.find(&format!("</{}>", tag)) else {
+            break;
+        };
+        found.push(&body[..close]);
+        rest = &body[close..];
+    }
+    found
+}
+
+/// Backups in a WebDAV `PROPFIND` (Depth: 1) response.
+pub fn parse_propfind(xml: &str) -> Vec<RemoteBackup> {
+    elements(xml, "response")
+        .into_iter()
+        .filter_map(|response| {
+            let href = elements(response, "href").first()?.trim();
+            let id = href.trim_end_matches('/').rsplit('/').next()?.to_string();
+            is_backup_id(&id).then(|| RemoteBackup {
+                id,
+                size: elements(response, "getcontentlength")
+                    .first()
+                    .and_then(|s| s.trim().parse().ok()),
+                modified: elements(response, "getlastmodified")
+                    .first()
+                    .map(|s| s.trim().to_string()),
+            })
+        })
+        .collect()
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct WebDavTarget {
+    /// Folder URL, e.g. `https://cloud.example.com/remote.php/dav/files/me/openclaw`.
+    pub url: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl WebDavTarget {
+    fn folder(&self) -> String {
+        format!("{}/", self.url.trim().trim_end_matches('/'))
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        url: &str,
+    ) -> Result<reqwest::blocking::RequestBuilder, String> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let method = reqwest::Method::from_bytes(method.as_bytes()).map_err(|e| e.to_string())?;
+        Ok(client
+            .request(method, url)
+            .basic_auth(&self.username, Some(&self.password)))
+    }
+
+    fn send(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> Result<reqwest::blocking::Response, String> {
+        let resp = request
+            .send()
+            .map_err(|e| format!("Failed to reach {}: {}", self.url, e))?;
+        match resp.status().as_u16() {
+            401 | 403 => Err("The WebDAV server rejected the username or password".to_string()),
+            _ => Ok(resp),
+        }
+    }
+
+    fn file_url(&self, id: &str) -> Result<String, String> {
+        if !is_backup_id(id) {
+            return Err(format!("Invalid backup id: {}", id));
+        }
+        Ok(format!("{}{}", self.folder(), id))
+    }
+}
+
+impl BackupTarget for WebDavTarget {
+    fn prepare(&self) -> Result<(), String> {
+        if !self.url.trim().starts_with("https://") {
+            return Err("The WebDAV URL must start with https://".to_string());
+        }
+        let resp = self.send(
+            self.request("PROPFIND", &self.folder())?
+                .header("Depth", "0"),
+        )?;
+        match resp.status().as_u16() {
+            200..=299 => Ok(()),
+            404 => {
+                let resp = self.send(self.request("MKCOL", &self.folder())?)?;
+                if resp.status().is_success() {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "Could not create the backup folder (HTTP {})",
+                        resp.status().as_u16()
+                    ))
+                }
+            }
+            code => Err(format!("The WebDAV server returned HTTP {}", code)),
+        }
+    }
+
+    fn upload(&self, id: &str, data: &[u8]) -> Result<(), String> {
+        let resp = self.send(
+            self.request("PUT", &self.file_url(id)?)?
+                .body(data.to_vec()),
+        )?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Upload failed with HTTP {}",
+                resp.status().as_u16()
+            ))
+        }
+    }
+
+    fn list(&self) -> Result<Vec<RemoteBackup>, String> {
+        let resp = self.send(
+            self.request("PROPFIND", &self.folder())?
+                .header("Depth", "1"),
+        )?;
+        match resp.status().as_u16() {
+            404 => Ok(Vec::new()),
+            200..=299 => {
+                let mut backups = parse_propfind(&resp.text().map_err(|e| e.to_string())?);
+                backups.sort_by(|a, b| b.id.cmp(&a.id));
+                Ok(backups)
+            }
+            code => Err(format!("Listing backups failed with HTTP {}", code)),
+        }
+    }
+
+    fn download(&self, id: &str) -> Result<Vec<u8>, String> {
+        let resp = self.send(self.request("GET", &self.file_url(id)?)?)?;
+        if !resp.status().is_success() {
+            return Err(format!(
+                "Download failed with HTTP {}",
+                resp.status().as_u16()
+            ));
+        }
+        resp.bytes()
+            .map(|b| b.to_vec())
+            .map_err(|e| format!("Download failed: {}", e))
+    }
+
+    fn delete(&self, id: &str) -> Result<(), String> {
+        let resp = self.send(self.request("DELETE", &self.file_url(id)?)?)?;
+        match resp.status().as_u16() {
+            200..=299 | 404 => Ok(()),
+            code => Err(format!("Deleting {} failed with HTTP {}", id, code)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_encryption_round_trip() {
+        let archive = b"tar.gz bytes".to_vec();
+        let sealed = encrypt_archive("correct horse", &archive).unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert_eq!(decrypt_archive("correct horse", &sealed).unwrap(), archive);
+        assert!(decrypt_archive("wrong", &sealed).is_err());
+        assert!(decrypt_archive("correct horse", b"plain").is_err());
+        // RFC 7914 section 11 PBKDF2-HMAC-SHA256 vector.
+        assert_eq!(
+            pbkdf2_sha256("passwd", b"salt", 1)[..4],
+            [0x55, 0xac, 0x04, 0x6e]
+        );
+        assert_eq!(backup_id(1_760_000_000), "openclaw-20251009-085320.clawbak");
+    }
+
+    #[test]
+    fn test_archives_to_prune() {
+        let names: Vec<String> = [
+            "openclaw-20251009-085320.tar.gz",
+            "openclaw-20251009-020000.tar.gz",
+            "openclaw-20251008-020000.tar.gz",
+            "openclaw-20251001-020000.tar.gz",
+            "openclaw-20250920-020000.tar.gz",
+            "pre-restore-1760000000.tar.gz",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(archive_day("openclaw-19700102-000000.tar.gz"), Some(1));
+        assert_eq!(
+            archives_to_prune(&names, 2, 2),
+            vec![
+                "openclaw-20251009-020000.tar.gz",
+                "openclaw-20250920-020000.tar.gz"
+            ]
+        );
+        assert_eq!(archives_to_prune(&names, 0, 0).len(), 5);
+    }
+
+    #[test]
+    fn test_parse_propfind() {
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response><d:href>/dav/openclaw/</d:href><d:propstat><d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop></d:propstat></d:response>
+  <d:response><d:href>/dav/openclaw/openclaw-20251009-085320.clawbak</d:href><d:propstat><d:prop>
+    <d:getcontentlength>2048</d:getcontentlength><d:getlastmodified>Thu, 09 Oct 2025 08:53:21 GMT</d:getlastmodified>
+  </d:prop></d:propstat></d:response>
+  <D:response xmlns:D="DAV:"><D:href>/dav/openclaw/notes.txt</D:href></D:response>
+</d:multistatus>"#;
+        assert_eq!(
+            parse_propfind(xml),
+            vec![RemoteBackup {
+                id: "openclaw-20251009-085320.clawbak".to_string(),
+                size: Some(2048),
+                modified: Some("Thu, 09 Oct 2025 08:53:21 GMT".to_string()),
+            }]
+        );
+    }
+}
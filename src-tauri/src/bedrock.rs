@@ -0,0 +1,368 @@
+//! AWS Bedrock credentials and a signed `ListFoundationModels` check.
+//!
+//! The gateway reaches Bedrock through the AWS SDK's default credential chain, so setup only
+//! has to make sure a usable AWS profile exists: either a named profile from
+//! `~/.aws/credentials`, or access keys the user entered, which are saved there under
+//! `CREDENTIALS_PROFILE`. Requests are signed with SigV4 directly; the check only needs one
+//! GET and the AWS SDK would be a large dependency for it.
+
+use sha2::{Digest, Sha256};
+
+pub const PROVIDER: &str = "amazon-bedrock";
+/// Profile in `~/.aws/credentials` that holds keys entered during setup.
+pub const CREDENTIALS_PROFILE: &str = "openclaw";
+const SERVICE: &str = "bedrock";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct BedrockConfig {
+    pub region: String,
+    /// Named AWS profile; ignored when access keys are given.
+    pub profile: Option<String>,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub session_token: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl BedrockConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let region = self.region.trim();
+        if region.is_empty()
+            || !region
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err(format!("Invalid AWS region: {}", self.region));
+        }
+        match (&self.access_key_id, &self.secret_access_key) {
+            (Some(id), Some(secret)) if !id.trim().is_empty() && !secret.trim().is_empty() => {
+                Ok(())
+            }
+            (Some(_), _) | (_, Some(_)) => {
+                Err("Enter both the AWS access key ID and secret access key.".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Keys entered in setup, if any.
+    pub fn static_credentials(&self) -> Option<Credentials> {
+        let id = self.access_key_id.as_deref()?.trim();
+        let secret = self.secret_access_key.as_deref()?.trim();
+        (!id.is_empty() && !secret.is_empty()).then(|| Credentials {
+            access_key_id: id.to_string(),
+            secret_access_key: secret.to_string(),
+            session_token: self
+                .session_token
+                .as_deref()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string),
+        })
+    }
+
+    /// Profile the gateway should use: the one holding entered keys, else the named one.
+    pub fn effective_profile(&self) -> String {
+        if self.static_credentials().is_some() {
+            CREDENTIALS_PROFILE.to_string()
+        } else {
+            self.profile
+                .as_deref()
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .unwrap_or("default")
+                .to_string()
+        }
+    }
+}
+
+fn section_name(line: &str) -> Option<&str> {
+    let name = line.trim().strip_prefix('[')?.strip_suffix(']')?.trim();
+    Some(name.strip_prefix("profile ").unwrap_or(name).trim())
+}
+
+/// Keys for `profile` from the contents of `~/.aws/credentials`.
+pub fn profile_credentials(contents: &str, profile: &str) -> Option<Credentials> {
+    let mut in_profile = false;
+    let (mut id, mut secret, mut token) = (None, None, None);
+    for line in contents.lines() {
+        if let Some(name) = section_name(line) {
+            in_profile = name == profile;
+            continue;
+        }
+        if !in_profile {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = Some(value.trim().to_string());
+        match key.trim() {
+            "aws_access_key_id" => id = value,
+            "aws_secret_access_key" => secret = value,
+            "aws_session_token" => token = value,
+            _ => {}
+        }
+    }
+    Some(Credentials {
+        access_key_id: id?,
+        secret_access_key: secret?,
+        session_token: token,
+    })
+}
+
+/// `contents` of `~/.aws/credentials` with `profile` replaced by `credentials`, leaving other
+/// profiles untouched.
+pub fn upsert_profile(contents: &str, profile: &str, credentials: &Credentials) -> String {
+    let mut out = Vec::new();
+    let mut skipping = false;
+    for line in contents.lines() {
+        if let Some(name) = section_name(line) {
+            skipping = name == profile;
+        }
+        if !skipping {
+            out.push(line.to_string());
+        }
+    }
+    while out.last().is_some_and(|line| line.trim().is_empty()) {
+        out.pop();
+    }
+    if !out.is_empty() {
+        out.push(String::new());
+    }
+    out.push(format!("[{}]", profile));
+    out.push(format!("aws_access_key_id = {}", credentials.access_key_id));
+    out.push(format!(
+        "aws_secret_access_key = {}",
+        credentials.secret_access_key
+    ));
+    if let Some(token) = &credentials.session_token {
+        out.push(format!("aws_session_token = {}", token));
+    }
+    out.join("\n") + "\n"
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    const BLOCK: usize = 64;
+    let mut key_block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        key_block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        key_block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| key_block.iter().map(|k| k ^ byte).collect::<Vec<u8>>();
+    let mut inner = Sha256::new();
+    inner.update(pad(0x36));
+    inner.update(data);
+    let mut outer = Sha256::new();
+    outer.update(pad(0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().to_vec()
+}
+
+/// `YYYYMMDDTHHMMSSZ` for unix seconds `now`.
+fn amz_date(now: u64) -> String {
+    let secs = now % 86_400;
+    format!(
+        "{}T{:02}{:02}{:02}Z",
+        crate::civil_date_from_unix_days((now / 86_400) as i64).replace('-', ""),
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// SigV4 headers for a bodyless GET of `path?query` on `host`. `query` must already be in
+/// canonical form (sorted, URI-encoded).
+pub fn sign_get(
+    host: &str,
+    path: &str,
+    query: &str,
+    region: &str,
+    service: &str,
+    credentials: &Credentials,
+    now: u64,
+) -> Vec<(String, String)> {
+    let timestamp = amz_date(now);
+    let date = &timestamp[..8];
+    let mut headers = vec![
+        ("host".to_string(), host.to_string()),
+        ("x-amz-date".to_string(), timestamp.clone()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "GET\n{}\n{}\n{}\n{}\n{}",
+        path,
+        query,
+        canonical_headers,
+        signed_headers,
+        hex(&Sha256::digest(b""))
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        timestamp,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let mut key = format!("AWS4{}", credentials.secret_access_key).into_bytes();
+    for part in [date, region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+    headers.push((
+        "authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+    headers.retain(|(name, _)| name != "host");
+    headers
+}
+
+/// Text models a `ListFoundationModels` response offers.
+pub fn text_model_ids(listing: &serde_json::Value) -> Vec<String> {
+    listing
+        .get("modelSummaries")
+        .and_then(|m| m.as_array())
+        .map(|models| {
+            models
+                .iter()
+                .filter(|m| {
+                    m.get("outputModalities")
+                        .and_then(|o| o.as_array())
+                        .is_none_or(|o| o.iter().any(|v| v == "TEXT"))
+                })
+                .filter_map(|m| m.get("modelId").and_then(|id| id.as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Calls `ListFoundationModels` in `region`; returns the text model ids.
+pub async fn list_foundation_models(
+    region: &str,
+    credentials: &Credentials,
+    now: u64,
+) -> Result<Vec<String>, String> {
+    let host = format!("bedrock.{}.amazonaws.com", region);
+    let query = "byOutputModality=TEXT";
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client.get(format!("https://{}/foundation-models?{}", host, query));
+    for (name, value) in sign_get(
+        &host,
+        "/foundation-models",
+        query,
+        region,
+        SERVICE,
+        credentials,
+        now,
+    ) {
+        request = request.header(name, value);
+    }
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach Bedrock in {}: {}", region, e))?;
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    match status.as_u16() {
+        200..=299 => serde_json::from_str(&body)
+            .map(|listing| text_model_ids(&listing))
+            .map_err(|_| "Bedrock returned an unreadable model list".to_string()),
+        401 | 403 if body.contains("AccessDenied") => Err(format!(
+            "These AWS credentials are not allowed to call bedrock:ListFoundationModels in {}",
+            region
+        )),
+        401 | 403 => Err(
+            "AWS rejected the credentials; check the access key, secret and session token"
+                .to_string(),
+        ),
+        code => Err(format!(
+            "Bedrock returned HTTP {}: {}",
+            code,
+            body.chars().take(300).collect::<String>()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_get_matches_aws_vector() {
+        // AWS SigV4 test suite, "get-vanilla".
+        let credentials = Credentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        };
+        let headers = sign_get(
+            "example.amazonaws.com",
+            "/",
+            "",
+            "us-east-1",
+            "service",
+            &credentials,
+            1_440_938_160,
+        );
+        assert_eq!(
+            headers[0],
+            ("x-amz-date".to_string(), "20150830T123600Z".to_string())
+        );
+        assert_eq!(
+            headers[1].1,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_credentials_file_round_trip() {
+        let contents = "[default]\naws_access_key_id = AKIA1\naws_secret_access_key = s1\n\n[openclaw]\naws_access_key_id = OLD\naws_secret_access_key = old\n";
+        let credentials = Credentials {
+            access_key_id: "AKIA2".to_string(),
+            secret_access_key: "s2".to_string(),
+            session_token: Some("t2".to_string()),
+        };
+        let updated = upsert_profile(contents, CREDENTIALS_PROFILE, &credentials);
+        assert_eq!(
+            profile_credentials(&updated, CREDENTIALS_PROFILE),
+            Some(credentials)
+        );
+        assert_eq!(
+            profile_credentials(&updated, "default")
+                .unwrap()
+                .access_key_id,
+            "AKIA1"
+        );
+        assert!(!updated.contains("OLD"));
+        assert!(profile_credentials(&updated, "missing").is_none());
+    }
+}
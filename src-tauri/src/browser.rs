@@ -0,0 +1,125 @@
+//! Setup of the agent's browser tool, which drives a local Chromium-family browser.
+//!
+//! `detect_script` looks for an installed Chrome, Chromium, Brave or Edge and then for a
+//! Chromium downloaded by Playwright; when none is found the app installs one with
+//! `playwright install chromium`, following the download through `download_percent`.
+//! `enable_in_config` points `browser` at the executable and lifts tool-level blocks.
+
+/// Browser commands looked up on PATH, preferred first.
+const BROWSER_COMMANDS: &[&str] = &[
+    "google-chrome",
+    "google-chrome-stable",
+    "chromium",
+    "chromium-browser",
+    "brave-browser",
+    "microsoft-edge",
+];
+const MACOS_APPS: &[&str] = &[
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+    "/Applications/Brave Browser.app/Contents/MacOS/Brave Browser",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+];
+/// Playwright's download directories and the executables inside them. Searched with
+/// `find` rather than globs, which zsh rejects when nothing matches.
+const PLAYWRIGHT_DIRS: &[&str] = &[
+    "\"$HOME\"/.cache/ms-playwright",
+    "\"$HOME\"/Library/Caches/ms-playwright",
+];
+const PLAYWRIGHT_EXECUTABLES: &[&str] = &[
+    "*/chromium-*/chrome-linux*/chrome",
+    "*/chromium-*/chrome-mac*/Chromium.app/Contents/MacOS/Chromium",
+];
+pub const INSTALL_COMMAND: &str = "npx -y playwright install chromium";
+pub const TOOL_NAME: &str = "browser";
+
+/// Shell script printing the path of the first browser found, or nothing.
+pub fn detect_script() -> String {
+    let apps: Vec<String> = MACOS_APPS.iter().map(|p| format!("'{}'", p)).collect();
+    let executables: Vec<String> = PLAYWRIGHT_EXECUTABLES
+        .iter()
+        .map(|p| format!("-path '{}'", p))
+        .collect();
+    format!(
+        "for p in {}; do [ -x \"$p\" ] && {{ echo \"$p\"; exit 0; }}; done; \
+         for c in {}; do command -v \"$c\" && exit 0; done; \
+         find {} -maxdepth 6 -type f \\( {} \\) 2>/dev/null | sort -V | tail -n 1",
+        apps.join(" "),
+        BROWSER_COMMANDS.join(" "),
+        PLAYWRIGHT_DIRS.join(" "),
+        executables.join(" -o ")
+    )
+}
+
+/// Download progress from a Playwright output line such as `|■■■■    |  40% of 150.2 MiB`.
+pub fn download_percent(line: &str) -> Option<u8> {
+    let (before, _) = line.split_once("% of ")?;
+    let digits: String = before
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    digits.parse::<u8>().ok().filter(|p| *p <= 100)
+}
+
+/// Enables the browser tool on `executable`: `browser.enabled`, `browser.executablePath`,
+/// no `browser` in `tools.deny`, and `browser` in `tools.allow` when an allowlist is used.
+pub fn enable_in_config(config: &mut serde_json::Value, executable: &str) {
+    let Some(root) = config.as_object_mut() else {
+        return;
+    };
+    let browser = root
+        .entry("browser")
+        .or_insert_with(|| serde_json::json!({}));
+    if let Some(browser) = browser.as_object_mut() {
+        browser.insert("enabled".to_string(), serde_json::json!(true));
+        browser.insert("executablePath".to_string(), serde_json::json!(executable));
+    }
+    let Some(tools) = root.get_mut("tools").and_then(|t| t.as_object_mut()) else {
+        return;
+    };
+    if let Some(deny) = tools.get_mut("deny").and_then(|d| d.as_array_mut()) {
+        deny.retain(|tool| tool.as_str() != Some(TOOL_NAME));
+    }
+    if let Some(allow) = tools.get_mut("allow").and_then(|a| a.as_array_mut()) {
+        if !allow.is_empty() && !allow.iter().any(|tool| tool.as_str() == Some(TOOL_NAME)) {
+            allow.push(serde_json::json!(TOOL_NAME));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_percent() {
+        assert_eq!(
+            download_percent("|■■■■■■■■                |  40% of 150.2 MiB"),
+            Some(40)
+        );
+        assert_eq!(download_percent("Chromium downloaded to /x"), None);
+        assert_eq!(download_percent("100% of 2 MiB"), Some(100));
+    }
+
+    #[test]
+    fn test_enable_in_config_lifts_tool_blocks() {
+        let mut config = serde_json::json!({
+            "browser": { "headless": true },
+            "tools": { "deny": ["browser", "exec"], "allow": ["web_search"] }
+        });
+        enable_in_config(&mut config, "/usr/bin/chromium");
+        assert_eq!(config["browser"]["headless"], true);
+        assert_eq!(config["browser"]["enabled"], true);
+        assert_eq!(config["browser"]["executablePath"], "/usr/bin/chromium");
+        assert_eq!(config["tools"]["deny"], serde_json::json!(["exec"]));
+        assert_eq!(
+            config["tools"]["allow"],
+            serde_json::json!(["web_search", "browser"])
+        );
+        assert!(detect_script().contains("command -v \"$c\""));
+    }
+}
@@ -0,0 +1,304 @@
+//! Typed wrappers around `openclaw <subcommand> --json`.
+//!
+//! Newer CLIs print machine-readable JSON when given `--json`. Older releases reject the
+//! flag, which surfaces as `CliError::JsonUnsupported` so callers can fall back to
+//! scraping the human-readable output. Transient failures (the gateway restarting under
+//! the query) are retried with `retry::CLI`.
+
+use serde::de::DeserializeOwned;
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    JsonUnsupported,
+    Failed(String),
+    Parse(String),
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::JsonUnsupported => write!(
+                f,
+                "This openclaw version does not support --json output. Update OpenClaw."
+            ),
+            CliError::Failed(output) => write!(f, "{}", output.trim()),
+            CliError::Parse(e) => write!(f, "Failed to parse openclaw output: {}", e),
+        }
+    }
+}
+
+impl From<CliError> for String {
+    fn from(err: CliError) -> Self {
+        err.to_string()
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+pub struct PluginList {
+    #[serde(default)]
+    pub plugins: Vec<PluginEntry>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginEntry {
+    pub id: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<String>,
+    pub origin: Option<String>,
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub provider_ids: Vec<String>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+pub struct PairingList {
+    #[serde(default)]
+    pub requests: Vec<PairingRequest>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+pub struct PairingRequest {
+    pub code: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct AgentEntry {
+    pub id: String,
+    pub name: Option<String>,
+    pub identity_name: Option<String>,
+    pub workspace: Option<String>,
+    pub model: Option<String>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct SessionEntry {
+    pub key: String,
+    pub session_id: Option<String>,
+    pub kind: Option<String>,
+    /// Milliseconds since the epoch.
+    pub updated_at: Option<u64>,
+    pub model: Option<String>,
+    pub total_tokens: Option<u64>,
+    pub context_tokens: Option<u64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct ModelEntry {
+    pub key: String,
+    pub name: Option<String>,
+    pub context_window: Option<u64>,
+    #[serde(default)]
+    pub local: bool,
+    pub available: Option<bool>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ChannelStatus {
+    pub id: String,
+    pub label: String,
+    pub configured: bool,
+    pub running: bool,
+    pub accounts: Vec<ChannelAccountStatus>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct ChannelAccountStatus {
+    #[serde(default)]
+    pub account_id: String,
+    pub enabled: Option<bool>,
+    pub configured: Option<bool>,
+    pub running: Option<bool>,
+    pub connected: Option<bool>,
+    pub last_error: Option<String>,
+}
+
+/// Old CLIs (commander) reject the flag with "unknown option '--json'".
+pub fn json_flag_rejected(output: &str) -> bool {
+    let lower = output.to_lowercase();
+    lower.contains("--json")
+        && (lower.contains("unknown option")
+            || lower.contains("unexpected argument")
+            || lower.contains("unrecognized"))
+}
+
+/// Parses the JSON document in `output`, skipping any warning lines printed before it.
+pub fn parse_json_output<T: DeserializeOwned>(output: &str) -> Result<T, CliError> {
+    let start = output
+        .find(|c| c == '{' || c == '[')
+        .ok_or_else(|| CliError::Parse("no JSON in output".to_string()))?;
+    serde_json::from_str(output[start..].trim()).map_err(|e| CliError::Parse(e.to_string()))
+}
+
+pub fn run_json<T: DeserializeOwned>(args: &[&str]) -> Result<T, CliError> {
+    let mut argv = args.to_vec();
+    argv.push("--json");
+    let output = crate::retry::retry(
+        &crate::retry::CLI,
+        |_| crate::process::run("openclaw", &argv),
+        |output| !json_flag_rejected(output) && crate::retry::is_transient(output),
+        |_, _| {},
+    );
+    match output {
+        Ok(output) => parse_json_output(&output),
+        Err(output) if json_flag_rejected(&output) => Err(CliError::JsonUnsupported),
+        Err(output) => Err(CliError::Failed(output)),
+    }
+}
+
+pub fn plugins_list() -> Result<PluginList, CliError> {
+    run_json(&["plugins", "list"])
+}
+
+pub fn pairing_requests(channel: &str) -> Result<PairingList, CliError> {
+    run_json(&["pairing", "list", "--channel", channel])
+}
+
+pub fn gateway_status() -> Result<serde_json::Value, CliError> {
+    run_json(&["gateway", "status"])
+}
+
+/// Entries of a list printed either bare (`[...]`) or wrapped (`{"<field>": [...]}`).
+fn list_items<T: DeserializeOwned>(
+    value: serde_json::Value,
+    field: &str,
+) -> Result<Vec<T>, CliError> {
+    let items = match value {
+        serde_json::Value::Array(_) => value,
+        mut other => other
+            .get_mut(field)
+            .map(serde_json::Value::take)
+            .unwrap_or_else(|| serde_json::Value::Array(Vec::new())),
+    };
+    serde_json::from_value(items).map_err(|e| CliError::Parse(e.to_string()))
+}
+
+/// `channels status --json`: `channels` holds per-channel flags, `channelAccounts` the
+/// accounts of each, and `channelOrder`/`channelLabels` how to present them.
+pub fn parse_channels_status(value: &serde_json::Value) -> Vec<ChannelStatus> {
+    let channels = value.get("channels").and_then(|c| c.as_object());
+    let mut order: Vec<String> = value
+        .get("channelOrder")
+        .and_then(|o| o.as_array())
+        .map(|o| {
+            o.iter()
+                .filter_map(|id| id.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    for id in channels.into_iter().flat_map(|c| c.keys()) {
+        if !order.contains(id) {
+            order.push(id.clone());
+        }
+    }
+    let flag = |id: &str, name: &str| {
+        channels
+            .and_then(|c| c.get(id))
+            .and_then(|c| c.get(name))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    };
+    order
+        .into_iter()
+        .map(|id| ChannelStatus {
+            label: value
+                .pointer(&format!("/channelLabels/{}", id))
+                .and_then(|l| l.as_str())
+                .unwrap_or(id.as_str())
+                .to_string(),
+            configured: flag(&id, "configured"),
+            running: flag(&id, "running"),
+            accounts: value
+                .pointer(&format!("/channelAccounts/{}", id))
+                .cloned()
+                .and_then(|a| serde_json::from_value(a).ok())
+                .unwrap_or_default(),
+            id,
+        })
+        .collect()
+}
+
+pub fn agents_list() -> Result<Vec<AgentEntry>, CliError> {
+    list_items(run_json(&["agents", "list"])?, "agents")
+}
+
+pub fn sessions_list() -> Result<Vec<SessionEntry>, CliError> {
+    list_items(run_json(&["sessions"])?, "sessions")
+}
+
+pub fn models_list() -> Result<Vec<ModelEntry>, CliError> {
+    list_items(run_json(&["models", "list"])?, "models")
+}
+
+pub fn channels_status() -> Result<Vec<ChannelStatus>, CliError> {
+    Ok(parse_channels_status(&run_json(&["channels", "status"])?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_flag_rejected() {
+        assert!(json_flag_rejected("error: unknown option '--json'"));
+        assert!(!json_flag_rejected("error: gateway not running"));
+    }
+
+    #[test]
+    fn test_parse_json_output_skips_leading_warnings() {
+        let output = "(node:123) Warning: something\n{\"plugins\":[{\"id\":\"telegram\",\"providerIds\":[\"x\"]}]}";
+        let list: PluginList = parse_json_output(output).unwrap();
+        assert_eq!(list.plugins[0].id, "telegram");
+        assert_eq!(list.plugins[0].provider_ids, vec!["x".to_string()]);
+        assert!(parse_json_output::<PluginList>("no json here").is_err());
+    }
+
+    #[test]
+    fn test_pairing_list_defaults_to_empty() {
+        let list: PairingList = parse_json_output("{\"channel\":\"telegram\"}").unwrap();
+        assert!(list.requests.is_empty());
+    }
+
+    #[test]
+    fn test_list_items_and_channels_status() {
+        let agents: Vec<AgentEntry> = list_items(
+            serde_json::json!([{ "id": "main", "identityName": "Claw", "isDefault": true }]),
+            "agents",
+        )
+        .unwrap();
+        assert_eq!(agents[0].identity_name.as_deref(), Some("Claw"));
+        assert!(agents[0].is_default);
+        let sessions: Vec<SessionEntry> = list_items(
+            serde_json::json!({ "count": 1, "sessions": [{ "key": "agent:main:main", "sessionId": "s1", "updatedAt": 5 }] }),
+            "sessions",
+        )
+        .unwrap();
+        assert_eq!(sessions[0].session_id.as_deref(), Some("s1"));
+        assert!(list_items::<ModelEntry>(serde_json::json!({}), "models")
+            .unwrap()
+            .is_empty());
+
+        let status = parse_channels_status(&serde_json::json!({
+            "channelOrder": ["telegram"],
+            "channelLabels": { "telegram": "Telegram" },
+            "channels": { "telegram": { "configured": true, "running": true }, "slack": { "configured": true } },
+            "channelAccounts": { "telegram": [{ "accountId": "default", "connected": true }] }
+        }));
+        assert_eq!(status.len(), 2);
+        assert_eq!(status[0].label, "Telegram");
+        assert!(status[0].running);
+        assert_eq!(status[0].accounts[0].connected, Some(true));
+        assert_eq!(status[1].id, "slack");
+        assert!(!status[1].running);
+    }
+}
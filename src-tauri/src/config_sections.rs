@@ -0,0 +1,231 @@
+//! Section-scoped access to `openclaw.json`.
+//!
+//! Editors read and write one dotted path (`gateway`, `agents.defaults`,
+//! `channels.telegram`) instead of round-tripping the whole file, so saving one form cannot
+//! clobber unrelated sections. Sections with a typed model below are checked against it on
+//! every update; unknown fields inside them are preserved via `extra`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+const GATEWAY_BINDS: &[&str] = &["loopback", "lan", "tailnet", "auto", "custom"];
+const GATEWAY_AUTH_MODES: &[&str] = &["token", "password", "none"];
+const DM_POLICIES: &[&str] = &["pairing", "allowlist", "open", "disabled"];
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GatewayAuth {
+    mode: Option<String>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GatewaySection {
+    port: Option<u16>,
+    bind: Option<String>,
+    custom_bind_host: Option<String>,
+    auth: Option<GatewayAuth>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ModelSelection {
+    primary: Option<String>,
+    fallbacks: Option<Vec<String>>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct AgentDefaultsSection {
+    workspace: Option<String>,
+    model: Option<ModelSelection>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ChannelSection {
+    enabled: Option<bool>,
+    dm_policy: Option<String>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+fn one_of(field: &str, value: &Option<String>, allowed: &[&str]) -> Result<(), String> {
+    match value {
+        Some(v) if !allowed.contains(&v.as_str()) => Err(format!(
+            "Invalid {} '{}'; expected one of {}",
+            field,
+            v,
+            allowed.join(", ")
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn typed<T: for<'de> Deserialize<'de>>(path: &str, value: &Value) -> Result<T, String> {
+    serde_json::from_value(value.clone()).map_err(|e| format!("Invalid {}: {}", path, e))
+}
+
+/// Checks a known section against its model; unknown paths are accepted as-is.
+fn validate_section(path: &str, value: &Value) -> Result<(), String> {
+    let segments: Vec<&str> = path.split('.').collect();
+    match segments.as_slice() {
+        ["gateway"] => {
+            let gateway: GatewaySection = typed(path, value)?;
+            if gateway.port == Some(0) {
+                return Err("Invalid gateway.port: must be between 1 and 65535".to_string());
+            }
+            one_of("gateway.bind", &gateway.bind, GATEWAY_BINDS)?;
+            if gateway.bind.as_deref() == Some("custom") && gateway.custom_bind_host.is_none() {
+                return Err("gateway.customBindHost is required when bind is custom".to_string());
+            }
+            if let Some(auth) = &gateway.auth {
+                one_of("gateway.auth.mode", &auth.mode, GATEWAY_AUTH_MODES)?;
+            }
+            Ok(())
+        }
+        ["agents", "defaults"] => {
+            let defaults: AgentDefaultsSection = typed(path, value)?;
+            if let Some(primary) = defaults.model.as_ref().and_then(|m| m.primary.as_ref()) {
+                if !primary.contains('/') {
+                    return Err(format!(
+                        "Invalid agents.defaults.model.primary '{}'; expected provider/model",
+                        primary
+                    ));
+                }
+            }
+            Ok(())
+        }
+        ["channels", _] => {
+            let channel: ChannelSection = typed(path, value)?;
+            one_of(
+                &format!("{}.dmPolicy", path),
+                &channel.dm_policy,
+                DM_POLICIES,
+            )
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_path(path: &str) -> Result<Vec<&str>, String> {
+    let segments: Vec<&str> = path.split('.').collect();
+    let valid = segments.iter().all(|s| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '@')
+    });
+    if !valid {
+        return Err(format!("Invalid config path '{}'", path));
+    }
+    Ok(segments)
+}
+
+/// The value at dotted `path`, or `Value::Null` when absent.
+pub fn get(config: &Value, path: &str) -> Result<Value, String> {
+    let mut current = config;
+    for segment in validate_path(path)? {
+        match current.get(segment) {
+            Some(next) => current = next,
+            None => return Ok(Value::Null),
+        }
+    }
+    Ok(current.clone())
+}
+
+/// Replaces the value at `path` (creating parents) and validates every typed section the
+/// change touches. `config` is left unchanged on error.
+pub fn update(config: &mut Value, path: &str, value: Value) -> Result<(), String> {
+    let segments = validate_path(path)?;
+    let mut updated = config.clone();
+    let mut current = &mut updated;
+    for segment in &segments[..segments.len() - 1] {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        current = current
+            .as_object_mut()
+            .map(|obj| obj.entry(segment.to_string()).or_insert(Value::Null))
+            .ok_or_else(|| format!("Invalid config path '{}'", path))?;
+    }
+    if !current.is_object() {
+        *current = Value::Object(Map::new());
+    }
+    if let Some(obj) = current.as_object_mut() {
+        obj.insert(segments[segments.len() - 1].to_string(), value);
+    }
+
+    for section in touched_sections(&updated, path) {
+        let section_value = get(&updated, &section)?;
+        if !section_value.is_null() {
+            validate_section(&section, &section_value)?;
+        }
+    }
+    *config = updated;
+    Ok(())
+}
+
+/// Typed sections that overlap `path` (contain it or are contained by it).
+fn touched_sections(config: &Value, path: &str) -> Vec<String> {
+    let mut sections = vec!["gateway".to_string(), "agents.defaults".to_string()];
+    if let Some(channels) = config.get("channels").and_then(|c| c.as_object()) {
+        sections.extend(channels.keys().map(|name| format!("channels.{}", name)));
+    }
+    let overlaps = |a: &str, b: &str| a == b || a.starts_with(&format!("{}.", b));
+    sections
+        .into_iter()
+        .filter(|section| overlaps(path, section) || overlaps(section, path))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_update_replaces_only_the_section() {
+        let mut config = json!({
+            "gateway": { "port": 18789, "auth": { "mode": "token", "token": "t" } },
+            "channels": { "telegram": { "botToken": "x" } }
+        });
+        update(&mut config, "gateway.port", json!(18800)).unwrap();
+        update(
+            &mut config,
+            "agents.defaults.model",
+            json!({ "primary": "anthropic/claude" }),
+        )
+        .unwrap();
+        assert_eq!(config["gateway"]["port"], 18800);
+        assert_eq!(config["gateway"]["auth"]["token"], "t");
+        assert_eq!(config["channels"]["telegram"]["botToken"], "x");
+        assert_eq!(
+            get(&config, "agents.defaults.model.primary").unwrap(),
+            "anthropic/claude"
+        );
+        assert_eq!(get(&config, "tools.profile").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_update_validates_typed_sections() {
+        let mut config = json!({ "gateway": { "port": 18789 } });
+        assert!(update(&mut config, "gateway.bind", json!("everywhere")).is_err());
+        assert!(update(&mut config, "gateway.port", json!("high")).is_err());
+        assert!(update(
+            &mut config,
+            "channels.telegram",
+            json!({ "dmPolicy": "maybe" })
+        )
+        .is_err());
+        assert!(update(&mut config, "gateway..port", json!(1)).is_err());
+        assert_eq!(config, json!({ "gateway": { "port": 18789 } }));
+    }
+}
@@ -0,0 +1,226 @@
+//! Crash log and the structured errors the invoke handler rejects commands with.
+//!
+//! The panic hook installed in `main` writes every panic, with its backtrace, to `crash.log`
+//! in the app data directory and remembers it on the panicking thread. `#[command]` (see the
+//! `clawnetes-macros` crate) runs every command body inside `catch_unwind` (polled inside it,
+//! for async commands), so instead of taking the app down or leaving the call pending a panic
+//! rejects it with an `AppError::Internal` that points at the crash log entry.
+
+use serde::Serialize;
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const CRASH_LOG_FILE: &str = "crash.log";
+/// The log is rotated to `crash.log.1` once it grows past this.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+thread_local! {
+    static LAST_CRASH: RefCell<Option<Crash>> = const { RefCell::new(None) };
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Crash {
+    /// Short id quoted in the error and the log, e.g. `3f9c1a2b`.
+    pub id: String,
+    pub timestamp: u64,
+    pub thread: String,
+    pub location: String,
+    pub message: String,
+    pub backtrace: String,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppError {
+    /// The command panicked; a bug rather than something the user can fix.
+    Internal {
+        command: String,
+        message: String,
+        crash_id: String,
+        crash_log: Option<String>,
+    },
+    /// Refused because the app is in read-only observer mode; see `observer`.
+    ReadOnly { command: String },
+    /// A child process was killed at its timeout; running the command again may succeed.
+    Timeout {
+        command: String,
+        process: String,
+        timeout_secs: u64,
+        message: String,
+    },
+}
+
+impl AppError {
+    pub fn internal(command: &str, crash: &Crash, crash_log: Option<&Path>) -> Self {
+        AppError::Internal {
+            command: command.to_string(),
+            message: crash.message.clone(),
+            crash_id: crash.id.clone(),
+            crash_log: crash_log.map(|path| path.to_string_lossy().to_string()),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Internal {
+                command,
+                message,
+                crash_id,
+                crash_log,
+            } => {
+                write!(
+                    f,
+                    "`{}` failed unexpectedly: {} (crash {}",
+                    command, message, crash_id
+                )?;
+                if let Some(log) = crash_log {
+                    write!(f, ", details in {}", log)?;
+                }
+                write!(f, ")")
+            }
+            AppError::ReadOnly { command } => write!(
+                f,
+                "{}",
+                crate::messages::text("observer.read_only", &[("command", command)])
+            ),
+            AppError::Timeout { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+/// What a command rejects with. Ordinary failures stay the plain message the frontend has
+/// always shown; the structured errors serialize as the tagged `AppError` object.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum CommandError {
+    Message(String),
+    App(AppError),
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Message(message)
+    }
+}
+
+impl From<AppError> for CommandError {
+    fn from(error: AppError) -> Self {
+        CommandError::App(error)
+    }
+}
+
+/// The text a panic was raised with.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic".to_string())
+}
+
+pub fn new_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..8].to_string()
+}
+
+/// Keeps `crash` for the `catch_unwind` caller on this thread.
+pub fn remember(crash: Crash) {
+    LAST_CRASH.with(|last| *last.borrow_mut() = Some(crash));
+}
+
+pub fn take_last() -> Option<Crash> {
+    LAST_CRASH.with(|last| last.borrow_mut().take())
+}
+
+pub fn format_entry(crash: &Crash) -> String {
+    let days = (crash.timestamp / 86_400) as i64;
+    let secs = crash.timestamp % 86_400;
+    format!(
+        "=== crash {} at {} {:02}:{:02}:{:02} UTC\nthread '{}' panicked at {}:\n{}\n{}\n\n",
+        crash.id,
+        crate::civil_date_from_unix_days(days),
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        crash.thread,
+        crash.location,
+        crash.message,
+        crash.backtrace.trim_end()
+    )
+}
+
+pub fn log_path(dir: &Path) -> PathBuf {
+    dir.join(CRASH_LOG_FILE)
+}
+
+/// Appends `crash` to the crash log in `dir`, rotating a full log first.
+pub fn append(dir: &Path, crash: &Crash) -> Result<PathBuf, String> {
+    let path = log_path(dir);
+    if fs::metadata(&path).is_ok_and(|meta| meta.len() > MAX_LOG_BYTES) {
+        let _ = fs::rename(&path, dir.join(format!("{}.1", CRASH_LOG_FILE)));
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(format_entry(crash).as_bytes()))
+        .map_err(|e| format!("Failed to write crash log: {}", e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_is_logged_and_reported() {
+        let dir = std::env::temp_dir().join(format!("clawsetup-crash-{}", std::process::id()));
+        let crash = Crash {
+            id: "3f9c1a2b".to_string(),
+            timestamp: 86_400 + 3_661,
+            thread: "main".to_string(),
+            location: "src/main.rs:10".to_string(),
+            message: panic_message(&"index out of bounds"),
+            backtrace: "   0: clawnetes::main\n".to_string(),
+        };
+        let path = append(&dir, &crash).unwrap();
+        append(&dir, &crash).unwrap();
+        let log = fs::read_to_string(&path).unwrap();
+        assert!(log.starts_with(
+            "=== crash 3f9c1a2b at 1970-01-02 01:01:01 UTC\nthread 'main' panicked at src/main.rs:10:\nindex out of bounds\n   0: clawnetes::main\n\n"
+        ));
+        assert_eq!(log.matches("=== crash").count(), 2);
+
+        let error = AppError::internal("start_gateway", &crash, None);
+        assert_eq!(
+            error.to_string(),
+            "`start_gateway` failed unexpectedly: index out of bounds (crash 3f9c1a2b)"
+        );
+        assert_eq!(serde_json::to_value(&error).unwrap()["kind"], "internal");
+        assert_eq!(
+            serde_json::to_value(CommandError::from(error)).unwrap()["crash_id"],
+            "3f9c1a2b"
+        );
+        assert_eq!(
+            serde_json::to_value(CommandError::from("Gateway is not running".to_string())).unwrap(),
+            "Gateway is not running"
+        );
+        let timeout = AppError::Timeout {
+            command: "restart_gateway".to_string(),
+            process: "openclaw gateway".to_string(),
+            timeout_secs: 180,
+            message: "`openclaw gateway` timed out".to_string(),
+        };
+        assert_eq!(serde_json::to_value(&timeout).unwrap()["kind"], "timeout");
+        remember(crash.clone());
+        assert_eq!(take_last(), Some(crash));
+        assert_eq!(take_last(), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
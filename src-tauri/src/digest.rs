@@ -0,0 +1,138 @@
+//! Daily digest: one OpenClaw cron job that has the agent write a summary every day at a
+//! fixed time and deliver it to a paired chat.
+//!
+//! The job itself lives in the gateway's cron store; `digest.json` in the app data directory
+//! remembers the chosen time, channel and sections and the id of the job created for them,
+//! so the settings can be shown and the job replaced when they change.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const DIGEST_FILE: &str = "digest.json";
+pub const JOB_NAME: &str = "Daily digest";
+
+/// Section ids and the instruction each adds to the digest prompt.
+pub const SECTIONS: &[(&str, &str)] = &[
+    ("activity", "what you worked on for me in the last 24 hours"),
+    ("calendar", "today's calendar events"),
+    ("email", "unread email that needs my attention"),
+    ("news", "top headlines related to my interests"),
+    ("tasks", "open tasks and reminders due soon"),
+    ("usage", "yesterday's model usage and cost"),
+    ("weather", "today's weather forecast"),
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct DigestConfig {
+    /// Local time as `HH:MM`.
+    pub time: String,
+    pub channel: String,
+    /// Chat to deliver to; the first paired chat on `channel` when `None`.
+    pub to: Option<String>,
+    /// Ids from `SECTIONS`, in the order they appear in the digest.
+    pub sections: Vec<String>,
+    /// IANA zone for `time`, e.g. `Europe/Berlin`; the gateway's zone when `None`.
+    pub timezone: Option<String>,
+    /// Id of the cron job created for this configuration.
+    pub job_id: Option<String>,
+}
+
+fn parse_time(time: &str) -> Option<(u32, u32)> {
+    let (hour, minute) = time.trim().split_once(':')?;
+    let hour: u32 = hour.parse().ok()?;
+    let minute: u32 = minute.parse().ok()?;
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
+
+pub fn validate(config: &DigestConfig) -> Result<(), String> {
+    if parse_time(&config.time).is_none() {
+        return Err(format!(
+            "Digest time '{}' must be HH:MM in 24-hour time",
+            config.time
+        ));
+    }
+    if config.sections.is_empty() {
+        return Err("Pick at least one section for the digest.".to_string());
+    }
+    if let Some(unknown) = config
+        .sections
+        .iter()
+        .find(|s| !SECTIONS.iter().any(|(id, _)| id == s))
+    {
+        return Err(format!("Unknown digest section '{}'", unknown));
+    }
+    Ok(())
+}
+
+/// Cron expression firing daily at `time` (`HH:MM`).
+pub fn cron_expression(time: &str) -> Result<String, String> {
+    let (hour, minute) =
+        parse_time(time).ok_or_else(|| format!("Digest time '{}' must be HH:MM", time))?;
+    Ok(format!("{} {} * * *", minute, hour))
+}
+
+/// The message the agent is given each day.
+pub fn prompt(sections: &[String]) -> String {
+    let items: Vec<String> = sections
+        .iter()
+        .filter_map(|section| SECTIONS.iter().find(|(id, _)| id == section))
+        .map(|(_, instruction)| format!("- {}", instruction))
+        .collect();
+    format!(
+        "Write my daily digest. Cover, in this order:\n{}\nKeep each part to a few short bullet points and skip a part if there is nothing to report.",
+        items.join("\n")
+    )
+}
+
+pub fn load(app_dir: &Path) -> Option<DigestConfig> {
+    fs::read_to_string(app_dir.join(DIGEST_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+}
+
+pub fn save(app_dir: &Path, config: &DigestConfig) -> Result<(), String> {
+    fs::create_dir_all(app_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let raw = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize digest settings: {}", e))?;
+    fs::write(app_dir.join(DIGEST_FILE), raw)
+        .map_err(|e| format!("Failed to save digest settings: {}", e))
+}
+
+pub fn remove(app_dir: &Path) -> Result<(), String> {
+    match fs::remove_file(app_dir.join(DIGEST_FILE)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove digest settings: {}", e))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_schedule_and_prompt() {
+        assert_eq!(cron_expression("07:30").unwrap(), "30 7 * * *");
+        assert_eq!(cron_expression("0:05").unwrap(), "5 0 * * *");
+        assert!(cron_expression("24:00").is_err());
+        assert!(cron_expression("7pm").is_err());
+
+        let mut config = DigestConfig {
+            time: "08:00".to_string(),
+            channel: "telegram".to_string(),
+            sections: vec!["weather".to_string(), "calendar".to_string()],
+            ..Default::default()
+        };
+        assert!(validate(&config).is_ok());
+        assert!(prompt(&config.sections)
+            .contains("\n- today's weather forecast\n- today's calendar events\n"));
+        config.sections.push("horoscope".to_string());
+        assert!(validate(&config).is_err());
+        config.sections.clear();
+        assert!(validate(&config).is_err());
+    }
+}
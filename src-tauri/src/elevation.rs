@@ -0,0 +1,188 @@
+//! Re-running a single operation with administrator rights.
+//!
+//! Uses the OS's own prompt — an osascript admin dialog on macOS, pkexec on Linux and UAC on
+//! Windows — so the app never sees or stores the password. Only the one command runs
+//! elevated; everything else keeps running as the user.
+
+use crate::process;
+
+#[derive(Debug, PartialEq)]
+pub enum ElevationError {
+    /// The user dismissed or denied the password/UAC prompt.
+    Declined,
+    Failed(String),
+}
+
+impl std::fmt::Display for ElevationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElevationError::Declined => write!(
+                f,
+                "Administrator approval was declined. Approve the system prompt to continue."
+            ),
+            ElevationError::Failed(detail) => write!(f, "{}", detail),
+        }
+    }
+}
+
+impl From<ElevationError> for String {
+    fn from(err: ElevationError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Whether a failed command's output says it needed more privileges than it had.
+pub fn is_permission_error(output: &str) -> bool {
+    let lower = output.to_lowercase();
+    [
+        "eacces",
+        "eperm",
+        "permission denied",
+        "operation not permitted",
+        "must be run as root",
+        "requires root",
+        "access is denied",
+    ]
+    .iter()
+    .any(|needle| lower.contains(needle))
+}
+
+/// Quotes `value` for embedding in an AppleScript string literal.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn applescript_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quotes `value` as a PowerShell single-quoted string.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn powershell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// `Start-Process -ArgumentList` takes one command line; quote arguments containing spaces.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn windows_argument_line(args: &[String]) -> String {
+    args.iter()
+        .map(|a| {
+            if a.contains(' ') || a.is_empty() {
+                format!("\"{}\"", a.replace('"', "\\\""))
+            } else {
+                a.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn declined(output: &process::Output) -> bool {
+    let stderr = output.stderr.to_lowercase();
+    // osascript: "User canceled. (-128)"; pkexec: exit 126 when the dialog is dismissed;
+    // UAC: "The operation was canceled by the user."
+    stderr.contains("-128") || stderr.contains("canceled") || output.code == Some(126)
+}
+
+/// Runs `program args...` with administrator rights and returns its stdout. On Windows
+/// `program` is a Windows executable (e.g. `wsl.exe`) and output is not captured.
+pub fn run_elevated(program: &str, args: &[String]) -> Result<String, ElevationError> {
+    let output = elevated_output(program, args)?;
+    if output.success() {
+        Ok(output.stdout)
+    } else if declined(&output) {
+        Err(ElevationError::Declined)
+    } else {
+        Err(ElevationError::Failed(
+            output.into_result().err().unwrap_or_default(),
+        ))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn elevated_output(program: &str, args: &[String]) -> Result<process::Output, ElevationError> {
+    let resolved = process::resolve(program).map_err(ElevationError::Failed)?;
+    // Keep the probed PATH so `#!/usr/bin/env node` scripts still find node as root.
+    let path = process::environment_info()
+        .shell_path
+        .unwrap_or_else(|| "/usr/local/bin:/usr/bin:/bin".to_string());
+    let command_line = std::iter::once(format!("PATH={}", crate::shell_single_quote(&path)))
+        .chain(std::iter::once(crate::shell_single_quote(&resolved)))
+        .chain(args.iter().map(|a| crate::shell_single_quote(a)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let script = format!(
+        "do shell script {} with administrator privileges",
+        applescript_quote(&command_line)
+    );
+    process::output_with_timeout("osascript", &["-e", &script], process::INSTALL_TIMEOUT)
+        .map_err(|e| ElevationError::Failed(e.to_string()))
+}
+
+#[cfg(target_os = "linux")]
+fn elevated_output(program: &str, args: &[String]) -> Result<process::Output, ElevationError> {
+    let resolved = process::resolve(program).map_err(ElevationError::Failed)?;
+    let path = process::environment_info()
+        .shell_path
+        .unwrap_or_else(|| "/usr/local/bin:/usr/bin:/bin".to_string());
+    let argv: Vec<String> = ["env".to_string(), format!("PATH={}", path), resolved]
+        .into_iter()
+        .chain(args.iter().cloned())
+        .collect();
+    process::output_with_timeout("pkexec", &argv, process::INSTALL_TIMEOUT)
+        .map_err(|e| ElevationError::Failed(e.to_string()))
+}
+
+#[cfg(target_os = "windows")]
+fn elevated_output(program: &str, args: &[String]) -> Result<process::Output, ElevationError> {
+    // Start-Process -Verb RunAs shows the UAC dialog; -Wait blocks until it finishes.
+    let script = format!(
+        "$p = Start-Process -FilePath {} -ArgumentList {} -Verb RunAs -Wait -PassThru; exit $p.ExitCode",
+        powershell_quote(program),
+        powershell_quote(&windows_argument_line(args))
+    );
+    let mut command = std::process::Command::new("powershell");
+    command.args(["-NoProfile", "-Command", &script]);
+    process::spawn_with_timeout(command, program.to_string(), process::INSTALL_TIMEOUT)
+        .map_err(|e| ElevationError::Failed(e.to_string()))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn elevated_output(_program: &str, _args: &[String]) -> Result<process::Output, ElevationError> {
+    Err(ElevationError::Failed(
+        "Running with administrator rights is not supported on this platform.".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applescript_quote() {
+        assert_eq!(
+            applescript_quote(r#"PATH='/a b' echo "hi" \ok"#),
+            r#""PATH='/a b' echo \"hi\" \\ok""#
+        );
+    }
+
+    #[test]
+    fn test_windows_argument_line_and_powershell_quote() {
+        let args = vec![
+            "--install".to_string(),
+            "--distribution".to_string(),
+            "My Distro".to_string(),
+        ];
+        assert_eq!(
+            windows_argument_line(&args),
+            "--install --distribution \"My Distro\""
+        );
+        assert_eq!(powershell_quote("it's"), "'it''s'");
+    }
+
+    #[test]
+    fn test_is_permission_error() {
+        assert!(is_permission_error("npm ERR! code EACCES"));
+        assert!(is_permission_error(
+            "launchctl: Operation not permitted while bootstrapping"
+        ));
+        assert!(!is_permission_error("Port 18789 is already in use"));
+    }
+}
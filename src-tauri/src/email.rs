@@ -0,0 +1,205 @@
+//! IMAP/SMTP credential checks for the email channel.
+//!
+//! Opens a real TLS session and logs in (IMAP `LOGIN`, SMTP `AUTH PLAIN`) so a typo in a host,
+//! port or app password is reported during setup rather than as a silent gateway error.
+//! IMAP uses implicit TLS; SMTP uses implicit TLS on 465 and STARTTLS on any other port.
+
+use base64::Engine;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+const SMTPS_PORT: u16 = 465;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct MailServer {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+}
+
+impl MailServer {
+    pub fn validate(&self, label: &str) -> Result<(), String> {
+        let host_ok = !self.host.is_empty()
+            && self
+                .host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+        if !host_ok {
+            return Err(format!("Invalid {} host: {}", label, self.host));
+        }
+        if self.port == 0 {
+            return Err(format!("Invalid {} port", label));
+        }
+        if self.user.trim().is_empty() || self.password.is_empty() {
+            return Err(format!("{} username and password are required.", label));
+        }
+        Ok(())
+    }
+}
+
+/// Quotes `value` as an IMAP quoted string.
+fn imap_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn smtp_auth_plain(user: &str, password: &str) -> String {
+    base64::engine::general_purpose::STANDARD.encode(format!("\0{}\0{}", user, password))
+}
+
+/// Code of a complete SMTP reply, or `None` while more `NNN-` continuation lines follow.
+fn smtp_reply_code(line: &str) -> Option<u16> {
+    if line.len() < 3 || line.as_bytes().get(3) == Some(&b'-') {
+        return None;
+    }
+    line[..3].parse().ok()
+}
+
+fn connect(host: &str, port: u16) -> Result<TcpStream, String> {
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Could not resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("Could not resolve {}", host))?;
+    let stream = TcpStream::connect_timeout(&addr, TIMEOUT)
+        .map_err(|e| format!("Could not connect to {}:{}: {}", host, port, e))?;
+    let _ = stream.set_read_timeout(Some(TIMEOUT));
+    let _ = stream.set_write_timeout(Some(TIMEOUT));
+    Ok(stream)
+}
+
+fn tls<S: std::io::Read + Write + std::fmt::Debug + 'static>(
+    host: &str,
+    stream: S,
+) -> Result<native_tls::TlsStream<S>, String> {
+    native_tls::TlsConnector::new()
+        .map_err(|e| e.to_string())?
+        .connect(host, stream)
+        .map_err(|e| format!("TLS handshake with {} failed: {}", host, e))
+}
+
+fn read_line<R: BufRead>(reader: &mut R) -> Result<String, String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => Err("Server closed the connection.".to_string()),
+        Ok(_) => Ok(line.trim_end().to_string()),
+        Err(e) => Err(format!("Failed to read from server: {}", e)),
+    }
+}
+
+fn send<W: Write>(writer: &mut W, line: &str) -> Result<(), String> {
+    writer
+        .write_all(format!("{}\r\n", line).as_bytes())
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("Failed to write to server: {}", e))
+}
+
+pub fn check_imap(server: &MailServer) -> Result<(), String> {
+    let stream = tls(&server.host, connect(&server.host, server.port)?)?;
+    let mut reader = BufReader::new(stream);
+    let greeting = read_line(&mut reader)?;
+    if !greeting.starts_with("* OK") {
+        return Err(format!("Unexpected IMAP greeting: {}", greeting));
+    }
+    send(
+        reader.get_mut(),
+        &format!(
+            "a1 LOGIN {} {}",
+            imap_quote(&server.user),
+            imap_quote(&server.password)
+        ),
+    )?;
+    loop {
+        let line = read_line(&mut reader)?;
+        if let Some(status) = line.strip_prefix("a1 ") {
+            let _ = send(reader.get_mut(), "a2 LOGOUT");
+            return if status.starts_with("OK") {
+                Ok(())
+            } else {
+                Err(format!("IMAP login failed: {}", status))
+            };
+        }
+    }
+}
+
+/// Reads one (possibly multi-line) reply and checks its code.
+fn expect_smtp<R: BufRead>(reader: &mut R, expected: u16) -> Result<(), String> {
+    loop {
+        let line = read_line(reader)?;
+        if let Some(code) = smtp_reply_code(&line) {
+            return if code == expected {
+                Ok(())
+            } else {
+                Err(format!("SMTP server replied: {}", line))
+            };
+        }
+    }
+}
+
+fn smtp_login<S: std::io::Read + Write>(
+    reader: &mut BufReader<S>,
+    server: &MailServer,
+    greeted: bool,
+) -> Result<(), String> {
+    if !greeted {
+        expect_smtp(reader, 220)?;
+    }
+    send(reader.get_mut(), "EHLO clawnetes")?;
+    expect_smtp(reader, 250)?;
+    send(
+        reader.get_mut(),
+        &format!(
+            "AUTH PLAIN {}",
+            smtp_auth_plain(&server.user, &server.password)
+        ),
+    )?;
+    expect_smtp(reader, 235).map_err(|e| format!("SMTP login failed. {}", e))?;
+    let _ = send(reader.get_mut(), "QUIT");
+    Ok(())
+}
+
+pub fn check_smtp(server: &MailServer) -> Result<(), String> {
+    let stream = connect(&server.host, server.port)?;
+    if server.port == SMTPS_PORT {
+        let mut reader = BufReader::new(tls(&server.host, stream)?);
+        return smtp_login(&mut reader, server, false);
+    }
+
+    let mut reader = BufReader::new(stream);
+    expect_smtp(&mut reader, 220)?;
+    send(reader.get_mut(), "EHLO clawnetes")?;
+    expect_smtp(&mut reader, 250)?;
+    send(reader.get_mut(), "STARTTLS")?;
+    expect_smtp(&mut reader, 220).map_err(|e| format!("STARTTLS not offered. {}", e))?;
+    let mut reader = BufReader::new(tls(&server.host, reader.into_inner())?);
+    smtp_login(&mut reader, server, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_helpers() {
+        assert_eq!(imap_quote(r#"pa"ss\word"#), r#""pa\"ss\\word""#);
+        assert_eq!(smtp_auth_plain("me", "pw"), "AG1lAHB3");
+        assert_eq!(smtp_reply_code("250-SIZE 35882577"), None);
+        assert_eq!(smtp_reply_code("250 OK"), Some(250));
+        assert_eq!(smtp_reply_code("535"), Some(535));
+    }
+
+    #[test]
+    fn test_mail_server_validate() {
+        let mut server = MailServer {
+            host: "imap.example.com".to_string(),
+            port: 993,
+            user: "me@example.com".to_string(),
+            password: "app-password".to_string(),
+        };
+        assert!(server.validate("IMAP").is_ok());
+        server.host = "imap.example.com; rm -rf /".to_string();
+        assert!(server.validate("IMAP").is_err());
+    }
+}
@@ -0,0 +1,177 @@
+//! Typed view of the gateway's live event stream.
+//!
+//! An operator connection receives every `chat` and `agent` event the gateway broadcasts.
+//! `classify` turns the ones the UI shows (finished messages, tool calls and failures) into
+//! `GatewayEvent`s, which the bridge in `main` re-emits as Tauri events named by
+//! `GatewayEvent::name`. Streaming deltas and lifecycle noise are dropped.
+
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GatewayEvent {
+    Message {
+        session_key: Option<String>,
+        agent: Option<String>,
+        run_id: Option<String>,
+        /// `assistant` or `user`.
+        role: String,
+        text: String,
+    },
+    ToolCall {
+        session_key: Option<String>,
+        agent: Option<String>,
+        run_id: Option<String>,
+        tool: String,
+        /// `start`, `update` or `result`.
+        phase: String,
+        tool_call_id: Option<String>,
+    },
+    Error {
+        session_key: Option<String>,
+        agent: Option<String>,
+        run_id: Option<String>,
+        message: String,
+    },
+}
+
+impl GatewayEvent {
+    /// Name of the Tauri event this is emitted as.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GatewayEvent::Message { .. } => "gateway-message",
+            GatewayEvent::ToolCall { .. } => "gateway-tool-call",
+            GatewayEvent::Error { .. } => "gateway-error",
+        }
+    }
+}
+
+/// `main` from a session key like `agent:main:telegram:dm:42`.
+pub fn agent_from_session_key(session_key: &str) -> Option<String> {
+    let mut parts = session_key.split(':');
+    match (parts.next(), parts.next()) {
+        (Some("agent"), Some(agent)) if !agent.is_empty() => Some(agent.to_string()),
+        _ => None,
+    }
+}
+
+fn text(value: &serde_json::Value, key: &str) -> Option<String> {
+    value.get(key).and_then(|v| v.as_str()).map(str::to_string)
+}
+
+/// The event carried by a gateway frame, if it is one the UI cares about.
+pub fn classify(frame: &serde_json::Value) -> Option<GatewayEvent> {
+    if frame.get("type").and_then(|v| v.as_str()) != Some("event") {
+        return None;
+    }
+    let payload = frame.get("payload")?;
+    let session_key = text(payload, "sessionKey");
+    let agent = session_key.as_deref().and_then(agent_from_session_key);
+    let run_id = text(payload, "runId");
+
+    match frame.get("event").and_then(|v| v.as_str())? {
+        "chat" => match payload.get("state").and_then(|v| v.as_str())? {
+            "final" => {
+                let message = payload.get("message")?;
+                Some(GatewayEvent::Message {
+                    role: text(message, "role").unwrap_or_else(|| "assistant".to_string()),
+                    text: crate::chat_message_text(message).unwrap_or_default(),
+                    session_key,
+                    agent,
+                    run_id,
+                })
+            }
+            "error" => Some(GatewayEvent::Error {
+                message: text(payload, "errorMessage")
+                    .unwrap_or_else(|| "The agent run failed".to_string()),
+                session_key,
+                agent,
+                run_id,
+            }),
+            _ => None,
+        },
+        "agent" => {
+            let data = payload.get("data")?;
+            match payload.get("stream").and_then(|v| v.as_str())? {
+                "tool" => Some(GatewayEvent::ToolCall {
+                    tool: text(data, "name")?,
+                    phase: text(data, "phase").unwrap_or_else(|| "start".to_string()),
+                    tool_call_id: text(data, "toolCallId"),
+                    session_key,
+                    agent,
+                    run_id,
+                }),
+                "lifecycle" if text(data, "phase").as_deref() == Some("error") => {
+                    Some(GatewayEvent::Error {
+                        message: text(data, "error")
+                            .unwrap_or_else(|| "The agent run failed".to_string()),
+                        session_key,
+                        agent,
+                        run_id,
+                    })
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_gateway_frames() {
+        let message = serde_json::json!({
+            "type": "event",
+            "event": "chat",
+            "payload": {
+                "runId": "r1",
+                "sessionKey": "agent:main:telegram:dm:42",
+                "state": "final",
+                "message": { "role": "assistant", "content": [{ "type": "text", "text": "Done" }] }
+            }
+        });
+        let event = classify(&message).unwrap();
+        assert_eq!(event.name(), "gateway-message");
+        assert_eq!(
+            event,
+            GatewayEvent::Message {
+                session_key: Some("agent:main:telegram:dm:42".to_string()),
+                agent: Some("main".to_string()),
+                run_id: Some("r1".to_string()),
+                role: "assistant".to_string(),
+                text: "Done".to_string(),
+            }
+        );
+
+        let tool = serde_json::json!({
+            "type": "event",
+            "event": "agent",
+            "payload": { "runId": "r1", "stream": "tool", "data": { "phase": "result", "name": "exec", "toolCallId": "t1" } }
+        });
+        assert_eq!(classify(&tool).unwrap().name(), "gateway-tool-call");
+
+        let failure = serde_json::json!({
+            "type": "event",
+            "event": "agent",
+            "payload": { "stream": "lifecycle", "data": { "phase": "error", "error": "rate limited" } }
+        });
+        assert!(matches!(
+            classify(&failure),
+            Some(GatewayEvent::Error { message, .. }) if message == "rate limited"
+        ));
+
+        let delta = serde_json::json!({
+            "type": "event",
+            "event": "chat",
+            "payload": { "state": "delta", "message": "Do" }
+        });
+        assert_eq!(classify(&delta), None);
+        assert_eq!(
+            classify(&serde_json::json!({ "type": "res", "id": "a" })),
+            None
+        );
+    }
+}
@@ -0,0 +1,478 @@
+//! Direct client for the OpenClaw gateway's WebSocket RPC API.
+//!
+//! Commands prefer this over shelling out to the `openclaw` CLI: one socket round trip is
+//! much faster than spawning a login shell and returns structured payloads. Callers check
+//! `is_reachable` first and fall back to the CLI when the gateway is down.
+
+use futures_util::{SinkExt, StreamExt};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+type GatewaySocket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+// The gateway auto-approves new client devices asynchronously; from the logs this takes
+// up to ~30 seconds. 5 attempts × 10 s stays well above that worst case.
+const CONNECT_ATTEMPTS: u8 = 5;
+const PAIRING_RETRY_DELAY: Duration = Duration::from_secs(10);
+const REACHABLE_TIMEOUT: Duration = Duration::from_secs(1);
+pub const DEFAULT_RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum RpcError {
+    /// The socket failed or closed before a response arrived.
+    Transport(String),
+    /// The gateway answered with `ok: false`; carries the `error` object.
+    Rejected(serde_json::Value),
+    TimedOut,
+}
+
+impl RpcError {
+    pub fn message(&self) -> String {
+        match self {
+            RpcError::Transport(msg) => msg.clone(),
+            RpcError::Rejected(err) => err
+                .get("message")
+                .and_then(|m| m.as_str())
+                .map(|m| m.to_string())
+                .unwrap_or_else(|| err.to_string()),
+            RpcError::TimedOut => "Gateway request timed out".to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcError::Transport(msg) => write!(f, "WebSocket error: {}", msg),
+            RpcError::Rejected(err) => write!(f, "Gateway error: {}", err),
+            RpcError::TimedOut => write!(f, "Gateway request timed out"),
+        }
+    }
+}
+
+impl From<RpcError> for String {
+    fn from(err: RpcError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Returns the response for `id` if `frame` is one: `Ok(payload)` or `Err(error)`.
+pub fn parse_rpc_response(
+    frame: &serde_json::Value,
+    id: &str,
+) -> Option<Result<serde_json::Value, serde_json::Value>> {
+    if frame.get("id").and_then(|v| v.as_str()) != Some(id) {
+        return None;
+    }
+    if frame.get("ok").and_then(|v| v.as_bool()) == Some(true) {
+        Some(Ok(frame
+            .get("payload")
+            .cloned()
+            .unwrap_or(serde_json::Value::Null)))
+    } else {
+        Some(Err(frame
+            .get("error")
+            .cloned()
+            .unwrap_or_else(|| frame.clone())))
+    }
+}
+
+/// NOT_PAIRED / DEVICE_IDENTITY_REQUIRED mean the gateway has started approving this
+/// client device and the connection should be retried after a pause.
+pub fn is_pairing_pending_error(error: &serde_json::Value) -> bool {
+    let code = error.get("code").and_then(|c| c.as_str()).unwrap_or("");
+    let detail_code = error
+        .get("details")
+        .and_then(|d| d.get("code"))
+        .and_then(|c| c.as_str())
+        .unwrap_or("");
+    code == "NOT_PAIRED" || detail_code == "DEVICE_IDENTITY_REQUIRED"
+}
+
+#[derive(Clone)]
+pub struct GatewayClient {
+    url: String,
+    port: u16,
+    token: Option<String>,
+    connect_attempts: u8,
+}
+
+impl GatewayClient {
+    pub fn new(port: u16, token: Option<String>) -> Self {
+        GatewayClient {
+            url: format!("ws://127.0.0.1:{}", port),
+            port,
+            token,
+            connect_attempts: CONNECT_ATTEMPTS,
+        }
+    }
+
+    /// Client for a gateway on another machine, at a `ws://` or `wss://` URL.
+    pub fn remote(url: &str, token: Option<String>) -> Result<Self, String> {
+        let port = url::Url::parse(url)
+            .ok()
+            .and_then(|parsed| parsed.port_or_known_default())
+            .ok_or_else(|| format!("Invalid gateway URL '{}'", url))?;
+        Ok(GatewayClient {
+            url: url.to_string(),
+            port,
+            token,
+            connect_attempts: CONNECT_ATTEMPTS,
+        })
+    }
+
+    /// Client for the gateway described by the local `openclaw.json`.
+    pub fn local() -> Self {
+        let config = crate::read_local_openclaw_config().unwrap_or(serde_json::json!({}));
+        let gateway = config.get("gateway");
+        let port = gateway
+            .and_then(|g| g.get("port"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(18789) as u16;
+        let token = gateway
+            .and_then(|g| g.get("auth"))
+            .and_then(|a| a.get("token"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        GatewayClient::new(port, token)
+    }
+
+    /// Gives up on the first NOT_PAIRED answer instead of waiting for approval, for callers
+    /// that have a CLI fallback.
+    pub fn single_attempt(mut self) -> Self {
+        self.connect_attempts = 1;
+        self
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub async fn is_reachable(&self) -> bool {
+        let Some(addr) = url::Url::parse(&self.url).ok().and_then(|url| {
+            Some(format!(
+                "{}:{}",
+                url.host_str()?,
+                url.port_or_known_default()?
+            ))
+        }) else {
+            return false;
+        };
+        matches!(
+            tokio::time::timeout(REACHABLE_TIMEOUT, tokio::net::TcpStream::connect(addr)).await,
+            Ok(Ok(_))
+        )
+    }
+
+    fn connect_request(&self, id: &str) -> serde_json::Value {
+        let mut msg = serde_json::json!({
+            "type": "req",
+            "id": id,
+            "method": "connect",
+            "params": {
+                "client": {
+                    "id": "gateway-client",
+                    "version": "1.0",
+                    "platform": std::env::consts::OS,
+                    "mode": "backend"
+                },
+                "minProtocol": 3,
+                "maxProtocol": 3,
+                "role": "operator",
+                "scopes": ["operator.admin"]
+            }
+        });
+        if let Some(ref token) = self.token {
+            if let Some(params) = msg.get_mut("params").and_then(|p| p.as_object_mut()) {
+                params.insert("auth".to_string(), serde_json::json!({ "token": token }));
+            }
+        }
+        msg
+    }
+
+    /// Performs one connect handshake. `Ok(None)` means the device is still awaiting
+    /// pairing approval and the gateway closed the connection.
+    pub async fn connect_once(&self) -> Result<Option<GatewayConnection>, RpcError> {
+        let (mut socket, _) = connect_async(&self.url)
+            .await
+            .map_err(|e| RpcError::Transport(format!("connect failed: {}", e)))?;
+
+        let id = uuid::Uuid::new_v4().to_string();
+        socket
+            .send(Message::Text(self.connect_request(&id).to_string()))
+            .await
+            .map_err(|e| RpcError::Transport(format!("send connect failed: {}", e)))?;
+
+        while let Some(msg) = socket.next().await {
+            match msg {
+                Ok(Message::Text(text)) => {
+                    let frame: serde_json::Value =
+                        serde_json::from_str(&text).unwrap_or(serde_json::json!({}));
+                    match parse_rpc_response(&frame, &id) {
+                        Some(Ok(_)) => return Ok(Some(GatewayConnection { socket })),
+                        Some(Err(error)) if is_pairing_pending_error(&error) => return Ok(None),
+                        Some(Err(error)) => return Err(RpcError::Rejected(error)),
+                        None => {}
+                    }
+                }
+                Ok(Message::Close(_)) => break,
+                Err(e) => return Err(RpcError::Transport(e.to_string())),
+                _ => {}
+            }
+        }
+
+        Err(RpcError::Transport(
+            "connection closed during handshake".to_string(),
+        ))
+    }
+
+    /// Connects, retrying while the gateway approves this client device.
+    pub async fn connect(&self) -> Result<GatewayConnection, RpcError> {
+        for attempt in 0..self.connect_attempts {
+            if attempt > 0 {
+                tokio::time::sleep(PAIRING_RETRY_DELAY).await;
+            }
+            if let Some(connection) = self.connect_once().await? {
+                return Ok(connection);
+            }
+        }
+        Err(RpcError::Transport(if self.connect_attempts > 1 {
+            "Gateway connect handshake failed after retries".to_string()
+        } else {
+            "This device is still awaiting pairing approval".to_string()
+        }))
+    }
+
+    /// One-shot RPC on a fresh connection.
+    pub async fn call(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, RpcError> {
+        let mut connection = self.connect().await?;
+        connection
+            .request(method, params, DEFAULT_RPC_TIMEOUT)
+            .await
+    }
+
+    pub async fn status(&self) -> Result<serde_json::Value, RpcError> {
+        self.call("status", serde_json::json!({})).await
+    }
+
+    pub async fn list_sessions(
+        &self,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, RpcError> {
+        self.call("sessions.list", params).await
+    }
+
+    /// Per-channel runtime state (`running`, `connected`, per-account entries).
+    pub async fn channels_status(&self) -> Result<serde_json::Value, RpcError> {
+        self.call("channels.status", serde_json::json!({})).await
+    }
+
+    pub async fn reload_config(&self) -> Result<serde_json::Value, RpcError> {
+        self.call("config.reload", serde_json::json!({})).await
+    }
+
+    /// The config the running gateway loaded, including the `hash` of the file it read.
+    pub async fn config_snapshot(&self) -> Result<serde_json::Value, RpcError> {
+        self.call("config.get", serde_json::json!({})).await
+    }
+
+    /// `account` targets one bot when the channel has several accounts.
+    pub async fn approve_pairing(
+        &self,
+        channel: &str,
+        code: &str,
+        account: Option<&str>,
+    ) -> Result<serde_json::Value, RpcError> {
+        let mut params = serde_json::json!({ "channel": channel, "code": code });
+        if let Some(account) = account {
+            params["accountId"] = serde_json::json!(account);
+        }
+        self.call("pairing.approve", params).await
+    }
+
+    /// Has the agent send `message` to `to` on `channel` on its own initiative.
+    pub async fn send_message(
+        &self,
+        channel: &str,
+        to: &str,
+        message: &str,
+        account: Option<&str>,
+    ) -> Result<serde_json::Value, RpcError> {
+        let mut params = serde_json::json!({
+            "channel": channel,
+            "to": to,
+            "message": message,
+            "idempotencyKey": uuid::Uuid::new_v4().to_string()
+        });
+        if let Some(account) = account {
+            params["accountId"] = serde_json::json!(account);
+        }
+        self.call("send", params).await
+    }
+
+    /// The last `limit` lines of the gateway's log file.
+    pub async fn tail_logs(&self, limit: u32) -> Result<serde_json::Value, RpcError> {
+        self.call("logs.tail", serde_json::json!({ "limit": limit }))
+            .await
+    }
+}
+
+pub struct GatewayConnection {
+    socket: GatewaySocket,
+}
+
+impl GatewayConnection {
+    /// Sends a request frame and returns its id without waiting for the response.
+    pub async fn send_request(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<String, RpcError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let frame = serde_json::json!({
+            "type": "req",
+            "id": id,
+            "method": method,
+            "params": params
+        });
+        self.socket
+            .send(Message::Text(frame.to_string()))
+            .await
+            .map_err(|e| RpcError::Transport(format!("send failed: {}", e)))?;
+        Ok(id)
+    }
+
+    /// Next JSON frame from the gateway (responses and events alike); `None` once closed.
+    pub async fn next_frame(&mut self) -> Option<Result<serde_json::Value, RpcError>> {
+        while let Some(msg) = self.socket.next().await {
+            match msg {
+                Ok(Message::Text(text)) => {
+                    return Some(Ok(
+                        serde_json::from_str(&text).unwrap_or(serde_json::json!({}))
+                    ))
+                }
+                Ok(Message::Close(_)) => return None,
+                Err(e) => return Some(Err(RpcError::Transport(e.to_string()))),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    pub async fn request(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+        timeout: Duration,
+    ) -> Result<serde_json::Value, RpcError> {
+        let id = self.send_request(method, params).await?;
+        let wait = async {
+            while let Some(frame) = self.next_frame().await {
+                if let Some(result) = parse_rpc_response(&frame?, &id) {
+                    return result.map_err(RpcError::Rejected);
+                }
+            }
+            Err(RpcError::Transport(
+                "connection closed before response".to_string(),
+            ))
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .unwrap_or(Err(RpcError::TimedOut))
+    }
+}
+
+/// Log lines from a `logs.tail` payload; entries may be plain strings or structured records.
+pub fn log_tail_lines(payload: &serde_json::Value) -> Vec<String> {
+    payload
+        .get("lines")
+        .and_then(|lines| lines.as_array())
+        .map(|lines| {
+            lines
+                .iter()
+                .map(|line| match line {
+                    serde_json::Value::String(text) => text.clone(),
+                    other => other
+                        .get("raw")
+                        .or_else(|| other.get("message"))
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string)
+                        .unwrap_or_else(|| other.to_string()),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rpc_response_matches_id() {
+        let ok = serde_json::json!({ "type": "res", "id": "a", "ok": true, "payload": { "x": 1 } });
+        assert_eq!(
+            parse_rpc_response(&ok, "a"),
+            Some(Ok(serde_json::json!({ "x": 1 })))
+        );
+        assert_eq!(parse_rpc_response(&ok, "b"), None);
+
+        let err = serde_json::json!({ "id": "a", "ok": false, "error": { "code": "BAD" } });
+        assert_eq!(
+            parse_rpc_response(&err, "a"),
+            Some(Err(serde_json::json!({ "code": "BAD" })))
+        );
+    }
+
+    #[test]
+    fn test_is_pairing_pending_error() {
+        assert!(is_pairing_pending_error(
+            &serde_json::json!({ "code": "NOT_PAIRED" })
+        ));
+        assert!(is_pairing_pending_error(&serde_json::json!({
+            "code": "UNAUTHORIZED",
+            "details": { "code": "DEVICE_IDENTITY_REQUIRED" }
+        })));
+        assert!(!is_pairing_pending_error(
+            &serde_json::json!({ "code": "UNAUTHORIZED" })
+        ));
+    }
+
+    #[test]
+    fn test_log_tail_lines_and_remote_port() {
+        let payload = serde_json::json!({
+            "cursor": 10,
+            "lines": ["plain", { "raw": "structured" }, { "message": "fallback" }]
+        });
+        assert_eq!(
+            log_tail_lines(&payload),
+            ["plain", "structured", "fallback"]
+        );
+        assert!(log_tail_lines(&serde_json::json!({})).is_empty());
+
+        assert_eq!(
+            GatewayClient::remote("wss://office.example", None)
+                .unwrap()
+                .port(),
+            443
+        );
+        assert_eq!(
+            GatewayClient::remote("ws://10.0.0.2:18789", None)
+                .unwrap()
+                .port(),
+            18789
+        );
+    }
+
+    #[test]
+    fn test_rpc_error_message_prefers_gateway_message() {
+        let err = RpcError::Rejected(serde_json::json!({ "code": "X", "message": "nope" }));
+        assert_eq!(err.message(), "nope");
+        assert_eq!(RpcError::TimedOut.message(), "Gateway request timed out");
+    }
+}
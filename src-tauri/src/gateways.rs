@@ -0,0 +1,152 @@
+//! Registry of OpenClaw gateways on other machines, stored as `gateways.json` in the app
+//! data directory.
+//!
+//! Only the name and URL are kept in the file; each gateway's token lives in the system
+//! keychain under `keychain_account(id)`. Commands that take a `gateway_id` talk to the
+//! registered gateway over its WebSocket API, and treat a missing id or `local` as the
+//! gateway on this machine.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const GATEWAYS_FILE: &str = "gateways.json";
+pub const LOCAL_GATEWAY_ID: &str = "local";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Gateway {
+    /// Derived from the name, e.g. `office-mac`.
+    pub id: String,
+    pub name: String,
+    /// `ws://` or `wss://` URL of the gateway.
+    pub url: String,
+}
+
+pub fn keychain_account(id: &str) -> String {
+    format!("gateway:{}", id)
+}
+
+/// Accepts `ws(s)://`, `http(s)://` (the dashboard address) or a bare `host:port`, and returns
+/// the WebSocket URL without a trailing slash.
+pub fn normalize_url(url: &str) -> Result<String, String> {
+    let url = url.trim().trim_end_matches('/');
+    let normalized = if let Some(rest) = url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else if url.starts_with("ws://") || url.starts_with("wss://") {
+        url.to_string()
+    } else if url.contains("://") {
+        return Err(format!("Gateway URL '{}' must use ws:// or wss://", url));
+    } else {
+        format!("ws://{}", url)
+    };
+    let parsed = url::Url::parse(&normalized)
+        .map_err(|e| format!("Invalid gateway URL '{}': {}", url, e))?;
+    if parsed.host_str().unwrap_or_default().is_empty() {
+        return Err(format!("Gateway URL '{}' has no host", url));
+    }
+    Ok(normalized)
+}
+
+/// Dashboard address served by the gateway at `url`, logged in with `token`.
+pub fn dashboard_url(url: &str, token: Option<&str>) -> String {
+    let base = if let Some(rest) = url.strip_prefix("wss://") {
+        format!("https://{}", rest)
+    } else {
+        format!("http://{}", url.trim_start_matches("ws://"))
+    };
+    match token {
+        Some(token) => format!("{}/#token={}", base, token),
+        None => format!("{}/", base),
+    }
+}
+
+fn slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Adds a gateway named `name` at `url` to `gateways`, returning the new entry.
+pub fn add(gateways: &mut Vec<Gateway>, name: &str, url: &str) -> Result<Gateway, String> {
+    let id = slug(name);
+    if id.is_empty() {
+        return Err("Give the gateway a name with at least one letter or digit.".to_string());
+    }
+    if id == LOCAL_GATEWAY_ID || gateways.iter().any(|g| g.id == id) {
+        return Err(format!("A gateway named '{}' already exists.", name.trim()));
+    }
+    let gateway = Gateway {
+        id,
+        name: name.trim().to_string(),
+        url: normalize_url(url)?,
+    };
+    gateways.push(gateway.clone());
+    Ok(gateway)
+}
+
+pub fn find<'a>(gateways: &'a [Gateway], id: &str) -> Result<&'a Gateway, String> {
+    gateways
+        .iter()
+        .find(|g| g.id == id)
+        .ok_or_else(|| format!("Unknown gateway '{}'", id))
+}
+
+pub fn load(app_dir: &Path) -> Vec<Gateway> {
+    fs::read_to_string(app_dir.join(GATEWAYS_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(app_dir: &Path, gateways: &[Gateway]) -> Result<(), String> {
+    fs::create_dir_all(app_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let raw = serde_json::to_string_pretty(gateways)
+        .map_err(|e| format!("Failed to serialize gateways: {}", e))?;
+    fs::write(app_dir.join(GATEWAYS_FILE), raw)
+        .map_err(|e| format!("Failed to save gateways: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_normalizes_url_and_rejects_duplicates() {
+        let mut gateways = Vec::new();
+        let office = add(
+            &mut gateways,
+            " Office Mac ",
+            "https://office.tail1234.ts.net/",
+        )
+        .unwrap();
+        assert_eq!(office.id, "office-mac");
+        assert_eq!(office.url, "wss://office.tail1234.ts.net");
+        assert_eq!(
+            add(&mut gateways, "pi", "192.168.1.20:18789").unwrap().url,
+            "ws://192.168.1.20:18789"
+        );
+        assert!(add(&mut gateways, "office mac", "ws://other:18789").is_err());
+        assert!(add(&mut gateways, "Local", "ws://other:18789").is_err());
+        assert!(add(&mut gateways, "nas", "ftp://nas").is_err());
+        assert_eq!(find(&gateways, "pi").unwrap().name, "pi");
+        assert!(find(&gateways, "laptop").is_err());
+
+        assert_eq!(
+            dashboard_url(&office.url, Some("abc")),
+            "https://office.tail1234.ts.net/#token=abc"
+        );
+        assert_eq!(
+            dashboard_url("ws://192.168.1.20:18789", None),
+            "http://192.168.1.20:18789/"
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! Headless mode: `clawnetes <command>` runs setup steps without opening the window, for
+//! servers and scripts. Each command calls the same functions as the GUI, prints JSON or a
+//! message to stdout and exits non-zero on failure.
+
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: clawnetes <command>
+
+Commands:
+  check                       Check prerequisites and system resources
+  install [--backend <name>]  Install openclaw (npm, pnpm, bun or brew)
+  configure --file <path>     Configure the agent from a JSON config file
+  gateway start               Start the gateway and wait until it accepts connections
+  doctor [--fix]              Run connectivity checks and openclaw doctor
+  help [<command>]            Show this message, or how to use one command
+
+Without a command the setup window opens.";
+
+#[derive(Debug, PartialEq)]
+pub enum HeadlessCommand {
+    Check,
+    Install { backend: Option<String> },
+    Configure { file: PathBuf },
+    GatewayStart,
+    Doctor { fix: bool },
+    /// Usage of `command`, or of every command.
+    Help {
+        command: Option<String>,
+    },
+}
+
+/// A command's arguments, checked against the options it takes.
+#[derive(Default)]
+struct CommandArgs<'a> {
+    positional: Vec<&'a str>,
+    values: Vec<(&'static str, &'a str)>,
+    switches: Vec<&'static str>,
+    help: bool,
+}
+
+impl CommandArgs<'_> {
+    fn value(&self, flag: &str) -> Option<String> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == flag)
+            .map(|(_, value)| value.to_string())
+    }
+
+    fn switch(&self, flag: &str) -> bool {
+        self.switches.contains(&flag)
+    }
+}
+
+/// Splits `rest` into positionals, `value_flags` (`--flag value` or `--flag=value`) and
+/// `switches`. `--help` anywhere asks for the command's usage; any other option is an error,
+/// so a typo such as `doctor --fxi` does not run plain `doctor`.
+fn command_args<'a>(
+    command: &str,
+    rest: &'a [String],
+    value_flags: &[&'static str],
+    switches: &[&'static str],
+) -> Result<CommandArgs<'a>, String> {
+    let mut parsed = CommandArgs::default();
+    let mut unknown = None;
+    let mut args = rest.iter().map(String::as_str);
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            parsed.help = true;
+        } else if !arg.starts_with('-') {
+            parsed.positional.push(arg);
+        } else {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag, Some(value)),
+                None => (arg, None),
+            };
+            if let Some(&flag) = value_flags.iter().find(|f| **f == flag) {
+                let value = inline.or_else(|| args.next().filter(|v| !v.starts_with('-')));
+                match value.filter(|v| !v.is_empty()) {
+                    Some(value) => parsed.values.push((flag, value)),
+                    None => unknown = unknown.or(Some(format!("{} needs a value", flag))),
+                }
+            } else if let Some(&switch) = switches.iter().find(|s| **s == arg) {
+                parsed.switches.push(switch);
+            } else {
+                unknown = unknown.or(Some(format!("Unknown option '{}' for {}", arg, command)));
+            }
+        }
+    }
+    match unknown {
+        Some(e) if !parsed.help => Err(e),
+        _ => Ok(parsed),
+    }
+}
+
+/// `Usage: clawnetes <command> …` and the command's description, from its line in `USAGE`.
+pub fn command_usage(command: &str) -> Option<String> {
+    USAGE.lines().find_map(|line| {
+        let (synopsis, description) = line.strip_prefix("  ")?.split_once("  ")?;
+        (synopsis.split_whitespace().next() == Some(command)).then(|| {
+            format!(
+                "Usage: clawnetes {}\n\n{}",
+                synopsis.trim(),
+                description.trim()
+            )
+        })
+    })
+}
+
+/// `Ok(None)` means no command was given and the GUI should start. Flags the OS passes to
+/// GUI launches (e.g. macOS `-psn_…`) are ignored the same way.
+pub fn parse_args(args: &[String]) -> Result<Option<HeadlessCommand>, String> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(None);
+    };
+    let name = command.as_str();
+    let (value_flags, switches): (&[&'static str], &[&'static str]) = match name {
+        "help" | "--help" | "-h" => {
+            let command = rest.first().cloned();
+            if let Some(other) = command.as_deref().filter(|c| command_usage(c).is_none()) {
+                return Err(format!("Unknown command '{}'", other));
+            }
+            return Ok(Some(HeadlessCommand::Help { command }));
+        }
+        "check" | "gateway" => (&[], &[]),
+        "install" => (&["--backend"], &[]),
+        "configure" => (&["--file"], &[]),
+        "doctor" => (&[], &["--fix"]),
+        other if other.starts_with('-') => return Ok(None),
+        other => return Err(format!("Unknown command '{}'", other)),
+    };
+    let parsed = command_args(name, rest, value_flags, switches)?;
+    if parsed.help {
+        return Ok(Some(HeadlessCommand::Help {
+            command: Some(name.to_string()),
+        }));
+    }
+    if name == "gateway" {
+        return match parsed.positional[..] {
+            ["start"] => Ok(Some(HeadlessCommand::GatewayStart)),
+            _ => Err("Unknown gateway command; expected `gateway start`".to_string()),
+        };
+    }
+    if let Some(arg) = parsed.positional.first() {
+        return Err(format!("Unexpected argument '{}' for {}", arg, name));
+    }
+    let command = match name {
+        "check" => HeadlessCommand::Check,
+        "install" => HeadlessCommand::Install {
+            backend: parsed.value("--backend"),
+        },
+        "configure" => HeadlessCommand::Configure {
+            file: parsed
+                .value("--file")
+                .map(PathBuf::from)
+                .ok_or("configure needs --file <path>")?,
+        },
+        _ => HeadlessCommand::Doctor {
+            fix: parsed.switch("--fix"),
+        },
+    };
+    Ok(Some(command))
+}
+
+fn print_json<T: serde::Serialize>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Failed to serialize output: {}", e),
+    }
+}
+
+fn report(result: Result<String, String>) -> i32 {
+    match result {
+        Ok(message) => {
+            println!("{}", message.trim());
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e.trim());
+            1
+        }
+    }
+}
+
+fn doctor(fix: bool) -> i32 {
+    let connectivity = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())
+        .and_then(|rt| rt.block_on(crate::check_connectivity(None)));
+    let connectivity_ok = match connectivity {
+        Ok(report) => {
+            print_json(&report);
+            report.status == "ok"
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            false
+        }
+    };
+    let doctor = if fix {
+        crate::run_doctor_repair()
+    } else {
+        crate::shell_command("openclaw doctor --non-interactive")
+    };
+    match (report(doctor), connectivity_ok) {
+        (0, true) => 0,
+        _ => 1,
+    }
+}
+
+/// The GUI's app data directory, resolved without an `AppHandle` from the bundle identifier
+/// in `tauri.conf.json`.
+fn app_data_dir() -> Option<PathBuf> {
+    let conf: serde_json::Value = serde_json::from_str(include_str!("../tauri.conf.json")).ok()?;
+    let mut config = tauri::Config::default();
+    config.tauri.bundle.identifier = conf
+        .pointer("/tauri/bundle/identifier")?
+        .as_str()?
+        .to_string();
+    tauri::api::path::app_data_dir(&config)
+}
+
+/// Runs `command` and returns the process exit code.
+pub fn run(command: HeadlessCommand) -> i32 {
+    // Same settings as the window, so e.g. the active profile's root is used.
+    if let Some(dir) = app_data_dir() {
+        crate::apply_app_settings(&crate::settings::load(&dir));
+    }
+    crate::process::probe_environment();
+    match command {
+        HeadlessCommand::Check => {
+            print_json(&crate::check_prerequisites());
+            0
+        }
+        HeadlessCommand::Install { backend } => report(crate::install_openclaw_with(
+            Default::default(),
+            backend,
+            &crate::progress::Progress::stderr("install"),
+        )),
+        HeadlessCommand::Configure { file } => report(
+            std::fs::read_to_string(&file)
+                .map_err(|e| format!("Failed to read {}: {}", file.display(), e))
+                .and_then(|raw| {
+                    serde_json::from_str(&raw)
+                        .map_err(|e| format!("Invalid config file {}: {}", file.display(), e))
+                })
+                .and_then(crate::configure_agent),
+        ),
+        HeadlessCommand::GatewayStart => report(crate::start_local_gateway(
+            &crate::progress::Progress::stderr("gateway"),
+        )),
+        HeadlessCommand::Doctor { fix } => doctor(fix),
+        HeadlessCommand::Help { command } => {
+            match command.as_deref().and_then(command_usage) {
+                Some(usage) => println!("{}", usage),
+                None => println!("{}", USAGE),
+            }
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(&[]), Ok(None));
+        assert_eq!(parse_args(&args("-psn_0_12345")), Ok(None));
+        assert_eq!(
+            parse_args(&args("install --backend pnpm")),
+            Ok(Some(HeadlessCommand::Install {
+                backend: Some("pnpm".to_string())
+            }))
+        );
+        assert_eq!(
+            parse_args(&args("configure --file agent.json")),
+            Ok(Some(HeadlessCommand::Configure {
+                file: PathBuf::from("agent.json")
+            }))
+        );
+        assert_eq!(
+            parse_args(&args("gateway start")),
+            Ok(Some(HeadlessCommand::GatewayStart))
+        );
+        assert!(parse_args(&args("configure")).is_err());
+        assert!(parse_args(&args("install --backend")).is_err());
+        assert!(parse_args(&args("deploy")).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_rejects_unknown_options() {
+        assert_eq!(
+            parse_args(&args("doctor --fix")),
+            Ok(Some(HeadlessCommand::Doctor { fix: true }))
+        );
+        assert_eq!(
+            parse_args(&args("install --backend=bun")),
+            Ok(Some(HeadlessCommand::Install {
+                backend: Some("bun".to_string())
+            }))
+        );
+        assert_eq!(
+            parse_args(&args("doctor --fxi")),
+            Err("Unknown option '--fxi' for doctor".to_string())
+        );
+        assert!(parse_args(&args("check --verbose")).is_err());
+        assert!(parse_args(&args("install --backend pnpm --force")).is_err());
+        assert!(parse_args(&args("configure --file agent.json --fix")).is_err());
+        assert!(parse_args(&args("gateway start --now")).is_err());
+        assert!(parse_args(&args("check now")).is_err());
+        assert!(parse_args(&args("gateway start now")).is_err());
+        assert!(parse_args(&args("gateway stop")).is_err());
+        assert!(parse_args(&args("install --backend --fix")).is_err());
+        assert!(parse_args(&args("help deploy")).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_help() {
+        assert_eq!(
+            parse_args(&args("--help")),
+            Ok(Some(HeadlessCommand::Help { command: None }))
+        );
+        assert_eq!(
+            parse_args(&args("help doctor")),
+            Ok(Some(HeadlessCommand::Help {
+                command: Some("doctor".to_string())
+            }))
+        );
+        // Help wins over the command's other arguments, so it can be asked for mid-typo.
+        assert_eq!(
+            parse_args(&args("configure --help")),
+            Ok(Some(HeadlessCommand::Help {
+                command: Some("configure".to_string())
+            }))
+        );
+        assert_eq!(
+            parse_args(&args("doctor --fxi -h")),
+            Ok(Some(HeadlessCommand::Help {
+                command: Some("doctor".to_string())
+            }))
+        );
+        assert_eq!(
+            command_usage("install").as_deref(),
+            Some("Usage: clawnetes install [--backend <name>]\n\nInstall openclaw (npm, pnpm, bun or brew)")
+        );
+        assert_eq!(
+            command_usage("gateway").as_deref(),
+            Some("Usage: clawnetes gateway start\n\nStart the gateway and wait until it accepts connections")
+        );
+        assert_eq!(command_usage("deploy"), None);
+    }
+
+    #[test]
+    fn test_app_data_dir_uses_bundle_identifier() {
+        if let Some(dir) = app_data_dir() {
+            assert!(dir.ends_with("com.clawnetes.app"));
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Secrets kept in the operating system's credential store rather than on disk.
+//!
+//! macOS uses the login keychain through `security`, Linux the Secret Service through
+//! `secret-tool` and Windows the Credential Locker through PowerShell. Secrets always travel
+//! over the child's stdin so they never show up in a process listing.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+pub const SERVICE: &str = "com.clawnetes.app";
+
+/// Input for `security -i`, which reads one command per line.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub fn security_store_input(account: &str, secret: &str) -> String {
+    let quote = |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+    format!(
+        "add-generic-password -U -s {} -a {} -w {}\n",
+        quote(SERVICE),
+        quote(account),
+        quote(secret)
+    )
+}
+
+#[cfg(target_os = "windows")]
+fn vault_script(body: &str) -> String {
+    format!(
+        "[void][Windows.Security.Credentials.PasswordVault,Windows.Security.Credentials,ContentType=WindowsRuntime]; \
+         $v = New-Object Windows.Security.Credentials.PasswordVault; {}",
+        body
+    )
+}
+
+/// Runs `program` with `input` on stdin and returns stdout on success.
+fn run_with_input(program: &str, args: &[&str], input: &str) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("No system keychain available ({}): {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("Failed to talk to the keychain: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to talk to the keychain: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(format!(
+            "Keychain error: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+pub fn store(account: &str, secret: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    run_with_input("security", &["-i"], &security_store_input(account, secret))?;
+    #[cfg(target_os = "linux")]
+    run_with_input(
+        "secret-tool",
+        &[
+            "store",
+            "--label",
+            &format!("Clawnetes {}", account),
+            "service",
+            SERVICE,
+            "account",
+            account,
+        ],
+        secret,
+    )?;
+    #[cfg(target_os = "windows")]
+    run_with_input(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            &vault_script(&format!(
+                "$v.Add((New-Object Windows.Security.Credentials.PasswordCredential('{}', '{}', [Console]::In.ReadToEnd())))",
+                SERVICE, account
+            )),
+        ],
+        secret,
+    )?;
+    Ok(())
+}
+
+/// The secret for `account`, or `None` when nothing is stored.
+pub fn load(account: &str) -> Option<String> {
+    #[cfg(target_os = "macos")]
+    let output = run_with_input(
+        "security",
+        &["find-generic-password", "-s", SERVICE, "-a", account, "-w"],
+        "",
+    );
+    #[cfg(target_os = "linux")]
+    let output = run_with_input(
+        "secret-tool",
+        &["lookup", "service", SERVICE, "account", account],
+        "",
+    );
+    #[cfg(target_os = "windows")]
+    let output = run_with_input(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            &vault_script(&format!(
+                "$c = $v.Retrieve('{}', '{}'); $c.RetrievePassword(); $c.Password",
+                SERVICE, account
+            )),
+        ],
+        "",
+    );
+    output
+        .ok()
+        .map(|secret| secret.trim_end_matches(['\r', '\n']).to_string())
+        .filter(|secret| !secret.is_empty())
+}
+
+pub fn delete(account: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let output = run_with_input(
+        "security",
+        &["delete-generic-password", "-s", SERVICE, "-a", account],
+        "",
+    );
+    #[cfg(target_os = "linux")]
+    let output = run_with_input(
+        "secret-tool",
+        &["clear", "service", SERVICE, "account", account],
+        "",
+    );
+    #[cfg(target_os = "windows")]
+    let output = run_with_input(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            &vault_script(&format!(
+                "$v.Remove($v.Retrieve('{}', '{}'))",
+                SERVICE, account
+            )),
+        ],
+        "",
+    );
+    // Deleting a secret that was never stored is not an error.
+    match output {
+        Err(e) if load(account).is_some() => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_security_store_input_escapes_quotes() {
+        assert_eq!(
+            security_store_input("gateway:office", "a\"b\\c"),
+            "add-generic-password -U -s \"com.clawnetes.app\" -a \"gateway:office\" -w \"a\\\"b\\\\c\"\n"
+        );
+    }
+}
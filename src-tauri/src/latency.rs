@@ -0,0 +1,166 @@
+//! Provider latency and availability from periodic probes.
+//!
+//! The prober times one cheap request per configured provider every few minutes and feeds
+//! the result to `Tracker::record`; stats cover the last `WINDOW` probes. `recommend_fallbacks`
+//! reorders a fallback chain so providers that answer, and answer quickly, come first.
+//! Providers that were never probed keep their configured place relative to each other.
+
+use std::collections::{BTreeMap, VecDeque};
+
+pub const WINDOW: usize = 12;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Sample {
+    at: u64,
+    result: Result<u64, String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct ProviderLatency {
+    pub provider: String,
+    /// Whether the most recent probe got an answer.
+    pub available: bool,
+    pub last_ms: Option<u64>,
+    /// Mean of the successful probes in the window.
+    pub average_ms: Option<u64>,
+    /// Percentage of probes in the window that succeeded.
+    pub success_rate: u8,
+    pub checked_at: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct Tracker {
+    samples: BTreeMap<String, VecDeque<Sample>>,
+}
+
+impl Tracker {
+    /// Records one probe: round-trip milliseconds, or why it failed.
+    pub fn record(&mut self, provider: &str, result: Result<u64, String>, now: u64) {
+        let samples = self.samples.entry(provider.to_string()).or_default();
+        samples.push_back(Sample { at: now, result });
+        while samples.len() > WINDOW {
+            samples.pop_front();
+        }
+    }
+
+    /// Forgets providers that are no longer configured.
+    pub fn retain(&mut self, providers: &[String]) {
+        self.samples
+            .retain(|provider, _| providers.iter().any(|p| p == provider));
+    }
+
+    pub fn snapshot(&self) -> Vec<ProviderLatency> {
+        self.samples
+            .iter()
+            .filter_map(|(provider, samples)| {
+                let last = samples.back()?;
+                let successes: Vec<u64> = samples
+                    .iter()
+                    .filter_map(|s| s.result.as_ref().ok().copied())
+                    .collect();
+                Some(ProviderLatency {
+                    provider: provider.clone(),
+                    available: last.result.is_ok(),
+                    last_ms: last.result.as_ref().ok().copied(),
+                    average_ms: (!successes.is_empty())
+                        .then(|| successes.iter().sum::<u64>() / successes.len() as u64),
+                    success_rate: (successes.len() * 100 / samples.len()) as u8,
+                    checked_at: last.at,
+                    last_error: samples
+                        .iter()
+                        .rev()
+                        .find_map(|s| s.result.as_ref().err().cloned()),
+                })
+            })
+            .collect()
+    }
+}
+
+/// `fallbacks` (`provider/model` refs) reordered: available providers by average latency,
+/// then unprobed ones, then unavailable ones. Providers in `unhealthy` (rate limited, out of
+/// quota) count as unavailable however fast they answer.
+pub fn recommend_fallbacks(
+    fallbacks: &[String],
+    stats: &[ProviderLatency],
+    unhealthy: &[String],
+) -> Vec<String> {
+    let rank = |model: &String| {
+        let provider = model.split('/').next().unwrap_or_default();
+        match stats.iter().find(|s| s.provider == provider) {
+            _ if unhealthy.iter().any(|p| p == provider) => (2, 0),
+            Some(s) if s.available => (0, s.average_ms.or(s.last_ms).unwrap_or(0)),
+            Some(_) => (2, 0),
+            None => (1, 0),
+        }
+    };
+    let mut ordered = fallbacks.to_vec();
+    ordered.sort_by_key(rank);
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_window_and_stats() {
+        let mut tracker = Tracker::default();
+        for i in 0..WINDOW as u64 {
+            tracker.record("anthropic", Ok(1000), i);
+        }
+        tracker.record("anthropic", Ok(200), 100);
+        tracker.record("anthropic", Err("HTTP 503".to_string()), 200);
+        tracker.record("openai", Ok(300), 200);
+
+        let stats = tracker.snapshot();
+        let anthropic = &stats[0];
+        assert!(!anthropic.available);
+        assert_eq!(anthropic.last_ms, None);
+        assert_eq!(anthropic.average_ms, Some((1000 * 10 + 200) / 11));
+        assert_eq!(anthropic.success_rate, 91);
+        assert_eq!(anthropic.last_error.as_deref(), Some("HTTP 503"));
+
+        tracker.retain(&["openai".to_string()]);
+        assert_eq!(tracker.snapshot().len(), 1);
+    }
+
+    #[test]
+    fn test_recommend_fallbacks() {
+        let stat = |provider: &str, available: bool, average_ms: u64| ProviderLatency {
+            provider: provider.to_string(),
+            available,
+            last_ms: available.then_some(average_ms),
+            average_ms: Some(average_ms),
+            success_rate: 100,
+            checked_at: 0,
+            last_error: None,
+        };
+        let stats = vec![
+            stat("openai", true, 900),
+            stat("google", true, 300),
+            stat("xai", false, 100),
+            stat("groq", true, 50),
+        ];
+        let fallbacks: Vec<String> = [
+            "xai/grok-4",
+            "openai/gpt-5.4",
+            "mistral/mistral-large",
+            "groq/llama-3.3-70b",
+            "google/gemini-2.5-pro",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(
+            recommend_fallbacks(&fallbacks, &stats, &["groq".to_string()]),
+            vec![
+                "google/gemini-2.5-pro",
+                "openai/gpt-5.4",
+                "mistral/mistral-large",
+                "xai/grok-4",
+                "groq/llama-3.3-70b",
+            ]
+        );
+    }
+}
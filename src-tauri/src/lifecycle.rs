@@ -0,0 +1,357 @@
+//! App lifetime: background task registry and graceful shutdown.
+//!
+//! Long-running threads are started with `spawn` and wait with `sleep`, which returns
+//! `false` as soon as shutdown begins so loops can exit between iterations. Async tasks
+//! can `subscribe` to the same signal. Config writers hold `write_guard` while writing;
+//! `shutdown` waits for them before the process exits, so a half-written openclaw.json
+//! is never left behind. Changes to openclaw.json additionally hold `config_lock` from the
+//! read to the write, so two read-modify-writes cannot interleave and lose one's edit. The
+//! state is also managed by Tauri for commands that report on it.
+//!
+//! Mutating commands (install, configure, gateway start/restart, ...) run one at a time. Async
+//! commands queue with `begin_operation`, which waits for the running one without holding a
+//! runtime worker; sync commands run on the main thread and must not wait, so
+//! `try_begin_operation` fails with "X is running" while the slot is taken. The returned guard
+//! releases the slot on drop. Operations must not nest, since the second would wait for (or
+//! be refused by) the first.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// How long `shutdown` waits for background tasks to finish.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct AppState {
+    stopping: AtomicBool,
+    wake: (Mutex<()>, Condvar),
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    writes: RwLock<()>,
+    config: Mutex<()>,
+    tasks: Mutex<Vec<(String, JoinHandle<()>)>>,
+    operations: Mutex<OperationStatus>,
+    operation_slot: Arc<Semaphore>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct ActiveOperation {
+    pub name: String,
+    pub started_at: u64,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+pub struct OperationStatus {
+    pub active: Option<ActiveOperation>,
+    /// Operations waiting for `active`, oldest first.
+    pub queued: Vec<String>,
+}
+
+/// Holds the operation slot; dropping it lets the next queued operation run.
+pub struct OperationGuard {
+    state: Arc<AppState>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        if let Ok(mut ops) = self.state.operations.lock() {
+            ops.active = None;
+        }
+    }
+}
+
+/// Keeps `name` in the queue while `begin_operation` waits, including when the wait is
+/// cancelled.
+struct QueuedOperation<'a> {
+    state: &'a AppState,
+    name: &'a str,
+}
+
+impl Drop for QueuedOperation<'_> {
+    fn drop(&mut self) {
+        let mut ops = self.state.lock_operations();
+        if let Some(index) = ops.queued.iter().position(|queued| queued == self.name) {
+            ops.queued.remove(index);
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct TaskInfo {
+    pub name: String,
+    pub running: bool,
+}
+
+lazy_static! {
+    static ref STATE: Arc<AppState> = Arc::new(AppState::new());
+}
+
+impl AppState {
+    fn new() -> Self {
+        AppState {
+            stopping: AtomicBool::new(false),
+            wake: (Mutex::new(()), Condvar::new()),
+            shutdown_tx: tokio::sync::broadcast::channel(1).0,
+            writes: RwLock::new(()),
+            config: Mutex::new(()),
+            tasks: Mutex::new(Vec::new()),
+            operations: Mutex::new(OperationStatus::default()),
+            operation_slot: Arc::new(Semaphore::new(1)),
+        }
+    }
+
+    fn lock_operations(&self) -> std::sync::MutexGuard<'_, OperationStatus> {
+        self.operations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn start_operation(
+        self: &Arc<Self>,
+        name: &str,
+        permit: OwnedSemaphorePermit,
+    ) -> OperationGuard {
+        self.lock_operations().active = Some(ActiveOperation {
+            name: name.to_string(),
+            started_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        });
+        OperationGuard {
+            state: self.clone(),
+            _permit: permit,
+        }
+    }
+
+    /// Waits for the running operation, if any, then marks `name` as active.
+    pub async fn begin_operation(self: &Arc<Self>, name: &str) -> OperationGuard {
+        let queued = QueuedOperation { state: self, name };
+        self.lock_operations().queued.push(name.to_string());
+        let permit = self
+            .operation_slot
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the operation slot is never closed");
+        drop(queued);
+        self.start_operation(name, permit)
+    }
+
+    /// Marks `name` as active, or fails naming the operation that holds the slot.
+    pub fn try_begin_operation(self: &Arc<Self>, name: &str) -> Result<OperationGuard, String> {
+        match self.operation_slot.clone().try_acquire_owned() {
+            Ok(permit) => Ok(self.start_operation(name, permit)),
+            Err(_) => {
+                let running = self
+                    .lock_operations()
+                    .active
+                    .as_ref()
+                    .map(|op| op.name.clone())
+                    .unwrap_or_else(|| "Another operation".to_string());
+                Err(crate::messages::text(
+                    "operation.busy",
+                    &[("operation", &running)],
+                ))
+            }
+        }
+    }
+
+    pub fn operation_status(&self) -> OperationStatus {
+        self.operations
+            .lock()
+            .map(|ops| ops.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::Relaxed)
+    }
+
+    /// Sleeps for `duration` or until shutdown begins. Returns whether to keep running.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let (lock, condvar) = &self.wake;
+        if let Ok(guard) = lock.lock() {
+            let _ = condvar.wait_timeout_while(guard, duration, |_| !self.is_stopping());
+        }
+        !self.is_stopping()
+    }
+
+    pub fn spawn<F>(&self, name: &str, task: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let handle = std::thread::spawn(task);
+        if let Ok(mut tasks) = self.tasks.lock() {
+            tasks.retain(|(_, handle)| !handle.is_finished());
+            tasks.push((name.to_string(), handle));
+        }
+    }
+
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        self.tasks
+            .lock()
+            .map(|tasks| {
+                tasks
+                    .iter()
+                    .map(|(name, handle)| TaskInfo {
+                        name: name.clone(),
+                        running: !handle.is_finished(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Signals every task, waits for in-flight config writes, then gives tasks up to
+    /// `timeout` to return. Returns the names of tasks still running afterwards.
+    pub fn shutdown(&self, timeout: Duration) -> Vec<String> {
+        // Set under the wake lock: `sleep` checks the flag holding it, so the notification
+        // cannot fall between its check and its wait.
+        {
+            let _wake = self.wake.0.lock();
+            self.stopping.store(true, Ordering::Relaxed);
+        }
+        let _ = self.shutdown_tx.send(());
+        self.wake.1.notify_all();
+        drop(self.writes.write());
+
+        let deadline = Instant::now() + timeout;
+        let tasks = self
+            .tasks
+            .lock()
+            .map(|mut tasks| std::mem::take(&mut *tasks))
+            .unwrap_or_default();
+        let mut stuck = Vec::new();
+        for (name, handle) in tasks {
+            while !handle.is_finished() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            if handle.is_finished() {
+                let _ = handle.join();
+            } else {
+                stuck.push(name);
+            }
+        }
+        stuck
+    }
+}
+
+pub fn state() -> Arc<AppState> {
+    STATE.clone()
+}
+
+pub fn is_stopping() -> bool {
+    STATE.is_stopping()
+}
+
+pub fn sleep(duration: Duration) -> bool {
+    STATE.sleep(duration)
+}
+
+pub fn spawn<F>(name: &str, task: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    STATE.spawn(name, task)
+}
+
+/// Waits until no other mutating operation runs, then marks `name` as active.
+pub async fn begin_operation(name: &str) -> OperationGuard {
+    STATE.begin_operation(name).await
+}
+
+/// Marks `name` as active, failing with "X is running" instead of waiting; for sync commands.
+pub fn try_begin_operation(name: &str) -> Result<OperationGuard, String> {
+    STATE.try_begin_operation(name)
+}
+
+/// Receiver that fires once shutdown begins, for async tasks.
+pub fn subscribe() -> tokio::sync::broadcast::Receiver<()> {
+    STATE.shutdown_tx.subscribe()
+}
+
+/// Held for the duration of a config write so shutdown waits for it.
+pub fn write_guard() -> Option<RwLockReadGuard<'static, ()>> {
+    STATE.writes.read().ok()
+}
+
+/// Held from reading openclaw.json to writing it back; not reentrant.
+pub fn config_lock() -> MutexGuard<'static, ()> {
+    STATE
+        .config
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub fn shutdown() {
+    let stuck = STATE.shutdown(SHUTDOWN_TIMEOUT);
+    if !stuck.is_empty() {
+        eprintln!(
+            "Background tasks still running at exit: {}",
+            stuck.join(", ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shutdown_stops_sleeping_tasks() {
+        let state = Arc::new(AppState::new());
+        let worker = state.clone();
+        let task = move || {
+            while worker.sleep(Duration::from_secs(60)) {}
+        };
+        state.spawn("worker", task);
+        let started = Instant::now();
+        assert!(state.shutdown(Duration::from_secs(2)).is_empty());
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(state.is_stopping());
+        assert!(!state.sleep(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_operations_run_one_at_a_time() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let state = Arc::new(AppState::new());
+        let first = state.try_begin_operation("configure_agent").unwrap();
+        assert_eq!(
+            state
+                .try_begin_operation("install_openclaw")
+                .err()
+                .as_deref(),
+            Some("configure_agent is running; try again when it finishes.")
+        );
+
+        let waiter = state.clone();
+        let second = runtime.spawn(async move {
+            let _guard = waiter.begin_operation("start_gateway").await;
+            waiter.operation_status().active.map(|op| op.name)
+        });
+        runtime.block_on(async {
+            while state.operation_status().queued.is_empty() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        });
+        let status = state.operation_status();
+        assert_eq!(
+            status.active.map(|op| op.name).as_deref(),
+            Some("configure_agent")
+        );
+        assert_eq!(status.queued, vec!["start_gateway".to_string()]);
+
+        drop(first);
+        let second = runtime.block_on(second).unwrap();
+        assert_eq!(second.as_deref(), Some("start_gateway"));
+        assert_eq!(state.operation_status(), OperationStatus::default());
+        assert!(state.try_begin_operation("install_openclaw").is_ok());
+    }
+}
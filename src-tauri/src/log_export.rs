@@ -0,0 +1,276 @@
+//! Filtered, redacted gateway log exports.
+//!
+//! Gateway log lines are JSON (time in `time` or `_meta.date`, subsystem in `subsystem` or a
+//! JSON-encoded first argument); plain lines are accepted too and inherit the time of the line
+//! before them, so stack traces stay with their error. Times are compared as ISO-8601 UTC
+//! strings. Redaction works word by word: known key prefixes, long mixed-case tokens, bearer
+//! tokens and values written after a secret-looking key are replaced.
+
+const REDACTED: &str = "[REDACTED]";
+const SECRET_PREFIXES: &[&str] = &[
+    "sk-",
+    "sk_",
+    "xoxb-",
+    "xoxp-",
+    "xapp-",
+    "ghp_",
+    "gho_",
+    "github_pat_",
+    "glpat-",
+    "AKIA",
+    "ASIA",
+    "AIza",
+];
+const MIN_PREFIXED_LEN: usize = 16;
+const MIN_OPAQUE_LEN: usize = 30;
+
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct LogFilter {
+    /// Inclusive ISO-8601 UTC bounds, e.g. `2025-10-09T08:00:00.000Z`.
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// Subsystem fragments such as `telegram` or `gateway/ws`; empty keeps every component.
+    #[serde(default)]
+    pub components: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Entry {
+    time: Option<String>,
+    level: Option<String>,
+    component: Option<String>,
+    message: String,
+    /// Not a JSON line; exported as written.
+    raw: bool,
+}
+
+fn text(value: Option<&serde_json::Value>) -> Option<String> {
+    value.and_then(|v| v.as_str()).map(str::to_string)
+}
+
+fn parse_entry(line: &str) -> Entry {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(line.trim()) else {
+        let time = line
+            .split_whitespace()
+            .next()
+            .filter(|word| word.len() >= 20 && word.as_bytes()[4] == b'-' && word.contains('T'))
+            .map(str::to_string);
+        return Entry {
+            time,
+            message: line.to_string(),
+            raw: true,
+            ..Default::default()
+        };
+    };
+    let meta = value.get("_meta");
+    // tslog writes the bindings (e.g. `{"subsystem":"gateway/ws"}`) as the first argument.
+    let bindings = text(value.get("0"))
+        .and_then(|first| serde_json::from_str::<serde_json::Value>(&first).ok())
+        .filter(|b| b.is_object());
+    let component = text(value.get("subsystem"))
+        .or_else(|| text(bindings.as_ref().and_then(|b| b.get("subsystem"))))
+        .or_else(|| text(meta.and_then(|m| m.get("name"))));
+    let message = ["msg", "message"]
+        .iter()
+        .find_map(|key| text(value.get(*key)))
+        .or_else(|| {
+            let args: Vec<String> = (0..10)
+                .skip(usize::from(bindings.is_some()))
+                .map_while(|i| value.get(i.to_string()))
+                .map(|v| {
+                    v.as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| v.to_string())
+                })
+                .collect();
+            (!args.is_empty()).then(|| args.join(" "))
+        })
+        .unwrap_or_else(|| line.trim().to_string());
+    Entry {
+        time: text(value.get("time")).or_else(|| text(meta.and_then(|m| m.get("date")))),
+        level: text(value.get("level")).or_else(|| text(meta.and_then(|m| m.get("logLevelName")))),
+        component,
+        message,
+        raw: false,
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || "\"'`,;:=&{}[]()<>".contains(c)
+}
+
+fn looks_like_secret(word: &str) -> bool {
+    if SECRET_PREFIXES
+        .iter()
+        .any(|prefix| word.starts_with(prefix) && word.len() >= MIN_PREFIXED_LEN)
+    {
+        return true;
+    }
+    word.len() >= MIN_OPAQUE_LEN
+        && word.chars().all(|c| {
+            c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' || c == '/' || c == '+'
+        })
+        && word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().any(|c| c.is_ascii_uppercase())
+        && word.chars().any(|c| c.is_ascii_lowercase())
+}
+
+/// `line` with secrets replaced, and how many were replaced.
+pub fn redact_line(line: &str) -> (String, usize) {
+    let mut out = String::with_capacity(line.len());
+    let mut redactions = 0;
+    let mut previous = "";
+    let mut separator = String::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+        let word_end = rest.find(is_delimiter).unwrap_or(rest.len());
+        if word_end == 0 {
+            let c = rest.chars().next().unwrap();
+            separator.push(c);
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let word = &rest[..word_end];
+        let after_key = crate::is_secret_key(previous) && separator.contains([':', '=']);
+        let after_bearer = previous.eq_ignore_ascii_case("bearer");
+        if after_key || after_bearer || looks_like_secret(word) {
+            out.push_str(REDACTED);
+            redactions += 1;
+        } else {
+            out.push_str(word);
+        }
+        previous = word;
+        separator.clear();
+        rest = &rest[word_end..];
+    }
+    (out, redactions)
+}
+
+fn matches(entry: &Entry, filter: &LogFilter) -> bool {
+    let in_range = entry.time.as_deref().is_some_and(|time| {
+        filter.since.as_deref().is_none_or(|since| time >= since)
+            && filter.until.as_deref().is_none_or(|until| time <= until)
+    }) || (filter.since.is_none() && filter.until.is_none());
+    let component = entry
+        .component
+        .as_deref()
+        .unwrap_or_default()
+        .to_lowercase();
+    in_range
+        && (filter.components.is_empty()
+            || filter
+                .components
+                .iter()
+                .any(|c| component.contains(&c.trim().to_lowercase())))
+}
+
+/// The matching lines of `log` as plain text (`text`) or normalised JSON lines (`jsonl`),
+/// redacted. Returns the export, the number of lines and the number of redactions.
+pub fn export(
+    log: &str,
+    filter: &LogFilter,
+    format: &str,
+) -> Result<(String, usize, usize), String> {
+    if format != "text" && format != "jsonl" {
+        return Err(format!(
+            "Unknown log export format '{}'; use text or jsonl",
+            format
+        ));
+    }
+    let mut out = Vec::new();
+    let mut redactions = 0;
+    let mut previous: Option<Entry> = None;
+    for line in log.lines().filter(|l| !l.trim().is_empty()) {
+        let mut entry = parse_entry(line);
+        if entry.time.is_none() {
+            if let Some(previous) = &previous {
+                entry.time = previous.time.clone();
+                entry.component = entry.component.or_else(|| previous.component.clone());
+            }
+        }
+        if matches(&entry, filter) {
+            let (message, count) = redact_line(&entry.message);
+            redactions += count;
+            out.push(if format == "jsonl" {
+                serde_json::json!({
+                    "time": entry.time,
+                    "level": entry.level,
+                    "component": entry.component,
+                    "message": message,
+                })
+                .to_string()
+            } else if entry.raw {
+                message
+            } else {
+                let prefix: Vec<String> = [
+                    entry.time.clone(),
+                    entry.level.clone(),
+                    entry.component.as_ref().map(|c| format!("[{}]", c)),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                if prefix.is_empty() {
+                    message
+                } else {
+                    format!("{} {}", prefix.join(" "), message)
+                }
+            });
+        }
+        previous = Some(entry);
+    }
+    let lines = out.len();
+    Ok((
+        out.join("\n") + if lines > 0 { "\n" } else { "" },
+        lines,
+        redactions,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_line() {
+        let (line, count) = redact_line(
+            r#"{"apiKey":"abc123","Authorization":"Bearer eyJhbGciOi","note":"sk-ant-api03-AAAAAAAAAAAA bot 123456789:AAHk2jZ9xQvB7mN4pL8rT1sW6yU3eC5dF0g"}"#,
+        );
+        assert_eq!(count, 4);
+        assert!(!line.contains("abc123"));
+        assert!(!line.contains("eyJhbGciOi"));
+        assert!(!line.contains("sk-ant"));
+        assert!(!line.contains("AAHk2j"));
+        assert!(line.contains("123456789"));
+        assert_eq!(redact_line("refreshing token for anthropic").1, 0);
+    }
+
+    #[test]
+    fn test_export_filters_by_time_and_component() {
+        let log = [
+            r#"{"0":"{\"subsystem\":\"gateway/channels/telegram\"}","1":"poll failed token=123:abc","_meta":{"date":"2025-10-09T08:00:00.000Z","logLevelName":"ERROR"}}"#,
+            "    at poll (telegram.js:10)",
+            r#"{"0":"{\"subsystem\":\"gateway/ws\"}","1":"client connected","_meta":{"date":"2025-10-09T08:00:01.000Z","logLevelName":"INFO"}}"#,
+            r#"{"0":"{\"subsystem\":\"gateway/channels/telegram\"}","1":"recovered","_meta":{"date":"2025-10-09T09:30:00.000Z","logLevelName":"INFO"}}"#,
+        ]
+        .join("\n");
+        let filter = LogFilter {
+            since: Some("2025-10-09T07:59:00.000Z".to_string()),
+            until: Some("2025-10-09T09:00:00.000Z".to_string()),
+            components: vec!["Telegram".to_string()],
+        };
+        let (text, lines, redactions) = export(&log, &filter, "text").unwrap();
+        assert_eq!(lines, 2);
+        assert_eq!(redactions, 1);
+        assert!(text.starts_with(
+            "2025-10-09T08:00:00.000Z ERROR [gateway/channels/telegram] poll failed token=[REDACTED]"
+        ));
+        assert!(text.contains("at poll (telegram.js:10)"));
+
+        let (jsonl, lines, _) = export(&log, &LogFilter::default(), "jsonl").unwrap();
+        assert_eq!(lines, 4);
+        assert!(jsonl.lines().all(|l| l.starts_with('{')));
+        assert!(export(&log, &filter, "pdf").is_err());
+    }
+}
@@ -0,0 +1,193 @@
+//! Recognises known failure patterns in gateway log lines.
+//!
+//! `Detector::observe` classifies one line at a time: provider auth failures, rate limits,
+//! exhausted credit, and crash loops (the gateway starting `CRASH_LOOP_STARTS` times within
+//! `CRASH_LOOP_WINDOW_SECS`). Each kind is reported at most once per `COOLDOWN_SECS`; lines
+//! matched during the cooldown are counted into the next report instead.
+
+use std::collections::{HashMap, VecDeque};
+
+pub const COOLDOWN_SECS: u64 = 10 * 60;
+pub const CRASH_LOOP_STARTS: usize = 3;
+pub const CRASH_LOOP_WINDOW_SECS: u64 = 5 * 60;
+const MAX_LINE_CHARS: usize = 300;
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct DetectedIssue {
+    /// `auth_failure`, `rate_limit`, `out_of_credit` or `crash_loop`.
+    pub kind: String,
+    /// `warning` or `error`.
+    pub severity: String,
+    pub title: String,
+    pub remediation: String,
+    /// The log line that triggered the report, truncated.
+    pub line: String,
+    /// Matching lines since the previous report of this kind, including this one.
+    pub occurrences: u32,
+    pub detected_at: u64,
+}
+
+#[derive(Default)]
+pub struct Detector {
+    starts: VecDeque<u64>,
+    last_reported: HashMap<&'static str, u64>,
+    suppressed: HashMap<&'static str, u32>,
+}
+
+const OUT_OF_CREDIT: &[&str] = &[
+    "insufficient_quota",
+    "exceeded your current quota",
+    "credit balance is too low",
+    "insufficient credits",
+    "payment required",
+    "billing_hard_limit_reached",
+    "billing_not_active",
+];
+
+const RATE_LIMIT: &[&str] = &[
+    "rate limit",
+    "rate_limit",
+    "ratelimit",
+    "too many requests",
+    "resource_exhausted",
+];
+
+const AUTH_FAILURE: &[&str] = &[
+    "invalid api key",
+    "invalid_api_key",
+    "incorrect api key",
+    "invalid x-api-key",
+    "authentication_error",
+    "unauthorized",
+    "api key not valid",
+    "permission_denied",
+];
+
+const GATEWAY_START: &[&str] = &["gateway listening", "gateway started", "starting gateway"];
+
+/// Whether `code` appears as a standalone number, so "401" does not match "14012".
+fn has_status_code(lower: &str, code: &str) -> bool {
+    lower.match_indices(code).any(|(i, _)| {
+        let before = lower[..i].chars().last();
+        let after = lower[i + code.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_digit()) && !after.is_some_and(|c| c.is_ascii_digit())
+    })
+}
+
+fn matches_any(lower: &str, needles: &[&str]) -> bool {
+    needles.iter().any(|needle| lower.contains(needle))
+}
+
+/// Kind of problem a single line shows; crash loops need `Detector` state.
+pub fn classify_line(line: &str) -> Option<&'static str> {
+    let lower = line.to_lowercase();
+    // Quota errors often arrive as HTTP 429, so they are checked before rate limits.
+    if matches_any(&lower, OUT_OF_CREDIT) || has_status_code(&lower, "402") {
+        Some("out_of_credit")
+    } else if matches_any(&lower, RATE_LIMIT) || has_status_code(&lower, "429") {
+        Some("rate_limit")
+    } else if matches_any(&lower, AUTH_FAILURE) || has_status_code(&lower, "401") {
+        Some("auth_failure")
+    } else {
+        None
+    }
+}
+
+/// Severity of `kind`; its title and remediation are `issue.<kind>.*` in the message catalog.
+fn severity(kind: &str) -> &'static str {
+    match kind {
+        "rate_limit" => "warning",
+        _ => "error",
+    }
+}
+
+impl Detector {
+    pub fn observe(&mut self, line: &str, now: u64) -> Option<DetectedIssue> {
+        let kind = match classify_line(line) {
+            Some(kind) => kind,
+            None if matches_any(&line.to_lowercase(), GATEWAY_START) => {
+                self.starts.push_back(now);
+                while self
+                    .starts
+                    .front()
+                    .is_some_and(|t| now.saturating_sub(*t) > CRASH_LOOP_WINDOW_SECS)
+                {
+                    self.starts.pop_front();
+                }
+                if self.starts.len() < CRASH_LOOP_STARTS {
+                    return None;
+                }
+                self.starts.clear();
+                "crash_loop"
+            }
+            None => return None,
+        };
+
+        let suppressed = self.suppressed.entry(kind).or_insert(0);
+        *suppressed += 1;
+        if self
+            .last_reported
+            .get(kind)
+            .is_some_and(|t| now.saturating_sub(*t) < COOLDOWN_SECS)
+        {
+            return None;
+        }
+        let occurrences = std::mem::take(suppressed);
+        self.last_reported.insert(kind, now);
+        let message = |part: &str| crate::messages::text(&format!("issue.{}.{}", kind, part), &[]);
+        Some(DetectedIssue {
+            kind: kind.to_string(),
+            severity: severity(kind).to_string(),
+            title: message("title"),
+            remediation: message("remediation"),
+            line: line.trim().chars().take(MAX_LINE_CHARS).collect(),
+            occurrences,
+            detected_at: now,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_line() {
+        assert_eq!(
+            classify_line(r#"{"msg":"anthropic error 401: invalid x-api-key"}"#),
+            Some("auth_failure")
+        );
+        assert_eq!(
+            classify_line("openai 429: You exceeded your current quota"),
+            Some("out_of_credit")
+        );
+        assert_eq!(
+            classify_line("HTTP 429 Too Many Requests"),
+            Some("rate_limit")
+        );
+        assert_eq!(classify_line("processed 14012 tokens"), None);
+        assert_eq!(classify_line("gateway listening on 18789"), None);
+    }
+
+    #[test]
+    fn test_detector_cooldown_and_crash_loop() {
+        let mut detector = Detector::default();
+        let first = detector.observe("401 Unauthorized", 100).unwrap();
+        assert_eq!(first.kind, "auth_failure");
+        assert_eq!(first.occurrences, 1);
+        assert!(detector.observe("401 Unauthorized", 200).is_none());
+        let again = detector
+            .observe("401 Unauthorized", 100 + COOLDOWN_SECS)
+            .unwrap();
+        assert_eq!(again.occurrences, 2);
+
+        let start = "gateway listening on 18789";
+        assert!(detector.observe(start, 1000).is_none());
+        assert!(detector.observe(start, 1060).is_none());
+        let crash = detector.observe(start, 1120).unwrap();
+        assert_eq!(crash.kind, "crash_loop");
+        assert!(detector
+            .observe(start, 1000 + 2 * CRASH_LOOP_WINDOW_SECS)
+            .is_none());
+    }
+}
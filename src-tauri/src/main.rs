@@ -3,7 +3,8 @@ use aes_gcm::{Aes256Gcm, Nonce};
 use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
 use base64::Engine as _;
 use sha2::{Digest, Sha256};
-use tauri::command;
+use clawnetes_macros::command;
+use tauri::Manager;
 // Updated: Force rebuild trigger
 use rand::Rng;
 use ssh2::Session;
@@ -13,6 +14,7 @@ use std::net::{TcpListener, TcpStream};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -22,8 +24,86 @@ use std::os::unix::fs::PermissionsExt;
 #[macro_use]
 extern crate lazy_static;
 
+mod activity;
+mod backup;
+mod bedrock;
+mod browser;
+mod cli;
+mod config_sections;
+mod crash;
+mod digest;
+mod elevation;
+mod email;
+mod event_bridge;
+mod gateway_client;
+mod gateways;
+mod headless;
+mod keychain;
+mod latency;
+mod lifecycle;
+mod log_export;
+mod log_monitor;
+mod mcp;
+mod messages;
+mod metrics;
+mod migrations;
+mod observer;
+mod paths;
+mod portable;
+mod process;
+mod profiles;
+mod progress;
+mod provider_health;
+mod qr;
+mod retry;
+mod security_audit;
+mod service_status;
+mod settings;
+mod telemetry;
+mod templates;
+mod terminal;
+
 lazy_static! {
     static ref TUNNEL_RUNNING: AtomicBool = AtomicBool::new(false);
+    static ref DASHBOARD_PROXY: Mutex<Option<DashboardProxyHandle>> = Mutex::new(None);
+    static ref HEALTH_ENDPOINT: Mutex<Option<HealthEndpointHandle>> = Mutex::new(None);
+    static ref GATEWAY_SUPERVISION: Mutex<GatewaySupervisionStatus> =
+        Mutex::new(GatewaySupervisionStatus::default());
+    static ref SUPERVISOR_THREAD_RUNNING: AtomicBool = AtomicBool::new(false);
+    static ref BUDGET_STATUS: Mutex<BudgetStatus> = Mutex::new(BudgetStatus::default());
+    static ref BUDGET_MONITOR_RUNNING: AtomicBool = AtomicBool::new(false);
+    static ref BUDGET_PAUSED: AtomicBool = AtomicBool::new(false);
+    /// `month:agent` keys of subagent caps already alerted on.
+    static ref SUBAGENT_CAP_ALERTS: Mutex<std::collections::HashSet<String>> =
+        Mutex::new(std::collections::HashSet::new());
+    static ref SETUP_RUN: Mutex<SetupRunState> = Mutex::new(SetupRunState::default());
+    static ref SETUP_ABORT_REQUESTED: AtomicBool = AtomicBool::new(false);
+    static ref LOG_ROTATION_RUNNING: AtomicBool = AtomicBool::new(false);
+    static ref LOG_MONITOR_RUNNING: AtomicBool = AtomicBool::new(false);
+    static ref EVENT_BRIDGE_RUNNING: AtomicBool = AtomicBool::new(false);
+    static ref DETECTED_ISSUES: Mutex<std::collections::VecDeque<log_monitor::DetectedIssue>> =
+        Mutex::new(std::collections::VecDeque::new());
+    static ref PROVIDER_HEALTH: Mutex<provider_health::Tracker> =
+        Mutex::new(provider_health::Tracker::default());
+    static ref LATENCY_PROBER_RUNNING: AtomicBool = AtomicBool::new(false);
+    static ref CLOUD_BACKUP_RUNNING: AtomicBool = AtomicBool::new(false);
+    static ref BACKUP_SCHEDULER_RUNNING: AtomicBool = AtomicBool::new(false);
+    static ref TELEMETRY: Mutex<TelemetryState> = Mutex::new(TelemetryState::default());
+    static ref TELEMETRY_UPLOADER_RUNNING: AtomicBool = AtomicBool::new(false);
+    /// App data directory the panic hook writes `crash.log` to.
+    static ref CRASH_LOG_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+    /// Read-only observer mode; see `observer`.
+    static ref OBSERVER_MODE: AtomicBool = AtomicBool::new(false);
+    static ref TERMINAL_SESSIONS: Mutex<std::collections::HashMap<String, TerminalSession>> =
+        Mutex::new(std::collections::HashMap::new());
+    static ref PROVIDER_LATENCY: Mutex<latency::Tracker> =
+        Mutex::new(latency::Tracker::default());
+    static ref CONFIG_WATCH_ACTIVE: AtomicBool = AtomicBool::new(false);
+    static ref CONFIG_WATCH_RUNNING: AtomicBool = AtomicBool::new(false);
+    /// Hash of openclaw.json as last read or written by the app.
+    static ref KNOWN_CONFIG_HASH: Mutex<Option<String>> = Mutex::new(None);
+    /// Held by the background workspace push, so pushes run one at a time.
+    static ref WORKSPACE_PUSH: Mutex<()> = Mutex::new(());
 }
 
 const ADVANCED_LICENSE_PRODUCT_ID: &str = "gsFyrV978DfW2ZYp5pzetQ==";
@@ -389,6 +469,9 @@ struct CurrentConfig {
     is_paired: bool,
     cron_jobs: Option<Vec<CronJobConfig>>,
     local_base_url: Option<String>,
+    base_url: Option<String>,
+    azure: Option<AzureOpenAiConfig>,
+    bedrock: Option<bedrock::BedrockConfig>,
     thinking_level: Option<String>,
     whatsapp_enabled: Option<bool>,
     whatsapp_dm_policy: Option<String>,
@@ -404,7 +487,15 @@ struct ProviderAuthData {
     oauth_provider_id: Option<String>,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+struct AzureOpenAiConfig {
+    /// `https://<resource>.openai.azure.com`
+    endpoint: String,
+    deployment: String,
+    api_version: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone)]
 struct AgentConfig {
     provider: String,
     api_key: String,
@@ -451,6 +542,12 @@ struct AgentConfig {
     cron_jobs: Option<Vec<CronJobConfig>>,
     // Local model support
     local_base_url: Option<String>,
+    // Custom OpenAI-compatible endpoint
+    base_url: Option<String>,
+    // Azure OpenAI resource; `model` is `azure/<deployment>`
+    azure: Option<AzureOpenAiConfig>,
+    // AWS Bedrock region and credentials
+    bedrock: Option<bedrock::BedrockConfig>,
     // OpenClaw latest features
     thinking_level: Option<String>,
     // WhatsApp channel
@@ -464,6 +561,9 @@ struct PrereqCheck {
     node_installed: bool,
     docker_running: bool,
     openclaw_installed: bool,
+    /// Install backends found on PATH, from `INSTALL_BACKENDS`.
+    package_managers: Vec<String>,
+    resources: ResourceCheck,
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -516,7 +616,7 @@ fn default_provider_auth(
         "provider".to_string(),
         serde_json::Value::String(provider.to_string()),
     );
-    if provider == "lmstudio" || provider == "local" {
+    if provider == "lmstudio" || provider == "local" || provider == CUSTOM_PROVIDER {
         profile.insert(
             "api".to_string(),
             serde_json::Value::String("openai".to_string()),
@@ -646,17 +746,26 @@ fn get_provider_auth_map(
     config: &AgentConfig,
 ) -> std::collections::HashMap<String, ProviderAuthData> {
     let mut provider_auths = config.provider_auths.clone().unwrap_or_default();
-    if !provider_auths.contains_key(&config.provider) {
+    // Bedrock authenticates through the AWS profile, so it has no auth profile of its own.
+    if !provider_auths.contains_key(&config.provider) && config.provider != bedrock::PROVIDER {
         provider_auths.insert(
             config.provider.clone(),
             default_provider_auth(
                 &config.provider,
                 &config.api_key,
                 config.auth_method.as_deref().unwrap_or("token"),
-                config.local_base_url.as_ref(),
+                provider_base_url(config),
             ),
         );
     }
+    if let (Some(azure), Some(profile)) = (
+        config.azure.as_ref(),
+        provider_auths
+            .get_mut(AZURE_PROVIDER)
+            .and_then(|auth| auth.profile.as_mut()),
+    ) {
+        apply_azure_profile_fields(profile, azure);
+    }
     provider_auths
 }
 
@@ -748,32 +857,13 @@ fn enable_openclaw_plugin(plugin_id: &str) -> Result<(), String> {
 }
 
 fn provider_id_is_available(oauth_provider_id: &str) -> Result<bool, String> {
-    let output = shell_command("openclaw plugins list --json")?;
-    let parsed: serde_json::Value =
-        serde_json::from_str(&output).map_err(|e| format!("Failed to parse plugin list: {}", e))?;
-
-    Ok(parsed
-        .get("plugins")
-        .and_then(|plugins| plugins.as_array())
-        .map(|plugins| {
-            plugins.iter().any(|plugin| {
-                plugin
-                    .get("status")
-                    .and_then(|status| status.as_str())
-                    .map(|status| status == "loaded")
-                    .unwrap_or(false)
-                    && plugin
-                        .get("providerIds")
-                        .and_then(|provider_ids| provider_ids.as_array())
-                        .map(|provider_ids| {
-                            provider_ids
-                                .iter()
-                                .any(|provider_id| provider_id.as_str() == Some(oauth_provider_id))
-                        })
-                        .unwrap_or(false)
-            })
-        })
-        .unwrap_or(false))
+    Ok(cli::plugins_list()?.plugins.iter().any(|plugin| {
+        plugin.status.as_deref() == Some("loaded")
+            && plugin
+                .provider_ids
+                .iter()
+                .any(|provider_id| provider_id == oauth_provider_id)
+    }))
 }
 
 fn resolve_profile_name(provider: &str, provider_auth: &ProviderAuthData) -> String {
@@ -825,7 +915,7 @@ fn build_auth_profiles_doc(
         }
     }
 
-    if !last_good.contains_key(primary_provider) {
+    if !last_good.contains_key(primary_provider) && primary_provider != bedrock::PROVIDER {
         last_good.insert(
             primary_provider.to_string(),
             serde_json::Value::String(format!("{}:default", primary_provider)),
@@ -1030,6 +1120,15 @@ fn find_oauth_port_listeners(port: u16) -> Result<Vec<PortListenerInfo>, String>
     shell_command(&cmd).map(|output| parse_lsof_listener_info(&output))
 }
 
+/// PID of the openclaw process listening on the local gateway port.
+fn gateway_listener_pid() -> Option<i32> {
+    find_oauth_port_listeners(read_local_gateway_port())
+        .ok()?
+        .into_iter()
+        .find(is_openclaw_listener)
+        .map(|listener| listener.pid)
+}
+
 fn terminate_listener_process(listener: &PortListenerInfo, port: u16) -> Result<(), String> {
     let cmd = format!("kill {}", listener.pid);
     shell_command(&cmd).map(|_| ()).map_err(|err| {
@@ -1369,8 +1468,7 @@ fn launch_provider_auth_terminal(command: &str) -> Result<(), String> {
 
 #[cfg(target_os = "windows")]
 fn launch_provider_auth_terminal(command: &str) -> Result<(), String> {
-    let home = wsl_home_dir()?.trim().to_string();
-    let marker_dir = format!("{}/.openclaw/tmp", home);
+    let marker_dir = paths::local_path("tmp")?;
     wsl_mkdir_p(&marker_dir)?;
     let marker_path = format!(
         "{}/openclaw-auth-{}.exit",
@@ -1402,8 +1500,7 @@ fn launch_provider_auth_terminal(command: &str) -> Result<(), String> {
 
 #[cfg(target_os = "windows")]
 fn read_provider_auth_profiles() -> Result<serde_json::Value, String> {
-    let home = wsl_home_dir()?.trim().to_string();
-    let auth_profiles_path = format!("{}/.openclaw/agents/main/agent/auth-profiles.json", home);
+    let auth_profiles_path = paths::local_path("agents/main/agent/auth-profiles.json")?;
     let auth_profiles_str = wsl_read_file(&auth_profiles_path)
         .map_err(|e| format!("Failed to read auth profiles: {}", e))?;
     serde_json::from_str(&auth_profiles_str)
@@ -1412,8 +1509,7 @@ fn read_provider_auth_profiles() -> Result<serde_json::Value, String> {
 
 #[cfg(not(target_os = "windows"))]
 fn read_provider_auth_profiles() -> Result<serde_json::Value, String> {
-    let home = std::env::var("HOME").map_err(|e| e.to_string())?;
-    let auth_profiles_path = format!("{}/.openclaw/agents/main/agent/auth-profiles.json", home);
+    let auth_profiles_path = paths::local_path("agents/main/agent/auth-profiles.json")?;
     let auth_profiles_str = fs::read_to_string(&auth_profiles_path)
         .map_err(|e| format!("Failed to read auth profiles: {}", e))?;
     serde_json::from_str(&auth_profiles_str)
@@ -1477,7 +1573,7 @@ fn authenticate_with_key(sess: &Session, user: &str, key_path: &Path) -> Result<
     }
 
     // If all failed, return a informative error
-    Err("Key authentication failed. libssh2 reported an error. Please ensure the key is a valid OpenSSH format, matches the remote user, and is not passphrase-protected.".to_string())
+    Err(messages::text("ssh.key_auth_failed", &[]))
 }
 
 fn connect_ssh(remote: &RemoteInfo) -> Result<Session, String> {
@@ -1580,7 +1676,7 @@ async fn test_ssh_connection(remote: RemoteInfo) -> Result<String, String> {
 fn read_workspace_files() -> Result<serde_json::Value, String> {
     #[cfg(target_os = "windows")]
     {
-        let workspace = wsl_home_dir()?.trim().to_string() + "/.openclaw/workspace";
+        let workspace = paths::local_path("workspace")?;
         let identity = wsl_read_file(&format!("{}/IDENTITY.md", workspace)).unwrap_or_default();
         let user = wsl_read_file(&format!("{}/USER.md", workspace)).unwrap_or_default();
         let soul = wsl_read_file(&format!("{}/SOUL.md", workspace)).unwrap_or_default();
@@ -1594,8 +1690,7 @@ fn read_workspace_files() -> Result<serde_json::Value, String> {
 
     #[cfg(not(target_os = "windows"))]
     {
-        let home = dirs::home_dir().ok_or("Could not find home directory")?;
-        let workspace = home.join(".openclaw").join("workspace");
+        let workspace = PathBuf::from(paths::local_path("workspace")?);
 
         let identity = fs::read_to_string(workspace.join("IDENTITY.md")).unwrap_or_default();
         let user = fs::read_to_string(workspace.join("USER.md")).unwrap_or_default();
@@ -1618,11 +1713,11 @@ fn save_workspace_files(
 ) -> Result<String, String> {
     #[cfg(target_os = "windows")]
     {
-        let home = wsl_home_dir()?.trim().to_string();
-        let workspace = if let Some(id) = agent_id {
-            format!("{}/.openclaw/agents/{}/workspace", home, id)
+        let root = paths::local_root()?;
+        let workspace = if let Some(id) = &agent_id {
+            format!("{}/agents/{}/workspace", root, id)
         } else {
-            format!("{}/.openclaw/workspace", home)
+            format!("{}/workspace", root)
         };
 
         wsl_mkdir_p(&workspace)?;
@@ -1630,23 +1725,21 @@ fn save_workspace_files(
         wsl_write_file(&format!("{}/IDENTITY.md", workspace), &identity)?;
         wsl_write_file(&format!("{}/USER.md", workspace), &user)?;
         wsl_write_file(&format!("{}/SOUL.md", workspace), &soul)?;
+        commit_workspace_changes(agent_id.as_deref(), "Update identity, user and soul files");
 
         Ok("Workspace files saved successfully".to_string())
     }
 
     #[cfg(not(target_os = "windows"))]
     {
-        let home = dirs::home_dir().ok_or("Could not find home directory")?;
+        let root = PathBuf::from(paths::local_root()?);
 
-        let workspace = if let Some(id) = agent_id {
+        let workspace = if let Some(id) = &agent_id {
             // Save to agent-specific workspace
-            home.join(".openclaw")
-                .join("agents")
-                .join(id)
-                .join("workspace")
+            root.join("agents").join(id).join("workspace")
         } else {
             // Save to global workspace
-            home.join(".openclaw").join("workspace")
+            root.join("workspace")
         };
 
         fs::create_dir_all(&workspace).map_err(|e| e.to_string())?;
@@ -1654,6 +1747,7 @@ fn save_workspace_files(
         fs::write(workspace.join("IDENTITY.md"), identity).map_err(|e| e.to_string())?;
         fs::write(workspace.join("USER.md"), user).map_err(|e| e.to_string())?;
         fs::write(workspace.join("SOUL.md"), soul).map_err(|e| e.to_string())?;
+        commit_workspace_changes(agent_id.as_deref(), "Update identity, user and soul files");
 
         Ok("Workspace files saved successfully".to_string())
     }
@@ -1663,26 +1757,25 @@ fn save_workspace_files(
 fn create_custom_skill(name: String, content: String) -> Result<String, String> {
     #[cfg(target_os = "windows")]
     {
-        let home = wsl_home_dir()?.trim().to_string();
-        let skill_dir = format!("{}/.openclaw/workspace/skills/{}", home, name);
+        let skill_dir = paths::local_path(&format!("workspace/skills/{}", name))?;
 
         wsl_mkdir_p(&skill_dir)?;
         wsl_write_file(&format!("{}/SKILL.md", skill_dir), &content)?;
+        commit_workspace_changes(None, &format!("Add skill {}", name));
 
         Ok(format!("Custom skill '{}' created successfully", name))
     }
 
     #[cfg(not(target_os = "windows"))]
     {
-        let home = dirs::home_dir().ok_or("Could not find home directory")?;
-        let skill_dir = home
-            .join(".openclaw")
+        let skill_dir = PathBuf::from(paths::local_root()?)
             .join("workspace")
             .join("skills")
             .join(&name);
 
         fs::create_dir_all(&skill_dir).map_err(|e| e.to_string())?;
         fs::write(skill_dir.join("SKILL.md"), content).map_err(|e| e.to_string())?;
+        commit_workspace_changes(None, &format!("Add skill {}", name));
 
         Ok(format!("Custom skill '{}' created successfully", name))
     }
@@ -1922,7 +2015,7 @@ async fn setup_remote_openclaw(remote: RemoteInfo, config: AgentConfig) -> Resul
                 &config.provider,
                 &config.api_key,
                 config.auth_method.as_deref().unwrap_or("token"),
-                config.local_base_url.as_ref(),
+                provider_base_url(&config),
             )
         });
     let effective_primary_model = apply_model_provider_auth(&config.model, &provider_auths);
@@ -1944,11 +2037,16 @@ async fn setup_remote_openclaw(remote: RemoteInfo, config: AgentConfig) -> Resul
     let gateway_port = config.gateway_port.unwrap_or(18789);
     let gateway_bind = config
         .gateway_bind
+        .clone()
         .unwrap_or_else(|| "loopback".to_string());
     let gateway_auth_mode = config
         .gateway_auth_mode
+        .clone()
         .unwrap_or_else(|| "token".to_string());
-    let tailscale_mode = config.tailscale_mode.unwrap_or_else(|| "off".to_string());
+    let tailscale_mode = config
+        .tailscale_mode
+        .clone()
+        .unwrap_or_else(|| "off".to_string());
 
     // Build models config including fallback models
     let mut defaults_obj = serde_json::json!({
@@ -2102,7 +2200,7 @@ async fn setup_remote_openclaw(remote: RemoteInfo, config: AgentConfig) -> Resul
     );
 
     let mut config_val = serde_json::json!({
-        "messages": { "ackReactionScope": "group-mentions" },
+        "messages": { "ackReactionScope": DEFAULT_ACK_REACTION_SCOPE },
         "agents": {
             "defaults": defaults_obj,
             "list": agents_list
@@ -2264,62 +2362,24 @@ async fn setup_remote_openclaw(remote: RemoteInfo, config: AgentConfig) -> Resul
         }
     }
 
-    // Add models.providers section for LM Studio so openclaw can resolve lmstudio/ models
-    if config.provider == "lmstudio" {
-        let base_url = config
-            .local_base_url
-            .as_deref()
-            .unwrap_or("http://localhost:1234");
-        let base_url_v1 = if base_url.ends_with("/v1") {
-            base_url.to_string()
-        } else {
-            format!("{}/v1", base_url.trim_end_matches('/'))
-        };
-        let model_id = if config.model.starts_with("lmstudio/") {
-            config.model.strip_prefix("lmstudio/").unwrap().to_string()
-        } else {
-            config.model.clone()
-        };
-        let mut model_ids = vec![model_id];
-        if let Some(fb) = &config.fallback_models {
-            for fb_model in fb {
-                if let Some(stripped) = fb_model.strip_prefix("lmstudio/") {
-                    if !model_ids.contains(&stripped.to_string()) {
-                        model_ids.push(stripped.to_string());
-                    }
-                }
-            }
-        }
-        let lmstudio_models: Vec<serde_json::Value> = model_ids
-            .iter()
-            .map(|id| {
-                serde_json::json!({
-                    "id": id,
-                    "name": id,
-                    "reasoning": false,
-                    "input": ["text"],
-                    "cost": { "input": 0, "output": 0, "cacheRead": 0, "cacheWrite": 0 },
-                    "contextWindow": 131072,
-                    "maxTokens": 8192
-                })
-            })
-            .collect();
-        if let Some(obj) = config_val.as_object_mut() {
-            obj.insert(
-                "models".to_string(),
-                serde_json::json!({
-                    "mode": "merge",
-                    "providers": {
-                        "lmstudio": {
-                            "baseUrl": base_url_v1,
-                            "apiKey": "lmstudio",
-                            "api": "openai-completions",
-                            "models": lmstudio_models
-                        }
-                    }
-                }),
-            );
-        }
+    // Register lmstudio/, custom/, azure/ and Bedrock models so openclaw can resolve them
+    apply_provider_models(&config, &mut config_val)?;
+    if let Some(credentials) = config
+        .bedrock
+        .as_ref()
+        .filter(|_| config.provider == bedrock::PROVIDER)
+        .and_then(|b| b.static_credentials())
+    {
+        let existing = execute_ssh(&sess, "cat ~/.aws/credentials 2>/dev/null || true")?;
+        let updated =
+            bedrock::upsert_profile(&existing, bedrock::CREDENTIALS_PROFILE, &credentials);
+        execute_ssh(
+            &sess,
+            &format!(
+                "mkdir -p ~/.aws && umask 077 && printf '%s' '{}' > ~/.aws/credentials",
+                updated.replace("'", "'\\''")
+            ),
+        )?;
     }
 
     let config_json_final = serde_json::to_string_pretty(&config_val).map_err(|e| e.to_string())?;
@@ -2657,7 +2717,7 @@ fn start_ssh_tunnel(remote: RemoteInfo) -> Result<String, String> {
 
         let _ = listener.set_nonblocking(true);
 
-        while TUNNEL_RUNNING.load(Ordering::Relaxed) {
+        while TUNNEL_RUNNING.load(Ordering::Relaxed) && !lifecycle::is_stopping() {
             match listener.accept() {
                 Ok((mut stream, _)) => {
                     let remote_clone = remote_info.clone();
@@ -2760,13 +2820,23 @@ fn stop_ssh_tunnel() -> Result<(), String> {
 #[command]
 async fn check_remote_prerequisites(remote: RemoteInfo) -> Result<PrereqCheck, String> {
     let sess = connect_ssh(&remote)?;
+    let is_macos = execute_ssh(&sess, "uname -s")
+        .map(|os| os.trim() == "Darwin")
+        .unwrap_or(false);
     let node = execute_ssh(&sess, "node -v").is_ok();
     let openclaw = execute_ssh(&sess, "openclaw --version").is_ok();
+    let package_managers = INSTALL_BACKENDS
+        .iter()
+        .filter(|pm| execute_ssh(&sess, &format!("{} --version", pm)).is_ok())
+        .map(|pm| pm.to_string())
+        .collect();
 
     Ok(PrereqCheck {
         node_installed: node,
         docker_running: true, // Not needed for OpenClaw native
         openclaw_installed: openclaw,
+        package_managers,
+        resources: collect_resources(&|cmd| execute_ssh(&sess, cmd), is_macos),
     })
 }
 
@@ -2902,15 +2972,29 @@ fn close_app(window: tauri::Window) {
     let _ = window.close();
 }
 
+/// A positional CLI argument that cannot be read as an option or split into several.
+fn is_cli_operand(arg: &str) -> bool {
+    !arg.is_empty() && !arg.starts_with('-') && !arg.contains(char::is_whitespace)
+}
+
 #[command]
 fn install_skill(name: String) -> Result<String, String> {
-    shell_command(&format!("npx clawhub install {}", name))
+    if !is_cli_operand(&name) {
+        return Err(format!("Invalid skill name: {}", name));
+    }
+    process::run("npx", &["clawhub", "install", &name])
 }
 
 #[command]
 async fn install_remote_skill(remote: RemoteInfo, name: String) -> Result<String, String> {
+    if !is_cli_operand(&name) {
+        return Err(format!("Invalid skill name: {}", name));
+    }
     let sess = connect_ssh(&remote)?;
-    execute_ssh(&sess, &format!("npx clawhub install {}", name))
+    execute_ssh(
+        &sess,
+        &format!("npx clawhub install {}", shell_single_quote(&name)),
+    )
 }
 
 #[command]
@@ -2923,6 +3007,10 @@ fn get_openclaw_version() -> String {
 
 #[command]
 fn uninstall_openclaw() -> Result<String, String> {
+    // Stop supervising first so the gateway is not restarted mid-uninstall.
+    if let Ok(mut supervision) = GATEWAY_SUPERVISION.lock() {
+        supervision.enabled = false;
+    }
     let _ = shell_command("openclaw gateway stop");
 
     // On Windows, global npm uninstall requires root inside WSL
@@ -2932,15 +3020,16 @@ fn uninstall_openclaw() -> Result<String, String> {
     #[cfg(not(target_os = "windows"))]
     shell_command("npm uninstall -g openclaw")?;
 
+    process::probe_environment();
+
     #[cfg(target_os = "windows")]
     {
-        wsl_remove_dir("~/.openclaw")?;
+        wsl_remove_dir(&paths::local_root()?)?;
     }
 
     #[cfg(not(target_os = "windows"))]
     {
-        let home = dirs::home_dir().ok_or("Could not find home directory")?;
-        let openclaw_root = home.join(".openclaw");
+        let openclaw_root = PathBuf::from(paths::local_root()?);
         if openclaw_root.exists() {
             fs::remove_dir_all(openclaw_root).map_err(|e| e.to_string())?;
         }
@@ -2971,54 +3060,607 @@ fn check_prerequisites() -> PrereqCheck {
                 node_installed: false,
                 docker_running: true,
                 openclaw_installed: false,
+                package_managers: Vec::new(),
+                resources: ResourceCheck::default(),
             };
         }
     }
 
     let node = shell_command("node -v").is_ok();
     let openclaw = shell_command("openclaw --version").is_ok();
+    let env = process::environment_info();
+    let package_managers = [("npm", &env.npm), ("pnpm", &env.pnpm), ("bun", &env.bun)]
+        .iter()
+        .filter(|(_, path)| path.is_some())
+        .map(|(pm, _)| pm.to_string())
+        .collect();
 
     PrereqCheck {
         node_installed: node,
         docker_running: true,
         openclaw_installed: openclaw,
+        package_managers,
+        resources: collect_resources(&shell_command, cfg!(target_os = "macos")),
+    }
+}
+
+// Resource preflight
+
+/// Below these the gateway commonly crashes or stalls (npm installs, session writes).
+const MIN_FREE_DISK_MB: u64 = 2048;
+const MIN_AVAILABLE_MEMORY_MB: u64 = 1024;
+
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+struct ResourceCheck {
+    free_disk_mb: Option<u64>,
+    available_memory_mb: Option<u64>,
+    on_battery: Option<bool>,
+    low_power_mode: Option<bool>,
+    warnings: Vec<String>,
+}
+
+/// Available KiB from `df -Pk` output.
+fn parse_df_available_kb(output: &str) -> Option<u64> {
+    output
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()
+}
+
+fn parse_meminfo_available_kb(output: &str) -> Option<u64> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Free + inactive + speculative pages from macOS `vm_stat`, in KiB.
+fn parse_vm_stat_available_kb(output: &str) -> Option<u64> {
+    let page_size: u64 = output
+        .lines()
+        .next()?
+        .split("page size of ")
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    let pages: u64 = output
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            if !["Pages free", "Pages inactive", "Pages speculative"].contains(&name.trim()) {
+                return None;
+            }
+            value.trim().trim_end_matches('.').parse::<u64>().ok()
+        })
+        .sum();
+    Some(pages * page_size / 1024)
+}
+
+fn resource_warnings(check: &ResourceCheck) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let Some(disk) = check.free_disk_mb.filter(|mb| *mb < MIN_FREE_DISK_MB) {
+        warnings.push(format!(
+            "Only {} MB of disk space is free in your home directory; OpenClaw needs at least {} MB for installs, logs and sessions.",
+            disk, MIN_FREE_DISK_MB
+        ));
+    }
+    if let Some(memory) = check
+        .available_memory_mb
+        .filter(|mb| *mb < MIN_AVAILABLE_MEMORY_MB)
+    {
+        warnings.push(format!(
+            "Only {} MB of memory is available; the gateway may be killed or stall below {} MB.",
+            memory, MIN_AVAILABLE_MEMORY_MB
+        ));
+    }
+    if check.on_battery == Some(true) {
+        warnings.push("Running on battery; the gateway stops when the machine sleeps.".to_string());
+    }
+    if check.low_power_mode == Some(true) {
+        warnings.push(
+            "Low Power Mode is on, which throttles background work such as the gateway."
+                .to_string(),
+        );
+    }
+    warnings
+}
+
+/// Collects resource figures with `run` (local shell or SSH) on a macOS or Linux host.
+fn collect_resources(run: &dyn Fn(&str) -> Result<String, String>, macos: bool) -> ResourceCheck {
+    let kb_to_mb = |kb: u64| kb / 1024;
+    let free_disk_mb = run("df -Pk \"$HOME\"")
+        .ok()
+        .and_then(|out| parse_df_available_kb(&out))
+        .map(kb_to_mb);
+    let mut check = if macos {
+        let batt = run("pmset -g batt").ok();
+        ResourceCheck {
+            free_disk_mb,
+            available_memory_mb: run("vm_stat")
+                .ok()
+                .and_then(|out| parse_vm_stat_available_kb(&out))
+                .map(kb_to_mb),
+            on_battery: batt.map(|out| out.contains("'Battery Power'")),
+            low_power_mode: run("pmset -g | grep -i lowpowermode")
+                .ok()
+                .and_then(|out| out.split_whitespace().last().map(|v| v == "1")),
+            warnings: Vec::new(),
+        }
+    } else {
+        ResourceCheck {
+            free_disk_mb,
+            available_memory_mb: run("cat /proc/meminfo")
+                .ok()
+                .and_then(|out| parse_meminfo_available_kb(&out))
+                .map(kb_to_mb),
+            on_battery: run("cat /sys/class/power_supply/*/status 2>/dev/null")
+                .ok()
+                .filter(|out| !out.trim().is_empty())
+                .map(|out| out.lines().any(|l| l.trim() == "Discharging")),
+            low_power_mode: None,
+            warnings: Vec::new(),
+        }
+    };
+    check.warnings = resource_warnings(&check);
+    check
+}
+
+// Connectivity preflight
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct EndpointCheck {
+    name: String,
+    host: String,
+    dns_ok: bool,
+    https_ok: bool,
+    http_status: Option<u16>,
+    error: Option<String>,
+    /// Server `Date` minus local time, in seconds.
+    clock_skew_secs: Option<i64>,
+    /// Set when the certificate does not chain to a public CA: the inspection product named
+    /// in it, or `UNKNOWN_TLS_INTERCEPTOR`.
+    tls_interceptor: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct ConnectivityReport {
+    /// `ok`, `no_internet`, `proxy_required`, `provider_blocked` or `partial`.
+    status: String,
+    message: String,
+    proxy_configured: bool,
+    checks: Vec<EndpointCheck>,
+    clock_skew_secs: Option<i64>,
+    tls_interceptor: Option<String>,
+    /// Clock and TLS findings that commonly break provider auth.
+    warnings: Vec<String>,
+}
+
+/// API host for cloud providers; local providers have none.
+fn provider_api_host(provider: &str) -> Option<&'static str> {
+    match provider {
+        "anthropic" => Some("api.anthropic.com"),
+        "openai" => Some("api.openai.com"),
+        "google" => Some("generativelanguage.googleapis.com"),
+        "openrouter" => Some("openrouter.ai"),
+        "xai" => Some("api.x.ai"),
+        "groq" => Some("api.groq.com"),
+        "mistral" => Some("api.mistral.ai"),
+        "deepseek" => Some("api.deepseek.com"),
+        "cerebras" => Some("api.cerebras.ai"),
+        _ => None,
+    }
+}
+
+fn proxy_configured() -> bool {
+    ["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+        .iter()
+        .any(|name| std::env::var(name).is_ok_and(|v| !v.trim().is_empty()))
+}
+
+/// Whether `error` (or one of its causes) is a certificate that no trusted root vouches for.
+fn is_untrusted_certificate_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut current = Some(error);
+    while let Some(err) = current {
+        if err.to_string().contains("UnknownIssuer") {
+            return true;
+        }
+        current = err.source();
     }
+    false
+}
+
+/// `client` trusts the system store, `public_roots` only the bundled Mozilla roots; a host
+/// only the former accepts is served a certificate minted by an inspection proxy.
+async fn check_endpoint(
+    client: &reqwest::Client,
+    public_roots: &reqwest::Client,
+    name: &str,
+    host: &str,
+) -> EndpointCheck {
+    let dns = tokio::net::lookup_host((host, 443)).await;
+    let dns_ok = dns.map(|mut addrs| addrs.next().is_some()).unwrap_or(false);
+    let mut check = EndpointCheck {
+        name: name.to_string(),
+        host: host.to_string(),
+        dns_ok,
+        https_ok: false,
+        http_status: None,
+        error: None,
+        clock_skew_secs: None,
+        tls_interceptor: None,
+    };
+    match client.get(format!("https://{}/", host)).send().await {
+        Ok(resp) => {
+            let status = resp.status();
+            check.http_status = Some(status.as_u16());
+            if status == reqwest::StatusCode::PROXY_AUTHENTICATION_REQUIRED {
+                check.error = Some("Proxy authentication required".to_string());
+                return check;
+            }
+            // Any HTTP answer (even 404) proves the TLS connection works.
+            check.https_ok = true;
+            check.clock_skew_secs = resp
+                .headers()
+                .get(reqwest::header::DATE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_http_date)
+                .map(|server| server - unix_now_secs() as i64);
+            let leaf = resp
+                .extensions()
+                .get::<reqwest::tls::TlsInfo>()
+                .and_then(|info| info.peer_certificate())
+                .map(|der| der.to_vec());
+            let publicly_trusted =
+                match public_roots.head(format!("https://{}/", host)).send().await {
+                    Ok(_) => true,
+                    Err(e) => !is_untrusted_certificate_error(&e),
+                };
+            check.tls_interceptor = (!publicly_trusted).then(|| {
+                leaf.as_deref()
+                    .and_then(detect_tls_interceptor)
+                    .unwrap_or(UNKNOWN_TLS_INTERCEPTOR)
+                    .to_string()
+            });
+        }
+        Err(e) => check.error = Some(e.to_string()),
+    }
+    check
+}
+
+fn diagnose_connectivity(checks: &[EndpointCheck], proxy: bool) -> (String, String) {
+    let failed: Vec<&EndpointCheck> = checks.iter().filter(|c| !c.https_ok).collect();
+    if failed.is_empty() {
+        return ("ok".to_string(), "All endpoints are reachable.".to_string());
+    }
+    if checks.iter().any(|c| c.http_status == Some(407)) {
+        return (
+            "proxy_required".to_string(),
+            "Your network proxy requires authentication. Set HTTPS_PROXY with credentials and retry.".to_string(),
+        );
+    }
+    if failed.len() == checks.len() {
+        if checks.iter().all(|c| !c.dns_ok) && !proxy {
+            return (
+                "no_internet".to_string(),
+                "No internet connection: DNS lookups failed for every endpoint.".to_string(),
+            );
+        }
+        return (
+            "proxy_required".to_string(),
+            if proxy {
+                "The configured proxy is not letting HTTPS traffic through. Check HTTPS_PROXY."
+            } else {
+                "Names resolve but HTTPS connections fail; this network likely requires a proxy (set HTTPS_PROXY)."
+            }
+            .to_string(),
+        );
+    }
+    let names: Vec<&str> = failed.iter().map(|c| c.host.as_str()).collect();
+    if failed.iter().any(|c| c.name == "provider") {
+        return (
+            "provider_blocked".to_string(),
+            format!(
+                "The internet works but the model provider is unreachable ({}). It may be blocked on this network.",
+                names.join(", ")
+            ),
+        );
+    }
+    (
+        "partial".to_string(),
+        format!("Some endpoints are unreachable: {}", names.join(", ")),
+    )
+}
+
+#[command]
+async fn check_connectivity(provider: Option<String>) -> Result<ConnectivityReport, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(8))
+        .tls_info(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let public_roots = reqwest::Client::builder()
+        .timeout(Duration::from_secs(8))
+        .use_rustls_tls()
+        .tls_built_in_root_certs(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut targets = vec![("npm", "registry.npmjs.org")];
+    if let Some(host) = provider.as_deref().and_then(provider_api_host) {
+        targets.push(("provider", host));
+    }
+    targets.push(("telegram", "api.telegram.org"));
+
+    let checks = futures_util::future::join_all(
+        targets
+            .iter()
+            .map(|(name, host)| check_endpoint(&client, &public_roots, name, host)),
+    )
+    .await;
+    let proxy = proxy_configured();
+    let (status, message) = diagnose_connectivity(&checks, proxy);
+    let clock_skew_secs = checks.iter().find_map(|c| c.clock_skew_secs);
+    let tls_interceptor = checks.iter().find_map(|c| c.tls_interceptor.clone());
+    Ok(ConnectivityReport {
+        status,
+        message,
+        proxy_configured: proxy,
+        checks,
+        clock_skew_secs,
+        warnings: clock_and_tls_warnings(clock_skew_secs, tls_interceptor.as_deref()),
+        tls_interceptor,
+    })
+}
+
+/// Beyond this, token and OAuth validation at providers starts failing.
+const MAX_CLOCK_SKEW_SECS: i64 = 120;
+
+/// Label for a certificate outside the public CAs that names none of `TLS_INTERCEPTORS`.
+const UNKNOWN_TLS_INTERCEPTOR: &str = "unknown";
+
+/// TLS-inspection products whose names appear in the certificates they mint.
+const TLS_INTERCEPTORS: &[&str] = &[
+    "Zscaler",
+    "Netskope",
+    "Fortinet",
+    "FortiGate",
+    "Palo Alto Networks",
+    "Blue Coat",
+    "Symantec Web",
+    "Cisco Umbrella",
+    "Forcepoint",
+    "Sophos",
+    "Kaspersky",
+    "Avast",
+    "AVG Technologies",
+    "ESET",
+    "Bitdefender",
+    "mitmproxy",
+    "Charles Proxy",
+    "Fiddler",
+];
+
+/// Unix seconds from an IMF-fixdate `Date` header, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+fn parse_http_date(value: &str) -> Option<i64> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [_, day, month, year, time, "GMT"] = parts.as_slice() else {
+        return None;
+    };
+    let month = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ]
+    .iter()
+    .position(|m| m == month)? as i64
+        + 1;
+    let (day, year): (i64, i64) = (day.parse().ok()?, year.parse().ok()?);
+    let hms: Vec<i64> = time.split(':').filter_map(|p| p.parse().ok()).collect();
+    let [h, m, s] = hms.as_slice() else {
+        return None;
+    };
+    // Days since 1970-01-01 (Howard Hinnant's days_from_civil).
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    Some(days * 86400 + h * 3600 + m * 60 + s)
+}
+
+/// Interception product named in a DER certificate. Only used to label a certificate already
+/// known not to chain to a public CA; a name match alone proves nothing.
+fn detect_tls_interceptor(der: &[u8]) -> Option<&'static str> {
+    let text = String::from_utf8_lossy(der);
+    TLS_INTERCEPTORS
+        .iter()
+        .find(|name| text.contains(*name))
+        .copied()
+}
+
+fn clock_and_tls_warnings(skew: Option<i64>, interceptor: Option<&str>) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let Some(skew) = skew.filter(|s| s.abs() > MAX_CLOCK_SKEW_SECS) {
+        warnings.push(format!(
+            "Your system clock is {} minutes {} internet time. Provider sign-in and API keys can fail until you enable automatic time sync.",
+            skew.abs() / 60,
+            if skew > 0 { "behind" } else { "ahead of" }
+        ));
+    }
+    if let Some(name) = interceptor {
+        let by = if name == UNKNOWN_TLS_INTERCEPTOR {
+            String::new()
+        } else {
+            format!(" (by {})", name)
+        };
+        warnings.push(format!(
+            "HTTPS certificate not issued by a public CA: traffic is being inspected{}. If provider calls fail with certificate errors, ask IT to exempt AI provider domains or set NODE_EXTRA_CA_CERTS to the corporate root certificate.",
+            by
+        ));
+    }
+    warnings
+}
+
+const INSTALL_BACKENDS: &[&str] = &["npm", "pnpm", "bun"];
+
+/// Ways to install openclaw: the Node package managers plus Homebrew when a formula exists.
+const INSTALL_STRATEGIES: &[&str] = &["npm", "pnpm", "bun", "brew"];
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct InstallStrategy {
+    id: String,
+    available: bool,
+    /// What `install_openclaw` picks when no strategy is given or preferred.
+    recommended: bool,
+}
+
+/// Automatic choice: keep Homebrew when openclaw is already brewed (mixing it with an npm
+/// global leaves two copies on PATH), otherwise the first available in `INSTALL_STRATEGIES`.
+fn choose_install_strategy<'a>(available: &[&'a str], brewed: bool) -> Option<&'a str> {
+    if brewed && available.contains(&"brew") {
+        return Some("brew");
+    }
+    INSTALL_STRATEGIES
+        .iter()
+        .find_map(|s| available.iter().find(|a| *a == s).copied())
+}
+
+/// Strategies usable on this machine, and whether openclaw is currently brewed.
+fn available_install_strategies() -> (Vec<&'static str>, bool) {
+    let env = process::environment_info();
+    let mut available: Vec<&'static str> =
+        [("npm", &env.npm), ("pnpm", &env.pnpm), ("bun", &env.bun)]
+            .iter()
+            .filter(|(_, path)| path.is_some())
+            .map(|(id, _)| *id)
+            .collect();
+    let mut brewed = false;
+    if cfg!(not(target_os = "windows")) && shell_command("brew info --formula openclaw").is_ok() {
+        available.push("brew");
+        brewed = shell_command("brew list --formula openclaw").is_ok();
+    }
+    (available, brewed)
+}
+
+#[command]
+fn get_install_strategies() -> Vec<InstallStrategy> {
+    let (available, brewed) = available_install_strategies();
+    let recommended = choose_install_strategy(&available, brewed);
+    INSTALL_STRATEGIES
+        .iter()
+        .map(|id| InstallStrategy {
+            id: id.to_string(),
+            available: available.contains(id),
+            recommended: recommended == Some(*id),
+        })
+        .collect()
 }
 
+/// Install command line for `strategy`. pnpm understands npm's registry/proxy flags; bun
+/// only takes `--registry`; Homebrew uses its own mirrors.
+fn install_backend_args(strategy: &str, settings: &InstallSettings) -> Result<Vec<String>, String> {
+    let mut args: Vec<String> = match strategy {
+        "npm" => vec!["install", "-g", "openclaw"],
+        "pnpm" | "bun" => vec!["add", "-g", "openclaw"],
+        "brew" => return Ok(vec!["install".to_string(), "openclaw".to_string()]),
+        other => return Err(format!("Unsupported install strategy: {}", other)),
+    }
+    .into_iter()
+    .map(|a| a.to_string())
+    .collect();
+    if strategy == "bun" {
+        if let Some(registry) = &settings.registry {
+            args.push(format!("--registry={}", registry));
+        }
+    } else {
+        args.extend(npm_config_args(settings));
+    }
+    Ok(args)
+}
+
+/// Installs openclaw with `backend` (one of `INSTALL_STRATEGIES`), falling back to the
+/// saved preference and then the automatic choice. pnpm/bun global bin directories are
+/// picked up by the environment probe afterwards.
 #[command]
-fn install_openclaw() -> Result<String, String> {
+fn install_openclaw(
+    app: tauri::AppHandle,
+    backend: Option<String>,
+    task_id: Option<String>,
+) -> Result<String, String> {
+    let progress = progress::Progress::new(&app, "install", task_id);
+    let _operation = lifecycle::try_begin_operation("install_openclaw")?;
+    install_openclaw_with(
+        read_install_settings(&app).unwrap_or_default(),
+        backend,
+        &progress,
+    )
+}
+
+fn install_openclaw_with(
+    settings: InstallSettings,
+    backend: Option<String>,
+    progress: &progress::Progress,
+) -> Result<String, String> {
     #[cfg(target_os = "windows")]
     {
+        progress.info("wsl", 0, "Checking WSL2");
         ensure_wsl2_installed()?;
-        // Node.js should already be installed by install_local_nodejs()
-        // Global npm install needs root for /usr/lib/node_modules
-        wsl_root_command("npm install -g openclaw")?;
-        shell_command("openclaw --version")?;
-        Ok("OpenClaw installed successfully in WSL2.".to_string())
     }
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        shell_command("npm install -g openclaw")?;
-        shell_command("openclaw --version")?;
-        Ok("OpenClaw installed successfully.".to_string())
+    progress.info("backend", 5, "Choosing a package manager");
+
+    let (available, brewed) = available_install_strategies();
+    let backend = match backend.or_else(|| settings.preferred_strategy.clone()) {
+        Some(chosen) => chosen,
+        None => choose_install_strategy(&available, brewed)
+            .ok_or("No package manager found. Install Node.js first.")?
+            .to_string(),
+    };
+    if !available.contains(&backend.as_str()) {
+        return Err(format!("{} is not available on this machine.", backend));
+    }
+    let args = install_backend_args(&backend, &settings)?;
+    if (backend == "npm" || backend == "pnpm") && has_network_overrides(&settings) {
+        progress.info("registry", 10, "Checking the package registry");
+        check_npm_registry(&settings, progress)?;
     }
+    progress.info(
+        "install",
+        20,
+        &format!("Installing openclaw with {}", backend),
+    );
+    if backend == "npm" {
+        run_npm_global(&args).map_err(explain_npm_permission_error)?;
+    } else {
+        // pnpm, bun and Homebrew install under user-owned prefixes, so no root is needed.
+        process::run(&backend, &args)?;
+    }
+    progress.info("verify", 90, "Checking the installed openclaw");
+    process::probe_environment();
+    shell_command("openclaw --version")?;
+    progress.info("done", 100, "OpenClaw installed");
+
+    #[cfg(target_os = "windows")]
+    return Ok("OpenClaw installed successfully in WSL2.".to_string());
+
+    #[cfg(not(target_os = "windows"))]
+    Ok("OpenClaw installed successfully.".to_string())
 }
 
 #[command]
 fn configure_agent(config: AgentConfig) -> Result<String, String> {
+    let _operation = lifecycle::try_begin_operation("configure_agent")?;
     // Platform-abstracted filesystem operations.
     // On Windows, openclaw runs inside WSL, so we must write to the WSL filesystem.
     // On macOS/Linux, we use native filesystem operations.
 
-    #[cfg(target_os = "windows")]
-    let home: String = wsl_home_dir()?;
-
-    #[cfg(not(target_os = "windows"))]
-    let home: String = dirs::home_dir()
-        .ok_or("Could not find home directory")?
-        .to_string_lossy()
-        .to_string();
+    let openclaw_root = paths::local_root()?;
 
     // Closures for platform-abstracted filesystem operations
     let mkdir_p_fn = |path: &str| -> Result<(), String> {
@@ -3059,16 +3701,19 @@ fn configure_agent(config: AgentConfig) -> Result<String, String> {
         let _ = shell_command("openclaw gateway stop");
         // DO NOT remove openclaw.json. The token is tied to keychain.
         // install --force will scaffold missing fields while keeping the token.
-        let _ = shell_command("openclaw gateway install --force --profile messaging");
+        install_gateway_with_rollback("messaging")?;
     }
 
-    let openclaw_root = format!("{}/.openclaw", home);
     let workspace = format!("{}/workspace", openclaw_root);
     let agents_dir = format!("{}/agents/main/agent", openclaw_root);
 
     mkdir_p_fn(&workspace)?;
     mkdir_p_fn(&agents_dir)?;
 
+    // Held from the reads of the existing config below to the write that replaces it, so
+    // no other config update lands in between and is lost.
+    let config_lock = lifecycle::config_lock();
+
     // Always preserve existing/scaffolded gateway token to avoid device token mismatch
     let gateway_token: String = {
         let existing_config_path = format!("{}/openclaw.json", openclaw_root);
@@ -3140,7 +3785,7 @@ fn configure_agent(config: AgentConfig) -> Result<String, String> {
                 &config.provider,
                 &config.api_key,
                 config.auth_method.as_deref().unwrap_or("token"),
-                config.local_base_url.as_ref(),
+                provider_base_url(&config),
             )
         });
     let effective_primary_model = apply_model_provider_auth(&config.model, &provider_auths);
@@ -3157,7 +3802,7 @@ fn configure_agent(config: AgentConfig) -> Result<String, String> {
     let auth_mode = normalize_auth_mode(&primary_provider_auth.auth_method);
     let required_plugin_ids = collect_required_plugin_ids(&provider_auths, config.skills.as_ref());
 
-    let gateway_port = config.gateway_port.unwrap_or(18789);
+    let gateway_port = config.gateway_port.unwrap_or_else(read_local_gateway_port);
     let gateway_bind = config.gateway_bind.as_deref().unwrap_or("loopback");
     let gateway_auth_mode = config.gateway_auth_mode.as_deref().unwrap_or("token");
     let tailscale_mode = config.tailscale_mode.as_deref().unwrap_or("off");
@@ -3174,8 +3819,8 @@ fn configure_agent(config: AgentConfig) -> Result<String, String> {
             let mut agent_obj = serde_json::json!({
                 "id": agent.id,
                 "name": agent.name,
-                "workspace": format!("{}/.openclaw/agents/{}/workspace", home, agent.id),
-                "agentDir": format!("{}/.openclaw/agents/{}/agent", home, agent.id),
+                "workspace": format!("{}/agents/{}/workspace", openclaw_root, agent.id),
+                "agentDir": format!("{}/agents/{}/agent", openclaw_root, agent.id),
                 "model": {
                     "primary": apply_model_provider_auth(&agent.model, &provider_auths)
                 }
@@ -3208,8 +3853,8 @@ fn configure_agent(config: AgentConfig) -> Result<String, String> {
         let mut main_obj = serde_json::json!({
             "id": "main",
             "name": config.agent_name,
-            "workspace": format!("{}/.openclaw/workspace", home),
-            "agentDir": format!("{}/.openclaw/agents/main/agent", home),
+            "workspace": workspace.clone(),
+            "agentDir": agents_dir,
             "model": {
                 "primary": effective_primary_model
             }
@@ -3246,10 +3891,9 @@ fn configure_agent(config: AgentConfig) -> Result<String, String> {
             .entry("messages".to_string())
             .or_insert(serde_json::json!({}));
         if let Some(m) = messages_entry.as_object_mut() {
-            m.insert(
-                "ackReactionScope".to_string(),
-                serde_json::json!("group-mentions"),
-            );
+            // Keep a scope chosen in the messaging policy editor.
+            m.entry("ackReactionScope".to_string())
+                .or_insert(serde_json::json!(DEFAULT_ACK_REACTION_SCOPE));
         }
 
         // Agents
@@ -3351,13 +3995,21 @@ fn configure_agent(config: AgentConfig) -> Result<String, String> {
                     }
                 }
 
+                // Bots added with `add_telegram_account` survive a reconfigure.
+                let mut accounts = obj
+                    .get("channels")
+                    .and_then(|c| c.get("telegram"))
+                    .and_then(|t| t.get("accounts"))
+                    .and_then(|a| a.as_object())
+                    .cloned()
+                    .unwrap_or_default();
+                accounts.insert("default".to_string(), channel_config);
+
                 obj.insert(
                     "channels".to_string(),
                     serde_json::json!({
                         "telegram": {
-                            "accounts": {
-                                "default": channel_config
-                            }
+                            "accounts": accounts
                         }
                     }),
                 );
@@ -3577,78 +4229,26 @@ fn configure_agent(config: AgentConfig) -> Result<String, String> {
     // NOTE: agent_type is NOT stored in openclaw.json (it's not a valid OpenClaw key).
     // It's stored in a separate clawnetes-meta.json file for our own tracking.
 
-    // Add models.providers section for LM Studio so openclaw can resolve lmstudio/ models
-    if config.provider == "lmstudio" {
-        let base_url = config
-            .local_base_url
-            .as_deref()
-            .unwrap_or("http://localhost:1234");
-        let base_url_v1 = if base_url.ends_with("/v1") {
-            base_url.to_string()
-        } else {
-            format!("{}/v1", base_url.trim_end_matches('/'))
-        };
-        // Extract model ID by stripping the "lmstudio/" prefix
-        let model_id = if config.model.starts_with("lmstudio/") {
-            config.model.strip_prefix("lmstudio/").unwrap().to_string()
-        } else {
-            config.model.clone()
-        };
-        let mut model_ids = vec![model_id];
-        // Also register any lmstudio fallback models
-        if let Some(fb) = &config.fallback_models {
-            for fb_model in fb {
-                if let Some(stripped) = fb_model.strip_prefix("lmstudio/") {
-                    if !model_ids.contains(&stripped.to_string()) {
-                        model_ids.push(stripped.to_string());
-                    }
-                }
-            }
-        }
-        let lmstudio_models: Vec<serde_json::Value> = model_ids
-            .iter()
-            .map(|id| {
-                serde_json::json!({
-                    "id": id,
-                    "name": id,
-                    "reasoning": false,
-                    "input": ["text"],
-                    "cost": { "input": 0, "output": 0, "cacheRead": 0, "cacheWrite": 0 },
-                    "contextWindow": 131072,
-                    "maxTokens": 8192
-                })
-            })
-            .collect();
-        if let Some(obj) = config_json.as_object_mut() {
-            obj.insert(
-                "models".to_string(),
-                serde_json::json!({
-                    "mode": "merge",
-                    "providers": {
-                        "lmstudio": {
-                            "baseUrl": base_url_v1,
-                            "apiKey": "lmstudio",
-                            "api": "openai-completions",
-                            "models": lmstudio_models
-                        }
-                    }
-                }),
-            );
-        }
+    // Register lmstudio/, custom/, azure/ and Bedrock models so openclaw can resolve them
+    apply_provider_models(&config, &mut config_json)?;
+    if let Some(bedrock) = config
+        .bedrock
+        .as_ref()
+        .filter(|_| config.provider == bedrock::PROVIDER)
+    {
+        save_bedrock_credentials(bedrock)?;
     }
 
     let config_json_raw = serde_json::to_string_pretty(&config_json).map_err(|e| e.to_string())?;
 
-    write_file_fn(
-        &format!("{}/openclaw.json", openclaw_root),
-        &config_json_raw,
-    )?;
+    store_local_openclaw_file("openclaw.json", &config_json_raw)?;
+    drop(config_lock);
 
     // Force sync the token to keychain to permanently fix any token mismatches
-    let _ = shell_command(&format!(
-        "openclaw config set gateway.auth.token {}",
-        gateway_token
-    ));
+    let _ = process::run(
+        "openclaw",
+        &["config", "set", "gateway.auth.token", &gateway_token],
+    );
 
     // Store Clawnetes-specific metadata in a separate file
     {
@@ -3751,7 +4351,7 @@ Serve {}."#,
     }
 
     if let Some(nm) = config.node_manager {
-        let _ = shell_command(&format!("openclaw config set skills.nodeManager {}", nm));
+        let _ = process::run("openclaw", &["config", "set", "skills.nodeManager", &nm]);
     }
 
     // Telegram config is now written inline in the JSON above.
@@ -3771,6 +4371,13 @@ Serve {}."#,
         &auth_profiles_json,
     )?;
 
+    // What this run produced, for `check_config_drift`.
+    let applied = serde_json::json!({ "config": config_json, "authProfiles": auth_profiles_val });
+    write_file_fn(
+        &format!("{}/{}", openclaw_root, APPLIED_CONFIG_FILE),
+        &serde_json::to_string_pretty(&applied).map_err(|e| e.to_string())?,
+    )?;
+
     let identity_md = if let Some(custom) = config.identity_md {
         custom
     } else {
@@ -3827,13 +4434,23 @@ Serve {}."#,
 }
 
 #[command]
-fn start_gateway() -> Result<String, String> {
+fn start_gateway(app: tauri::AppHandle, task_id: Option<String>) -> Result<String, String> {
+    let progress = progress::Progress::new(&app, "gateway", task_id);
+    let _operation = lifecycle::try_begin_operation("start_gateway")?;
+    let message = start_local_gateway(&progress)?;
+    notify_gateway_restarted(&app);
+    Ok(message)
+}
+
+fn start_local_gateway(progress: &progress::Progress) -> Result<String, String> {
     #[cfg(target_os = "macos")]
     let home = dirs::home_dir().ok_or("Could not find home directory")?;
     // config_path removed as unused
 
+    progress.info("stop", 0, "Stopping the running gateway");
     let _ = shell_command("openclaw gateway stop");
     thread::sleep(Duration::from_secs(2));
+    let _ = sync_gateway_env_to_service(&Default::default());
 
     // Ensure service is loaded on macOS (fix for "Could not find service" error)
     #[cfg(target_os = "macos")]
@@ -3853,56 +4470,81 @@ fn start_gateway() -> Result<String, String> {
     // Installation is now handled in configure_agent / setup_remote_openclaw.
 
     // Run doctor --fix to auto-migrate any pairing stores and resolve schema quirks
+    progress.info("doctor", 20, "Running openclaw doctor --fix");
     let _ = shell_command("openclaw doctor --fix --yes || true");
 
-    let start_output = shell_command("openclaw gateway start")?;
-
-    if start_output.to_lowercase().contains("error")
-        || start_output.to_lowercase().contains("failed")
-    {
-        return Err(format!("Gateway start may have failed: {}", start_output));
-    }
+    let port = read_local_gateway_port();
+    progress.info(
+        "start",
+        40,
+        &format!("Starting the gateway on port {}", port),
+    );
+    run_gateway_cli("openclaw gateway start", port).map_err(|failure| failure.to_string())?;
 
+    progress.info("wait", 50, "Waiting for the gateway to come up");
     thread::sleep(Duration::from_secs(5));
 
-    let mut last_error = String::new();
-    for attempt in 1..=8 {
-        if TcpStream::connect("127.0.0.1:18789").is_ok() {
-            return Ok("Gateway started successfully and is accessible on port 18789.".to_string());
-        }
-
-        if let Ok(status) = shell_command("openclaw gateway status") {
-            let status_lower = status.to_lowercase();
-            last_error = format!("Status: {} | Port 18789: not accessible", status.trim());
-
-            if status_lower.contains("starting") || status_lower.contains("initializing") {
-                last_error = format!("Gateway is starting... (attempt {}/8)", attempt);
-            }
-        } else {
-            last_error = format!("Gateway status check failed (attempt {}/8)", attempt);
-        }
-
-        if attempt < 8 {
-            thread::sleep(Duration::from_secs(3));
-        }
-    }
+    let started = retry::retry(
+        &retry::GATEWAY_STARTUP,
+        |_| probe_local_gateway(port),
+        |_| true,
+        |attempt, error| {
+            progress.info(
+                "wait",
+                progress::fraction(60, 95, attempt.number, attempt.of),
+                &attempt.describe(error),
+            )
+        },
+    );
+    let Err(last_error) = started else {
+        progress.info(
+            "done",
+            100,
+            &format!("Gateway is listening on port {}", port),
+        );
+        return Ok(format!(
+            "Gateway started successfully and is accessible on port {}.",
+            port
+        ));
+    };
 
     let final_status = shell_command("openclaw gateway status")
         .unwrap_or_else(|_| "Unable to get status".to_string());
+    progress.error("wait", 100, &format!("Gateway did not open port {}", port));
 
     Err(format!(
-        "Gateway did not become accessible on port 18789 after 24+ seconds.\n\
+        "Gateway did not become accessible on port {port} after 24+ seconds.\n\
         Last status: {}\n\
         Final gateway status:\n{}\n\n\
         Troubleshooting:\n\
         1. Check gateway logs: 'openclaw gateway logs'\n\
         2. Check gateway status: 'openclaw gateway status'\n\
         3. Try manual start: 'openclaw gateway stop && openclaw gateway start'\n\
-        4. Check if port 18789 is in use: 'lsof -i :18789'",
-        last_error, final_status
+        4. Check if port {port} is in use: 'lsof -i :{port}'",
+        last_error,
+        final_status,
+        port = port
     ))
 }
 
+/// One look at the local gateway: listening on `port`, or what `openclaw gateway status` says.
+fn probe_local_gateway(port: u16) -> Result<(), String> {
+    if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+        return Ok(());
+    }
+    Err(match shell_command("openclaw gateway status") {
+        Ok(status) => {
+            let status_lower = status.to_lowercase();
+            if status_lower.contains("starting") || status_lower.contains("initializing") {
+                "Gateway is starting...".to_string()
+            } else {
+                format!("Status: {} | Port {}: not accessible", status.trim(), port)
+            }
+        }
+        Err(_) => "Gateway status check failed".to_string(),
+    })
+}
+
 #[command]
 fn initialize_agent_sessions(agent_ids: Vec<String>) -> Result<String, String> {
     let mut initialized = 0;
@@ -3924,18 +4566,93 @@ fn generate_pairing_code() -> Result<String, String> {
     Ok("Ready! Send any message to your Telegram bot to start pairing. The bot will respond automatically with a code.".to_string())
 }
 
-#[command]
-async fn approve_pairing(code: String, remote: Option<RemoteInfo>) -> Result<String, String> {
-    // Run: openclaw pairing approve <code> --channel telegram
-    let cmd_raw = format!("openclaw pairing approve {} --channel telegram", code);
+/// Checks the pending list first when the CLI supports `--json`, so an unknown code is
+/// reported without scraping the approve output; older CLIs fall back to scraping.
+fn approve_pairing_via_cli(code: &str, account: Option<&str>) -> Result<String, String> {
+    if !is_cli_operand(code) {
+        return Err(format!("Invalid pairing code: {}", code));
+    }
+    let mut approve_args = vec!["pairing", "approve", code, "--channel", "telegram"];
+    if let Some(account) = account {
+        approve_args.extend(["--account", account]);
+    }
+    let pending = match cli::pairing_requests("telegram") {
+        Ok(list) => list.requests,
+        Err(_) => return process::run("openclaw", &approve_args),
+    };
+    if !pending.iter().any(|r| r.code.eq_ignore_ascii_case(code)) {
+        return Err("No pending pairing request found for this code.".to_string());
+    }
+
+    let output = process::output("openclaw", &approve_args)?;
+    if output.success() {
+        Ok(String::new())
+    } else if output.stderr.trim().is_empty() {
+        Err(output.stdout)
+    } else {
+        Err(output.stderr)
+    }
+}
 
-    let output = if let Some(r) = remote {
+#[command]
+async fn approve_pairing(
+    app: tauri::AppHandle,
+    code: String,
+    remote: Option<RemoteInfo>,
+    account: Option<String>,
+    gateway_id: Option<String>,
+) -> Result<String, String> {
+    if let Some(account) = &account {
+        if !is_valid_session_id(account) {
+            return Err(format!("Invalid Telegram account: {}", account));
+        }
+    }
+    let account_flag = account
+        .as_deref()
+        .map(|a| format!(" --account {}", a))
+        .unwrap_or_default();
+    let output = if let Some(client) = registered_gateway_client(&app, gateway_id.as_deref())? {
+        client
+            .approve_pairing("telegram", &code, account.as_deref())
+            .await
+            .map(|_| String::new())
+            .map_err(|e| e.message())
+    } else if let Some(r) = remote {
         let sess = connect_ssh(&r)?;
         let os_type = execute_ssh(&sess, "uname -s")?.trim().to_string();
         let prefix = get_env_prefix(&os_type);
-        execute_ssh(&sess, &format!("{}{}", prefix, cmd_raw))
+        // SSH only takes a command line, so the code is quoted rather than interpolated.
+        execute_ssh(
+            &sess,
+            &format!(
+                "{}openclaw pairing approve {} --channel telegram{}",
+                prefix,
+                shell_single_quote(&code),
+                account_flag
+            ),
+        )
     } else {
-        shell_command(&cmd_raw)
+        // Prefer the gateway API; older gateways without the RPC fall back to the CLI.
+        let client = gateway_client::GatewayClient::local().single_attempt();
+        let via_gateway = if client.is_reachable().await {
+            match client
+                .approve_pairing("telegram", &code, account.as_deref())
+                .await
+            {
+                Ok(_) => Some(Ok(String::new())),
+                Err(e @ gateway_client::RpcError::Rejected(_))
+                    if e.message()
+                        .to_lowercase()
+                        .contains("no pending pairing request") =>
+                {
+                    Some(Err(e.message()))
+                }
+                Err(_) => None,
+            }
+        } else {
+            None
+        };
+        via_gateway.unwrap_or_else(|| approve_pairing_via_cli(&code, account.as_deref()))
     };
 
     match output {
@@ -3943,7 +4660,7 @@ async fn approve_pairing(code: String, remote: Option<RemoteInfo>) -> Result<Str
             let out_lower = out.to_lowercase();
             if out_lower.contains("error") {
                 if out_lower.contains("no pending pairing request found") {
-                    return Err("Invalid pairing code. Please make sure you sent a message to the bot and try again.".to_string());
+                    return Err(messages::text("pairing.invalid_code", &[]));
                 }
                 return Err(out);
             }
@@ -3952,7 +4669,7 @@ async fn approve_pairing(code: String, remote: Option<RemoteInfo>) -> Result<Str
         Err(err) => {
             let err_lower = err.to_lowercase();
             if err_lower.contains("no pending pairing request found") {
-                return Err("Invalid pairing code. Please make sure you sent a message to the bot and try again.".to_string());
+                return Err(messages::text("pairing.invalid_code", &[]));
             }
             Err(err)
         }
@@ -3960,7 +4677,15 @@ async fn approve_pairing(code: String, remote: Option<RemoteInfo>) -> Result<Str
 }
 
 #[command]
-fn get_dashboard_url(is_remote: bool, remote: Option<RemoteInfo>) -> Result<String, String> {
+fn get_dashboard_url(
+    app: tauri::AppHandle,
+    is_remote: bool,
+    remote: Option<RemoteInfo>,
+    gateway_id: Option<String>,
+) -> Result<String, String> {
+    if let Some((gateway, token)) = registered_gateway(&app, gateway_id.as_deref())? {
+        return Ok(gateways::dashboard_url(&gateway.url, token.as_deref()));
+    }
     let token = if is_remote && remote.is_some() {
         let r = remote.unwrap();
         let sess = connect_ssh(&r)?;
@@ -4002,9 +4727,7 @@ fn get_dashboard_url(is_remote: bool, remote: Option<RemoteInfo>) -> Result<Stri
             {
                 token
             } else {
-                let home = wsl_home_dir()?.trim().to_string();
-                let config_path = format!("{}/.openclaw/openclaw.json", home);
-                let config_str = wsl_read_file(&config_path)?;
+                let config_str = read_local_openclaw_file("openclaw.json")?;
                 extract_gateway_token_from_config(&config_str, "config")?
             }
         }
@@ -4024,15 +4747,19 @@ fn get_dashboard_url(is_remote: bool, remote: Option<RemoteInfo>) -> Result<Stri
             {
                 token
             } else {
-                let home = dirs::home_dir().ok_or("Could not find home directory")?;
-                let config_path = home.join(".openclaw").join("openclaw.json");
-                let config_str = fs::read_to_string(&config_path).map_err(|e| e.to_string())?;
+                let config_str = read_local_openclaw_file("openclaw.json")?;
                 extract_gateway_token_from_config(&config_str, "config")?
             }
         }
     };
 
-    Ok(format!("http://127.0.0.1:18789/#token={}", token))
+    // Remote dashboards are reached through the SSH tunnel on the default port.
+    let port = if is_remote {
+        profiles::DEFAULT_GATEWAY_PORT
+    } else {
+        read_local_gateway_port()
+    };
+    Ok(format!("http://127.0.0.1:{}/#token={}", port, token))
 }
 
 #[command]
@@ -4133,2938 +4860,17378 @@ fn verify_tunnel_connectivity(remote: RemoteInfo) -> Result<bool, String> {
     ))
 }
 
-// WSL2 Helper Functions
+// Gateway exposure helpers
 
-#[cfg(target_os = "windows")]
-fn check_wsl2_installed() -> bool {
-    let output = Command::new("powershell")
-        .args(["-Command", "wsl -l -v 2>$null; exit $LASTEXITCODE"])
-        .output();
+#[derive(serde::Serialize)]
+struct FirewallStatus {
+    detected: bool,
+    enabled: Option<bool>,
+    guidance: String,
+}
 
-    output.map(|o| o.status.success()).unwrap_or(false)
+#[derive(serde::Serialize)]
+struct GatewayExposureReport {
+    mode: String,
+    bind: String,
+    host: String,
+    port: u16,
+    reachable: bool,
+    warnings: Vec<String>,
+    firewall: FirewallStatus,
 }
 
-/// Poll WSL Ubuntu until it responds, with a configurable timeout.
-/// Used after installing WSL or before running commands that need WSL to be ready.
-#[cfg(target_os = "windows")]
-fn wait_for_wsl_ready(timeout_secs: u64) -> Result<(), String> {
-    let start = std::time::Instant::now();
-    let timeout = Duration::from_secs(timeout_secs);
-    while start.elapsed() < timeout {
-        let output = Command::new("wsl")
-            .args(["-d", "Ubuntu", "-u", "root", "--", "echo", "ready"])
-            .output();
-        if let Ok(o) = output {
-            if o.status.success() {
-                let stdout = String::from_utf8_lossy(&o.stdout);
-                if stdout.trim() == "ready" {
-                    return Ok(());
-                }
-            }
-        }
-        thread::sleep(Duration::from_secs(3));
+/// Reads a file relative to the OpenClaw root on the machine running openclaw (WSL on
+/// Windows).
+fn read_local_openclaw_file(relative: &str) -> Result<String, String> {
+    let path = paths::local_path(relative)?;
+
+    #[cfg(target_os = "windows")]
+    {
+        wsl_read_file(&path)
     }
-    Err(format!(
-        "WSL Ubuntu not ready after {} seconds",
-        timeout_secs
-    ))
-}
 
-#[cfg(target_os = "windows")]
-fn ensure_wsl2_installed() -> Result<(), String> {
-    // Check if WSL2 is already installed
-    if check_wsl2_installed() {
-        return Ok(());
+    #[cfg(not(target_os = "windows"))]
+    {
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", relative, e))
     }
+}
 
-    // Install WSL2 using elevated PowerShell (triggers UAC admin prompt)
-    // Start-Process -Verb RunAs launches the command with admin privileges,
-    // showing the user a UAC confirmation dialog they can click to approve.
-    // -Wait ensures we block until the elevated process completes.
-    let output = Command::new("powershell")
-        .args([
-            "-Command",
-            "Start-Process -FilePath 'wsl.exe' -ArgumentList '--install --distribution Ubuntu' -Verb RunAs -Wait"
-        ])
-        .output()
-        .map_err(|e| format!("Failed to execute WSL2 installation: {}", e))?;
+fn write_local_openclaw_file(relative: &str, content: &str) -> Result<(), String> {
+    let _config = (relative == "openclaw.json").then(lifecycle::config_lock);
+    store_local_openclaw_file(relative, content)
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        // Check if user declined the UAC prompt
-        if stderr.contains("canceled")
-            || stderr.contains("denied")
-            || stderr.contains("not have permission")
-        {
-            return Err("WSL2 installation requires administrator approval. Please click 'Yes' on the admin dialog when prompted.".to_string());
-        }
-        return Err(format!(
-            "WSL2 installation failed. Please ensure virtualization is enabled in BIOS. Error: {}",
-            stderr
-        ));
+/// `write_local_openclaw_file` for callers already holding `lifecycle::config_lock`.
+fn store_local_openclaw_file(relative: &str, content: &str) -> Result<(), String> {
+    let _write = lifecycle::write_guard();
+    let path = paths::local_path(relative)?;
+    if relative == "openclaw.json" {
+        remember_config_content(content);
     }
 
-    // Wait for WSL Ubuntu to become responsive (first-time init can be slow)
-    wait_for_wsl_ready(90).map_err(|e| {
-        format!("WSL2 was installed but Ubuntu is not responding. You may need to restart your computer. Error: {}", e)
-    })?;
+    #[cfg(target_os = "windows")]
+    {
+        wsl_write_file(&path, content)
+    }
 
-    // Verify WSL2 is now available
-    if !check_wsl2_installed() {
-        return Err("WSL2 was installed but may require a system restart. Please restart your computer and run this setup again.".to_string());
+    #[cfg(not(target_os = "windows"))]
+    {
+        fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", relative, e))
     }
+}
 
-    // Configure Ubuntu with a default user non-interactively.
-    // Without this, Ubuntu prompts for username/password on first launch.
+fn read_local_openclaw_config() -> Result<serde_json::Value, String> {
+    let config_str = read_local_openclaw_file("openclaw.json")?;
+    serde_json::from_str(&config_str).map_err(|e| format!("Failed to parse openclaw.json: {}", e))
+}
 
-    // Create a non-root user 'openclaw' with a password, for general use
-    let user_setup = Command::new("wsl")
-        .args(["-d", "Ubuntu", "-u", "root", "--", "/bin/bash", "-c",
-            "id openclaw >/dev/null 2>&1 || (useradd -m -s /bin/bash openclaw && echo 'openclaw:openclaw' | chpasswd && usermod -aG sudo openclaw)"
-        ])
-        .output()
-        .map_err(|e| format!("Failed to create openclaw user: {}", e))?;
+/// Writes the whole openclaw.json; the caller holds `lifecycle::config_lock`.
+fn store_local_openclaw_config(config: &serde_json::Value) -> Result<(), String> {
+    let raw = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    store_local_openclaw_file("openclaw.json", &raw)
+}
 
-    if !user_setup.status.success() {
-        let stderr = String::from_utf8_lossy(&user_setup.stderr);
-        eprintln!(
-            "Warning: user setup returned error (may be harmless if user exists): {}",
-            stderr
-        );
-    }
+/// Reads openclaw.json, lets `change` edit it and writes it back if anything changed, all
+/// under `lifecycle::config_lock` so concurrent updates cannot overwrite each other. A missing
+/// or unreadable config fails; `update_local_openclaw_config_or_default` starts from `{}`.
+fn update_local_openclaw_config<T>(
+    change: impl FnOnce(&mut serde_json::Value) -> Result<T, String>,
+) -> Result<T, String> {
+    let _config = lifecycle::config_lock();
+    let config = read_local_openclaw_config()?;
+    change_local_openclaw_config(config, change)
+}
 
-    // Write /etc/wsl.conf to set default user (more reliable than `ubuntu config --default-user`)
-    let wsl_conf = Command::new("wsl")
-        .args([
-            "-d",
-            "Ubuntu",
-            "-u",
-            "root",
-            "--",
-            "/bin/bash",
-            "-c",
-            "printf '[user]\\ndefault=openclaw\\n' > /etc/wsl.conf",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to write /etc/wsl.conf: {}", e))?;
+fn update_local_openclaw_config_or_default<T>(
+    change: impl FnOnce(&mut serde_json::Value) -> Result<T, String>,
+) -> Result<T, String> {
+    let _config = lifecycle::config_lock();
+    let config = read_local_openclaw_config().unwrap_or_else(|_| serde_json::json!({}));
+    change_local_openclaw_config(config, change)
+}
 
-    if !wsl_conf.status.success() {
-        let stderr = String::from_utf8_lossy(&wsl_conf.stderr);
-        eprintln!("Warning: failed to write wsl.conf: {}", stderr);
+fn change_local_openclaw_config<T>(
+    mut config: serde_json::Value,
+    change: impl FnOnce(&mut serde_json::Value) -> Result<T, String>,
+) -> Result<T, String> {
+    let before = config.clone();
+    let result = change(&mut config)?;
+    if config != before {
+        store_local_openclaw_config(&config)?;
     }
+    Ok(result)
+}
 
-    // Terminate Ubuntu so wsl.conf takes effect on next launch
-    let _ = Command::new("wsl").args(["--terminate", "Ubuntu"]).output();
-
+/// Stop → bootstrap → start, mirroring `restart_openclaw_gateway` for synchronous callers.
+fn restart_local_gateway() -> Result<(), String> {
+    let _ = shell_command("openclaw gateway stop");
     thread::sleep(Duration::from_secs(2));
+    let _ = sync_gateway_env_to_service(&Default::default());
 
-    // Wait for Ubuntu to come back with the new default user
-    wait_for_wsl_ready(30).map_err(|e| {
-        format!(
-            "WSL Ubuntu failed to restart after user configuration: {}",
-            e
-        )
-    })?;
+    #[cfg(target_os = "macos")]
+    if let Some(home) = dirs::home_dir() {
+        let plist = home.join("Library/LaunchAgents/ai.openclaw.gateway.plist");
+        if plist.exists() {
+            let _ = shell_command(&format!(
+                "launchctl bootstrap gui/$(id -u) \"{}\"",
+                plist.to_string_lossy()
+            ));
+        }
+    }
 
-    Ok(())
+    run_gateway_cli("openclaw gateway start", read_local_gateway_port())
+        .map(|_| ())
+        .map_err(|failure| format!("Gateway restart failed: {}", failure))
 }
 
-/// Run a command as root inside WSL (for apt-get, system setup, etc.)
-/// This avoids the sudo password prompt by using `wsl -u root` directly.
-#[cfg(target_os = "windows")]
-fn wsl_root_command(cmd: &str) -> Result<String, String> {
-    let output = Command::new("wsl")
-        .args([
-            "-d",
-            "Ubuntu",
-            "--user",
-            "root",
-            "--",
-            "/bin/bash",
-            "-c",
-            cmd,
-        ])
-        .output()
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
+const RESTART_VERIFY_ATTEMPTS: u32 = 15;
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct GatewayRestartVerification {
+    /// The running gateway loaded the config currently on disk.
+    config_matches: bool,
+    disk_hash: String,
+    running_hash: Option<String>,
+    detail: String,
+}
 
-    if output.status.success() {
-        Ok(stdout)
-    } else {
-        Err(format!("{}\n{}", stdout, stderr))
+/// Compares the gateway's `config.get` snapshot with the file on disk: by hash when the
+/// gateway reports one, otherwise by the parsed config. `None` when neither is present.
+fn running_config_matches(
+    snapshot: &serde_json::Value,
+    disk_raw: &str,
+    disk_hash: &str,
+) -> Option<bool> {
+    let hash_matches = snapshot
+        .get("hash")
+        .and_then(|v| v.as_str())
+        .map(|hash| hash.eq_ignore_ascii_case(disk_hash));
+    let running_config = snapshot.get("config").or_else(|| snapshot.get("parsed"));
+    let config_matches = running_config.map(|config| {
+        serde_json::from_str::<serde_json::Value>(disk_raw)
+            .map(|disk| &disk == config)
+            .unwrap_or(false)
+    });
+    match (hash_matches, config_matches) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (None, None) => None,
+        _ => Some(false),
     }
 }
 
-// --- WSL filesystem helpers (Windows only) ---
-// On Windows, openclaw runs inside WSL but Tauri runs natively.
-// dirs::home_dir() returns C:\Users\... but we need /home/user/... inside WSL.
+/// Restarts the local gateway and confirms through its API that it is running the config
+/// that is on disk, so edits that did not take effect are reported instead of ignored.
+#[command]
+async fn restart_gateway_and_verify(
+    app: tauri::AppHandle,
+) -> Result<GatewayRestartVerification, String> {
+    let disk_raw = read_local_openclaw_file("openclaw.json")?;
+    let disk_hash = format!("{:x}", Sha256::digest(disk_raw.as_bytes()));
+
+    restart_local_gateway()?;
+
+    let client = gateway_client::GatewayClient::local();
+    let mut snapshot = None;
+    let mut last_error = "Gateway did not come back after the restart".to_string();
+    for _ in 0..RESTART_VERIFY_ATTEMPTS {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        if !client.is_reachable().await {
+            continue;
+        }
+        match client.config_snapshot().await {
+            Ok(value) => {
+                snapshot = Some(value);
+                break;
+            }
+            Err(e) => last_error = e.message(),
+        }
+    }
+    let snapshot = snapshot.ok_or(last_error)?;
+    notify_gateway_restarted(&app);
 
-#[cfg(target_os = "windows")]
-fn wsl_home_dir() -> Result<String, String> {
-    shell_command("echo $HOME").map(|s| s.trim().to_string())
+    let running_hash = snapshot
+        .get("hash")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let (config_matches, detail) = match running_config_matches(&snapshot, &disk_raw, &disk_hash) {
+        Some(true) => (
+            true,
+            "The gateway is running the config on disk.".to_string(),
+        ),
+        Some(false) => (
+            false,
+            "The gateway restarted but is not running the config on disk. Check \
+             `openclaw config validate` and the gateway logs."
+                .to_string(),
+        ),
+        None => (
+            false,
+            "The gateway restarted but did not report which config it loaded.".to_string(),
+        ),
+    };
+    Ok(GatewayRestartVerification {
+        config_matches,
+        disk_hash,
+        running_hash,
+        detail,
+    })
 }
 
-#[cfg(target_os = "windows")]
-fn wsl_write_file(path: &str, content: &str) -> Result<(), String> {
-    // Escape single quotes for safe shell embedding: ' -> '\''
-    let escaped = content.replace('\'', "'\\''");
-    let cmd = format!("printf '%s' '{}' > \"{}\"", escaped, path);
-    shell_command(&cmd)?;
-    Ok(())
-}
-
-#[cfg(target_os = "windows")]
-fn wsl_read_file(path: &str) -> Result<String, String> {
-    shell_command(&format!("cat \"{}\" 2>/dev/null", path))
+fn detect_lan_ipv4() -> Option<String> {
+    // Connecting a UDP socket only selects the outbound interface; nothing is sent.
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    if ip.is_loopback() || ip.is_unspecified() {
+        None
+    } else {
+        Some(ip.to_string())
+    }
 }
 
-#[cfg(target_os = "windows")]
-fn wsl_mkdir_p(path: &str) -> Result<(), String> {
-    shell_command(&format!("mkdir -p \"{}\"", path))?;
-    Ok(())
+fn gateway_bind_for_exposure(mode: &str) -> Result<(&'static str, Option<&'static str>), String> {
+    match mode {
+        "loopback" => Ok(("loopback", None)),
+        "lan" => Ok(("lan", None)),
+        "all" => Ok(("custom", Some("0.0.0.0"))),
+        _ => Err(format!(
+            "Unsupported gateway exposure mode: {}. Use loopback, lan, or all.",
+            mode
+        )),
+    }
 }
 
-#[cfg(target_os = "windows")]
-fn wsl_list_dirs(base_path: &str) -> Vec<String> {
-    let mut dirs_found = Vec::new();
-    if let Ok(output) = shell_command(&format!("ls -1 -F \"{}\" 2>/dev/null", base_path)) {
-        for line in output.lines() {
-            if line.trim().ends_with('/') {
-                dirs_found.push(line.trim().trim_matches('/').to_string());
+fn apply_gateway_exposure(config: &mut serde_json::Value, bind: &str, custom_host: Option<&str>) {
+    if let Some(obj) = config.as_object_mut() {
+        let gateway_entry = obj
+            .entry("gateway".to_string())
+            .or_insert(serde_json::json!({}));
+        if let Some(g) = gateway_entry.as_object_mut() {
+            g.insert("bind".to_string(), serde_json::json!(bind));
+            match custom_host {
+                Some(host) => {
+                    g.insert("customBindHost".to_string(), serde_json::json!(host));
+                }
+                None => {
+                    g.remove("customBindHost");
+                }
             }
         }
     }
-    dirs_found
 }
 
-#[cfg(target_os = "windows")]
-fn wsl_remove_dir(path: &str) -> Result<(), String> {
-    let cmd = if path.starts_with("~/") {
-        format!("rm -rf \"$HOME/{}\"", &path[2..])
-    } else {
-        format!("rm -rf \"{}\"", path)
-    };
-    shell_command(&cmd)?;
-    Ok(())
+fn gateway_exposure_warnings(mode: &str, auth_mode: &str, token: Option<&str>) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if mode == "loopback" {
+        return warnings;
+    }
+
+    if auth_mode == "none" || auth_mode == "off" {
+        warnings.push(
+            "Gateway auth is disabled while the gateway is reachable from other machines. Switch the auth mode to token before exposing it."
+                .to_string(),
+        );
+    } else if auth_mode == "token" && token.map(|t| t.trim().len() < 24).unwrap_or(true) {
+        warnings.push(
+            "The gateway token is missing or shorter than 24 characters. Regenerate a longer token before exposing the gateway."
+                .to_string(),
+        );
+    }
+
+    if mode == "all" {
+        warnings.push(
+            "All-interfaces mode also listens on VPN and public interfaces. Prefer LAN mode unless remote devices need it."
+                .to_string(),
+        );
+    }
+
+    warnings
 }
 
-fn shell_command(cmd: &str) -> Result<String, String> {
-    #[cfg(target_os = "macos")]
-    let (shell, args) = ("/bin/zsh", vec!["-l", "-c"]);
+#[cfg(any(target_os = "macos", test))]
+fn parse_macos_firewall_state(output: &str) -> Option<bool> {
+    let lower = output.to_lowercase();
+    if lower.contains("disabled") || lower.contains("state = 0") {
+        Some(false)
+    } else if lower.contains("enabled") || lower.contains("state = 1") {
+        Some(true)
+    } else {
+        None
+    }
+}
 
-    #[cfg(target_os = "windows")]
-    let (shell, args) = ("wsl", vec!["--", "/bin/bash", "-c"]);
+#[cfg(any(all(unix, not(target_os = "macos")), test))]
+fn parse_ufw_state(output: &str) -> Option<bool> {
+    output.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("Status:")
+            .map(|status| status.trim() == "active")
+    })
+}
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    let (shell, args) = ("sh", vec!["-c"]);
+#[cfg(any(target_os = "windows", test))]
+fn parse_netsh_firewall_state(output: &str) -> Option<bool> {
+    let states = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("State"))
+        .filter_map(|line| line.split_whitespace().last())
+        .map(|value| value.eq_ignore_ascii_case("on"))
+        .collect::<Vec<_>>();
+    if states.is_empty() {
+        None
+    } else {
+        Some(states.iter().any(|enabled| *enabled))
+    }
+}
 
-    let output = Command::new(shell)
-        .args(&args)
-        .arg(cmd)
+#[cfg(target_os = "macos")]
+fn detect_firewall_status(port: u16) -> FirewallStatus {
+    let enabled = Command::new("/usr/libexec/ApplicationFirewall/socketfilterfw")
+        .arg("--getglobalstate")
         .output()
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
+        .ok()
+        .and_then(|o| parse_macos_firewall_state(&String::from_utf8_lossy(&o.stdout)));
+    let guidance = match enabled {
+        Some(true) => format!(
+            "The macOS Application Firewall is on. Allow incoming connections for node in System Settings → Network → Firewall → Options so other devices can reach port {}.",
+            port
+        ),
+        Some(false) => "The macOS Application Firewall is off; no firewall changes are needed.".to_string(),
+        None => "Could not read the macOS firewall state. If other devices cannot connect, check System Settings → Network → Firewall.".to_string(),
+    };
+    FirewallStatus {
+        detected: enabled.is_some(),
+        enabled,
+        guidance,
+    }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+#[cfg(all(unix, not(target_os = "macos")))]
+fn detect_firewall_status(port: u16) -> FirewallStatus {
+    if let Some(enabled) = shell_command("ufw status 2>/dev/null")
+        .ok()
+        .and_then(|output| parse_ufw_state(&output))
+    {
+        let guidance = if enabled {
+            format!(
+                "ufw is active. Run `sudo ufw allow {}/tcp` to let other devices reach the gateway.",
+                port
+            )
+        } else {
+            "ufw is inactive; no firewall changes are needed.".to_string()
+        };
+        return FirewallStatus {
+            detected: true,
+            enabled: Some(enabled),
+            guidance,
+        };
+    }
 
-    if output.status.success() {
-        Ok(stdout)
-    } else {
-        // If stderr is populated, return it.
-        if !stderr.is_empty() {
-            Err(stderr)
-        } else if !stdout.is_empty() {
-            Err(stdout) // sometimes error messages are in stdout
+    if let Ok(state) = shell_command("firewall-cmd --state 2>/dev/null") {
+        let enabled = state.trim() == "running";
+        let guidance = if enabled {
+            format!(
+                "firewalld is running. Run `sudo firewall-cmd --add-port={}/tcp --permanent && sudo firewall-cmd --reload` to let other devices reach the gateway.",
+                port
+            )
         } else {
-            Err(format!(
-                "Command failed with exit code: {}",
-                output.status.code().unwrap_or(-1)
-            ))
+            "firewalld is not running; no firewall changes are needed.".to_string()
+        };
+        return FirewallStatus {
+            detected: true,
+            enabled: Some(enabled),
+            guidance,
+        };
+    }
+
+    FirewallStatus {
+        detected: false,
+        enabled: None,
+        guidance: format!(
+            "No supported firewall (ufw, firewalld) was detected. If other devices cannot connect, make sure TCP port {} is allowed.",
+            port
+        ),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn detect_firewall_status(port: u16) -> FirewallStatus {
+    let enabled = Command::new("netsh")
+        .args(["advfirewall", "show", "allprofiles", "state"])
+        .output()
+        .ok()
+        .and_then(|o| parse_netsh_firewall_state(&String::from_utf8_lossy(&o.stdout)));
+    let guidance = match enabled {
+        Some(true) => format!(
+            "Windows Defender Firewall is on. From an elevated prompt run `netsh advfirewall firewall add rule name=\"OpenClaw Gateway\" dir=in action=allow protocol=TCP localport={}`. WSL2 may also need port forwarding to reach the gateway.",
+            port
+        ),
+        Some(false) => "Windows Defender Firewall is off; no firewall changes are needed.".to_string(),
+        None => "Could not read the Windows firewall state.".to_string(),
+    };
+    FirewallStatus {
+        detected: enabled.is_some(),
+        enabled,
+        guidance,
+    }
+}
+
+fn wait_for_gateway_port(host: &str, port: u16, attempts: u32) -> bool {
+    let Ok(addr) = format!("{}:{}", host, port).parse::<std::net::SocketAddr>() else {
+        return false;
+    };
+    for attempt in 0..attempts {
+        if attempt > 0 {
+            thread::sleep(Duration::from_secs(2));
+        }
+        if TcpStream::connect_timeout(&addr, Duration::from_secs(2)).is_ok() {
+            return true;
         }
     }
+    false
 }
 
 #[command]
-fn check_pairing_status(remote: Option<RemoteInfo>) -> Result<bool, String> {
-    if let Some(r) = remote {
-        let sess = connect_ssh(&r)?;
-        let os_type = execute_ssh(&sess, "uname -s")?.trim().to_string();
-        let prefix = get_env_prefix(&os_type);
+fn set_gateway_exposure(
+    app: tauri::AppHandle,
+    mode: String,
+) -> Result<GatewayExposureReport, String> {
+    let (bind, custom_host) = gateway_bind_for_exposure(&mode)?;
+
+    let (port, auth_mode, token) = update_local_openclaw_config(|config| {
+        let gateway = config
+            .get("gateway")
+            .cloned()
+            .unwrap_or(serde_json::json!({}));
+        let port = gateway
+            .get("port")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(18789) as u16;
+        let auth_mode = gateway
+            .get("auth")
+            .and_then(|a| a.get("mode"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("token")
+            .to_string();
+        let token = gateway
+            .get("auth")
+            .and_then(|a| a.get("token"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
 
-        if let Some(policy) = read_telegram_dm_policy_via_cli(|cmd| execute_ssh(&sess, &format!("{}{}", prefix, cmd)))? {
-            if telegram_pairing_status_from_dm_policy(&policy) {
-                return Ok(true);
-            }
-        }
+        apply_gateway_exposure(config, bind, custom_host);
+        Ok((port, auth_mode, token))
+    })?;
+    restart_local_gateway()?;
 
-        if let Some(policy) =
-            read_telegram_dm_policy_from_config_str(&execute_ssh(&sess, "cat ~/.openclaw/openclaw.json")?)
-        {
-            if telegram_pairing_status_from_dm_policy(&policy) {
-                return Ok(true);
-            }
-        }
+    let mut warnings = gateway_exposure_warnings(&mode, &auth_mode, token.as_deref());
+    let host = if mode == "loopback" {
+        "127.0.0.1".to_string()
+    } else {
+        detect_lan_ipv4().unwrap_or_else(|| {
+            warnings.push(
+                "Could not detect a LAN address; reachability was only verified on 127.0.0.1."
+                    .to_string(),
+            );
+            "127.0.0.1".to_string()
+        })
+    };
 
-        return telegram_allow_from_is_linked_remote(&sess);
+    let reachable = wait_for_gateway_port(&host, port, 8);
+    if reachable {
+        notify_gateway_restarted(&app);
     }
-
-    if let Some(policy) = read_telegram_dm_policy_via_cli(shell_command)? {
-        if telegram_pairing_status_from_dm_policy(&policy) {
-            return Ok(true);
-        }
+    if !reachable {
+        warnings.push(format!(
+            "The gateway did not answer on {}:{} after restart. Check 'openclaw gateway status' and the firewall guidance.",
+            host, port
+        ));
     }
 
-    let home_dir = dirs::home_dir().ok_or("Could not determine local home directory.")?;
-    let config_path = home_dir.join(".openclaw").join("openclaw.json");
-    if let Ok(config_str) = fs::read_to_string(&config_path) {
-        if let Some(policy) = read_telegram_dm_policy_from_config_str(&config_str) {
-            if telegram_pairing_status_from_dm_policy(&policy) {
-                return Ok(true);
-            }
+    let firewall = if mode == "loopback" {
+        FirewallStatus {
+            detected: false,
+            enabled: None,
+            guidance:
+                "Loopback mode is only reachable from this machine; no firewall changes are needed."
+                    .to_string(),
         }
-    }
+    } else {
+        detect_firewall_status(port)
+    };
 
-    let credentials_dir = home_dir.join(".openclaw").join("credentials");
-    Ok(telegram_allow_from_is_linked_local(&credentials_dir))
+    Ok(GatewayExposureReport {
+        mode,
+        bind: bind.to_string(),
+        host,
+        port,
+        reachable,
+        warnings,
+        firewall,
+    })
 }
 
-fn telegram_pairing_status_from_dm_policy(policy: &str) -> bool {
-    policy.trim().trim_matches('"') != "pairing"
+// Dashboard proxy
+//
+// The dashboard normally receives the gateway token through the URL, which leaks it into
+// browser history. The proxy listens on an ephemeral loopback port and injects the token
+// as an Authorization header on every upstream request instead. Since that makes every
+// accepted request fully authenticated, the proxy only accepts requests addressed to itself
+// by name (no DNS rebinding) that carry the per-launch secret cookie, which the dashboard
+// window obtains by opening the login URL. Gateway port and token are read per connection
+// so a regenerated token or a profile switch takes effect immediately.
+
+const DASHBOARD_PROXY_COOKIE: &str = "clawnetes_dashboard";
+const DASHBOARD_PROXY_LOGIN_PATH: &str = "/__clawnetes/login";
+
+struct DashboardProxyHandle {
+    port: u16,
+    secret: String,
+    running: Arc<AtomicBool>,
 }
 
-fn parse_config_string_output(output: &str) -> Option<String> {
-    let value = output.trim().trim_matches('"');
-    if value.is_empty() || value == "null" || value == "undefined" {
-        None
-    } else {
-        Some(value.to_string())
+impl DashboardProxyHandle {
+    /// Opening this sets the secret cookie and redirects to the dashboard.
+    fn login_url(&self) -> String {
+        format!(
+            "http://127.0.0.1:{}{}?key={}",
+            self.port, DASHBOARD_PROXY_LOGIN_PATH, self.secret
+        )
     }
 }
 
-fn extract_telegram_dm_policy_from_config(config: &serde_json::Value) -> Option<String> {
-    config
-        .get("channels")
-        .and_then(|c| c.get("telegram"))
-        .and_then(|t| t.get("accounts"))
-        .and_then(|a| a.get("default"))
-        .and_then(|m| m.get("dmPolicy"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
-        .or_else(|| {
-            config
-                .get("channels")
-                .and_then(|c| c.get("telegram"))
-                .and_then(|t| t.get("dmPolicy"))
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-        })
+#[derive(Debug, PartialEq)]
+enum DashboardProxyDecision {
+    Forward,
+    /// The login URL with the right secret: answer with the cookie instead of forwarding.
+    Login,
+    Reject(&'static str),
 }
 
-fn read_telegram_dm_policy_from_config_str(config_str: &str) -> Option<String> {
-    serde_json::from_str::<serde_json::Value>(config_str)
-        .ok()
-        .and_then(|config| extract_telegram_dm_policy_from_config(&config))
+fn http_request_header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.split("\r\n").skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
 }
 
-fn read_telegram_dm_policy_via_cli<F>(mut run_command: F) -> Result<Option<String>, String>
-where
-    F: FnMut(&str) -> Result<String, String>,
-{
-    for cmd in [
-        "openclaw config get channels.telegram.accounts.default.dmPolicy",
-        "openclaw config get channels.telegram.dmPolicy",
-    ] {
-        match run_command(cmd) {
-            Ok(output) => {
-                if let Some(policy) = parse_config_string_output(&output) {
-                    return Ok(Some(policy));
-                }
-            }
-            Err(_) => continue,
+/// Decides whether a request reached the proxy legitimately: addressed to
+/// `127.0.0.1:<proxy_port>` (from a page of that origin, if any) and carrying `secret`.
+fn check_dashboard_request(head: &str, proxy_port: u16, secret: &str) -> DashboardProxyDecision {
+    let authority = format!("127.0.0.1:{}", proxy_port);
+    if http_request_header(head, "host") != Some(authority.as_str()) {
+        return DashboardProxyDecision::Reject("unexpected Host header");
+    }
+    if let Some(origin) = http_request_header(head, "origin") {
+        if origin != format!("http://{}", authority) {
+            return DashboardProxyDecision::Reject("cross-origin request");
         }
     }
+    let target = head.split(' ').nth(1).unwrap_or_default();
+    if target == format!("{}?key={}", DASHBOARD_PROXY_LOGIN_PATH, secret) {
+        return DashboardProxyDecision::Login;
+    }
+    let has_session = http_request_header(head, "cookie").is_some_and(|cookies| {
+        cookies
+            .split(';')
+            .filter_map(|pair| pair.trim().split_once('='))
+            .any(|(name, value)| name == DASHBOARD_PROXY_COOKIE && value == secret)
+    });
+    if has_session {
+        DashboardProxyDecision::Forward
+    } else {
+        DashboardProxyDecision::Reject("missing dashboard session")
+    }
+}
 
-    Ok(None)
+fn read_http_request_head(stream: &mut TcpStream) -> std::io::Result<(String, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut chunk)?;
+        if n == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "connection closed before request head",
+            ));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let rest = buf.split_off(pos + 4);
+            return Ok((String::from_utf8_lossy(&buf).to_string(), rest));
+        }
+        if buf.len() > 64 * 1024 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "request head too large",
+            ));
+        }
+    }
 }
 
-fn telegram_allow_from_entries_from_str(content: &str) -> Option<usize> {
-    serde_json::from_str::<serde_json::Value>(content)
-        .ok()
-        .and_then(|json| json.get("allowFrom").and_then(|value| value.as_array()).map(|items| items.len()))
+/// Rewrites a proxied request head: points Host at the gateway, replaces any
+/// Authorization header with the gateway token, drops the proxy's session cookie and, for
+/// plain HTTP requests, forces `Connection: close` so every request on a connection passes
+/// through this rewrite.
+fn rewrite_dashboard_request_head(head: &str, token: &str, upstream_port: u16) -> (String, bool) {
+    let mut lines = head.split("\r\n").filter(|line| !line.is_empty());
+    let request_line = lines.next().unwrap_or_default().to_string();
+    let mut headers = Vec::new();
+    let mut is_upgrade = false;
+
+    for line in lines {
+        let name = line
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match name.as_str() {
+            "host" | "authorization" => continue,
+            "cookie" => {
+                let value = line.split_once(':').map(|(_, v)| v).unwrap_or_default();
+                let kept: Vec<&str> = value
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|pair| {
+                        !pair.is_empty()
+                            && !pair.starts_with(&format!("{}=", DASHBOARD_PROXY_COOKIE))
+                    })
+                    .collect();
+                if !kept.is_empty() {
+                    headers.push(format!("Cookie: {}", kept.join("; ")));
+                }
+            }
+            "upgrade" => {
+                is_upgrade = true;
+                headers.push(line.to_string());
+            }
+            "connection" => {
+                if line.to_ascii_lowercase().contains("upgrade") {
+                    is_upgrade = true;
+                    headers.push(line.to_string());
+                }
+            }
+            _ => headers.push(line.to_string()),
+        }
+    }
+
+    let mut rewritten = format!("{}\r\nHost: 127.0.0.1:{}\r\n", request_line, upstream_port);
+    rewritten.push_str(&format!("Authorization: Bearer {}\r\n", token));
+    for header in headers {
+        rewritten.push_str(&header);
+        rewritten.push_str("\r\n");
+    }
+    if !is_upgrade {
+        rewritten.push_str("Connection: close\r\n");
+    }
+    rewritten.push_str("\r\n");
+    (rewritten, is_upgrade)
 }
 
-fn is_telegram_allow_from_filename(name: &str) -> bool {
-    name.starts_with("telegram") && name.ends_with("-allowFrom.json")
+fn write_dashboard_proxy_response(
+    client: &mut TcpStream,
+    status: &str,
+    headers: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    client.write_all(
+        format!(
+            "HTTP/1.1 {}\r\n{}Content-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            headers,
+            body.len(),
+            body
+        )
+        .as_bytes(),
+    )
 }
 
-fn telegram_allow_from_is_linked_local(credentials_dir: &Path) -> bool {
-    let Ok(entries) = fs::read_dir(credentials_dir) else {
-        return false;
+fn proxy_dashboard_connection(
+    mut client: TcpStream,
+    proxy_port: u16,
+    secret: &str,
+) -> std::io::Result<()> {
+    let (head, body_start) = read_http_request_head(&mut client)?;
+    match check_dashboard_request(&head, proxy_port, secret) {
+        DashboardProxyDecision::Forward => {}
+        DashboardProxyDecision::Login => {
+            let cookie = format!(
+                "Location: /\r\nSet-Cookie: {}={}; Path=/; HttpOnly; SameSite=Strict\r\n",
+                DASHBOARD_PROXY_COOKIE, secret
+            );
+            return write_dashboard_proxy_response(&mut client, "302 Found", &cookie, "");
+        }
+        DashboardProxyDecision::Reject(reason) => {
+            return write_dashboard_proxy_response(&mut client, "403 Forbidden", "", reason);
+        }
+    }
+    let (upstream_port, token) = match read_local_gateway_port_and_token() {
+        Ok(gateway) => gateway,
+        Err(e) => {
+            return write_dashboard_proxy_response(&mut client, "502 Bad Gateway", "", &e);
+        }
     };
+    let (rewritten, _) = rewrite_dashboard_request_head(&head, &token, upstream_port);
 
-    entries.flatten().any(|entry| {
-        let path = entry.path();
-        let Some(name) = path.file_name().and_then(|value| value.to_str()) else {
-            return false;
-        };
-        if !path.is_file() || !is_telegram_allow_from_filename(name) {
-            return false;
-        }
+    let mut upstream = TcpStream::connect(("127.0.0.1", upstream_port))?;
+    upstream.write_all(rewritten.as_bytes())?;
+    upstream.write_all(&body_start)?;
 
-        fs::read_to_string(&path)
-            .ok()
-            .and_then(|content| telegram_allow_from_entries_from_str(&content))
-            .map(|count| count > 0)
-            .unwrap_or(false)
-    })
+    let mut client_reader = client.try_clone()?;
+    let mut upstream_writer = upstream.try_clone()?;
+    let uploader = thread::spawn(move || {
+        let _ = std::io::copy(&mut client_reader, &mut upstream_writer);
+        let _ = upstream_writer.shutdown(std::net::Shutdown::Write);
+    });
+
+    let _ = std::io::copy(&mut upstream, &mut client);
+    let _ = client.shutdown(std::net::Shutdown::Both);
+    let _ = uploader.join();
+    Ok(())
 }
 
-fn telegram_allow_from_is_linked_remote(sess: &Session) -> Result<bool, String> {
-    let files = execute_ssh(
-        sess,
-        "find \"$HOME/.openclaw/credentials\" -maxdepth 1 -type f -name 'telegram*-allowFrom.json' 2>/dev/null",
-    )?;
+fn read_local_gateway_port_and_token() -> Result<(u16, String), String> {
+    let config = read_local_openclaw_config()?;
+    let port = config
+        .get("gateway")
+        .and_then(|g| g.get("port"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(18789) as u16;
+    let token = extract_gateway_token_from_config(&config.to_string(), "config")?;
+    Ok((port, token))
+}
 
-    for file in files.lines().map(str::trim).filter(|line| !line.is_empty()) {
-        let content = execute_ssh(sess, &format!("cat {}", shell_single_quote(file)))?;
-        if telegram_allow_from_entries_from_str(&content).unwrap_or(0) > 0 {
-            return Ok(true);
+/// Starts the proxy unless it is running and returns its login URL.
+fn ensure_dashboard_proxy() -> Result<String, String> {
+    let mut guard = DASHBOARD_PROXY
+        .lock()
+        .map_err(|_| "Dashboard proxy state is poisoned".to_string())?;
+    if let Some(handle) = guard.as_ref() {
+        if handle.running.load(Ordering::Relaxed) {
+            return Ok(handle.login_url());
         }
     }
 
-    Ok(false)
+    // Fail early, before a window opens on a proxy that cannot reach anything.
+    read_local_gateway_port_and_token()?;
+    let secret: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| format!("Failed to bind dashboard proxy: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read dashboard proxy address: {}", e))?
+        .port();
+    let _ = listener.set_nonblocking(true);
+
+    let running = Arc::new(AtomicBool::new(true));
+    let thread_running = running.clone();
+    let thread_secret = secret.clone();
+    thread::spawn(move || {
+        while thread_running.load(Ordering::Relaxed) && !lifecycle::is_stopping() {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let _ = stream.set_nonblocking(false);
+                    let secret = thread_secret.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = proxy_dashboard_connection(stream, port, &secret) {
+                            eprintln!("Dashboard proxy connection failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                Err(_) => break,
+            }
+        }
+        thread_running.store(false, Ordering::Relaxed);
+    });
+
+    let handle = DashboardProxyHandle {
+        port,
+        secret,
+        running,
+    };
+    let url = handle.login_url();
+    *guard = Some(handle);
+    Ok(url)
 }
 
-fn whatsapp_session_is_linked(session_dir: &Path) -> bool {
-    if !session_dir.exists() {
-        return false;
+#[command]
+fn start_dashboard_proxy() -> Result<String, String> {
+    ensure_dashboard_proxy()
+}
+
+#[command]
+fn stop_dashboard_proxy() -> Result<(), String> {
+    let mut guard = DASHBOARD_PROXY
+        .lock()
+        .map_err(|_| "Dashboard proxy state is poisoned".to_string())?;
+    if let Some(handle) = guard.take() {
+        handle.running.store(false, Ordering::Relaxed);
     }
+    Ok(())
+}
 
-    let mut stack = vec![session_dir.to_path_buf()];
-    while let Some(path) = stack.pop() {
-        let Ok(entries) = fs::read_dir(&path) else {
-            continue;
-        };
-        for entry in entries.flatten() {
-            let child = entry.path();
-            if child.is_file() {
-                return true;
+// Health endpoint
+//
+// A tiny localhost HTTP server for uptime monitors: `/health` answers 200 or 503 depending on
+// whether the gateway accepts connections, `/metrics` serves the same in Prometheus text
+// format along with the sampler's CPU and memory figures. It is served by Clawnetes rather
+// than the gateway, so it works with any OpenClaw version.
+
+const DEFAULT_HEALTH_ENDPOINT_PORT: u16 = 9469;
+
+struct HealthEndpointHandle {
+    port: u16,
+    running: Arc<AtomicBool>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct HealthEndpointStatus {
+    enabled: bool,
+    port: u16,
+    url: Option<String>,
+}
+
+/// Status code, content type and body for a request to `path`.
+fn health_endpoint_response(
+    path: &str,
+    gateway_port: u16,
+    gateway_up: bool,
+    usage: (Option<f64>, Option<u64>),
+) -> (u16, &'static str, String) {
+    match path.split('?').next().unwrap_or(path) {
+        "/health" | "/" => {
+            let body = serde_json::json!({
+                "status": if gateway_up { "ok" } else { "down" },
+                "gateway": { "running": gateway_up, "port": gateway_port },
+            })
+            .to_string();
+            (if gateway_up { 200 } else { 503 }, "application/json", body)
+        }
+        "/metrics" => {
+            let mut body = format!(
+                "# HELP openclaw_gateway_up Whether the gateway accepts connections.\n\
+                 # TYPE openclaw_gateway_up gauge\n\
+                 openclaw_gateway_up {}\n",
+                u8::from(gateway_up)
+            );
+            if let Some(cpu) = usage.0 {
+                body.push_str(&format!(
+                    "# HELP openclaw_gateway_cpu_percent Gateway process CPU usage.\n\
+                     # TYPE openclaw_gateway_cpu_percent gauge\n\
+                     openclaw_gateway_cpu_percent {}\n",
+                    cpu
+                ));
             }
-            if child.is_dir() {
-                stack.push(child);
+            if let Some(rss_kb) = usage.1 {
+                body.push_str(&format!(
+                    "# HELP openclaw_gateway_memory_bytes Gateway process resident memory.\n\
+                     # TYPE openclaw_gateway_memory_bytes gauge\n\
+                     openclaw_gateway_memory_bytes {}\n",
+                    rss_kb * 1024
+                ));
             }
+            (200, "text/plain; version=0.0.4", body)
         }
+        _ => (404, "text/plain", "Not found\n".to_string()),
     }
+}
 
-    false
+fn serve_health_request(mut stream: TcpStream) -> Result<(), String> {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let mut buf = [0u8; 2048];
+    let n = stream.read(&mut buf).map_err(|e| e.to_string())?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let path = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/");
+
+    let gateway_port = read_local_gateway_port();
+    let gateway_up = TcpStream::connect_timeout(
+        &std::net::SocketAddr::from(([127, 0, 0, 1], gateway_port)),
+        Duration::from_secs(2),
+    )
+    .is_ok();
+    let (status, content_type, body) =
+        health_endpoint_response(path, gateway_port, gateway_up, metrics::latest_usage());
+    let reason = match status {
+        200 => "OK",
+        503 => "Service Unavailable",
+        _ => "Not Found",
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        body
+    );
+    stream
+        .write_all(response.as_bytes())
+        .map_err(|e| e.to_string())
 }
 
-fn check_whatsapp_link_status(remote: Option<RemoteInfo>) -> Result<bool, String> {
-    if let Some(r) = remote {
-        let sess = connect_ssh(&r)?;
-        let output = execute_ssh(
-            &sess,
-            "if [ -d \"$HOME/.openclaw/credentials/whatsapp/default\" ] && find \"$HOME/.openclaw/credentials/whatsapp/default\" -type f 2>/dev/null | grep -q .; then printf linked; else printf unlinked; fi",
-        )?;
-        Ok(output.trim() == "linked")
-    } else {
-        let home_dir = dirs::home_dir().ok_or("Could not determine local home directory.")?;
-        let session_dir = home_dir.join(".openclaw/credentials/whatsapp/default");
-        Ok(whatsapp_session_is_linked(&session_dir))
+fn stop_health_endpoint_server() {
+    if let Ok(mut guard) = HEALTH_ENDPOINT.lock() {
+        if let Some(handle) = guard.take() {
+            handle.running.store(false, Ordering::Relaxed);
+        }
     }
 }
 
-#[command]
-fn check_messaging_link_status(
-    channel: String,
-    remote: Option<RemoteInfo>,
-) -> Result<bool, String> {
-    match channel.as_str() {
-        "telegram" => check_pairing_status(remote),
-        "whatsapp" => check_whatsapp_link_status(remote),
-        "none" => Ok(true),
-        _ => Err(format!("Unsupported messaging channel: {}", channel)),
+fn start_health_endpoint_server(port: u16) -> Result<(), String> {
+    let mut guard = HEALTH_ENDPOINT
+        .lock()
+        .map_err(|_| "Health endpoint state is poisoned".to_string())?;
+    if let Some(handle) = guard.as_ref() {
+        if handle.port == port && handle.running.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        handle.running.store(false, Ordering::Relaxed);
     }
-}
 
-#[command]
-async fn get_current_config(remote: Option<RemoteInfo>) -> Result<CurrentConfig, String> {
-    // Helper to extract values from markdown
-    fn extract_md_value(content: &str, key: &str) -> String {
-        let pattern = format!("**{}:**", key);
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("Failed to bind health endpoint on port {}: {}", port, e))?;
+    let _ = listener.set_nonblocking(true);
 
-        for line in content.lines() {
-            let trimmed = line.trim();
-            // Look for the pattern **Key:** in the line
-            if let Some(pattern_pos) = trimmed.find(&pattern) {
-                // Extract everything after the pattern
-                let value_start = pattern_pos + pattern.len();
-                let value = &trimmed[value_start..];
-                return value.trim().to_string();
+    let running = Arc::new(AtomicBool::new(true));
+    let thread_running = running.clone();
+    thread::spawn(move || {
+        while thread_running.load(Ordering::Relaxed) && !lifecycle::is_stopping() {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let _ = stream.set_nonblocking(false);
+                    thread::spawn(move || {
+                        if let Err(e) = serve_health_request(stream) {
+                            eprintln!("Health endpoint request failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                Err(_) => break,
             }
         }
-        String::new()
+        thread_running.store(false, Ordering::Relaxed);
+    });
+
+    *guard = Some(HealthEndpointHandle { port, running });
+    Ok(())
+}
+
+fn health_endpoint_status(settings: &settings::AppSettings) -> HealthEndpointStatus {
+    let port = settings
+        .health_endpoint_port
+        .unwrap_or(DEFAULT_HEALTH_ENDPOINT_PORT);
+    HealthEndpointStatus {
+        enabled: settings.health_endpoint_port.is_some(),
+        port,
+        url: settings
+            .health_endpoint_port
+            .map(|p| format!("http://127.0.0.1:{}/health", p)),
     }
+}
 
-    // Establish session ONCE if remote
-    let session = if let Some(ref r) = remote {
-        Some(connect_ssh(r)?)
+#[command]
+fn get_health_endpoint(app: tauri::AppHandle) -> Result<HealthEndpointStatus, String> {
+    Ok(health_endpoint_status(&settings::load(&app_data_dir(
+        &app,
+    )?)))
+}
+
+/// Turns the endpoint on or off and remembers the choice, so it comes back on next launch.
+#[command]
+fn set_health_endpoint(
+    app: tauri::AppHandle,
+    enabled: bool,
+    port: Option<u16>,
+) -> Result<HealthEndpointStatus, String> {
+    let dir = app_data_dir(&app)?;
+    let mut settings = settings::load(&dir);
+    if enabled {
+        let port = port.unwrap_or(DEFAULT_HEALTH_ENDPOINT_PORT);
+        start_health_endpoint_server(port)?;
+        settings.health_endpoint_port = Some(port);
     } else {
-        None
+        stop_health_endpoint_server();
+        settings.health_endpoint_port = None;
+    }
+    settings::save(&dir, &settings)?;
+    Ok(health_endpoint_status(&settings))
+}
+
+// Crash and error reporting
+
+/// Endpoint baked in at build time for official releases; users can point elsewhere.
+const DEFAULT_TELEMETRY_ENDPOINT: Option<&str> = option_env!("CLAWNETES_TELEMETRY_DSN");
+const TELEMETRY_UPLOAD_SECS: u64 = 300;
+
+#[derive(Default)]
+struct TelemetryState {
+    /// App data directory holding the queue; set once reporting is configured.
+    dir: Option<PathBuf>,
+    enabled: bool,
+    endpoint: Option<String>,
+    last_sent_at: Option<u64>,
+    last_error: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct TelemetryStatus {
+    enabled: bool,
+    endpoint: Option<String>,
+    queued: usize,
+    last_sent_at: Option<u64>,
+    last_error: Option<String>,
+}
+
+fn telemetry_endpoint(settings: &settings::AppSettings) -> Option<String> {
+    settings
+        .telemetry_endpoint
+        .clone()
+        .or_else(|| DEFAULT_TELEMETRY_ENDPOINT.map(str::to_string))
+        .filter(|endpoint| !endpoint.trim().is_empty())
+}
+
+fn configure_telemetry(dir: &Path, settings: &settings::AppSettings) {
+    let mut state = TELEMETRY.lock().unwrap();
+    state.dir = Some(dir.to_path_buf());
+    state.enabled = settings.telemetry_enabled;
+    state.endpoint = telemetry_endpoint(settings);
+}
+
+/// Queues a report when the user has opted in. Uses `try_lock` so a panic raised while the
+/// state is locked cannot deadlock the hook.
+fn report_error(kind: &str, source: &str, message: &str) {
+    let dir = match TELEMETRY.try_lock() {
+        Ok(state) if state.enabled => state.dir.clone(),
+        _ => None,
+    };
+    let Some(dir) = dir else {
+        return;
     };
+    let home = dirs::home_dir().map(|home| home.to_string_lossy().to_string());
+    let report = telemetry::new_report(kind, source, message, home.as_deref());
+    if let Err(e) = telemetry::enqueue(&dir, report) {
+        eprintln!("{}", e);
+    }
+}
 
-    // Resolve Home Directory (Absolute) to avoid '~' ambiguity
-    let home_dir = if let Some(sess) = &session {
-        execute_ssh(sess, "echo $HOME")
-            .map_err(|e| format!("Failed to get remote home: {}", e))?
-            .trim()
-            .to_string()
-    } else {
-        // On Windows, openclaw runs inside WSL — use WSL home, not Windows home
-        #[cfg(target_os = "windows")]
-        {
-            wsl_home_dir()?
-        }
-        #[cfg(not(target_os = "windows"))]
-        {
-            dirs::home_dir()
-                .ok_or("Could not find local home directory")?
-                .to_string_lossy()
-                .to_string()
+/// Called for every error-level progress event, i.e. failed long-running commands.
+fn report_command_failure(task: &str, message: &str) {
+    report_error("command_error", task, message);
+}
+
+fn flush_telemetry() {
+    let (dir, endpoint) = {
+        let state = TELEMETRY.lock().unwrap();
+        match (&state.dir, &state.endpoint) {
+            (Some(dir), Some(endpoint)) if state.enabled => (dir.clone(), endpoint.clone()),
+            _ => return,
         }
     };
+    let result = telemetry::flush(&dir, &endpoint);
+    let mut state = TELEMETRY.lock().unwrap();
+    match result {
+        Ok(0) => {}
+        Ok(_) => {
+            state.last_sent_at = Some(unix_now_secs());
+            state.last_error = None;
+        }
+        Err(e) => state.last_error = Some(e),
+    }
+}
 
-    // Helper to read file content (using absolute paths)
-    let read_file_content = |path: &str| -> String {
-        if let Some(sess) = &session {
-            // Remote read
-            execute_ssh(sess, &format!("cat \"{}\"", path)).unwrap_or_default()
-        } else {
-            // On Windows, read from WSL filesystem
-            #[cfg(target_os = "windows")]
-            {
-                wsl_read_file(path).unwrap_or_default()
-            }
-            #[cfg(not(target_os = "windows"))]
-            {
-                fs::read_to_string(path).unwrap_or_default()
+/// Uploads queued reports every few minutes; reports stay queued while offline.
+fn start_telemetry_uploader() {
+    if TELEMETRY_UPLOADER_RUNNING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    lifecycle::spawn("telemetry-uploader", || {
+        loop {
+            flush_telemetry();
+            if !lifecycle::sleep(Duration::from_secs(TELEMETRY_UPLOAD_SECS)) {
+                break;
             }
         }
-    };
+        TELEMETRY_UPLOADER_RUNNING.store(false, Ordering::Relaxed);
+    });
+}
 
-    // Helper to list directories (used for skills)
-    let list_directories = |base_path: &str| -> Vec<String> {
-        let mut dirs_found = Vec::new();
-        if let Some(sess) = &session {
-            // Remote: use ls -F to mark directories with /
-            if let Ok(output) = execute_ssh(sess, &format!("ls -1 -F \"{}\"", base_path)) {
-                for line in output.lines() {
-                    if line.trim().ends_with('/') {
-                        dirs_found.push(line.trim().trim_matches('/').to_string());
-                    }
-                }
-            }
-        } else {
-            // On Windows, list dirs inside WSL filesystem
-            #[cfg(target_os = "windows")]
-            {
-                dirs_found = wsl_list_dirs(base_path);
-            }
-            #[cfg(not(target_os = "windows"))]
-            {
-                let path = std::path::Path::new(base_path);
-                if let Ok(entries) = fs::read_dir(path) {
-                    for entry in entries.flatten() {
-                        if let Ok(ft) = entry.file_type() {
-                            if ft.is_dir() {
-                                if let Ok(name) = entry.file_name().into_string() {
-                                    dirs_found.push(name);
-                                }
-                            }
-                        }
-                    }
-                }
+fn telemetry_status(dir: &Path) -> TelemetryStatus {
+    let state = TELEMETRY.lock().unwrap();
+    TelemetryStatus {
+        enabled: state.enabled,
+        endpoint: state.endpoint.clone(),
+        queued: telemetry::queued(dir),
+        last_sent_at: state.last_sent_at,
+        last_error: state.last_error.clone(),
+    }
+}
+
+#[command]
+fn get_telemetry_status(app: tauri::AppHandle) -> Result<TelemetryStatus, String> {
+    let dir = app_data_dir(&app)?;
+    configure_telemetry(&dir, &settings::load(&dir));
+    Ok(telemetry_status(&dir))
+}
+
+/// Opts in or out of error reporting. Opting out also discards reports not yet sent.
+#[command]
+fn set_telemetry_enabled(
+    app: tauri::AppHandle,
+    enabled: bool,
+    endpoint: Option<String>,
+) -> Result<TelemetryStatus, String> {
+    let dir = app_data_dir(&app)?;
+    let mut settings = settings::load(&dir);
+    settings.telemetry_enabled = enabled;
+    if let Some(endpoint) = endpoint {
+        let endpoint = endpoint.trim();
+        settings.telemetry_endpoint = (!endpoint.is_empty()).then(|| endpoint.to_string());
+    }
+    if enabled && telemetry_endpoint(&settings).is_none() {
+        return Err("Enter a Sentry DSN or report URL to send error reports to.".to_string());
+    }
+    settings::save(&dir, &settings)?;
+    configure_telemetry(&dir, &settings);
+    if enabled {
+        start_telemetry_uploader();
+    } else {
+        telemetry::clear_queue(&dir);
+    }
+    Ok(telemetry_status(&dir))
+}
+
+// Crash log
+
+/// Writes each panic, with a backtrace, to the crash log and queues it for opt-in reporting,
+/// then hands it to the previous hook as before.
+fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let crash = crash::Crash {
+            id: crash::new_id(),
+            timestamp: unix_now_secs(),
+            thread: std::thread::current()
+                .name()
+                .unwrap_or("unnamed")
+                .to_string(),
+            location: info
+                .location()
+                .map(|l| format!("{}:{}", l.file(), l.line()))
+                .unwrap_or_default(),
+            message: crash::panic_message(info.payload()),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        };
+        let dir = CRASH_LOG_DIR.try_lock().ok().and_then(|dir| dir.clone());
+        if let Some(dir) = dir {
+            if let Err(e) = crash::append(&dir, &crash) {
+                eprintln!("{}", e);
             }
         }
-        dirs_found
-    };
+        report_error("panic", &crash.location, &crash.message);
+        crash::remember(crash);
+        previous(info);
+    }));
+}
 
-    // Fetch Main Config Files
-    let openclaw_json_str = read_file_content(&format!("{}/.openclaw/openclaw.json", home_dir));
-    let auth_profiles_str = read_file_content(&format!(
-        "{}/.openclaw/agents/main/agent/auth-profiles.json",
-        home_dir
-    ));
-    let identity_str = read_file_content(&format!("{}/.openclaw/workspace/IDENTITY.md", home_dir));
-    let user_str = read_file_content(&format!("{}/.openclaw/workspace/USER.md", home_dir));
-    let soul_str = read_file_content(&format!("{}/.openclaw/workspace/SOUL.md", home_dir));
+/// The `AppError::Internal` for a panic caught on this thread, pointing at the crash log
+/// entry the hook wrote for it.
+fn internal_error(command: &str, payload: &(dyn std::any::Any + Send)) -> crash::AppError {
+    let crash = crash::take_last().unwrap_or_else(|| crash::Crash {
+        id: crash::new_id(),
+        timestamp: unix_now_secs(),
+        thread: std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string(),
+        location: String::new(),
+        message: crash::panic_message(payload),
+        backtrace: String::new(),
+    });
+    let log = CRASH_LOG_DIR
+        .lock()
+        .ok()
+        .and_then(|dir| dir.as_deref().map(crash::log_path));
+    let error = crash::AppError::internal(command, &crash, log.as_deref());
+    eprintln!("{}", error);
+    error
+}
 
-    if openclaw_json_str.is_empty() {
-        return Err("Configuration not found (openclaw.json is empty or missing)".to_string());
+/// `message` as the error of `command`: `AppError::Timeout` when it reports a child process
+/// killed at its timeout since the command `started`, so the frontend can offer a retry.
+fn command_error(command: &str, started: Instant, message: String) -> crash::CommandError {
+    for timeout in process::timeouts_since(started) {
+        let text = timeout.to_string();
+        if let process::ProcessError::Timeout {
+            command: process,
+            timeout_secs,
+        } = timeout
+        {
+            if message.contains(&text) {
+                return crash::AppError::Timeout {
+                    command: command.to_string(),
+                    process,
+                    timeout_secs,
+                    message,
+                }
+                .into();
+            }
+        }
     }
+    message.into()
+}
 
-    let oc_config: serde_json::Value = serde_json::from_str(&openclaw_json_str)
-        .map_err(|e| format!("Failed to parse openclaw.json: {}", e))?;
-    let auth_config: serde_json::Value =
-        serde_json::from_str(&auth_profiles_str).unwrap_or(serde_json::json!({}));
-    let empty_json = serde_json::json!({});
-
-    // Gateway Config
-    let gateway = oc_config.get("gateway").unwrap_or(&empty_json);
-    let gateway_port = gateway
-        .get("port")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(18789) as u16;
-    let gateway_bind = gateway
-        .get("bind")
-        .and_then(|v| v.as_str())
-        .unwrap_or("loopback")
-        .to_string();
-    let gateway_auth_mode = gateway
-        .get("auth")
-        .and_then(|a| a.get("mode"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("token")
-        .to_string();
-    let tailscale_mode = gateway
-        .get("tailscale")
-        .and_then(|t| t.get("mode"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("off")
-        .to_string();
+/// Runs sync command `command` for its `#[command]` wrapper, so a panic rejects the call with
+/// `AppError::Internal` instead of leaving the frontend's promise pending.
+fn guard_command<T>(
+    command: &str,
+    body: impl FnOnce() -> Result<T, String>,
+) -> Result<T, crash::CommandError> {
+    let started = Instant::now();
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(body)) {
+        Ok(result) => result.map_err(|message| command_error(command, started, message)),
+        Err(payload) => Err(internal_error(command, payload.as_ref()).into()),
+    }
+}
 
-    // Agent Config (Defaults / Main)
-    let defaults = oc_config
-        .get("agents")
-        .and_then(|a| a.get("defaults"))
-        .unwrap_or(&empty_json);
-    let model_primary_raw = defaults
-        .get("model")
-        .and_then(|m| m.get("primary"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("anthropic/claude-opus-4-6")
-        .to_string();
-    let model_primary = normalize_model_ref_for_ui(&model_primary_raw);
-    let fallback_models_raw: Vec<String> = defaults
-        .get("model")
-        .and_then(|m| m.get("fallbacks"))
-        .and_then(|v| serde_json::from_value(v.clone()).ok())
-        .unwrap_or_default();
-    let fallback_models: Vec<String> = fallback_models_raw
-        .iter()
-        .map(|model| normalize_model_ref_for_ui(model))
-        .collect();
+/// `guard_command` for async commands. The body is polled inside `catch_unwind`, so a panic
+/// is caught on the runtime worker that raised it, where the hook left its crash entry.
+async fn guard_async_command<T>(
+    command: &str,
+    body: impl std::future::Future<Output = Result<T, String>>,
+) -> Result<T, crash::CommandError> {
+    use futures_util::FutureExt;
+    let started = Instant::now();
+    match std::panic::AssertUnwindSafe(body).catch_unwind().await {
+        Ok(result) => result.map_err(|message| command_error(command, started, message)),
+        Err(payload) => Err(internal_error(command, payload.as_ref()).into()),
+    }
+}
 
-    // Auth & Provider (Main)
-    let base_provider = model_primary_raw
-        .split('/')
-        .next()
-        .map(normalize_provider_for_ui)
-        .unwrap_or_else(|| "anthropic".to_string());
-    let main_provider_auth = resolve_provider_auth_data(&base_provider, &auth_config)
-        .unwrap_or_else(|| default_provider_auth(&base_provider, "", "token", None));
-    let profile = main_provider_auth
-        .profile
-        .clone()
-        .unwrap_or(serde_json::json!({}));
-    let provider = base_provider.clone();
-    let api_key = main_provider_auth.token.clone();
-    let auth_method = main_provider_auth.auth_method.clone();
+/// Catches panics that escape the command bodies (argument parsing) so they do not take the
+/// app down. Their promise stays pending, as the resolver is gone by then; panics in the
+/// bodies reject through `guard_command` instead.
+fn catch_command_panics<F>(handler: F) -> impl Fn(tauri::Invoke) + Send + Sync + 'static
+where
+    F: Fn(tauri::Invoke) + Send + Sync + 'static,
+{
+    move |invoke: tauri::Invoke| {
+        let command = invoke.message.command().to_string();
+        if let Err(payload) =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| handler(invoke)))
+        {
+            internal_error(&command, payload.as_ref());
+        }
+    }
+}
 
-    // Markdown Extraction (Main)
-    let agent_name = extract_md_value(&identity_str, "Name");
-    let agent_vibe = extract_md_value(&identity_str, "Vibe");
-    let agent_emoji = extract_md_value(&identity_str, "Emoji");
-    let user_name = extract_md_value(&user_str, "Name");
+// Observer mode
 
-    // Telegram
-    let telegram_token = oc_config
-        .get("channels")
-        .and_then(|c| c.get("telegram"))
-        .and_then(|t| t.get("accounts"))
-        .and_then(|a| a.get("default"))
-        .and_then(|m| m.get("botToken"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
+#[derive(serde::Serialize, Clone, Debug)]
+struct ObserverModeStatus {
+    enabled: bool,
+    passcode_protected: bool,
+}
 
-    // Skills (Main)
-    // We look in ~/.openclaw/workspace/skills
-    let skills = list_directories(&format!("{}/.openclaw/workspace/skills", home_dir));
+fn observer_mode_status(state: &observer::ObserverState) -> ObserverModeStatus {
+    ObserverModeStatus {
+        enabled: state.enabled,
+        passcode_protected: state.passcode_hash.is_some(),
+    }
+}
 
-    let mut referenced_providers = std::collections::BTreeSet::new();
-    referenced_providers.insert(base_provider.clone());
-    for model in &fallback_models_raw {
-        if let Some(p) = model.split('/').next() {
-            referenced_providers.insert(normalize_provider_for_ui(p));
+/// Rejects every command outside `observer::ALLOWED_COMMANDS` with `AppError::ReadOnly`
+/// while observer mode is on.
+fn guard_observer_mode<F>(handler: F) -> impl Fn(tauri::Invoke) + Send + Sync + 'static
+where
+    F: Fn(tauri::Invoke) + Send + Sync + 'static,
+{
+    move |invoke: tauri::Invoke| {
+        let command = invoke.message.command().to_string();
+        if OBSERVER_MODE.load(Ordering::Relaxed) && !observer::is_allowed(&command) {
+            invoke
+                .resolver
+                .reject(crash::AppError::ReadOnly { command });
+            return;
         }
+        handler(invoke);
     }
+}
 
-    // Advanced Settings
-    let sandbox_mode = defaults
-        .get("sandbox")
-        .and_then(|s| s.get("mode"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("full")
-        .to_string();
-    let mapped_sandbox = if sandbox_mode == "all" {
-        "full"
-    } else if sandbox_mode == "non-main" {
-        "partial"
-    } else if sandbox_mode == "off" {
-        "none"
-    } else {
-        &sandbox_mode
-    };
+/// The generated command handler behind the observer mode check and panic catching.
+fn guard_commands<F>(handler: F) -> impl Fn(tauri::Invoke) + Send + Sync + 'static
+where
+    F: Fn(tauri::Invoke) + Send + Sync + 'static,
+{
+    catch_command_panics(guard_observer_mode(handler))
+}
 
-    let tools = oc_config.get("tools").unwrap_or(&empty_json);
-    let tools_profile = tools
-        .get("profile")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-    let allowed_tools: Vec<String> = tools
-        .get("allow")
-        .and_then(|v| serde_json::from_value(v.clone()).ok())
-        .unwrap_or_default();
-    let denied_tools: Vec<String> = tools
-        .get("deny")
-        .and_then(|v| serde_json::from_value(v.clone()).ok())
-        .unwrap_or_default();
-    let tools_mode = if tools_profile.as_deref() == Some("full") && denied_tools.is_empty() {
-        "all"
-    } else if !allowed_tools.is_empty() || tools_profile.is_some() {
-        "allowlist"
-    } else if !denied_tools.is_empty() {
-        "denylist"
-    } else {
-        "all"
-    };
+#[command]
+fn get_observer_mode(app: tauri::AppHandle) -> Result<ObserverModeStatus, String> {
+    Ok(observer_mode_status(&observer::load(&app_data_dir(&app)?)))
+}
 
-    let mut provider_auths = std::collections::HashMap::new();
-    for referenced_provider in &referenced_providers {
-        if let Some(auth) = resolve_provider_auth_data(referenced_provider, &auth_config) {
-            provider_auths.insert(referenced_provider.clone(), auth);
-        }
+/// Turns observer mode on, optionally locked with `passcode`, or off again; turning it off
+/// needs the same passcode.
+#[command]
+fn set_observer_mode(
+    app: tauri::AppHandle,
+    enabled: bool,
+    passcode: Option<String>,
+) -> Result<ObserverModeStatus, String> {
+    let dir = app_data_dir(&app)?;
+    let mut state = observer::load(&dir);
+    if enabled == state.enabled {
+        return Ok(observer_mode_status(&state));
+    }
+    if enabled {
+        state.enable(passcode.as_deref());
+    } else {
+        state.disable(passcode.as_deref())?;
     }
+    observer::save(&dir, &state)?;
+    OBSERVER_MODE.store(state.enabled, Ordering::Relaxed);
+    Ok(observer_mode_status(&state))
+}
 
-    let fallbacks = fallback_models.clone();
+// Embedded dashboard window
 
-    let heartbeat = defaults.get("heartbeat").unwrap_or(&empty_json);
-    let heartbeat_mode = if heartbeat.get("enabled") == Some(&serde_json::json!(false)) {
-        "never".to_string()
-    } else if let Some(mode) = heartbeat.get("mode").and_then(|v| v.as_str()) {
-        mode.to_string()
-    } else if let Some(every) = heartbeat.get("every").and_then(|v| v.as_str()) {
-        every.to_string()
-    } else {
-        "1h".to_string()
-    };
-    let idle_timeout = heartbeat
-        .get("timeout")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(3600000);
+const DASHBOARD_WINDOW_LABEL: &str = "dashboard";
 
-    // Multi-agent
-    let empty_vec = vec![];
-    let agent_list = oc_config
-        .get("agents")
-        .and_then(|a| a.get("list"))
-        .and_then(|v| v.as_array())
-        .unwrap_or(&empty_vec);
-    let enable_multi_agent = agent_list.len() > 1;
-    let mut agent_configs = Vec::new();
+/// Reloads the embedded dashboard (if open) and lets the frontend know the gateway
+/// came back, so stale WebSocket sessions in the dashboard are replaced.
+fn notify_gateway_restarted(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_window(DASHBOARD_WINDOW_LABEL) {
+        let _ = window.eval("window.location.reload()");
+    }
+    let _ = app.emit_all("gateway-restarted", ());
+}
 
-    if enable_multi_agent {
-        for agent_val in agent_list {
-            let aid = agent_val
-                .get("id")
-                .and_then(|s| s.as_str())
-                .unwrap_or("")
-                .to_string();
-            if aid.is_empty() || aid == "main" {
-                continue;
-            }
+#[command]
+async fn open_dashboard_window(app: tauri::AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_window(DASHBOARD_WINDOW_LABEL) {
+        window
+            .show()
+            .and_then(|_| window.set_focus())
+            .map_err(|e| format!("Failed to focus dashboard window: {}", e))?;
+        return Ok(());
+    }
 
-            // Basic info from openclaw.json
-            let mut name = agent_val
-                .get("name")
-                .and_then(|s| s.as_str())
-                .unwrap_or("Agent")
-                .to_string();
+    let dashboard_url: url::Url = ensure_dashboard_proxy()?
+        .parse()
+        .map_err(|e| format!("Invalid dashboard URL: {}", e))?;
 
-            // Robust Model Extraction: Handle nested {primary: "..."} or simple string "..."
-            let amodel_raw = if let Some(m_obj) = agent_val.get("model").and_then(|m| m.as_object())
-            {
-                m_obj
-                    .get("primary")
-                    .and_then(|s| s.as_str())
-                    .unwrap_or("")
-                    .to_string()
-            } else if let Some(m_str) = agent_val.get("model").and_then(|s| s.as_str()) {
-                m_str.to_string()
-            } else {
-                "".to_string()
-            };
-            let amodel = normalize_model_ref_for_ui(&amodel_raw);
+    // A dedicated data directory keeps the dashboard's cookies and local storage across
+    // app restarts without mixing them into the wizard webview.
+    let data_dir = app
+        .path_resolver()
+        .app_data_dir()
+        .ok_or("Could not determine app data directory")?
+        .join("dashboard-webview");
+    fs::create_dir_all(&data_dir)
+        .map_err(|e| format!("Failed to create dashboard data directory: {}", e))?;
+
+    tauri::WindowBuilder::new(
+        &app,
+        DASHBOARD_WINDOW_LABEL,
+        tauri::WindowUrl::External(dashboard_url),
+    )
+    .title("OpenClaw Dashboard")
+    .inner_size(1200.0, 850.0)
+    .resizable(true)
+    .data_directory(data_dir)
+    .build()
+    .map_err(|e| format!("Failed to open dashboard window: {}", e))?;
 
-            let afallbacks_raw: Vec<String> = agent_val
-                .get("model")
-                .and_then(|m| {
-                    if m.is_object() {
-                        m.get("fallbacks")
-                    } else {
-                        None
-                    }
-                })
-                .and_then(|v| serde_json::from_value(v.clone()).ok())
-                .unwrap_or_default();
-            let afallbacks: Vec<String> = afallbacks_raw
-                .iter()
-                .map(|model| normalize_model_ref_for_ui(model))
-                .collect();
+    Ok(())
+}
 
-            // Read Agent Files (Absolute Paths)
-            let agent_workspace_base = format!("{}/.openclaw/agents/{}/workspace", home_dir, aid);
+// Gateway supervision
 
-            let aid_md = read_file_content(&format!("{}/IDENTITY.md", agent_workspace_base));
-            let au_md = read_file_content(&format!("{}/USER.md", agent_workspace_base));
-            let as_md = read_file_content(&format!("{}/SOUL.md", agent_workspace_base));
+const SUPERVISOR_POLL_SECS: u64 = 5;
+// Consecutive healthy polls (~5 minutes) before the restart budget is replenished.
+const SUPERVISOR_HEALTHY_RESET_POLLS: u32 = 60;
 
-            // Extract Metadata
-            let extracted_name = extract_md_value(&aid_md, "Name");
-            if !extracted_name.is_empty() {
-                name = extracted_name;
-            } // Identity MD overrides config name
+#[derive(serde::Serialize, Clone, Default)]
+struct GatewaySupervisionStatus {
+    enabled: bool,
+    max_restarts: u32,
+    backoff_secs: u64,
+    crash_count: u32,
+    consecutive_restarts: u32,
+    last_exit_reason: Option<String>,
+    gave_up: bool,
+}
 
-            let avibe = extract_md_value(&aid_md, "Vibe");
-            let aemoji = extract_md_value(&aid_md, "Emoji");
+#[derive(serde::Serialize, Clone)]
+struct GatewaySupervisorEvent {
+    kind: String,
+    status: GatewaySupervisionStatus,
+}
 
-            // Extract Skills for this agent
-            let askills = list_directories(&format!("{}/skills", agent_workspace_base));
-            let askills_opt = if askills.is_empty() {
-                None
-            } else {
-                Some(askills)
-            };
+fn read_local_gateway_port() -> u16 {
+    read_local_openclaw_config()
+        .ok()
+        .and_then(|config| {
+            config
+                .get("gateway")
+                .and_then(|g| g.get("port"))
+                .and_then(|v| v.as_u64())
+        })
+        .unwrap_or(profiles::DEFAULT_GATEWAY_PORT as u64) as u16
+}
 
-            // Read additional md files for sub-agent
-            let a_tools_md_s = read_file_content(&format!("{}/TOOLS.md", agent_workspace_base));
-            let a_tools_md = if a_tools_md_s.is_empty() {
-                None
-            } else {
-                Some(a_tools_md_s)
-            };
-            let a_agents_md_s = read_file_content(&format!("{}/AGENTS.md", agent_workspace_base));
-            let a_agents_md = if a_agents_md_s.is_empty() {
-                None
-            } else {
-                Some(a_agents_md_s)
-            };
-            let a_heartbeat_md_s =
-                read_file_content(&format!("{}/HEARTBEAT.md", agent_workspace_base));
-            let a_heartbeat_md = if a_heartbeat_md_s.is_empty() {
-                None
-            } else {
-                Some(a_heartbeat_md_s)
-            };
-            let a_memory_md_s = read_file_content(&format!("{}/MEMORY.md", agent_workspace_base));
-            let a_memory_md = if a_memory_md_s.is_empty() {
-                None
-            } else {
-                Some(a_memory_md_s)
-            };
+fn gateway_status_indicates_stopped(status: &str) -> bool {
+    let lower = status.to_lowercase();
+    if lower.contains("starting") || lower.contains("initializing") {
+        return false;
+    }
+    if [
+        "not running",
+        "stopped",
+        "inactive",
+        "not loaded",
+        "failed",
+        "exited",
+    ]
+    .iter()
+    .any(|needle| lower.contains(needle))
+    {
+        return true;
+    }
+    !lower.contains("running")
+}
 
-            agent_configs.push(AgentData {
-                id: aid,
-                name,
-                model: amodel,
-                fallback_models: Some(afallbacks),
-                skills: askills_opt,
-                vibe: if avibe.is_empty() { None } else { Some(avibe) },
-                emoji: Some(aemoji),
-                identity_md: Some(aid_md),
-                user_md: Some(au_md),
-                soul_md: Some(as_md),
-                tools_md: a_tools_md,
-                agents_md: a_agents_md,
-                heartbeat_md: a_heartbeat_md,
-                memory_md: a_memory_md,
-                subagents: None,
-                tools: agent_val
-                    .get("tools")
-                    .and_then(|value| serde_json::from_value(value.clone()).ok()),
-            });
-            if let Some(agent_provider) =
-                agent_configs.last().and_then(|a| a.model.split('/').next())
-            {
-                referenced_providers.insert(normalize_provider_for_ui(agent_provider));
+fn extract_gateway_exit_reason(status: &str) -> Option<String> {
+    status
+        .lines()
+        .map(str::trim)
+        .find(|line| {
+            let lower = line.to_lowercase();
+            lower.contains("last exit")
+                || lower.contains("exit code")
+                || lower.contains("exited")
+                || lower.contains("error")
+        })
+        .map(|line| line.to_string())
+}
+
+fn supervisor_backoff_delay(base_secs: u64, attempt: u32) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(6);
+    Duration::from_secs(base_secs.saturating_mul(factor).min(300))
+}
+
+fn emit_supervisor_event(app: &tauri::AppHandle, kind: &str) {
+    let status = GATEWAY_SUPERVISION
+        .lock()
+        .map(|s| s.clone())
+        .unwrap_or_default();
+    let _ = app.emit_all(
+        "gateway-supervisor",
+        GatewaySupervisorEvent {
+            kind: kind.to_string(),
+            status,
+        },
+    );
+}
+
+fn run_gateway_supervisor(app: tauri::AppHandle) {
+    let mut failed_polls = 0u32;
+    let mut healthy_polls = 0u32;
+
+    loop {
+        if !lifecycle::sleep(Duration::from_secs(SUPERVISOR_POLL_SECS)) {
+            break;
+        }
+        let (max_restarts, backoff_secs, gave_up) = match GATEWAY_SUPERVISION.lock() {
+            Ok(s) if s.enabled => (s.max_restarts, s.backoff_secs, s.gave_up),
+            // Cleared under the lock `set_gateway_supervision` spawns under, so re-enabling
+            // either finds this thread still running or starts a new one.
+            Ok(_) => {
+                SUPERVISOR_THREAD_RUNNING.store(false, Ordering::Relaxed);
+                return;
             }
-            if let Some(agent_fallbacks) =
-                agent_configs.last().and_then(|a| a.fallback_models.clone())
-            {
-                for fallback in agent_fallbacks {
-                    if let Some(fallback_provider) = fallback.split('/').next() {
-                        referenced_providers.insert(normalize_provider_for_ui(fallback_provider));
-                    }
+            Err(_) => break,
+        };
+
+        let port = read_local_gateway_port();
+        if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+            failed_polls = 0;
+            healthy_polls = healthy_polls.saturating_add(1);
+            if healthy_polls == SUPERVISOR_HEALTHY_RESET_POLLS {
+                if let Ok(mut s) = GATEWAY_SUPERVISION.lock() {
+                    s.consecutive_restarts = 0;
+                    s.gave_up = false;
                 }
             }
+            continue;
+        }
+        healthy_polls = 0;
+        if gave_up || BUDGET_PAUSED.load(Ordering::Relaxed) {
+            continue;
         }
-    }
-
-    // Check Pairing Status
-    let credentials_dir = PathBuf::from(&home_dir).join(".openclaw").join("credentials");
-    let is_paired = extract_telegram_dm_policy_from_config(&oc_config)
-        .map(|policy| telegram_pairing_status_from_dm_policy(&policy))
-        .unwrap_or(false)
-        || telegram_allow_from_is_linked_local(&credentials_dir);
 
-    // Read additional workspace markdown files
-    let tools_md_s = read_file_content(&format!("{}/.openclaw/workspace/TOOLS.md", home_dir));
-    let tools_md_str = if tools_md_s.is_empty() {
-        None
-    } else {
-        Some(tools_md_s)
-    };
-    let agents_md_s = read_file_content(&format!("{}/.openclaw/workspace/AGENTS.md", home_dir));
-    let agents_md_str = if agents_md_s.is_empty() {
-        None
-    } else {
-        Some(agents_md_s)
-    };
-    let heartbeat_md_s =
-        read_file_content(&format!("{}/.openclaw/workspace/HEARTBEAT.md", home_dir));
-    let heartbeat_md_str = if heartbeat_md_s.is_empty() {
-        None
-    } else {
-        Some(heartbeat_md_s)
-    };
-    let memory_md_s = read_file_content(&format!("{}/.openclaw/workspace/MEMORY.md", home_dir));
-    let memory_md_str = if memory_md_s.is_empty() {
-        None
-    } else {
-        Some(memory_md_s)
-    };
+        // A closed port alone can be a restart in progress; require the CLI to agree
+        // on two consecutive polls before treating it as a crash.
+        let status = shell_command("openclaw gateway status").unwrap_or_else(|e| e);
+        if !gateway_status_indicates_stopped(&status) {
+            failed_polls = 0;
+            continue;
+        }
+        failed_polls += 1;
+        if failed_polls < 2 {
+            continue;
+        }
+        failed_polls = 0;
 
-    // Check memory enabled (memoryFlush is an object: { enabled: bool })
-    let memory_enabled = defaults
-        .get("compaction")
-        .and_then(|c| c.get("memoryFlush"))
-        .and_then(|v| {
-            // Handle both old format (bare bool) and new format ({ enabled: bool })
-            if v.is_boolean() {
-                v.as_bool()
-            } else {
-                v.get("enabled").and_then(|e| e.as_bool())
+        let reason = extract_gateway_exit_reason(&status).unwrap_or_else(|| {
+            format!(
+                "Gateway port {} closed and the service reports it is not running",
+                port
+            )
+        });
+        let attempt = match GATEWAY_SUPERVISION.lock() {
+            Ok(mut s) => {
+                s.crash_count += 1;
+                s.last_exit_reason = Some(reason);
+                if s.consecutive_restarts >= max_restarts {
+                    s.gave_up = true;
+                    None
+                } else {
+                    s.consecutive_restarts += 1;
+                    Some(s.consecutive_restarts)
+                }
             }
-        })
-        .unwrap_or(false);
-
-    // Read Clawnetes metadata from separate file
-    let meta_str = read_file_content(&format!("{}/.openclaw/clawnetes-meta.json", home_dir));
-    let meta: serde_json::Value = serde_json::from_str(&meta_str).unwrap_or(serde_json::json!({}));
+            Err(_) => break,
+        };
+        emit_supervisor_event(&app, "crashed");
 
-    // Read cron jobs from metadata
-    let cron_jobs: Option<Vec<CronJobConfig>> = meta
-        .get("cron_jobs")
-        .and_then(|c| serde_json::from_value(c.clone()).ok());
+        let Some(attempt) = attempt else {
+            emit_supervisor_event(&app, "gave_up");
+            continue;
+        };
 
-    // Read agent type from metadata (NOT from openclaw.json)
-    let agent_type = meta
-        .get("agent_type")
-        .and_then(|v| v.as_str())
-        .unwrap_or("custom")
-        .to_string();
+        if !lifecycle::sleep(supervisor_backoff_delay(backoff_secs, attempt)) {
+            break;
+        }
+        match restart_local_gateway() {
+            Ok(_) => {
+                emit_supervisor_event(&app, "restarted");
+                notify_gateway_restarted(&app);
+            }
+            Err(err) => {
+                if let Ok(mut s) = GATEWAY_SUPERVISION.lock() {
+                    s.last_exit_reason = Some(err);
+                }
+                emit_supervisor_event(&app, "restart_failed");
+            }
+        }
+    }
 
-    // Read WhatsApp config from openclaw.json
-    let whatsapp_enabled = oc_config
-        .get("plugins")
-        .and_then(|p| p.get("entries"))
-        .and_then(|e| e.get("whatsapp"))
-        .and_then(|w| w.get("enabled"))
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
+    SUPERVISOR_THREAD_RUNNING.store(false, Ordering::Relaxed);
+}
 
-    let whatsapp_dm_policy = oc_config
-        .get("channels")
-        .and_then(|c| c.get("whatsapp"))
-        .and_then(|w| w.get("accounts"))
-        .and_then(|a| a.get("default"))
-        .and_then(|m| m.get("dmPolicy"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+#[command]
+fn set_gateway_supervision(
+    app: tauri::AppHandle,
+    enabled: bool,
+    max_restarts: u32,
+    backoff: u64,
+) -> Result<GatewaySupervisionStatus, String> {
+    if backoff == 0 || backoff > 600 {
+        return Err("Backoff must be between 1 and 600 seconds.".to_string());
+    }
 
-    let thinking_level = defaults
-        .get("thinkingDefault")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+    let status = {
+        let mut s = GATEWAY_SUPERVISION
+            .lock()
+            .map_err(|_| "Gateway supervision state is poisoned".to_string())?;
+        s.enabled = enabled;
+        s.max_restarts = max_restarts;
+        s.backoff_secs = backoff;
+        if enabled {
+            s.gave_up = false;
+            s.consecutive_restarts = 0;
+            if !SUPERVISOR_THREAD_RUNNING.swap(true, Ordering::Relaxed) {
+                lifecycle::spawn("gateway-supervisor", move || run_gateway_supervisor(app));
+            }
+        }
+        s.clone()
+    };
 
-    Ok(CurrentConfig {
-        provider,
-        api_key,
-        auth_method,
-        model: model_primary,
-        user_name,
-        agent_name,
-        agent_vibe,
-        agent_emoji,
-        agent_type,
-        telegram_token,
-        gateway_port,
-        gateway_bind,
-        gateway_auth_mode,
-        tailscale_mode,
-        node_manager: "npm".to_string(),
-        skills,
-        service_keys: std::collections::HashMap::new(),
-        provider_auths,
-        sandbox_mode: mapped_sandbox.to_string(),
-        tools_mode: tools_mode.to_string(),
-        tools_profile,
-        allowed_tools,
-        denied_tools,
-        fallback_models: fallbacks,
-        heartbeat_mode,
-        idle_timeout_ms: idle_timeout,
-        identity_md: identity_str,
-        user_md: user_str,
-        soul_md: soul_str,
-        tools_md: tools_md_str,
-        agents_md: agents_md_str,
-        heartbeat_md: heartbeat_md_str,
-        memory_md: memory_md_str,
-        memory_enabled,
-        enable_multi_agent,
-        agent_configs,
-        is_paired,
-        cron_jobs,
-        local_base_url: profile
-            .get("baseUrl")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string()),
-        thinking_level,
-        whatsapp_enabled: Some(whatsapp_enabled),
-        whatsapp_dm_policy,
-        whatsapp_phone_number: oc_config
-            .get("channels")
-            .and_then(|c| c.get("whatsapp"))
-            .and_then(|w| w.get("allowFrom"))
-            .and_then(|a| a.as_array())
-            .and_then(|a| a.first())
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string()),
-    })
+    Ok(status)
 }
 
 #[command]
-fn has_saved_license(app: tauri::AppHandle) -> Result<bool, String> {
-    Ok(read_saved_license(&app)?.is_some())
+fn get_gateway_supervision_status() -> Result<GatewaySupervisionStatus, String> {
+    GATEWAY_SUPERVISION
+        .lock()
+        .map(|s| s.clone())
+        .map_err(|_| "Gateway supervision state is poisoned".to_string())
 }
 
-#[command]
-fn verify_and_store_license(app: tauri::AppHandle, key: String) -> Result<bool, String> {
-    let trimmed = key.trim();
-    if trimmed.is_empty() {
-        return Err("License key is required.".to_string());
-    }
+// Structured gateway status
+
+#[derive(serde::Serialize, Clone, Default, Debug, PartialEq)]
+struct GatewayStatus {
+    running: bool,
+    pid: Option<u32>,
+    uptime: Option<String>,
+    port: Option<u16>,
+    version: Option<String>,
+    active_channels: Vec<String>,
+    connected_clients: Option<u32>,
+    raw_output: String,
+}
 
-    verify_license_with_gumroad(trimmed)?;
-    write_saved_license(&app, trimmed)?;
-    Ok(true)
+fn find_number_after(haystack: &str, key: &str) -> Option<u64> {
+    let lower = haystack.to_lowercase();
+    let start = lower.find(key)? + key.len();
+    let digits = lower[start..]
+        .trim_start_matches(|c: char| c == ' ' || c == '=' || c == ':' || c == '(')
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>();
+    digits.parse().ok()
 }
 
-#[command]
-async fn install_local_nodejs() -> Result<String, String> {
-    #[cfg(target_os = "windows")]
-    {
-        // On Windows: install WSL2 first, then Node.js inside WSL2
-        ensure_wsl2_installed()?;
-        // Ensure WSL is responsive before running apt commands
-        wait_for_wsl_ready(30)
-            .map_err(|e| format!("WSL not ready for Node.js installation: {}", e))?;
-        // Use wsl_root_command to run as root directly (avoids sudo password prompt)
-        wsl_root_command("curl -fsSL https://deb.nodesource.com/setup_22.x | bash -")
-            .map_err(|e| format!("Failed to add NodeSource repository: {}", e))?;
-        wsl_root_command("apt-get install -y nodejs")
-            .map_err(|e| format!("Failed to install Node.js in WSL2: {}", e))?;
-        return Ok("Node.js installed successfully in WSL2.".to_string());
+fn status_line_value<'a>(output: &'a str, key: &str) -> Option<&'a str> {
+    output.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case(key) {
+            Some(value.trim()).filter(|value| !value.is_empty())
+        } else {
+            None
+        }
+    })
+}
+
+fn parse_gateway_status_text(output: &str) -> GatewayStatus {
+    let runtime = status_line_value(output, "Runtime").unwrap_or(output);
+    let running = !gateway_status_indicates_stopped(runtime);
+
+    let port = status_line_value(output, "Listening")
+        .and_then(|value| value.rsplit(':').next())
+        .and_then(|value| value.trim().parse::<u16>().ok())
+        .or_else(|| find_number_after(output, "port").map(|p| p as u16));
+
+    let active_channels = status_line_value(output, "Channels")
+        .map(|value| {
+            value
+                .split(',')
+                .map(|channel| channel.trim().to_string())
+                .filter(|channel| !channel.is_empty() && channel != "none")
+                .collect()
+        })
+        .unwrap_or_default();
+
+    GatewayStatus {
+        running,
+        pid: find_number_after(runtime, "pid").map(|pid| pid as u32),
+        uptime: status_line_value(output, "Uptime").map(|value| value.to_string()),
+        port,
+        version: status_line_value(output, "Version").map(|value| value.to_string()),
+        active_channels,
+        connected_clients: status_line_value(output, "Clients")
+            .and_then(|value| value.split_whitespace().next())
+            .and_then(|value| value.parse().ok()),
+        raw_output: output.to_string(),
     }
+}
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        // 1. Try brew (macOS standard)
-        if shell_command("brew --version").is_ok() {
-            return shell_command("brew install node");
-        }
+fn parse_gateway_status_json(value: &serde_json::Value) -> GatewayStatus {
+    let runtime = value.get("runtime").unwrap_or(value);
+    let running = runtime
+        .get("running")
+        .and_then(|v| v.as_bool())
+        .or_else(|| {
+            runtime
+                .get("status")
+                .and_then(|v| v.as_str())
+                .map(|status| !gateway_status_indicates_stopped(status))
+        })
+        .unwrap_or(false);
 
-        // 2. Try nvm (via curl) - Fallback for macOS without brew or Linux
-        let install_nvm_cmd =
-            "curl -o- https://raw.githubusercontent.com/nvm-sh/nvm/v0.40.1/install.sh | bash";
-        shell_command(install_nvm_cmd).map_err(|e| format!("Failed to install nvm: {}", e))?;
+    let active_channels = value
+        .get("channels")
+        .map(|channels| match channels {
+            serde_json::Value::Array(items) => items
+                .iter()
+                .filter_map(|item| {
+                    item.as_str()
+                        .or_else(|| item.get("id").and_then(|v| v.as_str()))
+                        .map(|s| s.to_string())
+                })
+                .collect(),
+            serde_json::Value::Object(map) => map
+                .iter()
+                .filter(|(_, state)| {
+                    state
+                        .get("connected")
+                        .or_else(|| state.get("running"))
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(true)
+                })
+                .map(|(name, _)| name.clone())
+                .collect(),
+            _ => Vec::new(),
+        })
+        .unwrap_or_default();
 
-        let install_node_cmd = "export NVM_DIR=\"$HOME/.nvm\"; \
-            [ -s \"$NVM_DIR/nvm.sh\" ] && \\. \"$NVM_DIR/nvm.sh\"; \
-            nvm install node && nvm use node && nvm alias default node";
+    GatewayStatus {
+        running,
+        pid: runtime
+            .get("pid")
+            .and_then(|v| v.as_u64())
+            .map(|pid| pid as u32),
+        uptime: runtime
+            .get("uptime")
+            .or_else(|| value.get("uptime"))
+            .and_then(|v| {
+                v.as_str()
+                    .map(|s| s.to_string())
+                    .or_else(|| v.as_u64().map(|secs| format!("{}s", secs)))
+            }),
+        port: value
+            .get("port")
+            .or_else(|| value.get("gateway").and_then(|g| g.get("port")))
+            .and_then(|v| v.as_u64())
+            .map(|port| port as u16),
+        version: value
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        active_channels,
+        connected_clients: value
+            .get("clients")
+            .and_then(|v| {
+                v.as_u64()
+                    .or_else(|| v.as_array().map(|items| items.len() as u64))
+            })
+            .map(|count| count as u32),
+        raw_output: value.to_string(),
+    }
+}
 
-        shell_command(install_node_cmd)
-            .map_err(|e| format!("Failed to install Node.js via nvm: {}", e))
+fn parse_gateway_status_output(output: &str) -> GatewayStatus {
+    match serde_json::from_str::<serde_json::Value>(output.trim()) {
+        Ok(value) if value.is_object() => parse_gateway_status_json(&value),
+        _ => parse_gateway_status_text(output),
     }
 }
 
+/// `gateway_id` targets a registered gateway (see `gateways`) instead of this machine or `remote`.
 #[command]
-fn get_ollama_models(remote: Option<RemoteInfo>) -> Result<Vec<String>, String> {
-    if let Some(r) = remote {
-        // Remote: SSH exec curl to hit Ollama API on the remote host
-        let sess = connect_ssh(&r).map_err(|e| format!("SSH connect failed: {}", e))?;
-        let output = execute_ssh(
-            &sess,
-            "curl -sf http://localhost:11434/api/tags 2>/dev/null || echo '{}'",
-        );
-        match output {
-            Ok(json_str) => {
-                let val: serde_json::Value =
-                    serde_json::from_str(&json_str).unwrap_or(serde_json::json!({}));
-                let models: Vec<String> = val
-                    .get("models")
-                    .and_then(|m| m.as_array())
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|m| {
-                                m.get("name")
-                                    .and_then(|n| n.as_str())
-                                    .map(|s| s.to_string())
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default();
-                Ok(models)
+async fn get_gateway_status(
+    app: tauri::AppHandle,
+    remote: Option<RemoteInfo>,
+    gateway_id: Option<String>,
+) -> Result<GatewayStatus, String> {
+    if let Some(client) = registered_gateway_client(&app, gateway_id.as_deref())? {
+        return Ok(query_gateway_status(&client)
+            .await
+            .unwrap_or_else(|e| GatewayStatus {
+                port: Some(client.port()),
+                raw_output: e,
+                ..Default::default()
+            }));
+    }
+    if remote.is_none() {
+        let client = gateway_client::GatewayClient::local().single_attempt();
+        if client.is_reachable().await {
+            if let Ok(payload) = client.status().await {
+                let mut status = parse_gateway_status_json(&payload);
+                status.running = true;
+                status.port = status.port.or(Some(client.port()));
+                if status.version.is_none() {
+                    status.version = shell_command("openclaw --version")
+                        .ok()
+                        .map(|v| v.trim().to_string())
+                        .filter(|v| !v.is_empty());
+                }
+                return Ok(status);
             }
-            Err(_) => Ok(vec![]),
         }
+    }
+
+    let Some(r) = remote else {
+        // Structured output first; text scraping only for CLIs without --json.
+        let mut status = match cli::gateway_status() {
+            Ok(value) => parse_gateway_status_json(&value),
+            Err(_) => parse_gateway_status_output(
+                &shell_command("openclaw gateway status").unwrap_or_else(|e| e),
+            ),
+        };
+        if status.version.is_none() {
+            status.version = shell_command("openclaw --version")
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty());
+        }
+        return Ok(status);
+    };
+
+    let sess = connect_ssh(&r)?;
+    let os_type = execute_ssh(&sess, "uname -s")?.trim().to_string();
+    let prefix = get_env_prefix(&os_type);
+    let run_command = |cmd: &str| execute_ssh(&sess, &format!("{}{}", prefix, cmd));
+
+    // Newer CLIs emit JSON; older ones only print the human-readable report.
+    let output = run_command("openclaw gateway status --json")
+        .or_else(|_| run_command("openclaw gateway status"))
+        .unwrap_or_else(|e| e);
+    let mut status = parse_gateway_status_output(&output);
+
+    if status.version.is_none() {
+        status.version = run_command("openclaw --version")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+    }
+
+    Ok(status)
+}
+
+// Gateway registry
+
+/// The registered gateway `id` and its token from the keychain; `None` for the local gateway.
+fn registered_gateway(
+    app: &tauri::AppHandle,
+    id: Option<&str>,
+) -> Result<Option<(gateways::Gateway, Option<String>)>, String> {
+    let Some(id) = id.filter(|id| *id != gateways::LOCAL_GATEWAY_ID) else {
+        return Ok(None);
+    };
+    let registry = gateways::load(&app_data_dir(app)?);
+    let gateway = gateways::find(&registry, id)?.clone();
+    let token = keychain::load(&gateways::keychain_account(id));
+    Ok(Some((gateway, token)))
+}
+
+fn registered_gateway_client(
+    app: &tauri::AppHandle,
+    id: Option<&str>,
+) -> Result<Option<gateway_client::GatewayClient>, String> {
+    registered_gateway(app, id)?
+        .map(|(gateway, token)| gateway_client::GatewayClient::remote(&gateway.url, token))
+        .transpose()
+}
+
+/// Status of a gateway reached over its API; `Err` when it cannot be reached or rejects us.
+async fn query_gateway_status(
+    client: &gateway_client::GatewayClient,
+) -> Result<GatewayStatus, String> {
+    if !client.is_reachable().await {
+        return Err(messages::text("gateway.not_running", &[]));
+    }
+    let payload = client.status().await.map_err(|e| e.message())?;
+    let mut status = parse_gateway_status_json(&payload);
+    status.running = true;
+    status.port = status.port.or(Some(client.port()));
+    Ok(status)
+}
+
+#[command]
+fn list_gateways(app: tauri::AppHandle) -> Result<Vec<gateways::Gateway>, String> {
+    Ok(gateways::load(&app_data_dir(&app)?))
+}
+
+/// Registers the gateway at `url`; its `token` goes to the system keychain, not to disk.
+#[command]
+fn add_gateway(
+    app: tauri::AppHandle,
+    name: String,
+    url: String,
+    token: Option<String>,
+) -> Result<gateways::Gateway, String> {
+    let dir = app_data_dir(&app)?;
+    let mut registry = gateways::load(&dir);
+    let gateway = gateways::add(&mut registry, &name, &url)?;
+    if let Some(token) = token.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        keychain::store(&gateways::keychain_account(&gateway.id), token)?;
+    }
+    gateways::save(&dir, &registry)?;
+    Ok(gateway)
+}
+
+#[command]
+fn remove_gateway(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let dir = app_data_dir(&app)?;
+    let mut registry = gateways::load(&dir);
+    gateways::find(&registry, &id)?;
+    keychain::delete(&gateways::keychain_account(&id))?;
+    registry.retain(|gateway| gateway.id != id);
+    gateways::save(&dir, &registry)
+}
+
+/// Connects to a registered gateway with its stored token and reports its status.
+#[command]
+async fn test_gateway(app: tauri::AppHandle, id: String) -> Result<GatewayStatus, String> {
+    let client = registered_gateway_client(&app, Some(&id))?
+        .ok_or_else(|| format!("Unknown gateway '{}'", id))?;
+    query_gateway_status(&client).await
+}
+
+/// The last `lines` lines of the gateway log, read through the gateway API; the local
+/// gateway falls back to its log files when the API is unavailable.
+#[command]
+async fn get_gateway_logs(
+    app: tauri::AppHandle,
+    gateway_id: Option<String>,
+    lines: Option<u32>,
+) -> Result<String, String> {
+    let lines = lines.unwrap_or(200).clamp(1, 5000);
+    let registered = registered_gateway_client(&app, gateway_id.as_deref())?;
+    let is_local = registered.is_none();
+    let client =
+        registered.unwrap_or_else(|| gateway_client::GatewayClient::local().single_attempt());
+    let via_api = if client.is_reachable().await {
+        client.tail_logs(lines).await.map_err(|e| e.message())
     } else {
-        // Local: use reqwest blocking
-        match reqwest::blocking::get("http://localhost:11434/api/tags") {
-            Ok(resp) => {
-                let json: serde_json::Value = resp.json().unwrap_or(serde_json::json!({}));
-                let models: Vec<String> = json
-                    .get("models")
-                    .and_then(|m| m.as_array())
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|m| {
-                                m.get("name")
-                                    .and_then(|n| n.as_str())
-                                    .map(|s| s.to_string())
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default();
-                Ok(models)
+        Err(messages::text("gateway.not_running", &[]))
+    };
+    match via_api {
+        Ok(payload) => Ok(gateway_client::log_tail_lines(&payload).join("\n")),
+        Err(_) if is_local => {
+            let log = read_all_gateway_logs()?;
+            let all: Vec<&str> = log.lines().collect();
+            Ok(all[all.len().saturating_sub(lines as usize)..].join("\n"))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// Test chat console
+
+const TEST_CHAT_SESSION_KEY: &str = "agent:main:clawnetes-test";
+const TEST_CHAT_TIMEOUT_SECS: u64 = 180;
+
+#[derive(serde::Serialize, Clone)]
+struct TestMessageToken {
+    delta: String,
+    text: String,
+}
+
+/// Concatenates the text parts of a chat event's `message` (string or content blocks).
+fn chat_message_text(message: &serde_json::Value) -> Option<String> {
+    if let Some(text) = message.as_str() {
+        return Some(text.to_string());
+    }
+    if let Some(text) = message.get("text").and_then(|v| v.as_str()) {
+        return Some(text.to_string());
+    }
+    let parts = message.get("content")?.as_array()?;
+    Some(
+        parts
+            .iter()
+            .filter(|part| part.get("type").and_then(|t| t.as_str()).unwrap_or("text") == "text")
+            .filter_map(|part| part.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join(""),
+    )
+}
+
+/// Chat deltas carry the reply accumulated so far; returns only the newly added suffix.
+fn chat_stream_suffix<'a>(previous: &str, current: &'a str) -> &'a str {
+    current.strip_prefix(previous).unwrap_or(current)
+}
+
+/// Sends `text` to the main agent over the gateway in `session_key` and waits for the
+/// reply, calling `on_token(delta, text_so_far)` as it streams in.
+async fn run_gateway_chat<F>(
+    session_key: &str,
+    text: &str,
+    mut on_token: F,
+) -> Result<String, String>
+where
+    F: FnMut(&str, &str),
+{
+    if text.trim().is_empty() {
+        return Err("Message cannot be empty".to_string());
+    }
+    let client = gateway_client::GatewayClient::local();
+    if !client.is_reachable().await {
+        return Err(messages::text("gateway.not_running", &[]));
+    }
+
+    let mut connection = client.connect().await?;
+    let request_id = connection
+        .send_request(
+            "chat.send",
+            serde_json::json!({
+                "sessionKey": session_key,
+                "message": text,
+                "idempotencyKey": uuid::Uuid::new_v4().to_string()
+            }),
+        )
+        .await?;
+
+    let conversation = async {
+        let mut run_id: Option<String> = None;
+        let mut reply = String::new();
+        while let Some(frame) = connection.next_frame().await {
+            let frame = frame?;
+            if let Some(result) = gateway_client::parse_rpc_response(&frame, &request_id) {
+                let payload =
+                    result.map_err(|e| gateway_client::RpcError::Rejected(e).message())?;
+                run_id = payload
+                    .get("runId")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string());
+                continue;
             }
-            Err(_) => Ok(vec![]),
+
+            if frame.get("event").and_then(|v| v.as_str()) != Some("chat") {
+                continue;
+            }
+            let payload = frame.get("payload").cloned().unwrap_or_default();
+            let event_run = payload.get("runId").and_then(|v| v.as_str());
+            let event_session = payload.get("sessionKey").and_then(|v| v.as_str());
+            let ours = match (&run_id, event_run) {
+                (Some(expected), Some(actual)) => expected == actual,
+                _ => event_session == Some(session_key),
+            };
+            if !ours {
+                continue;
+            }
+
+            let current = payload
+                .get("message")
+                .and_then(chat_message_text)
+                .unwrap_or_default();
+            match payload.get("state").and_then(|v| v.as_str()).unwrap_or("") {
+                "delta" => {
+                    let delta = chat_stream_suffix(&reply, &current).to_string();
+                    if !delta.is_empty() {
+                        reply = current;
+                        on_token(&delta, &reply);
+                    }
+                }
+                "final" => {
+                    return Ok(if current.is_empty() { reply } else { current });
+                }
+                "error" | "aborted" => {
+                    let message = payload
+                        .get("errorMessage")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("The agent did not finish its reply");
+                    return Err(message.to_string());
+                }
+                _ => {}
+            }
+        }
+        Err::<String, String>("Gateway closed the connection before the agent replied".to_string())
+    };
+
+    let mut shutdown = lifecycle::subscribe();
+    tokio::select! {
+        result = tokio::time::timeout(Duration::from_secs(TEST_CHAT_TIMEOUT_SECS), conversation) => {
+            result.unwrap_or_else(|_| Err("Timed out waiting for the agent to reply".to_string()))
         }
+        _ = shutdown.recv() => Err("Clawnetes is shutting down".to_string()),
     }
 }
 
 #[command]
-fn get_lmstudio_models(
-    base_url: Option<String>,
-    remote: Option<RemoteInfo>,
-) -> Result<Vec<String>, String> {
-    let url_base = base_url.as_deref().unwrap_or("http://localhost:1234");
-    let models_url = format!("{}/v1/models", url_base);
+async fn send_test_message(text: String) -> Result<String, String> {
+    run_gateway_chat(TEST_CHAT_SESSION_KEY, &text, |_, _| {}).await
+}
 
-    if let Some(r) = remote {
-        let sess = connect_ssh(&r).map_err(|e| format!("SSH connect failed: {}", e))?;
-        let output = execute_ssh(
-            &sess,
-            &format!("curl -sf {}/v1/models 2>/dev/null || echo '{{}}'", url_base),
+/// Same as `send_test_message`, emitting a `test-message-token` event per streamed chunk.
+#[command]
+async fn send_test_message_streaming(
+    app: tauri::AppHandle,
+    text: String,
+) -> Result<String, String> {
+    run_gateway_chat(TEST_CHAT_SESSION_KEY, &text, |delta, text| {
+        let _ = app.emit_all(
+            "test-message-token",
+            TestMessageToken {
+                delta: delta.to_string(),
+                text: text.to_string(),
+            },
         );
-        match output {
-            Ok(json_str) => {
-                let val: serde_json::Value =
-                    serde_json::from_str(&json_str).unwrap_or(serde_json::json!({}));
-                let models: Vec<String> = val
-                    .get("data")
-                    .and_then(|d| d.as_array())
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|m| {
-                                m.get("id").and_then(|n| n.as_str()).map(|s| s.to_string())
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default();
-                Ok(models)
+    })
+    .await
+}
+
+// Gateway event bridge
+
+const EVENT_BRIDGE_RECONNECT_SECS: u64 = 5;
+
+/// Keeps an operator connection to the local gateway open and re-emits its live events
+/// (see `event_bridge`) as `gateway-message`, `gateway-tool-call` and `gateway-error`.
+/// `gateway-events-connected` reports when the stream comes up or drops; the bridge
+/// reconnects on its own after gateway restarts.
+fn start_gateway_event_bridge(app: tauri::AppHandle) {
+    if EVENT_BRIDGE_RUNNING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown = lifecycle::subscribe();
+        loop {
+            let client = gateway_client::GatewayClient::local();
+            let connection = if client.is_reachable().await {
+                client.connect().await.ok()
+            } else {
+                None
+            };
+            if let Some(mut connection) = connection {
+                let _ = app.emit_all("gateway-events-connected", true);
+                loop {
+                    tokio::select! {
+                        frame = connection.next_frame() => match frame {
+                            Some(Ok(frame)) => {
+                                if let Some(event) = event_bridge::classify(&frame) {
+                                    let _ = app.emit_all(event.name(), event);
+                                }
+                            }
+                            _ => break,
+                        },
+                        _ = shutdown.recv() => return,
+                    }
+                }
+                let _ = app.emit_all("gateway-events-connected", false);
             }
-            Err(_) => Ok(vec![]),
-        }
-    } else {
-        match reqwest::blocking::get(&models_url) {
-            Ok(resp) => {
-                let json: serde_json::Value = resp.json().unwrap_or(serde_json::json!({}));
-                let models: Vec<String> = json
-                    .get("data")
-                    .and_then(|d| d.as_array())
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|m| {
-                                m.get("id").and_then(|n| n.as_str()).map(|s| s.to_string())
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default();
-                Ok(models)
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(EVENT_BRIDGE_RECONNECT_SECS)) => {}
+                _ = shutdown.recv() => return,
             }
-            Err(_) => Ok(vec![]),
         }
+    });
+}
+
+// Proactive messages
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct BroadcastResult {
+    channel: String,
+    target: String,
+    message_id: Option<String>,
+}
+
+/// Senders approved in `<channel>[-<account>]-allowFrom.json` under `credentials_dir`,
+/// i.e. the chats the user paired with the agent.
+fn paired_chats(credentials_dir: &Path, channel: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(credentials_dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(channel) && name.ends_with("-allowFrom.json"))
+        })
+        .collect();
+    files.sort();
+    files
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .filter_map(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .filter_map(|json| json.get("allowFrom").and_then(|v| v.as_array()).cloned())
+        .flatten()
+        .filter_map(|entry| match entry {
+            serde_json::Value::String(id) => Some(id),
+            serde_json::Value::Number(id) => Some(id.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Makes the agent send `text` to `target` on `channel` through the gateway, so users can
+/// check delivery or post announcements. Without a `target` the message goes to the first
+/// chat paired on that channel.
+#[command]
+async fn broadcast_message(
+    channel: String,
+    target: Option<String>,
+    text: String,
+    account: Option<String>,
+) -> Result<BroadcastResult, String> {
+    if !is_valid_session_id(&channel) {
+        return Err(format!("Invalid channel: {}", channel));
+    }
+    if text.trim().is_empty() {
+        return Err("Message cannot be empty".to_string());
+    }
+    let target = match target
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+    {
+        Some(target) => target,
+        None => {
+            let credentials_dir = PathBuf::from(paths::local_path("credentials")?);
+            paired_chats(&credentials_dir, &channel)
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("No chat is paired on {} yet; pass a target.", channel))?
+        }
+    };
+    let client = gateway_client::GatewayClient::local();
+    if !client.is_reachable().await {
+        return Err(messages::text("gateway.not_running", &[]));
+    }
+    let payload = client
+        .send_message(&channel, &target, &text, account.as_deref())
+        .await
+        .map_err(|e| e.message())?;
+    Ok(BroadcastResult {
+        message_id: payload
+            .get("messageId")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        channel,
+        target,
+    })
+}
+
+// Persona wizard
+
+const PERSONA_SESSION_KEY: &str = "agent:main:clawnetes-persona";
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
+struct PersonaSpec {
+    name: String,
+    emoji: Option<String>,
+    user_name: Option<String>,
+    /// e.g. "friendly", "formal", "playful".
+    tone: Option<String>,
+    /// "concise", "balanced" or "detailed".
+    verbosity: Option<String>,
+    boundaries: Option<Vec<String>>,
+    specialties: Option<Vec<String>>,
+    /// "none", "sparing" or "frequent".
+    emoji_usage: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct IdentityFiles {
+    identity_md: String,
+    soul_md: String,
+    drafted_by_model: bool,
+}
+
+const PERSONA_VERBOSITY: &[&str] = &["concise", "balanced", "detailed"];
+const PERSONA_EMOJI_USAGE: &[&str] = &["none", "sparing", "frequent"];
+
+fn validate_persona_spec(spec: &PersonaSpec) -> Result<(), String> {
+    if spec.name.trim().is_empty() {
+        return Err("Agent name is required.".to_string());
+    }
+    for (field, value, allowed) in [
+        ("verbosity", &spec.verbosity, PERSONA_VERBOSITY),
+        ("emoji usage", &spec.emoji_usage, PERSONA_EMOJI_USAGE),
+    ] {
+        if let Some(value) = value {
+            if !allowed.contains(&value.as_str()) {
+                return Err(format!(
+                    "Unknown {} '{}'; expected one of {}",
+                    field,
+                    value,
+                    allowed.join(", ")
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn persona_list(items: &Option<Vec<String>>) -> Vec<String> {
+    items
+        .iter()
+        .flatten()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// One-line vibe summary, written as `**Vibe:**` so `get_current_config` picks it up.
+fn persona_vibe(spec: &PersonaSpec) -> String {
+    [spec.tone.as_deref(), spec.verbosity.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn build_identity_md(spec: &PersonaSpec) -> String {
+    let mut doc = format!(
+        "# IDENTITY.md - Who Am I?\n- **Name:** {}\n- **Emoji:** {}\n",
+        spec.name.trim(),
+        spec.emoji.as_deref().unwrap_or("🦞")
+    );
+    let vibe = persona_vibe(spec);
+    if !vibe.is_empty() {
+        doc.push_str(&format!("- **Vibe:** {}\n", vibe));
+    }
+    let specialties = persona_list(&spec.specialties);
+    if !specialties.is_empty() {
+        doc.push_str(&format!("- **Specialties:** {}\n", specialties.join(", ")));
+    }
+    doc.push_str("---\nManaged by Clawnetes.");
+    doc
+}
+
+fn build_soul_md(spec: &PersonaSpec) -> String {
+    let user = spec
+        .user_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .unwrap_or("my human");
+    let mut doc = format!("# SOUL.md\n## Mission\nServe {}.\n", user);
+
+    let mut style = Vec::new();
+    if let Some(tone) = spec.tone.as_deref().filter(|t| !t.trim().is_empty()) {
+        style.push(format!("- Keep a {} tone.", tone.trim()));
+    }
+    match spec.verbosity.as_deref() {
+        Some("concise") => style.push("- Answer briefly; skip preamble and recaps.".to_string()),
+        Some("detailed") => {
+            style.push("- Explain reasoning and include relevant detail.".to_string())
+        }
+        Some(_) => style.push("- Match the length of the answer to the question.".to_string()),
+        None => {}
+    }
+    match spec.emoji_usage.as_deref() {
+        Some("none") => style.push("- Do not use emoji.".to_string()),
+        Some("sparing") => style.push("- Use emoji sparingly.".to_string()),
+        Some("frequent") => style.push("- Feel free to use emoji.".to_string()),
+        _ => {}
+    }
+    if !style.is_empty() {
+        doc.push_str(&format!("\n## Style\n{}\n", style.join("\n")));
+    }
+
+    let specialties = persona_list(&spec.specialties);
+    if !specialties.is_empty() {
+        let lines: Vec<String> = specialties.iter().map(|s| format!("- {}", s)).collect();
+        doc.push_str(&format!("\n## Specialties\n{}\n", lines.join("\n")));
+    }
+
+    let boundaries = persona_list(&spec.boundaries);
+    if !boundaries.is_empty() {
+        let lines: Vec<String> = boundaries.iter().map(|b| format!("- {}", b)).collect();
+        doc.push_str(&format!("\n## Boundaries\n{}\n", lines.join("\n")));
+    }
+    doc.trim_end().to_string()
+}
+
+fn persona_draft_prompt(spec: &PersonaSpec, template: &str) -> String {
+    format!(
+        "Write a SOUL.md file for an AI assistant named {}. Expand the outline below into \
+         a richer document with the same sections, written in the second person. Keep every \
+         boundary exactly as stated. Reply with the Markdown document only.\n\n{}",
+        spec.name.trim(),
+        template
+    )
+}
+
+/// Strips a surrounding ```markdown fence the model may add.
+fn strip_markdown_fence(reply: &str) -> String {
+    let trimmed = reply.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed.to_string();
+    };
+    let body = rest.split_once('\n').map(|(_, body)| body).unwrap_or("");
+    body.trim_end().trim_end_matches("```").trim().to_string()
+}
+
+/// Builds IDENTITY.md and SOUL.md from structured persona fields. With `use_model`, the
+/// SOUL.md outline is expanded by the agent's configured model through the local gateway.
+#[command]
+async fn generate_identity_files(
+    persona_spec: PersonaSpec,
+    use_model: Option<bool>,
+) -> Result<IdentityFiles, String> {
+    validate_persona_spec(&persona_spec)?;
+    let identity_md = build_identity_md(&persona_spec);
+    let template = build_soul_md(&persona_spec);
+    if !use_model.unwrap_or(false) {
+        return Ok(IdentityFiles {
+            identity_md,
+            soul_md: template,
+            drafted_by_model: false,
+        });
+    }
+
+    let prompt = persona_draft_prompt(&persona_spec, &template);
+    let reply = run_gateway_chat(PERSONA_SESSION_KEY, &prompt, |_, _| {})
+        .await
+        .map_err(|e| format!("Could not draft SOUL.md with the configured model: {}", e))?;
+    let drafted = strip_markdown_fence(&reply);
+    Ok(IdentityFiles {
+        identity_md,
+        drafted_by_model: !drafted.is_empty(),
+        soul_md: if drafted.is_empty() {
+            template
+        } else {
+            drafted
+        },
+    })
+}
+
+// Security audit
+
+/// Runs `action` with `$f` set to each existing secret file under the local root. Goes
+/// through the shell so that on Windows it acts on the files inside WSL.
+fn for_each_secret_file(action: &str) -> Result<String, String> {
+    shell_command(&format!(
+        "cd \"{}\" 2>/dev/null || exit 0; for f in {}; do [ -f \"$f\" ] && {}; done; true",
+        paths::shell_root(),
+        security_audit::SECRET_FILES.join(" "),
+        action
+    ))
+}
+
+fn local_secret_file_modes() -> Vec<security_audit::FileMode> {
+    for_each_secret_file("(stat -c '%a %n' \"$f\" 2>/dev/null || stat -f '%Lp %N' \"$f\")")
+        .map(|output| security_audit::parse_stat_output(&output))
+        .unwrap_or_default()
+}
+
+fn generate_gateway_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// Restricts the local OpenClaw root, logs and workspace to the current user. On Windows
+/// these live inside WSL, where the same modes apply.
+fn harden_local_permissions() -> Result<Vec<security_audit::PermissionChange>, String> {
+    let workspace = read_local_openclaw_config().ok().and_then(|config| {
+        config
+            .pointer("/agents/defaults/workspace")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    });
+    let output = shell_command(&security_audit::harden_script(
+        &paths::shell_root(),
+        workspace.as_deref(),
+    ))
+    .map_err(|e| format!("Failed to restrict permissions: {}", e))?;
+    Ok(security_audit::parse_permission_changes(&output))
+}
+
+/// Limits the app's own data directory (saved license, settings, crash log) to the current
+/// user: mode 0700 on Unix, an owner-only ACL without inheritance on Windows.
+fn harden_app_data_dir(dir: &Path) -> Result<Option<security_audit::PermissionChange>, String> {
+    let path = dir.to_string_lossy().to_string();
+    #[cfg(unix)]
+    {
+        let mode = fs::metadata(dir)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?
+            .permissions()
+            .mode()
+            & 0o777;
+        if mode == 0o700 {
+            return Ok(None);
+        }
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to restrict {}: {}", path, e))?;
+        Ok(Some(security_audit::PermissionChange {
+            path,
+            from: format!("{:o}", mode),
+            to: "700".to_string(),
+        }))
+    }
+    #[cfg(windows)]
+    {
+        let user = std::env::var("USERNAME").map_err(|_| "Could not determine the current user")?;
+        let output = Command::new("icacls")
+            .arg(&path)
+            .args(["/inheritance:r", "/grant:r"])
+            .arg(format!("{}:(OI)(CI)F", user))
+            .output()
+            .map_err(|e| format!("Failed to run icacls: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "icacls could not restrict {}: {}",
+                path,
+                String::from_utf8_lossy(&output.stdout).trim()
+            ));
+        }
+        Ok(Some(security_audit::PermissionChange {
+            path,
+            from: "inherited".to_string(),
+            to: "owner only".to_string(),
+        }))
+    }
+}
+
+/// Sets owner-only permissions on `openclaw.json`, auth profiles, credentials, logs, the
+/// workspace and the app's data directory. Returns what was changed.
+#[command]
+fn harden_permissions(
+    app: tauri::AppHandle,
+) -> Result<Vec<security_audit::PermissionChange>, String> {
+    let mut changes = harden_local_permissions()?;
+    changes.extend(harden_app_data_dir(&app_data_dir(&app)?)?);
+    Ok(changes)
+}
+
+/// Scores the local config and secret files and lists what to fix.
+#[command]
+fn audit_security() -> Result<security_audit::Report, String> {
+    let config = read_local_openclaw_config()?;
+    Ok(security_audit::audit(&config, &local_secret_file_modes()))
+}
+
+/// Applies a fix offered by `audit_security` and returns the new report. Changing gateway
+/// auth restarts the gateway; clients holding the old token need the new one.
+#[command]
+fn apply_security_fix(
+    app: tauri::AppHandle,
+    fix: String,
+) -> Result<security_audit::Report, String> {
+    // Fixes that leave the config alone (permissions) do not rewrite it.
+    let restart = update_local_openclaw_config(|config| {
+        let root = config
+            .as_object_mut()
+            .ok_or("openclaw.json is not a JSON object")?;
+        let mut restart = false;
+        match fix.as_str() {
+            security_audit::FIX_ENABLE_TOKEN_AUTH | security_audit::FIX_REGENERATE_TOKEN => {
+                let auth = object_entry(object_entry(root, "gateway"), "auth");
+                let token = auth.get("token").and_then(|t| t.as_str());
+                if fix == security_audit::FIX_REGENERATE_TOKEN
+                    || security_audit::token_weakness(token).is_some()
+                {
+                    auth.insert(
+                        "token".to_string(),
+                        serde_json::json!(generate_gateway_token()),
+                    );
+                }
+                auth.insert("mode".to_string(), serde_json::json!("token"));
+                restart = true;
+            }
+            security_audit::FIX_RESTRICT_PERMISSIONS => {
+                harden_local_permissions()?;
+            }
+            other => {
+                let channel = other
+                    .strip_prefix(security_audit::FIX_DM_PAIRING)
+                    .and_then(|rest| rest.strip_prefix(':'))
+                    .ok_or_else(|| format!("Unknown security fix: {}", other))?;
+                let channels = object_entry(root, "channels");
+                if !channels.contains_key(channel) {
+                    return Err(format!("Channel '{}' is not configured", channel));
+                }
+                object_entry(channels, channel)
+                    .insert("dmPolicy".to_string(), serde_json::json!("pairing"));
+            }
+        }
+        Ok(restart)
+    })?;
+    if restart {
+        restart_local_gateway()?;
+        notify_gateway_restarted(&app);
+    }
+    audit_security()
+}
+
+// Config repair
+
+const CONFIG_FIX_SESSION_KEY: &str = "agent:main:clawnetes-config-fix";
+/// Lower-cased key fragments whose string values are replaced before a config leaves the
+/// machine.
+const SECRET_KEY_HINTS: &[&str] = &["token", "secret", "password", "apikey", "api_key", "key"];
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct ConfigFixSuggestion {
+    errors: String,
+    proposed_config: String,
+    /// Line diff from the current file: `-`/`+`/` ` prefixed lines.
+    diff: String,
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEY_HINTS.iter().any(|hint| key.contains(hint))
+}
+
+/// Replaces the string values of secret-looking keys in `raw` with numbered placeholders.
+/// Scans string tokens rather than parsing, so it also covers configs that no longer parse,
+/// whatever their layout (minified, one line, JSON5 quotes or bare keys). Returns the
+/// redacted text and the originals, indexed by placeholder number.
+fn redact_config_secrets(raw: &str) -> (String, Vec<String>) {
+    let bytes = raw.as_bytes();
+    let followed_by_colon = |from: usize| {
+        bytes[from.min(bytes.len())..]
+            .iter()
+            .find(|b| !b.is_ascii_whitespace())
+            == Some(&b':')
+    };
+    let mut secrets = Vec::new();
+    let mut redacted = String::with_capacity(raw.len());
+    let mut copied = 0;
+    // The key whose value comes next.
+    let mut key: Option<&str> = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'"' | b'\'') => {
+                let start = i + 1;
+                let mut end = start;
+                while end < bytes.len() && bytes[end] != quote {
+                    end += if bytes[end] == b'\\' { 2 } else { 1 };
+                }
+                let end = end.min(bytes.len());
+                let text = &raw[start..end];
+                if followed_by_colon(end + 1) {
+                    key = Some(text);
+                } else {
+                    if key.is_some_and(is_secret_key) && !text.is_empty() && !text.starts_with("${")
+                    {
+                        secrets.push(text.to_string());
+                        redacted.push_str(&raw[copied..start]);
+                        redacted.push_str(&format!("__REDACTED_{}__", secrets.len()));
+                        copied = end;
+                    }
+                    key = None;
+                }
+                i = end + 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = raw[i..].find('\n').map_or(bytes.len(), |n| i + n);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = raw[i + 2..].find("*/").map_or(bytes.len(), |n| i + n + 4);
+            }
+            b if b.is_ascii_alphabetic() || b == b'_' || b == b'$' => {
+                let end = bytes[i..]
+                    .iter()
+                    .position(|b| !(b.is_ascii_alphanumeric() || *b == b'_' || *b == b'$'))
+                    .map_or(bytes.len(), |n| i + n);
+                key = followed_by_colon(end).then(|| &raw[i..end]);
+                i = end;
+            }
+            b if b == b':' || b.is_ascii_whitespace() => i += 1,
+            _ => {
+                key = None;
+                i += 1;
+            }
+        }
+    }
+    redacted.push_str(&raw[copied..]);
+    (redacted, secrets)
+}
+
+fn restore_config_secrets(redacted: &str, secrets: &[String]) -> String {
+    secrets
+        .iter()
+        .enumerate()
+        .rev()
+        .fold(redacted.to_string(), |text, (i, secret)| {
+            text.replace(&format!("__REDACTED_{}__", i + 1), secret)
+        })
+}
+
+/// Minimal line diff (longest common subsequence) for showing a proposed change.
+fn config_line_diff(old: &str, new: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push(format!("  {}", a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push(format!("- {}", a[i]));
+            i += 1;
+        } else {
+            out.push(format!("+ {}", b[j]));
+            j += 1;
+        }
+    }
+    out.join("\n")
+}
+
+fn config_fix_prompt(redacted_config: &str, errors: &str) -> String {
+    format!(
+        "The OpenClaw config file openclaw.json below is broken. Fix it with the smallest \
+         change that resolves the errors, keeping every other setting and every \
+         __REDACTED_N__ placeholder exactly as it is. Reply with the complete corrected \
+         file as JSON only.\n\nErrors:\n{}\n\nopenclaw.json:\n{}",
+        errors.trim(),
+        redacted_config
+    )
+}
+
+/// Parse error (if any) plus `openclaw config validate` output for the local config.
+fn local_config_errors(raw: &str) -> String {
+    let mut errors = Vec::new();
+    if let Err(e) = serde_json::from_str::<serde_json::Value>(raw) {
+        errors.push(format!("JSON parse error: {}", e));
+    }
+    if let Ok(output) = process::output("openclaw", &["config", "validate"]) {
+        if !output.success() {
+            errors.push(
+                format!("{}\n{}", output.stdout, output.stderr)
+                    .trim()
+                    .to_string(),
+            );
+        }
+    }
+    errors.join("\n")
+}
+
+/// Asks the configured model (through the local gateway) to repair `openclaw.json`. Secret
+/// values are replaced with placeholders before sending and restored in the proposal;
+/// nothing is written until `apply_config_fix`.
+#[command]
+async fn suggest_config_fix() -> Result<ConfigFixSuggestion, String> {
+    let raw = read_local_openclaw_file("openclaw.json")?;
+    let errors = local_config_errors(&raw);
+    if errors.is_empty() {
+        return Err("openclaw.json parses and validates; there is nothing to fix.".to_string());
+    }
+
+    let (redacted, secrets) = redact_config_secrets(&raw);
+    let reply = run_gateway_chat(
+        CONFIG_FIX_SESSION_KEY,
+        &config_fix_prompt(&redacted, &errors),
+        |_, _| {},
+    )
+    .await
+    .map_err(|e| format!("Could not reach the configured model: {}", e))?;
+    let proposed = restore_config_secrets(&strip_markdown_fence(&reply), &secrets);
+    let parsed: serde_json::Value = serde_json::from_str(&proposed)
+        .map_err(|e| format!("The model's proposal is not valid JSON: {}", e))?;
+    let proposed_config = serde_json::to_string_pretty(&parsed).map_err(|e| e.to_string())?;
+
+    Ok(ConfigFixSuggestion {
+        diff: config_line_diff(&raw, &proposed_config),
+        errors,
+        proposed_config,
+    })
+}
+
+/// Writes an approved `suggest_config_fix` proposal, keeping the broken file as
+/// `openclaw.json.bak`.
+#[command]
+async fn apply_config_fix(proposed_config: String) -> Result<(), String> {
+    let parsed: serde_json::Value = serde_json::from_str(&proposed_config)
+        .map_err(|e| format!("Proposed config is not valid JSON: {}", e))?;
+    {
+        let _config = lifecycle::config_lock();
+        if let Ok(current) = read_local_openclaw_file("openclaw.json") {
+            write_local_openclaw_file("openclaw.json.bak", &current)?;
+        }
+        store_local_openclaw_config(&parsed)?;
+    }
+    reload_gateway_config().await
+}
+
+// Section-scoped config editing
+
+/// The value at dotted `path` in the local openclaw.json (`null` when unset).
+#[command]
+fn get_config_section(path: String) -> Result<serde_json::Value, String> {
+    config_sections::get(&read_local_openclaw_config()?, &path)
+}
+
+/// Replaces one section of the local openclaw.json after validating it, leaving the rest of
+/// the file untouched, and asks the gateway to reload.
+#[command]
+async fn update_config_section(
+    path: String,
+    value: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let section = update_local_openclaw_config(|config| {
+        config_sections::update(config, &path, value)?;
+        config_sections::get(config, &path)
+    })?;
+    reload_gateway_config().await?;
+    Ok(section)
+}
+
+// Model parameters
+
+const REASONING_EFFORTS: &[&str] = &["off", "minimal", "low", "medium", "high"];
+const COMPACTION_MODES: &[&str] = &["default", "safeguard"];
+const MAX_OUTPUT_TOKENS_LIMIT: u32 = 200_000;
+
+/// Tunables for one model. Temperature, max tokens and reasoning effort live in
+/// `agents.defaults.models.<model>.params`; compaction is agent-wide
+/// (`agents.defaults.compaction.mode`).
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+struct ModelParams {
+    temperature: Option<f64>,
+    max_tokens: Option<u32>,
+    reasoning_effort: Option<String>,
+    compaction_mode: Option<String>,
+}
+
+/// Highest temperature the provider's API accepts.
+fn max_temperature_for_provider(provider: &str) -> f64 {
+    match provider {
+        "anthropic" => 1.0,
+        _ => 2.0,
+    }
+}
+
+fn validate_model_params(model: &str, params: &ModelParams) -> Result<(), String> {
+    let Some((provider, _)) = model.split_once('/') else {
+        return Err(format!(
+            "Invalid model '{}'; expected provider/model",
+            model
+        ));
+    };
+    if let Some(temperature) = params.temperature {
+        let max = max_temperature_for_provider(provider);
+        if !(0.0..=max).contains(&temperature) {
+            return Err(format!(
+                "Temperature for {} must be between 0 and {}",
+                provider, max
+            ));
+        }
+    }
+    if let Some(max_tokens) = params.max_tokens {
+        if max_tokens == 0 || max_tokens > MAX_OUTPUT_TOKENS_LIMIT {
+            return Err(format!(
+                "Max tokens must be between 1 and {}",
+                MAX_OUTPUT_TOKENS_LIMIT
+            ));
+        }
+    }
+    if let Some(effort) = &params.reasoning_effort {
+        if !REASONING_EFFORTS.contains(&effort.as_str()) {
+            return Err(format!(
+                "Unknown reasoning effort '{}'; expected one of {}",
+                effort,
+                REASONING_EFFORTS.join(", ")
+            ));
+        }
+        if effort == "minimal" && provider != "openai" && provider != "openai-codex" {
+            return Err(
+                "Reasoning effort 'minimal' is only supported by OpenAI models".to_string(),
+            );
+        }
+    }
+    if let Some(mode) = &params.compaction_mode {
+        if !COMPACTION_MODES.contains(&mode.as_str()) {
+            return Err(format!(
+                "Unknown compaction mode '{}'; expected one of {}",
+                mode,
+                COMPACTION_MODES.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn read_model_params(config: &serde_json::Value, model: &str) -> ModelParams {
+    let defaults = config.get("agents").and_then(|a| a.get("defaults"));
+    let params = defaults
+        .and_then(|d| d.get("models"))
+        .and_then(|m| m.get(model))
+        .and_then(|m| m.get("params"));
+    let field = |key: &str| params.and_then(|p| p.get(key));
+    ModelParams {
+        temperature: field("temperature").and_then(|v| v.as_f64()),
+        max_tokens: field("maxTokens")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32),
+        reasoning_effort: field("reasoningEffort")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        compaction_mode: defaults
+            .and_then(|d| d.get("compaction"))
+            .and_then(|c| c.get("mode"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+    }
+}
+
+/// Writes `params` for `model`; unset fields are removed so the provider default applies.
+fn apply_model_params(config: &mut serde_json::Value, model: &str, params: &ModelParams) {
+    if !config.is_object() {
+        *config = serde_json::json!({});
+    }
+    let defaults = config
+        .as_object_mut()
+        .map(|root| root.entry("agents").or_insert(serde_json::json!({})))
+        .and_then(|agents| agents.as_object_mut())
+        .map(|agents| agents.entry("defaults").or_insert(serde_json::json!({})))
+        .and_then(|defaults| defaults.as_object_mut());
+    let Some(defaults) = defaults else {
+        return;
+    };
+
+    if let Some(mode) = &params.compaction_mode {
+        let compaction = defaults
+            .entry("compaction")
+            .or_insert(serde_json::json!({}));
+        if let Some(compaction) = compaction.as_object_mut() {
+            compaction.insert("mode".to_string(), serde_json::json!(mode));
+        }
+    }
+
+    let entry = defaults
+        .entry("models")
+        .or_insert(serde_json::json!({}))
+        .as_object_mut()
+        .map(|models| {
+            models
+                .entry(model.to_string())
+                .or_insert(serde_json::json!({}))
+        });
+    let Some(entry) = entry.and_then(|e| e.as_object_mut()) else {
+        return;
+    };
+    let mut model_params = entry
+        .get("params")
+        .and_then(|p| p.as_object())
+        .cloned()
+        .unwrap_or_default();
+    for (key, value) in [
+        (
+            "temperature",
+            params.temperature.map(|t| serde_json::json!(t)),
+        ),
+        ("maxTokens", params.max_tokens.map(|t| serde_json::json!(t))),
+        (
+            "reasoningEffort",
+            params
+                .reasoning_effort
+                .as_ref()
+                .map(|e| serde_json::json!(e)),
+        ),
+    ] {
+        match value {
+            Some(value) => {
+                model_params.insert(key.to_string(), value);
+            }
+            None => {
+                model_params.remove(key);
+            }
+        }
+    }
+    if model_params.is_empty() {
+        entry.remove("params");
+    } else {
+        entry.insert(
+            "params".to_string(),
+            serde_json::Value::Object(model_params),
+        );
+    }
+}
+
+#[command]
+fn get_model_params(model: String) -> Result<ModelParams, String> {
+    Ok(read_model_params(&read_local_openclaw_config()?, &model))
+}
+
+#[command]
+async fn set_model_params(model: String, params: ModelParams) -> Result<ModelParams, String> {
+    validate_model_params(&model, &params)?;
+    let config = update_local_openclaw_config(|config| {
+        apply_model_params(config, &model, &params);
+        Ok(config.clone())
+    })?;
+    reload_gateway_config().await?;
+    Ok(read_model_params(&config, &model))
+}
+
+// Config file watcher
+
+const CONFIG_WATCH_POLL_SECS: u64 = 2;
+
+#[derive(serde::Serialize, Clone)]
+struct ConfigChangedEvent {
+    content: String,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct ConfigMerge {
+    merged: String,
+    /// Dotted paths changed on both sides; the user's value was kept.
+    conflicts: Vec<String>,
+}
+
+fn config_content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Records `content` as the app's own view of openclaw.json so the watcher does not report
+/// the app's writes as external changes.
+fn remember_config_content(content: &str) {
+    if let Ok(mut known) = KNOWN_CONFIG_HASH.lock() {
+        *known = Some(config_content_hash(content));
+    }
+}
+
+fn run_config_watcher(app: tauri::AppHandle) {
+    while CONFIG_WATCH_ACTIVE.load(Ordering::Relaxed)
+        && lifecycle::sleep(Duration::from_secs(CONFIG_WATCH_POLL_SECS))
+    {
+        let Ok(content) = read_local_openclaw_file("openclaw.json") else {
+            continue;
+        };
+        let hash = config_content_hash(&content);
+        let changed = match KNOWN_CONFIG_HASH.lock() {
+            Ok(mut known) if known.as_deref() != Some(hash.as_str()) => {
+                *known = Some(hash);
+                true
+            }
+            _ => false,
+        };
+        if changed && CONFIG_WATCH_ACTIVE.load(Ordering::Relaxed) {
+            let _ = app.emit_all("config-changed-externally", ConfigChangedEvent { content });
+        }
+    }
+    CONFIG_WATCH_RUNNING.store(false, Ordering::Relaxed);
+}
+
+/// Three-way merge of JSON values: keeps whichever side changed relative to `base`,
+/// recursing into objects changed on both sides. Values changed differently on both sides
+/// keep `ours` and are listed in `conflicts`.
+fn merge_json_three_way(
+    base: Option<&serde_json::Value>,
+    ours: &serde_json::Value,
+    theirs: &serde_json::Value,
+    path: &str,
+    conflicts: &mut Vec<String>,
+) -> serde_json::Value {
+    if ours == theirs || base == Some(theirs) {
+        return ours.clone();
+    }
+    if base == Some(ours) {
+        return theirs.clone();
+    }
+    if let (Some(our_map), Some(their_map)) = (ours.as_object(), theirs.as_object()) {
+        let base_map = base.and_then(|b| b.as_object());
+        let mut merged = serde_json::Map::new();
+        let keys: std::collections::BTreeSet<&String> =
+            our_map.keys().chain(their_map.keys()).collect();
+        for key in keys {
+            let child_path = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            let base_value = base_map.and_then(|m| m.get(key));
+            match (our_map.get(key), their_map.get(key)) {
+                (Some(o), Some(t)) => {
+                    let value = merge_json_three_way(base_value, o, t, &child_path, conflicts);
+                    merged.insert(key.clone(), value);
+                }
+                (Some(o), None) => {
+                    // Removed externally: drop it unless we edited it too.
+                    if base_value.is_some() && base_value != Some(o) {
+                        conflicts.push(child_path);
+                        merged.insert(key.clone(), o.clone());
+                    } else if base_value.is_none() {
+                        merged.insert(key.clone(), o.clone());
+                    }
+                }
+                (None, Some(t)) => {
+                    if base_value.is_some() && base_value != Some(t) {
+                        conflicts.push(child_path);
+                    } else if base_value.is_none() {
+                        merged.insert(key.clone(), t.clone());
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+        return serde_json::Value::Object(merged);
+    }
+    conflicts.push(path.to_string());
+    ours.clone()
+}
+
+/// Starts or stops watching openclaw.json while the config editor is open. Changes made
+/// outside the app (CLI, gateway) emit `config-changed-externally` with the new content.
+#[command]
+fn watch_config(app: tauri::AppHandle, active: bool) -> Result<(), String> {
+    CONFIG_WATCH_ACTIVE.store(active, Ordering::Relaxed);
+    if !active {
+        return Ok(());
+    }
+    if let Ok(content) = read_local_openclaw_file("openclaw.json") {
+        remember_config_content(&content);
+    }
+    if !CONFIG_WATCH_RUNNING.swap(true, Ordering::Relaxed) {
+        lifecycle::spawn("config-watcher", move || run_config_watcher(app));
+    }
+    Ok(())
+}
+
+/// Reconciles the editor's unsaved `ours` with an external change. `base` is the content
+/// the editor loaded; `theirs` defaults to the file currently on disk.
+#[command]
+fn merge_config_changes(
+    base: String,
+    ours: String,
+    theirs: Option<String>,
+) -> Result<ConfigMerge, String> {
+    let parse = |label: &str, raw: &str| {
+        serde_json::from_str::<serde_json::Value>(raw)
+            .map_err(|e| format!("Failed to parse {} config: {}", label, e))
+    };
+    let theirs = match theirs {
+        Some(theirs) => theirs,
+        None => read_local_openclaw_file("openclaw.json")?,
+    };
+    let base = parse("original", &base)?;
+    let ours = parse("edited", &ours)?;
+    let theirs = parse("external", &theirs)?;
+
+    let mut conflicts = Vec::new();
+    let merged = merge_json_three_way(Some(&base), &ours, &theirs, "", &mut conflicts);
+    Ok(ConfigMerge {
+        merged: serde_json::to_string_pretty(&merged).map_err(|e| e.to_string())?,
+        conflicts,
+    })
+}
+
+// Session browser
+
+const SESSIONS_DIR: &str = "agents/main/sessions";
+const SESSION_ARCHIVE_DIR: &str = "agents/main/sessions/archive";
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct SessionSummary {
+    key: String,
+    session_id: String,
+    updated_at: Option<u64>,
+    channel: Option<String>,
+    display_name: Option<String>,
+    archived: bool,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct TranscriptEntry {
+    role: String,
+    text: String,
+    timestamp: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct SessionTranscript {
+    session_id: String,
+    total: usize,
+    offset: usize,
+    entries: Vec<TranscriptEntry>,
+}
+
+fn is_valid_session_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn session_summary_from_entry(key: &str, entry: &serde_json::Value) -> Option<SessionSummary> {
+    let session_id = entry.get("sessionId").and_then(|v| v.as_str())?;
+    let text = |field: &str| {
+        entry
+            .get(field)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    };
+    Some(SessionSummary {
+        key: key.to_string(),
+        session_id: session_id.to_string(),
+        updated_at: entry.get("updatedAt").and_then(|v| v.as_u64()),
+        channel: text("channel").or_else(|| text("lastChannel")),
+        display_name: text("displayName").or_else(|| text("label")),
+        archived: false,
+    })
+}
+
+/// Accepts either the on-disk `sessions.json` map or a gateway `sessions.list` payload.
+fn parse_session_list(value: &serde_json::Value) -> Vec<SessionSummary> {
+    let mut sessions: Vec<SessionSummary> = match value.get("sessions") {
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| {
+                let key = item.get("key").and_then(|v| v.as_str()).unwrap_or("");
+                session_summary_from_entry(key, item)
+            })
+            .collect(),
+        _ => value
+            .as_object()
+            .map(|map| {
+                map.iter()
+                    .filter_map(|(key, entry)| session_summary_from_entry(key, entry))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    sessions
+}
+
+fn parse_transcript_line(line: &str) -> Option<TranscriptEntry> {
+    let value: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    if value.get("type").and_then(|v| v.as_str()) != Some("message") {
+        return None;
+    }
+    let message = value.get("message")?;
+    Some(TranscriptEntry {
+        role: message
+            .get("role")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string(),
+        text: chat_message_text(message).unwrap_or_default(),
+        timestamp: value
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+    })
+}
+
+fn paginate_transcript(
+    content: &str,
+    offset: usize,
+    limit: usize,
+) -> (usize, Vec<TranscriptEntry>) {
+    let entries: Vec<TranscriptEntry> = content.lines().filter_map(parse_transcript_line).collect();
+    let total = entries.len();
+    (
+        total,
+        entries.into_iter().skip(offset).take(limit).collect(),
+    )
+}
+
+fn list_archived_session_ids() -> Vec<String> {
+    paths::local_path(SESSION_ARCHIVE_DIR)
+        .and_then(|dir| list_local_dir(&dir))
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|name| name.strip_suffix(".jsonl"))
+        .filter(|id| is_valid_session_id(id))
+        .map(|id| id.to_string())
+        .collect()
+}
+
+/// Removes the store entry for `id` so the gateway starts a fresh session for that key.
+fn remove_session_store_entry(id: &str) -> Result<(), String> {
+    let store_path = format!("{}/sessions.json", SESSIONS_DIR);
+    let raw = read_local_openclaw_file(&store_path).unwrap_or_default();
+    let mut store: serde_json::Value =
+        serde_json::from_str(&raw).unwrap_or_else(|_| serde_json::json!({}));
+    if let Some(map) = store.as_object_mut() {
+        map.retain(|_, entry| entry.get("sessionId").and_then(|v| v.as_str()) != Some(id));
+    }
+    let updated = serde_json::to_string_pretty(&store).map_err(|e| e.to_string())?;
+    write_local_openclaw_file(&store_path, &updated)
+}
+
+#[command]
+async fn list_sessions(include_archived: Option<bool>) -> Result<Vec<SessionSummary>, String> {
+    let client = gateway_client::GatewayClient::local().single_attempt();
+    let from_gateway = if client.is_reachable().await {
+        client
+            .list_sessions(serde_json::json!({}))
+            .await
+            .ok()
+            .map(|payload| parse_session_list(&payload))
+    } else {
+        None
+    };
+
+    let mut sessions = match from_gateway {
+        Some(sessions) => sessions,
+        None => {
+            let raw = read_local_openclaw_file(&format!("{}/sessions.json", SESSIONS_DIR))
+                .unwrap_or_default();
+            let store: serde_json::Value =
+                serde_json::from_str(&raw).unwrap_or_else(|_| serde_json::json!({}));
+            parse_session_list(&store)
+        }
+    };
+
+    if include_archived.unwrap_or(false) {
+        sessions.extend(
+            list_archived_session_ids()
+                .into_iter()
+                .map(|id| SessionSummary {
+                    key: format!("archived:{}", id),
+                    session_id: id,
+                    updated_at: None,
+                    channel: None,
+                    display_name: None,
+                    archived: true,
+                }),
+        );
+    }
+    Ok(sessions)
+}
+
+#[command]
+fn get_session(id: String, offset: usize, limit: usize) -> Result<SessionTranscript, String> {
+    if !is_valid_session_id(&id) {
+        return Err(format!("Invalid session id: {}", id));
+    }
+    let content = read_local_openclaw_file(&format!("{}/{}.jsonl", SESSIONS_DIR, id))
+        .or_else(|_| read_local_openclaw_file(&format!("{}/{}.jsonl", SESSION_ARCHIVE_DIR, id)))
+        .map_err(|_| format!("Session {} not found", id))?;
+    let (total, entries) = paginate_transcript(&content, offset, limit);
+    Ok(SessionTranscript {
+        session_id: id,
+        total,
+        offset,
+        entries,
+    })
+}
+
+#[command]
+fn delete_session(id: String) -> Result<(), String> {
+    if !is_valid_session_id(&id) {
+        return Err(format!("Invalid session id: {}", id));
+    }
+    remove_session_store_entry(&id)?;
+    for dir in [SESSIONS_DIR, SESSION_ARCHIVE_DIR] {
+        remove_local_file(&paths::local_path(&format!("{}/{}.jsonl", dir, id))?)
+            .map_err(|e| format!("Failed to delete session transcript: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Moves the transcript into `sessions/archive` so it stays readable but no longer resumes.
+#[command]
+fn archive_session(id: String) -> Result<(), String> {
+    if !is_valid_session_id(&id) {
+        return Err(format!("Invalid session id: {}", id));
+    }
+    rename_local_file(
+        &paths::local_path(&format!("{}/{}.jsonl", SESSIONS_DIR, id))?,
+        &paths::local_path(&format!("{}/{}.jsonl", SESSION_ARCHIVE_DIR, id))?,
+    )
+    .map_err(|e| format!("Failed to archive session: {}", e))?;
+    remove_session_store_entry(&id)
+}
+
+// Usage and cost tracking
+
+/// USD per million tokens (input, output). Matched by substring, most specific first.
+const MODEL_PRICING: &[(&str, f64, f64)] = &[
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-haiku-4", 1.0, 5.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4.1-nano", 0.1, 0.4),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-5-nano", 0.05, 0.4),
+    ("gpt-5-mini", 0.25, 2.0),
+    ("gpt-5", 1.25, 10.0),
+    ("o4-mini", 1.1, 4.4),
+    ("o3", 2.0, 8.0),
+    ("gemini-2.5-flash", 0.3, 2.5),
+    ("gemini-2.5-pro", 1.25, 10.0),
+    ("deepseek", 0.27, 1.1),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+struct UsageRecord {
+    day: String,
+    provider: String,
+    model: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_read_tokens: u64,
+    cache_write_tokens: u64,
+    recorded_cost: Option<f64>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+struct UsageBucket {
+    day: String,
+    provider: String,
+    model: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_read_tokens: u64,
+    cache_write_tokens: u64,
+    requests: u64,
+    estimated_cost_usd: f64,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct UsageSummary {
+    range: String,
+    since: Option<String>,
+    total_tokens: u64,
+    total_requests: u64,
+    estimated_cost_usd: f64,
+    buckets: Vec<UsageBucket>,
+}
+
+/// Converts days since 1970-01-01 to a `YYYY-MM-DD` civil date.
+fn civil_date_from_unix_days(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Number of days before today to include; `None` means all history.
+fn usage_range_days(range: &str) -> Result<Option<i64>, String> {
+    match range.trim().to_lowercase().as_str() {
+        "all" | "" => Ok(None),
+        "today" | "day" => Ok(Some(0)),
+        "week" => Ok(Some(6)),
+        "month" => Ok(Some(29)),
+        other => other
+            .strip_suffix('d')
+            .and_then(|n| n.parse::<i64>().ok())
+            .filter(|n| *n > 0)
+            .map(|n| Some(n - 1))
+            .ok_or_else(|| format!("Unknown usage range: {}", range)),
+    }
+}
+
+fn model_pricing(model: &str) -> Option<(f64, f64)> {
+    let model = model.to_lowercase();
+    MODEL_PRICING
+        .iter()
+        .find(|(name, _, _)| model.contains(name))
+        .map(|(_, input, output)| (*input, *output))
+}
+
+/// Cache reads bill at a tenth of the input rate and cache writes at 1.25×, as most
+/// providers do; unknown models fall back to the cost recorded in the transcript.
+fn estimate_usage_cost(record: &UsageRecord) -> f64 {
+    match model_pricing(&record.model) {
+        Some((input, output)) => {
+            (record.input_tokens as f64 * input
+                + record.output_tokens as f64 * output
+                + record.cache_read_tokens as f64 * input * 0.1
+                + record.cache_write_tokens as f64 * input * 1.25)
+                / 1_000_000.0
+        }
+        None => record.recorded_cost.unwrap_or(0.0),
+    }
+}
+
+fn parse_usage_record(line: &str) -> Option<UsageRecord> {
+    let value: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    let message = value.get("message")?;
+    let usage = message.get("usage")?;
+    let tokens = |field: &str| usage.get(field).and_then(|v| v.as_u64()).unwrap_or(0);
+    let timestamp = value
+        .get("timestamp")
+        .and_then(|v| v.as_str())
+        .or_else(|| message.get("timestamp").and_then(|v| v.as_str()))?;
+    Some(UsageRecord {
+        day: timestamp.chars().take(10).collect(),
+        provider: message
+            .get("provider")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string(),
+        model: message
+            .get("model")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string(),
+        input_tokens: tokens("input"),
+        output_tokens: tokens("output"),
+        cache_read_tokens: tokens("cacheRead"),
+        cache_write_tokens: tokens("cacheWrite"),
+        recorded_cost: usage
+            .get("cost")
+            .and_then(|c| c.get("total").or(Some(c)))
+            .and_then(|v| v.as_f64()),
+    })
+}
+
+fn aggregate_usage(records: &[UsageRecord], since: Option<&str>) -> Vec<UsageBucket> {
+    let mut buckets: std::collections::BTreeMap<(String, String, String), UsageBucket> =
+        std::collections::BTreeMap::new();
+    for record in records {
+        if since.map(|s| record.day.as_str() < s).unwrap_or(false) {
+            continue;
+        }
+        let bucket = buckets
+            .entry((
+                record.day.clone(),
+                record.provider.clone(),
+                record.model.clone(),
+            ))
+            .or_insert_with(|| UsageBucket {
+                day: record.day.clone(),
+                provider: record.provider.clone(),
+                model: record.model.clone(),
+                ..Default::default()
+            });
+        bucket.input_tokens += record.input_tokens;
+        bucket.output_tokens += record.output_tokens;
+        bucket.cache_read_tokens += record.cache_read_tokens;
+        bucket.cache_write_tokens += record.cache_write_tokens;
+        bucket.requests += 1;
+        bucket.estimated_cost_usd += estimate_usage_cost(record);
+    }
+    buckets.into_values().collect()
+}
+
+fn today_unix_days() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| (d.as_secs() / 86_400) as i64)
+        .unwrap_or(0)
+}
+
+fn collect_usage_records() -> Result<Vec<UsageRecord>, String> {
+    collect_agent_usage_records("*")
+}
+
+/// Usage from one agent's transcripts (`agent` is an id, or `*` for all agents).
+fn collect_agent_usage_records(agent: &str) -> Result<Vec<UsageRecord>, String> {
+    let transcripts = shell_command(&format!(
+        "cat \"{root}\"/agents/{agent}/sessions/*.jsonl \"{root}\"/agents/{agent}/sessions/archive/*.jsonl 2>/dev/null; true",
+        root = paths::shell_root(),
+        agent = agent
+    ))?;
+    Ok(transcripts.lines().filter_map(parse_usage_record).collect())
+}
+
+#[command]
+fn get_usage_summary(range: String) -> Result<UsageSummary, String> {
+    let since = usage_range_days(&range)?
+        .map(|days_back| civil_date_from_unix_days(today_unix_days() - days_back));
+
+    let records = collect_usage_records()?;
+    let buckets = aggregate_usage(&records, since.as_deref());
+
+    Ok(UsageSummary {
+        range,
+        since,
+        total_tokens: buckets
+            .iter()
+            .map(|b| b.input_tokens + b.output_tokens + b.cache_read_tokens + b.cache_write_tokens)
+            .sum(),
+        total_requests: buckets.iter().map(|b| b.requests).sum(),
+        estimated_cost_usd: buckets.iter().map(|b| b.estimated_cost_usd).sum(),
+        buckets,
+    })
+}
+
+// Budget limits
+
+const BUDGET_STORAGE_FILE: &str = "budget.json";
+const BUDGET_POLL_SECS: u64 = 600;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+struct BudgetSettings {
+    monthly_usd: f64,
+    /// Percentages of `monthly_usd`, e.g. `[50, 80, 100]`.
+    alert_thresholds: Vec<f64>,
+    #[serde(default)]
+    pause_gateway_at_limit: bool,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Default)]
+struct BudgetStatus {
+    settings: Option<BudgetSettings>,
+    month: String,
+    spent_usd: f64,
+    alerted_thresholds: Vec<f64>,
+    paused: bool,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct BudgetAlertEvent {
+    kind: String,
+    threshold: f64,
+    spent_usd: f64,
+    monthly_usd: f64,
+}
+
+fn budget_storage_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_dir = app
+        .path_resolver()
+        .app_data_dir()
+        .ok_or("Could not determine app data directory")?;
+    Ok(app_dir.join(BUDGET_STORAGE_FILE))
+}
+
+fn read_budget_settings(app: &tauri::AppHandle) -> Option<BudgetSettings> {
+    let raw = fs::read_to_string(budget_storage_path(app).ok()?).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn validate_budget_settings(settings: &BudgetSettings) -> Result<(), String> {
+    if !settings.monthly_usd.is_finite() || settings.monthly_usd <= 0.0 {
+        return Err("Monthly budget must be greater than zero.".to_string());
+    }
+    if let Some(bad) = settings
+        .alert_thresholds
+        .iter()
+        .find(|t| !t.is_finite() || **t <= 0.0 || **t > 100.0)
+    {
+        return Err(format!(
+            "Alert threshold {} must be a percentage between 0 and 100.",
+            bad
+        ));
+    }
+    Ok(())
+}
+
+/// Thresholds reached by `spent_usd` that have not been alerted yet, in ascending order.
+fn crossed_budget_thresholds(
+    settings: &BudgetSettings,
+    spent_usd: f64,
+    alerted: &[f64],
+) -> Vec<f64> {
+    let percent = spent_usd / settings.monthly_usd * 100.0;
+    let mut crossed: Vec<f64> = settings
+        .alert_thresholds
+        .iter()
+        .copied()
+        .filter(|t| percent >= *t && !alerted.contains(t))
+        .collect();
+    crossed.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    crossed
+}
+
+fn month_to_date_spend(month: &str) -> Result<f64, String> {
+    agent_month_to_date_spend("*", month)
+}
+
+fn agent_month_to_date_spend(agent: &str, month: &str) -> Result<f64, String> {
+    let records = collect_agent_usage_records(agent)?;
+    Ok(aggregate_usage(&records, Some(&format!("{}-01", month)))
+        .iter()
+        .map(|b| b.estimated_cost_usd)
+        .sum())
+}
+
+fn current_month() -> String {
+    civil_date_from_unix_days(today_unix_days())[..7].to_string()
+}
+
+/// Moves `status` on to `month`, re-arming the alerts and lifting a pause from the previous
+/// month. Returns whether the month changed.
+fn roll_budget_month(status: &mut BudgetStatus, month: &str) -> bool {
+    if status.month == month {
+        return false;
+    }
+    status.month = month.to_string();
+    status.alerted_thresholds.clear();
+    status.paused = false;
+    true
+}
+
+fn check_budget(app: &tauri::AppHandle) {
+    let Some(settings) = read_budget_settings(app) else {
+        return;
+    };
+    let month = current_month();
+    let Ok(spent) = month_to_date_spend(&month) else {
+        return;
+    };
+
+    let crossed = match BUDGET_STATUS.lock() {
+        Ok(mut status) => {
+            if roll_budget_month(&mut status, &month) {
+                // The supervisor may start the gateway again; the new month has its own budget.
+                BUDGET_PAUSED.store(false, Ordering::Relaxed);
+            }
+            status.spent_usd = spent;
+            let crossed = crossed_budget_thresholds(&settings, spent, &status.alerted_thresholds);
+            status.alerted_thresholds.extend(crossed.iter().copied());
+            status.settings = Some(settings.clone());
+            crossed
+        }
+        Err(_) => return,
+    };
+
+    for threshold in crossed {
+        let _ = app.emit_all(
+            "budget-alert",
+            BudgetAlertEvent {
+                kind: "threshold".to_string(),
+                threshold,
+                spent_usd: spent,
+                monthly_usd: settings.monthly_usd,
+            },
+        );
+    }
+
+    if settings.pause_gateway_at_limit
+        && spent >= settings.monthly_usd
+        && !BUDGET_PAUSED.swap(true, Ordering::Relaxed)
+    {
+        // The supervisor checks BUDGET_PAUSED so it does not restart the gateway we stop here.
+        let _ = shell_command("openclaw gateway stop");
+        if let Ok(mut status) = BUDGET_STATUS.lock() {
+            status.paused = true;
+        }
+        let _ = app.emit_all(
+            "budget-alert",
+            BudgetAlertEvent {
+                kind: "paused".to_string(),
+                threshold: 100.0,
+                spent_usd: spent,
+                monthly_usd: settings.monthly_usd,
+            },
+        );
+    }
+}
+
+fn run_budget_monitor(app: tauri::AppHandle) {
+    while budget_monitor_needed(&app) {
+        check_budget(&app);
+        check_subagent_budget_caps(&app);
+        if !lifecycle::sleep(Duration::from_secs(BUDGET_POLL_SECS)) {
+            break;
+        }
+    }
+    BUDGET_MONITOR_RUNNING.store(false, Ordering::Relaxed);
+}
+
+fn start_budget_monitor(app: tauri::AppHandle) {
+    if budget_monitor_needed(&app) && !BUDGET_MONITOR_RUNNING.swap(true, Ordering::Relaxed) {
+        lifecycle::spawn("budget-monitor", move || run_budget_monitor(app));
+    }
+}
+
+/// Saves the budget (a `monthly_usd` of 0 clears it) and starts the background monitor.
+#[command]
+fn set_budget(
+    app: tauri::AppHandle,
+    monthly_usd: f64,
+    alert_thresholds: Vec<f64>,
+    pause_gateway_at_limit: Option<bool>,
+) -> Result<BudgetStatus, String> {
+    let path = budget_storage_path(&app)?;
+    if monthly_usd == 0.0 {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("Failed to clear budget: {}", e))?;
+        }
+        BUDGET_PAUSED.store(false, Ordering::Relaxed);
+        if let Ok(mut status) = BUDGET_STATUS.lock() {
+            *status = BudgetStatus::default();
+        }
+        return get_budget_status();
+    }
+
+    let settings = BudgetSettings {
+        monthly_usd,
+        alert_thresholds,
+        pause_gateway_at_limit: pause_gateway_at_limit.unwrap_or(false),
+    };
+    validate_budget_settings(&settings)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let raw = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize budget: {}", e))?;
+    fs::write(&path, raw).map_err(|e| format!("Failed to save budget: {}", e))?;
+
+    // A new limit re-arms alerts; the user restarts the gateway themselves after a pause.
+    BUDGET_PAUSED.store(false, Ordering::Relaxed);
+    if let Ok(mut status) = BUDGET_STATUS.lock() {
+        status.alerted_thresholds.clear();
+        status.paused = false;
+    }
+    check_budget(&app);
+    start_budget_monitor(app);
+    get_budget_status()
+}
+
+#[command]
+fn get_budget_status() -> Result<BudgetStatus, String> {
+    BUDGET_STATUS
+        .lock()
+        .map(|s| s.clone())
+        .map_err(|_| "Budget state is poisoned".to_string())
+}
+
+// Subagent settings
+
+const SUBAGENT_BUDGETS_FILE: &str = "subagent-budgets.json";
+const DEFAULT_SUBAGENT_MAX_CONCURRENT: u32 = 8;
+const SUBAGENT_MAX_CONCURRENT_LIMIT: u32 = 64;
+
+/// `max_concurrent` and `models` map to `agents.defaults.subagents` (`maxConcurrent` and
+/// `model` with the first entry as primary). OpenClaw has no per-agent spending limit, so
+/// `budget_caps` (agent id → monthly USD) are kept by the app and checked by the budget
+/// monitor.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+struct SubagentSettings {
+    max_concurrent: u32,
+    models: Vec<String>,
+    budget_caps: std::collections::BTreeMap<String, f64>,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct SubagentBudgetAlertEvent {
+    agent_id: String,
+    spent_usd: f64,
+    cap_usd: f64,
+}
+
+fn subagent_budgets_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_data_dir(app)?.join(SUBAGENT_BUDGETS_FILE))
+}
+
+fn read_subagent_budget_caps(app: &tauri::AppHandle) -> std::collections::BTreeMap<String, f64> {
+    subagent_budgets_path(app)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn budget_monitor_needed(app: &tauri::AppHandle) -> bool {
+    read_budget_settings(app).is_some() || !read_subagent_budget_caps(app).is_empty()
+}
+
+fn validate_subagent_settings(settings: &SubagentSettings) -> Result<(), String> {
+    if settings.max_concurrent == 0 || settings.max_concurrent > SUBAGENT_MAX_CONCURRENT_LIMIT {
+        return Err(format!(
+            "Subagent concurrency must be between 1 and {}.",
+            SUBAGENT_MAX_CONCURRENT_LIMIT
+        ));
+    }
+    if let Some(model) = settings.models.iter().find(|m| !m.contains('/')) {
+        return Err(format!(
+            "Invalid model '{}'; expected provider/model",
+            model
+        ));
+    }
+    for (agent, cap) in &settings.budget_caps {
+        if !is_valid_session_id(agent) {
+            return Err(format!("Invalid agent id: {}", agent));
+        }
+        if !cap.is_finite() || *cap <= 0.0 {
+            return Err(format!(
+                "Budget cap for {} must be greater than zero.",
+                agent
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn read_subagent_config(config: &serde_json::Value) -> (u32, Vec<String>) {
+    let subagents = config
+        .get("agents")
+        .and_then(|a| a.get("defaults"))
+        .and_then(|d| d.get("subagents"));
+    let max_concurrent = subagents
+        .and_then(|s| s.get("maxConcurrent"))
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
+        .unwrap_or(DEFAULT_SUBAGENT_MAX_CONCURRENT);
+    let model = subagents.and_then(|s| s.get("model"));
+    let models = match model {
+        Some(serde_json::Value::String(primary)) => vec![primary.clone()],
+        Some(model) => model
+            .get("primary")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .into_iter()
+            .chain(
+                model
+                    .get("fallbacks")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string())),
+            )
+            .collect(),
+        None => Vec::new(),
+    };
+    (max_concurrent, models)
+}
+
+fn apply_subagent_config(config: &mut serde_json::Value, max_concurrent: u32, models: &[String]) {
+    let Some(subagents) = config
+        .as_object_mut()
+        .map(|root| root.entry("agents").or_insert(serde_json::json!({})))
+        .and_then(|agents| agents.as_object_mut())
+        .map(|agents| agents.entry("defaults").or_insert(serde_json::json!({})))
+        .and_then(|defaults| defaults.as_object_mut())
+        .map(|defaults| defaults.entry("subagents").or_insert(serde_json::json!({})))
+        .and_then(|subagents| subagents.as_object_mut())
+    else {
+        return;
+    };
+    subagents.insert(
+        "maxConcurrent".to_string(),
+        serde_json::json!(max_concurrent),
+    );
+    match models.split_first() {
+        Some((primary, fallbacks)) => {
+            subagents.insert(
+                "model".to_string(),
+                serde_json::json!({ "primary": primary, "fallbacks": fallbacks }),
+            );
+        }
+        None => {
+            subagents.remove("model");
+        }
+    }
+}
+
+fn check_subagent_budget_caps(app: &tauri::AppHandle) {
+    let month = current_month();
+    for (agent, cap) in read_subagent_budget_caps(app) {
+        let Ok(spent) = agent_month_to_date_spend(&agent, &month) else {
+            continue;
+        };
+        if spent < cap {
+            continue;
+        }
+        let first_alert = SUBAGENT_CAP_ALERTS
+            .lock()
+            .map(|mut alerted| alerted.insert(format!("{}:{}", month, agent)))
+            .unwrap_or(false);
+        if first_alert {
+            let _ = app.emit_all(
+                "subagent-budget-alert",
+                SubagentBudgetAlertEvent {
+                    agent_id: agent,
+                    spent_usd: spent,
+                    cap_usd: cap,
+                },
+            );
+        }
+    }
+}
+
+#[command]
+fn get_subagent_settings(app: tauri::AppHandle) -> Result<SubagentSettings, String> {
+    let (max_concurrent, models) = read_subagent_config(&read_local_openclaw_config()?);
+    Ok(SubagentSettings {
+        max_concurrent,
+        models,
+        budget_caps: read_subagent_budget_caps(&app),
+    })
+}
+
+#[command]
+async fn set_subagent_settings(
+    app: tauri::AppHandle,
+    settings: SubagentSettings,
+) -> Result<SubagentSettings, String> {
+    validate_subagent_settings(&settings)?;
+    update_local_openclaw_config(|config| {
+        apply_subagent_config(config, settings.max_concurrent, &settings.models);
+        Ok(())
+    })?;
+
+    let path = subagent_budgets_path(&app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let raw = serde_json::to_string_pretty(&settings.budget_caps)
+        .map_err(|e| format!("Failed to serialize budget caps: {}", e))?;
+    fs::write(&path, raw).map_err(|e| format!("Failed to save budget caps: {}", e))?;
+    start_budget_monitor(app.clone());
+
+    reload_gateway_config().await?;
+    get_subagent_settings(app)
+}
+
+/// Chart data for the last `range_secs`, one point per `resolution_secs`.
+#[command]
+fn get_metrics(range_secs: u64, resolution_secs: u64) -> Vec<metrics::MetricPoint> {
+    metrics::start_sampler();
+    metrics::query(range_secs, resolution_secs)
+}
+
+// Custom OpenAI-compatible endpoint
+
+/// Provider id for an arbitrary OpenAI-compatible endpoint (vLLM, LiteLLM, corporate proxies).
+const CUSTOM_PROVIDER: &str = "custom";
+
+/// Base URL the provider's auth profile records: the custom or Azure endpoint, or the local
+/// server.
+fn provider_base_url(config: &AgentConfig) -> Option<&String> {
+    match config.provider.as_str() {
+        CUSTOM_PROVIDER => config.base_url.as_ref(),
+        AZURE_PROVIDER => config.azure.as_ref().map(|azure| &azure.endpoint),
+        _ => config.local_base_url.as_ref(),
+    }
+}
+
+/// Trimmed `base_url`, which must be http(s). Kept as given otherwise: proxies do not all
+/// serve the API under `/v1`.
+fn custom_endpoint_url(base_url: &str) -> Result<String, String> {
+    let url = base_url.trim().trim_end_matches('/');
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(format!(
+            "Endpoint URL '{}' must start with http:// or https://",
+            url
+        ));
+    }
+    Ok(url.to_string())
+}
+
+/// `models` section that lets openclaw resolve `lmstudio/`, `custom/`, `azure/` and Bedrock
+/// models: the primary and any fallbacks of the same provider, registered under its endpoint.
+fn provider_models_section(config: &AgentConfig) -> Result<Option<serde_json::Value>, String> {
+    let prefix = format!("{}/", config.provider);
+    let mut primary = config
+        .model
+        .strip_prefix(&prefix)
+        .unwrap_or(&config.model)
+        .to_string();
+    let mut entry = match config.provider.as_str() {
+        "lmstudio" => {
+            let base_url = config
+                .local_base_url
+                .as_deref()
+                .unwrap_or("http://localhost:1234");
+            let base_url_v1 = if base_url.ends_with("/v1") {
+                base_url.to_string()
+            } else {
+                format!("{}/v1", base_url.trim_end_matches('/'))
+            };
+            serde_json::json!({
+                "baseUrl": base_url_v1,
+                "apiKey": "lmstudio",
+                "api": "openai-completions",
+            })
+        }
+        CUSTOM_PROVIDER => {
+            let base_url = config
+                .base_url
+                .as_deref()
+                .ok_or("The custom provider needs the endpoint's base URL")?;
+            let api_key = if config.api_key.trim().is_empty() {
+                "none"
+            } else {
+                config.api_key.trim()
+            };
+            serde_json::json!({
+                "baseUrl": custom_endpoint_url(base_url)?,
+                "apiKey": api_key,
+                "api": "openai-completions",
+            })
+        }
+        AZURE_PROVIDER => {
+            let azure = config
+                .azure
+                .as_ref()
+                .ok_or("Azure OpenAI needs the resource endpoint and deployment name")?;
+            // Azure takes the key in an `api-key` header and the deployment as the model.
+            primary = azure.deployment.trim().to_string();
+            serde_json::json!({
+                "baseUrl": format!("{}/openai/v1", azure_endpoint(&azure.endpoint)?),
+                "apiKey": config.api_key.trim(),
+                "api": "openai-completions",
+                "headers": { "api-key": config.api_key.trim() },
+            })
+        }
+        bedrock::PROVIDER => {
+            let bedrock = config
+                .bedrock
+                .as_ref()
+                .ok_or("Bedrock needs an AWS region and credentials")?;
+            bedrock.validate()?;
+            // Credentials come from the AWS profile named in `env.vars`, not the config.
+            serde_json::json!({
+                "baseUrl": format!("https://bedrock-runtime.{}.amazonaws.com", bedrock.region.trim()),
+                "api": "bedrock-converse-stream",
+                "auth": "aws-sdk",
+            })
+        }
+        _ => return Ok(None),
+    };
+    let mut model_ids = vec![primary];
+    for fallback in config.fallback_models.iter().flatten() {
+        if let Some(id) = fallback.strip_prefix(&prefix) {
+            if !model_ids.iter().any(|m| m == id) {
+                model_ids.push(id.to_string());
+            }
+        }
+    }
+    entry["models"] = model_ids
+        .iter()
+        .map(|id| local_model_definition(id))
+        .collect();
+    Ok(Some(serde_json::json!({
+        "mode": "merge",
+        "providers": { config.provider.as_str(): entry }
+    })))
+}
+
+/// Writes the provider's `models` section, plus the AWS profile and region the gateway's
+/// Bedrock client should use, into `openclaw.json`.
+fn apply_provider_models(
+    config: &AgentConfig,
+    config_json: &mut serde_json::Value,
+) -> Result<(), String> {
+    let Some(models) = provider_models_section(config)? else {
+        return Ok(());
+    };
+    config_json["models"] = models;
+    if let (bedrock::PROVIDER, Some(bedrock)) = (config.provider.as_str(), &config.bedrock) {
+        if !config_json["env"].is_object() {
+            config_json["env"] = serde_json::json!({});
+        }
+        if !config_json["env"]["vars"].is_object() {
+            config_json["env"]["vars"] = serde_json::json!({});
+        }
+        let vars = &mut config_json["env"]["vars"];
+        vars["AWS_PROFILE"] = serde_json::json!(bedrock.effective_profile());
+        vars["AWS_REGION"] = serde_json::json!(bedrock.region.trim());
+    }
+    Ok(())
+}
+
+/// Checks an OpenAI-compatible endpoint by listing `<base_url>/models` with the key, and
+/// that it serves `model` when one is given. Returns the model ids it lists.
+#[command]
+async fn validate_custom_endpoint(
+    base_url: String,
+    api_key: Option<String>,
+    model: Option<String>,
+) -> Result<Vec<String>, String> {
+    let base_url = custom_endpoint_url(&base_url)?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let (client, url, key) = (
+        &client,
+        &format!("{}/models", base_url),
+        api_key.as_deref().map(str::trim).filter(|k| !k.is_empty()),
+    );
+    let listing = retry::retry_async(
+        &retry::PROVIDER_VALIDATION,
+        |_| async move {
+            let mut request = client.get(url.as_str());
+            if let Some(key) = key {
+                request = request.bearer_auth(key);
+            }
+            let resp = request
+                .send()
+                .await
+                .map_err(|e| format!("Failed to reach {}: {}", url, e))?;
+            let status = resp.status();
+            if status == reqwest::StatusCode::UNAUTHORIZED
+                || status == reqwest::StatusCode::FORBIDDEN
+            {
+                return Err(format!(
+                    "{} rejected the API key (HTTP {})",
+                    url,
+                    status.as_u16()
+                ));
+            }
+            if !status.is_success() {
+                return Err(format!("{} returned HTTP {}", url, status.as_u16()));
+            }
+            resp.json::<serde_json::Value>()
+                .await
+                .map_err(|_| format!("{} did not return JSON", url))
+        },
+        |e: &String| retry::is_transient(e),
+        |_, _| {},
+    )
+    .await?;
+    if !listing.get("data").is_some_and(|d| d.is_array()) {
+        return Err(format!(
+            "{} is not an OpenAI-compatible model list; check the base URL (it usually ends in /v1)",
+            url
+        ));
+    }
+    let models = parse_local_models(CUSTOM_PROVIDER, &listing);
+    if let Some(model) = model {
+        let id = model
+            .strip_prefix(&format!("{}/", CUSTOM_PROVIDER))
+            .unwrap_or(&model);
+        if !models.iter().any(|m| m == id) {
+            return Err(format!(
+                "The endpoint does not serve a model named '{}'",
+                id
+            ));
+        }
+    }
+    Ok(models)
+}
+
+// Azure OpenAI
+
+const AZURE_PROVIDER: &str = "azure";
+const AZURE_DEFAULT_API_VERSION: &str = "2024-10-21";
+
+/// Resource endpoint without a trailing slash or `/openai` path; must be https.
+fn azure_endpoint(endpoint: &str) -> Result<String, String> {
+    let url = endpoint.trim().trim_end_matches('/');
+    let url = url
+        .strip_suffix("/openai/v1")
+        .or_else(|| url.strip_suffix("/openai"))
+        .unwrap_or(url);
+    if !url.starts_with("https://") {
+        return Err(format!(
+            "Azure endpoint '{}' must start with https://",
+            endpoint.trim()
+        ));
+    }
+    Ok(url.to_string())
+}
+
+/// Adds the endpoint, deployment and API version to an Azure auth profile.
+fn apply_azure_profile_fields(profile: &mut serde_json::Value, azure: &AzureOpenAiConfig) {
+    let Some(profile) = profile.as_object_mut() else {
+        return;
+    };
+    if let Ok(endpoint) = azure_endpoint(&azure.endpoint) {
+        profile.insert("baseUrl".to_string(), serde_json::json!(endpoint));
+    }
+    profile.insert(
+        "deployment".to_string(),
+        serde_json::json!(azure.deployment.trim()),
+    );
+    profile.insert(
+        "apiVersion".to_string(),
+        serde_json::json!(azure
+            .api_version
+            .as_deref()
+            .unwrap_or(AZURE_DEFAULT_API_VERSION)),
+    );
+}
+
+fn azure_config_from_profile(profile: &serde_json::Value) -> Option<AzureOpenAiConfig> {
+    let field = |key: &str| {
+        profile
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    Some(AzureOpenAiConfig {
+        endpoint: field("baseUrl")?,
+        deployment: field("deployment")?,
+        api_version: field("apiVersion"),
+    })
+}
+
+/// Sends a one-token chat completion to the deployment to check the endpoint, key,
+/// deployment name and API version together.
+#[command]
+async fn validate_azure_openai(
+    azure: AzureOpenAiConfig,
+    api_key: String,
+) -> Result<String, String> {
+    let endpoint = azure_endpoint(&azure.endpoint)?;
+    let deployment = azure.deployment.trim();
+    if deployment.is_empty() {
+        return Err("Enter the Azure deployment name".to_string());
+    }
+    let url = format!(
+        "{}/openai/deployments/{}/chat/completions?api-version={}",
+        endpoint,
+        deployment,
+        azure
+            .api_version
+            .as_deref()
+            .unwrap_or(AZURE_DEFAULT_API_VERSION)
+    );
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let (client, url, key, endpoint) = (&client, &url, api_key.trim(), endpoint.as_str());
+    retry::retry_async(
+        &retry::PROVIDER_VALIDATION,
+        |_| async move {
+            let resp = client
+                .post(url.as_str())
+                .header("api-key", key)
+                .json(&serde_json::json!({
+                    "messages": [{ "role": "user", "content": "ping" }],
+                    "max_tokens": 1,
+                }))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to reach {}: {}", endpoint, e))?;
+            let status = resp.status();
+            if status.is_success() {
+                return Ok(format!("Deployment {} answered", deployment));
+            }
+            let body = resp.text().await.unwrap_or_default();
+            Err(match status.as_u16() {
+                401 | 403 => "Azure rejected the API key for this resource".to_string(),
+                404 => format!(
+                    "Deployment '{}' was not found on {}; check the name and API version",
+                    deployment, endpoint
+                ),
+                code => format!(
+                    "Azure returned HTTP {}: {}",
+                    code,
+                    body.chars().take(300).collect::<String>()
+                ),
+            })
+        },
+        |e: &String| retry::is_transient(e),
+        |_, _| {},
+    )
+    .await
+}
+
+// AWS Bedrock
+
+fn aws_credentials_path() -> Result<String, String> {
+    #[cfg(target_os = "windows")]
+    {
+        Ok(format!("{}/.aws/credentials", wsl_home_dir()?))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let home = dirs::home_dir().ok_or("Could not find home directory")?;
+        Ok(home.join(".aws/credentials").to_string_lossy().to_string())
+    }
+}
+
+/// Saves keys entered for Bedrock to `~/.aws/credentials` under the openclaw profile.
+fn save_bedrock_credentials(config: &bedrock::BedrockConfig) -> Result<(), String> {
+    let Some(credentials) = config.static_credentials() else {
+        return Ok(());
+    };
+    let path = aws_credentials_path()?;
+    let existing = read_local_text_file(&path).unwrap_or_default();
+    shell_command(&format!(
+        "mkdir -p {}",
+        shell_single_quote(path.trim_end_matches("/credentials"))
+    ))?;
+    write_local_text_file(
+        &path,
+        &bedrock::upsert_profile(&existing, bedrock::CREDENTIALS_PROFILE, &credentials),
+    )?;
+    shell_command(&format!("chmod 600 {}", shell_single_quote(&path))).map(|_| ())
+}
+
+fn bedrock_credentials(config: &bedrock::BedrockConfig) -> Result<bedrock::Credentials, String> {
+    if let Some(credentials) = config.static_credentials() {
+        return Ok(credentials);
+    }
+    let profile = config.effective_profile();
+    let path = aws_credentials_path()?;
+    read_local_text_file(&path)
+        .and_then(|contents| bedrock::profile_credentials(&contents, &profile))
+        .ok_or_else(|| {
+            format!(
+                "AWS profile '{}' has no access keys in {}. SSO and credential-process profiles cannot be checked here; enter access keys instead.",
+                profile, path
+            )
+        })
+}
+
+/// Checks Bedrock access with a signed `ListFoundationModels` call in the configured region
+/// and returns the text models offered there.
+#[command]
+async fn validate_bedrock(bedrock: bedrock::BedrockConfig) -> Result<Vec<String>, String> {
+    bedrock.validate()?;
+    let credentials = &bedrock_credentials(&bedrock)?;
+    let region = bedrock.region.trim();
+    retry::retry_async(
+        &retry::PROVIDER_VALIDATION,
+        |_| bedrock::list_foundation_models(region, credentials, unix_now_secs()),
+        |e: &String| retry::is_transient(e),
+        |_, _| {},
+    )
+    .await
+}
+
+// Local model servers
+
+/// Local servers openclaw can use offline: kind, default address and model listing path.
+const LOCAL_MODEL_SERVERS: &[(&str, &str, &str)] = &[
+    ("ollama", "http://127.0.0.1:11434", "/api/tags"),
+    ("lmstudio", "http://127.0.0.1:1234", "/v1/models"),
+];
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct LocalModelServer {
+    kind: String,
+    base_url: String,
+    running: bool,
+    models: Vec<String>,
+}
+
+/// Model ids from Ollama's `/api/tags` (`models[].name`) or an OpenAI-style `/v1/models`
+/// listing (`data[].id`).
+fn parse_local_models(kind: &str, listing: &serde_json::Value) -> Vec<String> {
+    let (array, field) = if kind == "ollama" {
+        ("models", "name")
+    } else {
+        ("data", "id")
+    };
+    listing
+        .get(array)
+        .and_then(|m| m.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|m| m.get(field).and_then(|n| n.as_str()).map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn local_server_root(base_url: &str) -> &str {
+    base_url.trim_end_matches('/').trim_end_matches("/v1")
+}
+
+fn local_model_definition(id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "name": id,
+        "reasoning": false,
+        "input": ["text"],
+        "cost": { "input": 0, "output": 0, "cacheRead": 0, "cacheWrite": 0 },
+        "contextWindow": 131072,
+        "maxTokens": 8192
+    })
+}
+
+/// Writes `models.providers.<kind>` for `server` (both Ollama and LM Studio serve the
+/// OpenAI API under `/v1`) and, when given, makes `<kind>/<primary>` the default model.
+fn apply_local_provider(
+    config: &mut serde_json::Value,
+    server: &LocalModelServer,
+    primary: Option<&str>,
+) {
+    if !config.is_object() {
+        *config = serde_json::json!({});
+    }
+    if !config["models"].is_object() {
+        config["models"] = serde_json::json!({ "mode": "merge" });
+    }
+    if !config["models"]["providers"].is_object() {
+        config["models"]["providers"] = serde_json::json!({});
+    }
+    config["models"]["providers"][server.kind.as_str()] = serde_json::json!({
+        "baseUrl": format!("{}/v1", local_server_root(&server.base_url)),
+        "apiKey": server.kind,
+        "api": "openai-completions",
+        "models": server
+            .models
+            .iter()
+            .map(|id| local_model_definition(id))
+            .collect::<Vec<_>>(),
+    });
+
+    let Some(primary) = primary else {
+        return;
+    };
+    let model_ref = format!("{}/{}", server.kind, primary);
+    if !config["agents"].is_object() {
+        config["agents"] = serde_json::json!({});
+    }
+    if !config["agents"]["defaults"].is_object() {
+        config["agents"]["defaults"] = serde_json::json!({});
+    }
+    let defaults = &mut config["agents"]["defaults"];
+    if defaults["model"].is_object() {
+        defaults["model"]["primary"] = serde_json::json!(model_ref);
+    } else {
+        defaults["model"] = serde_json::json!({ "primary": model_ref });
+    }
+    if let Some(catalog) = defaults["models"].as_object_mut() {
+        catalog.entry(model_ref).or_insert(serde_json::json!({}));
+    }
+}
+
+async fn probe_local_model_server(
+    client: &reqwest::Client,
+    kind: &str,
+    base_url: &str,
+) -> LocalModelServer {
+    let path = LOCAL_MODEL_SERVERS
+        .iter()
+        .find(|(k, _, _)| *k == kind)
+        .map_or("/v1/models", |(_, _, path)| *path);
+    let listing = match client
+        .get(format!("{}{}", local_server_root(base_url), path))
+        .send()
+        .await
+    {
+        Ok(resp) if resp.status().is_success() => resp.json::<serde_json::Value>().await.ok(),
+        _ => None,
+    };
+    LocalModelServer {
+        kind: kind.to_string(),
+        base_url: local_server_root(base_url).to_string(),
+        running: listing.is_some(),
+        models: listing
+            .map(|listing| parse_local_models(kind, &listing))
+            .unwrap_or_default(),
+    }
+}
+
+/// Probes Ollama and LM Studio at their default addresses, or at `base_urls` (kind → URL),
+/// and lists the models each one serves.
+#[command]
+async fn detect_local_model_servers(
+    base_urls: Option<std::collections::HashMap<String, String>>,
+) -> Result<Vec<LocalModelServer>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut servers = Vec::new();
+    for (kind, default_url, _) in LOCAL_MODEL_SERVERS {
+        let base_url = base_urls
+            .as_ref()
+            .and_then(|urls| urls.get(*kind))
+            .map_or(*default_url, String::as_str);
+        servers.push(probe_local_model_server(&client, kind, base_url).await);
+    }
+    Ok(servers)
+}
+
+/// Points openclaw at a running local server so the agent works offline: registers every
+/// model it serves under `models.providers.<kind>`, adds a placeholder `<kind>:default` auth
+/// profile and, with `set_primary`, makes `model` (default: the first one) the primary.
+/// `kind` is `ollama`, `lmstudio`, or `local` for whichever is running.
+#[command]
+async fn configure_local_provider(
+    kind: String,
+    base_url: Option<String>,
+    model: Option<String>,
+    set_primary: Option<bool>,
+) -> Result<LocalModelServer, String> {
+    let _operation = lifecycle::begin_operation("configure_local_provider").await;
+    if kind != "local" && !LOCAL_MODEL_SERVERS.iter().any(|(k, _, _)| *k == kind) {
+        return Err(format!(
+            "Unknown local provider '{}'; expected ollama, lmstudio or local",
+            kind
+        ));
+    }
+    let base_urls = base_url.map(|url| {
+        LOCAL_MODEL_SERVERS
+            .iter()
+            .filter(|(k, _, _)| kind == "local" || *k == kind)
+            .map(|(k, _, _)| (k.to_string(), url.clone()))
+            .collect()
+    });
+    let server = detect_local_model_servers(base_urls)
+        .await?
+        .into_iter()
+        .find(|s| s.running && (kind == "local" || s.kind == kind))
+        .ok_or_else(|| {
+            format!(
+                "No {} server is running. Start it and try again.",
+                if kind == "local" {
+                    "Ollama or LM Studio"
+                } else {
+                    kind.as_str()
+                }
+            )
+        })?;
+    if server.models.is_empty() {
+        return Err(format!(
+            "{} is running but has no models. Pull or load a model first.",
+            server.kind
+        ));
+    }
+    let primary = match model {
+        Some(model) => {
+            let id = model
+                .strip_prefix(&format!("{}/", server.kind))
+                .unwrap_or(&model)
+                .to_string();
+            if !server.models.contains(&id) {
+                return Err(format!(
+                    "{} does not serve a model named '{}'",
+                    server.kind, id
+                ));
+            }
+            id
+        }
+        None => server.models[0].clone(),
+    };
+
+    update_local_openclaw_config_or_default(|config| {
+        apply_local_provider(
+            config,
+            &server,
+            set_primary.unwrap_or(false).then_some(primary.as_str()),
+        );
+        Ok(())
+    })?;
+
+    let mut auth = read_provider_auth_profiles().unwrap_or_else(|_| serde_json::json!({}));
+    let profile_key = format!("{}:default", server.kind);
+    if !auth["profiles"].is_object() {
+        auth["profiles"] = serde_json::json!({});
+    }
+    if !auth["lastGood"].is_object() {
+        auth["lastGood"] = serde_json::json!({});
+    }
+    auth["profiles"][profile_key.as_str()] =
+        default_provider_auth(&server.kind, "", "token", Some(&server.base_url))
+            .profile
+            .unwrap_or_else(|| serde_json::json!({}));
+    auth["lastGood"][server.kind.as_str()] = serde_json::json!(profile_key);
+    let raw = serde_json::to_string_pretty(&auth).map_err(|e| e.to_string())?;
+    write_local_openclaw_file(AUTH_PROFILES_FILE, &raw)?;
+
+    reload_gateway_config().await?;
+    Ok(server)
+}
+
+// Model routing
+
+/// Task types and the `agents.defaults` model slot each one routes through. Rules map onto
+/// slots openclaw already has, so the config schema is unchanged; `agent` rules set
+/// `agents.list[].model` for one agent (e.g. a coding agent on a stronger model).
+const ROUTING_SLOTS: &[(&str, &str)] = &[
+    ("default", "model"),
+    ("subagents", "subagents.model"),
+    ("image", "imageModel"),
+    ("heartbeat", "heartbeat.model"),
+];
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+struct RoutingRule {
+    /// A key of `ROUTING_SLOTS`, or `agent`.
+    task: String,
+    /// Agent id; required for `agent` rules.
+    agent: Option<String>,
+    primary: String,
+    #[serde(default)]
+    fallbacks: Vec<String>,
+}
+
+/// `(primary, fallbacks)` from a model slot, which is a bare string or `{ primary, fallbacks }`.
+fn read_model_slot(value: Option<&serde_json::Value>) -> Option<(String, Vec<String>)> {
+    match value? {
+        serde_json::Value::String(primary) => Some((primary.clone(), Vec::new())),
+        slot => Some((
+            slot.get("primary")?.as_str()?.to_string(),
+            slot.get("fallbacks")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect(),
+        )),
+    }
+}
+
+fn routing_slot_pointer(task: &str) -> Option<String> {
+    ROUTING_SLOTS
+        .iter()
+        .find(|(name, _)| *name == task)
+        .map(|(_, path)| format!("/agents/defaults/{}", path.replace('.', "/")))
+}
+
+fn read_routing_rules(config: &serde_json::Value) -> Vec<RoutingRule> {
+    let mut rules: Vec<RoutingRule> = ROUTING_SLOTS
+        .iter()
+        .filter_map(|(task, _)| {
+            let pointer = routing_slot_pointer(task)?;
+            let (primary, fallbacks) = read_model_slot(config.pointer(&pointer))?;
+            Some(RoutingRule {
+                task: task.to_string(),
+                agent: None,
+                primary,
+                fallbacks,
+            })
+        })
+        .collect();
+    for agent in config
+        .pointer("/agents/list")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let Some(id) = agent.get("id").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        if let Some((primary, fallbacks)) = read_model_slot(agent.get("model")) {
+            rules.push(RoutingRule {
+                task: "agent".to_string(),
+                agent: Some(id.to_string()),
+                primary,
+                fallbacks,
+            });
+        }
+    }
+    rules
+}
+
+/// Providers a model may reference: those with an auth profile (`anthropic:default` →
+/// `anthropic`) plus custom providers under `models.providers`.
+fn configured_model_providers(
+    config: &serde_json::Value,
+    auth_profiles: &serde_json::Value,
+) -> std::collections::BTreeSet<String> {
+    let keys = |value: Option<&serde_json::Value>| -> Vec<String> {
+        value
+            .and_then(|v| v.as_object())
+            .map(|map| map.keys().cloned().collect())
+            .unwrap_or_default()
+    };
+    keys(auth_profiles.get("profiles"))
+        .into_iter()
+        .map(|profile| profile.split(':').next().unwrap_or("").to_string())
+        .chain(keys(config.pointer("/models/providers")))
+        .filter(|provider| !provider.is_empty())
+        .collect()
+}
+
+fn validate_routing_rules(
+    rules: &[RoutingRule],
+    config: &serde_json::Value,
+    providers: &std::collections::BTreeSet<String>,
+) -> Result<(), String> {
+    let mut seen = std::collections::HashSet::new();
+    for rule in rules {
+        let target = match (rule.task.as_str(), rule.agent.as_deref()) {
+            ("agent", Some(agent)) => {
+                let exists = config
+                    .pointer("/agents/list")
+                    .and_then(|v| v.as_array())
+                    .is_some_and(|list| {
+                        list.iter()
+                            .any(|a| a.get("id").and_then(|v| v.as_str()) == Some(agent))
+                    });
+                if !exists {
+                    return Err(format!("No agent with id '{}'", agent));
+                }
+                format!("agent:{}", agent)
+            }
+            ("agent", None) => return Err("Agent routing rules need an agent id".to_string()),
+            (task, _) if routing_slot_pointer(task).is_some() => task.to_string(),
+            (task, _) => {
+                return Err(format!(
+                    "Unknown routing task '{}'; expected one of {}, agent",
+                    task,
+                    ROUTING_SLOTS
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            }
+        };
+        if !seen.insert(target.clone()) {
+            return Err(format!("More than one routing rule for {}", target));
+        }
+        if rule.task == "heartbeat" && !rule.fallbacks.is_empty() {
+            return Err("Heartbeat runs take a single model without fallbacks".to_string());
+        }
+        for model in std::iter::once(&rule.primary).chain(&rule.fallbacks) {
+            let Some((provider, _)) = model
+                .split_once('/')
+                .filter(|(provider, name)| !provider.is_empty() && !name.is_empty())
+            else {
+                return Err(format!(
+                    "Invalid model '{}'; expected provider/model",
+                    model
+                ));
+            };
+            if !providers.contains(provider) {
+                return Err(format!(
+                    "Model '{}' uses provider '{}', which has no configured profile. Add the provider first.",
+                    model, provider
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn model_slot_value(rule: &RoutingRule) -> serde_json::Value {
+    if rule.task == "heartbeat" {
+        serde_json::json!(rule.primary)
+    } else {
+        serde_json::json!({ "primary": rule.primary, "fallbacks": rule.fallbacks })
+    }
+}
+
+/// Replaces the routing rules. Tasks missing from `rules` fall back to the default model,
+/// except `default` itself, which openclaw requires and is left unchanged. Referenced
+/// models are added to the `agents.defaults.models` allowlist when one is configured.
+fn apply_routing_rules(config: &mut serde_json::Value, rules: &[RoutingRule]) {
+    let Some(defaults) = config
+        .as_object_mut()
+        .map(|root| root.entry("agents").or_insert(serde_json::json!({})))
+        .and_then(|agents| agents.as_object_mut())
+        .map(|agents| agents.entry("defaults").or_insert(serde_json::json!({})))
+        .and_then(|defaults| defaults.as_object_mut())
+    else {
+        return;
+    };
+    for (task, path) in ROUTING_SLOTS {
+        let rule = rules.iter().find(|r| r.task == *task);
+        let (parent, key) = match path.split_once('.') {
+            Some((parent, key)) => (
+                defaults
+                    .entry(parent)
+                    .or_insert(serde_json::json!({}))
+                    .as_object_mut(),
+                key,
+            ),
+            None => (Some(&mut *defaults), *path),
+        };
+        let Some(parent) = parent else {
+            continue;
+        };
+        match rule {
+            Some(rule) => {
+                parent.insert(key.to_string(), model_slot_value(rule));
+            }
+            None if *task != "default" => {
+                parent.remove(key);
+            }
+            None => {}
+        }
+    }
+    if let Some(catalog) = defaults.get_mut("models").and_then(|m| m.as_object_mut()) {
+        for rule in rules {
+            for model in std::iter::once(&rule.primary).chain(&rule.fallbacks) {
+                catalog
+                    .entry(model.clone())
+                    .or_insert(serde_json::json!({}));
+            }
+        }
+    }
+
+    for agent in config
+        .pointer_mut("/agents/list")
+        .and_then(|v| v.as_array_mut())
+        .into_iter()
+        .flatten()
+    {
+        let id = agent.get("id").and_then(|v| v.as_str()).map(str::to_string);
+        let Some(agent) = agent.as_object_mut() else {
+            continue;
+        };
+        match rules.iter().find(|r| r.task == "agent" && r.agent == id) {
+            Some(rule) => {
+                agent.insert("model".to_string(), model_slot_value(rule));
+            }
+            None => {
+                agent.remove("model");
+            }
+        }
+    }
+}
+
+#[command]
+fn get_routing_rules() -> Result<Vec<RoutingRule>, String> {
+    Ok(read_routing_rules(&read_local_openclaw_config()?))
+}
+
+/// Validates that every referenced model belongs to a configured provider, writes the
+/// rules and hot-reloads the gateway.
+#[command]
+async fn set_routing_rules(rules: Vec<RoutingRule>) -> Result<Vec<RoutingRule>, String> {
+    let config = update_local_openclaw_config(|config| {
+        let auth_profiles = read_provider_auth_profiles().unwrap_or_else(|_| serde_json::json!({}));
+        validate_routing_rules(
+            &rules,
+            config,
+            &configured_model_providers(config, &auth_profiles),
+        )?;
+        apply_routing_rules(config, &rules);
+        Ok(config.clone())
+    })?;
+    reload_gateway_config().await?;
+    Ok(read_routing_rules(&config))
+}
+
+// Heartbeat
+
+const DEFAULT_HEARTBEAT_EVERY: &str = "1h";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+struct HeartbeatSettings {
+    enabled: bool,
+    /// Cadence such as `30m` or `2h`.
+    every: String,
+    prompt: Option<String>,
+    /// Contents of the workspace HEARTBEAT.md checklist.
+    heartbeat_md: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct HeartbeatRun {
+    time: Option<String>,
+    /// `ran`, `skipped` or `failed`.
+    outcome: String,
+    message: String,
+}
+
+fn is_valid_heartbeat_interval(every: &str) -> bool {
+    let Some(unit) = every.chars().last() else {
+        return false;
+    };
+    let amount = &every[..every.len() - unit.len_utf8()];
+    matches!(unit, 's' | 'm' | 'h' | 'd')
+        && !amount.is_empty()
+        && amount.chars().all(|c| c.is_ascii_digit())
+        && amount.parse::<u64>().map(|n| n > 0).unwrap_or(false)
+}
+
+fn read_heartbeat_config(config: &serde_json::Value) -> (bool, String, Option<String>) {
+    let heartbeat = config
+        .get("agents")
+        .and_then(|a| a.get("defaults"))
+        .and_then(|d| d.get("heartbeat"));
+    let enabled = heartbeat
+        .and_then(|h| h.get("enabled"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    let every = heartbeat
+        .and_then(|h| h.get("every"))
+        .and_then(|v| v.as_str())
+        .unwrap_or(DEFAULT_HEARTBEAT_EVERY)
+        .to_string();
+    let prompt = heartbeat
+        .and_then(|h| h.get("prompt"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    (enabled, every, prompt)
+}
+
+/// Writes `agents.defaults.heartbeat` the way `configure_agent` does: `{ "enabled": false }`
+/// when off, otherwise the cadence and optional prompt.
+fn apply_heartbeat_config(config: &mut serde_json::Value, settings: &HeartbeatSettings) {
+    let Some(defaults) = config
+        .as_object_mut()
+        .map(|root| root.entry("agents").or_insert(serde_json::json!({})))
+        .and_then(|agents| agents.as_object_mut())
+        .map(|agents| agents.entry("defaults").or_insert(serde_json::json!({})))
+        .and_then(|defaults| defaults.as_object_mut())
+    else {
+        return;
+    };
+    let heartbeat = if settings.enabled {
+        let mut heartbeat = serde_json::json!({ "every": settings.every });
+        if let Some(prompt) = settings.prompt.as_ref().filter(|p| !p.trim().is_empty()) {
+            heartbeat["prompt"] = serde_json::json!(prompt);
+        }
+        heartbeat
+    } else {
+        serde_json::json!({ "enabled": false })
+    };
+    defaults.insert("heartbeat".to_string(), heartbeat);
+}
+
+/// Parses a gateway log line (JSON) that mentions a heartbeat run.
+fn parse_heartbeat_log_line(line: &str) -> Option<HeartbeatRun> {
+    let entry: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    let message = ["msg", "message", "0", "1"]
+        .iter()
+        .filter_map(|key| entry.get(*key).and_then(|v| v.as_str()))
+        .find(|text| text.to_lowercase().contains("heartbeat"))?
+        .to_string();
+    let time = entry
+        .get("time")
+        .or_else(|| entry.get("_meta").and_then(|m| m.get("date")))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let lower = message.to_lowercase();
+    let outcome = if lower.contains("fail") || lower.contains("error") {
+        "failed"
+    } else if lower.contains("skip") {
+        "skipped"
+    } else {
+        "ran"
+    };
+    Some(HeartbeatRun {
+        time,
+        outcome: outcome.to_string(),
+        message,
+    })
+}
+
+#[command]
+fn get_heartbeat_settings() -> Result<HeartbeatSettings, String> {
+    let (enabled, every, prompt) = read_heartbeat_config(&read_local_openclaw_config()?);
+    Ok(HeartbeatSettings {
+        enabled,
+        every,
+        prompt,
+        heartbeat_md: read_local_openclaw_file("workspace/HEARTBEAT.md").ok(),
+    })
+}
+
+#[command]
+async fn set_heartbeat_settings(settings: HeartbeatSettings) -> Result<HeartbeatSettings, String> {
+    if settings.enabled && !is_valid_heartbeat_interval(&settings.every) {
+        return Err(format!(
+            "Invalid heartbeat interval '{}'; use a number followed by s, m, h or d (e.g. 30m).",
+            settings.every
+        ));
+    }
+    update_local_openclaw_config(|config| {
+        apply_heartbeat_config(config, &settings);
+        Ok(())
+    })?;
+    if let Some(heartbeat_md) = &settings.heartbeat_md {
+        write_local_openclaw_file("workspace/HEARTBEAT.md", heartbeat_md)?;
+    }
+    reload_gateway_config().await?;
+    get_heartbeat_settings()
+}
+
+/// The most recent heartbeat runs recorded in the gateway logs, newest first.
+#[command]
+fn get_heartbeat_status(limit: Option<usize>) -> Result<Vec<HeartbeatRun>, String> {
+    let lines = shell_command(&format!(
+        "grep -h -i heartbeat \"{}\"/logs/*.log /tmp/openclaw/openclaw-*.log 2>/dev/null | tail -n 200; true",
+        paths::shell_root()
+    ))?;
+    Ok(lines
+        .lines()
+        .rev()
+        .filter_map(parse_heartbeat_log_line)
+        .take(limit.unwrap_or(10))
+        .collect())
+}
+
+// Messaging policy
+
+const DEFAULT_ACK_REACTION_SCOPE: &str = "group-mentions";
+const ACK_REACTION_SCOPES: &[&str] = &["group-mentions", "group-all", "direct", "all"];
+/// `closed` is stored as OpenClaw's `disabled`.
+const MESSAGING_DM_POLICIES: &[&str] = &["pairing", "allowlist", "open", "closed"];
+const MESSAGING_GROUP_POLICIES: &[&str] = &["open", "allowlist", "disabled"];
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+struct ChannelMessagingPolicy {
+    dm_policy: Option<String>,
+    group_policy: Option<String>,
+    group_allow_from: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+struct MessagingPolicy {
+    ack_reaction_scope: String,
+    channels: std::collections::BTreeMap<String, ChannelMessagingPolicy>,
+}
+
+fn validate_messaging_policy(policy: &MessagingPolicy) -> Result<(), String> {
+    let check = |field: &str, value: &str, allowed: &[&str]| {
+        if allowed.contains(&value) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid {} '{}'; expected one of {}",
+                field,
+                value,
+                allowed.join(", ")
+            ))
+        }
+    };
+    check(
+        "ack reaction scope",
+        &policy.ack_reaction_scope,
+        ACK_REACTION_SCOPES,
+    )?;
+    for (channel, channel_policy) in &policy.channels {
+        if !is_valid_session_id(channel) {
+            return Err(format!("Invalid channel: {}", channel));
+        }
+        if let Some(dm) = &channel_policy.dm_policy {
+            check(&format!("{} DM policy", channel), dm, MESSAGING_DM_POLICIES)?;
+        }
+        if let Some(group) = &channel_policy.group_policy {
+            check(
+                &format!("{} group policy", channel),
+                group,
+                MESSAGING_GROUP_POLICIES,
+            )?;
+            if group == "allowlist" && channel_policy.group_allow_from.is_empty() {
+                return Err(format!(
+                    "{} group allowlist needs at least one group or sender.",
+                    channel
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Where a channel's DM/group settings live: Telegram keeps them per account
+/// (`accounts.default`, as written by `configure_agent`), other channels at the top level.
+fn channel_policy_target<'a>(
+    channel: &'a serde_json::Value,
+) -> Option<&'a serde_json::Map<String, serde_json::Value>> {
+    channel
+        .get("accounts")
+        .and_then(|a| a.get("default"))
+        .unwrap_or(channel)
+        .as_object()
+}
+
+fn read_messaging_policy(config: &serde_json::Value) -> MessagingPolicy {
+    let ack_reaction_scope = config
+        .get("messages")
+        .and_then(|m| m.get("ackReactionScope"))
+        .and_then(|v| v.as_str())
+        .unwrap_or(DEFAULT_ACK_REACTION_SCOPE)
+        .to_string();
+    let channels = config
+        .get("channels")
+        .and_then(|c| c.as_object())
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, channel)| {
+            let target = channel_policy_target(channel)?;
+            let text = |key: &str| {
+                target
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+            };
+            let policy = ChannelMessagingPolicy {
+                dm_policy: text("dmPolicy").map(|p| {
+                    if p == "disabled" {
+                        "closed".to_string()
+                    } else {
+                        p
+                    }
+                }),
+                group_policy: text("groupPolicy"),
+                group_allow_from: target
+                    .get("groupAllowFrom")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect(),
+            };
+            Some((name.clone(), policy))
+        })
+        .collect();
+    MessagingPolicy {
+        ack_reaction_scope,
+        channels,
+    }
+}
+
+/// Applies `policy` to configured channels; channels missing from the config are skipped
+/// rather than created.
+fn apply_messaging_policy(config: &mut serde_json::Value, policy: &MessagingPolicy) {
+    let Some(root) = config.as_object_mut() else {
+        return;
+    };
+    if let Some(messages) = root
+        .entry("messages")
+        .or_insert(serde_json::json!({}))
+        .as_object_mut()
+    {
+        messages.insert(
+            "ackReactionScope".to_string(),
+            serde_json::json!(policy.ack_reaction_scope),
+        );
+    }
+
+    let Some(channels) = root.get_mut("channels").and_then(|c| c.as_object_mut()) else {
+        return;
+    };
+    for (name, channel_policy) in &policy.channels {
+        let Some(channel) = channels.get_mut(name) else {
+            continue;
+        };
+        let has_default_account = channel
+            .get("accounts")
+            .and_then(|a| a.get("default"))
+            .is_some();
+        let target = if has_default_account {
+            &mut channel["accounts"]["default"]
+        } else {
+            channel
+        };
+        let Some(target) = target.as_object_mut() else {
+            continue;
+        };
+
+        if let Some(dm) = &channel_policy.dm_policy {
+            let stored = if dm == "closed" {
+                "disabled"
+            } else {
+                dm.as_str()
+            };
+            target.insert("dmPolicy".to_string(), serde_json::json!(stored));
+            if stored == "open" {
+                target.insert("allowFrom".to_string(), serde_json::json!(["*"]));
+            } else if target.get("allowFrom") == Some(&serde_json::json!(["*"])) {
+                target.remove("allowFrom");
+            }
+        }
+        if let Some(group) = &channel_policy.group_policy {
+            target.insert("groupPolicy".to_string(), serde_json::json!(group));
+            if group == "allowlist" {
+                target.insert(
+                    "groupAllowFrom".to_string(),
+                    serde_json::json!(channel_policy.group_allow_from),
+                );
+            } else {
+                target.remove("groupAllowFrom");
+            }
+        }
+    }
+}
+
+#[command]
+fn get_messaging_policy() -> Result<MessagingPolicy, String> {
+    Ok(read_messaging_policy(&read_local_openclaw_config()?))
+}
+
+#[command]
+async fn set_messaging_policy(policy: MessagingPolicy) -> Result<MessagingPolicy, String> {
+    validate_messaging_policy(&policy)?;
+    let config = update_local_openclaw_config(|config| {
+        apply_messaging_policy(config, &policy);
+        Ok(config.clone())
+    })?;
+    reload_gateway_config().await?;
+    Ok(read_messaging_policy(&config))
+}
+
+// Telegram accounts
+
+const TELEGRAM_DEFAULT_ACCOUNT: &str = "default";
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct TelegramAccountInfo {
+    id: String,
+    name: Option<String>,
+    dm_policy: Option<String>,
+    has_token: bool,
+}
+
+fn is_valid_telegram_bot_token(token: &str) -> bool {
+    match token.split_once(':') {
+        Some((id, secret)) => {
+            !id.is_empty()
+                && id.chars().all(|c| c.is_ascii_digit())
+                && secret.len() >= 20
+                && secret
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        }
+        None => false,
+    }
+}
+
+fn telegram_accounts(config: &serde_json::Value) -> Vec<TelegramAccountInfo> {
+    config
+        .get("channels")
+        .and_then(|c| c.get("telegram"))
+        .and_then(|t| t.get("accounts"))
+        .and_then(|a| a.as_object())
+        .into_iter()
+        .flatten()
+        .map(|(id, account)| {
+            let text = |key: &str| {
+                account
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+            };
+            TelegramAccountInfo {
+                id: id.clone(),
+                name: text("name"),
+                dm_policy: text("dmPolicy"),
+                has_token: text("botToken").is_some_and(|t| !t.is_empty()),
+            }
+        })
+        .collect()
+}
+
+fn telegram_accounts_mut(
+    config: &mut serde_json::Value,
+) -> Option<&mut serde_json::Map<String, serde_json::Value>> {
+    config
+        .as_object_mut()?
+        .entry("channels")
+        .or_insert(serde_json::json!({}))
+        .as_object_mut()?
+        .entry("telegram")
+        .or_insert(serde_json::json!({}))
+        .as_object_mut()?
+        .entry("accounts")
+        .or_insert(serde_json::json!({}))
+        .as_object_mut()
+}
+
+fn add_telegram_account_to_config(
+    config: &mut serde_json::Value,
+    id: &str,
+    bot_token: &str,
+    name: &str,
+    dm_policy: &str,
+) -> Result<(), String> {
+    if !is_valid_session_id(id) {
+        return Err(format!("Invalid Telegram account id: {}", id));
+    }
+    if !is_valid_telegram_bot_token(bot_token) {
+        return Err("Invalid Telegram bot token; expected <bot id>:<secret>.".to_string());
+    }
+    if !["pairing", "allowlist", "open", "disabled"].contains(&dm_policy) {
+        return Err(format!("Invalid DM policy '{}'", dm_policy));
+    }
+    let accounts = telegram_accounts_mut(config)
+        .ok_or_else(|| "Invalid channels.telegram in openclaw.json".to_string())?;
+    if accounts.contains_key(id) {
+        return Err(format!("Telegram account '{}' already exists", id));
+    }
+    let duplicate_token = accounts
+        .values()
+        .any(|a| a.get("botToken").and_then(|v| v.as_str()) == Some(bot_token));
+    if duplicate_token {
+        return Err("Another Telegram account already uses this bot token.".to_string());
+    }
+    let mut account = serde_json::json!({
+        "botToken": bot_token,
+        "name": name.trim(),
+        "dmPolicy": dm_policy
+    });
+    if dm_policy == "open" {
+        account["allowFrom"] = serde_json::json!(["*"]);
+    }
+    accounts.insert(id.to_string(), account);
+    Ok(())
+}
+
+fn rename_telegram_account_in_config(
+    config: &mut serde_json::Value,
+    id: &str,
+    name: &str,
+) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Account name cannot be empty.".to_string());
+    }
+    let account = telegram_accounts_mut(config)
+        .and_then(|accounts| accounts.get_mut(id))
+        .and_then(|a| a.as_object_mut())
+        .ok_or_else(|| format!("Telegram account '{}' not found", id))?;
+    account.insert("name".to_string(), serde_json::json!(name.trim()));
+    Ok(())
+}
+
+/// Removing the last account drops the Telegram channel entirely.
+fn remove_telegram_account_from_config(
+    config: &mut serde_json::Value,
+    id: &str,
+) -> Result<(), String> {
+    let accounts = telegram_accounts_mut(config)
+        .ok_or_else(|| "Invalid channels.telegram in openclaw.json".to_string())?;
+    if accounts.remove(id).is_none() {
+        return Err(format!("Telegram account '{}' not found", id));
+    }
+    if accounts.is_empty() {
+        if let Some(channels) = config.get_mut("channels").and_then(|c| c.as_object_mut()) {
+            channels.remove("telegram");
+        }
+    }
+    Ok(())
+}
+
+#[command]
+fn list_telegram_accounts() -> Result<Vec<TelegramAccountInfo>, String> {
+    Ok(telegram_accounts(&read_local_openclaw_config()?))
+}
+
+#[command]
+async fn add_telegram_account(
+    id: String,
+    bot_token: String,
+    name: String,
+    dm_policy: Option<String>,
+) -> Result<Vec<TelegramAccountInfo>, String> {
+    let config = update_local_openclaw_config(|config| {
+        add_telegram_account_to_config(
+            config,
+            &id,
+            bot_token.trim(),
+            &name,
+            dm_policy.as_deref().unwrap_or("pairing"),
+        )?;
+        merge_enabled_plugin_entries(config, &["telegram".to_string()]);
+        Ok(config.clone())
+    })?;
+    reload_gateway_config().await?;
+    Ok(telegram_accounts(&config))
+}
+
+#[command]
+async fn rename_telegram_account(
+    id: String,
+    name: String,
+) -> Result<Vec<TelegramAccountInfo>, String> {
+    let config = update_local_openclaw_config(|config| {
+        rename_telegram_account_in_config(config, &id, &name)?;
+        Ok(config.clone())
+    })?;
+    reload_gateway_config().await?;
+    Ok(telegram_accounts(&config))
+}
+
+#[command]
+async fn remove_telegram_account(id: String) -> Result<Vec<TelegramAccountInfo>, String> {
+    if id == TELEGRAM_DEFAULT_ACCOUNT {
+        return Err(
+            "The default Telegram account is managed by setup; reconfigure Telegram to change it."
+                .to_string(),
+        );
+    }
+    let config = update_local_openclaw_config(|config| {
+        remove_telegram_account_from_config(config, &id)?;
+        Ok(config.clone())
+    })?;
+    reload_gateway_config().await?;
+    Ok(telegram_accounts(&config))
+}
+
+// Telegram groups
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct TelegramGroup {
+    chat_id: String,
+    title: Option<String>,
+    /// `approved`, `denied`, or `pending` (seen in a session but not decided yet).
+    status: String,
+    require_mention: bool,
+}
+
+fn is_valid_telegram_chat_id(id: &str) -> bool {
+    let digits = id.strip_prefix('-').unwrap_or(id);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Group chat id from a session key such as `agent:main:telegram:group:-1001234:topic:7`.
+fn telegram_group_id_from_session_key(key: &str) -> Option<String> {
+    let segments: Vec<&str> = key.split(':').collect();
+    segments
+        .windows(3)
+        .find(|w| w[0] == "telegram" && w[1] == "group" && is_valid_telegram_chat_id(w[2]))
+        .map(|w| w[2].to_string())
+}
+
+/// Configured groups plus groups the bot has talked in (from `sessions`), deduplicated.
+fn telegram_groups(config: &serde_json::Value, sessions: &[SessionSummary]) -> Vec<TelegramGroup> {
+    let configured = config
+        .get("channels")
+        .and_then(|c| c.get("telegram"))
+        .and_then(|t| t.get("groups"))
+        .and_then(|g| g.as_object());
+    let mut groups: std::collections::BTreeMap<String, TelegramGroup> = configured
+        .into_iter()
+        .flatten()
+        .filter(|(id, _)| is_valid_telegram_chat_id(id))
+        .map(|(id, entry)| {
+            let enabled = entry.get("enabled").and_then(|v| v.as_bool()) != Some(false);
+            let group = TelegramGroup {
+                chat_id: id.clone(),
+                title: entry
+                    .get("title")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string()),
+                status: if enabled { "approved" } else { "denied" }.to_string(),
+                require_mention: entry
+                    .get("requireMention")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(true),
+            };
+            (id.clone(), group)
+        })
+        .collect();
+
+    for session in sessions {
+        let Some(chat_id) = telegram_group_id_from_session_key(&session.key) else {
+            continue;
+        };
+        let group = groups.entry(chat_id.clone()).or_insert(TelegramGroup {
+            chat_id,
+            title: None,
+            status: "pending".to_string(),
+            require_mention: true,
+        });
+        if group.title.is_none() {
+            group.title = session.display_name.clone();
+        }
+    }
+    groups.into_values().collect()
+}
+
+/// Writes the decision into `channels.telegram.groups`; a denied group stays listed with
+/// `enabled: false` so it is not re-offered as pending.
+fn set_telegram_group_in_config(
+    config: &mut serde_json::Value,
+    chat_id: &str,
+    approved: bool,
+    require_mention: Option<bool>,
+    title: Option<&str>,
+) -> Result<(), String> {
+    if !is_valid_telegram_chat_id(chat_id) {
+        return Err(format!("Invalid Telegram chat id: {}", chat_id));
+    }
+    let groups = config
+        .as_object_mut()
+        .and_then(|root| {
+            root.entry("channels")
+                .or_insert(serde_json::json!({}))
+                .as_object_mut()
+        })
+        .and_then(|channels| channels.get_mut("telegram"))
+        .and_then(|telegram| telegram.as_object_mut())
+        .ok_or_else(|| "Telegram is not configured.".to_string())?
+        .entry("groups")
+        .or_insert(serde_json::json!({}));
+    let Some(group) = groups
+        .as_object_mut()
+        .map(|g| {
+            g.entry(chat_id.to_string())
+                .or_insert(serde_json::json!({}))
+        })
+        .and_then(|g| g.as_object_mut())
+    else {
+        return Err("Invalid channels.telegram.groups in openclaw.json".to_string());
+    };
+    group.insert("enabled".to_string(), serde_json::json!(approved));
+    if let Some(require_mention) = require_mention {
+        group.insert(
+            "requireMention".to_string(),
+            serde_json::json!(require_mention),
+        );
+    } else {
+        group
+            .entry("requireMention".to_string())
+            .or_insert(serde_json::json!(true));
+    }
+    if let Some(title) = title.filter(|t| !t.trim().is_empty()) {
+        group.insert("title".to_string(), serde_json::json!(title.trim()));
+    }
+    Ok(())
+}
+
+#[command]
+async fn list_telegram_groups() -> Result<Vec<TelegramGroup>, String> {
+    let sessions = list_sessions(Some(false)).await.unwrap_or_default();
+    Ok(telegram_groups(&read_local_openclaw_config()?, &sessions))
+}
+
+#[command]
+async fn set_telegram_group_access(
+    chat_id: String,
+    approved: bool,
+    require_mention: Option<bool>,
+    title: Option<String>,
+) -> Result<Vec<TelegramGroup>, String> {
+    let config = update_local_openclaw_config(|config| {
+        set_telegram_group_in_config(
+            config,
+            &chat_id,
+            approved,
+            require_mention,
+            title.as_deref(),
+        )?;
+        Ok(config.clone())
+    })?;
+    reload_gateway_config().await?;
+    let sessions = list_sessions(Some(false)).await.unwrap_or_default();
+    Ok(telegram_groups(&config, &sessions))
+}
+
+// Telegram transport
+
+const TELEGRAM_WEBHOOK_PATH: &str = "/telegram-webhook";
+/// Port of OpenClaw's Telegram webhook listener, exposed through Tailscale funnel.
+const TELEGRAM_WEBHOOK_PORT: u16 = 8787;
+const WEBHOOK_VERIFY_ATTEMPTS: u32 = 10;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+struct TelegramTransport {
+    /// `polling` or `webhook`.
+    mode: String,
+    webhook_url: Option<String>,
+}
+
+fn read_telegram_transport(config: &serde_json::Value) -> TelegramTransport {
+    let webhook_url = config
+        .get("channels")
+        .and_then(|c| c.get("telegram"))
+        .and_then(|t| t.get("webhookUrl"))
+        .and_then(|v| v.as_str())
+        .filter(|url| !url.is_empty())
+        .map(|url| url.to_string());
+    TelegramTransport {
+        mode: if webhook_url.is_some() {
+            "webhook"
+        } else {
+            "polling"
+        }
+        .to_string(),
+        webhook_url,
+    }
+}
+
+/// `None` switches back to long polling.
+fn apply_telegram_transport(
+    config: &mut serde_json::Value,
+    webhook: Option<(&str, &str)>,
+) -> Result<(), String> {
+    let telegram = config
+        .get_mut("channels")
+        .and_then(|c| c.get_mut("telegram"))
+        .and_then(|t| t.as_object_mut())
+        .ok_or_else(|| "Telegram is not configured.".to_string())?;
+    match webhook {
+        Some((url, secret)) => {
+            telegram.insert("webhookUrl".to_string(), serde_json::json!(url));
+            telegram.insert("webhookSecret".to_string(), serde_json::json!(secret));
+            telegram.insert(
+                "webhookPath".to_string(),
+                serde_json::json!(TELEGRAM_WEBHOOK_PATH),
+            );
+        }
+        None => {
+            for key in ["webhookUrl", "webhookSecret", "webhookPath"] {
+                telegram.remove(key);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_webhook_url(url: &str) -> Result<(), String> {
+    let Some(rest) = url.strip_prefix("https://") else {
+        return Err("Telegram webhooks need an https:// URL.".to_string());
+    };
+    let host = rest.split(['/', ':']).next().unwrap_or("");
+    if host.is_empty() || host == "localhost" || host.starts_with("127.") {
+        return Err(format!("Webhook URL must be publicly reachable: {}", url));
+    }
+    Ok(())
+}
+
+/// Public webhook URL for this machine from `tailscale status --json`.
+fn tailscale_webhook_url(status: &serde_json::Value) -> Option<String> {
+    let dns_name = status
+        .get("Self")
+        .and_then(|s| s.get("DNSName"))
+        .and_then(|v| v.as_str())?
+        .trim_end_matches('.');
+    if dns_name.is_empty() {
+        return None;
+    }
+    Some(format!("https://{}{}", dns_name, TELEGRAM_WEBHOOK_PATH))
+}
+
+/// Checks Telegram's `getWebhookInfo` result against the transport we just applied.
+fn check_webhook_info(info: &serde_json::Value, expected_url: Option<&str>) -> Result<(), String> {
+    let result = info
+        .get("result")
+        .ok_or_else(|| "Telegram did not return webhook info.".to_string())?;
+    let url = result.get("url").and_then(|v| v.as_str()).unwrap_or("");
+    if url != expected_url.unwrap_or("") {
+        return Err(format!(
+            "Telegram reports webhook '{}' instead of '{}'",
+            url,
+            expected_url.unwrap_or("")
+        ));
+    }
+    if let Some(error) = result.get("last_error_message").and_then(|v| v.as_str()) {
+        if expected_url.is_some() {
+            return Err(format!("Telegram cannot deliver to the webhook: {}", error));
+        }
+    }
+    Ok(())
+}
+
+fn telegram_bot_token(config: &serde_json::Value) -> Option<String> {
+    config
+        .get("channels")
+        .and_then(|c| c.get("telegram"))
+        .and_then(|t| t.get("accounts"))
+        .and_then(|a| a.get(TELEGRAM_DEFAULT_ACCOUNT))
+        .and_then(|d| d.get("botToken"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// Any HTTP answer counts: the gateway's listener is not serving the path until the switch.
+async fn check_webhook_reachable(url: &str) -> Result<(), String> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?
+        .get(url)
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Webhook URL {} is not reachable: {}", url, e))
+}
+
+async fn verify_telegram_transport(token: &str, expected_url: Option<&str>) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let info_url = format!("https://api.telegram.org/bot{}/getWebhookInfo", token);
+    let mut last_error = String::new();
+    for _ in 0..WEBHOOK_VERIFY_ATTEMPTS {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let info = match client.get(&info_url).send().await {
+            Ok(resp) => resp
+                .json::<serde_json::Value>()
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(format!("Failed to reach Telegram: {}", e)),
+        };
+        match info.and_then(|info| check_webhook_info(&info, expected_url)) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+#[command]
+fn get_telegram_transport() -> Result<TelegramTransport, String> {
+    Ok(read_telegram_transport(&read_local_openclaw_config()?))
+}
+
+/// `source` is `tailscale` (funnel this machine) or `custom` (use `webhook_url`). Restores the
+/// previous config if Telegram does not confirm the new transport.
+#[command]
+async fn set_telegram_transport(
+    mode: String,
+    source: Option<String>,
+    webhook_url: Option<String>,
+) -> Result<TelegramTransport, String> {
+    let current = read_local_openclaw_config()?;
+    let token =
+        telegram_bot_token(&current).ok_or_else(|| "Telegram is not configured.".to_string())?;
+
+    let url = match mode.as_str() {
+        "polling" => None,
+        "webhook" => {
+            let url = match source.as_deref().unwrap_or("custom") {
+                "tailscale" => {
+                    let status = process::run("tailscale", &["status", "--json"])
+                        .map_err(|e| format!("Tailscale is not available: {}", e))?;
+                    let status: serde_json::Value = serde_json::from_str(&status)
+                        .map_err(|e| format!("Failed to parse tailscale status: {}", e))?;
+                    let url = tailscale_webhook_url(&status)
+                        .ok_or_else(|| "This machine has no Tailscale DNS name.".to_string())?;
+                    process::run(
+                        "tailscale",
+                        &["funnel", "--bg", &TELEGRAM_WEBHOOK_PORT.to_string()],
+                    )
+                    .map_err(|e| format!("Failed to enable Tailscale funnel: {}", e))?;
+                    url
+                }
+                "custom" => webhook_url
+                    .map(|u| u.trim().to_string())
+                    .ok_or_else(|| "A webhook URL is required.".to_string())?,
+                other => return Err(format!("Unknown webhook source '{}'", other)),
+            };
+            validate_webhook_url(&url)?;
+            check_webhook_reachable(&url).await?;
+            Some(url)
+        }
+        other => return Err(format!("Unknown Telegram transport '{}'", other)),
+    };
+
+    let secret = uuid::Uuid::new_v4().simple().to_string();
+    let (config, previous) = update_local_openclaw_config(|config| {
+        let previous = config["channels"]["telegram"].clone();
+        apply_telegram_transport(config, url.as_deref().map(|u| (u, secret.as_str())))?;
+        Ok((config.clone(), previous))
+    })?;
+    let verified = match reload_gateway_config().await {
+        Ok(()) => verify_telegram_transport(&token, url.as_deref()).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = verified {
+        update_local_openclaw_config(|config| {
+            config["channels"]["telegram"] = previous;
+            Ok(())
+        })?;
+        let _ = reload_gateway_config().await;
+        return Err(format!(
+            "{} The previous Telegram transport was restored.",
+            e
+        ));
+    }
+    Ok(read_telegram_transport(&config))
+}
+
+// Pairing QR code
+
+const TELEGRAM_PAIRING_START: &str = "pair";
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct PairingQr {
+    link: String,
+    /// QR code of `link` as an SVG document.
+    svg: String,
+    /// `svg` as a data URL, for an `<img src>`.
+    data_url: String,
+}
+
+fn telegram_deep_link(bot_username: &str, start: &str) -> String {
+    format!(
+        "https://t.me/{}?start={}",
+        bot_username.trim_start_matches('@'),
+        start
+    )
+}
+
+async fn telegram_bot_username(token: &str) -> Result<String, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let client = &client;
+    let me: serde_json::Value = retry::retry_async(
+        &retry::PROVIDER_VALIDATION,
+        |_| async move {
+            client
+                .get(format!("https://api.telegram.org/bot{}/getMe", token))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to reach Telegram: {}", e))?
+                .json::<serde_json::Value>()
+                .await
+                .map_err(|e| e.to_string())
+        },
+        |e: &String| retry::is_transient(e),
+        |_, _| {},
+    )
+    .await?;
+    if me.get("ok").and_then(|v| v.as_bool()) != Some(true) {
+        return Err(format!(
+            "Telegram rejected the bot token: {}",
+            me.get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown error")
+        ));
+    }
+    me.pointer("/result/username")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| "Telegram did not return the bot's username.".to_string())
+}
+
+/// Deep link that opens a chat with the configured bot, as a QR code for pairing from a phone.
+#[command]
+async fn get_pairing_qr(channel: String) -> Result<PairingQr, String> {
+    if channel != "telegram" {
+        return Err(format!(
+            "QR pairing is only available for Telegram, not '{}'.",
+            channel
+        ));
+    }
+    let token = telegram_bot_token(&read_local_openclaw_config()?)
+        .ok_or_else(|| "Telegram is not configured.".to_string())?;
+    let username = telegram_bot_username(&token).await?;
+    let link = telegram_deep_link(&username, TELEGRAM_PAIRING_START);
+    let svg = qr::QrCode::encode(link.as_bytes())?.to_svg();
+    Ok(PairingQr {
+        data_url: format!("data:image/svg+xml;base64,{}", BASE64_STANDARD.encode(&svg)),
+        link,
+        svg,
+    })
+}
+
+// Email channel
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+struct EmailChannelConfig {
+    address: String,
+    imap: email::MailServer,
+    smtp: email::MailServer,
+    /// Senders allowed to reach the agent; empty uses pairing.
+    allow_from: Vec<String>,
+}
+
+fn validate_email_channel(email_config: &EmailChannelConfig) -> Result<(), String> {
+    let address_ok = email_config
+        .address
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+    if !address_ok {
+        return Err(format!("Invalid email address: {}", email_config.address));
+    }
+    email_config.imap.validate("IMAP")?;
+    email_config.smtp.validate("SMTP")
+}
+
+fn apply_email_channel(config: &mut serde_json::Value, email_config: &EmailChannelConfig) {
+    let server = |s: &email::MailServer| {
+        serde_json::json!({
+            "host": s.host,
+            "port": s.port,
+            "user": s.user,
+            "password": s.password
+        })
+    };
+    let mut channel = serde_json::json!({
+        "enabled": true,
+        "address": email_config.address,
+        "imap": server(&email_config.imap),
+        "smtp": server(&email_config.smtp),
+        "dmPolicy": "pairing"
+    });
+    if !email_config.allow_from.is_empty() {
+        channel["dmPolicy"] = serde_json::json!("allowlist");
+        channel["allowFrom"] = serde_json::json!(email_config.allow_from);
+    }
+    let Some(root) = config.as_object_mut() else {
+        return;
+    };
+    if let Some(channels) = root
+        .entry("channels")
+        .or_insert(serde_json::json!({}))
+        .as_object_mut()
+    {
+        channels.insert("email".to_string(), channel);
+    }
+    merge_enabled_plugin_entries(config, &["email".to_string()]);
+}
+
+#[command]
+fn test_email_credentials(imap: email::MailServer, smtp: email::MailServer) -> Result<(), String> {
+    imap.validate("IMAP")?;
+    smtp.validate("SMTP")?;
+    email::check_imap(&imap)?;
+    email::check_smtp(&smtp)
+}
+
+#[command]
+async fn configure_email_channel(email_config: EmailChannelConfig) -> Result<String, String> {
+    validate_email_channel(&email_config)?;
+    email::check_imap(&email_config.imap)?;
+    email::check_smtp(&email_config.smtp)?;
+    update_local_openclaw_config(|config| {
+        apply_email_channel(config, &email_config);
+        Ok(())
+    })?;
+    reload_gateway_config().await?;
+    Ok(format!(
+        "Email channel configured for {}",
+        email_config.address
+    ))
+}
+
+// Twilio SMS channel
+
+const TWILIO_API_BASE: &str = "https://api.twilio.com/2010-04-01";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+struct TwilioConfig {
+    account_sid: String,
+    auth_token: String,
+    /// E.164 number owned by the account, e.g. `+15551234567`.
+    phone_number: String,
+}
+
+fn is_e164_number(number: &str) -> bool {
+    number.strip_prefix('+').is_some_and(|digits| {
+        (8..=15).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+fn validate_twilio_config(twilio: &TwilioConfig) -> Result<(), String> {
+    let sid_ok = twilio.account_sid.len() == 34
+        && twilio.account_sid.starts_with("AC")
+        && twilio.account_sid[2..]
+            .chars()
+            .all(|c| c.is_ascii_hexdigit());
+    if !sid_ok {
+        return Err(
+            "Invalid Twilio Account SID; it starts with AC followed by 32 hex characters."
+                .to_string(),
+        );
+    }
+    if twilio.auth_token.len() != 32 || !twilio.auth_token.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("Invalid Twilio auth token.".to_string());
+    }
+    if !is_e164_number(&twilio.phone_number) {
+        return Err(format!(
+            "Invalid phone number '{}'; use E.164 format like +15551234567",
+            twilio.phone_number
+        ));
+    }
+    Ok(())
+}
+
+/// Whether an `IncomingPhoneNumbers` listing contains `number`.
+fn twilio_number_listed(listing: &serde_json::Value, number: &str) -> bool {
+    listing
+        .get("incoming_phone_numbers")
+        .and_then(|n| n.as_array())
+        .is_some_and(|numbers| {
+            numbers
+                .iter()
+                .any(|n| n.get("phone_number").and_then(|v| v.as_str()) == Some(number))
+        })
+}
+
+fn apply_twilio_sms_channel(config: &mut serde_json::Value, twilio: &TwilioConfig) {
+    let Some(root) = config.as_object_mut() else {
+        return;
+    };
+    if let Some(channels) = root
+        .entry("channels")
+        .or_insert(serde_json::json!({}))
+        .as_object_mut()
+    {
+        channels.insert(
+            "sms".to_string(),
+            serde_json::json!({
+                "enabled": true,
+                "provider": "twilio",
+                "accountSid": twilio.account_sid,
+                "authToken": twilio.auth_token,
+                "fromNumber": twilio.phone_number,
+                "dmPolicy": "pairing"
+            }),
+        );
+    }
+    merge_enabled_plugin_entries(config, &["sms".to_string()]);
+}
+
+/// Checks the credentials and that the number belongs to the account.
+async fn verify_twilio_credentials(twilio: &TwilioConfig) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = client
+        .get(format!(
+            "{}/Accounts/{}/IncomingPhoneNumbers.json",
+            TWILIO_API_BASE, twilio.account_sid
+        ))
+        .query(&[("PhoneNumber", twilio.phone_number.as_str())])
+        .basic_auth(&twilio.account_sid, Some(&twilio.auth_token))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Err("Twilio rejected the Account SID or auth token.".to_string());
+    }
+    if !resp.status().is_success() {
+        return Err(format!("Twilio API error: HTTP {}", resp.status()));
+    }
+    let listing: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    if !twilio_number_listed(&listing, &twilio.phone_number) {
+        return Err(format!(
+            "{} is not a phone number on this Twilio account.",
+            twilio.phone_number
+        ));
+    }
+    Ok(())
+}
+
+#[command]
+async fn configure_twilio_sms(twilio: TwilioConfig) -> Result<String, String> {
+    let twilio = TwilioConfig {
+        account_sid: twilio.account_sid.trim().to_string(),
+        auth_token: twilio.auth_token.trim().to_string(),
+        phone_number: twilio.phone_number.replace([' ', '-', '(', ')'], ""),
+    };
+    validate_twilio_config(&twilio)?;
+    verify_twilio_credentials(&twilio).await?;
+    update_local_openclaw_config(|config| {
+        apply_twilio_sms_channel(config, &twilio);
+        Ok(())
+    })?;
+    reload_gateway_config().await?;
+    Ok(format!(
+        "SMS channel configured for {}",
+        twilio.phone_number
+    ))
+}
+
+// Channel toggles
+
+const CHANNEL_TOGGLE_VERIFY_ATTEMPTS: u32 = 10;
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct ChannelToggleResult {
+    channel: String,
+    enabled: bool,
+    /// `None` when the gateway is not running or cannot report channel state.
+    connected: Option<bool>,
+}
+
+/// Sets `channels.<channel>.enabled`, and the `enabled` flag of every Telegram-style account.
+fn set_channel_enabled_in_config(
+    config: &mut serde_json::Value,
+    channel: &str,
+    enabled: bool,
+) -> Result<(), String> {
+    let entry = config
+        .get_mut("channels")
+        .and_then(|c| c.get_mut(channel))
+        .and_then(|c| c.as_object_mut())
+        .ok_or_else(|| format!("Channel '{}' is not configured.", channel))?;
+    entry.insert("enabled".to_string(), serde_json::json!(enabled));
+    if let Some(accounts) = entry.get_mut("accounts").and_then(|a| a.as_object_mut()) {
+        for account in accounts.values_mut().filter_map(|a| a.as_object_mut()) {
+            account.insert("enabled".to_string(), serde_json::json!(enabled));
+        }
+    }
+    set_plugin_enabled_in_config(config, channel, enabled);
+    Ok(())
+}
+
+/// Whether `channel` is up according to a gateway `channels.status` payload.
+fn channel_running_from_status(status: &serde_json::Value, channel: &str) -> Option<bool> {
+    let flag = |value: &serde_json::Value| {
+        value
+            .get("connected")
+            .or_else(|| value.get("running"))
+            .and_then(|v| v.as_bool())
+    };
+    if let Some(accounts) = status
+        .get("channelAccounts")
+        .and_then(|a| a.get(channel))
+        .and_then(|a| a.as_array())
+    {
+        let flags: Vec<bool> = accounts.iter().filter_map(flag).collect();
+        if !flags.is_empty() {
+            return Some(flags.into_iter().any(|f| f));
+        }
+    }
+    match status.get("channels").and_then(|c| c.get(channel)) {
+        Some(entry) => flag(entry),
+        None => status.get("channels").map(|_| false),
+    }
+}
+
+#[command]
+async fn set_channel_enabled(
+    channel: String,
+    enabled: bool,
+) -> Result<ChannelToggleResult, String> {
+    if !is_valid_session_id(&channel) {
+        return Err(format!("Invalid channel: {}", channel));
+    }
+    update_local_openclaw_config(|config| {
+        set_channel_enabled_in_config(config, &channel, enabled)?;
+        Ok(())
+    })?;
+    reload_gateway_config().await?;
+
+    let client = gateway_client::GatewayClient::local();
+    let mut connected = None;
+    if client.is_reachable().await {
+        for _ in 0..CHANNEL_TOGGLE_VERIFY_ATTEMPTS {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            connected = match client.channels_status().await {
+                Ok(status) => channel_running_from_status(&status, &channel),
+                Err(_) => None,
+            };
+            if connected == Some(enabled) {
+                break;
+            }
+        }
+        if connected == Some(!enabled) {
+            return Err(format!(
+                "The gateway reloaded but {} is still {}.",
+                channel,
+                if enabled { "disconnected" } else { "connected" }
+            ));
+        }
+    }
+    Ok(ChannelToggleResult {
+        channel,
+        enabled,
+        connected,
+    })
+}
+
+// Setup manifest
+
+const AUTH_PROFILES_FILE: &str = "agents/main/agent/auth-profiles.json";
+
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ManifestProvider {
+    api_key: Option<String>,
+    /// Environment variable holding the key, read when the manifest is applied.
+    api_key_env: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ManifestModel {
+    primary: String,
+    #[serde(default)]
+    fallbacks: Vec<String>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ManifestWorkspace {
+    /// Workspace file name (e.g. `AGENTS.md`) to its full content.
+    #[serde(default)]
+    files: std::collections::BTreeMap<String, String>,
+}
+
+/// Desired state of a machine. Sections left out are not touched.
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct SetupManifest {
+    #[serde(default)]
+    providers: std::collections::BTreeMap<String, ManifestProvider>,
+    model: Option<ManifestModel>,
+    /// Channel name to the keys it must have; other keys of the channel are kept.
+    #[serde(default)]
+    channels: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    plugins: Vec<String>,
+    workspace: Option<ManifestWorkspace>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct ManifestResult {
+    /// What was changed, e.g. `model`, `channels.telegram`, `workspace/AGENTS.md`. Empty
+    /// when the machine already matched the manifest.
+    changed: Vec<String>,
+}
+
+fn parse_setup_manifest(path: &str, raw: &str) -> Result<SetupManifest, String> {
+    if path.ends_with(".yaml") || path.ends_with(".yml") {
+        return Err("YAML manifests are not supported yet; save the manifest as JSON.".to_string());
+    }
+    let manifest: SetupManifest =
+        serde_json::from_str(raw).map_err(|e| format!("Invalid manifest: {}", e))?;
+    if let Some(model) = &manifest.model {
+        for m in std::iter::once(&model.primary).chain(&model.fallbacks) {
+            if !m.contains('/') {
+                return Err(format!("Invalid model '{}'; expected provider/model", m));
+            }
+        }
+    }
+    for name in manifest.channels.keys().chain(&manifest.plugins) {
+        if !is_valid_session_id(name) {
+            return Err(format!("Invalid channel or plugin name: {}", name));
+        }
+    }
+    for name in manifest.workspace.iter().flat_map(|w| w.files.keys()) {
+        if name.contains('/') || name.contains("..") || !name.ends_with(".md") {
+            return Err(format!("Invalid workspace file name: {}", name));
+        }
+    }
+    Ok(manifest)
+}
+
+/// Applies the config-level parts of `manifest` and returns what changed.
+fn apply_manifest_to_config(
+    config: &mut serde_json::Value,
+    manifest: &SetupManifest,
+) -> Vec<String> {
+    let mut changed = Vec::new();
+    if !config.is_object() {
+        *config = serde_json::json!({});
+    }
+
+    if let Some(model) = &manifest.model {
+        let desired = serde_json::json!({ "primary": model.primary, "fallbacks": model.fallbacks });
+        if config.pointer("/agents/defaults/model") != Some(&desired) {
+            config["agents"]["defaults"]["model"] = desired;
+            changed.push("model".to_string());
+        }
+    }
+
+    for (name, desired) in &manifest.channels {
+        let current = config
+            .pointer(&format!("/channels/{}", name))
+            .cloned()
+            .unwrap_or(serde_json::json!({}));
+        let mut merged = current.clone();
+        match (merged.as_object_mut(), desired.as_object()) {
+            (Some(merged_obj), Some(desired_obj)) => {
+                for (key, value) in desired_obj {
+                    merged_obj.insert(key.clone(), value.clone());
+                }
+            }
+            _ => merged = desired.clone(),
+        }
+        if merged != current || config.pointer(&format!("/channels/{}", name)).is_none() {
+            config["channels"][name.as_str()] = merged;
+            changed.push(format!("channels.{}", name));
+        }
+    }
+
+    for plugin in &manifest.plugins {
+        let enabled = config
+            .pointer(&format!("/plugins/entries/{}/enabled", plugin))
+            .and_then(|v| v.as_bool());
+        if enabled != Some(true) {
+            set_plugin_enabled_in_config(config, plugin, true);
+            changed.push(format!("plugins.{}", plugin));
+        }
+    }
+    changed
+}
+
+/// Sets a `<provider>:default` API-key profile for each manifest provider.
+fn apply_manifest_to_auth_profiles(
+    doc: &mut serde_json::Value,
+    providers: &std::collections::BTreeMap<String, ManifestProvider>,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Result<Vec<String>, String> {
+    let mut changed = Vec::new();
+    if !doc.is_object() {
+        *doc =
+            serde_json::json!({ "version": 1, "profiles": {}, "lastGood": {}, "usageStats": {} });
+    }
+    for (provider, entry) in providers {
+        let key = match (&entry.api_key, &entry.api_key_env) {
+            (Some(key), _) => key.clone(),
+            (None, Some(var)) => env(var)
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| format!("Environment variable {} is not set", var))?,
+            (None, None) => return Err(format!("Provider {} needs apiKey or apiKeyEnv", provider)),
+        };
+        let profile_key = format!("{}:default", provider);
+        let profile = default_provider_auth(provider, &key, "token", None)
+            .profile
+            .unwrap_or(serde_json::json!({}));
+        if doc.pointer(&format!("/profiles/{}", profile_key)) != Some(&profile) {
+            doc["profiles"][profile_key.as_str()] = profile;
+            doc["lastGood"][provider.as_str()] = serde_json::json!(profile_key);
+            changed.push(format!("auth.{}", provider));
+        }
+    }
+    Ok(changed)
+}
+
+/// Converges this machine to the manifest at `path`. Re-running it with the same manifest
+/// changes nothing.
+#[command]
+async fn apply_setup_manifest(path: String) -> Result<ManifestResult, String> {
+    let _operation = lifecycle::begin_operation("apply_setup_manifest").await;
+    let raw = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let manifest = parse_setup_manifest(&path, &raw)?;
+    let mut changed = Vec::new();
+
+    if !manifest.providers.is_empty() {
+        let mut doc = read_provider_auth_profiles().unwrap_or(serde_json::json!({}));
+        let auth_changes =
+            apply_manifest_to_auth_profiles(&mut doc, &manifest.providers, &|var| {
+                std::env::var(var).ok()
+            })?;
+        if !auth_changes.is_empty() {
+            let raw = serde_json::to_string_pretty(&doc).map_err(|e| e.to_string())?;
+            write_local_openclaw_file(AUTH_PROFILES_FILE, &raw)?;
+            changed.extend(auth_changes);
+        }
+    }
+
+    changed.extend(update_local_openclaw_config_or_default(|config| {
+        Ok(apply_manifest_to_config(config, &manifest))
+    })?);
+
+    for (name, content) in manifest.workspace.iter().flat_map(|w| &w.files) {
+        let relative = format!("workspace/{}", name);
+        if read_local_openclaw_file(&relative).ok().as_deref() != Some(content.as_str()) {
+            write_local_openclaw_file(&relative, content)?;
+            changed.push(relative);
+        }
+    }
+
+    if !changed.is_empty() {
+        reload_gateway_config().await?;
+    }
+    Ok(ManifestResult { changed })
+}
+
+// Config drift
+
+/// Snapshot of the last `configure_agent` output, kept next to `clawnetes-meta.json`.
+const APPLIED_CONFIG_FILE: &str = "clawnetes-applied.json";
+/// Fields the gateway and CLI rewrite on their own; differences there are not drift.
+const DRIFT_IGNORED_PREFIXES: &[&str] = &["/meta", "/wizard", "/usageStats"];
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct DriftEntry {
+    /// `<file>:<json pointer>`, passed back to `apply_drift_fixes`.
+    id: String,
+    file: String,
+    path: String,
+    /// `None` when the field is missing on that side. Secret values are masked.
+    expected: Option<serde_json::Value>,
+    actual: Option<serde_json::Value>,
+}
+
+fn escape_pointer_segment(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// Leaf values of `value` keyed by JSON pointer; arrays count as leaves.
+fn flatten_json(
+    value: &serde_json::Value,
+    prefix: &str,
+    out: &mut std::collections::BTreeMap<String, serde_json::Value>,
+) {
+    match value.as_object() {
+        Some(obj) if !obj.is_empty() => {
+            for (key, child) in obj {
+                flatten_json(
+                    child,
+                    &format!("{}/{}", prefix, escape_pointer_segment(key)),
+                    out,
+                );
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+fn mask_secret(path: &str, value: Option<serde_json::Value>) -> Option<serde_json::Value> {
+    let key = path.rsplit('/').next().unwrap_or("");
+    match value {
+        Some(serde_json::Value::String(_)) if is_secret_key(key) => Some(serde_json::json!("••••")),
+        other => other,
+    }
+}
+
+fn config_drift(
+    file: &str,
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+) -> Vec<DriftEntry> {
+    let (mut want, mut have) = (Default::default(), Default::default());
+    flatten_json(expected, "", &mut want);
+    flatten_json(actual, "", &mut have);
+    let paths: std::collections::BTreeSet<&String> = want.keys().chain(have.keys()).collect();
+    paths
+        .into_iter()
+        .filter(|path| {
+            !DRIFT_IGNORED_PREFIXES
+                .iter()
+                .any(|prefix| path.starts_with(&format!("{}/", prefix)) || path == prefix)
+        })
+        .filter(|path| want.get(*path) != have.get(*path))
+        .map(|path| DriftEntry {
+            id: format!("{}:{}", file, path),
+            file: file.to_string(),
+            path: path.clone(),
+            expected: mask_secret(path, want.get(path).cloned()),
+            actual: mask_secret(path, have.get(path).cloned()),
+        })
+        .collect()
+}
+
+/// Sets (or with `None`, removes) the value at `pointer`, creating parent objects.
+fn set_json_pointer(root: &mut serde_json::Value, pointer: &str, value: Option<serde_json::Value>) {
+    let segments: Vec<String> = pointer
+        .split('/')
+        .skip(1)
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    let Some((last, parents)) = segments.split_last() else {
+        return;
+    };
+    let Some(value) = value else {
+        remove_json_path(root, &segments);
+        return;
+    };
+    let mut current = root;
+    for segment in parents {
+        if !current.is_object() {
+            *current = serde_json::json!({});
+        }
+        let Some(obj) = current.as_object_mut() else {
+            return;
+        };
+        current = obj
+            .entry(segment.clone())
+            .or_insert_with(|| serde_json::json!({}));
+    }
+    if !current.is_object() {
+        *current = serde_json::json!({});
+    }
+    if let Some(obj) = current.as_object_mut() {
+        obj.insert(last.clone(), value);
+    }
+}
+
+/// Removes the value at `segments` and any parent objects left empty by it.
+fn remove_json_path(value: &mut serde_json::Value, segments: &[String]) {
+    let Some((first, rest)) = segments.split_first() else {
+        return;
+    };
+    let Some(obj) = value.as_object_mut() else {
+        return;
+    };
+    if rest.is_empty() {
+        obj.remove(first);
+        return;
+    }
+    if let Some(child) = obj.get_mut(first) {
+        remove_json_path(child, rest);
+        if child.as_object().is_some_and(|c| c.is_empty()) {
+            obj.remove(first);
+        }
+    }
+}
+
+fn read_applied_config() -> Result<serde_json::Value, String> {
+    let raw = read_local_openclaw_file(APPLIED_CONFIG_FILE).map_err(|_| {
+        "No saved setup to compare against. Run the setup wizard once first.".to_string()
+    })?;
+    serde_json::from_str(&raw)
+        .map_err(|e| format!("Failed to parse {}: {}", APPLIED_CONFIG_FILE, e))
+}
+
+#[command]
+fn check_config_drift() -> Result<Vec<DriftEntry>, String> {
+    let applied = read_applied_config()?;
+    let config = read_local_openclaw_config().unwrap_or(serde_json::json!({}));
+    let auth = read_provider_auth_profiles().unwrap_or(serde_json::json!({}));
+    let mut drift = config_drift("openclaw.json", &applied["config"], &config);
+    drift.extend(config_drift(
+        "auth-profiles.json",
+        &applied["authProfiles"],
+        &auth,
+    ));
+    Ok(drift)
+}
+
+/// Restores the selected fields (ids from `check_config_drift`) to their configured values
+/// and returns the drift that remains.
+#[command]
+async fn apply_drift_fixes(selected: Vec<String>) -> Result<Vec<DriftEntry>, String> {
+    let _operation = lifecycle::begin_operation("apply_drift_fixes").await;
+    let applied = read_applied_config()?;
+    let (config_changed, auth_changed) = update_local_openclaw_config_or_default(|config| {
+        let mut auth = read_provider_auth_profiles().unwrap_or(serde_json::json!({}));
+        let (mut config_changed, mut auth_changed) = (false, false);
+        for id in &selected {
+            let Some((file, path)) = id.split_once(':') else {
+                return Err(format!("Invalid drift id: {}", id));
+            };
+            match file {
+                "openclaw.json" => {
+                    set_json_pointer(config, path, applied["config"].pointer(path).cloned());
+                    config_changed = true;
+                }
+                "auth-profiles.json" => {
+                    set_json_pointer(
+                        &mut auth,
+                        path,
+                        applied["authProfiles"].pointer(path).cloned(),
+                    );
+                    auth_changed = true;
+                }
+                _ => return Err(format!("Invalid drift id: {}", id)),
+            }
+        }
+        if auth_changed {
+            let raw = serde_json::to_string_pretty(&auth).map_err(|e| e.to_string())?;
+            write_local_openclaw_file(AUTH_PROFILES_FILE, &raw)?;
+        }
+        Ok((config_changed, auth_changed))
+    })?;
+    if config_changed || auth_changed {
+        reload_gateway_config().await?;
+    }
+    check_config_drift()
+}
+
+// Remote deployment
+
+const REMOTE_DEPLOY_STEPS: &[&str] = &["connect", "prerequisites", "setup", "verify"];
+
+#[derive(serde::Serialize)]
+struct RemoteDeployResult {
+    gateway_token: String,
+    status: GatewayStatus,
+}
+
+/// Overall percent when `step` starts, or finishes when `done`.
+fn remote_deploy_percent(step: &str, done: bool) -> u8 {
+    let index = REMOTE_DEPLOY_STEPS
+        .iter()
+        .position(|s| *s == step)
+        .unwrap_or(0);
+    progress::fraction(0, 100, index + usize::from(done), REMOTE_DEPLOY_STEPS.len())
+}
+
+fn remote_prerequisite_summary(check: &PrereqCheck) -> String {
+    let mut parts = vec![
+        if check.node_installed {
+            "Node.js found"
+        } else {
+            "Node.js missing (will install)"
+        }
+        .to_string(),
+        if check.openclaw_installed {
+            "openclaw found"
+        } else {
+            "openclaw missing (will install)"
+        }
+        .to_string(),
+    ];
+    parts.extend(check.resources.warnings.iter().cloned());
+    parts.join("; ")
+}
+
+/// Connects, checks prerequisites, installs openclaw, pushes the config and starts the gateway
+/// on `remote`, emitting a `task-progress` event as each step starts and finishes.
+#[command]
+async fn deploy_remote_gateway(
+    app: tauri::AppHandle,
+    remote: RemoteInfo,
+    config: AgentConfig,
+    task_id: Option<String>,
+) -> Result<RemoteDeployResult, String> {
+    let progress =
+        progress::Progress::new(&app, "remote-deploy", task_id).with_steps(REMOTE_DEPLOY_STEPS);
+    let emit = |step: &str, status: &str, message: &str| match status {
+        "failed" => progress.error(step, remote_deploy_percent(step, false), message),
+        "done" => progress.info(step, remote_deploy_percent(step, true), message),
+        _ => progress.info(step, remote_deploy_percent(step, false), message),
+    };
+    let fail = |step: &str, e: String| {
+        emit(step, "failed", &e);
+        e
+    };
+
+    emit(
+        "connect",
+        "running",
+        &format!("Connecting to {}", remote.ip),
+    );
+    test_ssh_connection(remote.clone())
+        .await
+        .map_err(|e| fail("connect", e))?;
+    emit(
+        "connect",
+        "done",
+        &format!("Connected to {}@{}", remote.user, remote.ip),
+    );
+
+    emit(
+        "prerequisites",
+        "running",
+        "Checking Node.js, openclaw and system resources",
+    );
+    let check = check_remote_prerequisites(remote.clone())
+        .await
+        .map_err(|e| fail("prerequisites", e))?;
+    emit(
+        "prerequisites",
+        "done",
+        &remote_prerequisite_summary(&check),
+    );
+
+    emit(
+        "setup",
+        "running",
+        "Installing openclaw, writing the config and starting the gateway",
+    );
+    let gateway_token = setup_remote_openclaw(remote.clone(), config)
+        .await
+        .map_err(|e| fail("setup", e))?;
+    emit("setup", "done", "Configuration pushed and gateway started");
+
+    emit("verify", "running", "Checking the remote gateway");
+    let status = get_gateway_status(app.clone(), Some(remote), None)
+        .await
+        .map_err(|e| fail("verify", e))?;
+    if !status.running {
+        return Err(fail(
+            "verify",
+            format!(
+                "The remote gateway is not running after setup. Status: {}",
+                status.raw_output.trim()
+            ),
+        ));
+    }
+    emit("verify", "done", "Remote gateway is running");
+
+    Ok(RemoteDeployResult {
+        gateway_token,
+        status,
+    })
+}
+
+// Container deployment
+
+const CONTAINER_RUNTIMES: &[&str] = &["docker", "podman"];
+const CONTAINER_NAME: &str = "clawnetes-gateway";
+const CONTAINER_IMAGE: &str = "node:22-bookworm-slim";
+const CONTAINER_COMPOSE_FILE: &str = "clawnetes-compose.yml";
+const CONTAINER_ENV_FILE: &str = "clawnetes-container.env";
+const CONTAINER_DEPLOY_TIMEOUT_SECS: u64 = 600;
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct ContainerStatus {
+    /// `docker` or `podman`; `None` when neither daemon answers.
+    runtime: Option<String>,
+    /// Whether Clawnetes has written a compose file.
+    deployed: bool,
+    running: bool,
+    /// Runtime state (`running`, `exited`, ...), `None` when the container does not exist.
+    state: Option<String>,
+    detail: Option<String>,
+}
+
+/// `<PROVIDER>_API_KEY` for a provider id, e.g. `google-vertex` -> `GOOGLE_VERTEX_API_KEY`.
+fn provider_env_var(provider: &str) -> String {
+    let name: String = provider
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}_API_KEY", name)
+}
+
+/// Secrets for the container's env file: the gateway token plus one API key per provider
+/// with a static token. OAuth and local providers keep using the mounted auth profiles.
+fn container_env_vars(
+    gateway_token: &str,
+    auth_profiles: &serde_json::Value,
+) -> Result<Vec<(String, String)>, String> {
+    let mut vars = vec![(
+        "OPENCLAW_GATEWAY_TOKEN".to_string(),
+        gateway_token.to_string(),
+    )];
+    if let Some(profiles) = auth_profiles.get("profiles").and_then(|p| p.as_object()) {
+        for profile in profiles.values() {
+            let (Some(provider), Some(token)) = (
+                profile.get("provider").and_then(|v| v.as_str()),
+                profile.get("token").and_then(|v| v.as_str()),
+            ) else {
+                continue;
+            };
+            let name = provider_env_var(provider);
+            if token.is_empty() || token == "dummy-token" || vars.iter().any(|(n, _)| *n == name) {
+                continue;
+            }
+            vars.push((name, token.to_string()));
+        }
+    }
+    if vars.iter().any(|(_, value)| value.contains(['\n', '\r'])) {
+        return Err(
+            "Secrets containing line breaks cannot be passed to the container.".to_string(),
+        );
+    }
+    Ok(vars)
+}
+
+fn render_container_env_file(vars: &[(String, String)]) -> String {
+    vars.iter()
+        .map(|(name, value)| format!("{}={}\n", name, value))
+        .collect()
+}
+
+/// The state directory is mounted at its host path, because `openclaw.json` refers to
+/// workspaces and agent directories by absolute host path.
+fn render_compose_file(openclaw_root: &str, port: u16) -> String {
+    format!(
+        r#"# Generated by Clawnetes. Secrets are injected from {env_file}.
+services:
+  gateway:
+    image: {image}
+    container_name: {name}
+    restart: unless-stopped
+    user: node
+    env_file: {env_file}
+    environment:
+      HOME: /home/node
+      NPM_CONFIG_PREFIX: /home/node/.npm-global
+      PATH: /home/node/.npm-global/bin:/usr/local/bin:/usr/bin:/bin
+      OPENCLAW_STATE_DIR: "{root}"
+      OPENCLAW_CONFIG_PATH: "{root}/openclaw.json"
+    command: ["sh", "-c", "npm install -g openclaw@latest && exec openclaw gateway --bind lan --port {port}"]
+    ports:
+      - "127.0.0.1:{port}:{port}"
+    volumes:
+      - "{root}:{root}"
+      - openclaw-npm:/home/node/.npm-global
+volumes:
+  openclaw-npm:
+"#,
+        env_file = CONTAINER_ENV_FILE,
+        image = CONTAINER_IMAGE,
+        name = CONTAINER_NAME,
+        port = port,
+        root = openclaw_root.replace('"', "\\\""),
+    )
+}
+
+/// Parses `<runtime> ps --format '{{.State}}|{{.Status}}'` output.
+fn parse_container_state(output: &str) -> Option<(String, String)> {
+    let line = output.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let (state, detail) = line.split_once('|').unwrap_or((line, ""));
+    Some((state.trim().to_lowercase(), detail.trim().to_string()))
+}
+
+fn detect_container_runtime() -> Option<&'static str> {
+    CONTAINER_RUNTIMES
+        .iter()
+        .copied()
+        .find(|rt| shell_command(&format!("{} info", rt)).is_ok())
+}
+
+/// `docker compose` / `podman compose`, falling back to the standalone `*-compose` binaries.
+fn container_compose_command(runtime: &str) -> Result<String, String> {
+    [
+        format!("{} compose", runtime),
+        format!("{}-compose", runtime),
+    ]
+    .into_iter()
+    .find(|cmd| shell_command(&format!("{} version", cmd)).is_ok())
+    .ok_or_else(|| format!("{} is installed but no compose plugin was found.", runtime))
+}
+
+fn container_compose_args() -> Result<String, String> {
+    let compose_path = paths::local_path(CONTAINER_COMPOSE_FILE)?;
+    Ok(format!(
+        "-p clawnetes -f {}",
+        shell_single_quote(&compose_path)
+    ))
+}
+
+/// Runs the gateway in a Docker or Podman container instead of the npm-global install. The
+/// local `.openclaw` directory is mounted into the container at the same path and secrets go through an env
+/// file readable only by the current user.
+#[command]
+async fn deploy_gateway_container() -> Result<String, String> {
+    let _operation = lifecycle::begin_operation("deploy_gateway_container").await;
+    let runtime = detect_container_runtime()
+        .ok_or("Neither Docker nor Podman is running. Start one and try again.")?;
+    let compose = container_compose_command(runtime)?;
+
+    let (port, token) = read_local_gateway_port_and_token()?;
+    let auth_profiles = read_provider_auth_profiles().unwrap_or_else(|_| serde_json::json!({}));
+    let vars = container_env_vars(&token, &auth_profiles)?;
+
+    write_local_private_file(
+        &paths::local_path(CONTAINER_ENV_FILE)?,
+        &render_container_env_file(&vars),
+    )?;
+    write_local_openclaw_file(
+        CONTAINER_COMPOSE_FILE,
+        &render_compose_file(&paths::local_root()?, port),
+    )?;
+
+    // The native service would hold the port the container publishes.
+    let _ = shell_command("openclaw gateway stop");
+
+    process::shell_with_timeout(
+        &format!("{} {} up -d", compose, container_compose_args()?),
+        Duration::from_secs(CONTAINER_DEPLOY_TIMEOUT_SECS),
+    )?
+    .into_result()
+    .map_err(|e| format!("Failed to start the gateway container: {}", e.trim()))?;
+
+    Ok(format!(
+        "Gateway container '{}' started with {} on port {}.",
+        CONTAINER_NAME, runtime, port
+    ))
+}
+
+#[command]
+fn container_status() -> Result<ContainerStatus, String> {
+    let deployed = paths::local_path(CONTAINER_COMPOSE_FILE)
+        .map(|p| Path::new(&p).exists())
+        .unwrap_or(false);
+    let Some(runtime) = detect_container_runtime() else {
+        return Ok(ContainerStatus {
+            runtime: None,
+            deployed,
+            running: false,
+            state: None,
+            detail: None,
+        });
+    };
+    let output = shell_command(&format!(
+        "{} ps -a --filter 'name=^{}$' --format '{{{{.State}}}}|{{{{.Status}}}}'",
+        runtime, CONTAINER_NAME
+    ))?;
+    let parsed = parse_container_state(&output);
+    Ok(ContainerStatus {
+        runtime: Some(runtime.to_string()),
+        deployed,
+        running: parsed.as_ref().is_some_and(|(state, _)| state == "running"),
+        state: parsed.as_ref().map(|(state, _)| state.clone()),
+        detail: parsed.map(|(_, detail)| detail).filter(|d| !d.is_empty()),
+    })
+}
+
+#[command]
+fn container_logs(lines: Option<u32>) -> Result<String, String> {
+    let runtime = detect_container_runtime().ok_or("Neither Docker nor Podman is running.")?;
+    let output = process::shell(&format!(
+        "{} logs --tail {} {}",
+        runtime,
+        lines.unwrap_or(200).min(5000),
+        CONTAINER_NAME
+    ))?;
+    // Container logs arrive on both streams; keep them together.
+    if output.success() {
+        Ok(format!("{}{}", output.stdout, output.stderr))
+    } else {
+        output.into_result()
+    }
+}
+
+// Smoke tests
+
+const SMOKE_TEST_FILE: &str = ".clawnetes-smoke-test";
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct SmokeTestResult {
+    name: String,
+    /// `pass`, `fail` or `skip`.
+    status: String,
+    message: String,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct SmokeTestReport {
+    passed: bool,
+    tests: Vec<SmokeTestResult>,
+}
+
+fn smoke_result(name: &str, result: Result<Option<String>, String>) -> SmokeTestResult {
+    let (status, message) = match result {
+        Ok(Some(message)) => ("pass", message),
+        Ok(None) => ("skip", "Not configured".to_string()),
+        Err(e) => ("fail", e),
+    };
+    SmokeTestResult {
+        name: name.to_string(),
+        status: status.to_string(),
+        message,
+    }
+}
+
+/// Request for a one-token completion with `model`, or `None` for providers without a
+/// known API shape.
+fn completion_probe_request(
+    client: &reqwest::Client,
+    provider: &str,
+    model: &str,
+    key: &str,
+) -> Option<reqwest::RequestBuilder> {
+    let openai_body = serde_json::json!({
+        "model": model,
+        "max_tokens": 1,
+        "messages": [{ "role": "user", "content": "ping" }],
+    });
+    let request = match provider {
+        "anthropic" => client
+            .post("https://api.anthropic.com/v1/messages")
+            .header("x-api-key", key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&openai_body),
+        "google" => client
+            .post(format!(
+                "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
+                model
+            ))
+            .header("x-goog-api-key", key)
+            .json(&serde_json::json!({
+                "contents": [{ "parts": [{ "text": "ping" }] }],
+                "generationConfig": { "maxOutputTokens": 1 },
+            })),
+        "openrouter" => client
+            .post("https://openrouter.ai/api/v1/chat/completions")
+            .bearer_auth(key)
+            .json(&openai_body),
+        _ => client
+            .post(format!(
+                "https://{}/v1/chat/completions",
+                provider_api_host(provider)?
+            ))
+            .bearer_auth(key)
+            .json(&openai_body),
+    };
+    Some(request)
+}
+
+/// Primary model as `(provider, model)`.
+fn primary_model(config: &serde_json::Value) -> Option<(String, String)> {
+    let primary = config
+        .pointer("/agents/defaults/model/primary")
+        .and_then(|v| v.as_str())?;
+    let (provider, model) = primary.split_once('/')?;
+    Some((provider.to_string(), model.to_string()))
+}
+
+async fn smoke_test_provider(config: &serde_json::Value) -> Result<Option<String>, String> {
+    let Some((provider, model)) = primary_model(config) else {
+        return Ok(None);
+    };
+    let profile = read_provider_auth_profiles()?
+        .pointer(&format!("/profiles/{}:default", provider))
+        .cloned();
+    let key = match profile
+        .as_ref()
+        .and_then(|p| p.get("token"))
+        .and_then(|v| v.as_str())
+    {
+        Some(key) if !key.is_empty() && key != "dummy-token" && !key.starts_with("sk-ant-oat") => {
+            key.to_string()
+        }
+        // OAuth sign-ins, Claude setup tokens and local models have no static key to try.
+        _ => return Ok(None),
+    };
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+    if completion_probe_request(&client, &provider, &model, &key).is_none() {
+        return Ok(None);
+    }
+    let (client, provider, model, key) = (&client, &provider, &model, &key);
+    let result = retry::retry_async(
+        &retry::PROVIDER_VALIDATION,
+        |_| async move {
+            let request = completion_probe_request(client, provider, model, key)
+                .ok_or_else(|| format!("No completion probe for {}", provider))?;
+            let resp = request
+                .send()
+                .await
+                .map_err(|e| format!("Failed to reach {}: {}", provider, e))?;
+            let status = resp.status();
+            if status.is_success() {
+                return Ok(Some(format!("{} answered with {}", provider, model)));
+            }
+            let body = resp.text().await.unwrap_or_default();
+            Err(format!(
+                "{} returned HTTP {}: {}",
+                provider,
+                status.as_u16(),
+                body.chars().take(300).collect::<String>()
+            ))
+        },
+        |e: &String| retry::is_transient(e),
+        |_, _| {},
+    )
+    .await;
+    if let Some(signal) = result
+        .as_ref()
+        .err()
+        .and_then(|e| provider_health::parse_provider_error(e))
+    {
+        if let Ok(mut tracker) = PROVIDER_HEALTH.lock() {
+            tracker.apply(&signal, unix_now_secs());
+        }
+    }
+    result
+}
+
+fn smoke_test_workspace(config: &serde_json::Value) -> Result<Option<String>, String> {
+    let workspace = match config
+        .pointer("/agents/defaults/workspace")
+        .and_then(|v| v.as_str())
+    {
+        Some(path) => path.to_string(),
+        None => paths::local_path("workspace")?,
+    };
+    let probe = format!("{}/{}", workspace.trim_end_matches('/'), SMOKE_TEST_FILE);
+    write_local_text_file(&probe, "ok")
+        .map_err(|e| format!("Workspace {} is not writable: {}", workspace, e))?;
+    remove_local_file(&probe)?;
+    Ok(Some(format!("{} is writable", workspace)))
+}
+
+fn smoke_test_permissions() -> Result<Option<String>, String> {
+    let exposed: Vec<String> = local_secret_file_modes()
+        .into_iter()
+        .filter(|file| file.mode & 0o004 != 0)
+        .map(|file| file.path)
+        .collect();
+    if exposed.is_empty() {
+        Ok(Some("Secret files are not world-readable".to_string()))
+    } else {
+        Err(format!(
+            "Readable by every user on this machine: {}. Use Harden permissions to fix this.",
+            exposed.join(", ")
+        ))
+    }
+}
+
+/// End-to-end checks for the final setup screen: CLI, gateway, provider key (one-token
+/// completion), Telegram bot, workspace and secret file permissions.
+#[command]
+async fn run_smoke_tests() -> Result<SmokeTestReport, String> {
+    let config = read_local_openclaw_config().unwrap_or_else(|_| serde_json::json!({}));
+    let mut tests = vec![
+        smoke_result(
+            "cli",
+            shell_command("openclaw --version").map(|v| Some(format!("openclaw {}", v.trim()))),
+        ),
+        smoke_result(
+            "gateway",
+            if gateway_client::GatewayClient::local().is_reachable().await {
+                Ok(Some("Gateway accepts connections".to_string()))
+            } else {
+                Err("The gateway is not reachable.".to_string())
+            },
+        ),
+        smoke_result("provider", smoke_test_provider(&config).await),
+    ];
+    let telegram = match telegram_bot_token(&config) {
+        Some(token) => telegram_bot_username(&token)
+            .await
+            .map(|name| Some(format!("Bot @{} is reachable", name))),
+        None => Ok(None),
+    };
+    tests.push(smoke_result("telegram", telegram));
+    tests.push(smoke_result("workspace", smoke_test_workspace(&config)));
+    tests.push(smoke_result("permissions", smoke_test_permissions()));
+
+    Ok(SmokeTestReport {
+        passed: tests.iter().all(|t| t.status != "fail"),
+        tests,
+    })
+}
+
+// Background tasks
+
+#[command]
+fn get_background_tasks(
+    state: tauri::State<'_, std::sync::Arc<lifecycle::AppState>>,
+) -> Vec<lifecycle::TaskInfo> {
+    state.tasks()
+}
+
+/// The mutating operation currently running and those waiting for it.
+#[command]
+fn get_active_operation(
+    state: tauri::State<'_, std::sync::Arc<lifecycle::AppState>>,
+) -> lifecycle::OperationStatus {
+    state.operation_status()
+}
+
+// Plugin manager
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct PluginInfo {
+    id: String,
+    name: String,
+    version: Option<String>,
+    description: Option<String>,
+    status: String,
+    origin: String,
+    installed: bool,
+    enabled: bool,
+}
+
+fn is_valid_plugin_spec(spec: &str) -> bool {
+    !spec.is_empty()
+        && !spec.starts_with('-')
+        && spec
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@/._-".contains(c))
+}
+
+/// Bundled plugins ship with openclaw and are "available"; everything else was installed.
+/// `plugins.entries.<id>.enabled` in the config wins over the CLI-reported status.
+fn plugin_infos(list: &cli::PluginList, config: &serde_json::Value) -> Vec<PluginInfo> {
+    let entries = config.get("plugins").and_then(|p| p.get("entries"));
+    list.plugins
+        .iter()
+        .map(|plugin| {
+            let status = plugin
+                .status
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string());
+            let origin = plugin
+                .origin
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string());
+            let enabled = entries
+                .and_then(|e| e.get(&plugin.id))
+                .and_then(|e| e.get("enabled"))
+                .and_then(|v| v.as_bool())
+                .or(plugin.enabled)
+                .unwrap_or(status == "loaded");
+            PluginInfo {
+                id: plugin.id.clone(),
+                name: plugin.name.clone().unwrap_or_else(|| plugin.id.clone()),
+                version: plugin.version.clone(),
+                description: plugin.description.clone(),
+                installed: origin != "bundled",
+                status,
+                origin,
+                enabled,
+            }
+        })
+        .collect()
+}
+
+/// Sets `plugins.entries.<id>.enabled`, preserving any other settings on the entry.
+fn set_plugin_enabled_in_config(config: &mut serde_json::Value, plugin_id: &str, enabled: bool) {
+    if !config.is_object() {
+        *config = serde_json::json!({});
+    }
+    let plugins = config
+        .as_object_mut()
+        .unwrap()
+        .entry("plugins".to_string())
+        .or_insert_with(|| serde_json::json!({}));
+    if !plugins.is_object() {
+        *plugins = serde_json::json!({});
+    }
+    let entries = plugins
+        .as_object_mut()
+        .unwrap()
+        .entry("entries".to_string())
+        .or_insert_with(|| serde_json::json!({}));
+    if !entries.is_object() {
+        *entries = serde_json::json!({});
+    }
+    let entry = entries
+        .as_object_mut()
+        .unwrap()
+        .entry(plugin_id.to_string())
+        .or_insert_with(|| serde_json::json!({}));
+    match entry.as_object_mut() {
+        Some(obj) => {
+            obj.insert("enabled".to_string(), serde_json::json!(enabled));
+        }
+        None => *entry = serde_json::json!({ "enabled": enabled }),
+    }
+}
+
+/// Asks a running gateway to reload its config, restarting it if the RPC is unavailable.
+async fn reload_gateway_config() -> Result<(), String> {
+    let client = gateway_client::GatewayClient::local().single_attempt();
+    if !client.is_reachable().await {
+        return Ok(());
+    }
+    if client.reload_config().await.is_ok() {
+        return Ok(());
+    }
+    restart_local_gateway()
+}
+
+async fn toggle_plugin(name: &str, enabled: bool) -> Result<Vec<PluginInfo>, String> {
+    if !is_valid_plugin_spec(name) {
+        return Err(format!("Invalid plugin name: {}", name));
+    }
+    update_local_openclaw_config(|config| {
+        set_plugin_enabled_in_config(config, name, enabled);
+        Ok(())
+    })?;
+    reload_gateway_config().await?;
+    list_plugins()
+}
+
+#[command]
+fn list_plugins() -> Result<Vec<PluginInfo>, String> {
+    let list = cli::plugins_list()?;
+    let config = read_local_openclaw_config().unwrap_or(serde_json::json!({}));
+    Ok(plugin_infos(&list, &config))
+}
+
+#[command]
+async fn enable_plugin(name: String) -> Result<Vec<PluginInfo>, String> {
+    toggle_plugin(&name, true).await
+}
+
+#[command]
+async fn disable_plugin(name: String) -> Result<Vec<PluginInfo>, String> {
+    toggle_plugin(&name, false).await
+}
+
+/// Installs a plugin (npm spec or bundled id) and enables it.
+#[command]
+async fn install_plugin(name: String) -> Result<Vec<PluginInfo>, String> {
+    if !is_valid_plugin_spec(&name) {
+        return Err(format!("Invalid plugin name: {}", name));
+    }
+    let before: Vec<String> = list_plugins()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| p.id)
+        .collect();
+    process::run("openclaw", &["plugins", "install", &name])
+        .map_err(|e| format!("Failed to install plugin {}: {}", name, e))?;
+
+    // npm specs (e.g. @scope/pkg) install under the plugin's own id; enable whatever appeared.
+    let installed: Vec<String> = list_plugins()?
+        .into_iter()
+        .map(|p| p.id)
+        .filter(|id| !before.contains(id) || *id == name)
+        .collect();
+    update_local_openclaw_config(|config| {
+        for id in &installed {
+            set_plugin_enabled_in_config(config, id, true);
+        }
+        Ok(())
+    })?;
+    reload_gateway_config().await?;
+    list_plugins()
+}
+
+// MCP servers
+
+const MCP_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[command]
+fn list_mcp_servers() -> Result<Vec<mcp::McpServer>, String> {
+    Ok(mcp::list(&read_local_openclaw_config()?))
+}
+
+/// Exports the secrets of `server` to the gateway service as `MCP_<SERVER>_<NAME>`
+/// variables, dropping the `removed` ones.
+fn export_mcp_secrets(
+    server: &str,
+    secrets: &[(String, String)],
+    removed: &[String],
+) -> Result<(), String> {
+    let previous = read_gateway_env();
+    let mut next = previous.clone();
+    for name in removed {
+        next.remove(&mcp::secret_env_name(server, name));
+    }
+    for (name, value) in secrets {
+        next.insert(mcp::secret_env_name(server, name), value.clone());
+    }
+    save_gateway_env(&previous, &next)
+}
+
+/// Adds or replaces the MCP server `name`, run as `command args` over stdio or reached at
+/// `url`. Credential-like `env` entries (headers for URL servers) go to the gateway
+/// environment instead of openclaw.json.
+#[command]
+async fn add_mcp_server(
+    name: String,
+    command: Option<String>,
+    args: Option<Vec<String>>,
+    url: Option<String>,
+    env: Option<std::collections::BTreeMap<String, String>>,
+) -> Result<Vec<mcp::McpServer>, String> {
+    let non_empty = |value: Option<String>| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let server = mcp::McpServer {
+        name: name.trim().to_string(),
+        command: non_empty(command),
+        args: args.unwrap_or_default(),
+        url: non_empty(url),
+        ..Default::default()
+    };
+    let (entry, secrets) = mcp::config_entry(&server, &env.unwrap_or_default())?;
+
+    let (config, stale) = update_local_openclaw_config(|config| {
+        let stale: Vec<String> = mcp::list(config)
+            .into_iter()
+            .find(|existing| existing.name == server.name)
+            .map(|existing| existing.secret_env)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|name| !secrets.iter().any(|(secret, _)| secret == name))
+            .collect();
+        config_sections::update(config, &format!("{}.{}", mcp::SECTION, server.name), entry)?;
+        Ok((config.clone(), stale))
+    })?;
+
+    if secrets.is_empty() && stale.is_empty() {
+        reload_gateway_config().await?;
+    } else {
+        // The gateway only reads its environment at startup.
+        export_mcp_secrets(&server.name, &secrets, &stale)?;
+        restart_local_gateway()?;
+    }
+    Ok(mcp::list(&config))
+}
+
+#[command]
+async fn remove_mcp_server(name: String) -> Result<Vec<mcp::McpServer>, String> {
+    let (config, server) = update_local_openclaw_config(|config| {
+        let server = mcp::list(config)
+            .into_iter()
+            .find(|server| server.name == name)
+            .ok_or_else(|| format!("Unknown MCP server '{}'", name))?;
+        if let Some(servers) = config
+            .pointer_mut("/mcp/servers")
+            .and_then(|s| s.as_object_mut())
+        {
+            servers.remove(&name);
+        }
+        Ok((config.clone(), server))
+    })?;
+
+    if server.secret_env.is_empty() {
+        reload_gateway_config().await?;
+    } else {
+        export_mcp_secrets(&name, &[], &server.secret_env)?;
+        restart_local_gateway()?;
+    }
+    Ok(mcp::list(&config))
+}
+
+/// Sends `initialize` to a stdio server started with `env` and waits for its answer.
+fn mcp_stdio_handshake(
+    server: &mcp::McpServer,
+    command: &str,
+    env: &std::collections::BTreeMap<String, String>,
+) -> Result<mcp::Handshake, String> {
+    let program = process::resolve(command)?;
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut cmd = Command::new("wsl");
+        cmd.args(["--exec", "/usr/bin/env"])
+            .args(
+                env.iter()
+                    .map(|(name, value)| format!("{}={}", name, value)),
+            )
+            .arg(&program)
+            .args(&server.args);
+        cmd
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut cmd = {
+        let mut cmd = Command::new(&program);
+        if let Some(path) = process::environment_info().shell_path {
+            cmd.env("PATH", path);
+        }
+        cmd.args(&server.args).envs(env);
+        cmd
+    };
+    let mut child = cmd
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", command, e))?;
+
+    let stdout = child.stdout.take();
+    let (tx, rx) = std::sync::mpsc::channel();
+    thread::spawn(move || {
+        let Some(stdout) = stdout else { return };
+        let lines = std::io::BufRead::lines(std::io::BufReader::new(stdout));
+        for line in lines.map_while(Result::ok) {
+            if let Some(message) = mcp::parse_message(&line) {
+                if tx.send(message).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    let request = format!("{}\n", mcp::initialize_request(1));
+    let written = child
+        .stdin
+        .as_mut()
+        .map(|stdin| stdin.write_all(request.as_bytes()))
+        .unwrap_or(Ok(()));
+
+    let deadline = Instant::now() + MCP_HANDSHAKE_TIMEOUT;
+    let response = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(message) if message.get("id") == Some(&serde_json::json!(1)) => break Ok(message),
+            Ok(_) => continue,
+            Err(_) => {
+                break Err(match (&written, child.try_wait()) {
+                    (Err(e), _) => format!("Failed to talk to {}: {}", command, e),
+                    (_, Ok(Some(status))) => format!("{} exited with {}", command, status),
+                    _ => "The MCP server did not answer within 30 seconds.".to_string(),
+                })
+            }
+        }
+    };
+    let _ = child.kill();
+    let _ = child.wait();
+    mcp::parse_handshake(&response?)
+}
+
+/// Posts `initialize` to a streamable-HTTP server with `headers`.
+fn mcp_http_handshake(
+    url: &str,
+    headers: &std::collections::BTreeMap<String, String>,
+) -> Result<mcp::Handshake, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(MCP_HANDSHAKE_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client
+        .post(url)
+        .header("Accept", "application/json, text/event-stream")
+        .json(&mcp::initialize_request(1));
+    for (name, value) in headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    let response = request
+        .send()
+        .map_err(|e| format!("Could not reach {}: {}", url, e))?;
+    let status = response.status();
+    let body = response.text().unwrap_or_default();
+    if !status.is_success() {
+        return Err(format!("{} answered HTTP {}", url, status.as_u16()));
+    }
+    let message = mcp::parse_message(&body)
+        .ok_or_else(|| format!("{} did not answer with an MCP message", url))?;
+    mcp::parse_handshake(&message)
+}
+
+/// Starts (or calls) the MCP server `name` with its stored secrets and performs the
+/// `initialize` handshake, reporting the server's name and protocol version.
+#[command]
+async fn test_mcp_server(name: String) -> Result<mcp::Handshake, String> {
+    let server = mcp::list(&read_local_openclaw_config()?)
+        .into_iter()
+        .find(|server| server.name == name)
+        .ok_or_else(|| format!("Unknown MCP server '{}'", name))?;
+    let mut env = server.env.clone();
+    let exported = read_gateway_env();
+    for secret in &server.secret_env {
+        let value = exported
+            .get(&mcp::secret_env_name(&name, secret))
+            .ok_or_else(|| {
+                format!(
+                    "{} for {} is missing from the gateway environment; add the server again.",
+                    secret, name
+                )
+            })?;
+        env.insert(secret.clone(), value.clone());
+    }
+    tauri::async_runtime::spawn_blocking(move || match (&server.command, &server.url) {
+        (Some(command), _) => mcp_stdio_handshake(&server, command, &env),
+        (None, Some(url)) => mcp_http_handshake(url, &env),
+        (None, None) => Err(format!(
+            "MCP server '{}' has no command or URL",
+            server.name
+        )),
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Browser tool
+
+const BROWSER_SETUP_STEPS: &[&str] = &["detect", "install", "configure"];
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct BrowserToolsResult {
+    executable: String,
+    /// Whether Chromium was downloaded because no browser was found.
+    installed: bool,
+}
+
+fn detect_browser() -> Option<String> {
+    let output = process::shell(&browser::detect_script()).ok()?;
+    output
+        .stdout
+        .lines()
+        .map(str::trim)
+        .rfind(|line| line.starts_with('/'))
+        .map(str::to_string)
+}
+
+/// Downloads Chromium with Playwright, reporting the download on the `install` step.
+fn install_playwright_chromium(progress: &progress::Progress) -> Result<(), String> {
+    let output = process::spawn_streaming(
+        process::shell_process(browser::INSTALL_COMMAND),
+        browser::INSTALL_COMMAND.to_string(),
+        process::INSTALL_TIMEOUT,
+        |line| match browser::download_percent(line) {
+            Some(percent) => progress.info("install", percent, line.trim()),
+            None if !line.trim().is_empty() => progress.info("install", 0, line.trim()),
+            None => {}
+        },
+    )?;
+    output.into_result().map(|_| ())
+}
+
+fn find_or_install_browser(progress: &progress::Progress) -> Result<BrowserToolsResult, String> {
+    progress.info("detect", 0, "Looking for an installed browser");
+    if let Some(executable) = detect_browser() {
+        progress.info("detect", 100, &format!("Found {}", executable));
+        return Ok(BrowserToolsResult {
+            executable,
+            installed: false,
+        });
+    }
+    progress.info("install", 0, "Downloading Chromium");
+    install_playwright_chromium(progress)?;
+    let executable =
+        detect_browser().ok_or("Chromium was downloaded but its executable could not be found.")?;
+    progress.info("install", 100, &format!("Installed {}", executable));
+    Ok(BrowserToolsResult {
+        executable,
+        installed: true,
+    })
+}
+
+/// Gives the agent's browser tool a browser to drive: an installed Chrome, Chromium, Brave or
+/// Edge, or else Chromium downloaded with Playwright. Then enables the tool in the config.
+#[command]
+async fn setup_browser_tools(
+    app: tauri::AppHandle,
+    task_id: Option<String>,
+) -> Result<BrowserToolsResult, String> {
+    let _operation = lifecycle::begin_operation("setup_browser_tools").await;
+    let progress =
+        progress::Progress::new(&app, "browser-setup", task_id).with_steps(BROWSER_SETUP_STEPS);
+    let reporter = progress.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || find_or_install_browser(&reporter))
+        .await
+        .map_err(|e| format!("Browser setup failed: {}", e))?;
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            progress.error("install", 0, &e);
+            return Err(e);
+        }
+    };
+    progress.info("configure", 0, "Enabling the browser tool");
+    update_local_openclaw_config(|config| {
+        browser::enable_in_config(config, &result.executable);
+        Ok(())
+    })?;
+    reload_gateway_config().await?;
+    progress.info("configure", 100, "Browser tool enabled");
+    Ok(result)
+}
+
+// Tool and skill permissions
+
+const TOOL_PROFILES: &[&str] = &["minimal", "coding", "messaging", "full"];
+const EXEC_ASK_MODES: &[&str] = &["off", "on-miss", "always"];
+const EXEC_SECURITY_MODES: &[&str] = &["deny", "allowlist", "full"];
+const SANDBOX_MODES: &[&str] = &["off", "non-main", "all"];
+const WORKSPACE_ACCESS_MODES: &[&str] = &["none", "ro", "rw"];
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+struct SkillPermission {
+    name: String,
+    enabled: bool,
+}
+
+/// Typed view of `tools.*`, `agents.defaults.sandbox` and `skills.entries` in openclaw.json.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+struct ToolPermissions {
+    profile: Option<String>,
+    allow: Vec<String>,
+    deny: Vec<String>,
+    /// When shell commands need user confirmation (`tools.exec.ask`).
+    exec_ask: Option<String>,
+    exec_security: Option<String>,
+    elevated_enabled: bool,
+    sandbox_mode: Option<String>,
+    workspace_access: Option<String>,
+    skills: Vec<SkillPermission>,
+}
+
+fn string_list(value: Option<&serde_json::Value>) -> Vec<String> {
+    value
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+fn read_tool_permissions(config: &serde_json::Value) -> ToolPermissions {
+    let tools = config.get("tools");
+    let text =
+        |value: Option<&serde_json::Value>| value.and_then(|v| v.as_str()).map(|s| s.to_string());
+    let sandbox = config
+        .get("agents")
+        .and_then(|a| a.get("defaults"))
+        .and_then(|d| d.get("sandbox"));
+    let mut skills: Vec<SkillPermission> = config
+        .get("skills")
+        .and_then(|s| s.get("entries"))
+        .and_then(|e| e.as_object())
+        .map(|entries| {
+            entries
+                .iter()
+                .map(|(name, entry)| SkillPermission {
+                    name: name.clone(),
+                    enabled: entry
+                        .get("enabled")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(true),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    skills.sort_by(|a, b| a.name.cmp(&b.name));
+
+    ToolPermissions {
+        profile: text(tools.and_then(|t| t.get("profile"))),
+        allow: string_list(tools.and_then(|t| t.get("allow"))),
+        deny: string_list(tools.and_then(|t| t.get("deny"))),
+        exec_ask: text(tools.and_then(|t| t.get("exec")).and_then(|e| e.get("ask"))),
+        exec_security: text(
+            tools
+                .and_then(|t| t.get("exec"))
+                .and_then(|e| e.get("security")),
+        ),
+        elevated_enabled: tools
+            .and_then(|t| t.get("elevated"))
+            .and_then(|e| e.get("enabled"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        sandbox_mode: text(sandbox.and_then(|s| s.get("mode"))),
+        workspace_access: text(sandbox.and_then(|s| s.get("workspaceAccess"))),
+        skills,
+    }
+}
+
+fn validate_tool_permissions(permissions: &ToolPermissions) -> Result<(), String> {
+    let check = |value: &Option<String>, allowed: &[&str], field: &str| match value {
+        Some(v) if !allowed.contains(&v.as_str()) => Err(format!(
+            "Invalid {} '{}'. Expected one of: {}",
+            field,
+            v,
+            allowed.join(", ")
+        )),
+        _ => Ok(()),
+    };
+    check(&permissions.profile, TOOL_PROFILES, "tool profile")?;
+    check(
+        &permissions.exec_ask,
+        EXEC_ASK_MODES,
+        "exec confirmation mode",
+    )?;
+    check(
+        &permissions.exec_security,
+        EXEC_SECURITY_MODES,
+        "exec security mode",
+    )?;
+    check(&permissions.sandbox_mode, SANDBOX_MODES, "sandbox mode")?;
+    check(
+        &permissions.workspace_access,
+        WORKSPACE_ACCESS_MODES,
+        "workspace access",
+    )?;
+    if let Some(tool) = permissions
+        .allow
+        .iter()
+        .find(|t| permissions.deny.contains(t))
+    {
+        return Err(format!(
+            "Tool '{}' cannot be both allowed and denied.",
+            tool
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the object at `key`, creating it (or replacing a non-object value) as needed.
+fn object_entry<'a>(
+    map: &'a mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+) -> &'a mut serde_json::Map<String, serde_json::Value> {
+    let child = map
+        .entry(key.to_string())
+        .or_insert_with(|| serde_json::json!({}));
+    if !child.is_object() {
+        *child = serde_json::json!({});
+    }
+    child.as_object_mut().unwrap()
+}
+
+fn set_or_remove(
+    map: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    value: Option<serde_json::Value>,
+) {
+    match value {
+        Some(v) => {
+            map.insert(key.to_string(), v);
+        }
+        None => {
+            map.remove(key);
+        }
+    }
+}
+
+fn remove_if_empty(map: &mut serde_json::Map<String, serde_json::Value>, key: &str) {
+    if map
+        .get(key)
+        .and_then(|v| v.as_object())
+        .map(|o| o.is_empty())
+        .unwrap_or(false)
+    {
+        map.remove(key);
+    }
+}
+
+/// Writes the typed permissions back, leaving unrelated keys in each section untouched.
+fn apply_tool_permissions(config: &mut serde_json::Value, permissions: &ToolPermissions) {
+    if !config.is_object() {
+        *config = serde_json::json!({});
+    }
+    let root = config.as_object_mut().unwrap();
+    let text = |value: &Option<String>| value.clone().map(serde_json::Value::from);
+    let list = |items: &Vec<String>| Some(serde_json::json!(items)).filter(|_| !items.is_empty());
+
+    let tools = object_entry(root, "tools");
+    set_or_remove(tools, "profile", text(&permissions.profile));
+    set_or_remove(tools, "allow", list(&permissions.allow));
+    set_or_remove(tools, "deny", list(&permissions.deny));
+    let exec = object_entry(tools, "exec");
+    set_or_remove(exec, "ask", text(&permissions.exec_ask));
+    set_or_remove(exec, "security", text(&permissions.exec_security));
+    remove_if_empty(tools, "exec");
+    if permissions.elevated_enabled || tools.contains_key("elevated") {
+        object_entry(tools, "elevated").insert(
+            "enabled".to_string(),
+            serde_json::json!(permissions.elevated_enabled),
+        );
+    }
+
+    let defaults = object_entry(object_entry(root, "agents"), "defaults");
+    let sandbox = object_entry(defaults, "sandbox");
+    set_or_remove(sandbox, "mode", text(&permissions.sandbox_mode));
+    set_or_remove(
+        sandbox,
+        "workspaceAccess",
+        text(&permissions.workspace_access),
+    );
+    remove_if_empty(defaults, "sandbox");
+
+    if !permissions.skills.is_empty() {
+        let entries = object_entry(object_entry(root, "skills"), "entries");
+        for skill in &permissions.skills {
+            object_entry(entries, &skill.name)
+                .insert("enabled".to_string(), serde_json::json!(skill.enabled));
+        }
+    }
+}
+
+#[command]
+fn get_tool_permissions() -> Result<ToolPermissions, String> {
+    Ok(read_tool_permissions(&read_local_openclaw_config()?))
+}
+
+#[command]
+async fn set_tool_permissions(permissions: ToolPermissions) -> Result<ToolPermissions, String> {
+    validate_tool_permissions(&permissions)?;
+    let config = update_local_openclaw_config(|config| {
+        apply_tool_permissions(config, &permissions);
+        Ok(config.clone())
+    })?;
+    reload_gateway_config().await?;
+    Ok(read_tool_permissions(&config))
+}
+
+// Scheduled tasks
+
+const CRON_MACROS: &[&str] = &[
+    "@hourly",
+    "@daily",
+    "@midnight",
+    "@weekly",
+    "@monthly",
+    "@yearly",
+    "@annually",
+];
+const CRON_MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const CRON_DAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct ScheduledTask {
+    id: String,
+    name: String,
+    schedule: String,
+    prompt: String,
+    channel: Option<String>,
+    enabled: bool,
+    next_run_at_ms: Option<u64>,
+    last_run_at_ms: Option<u64>,
+    /// Outcome of the last run, e.g. `ok` or `error`.
+    last_status: Option<String>,
+    last_error: Option<String>,
+}
+
+fn parse_cron_value(value: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    let lower = value.to_lowercase();
+    let parsed = match names.iter().position(|n| *n == lower) {
+        // Month names are 1-based, day names 0-based — `min` tells them apart.
+        Some(index) => index as u32 + min,
+        None => value
+            .parse::<u32>()
+            .map_err(|_| format!("'{}' is not a number", value))?,
+    };
+    if parsed < min || parsed > max {
+        return Err(format!("{} is outside {}-{}", parsed, min, max));
+    }
+    Ok(parsed)
+}
+
+fn validate_cron_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<(), String> {
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (part, None),
+        };
+        if let Some(step) = step {
+            match step.parse::<u32>() {
+                Ok(s) if s > 0 && s <= max => {}
+                _ => return Err(format!("invalid step '{}'", step)),
+            }
+        }
+        if range == "*" {
+            continue;
+        }
+        match range.split_once('-') {
+            Some((start, end)) => {
+                let start = parse_cron_value(start, min, max, names)?;
+                let end = parse_cron_value(end, min, max, names)?;
+                if start > end {
+                    return Err(format!("range {} is reversed", range));
+                }
+            }
+            None => {
+                parse_cron_value(range, min, max, names)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Validates a standard 5-field cron expression (or an @macro) before it reaches the CLI.
+fn validate_cron_expression(expr: &str) -> Result<(), String> {
+    let expr = expr.trim();
+    if expr.starts_with('@') {
+        return if CRON_MACROS.contains(&expr.to_lowercase().as_str()) {
+            Ok(())
+        } else {
+            Err(format!("Unknown cron macro '{}'", expr))
+        };
+    }
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(format!(
+            "Cron expression must have 5 fields (minute hour day month weekday), got {}",
+            fields.len()
+        ));
+    }
+    let specs: [(&str, u32, u32, &[&str]); 5] = [
+        ("minute", 0, 59, &[]),
+        ("hour", 0, 23, &[]),
+        ("day of month", 1, 31, &[]),
+        ("month", 1, 12, CRON_MONTH_NAMES),
+        ("day of week", 0, 7, CRON_DAY_NAMES),
+    ];
+    for (field, (label, min, max, names)) in fields.iter().zip(specs.iter()) {
+        validate_cron_field(field, *min, *max, names)
+            .map_err(|e| format!("Invalid {} field '{}': {}", label, field, e))?;
+    }
+    Ok(())
+}
+
+fn is_valid_task_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn parse_scheduled_tasks(output: &str) -> Result<Vec<ScheduledTask>, String> {
+    let parsed: serde_json::Value = serde_json::from_str(output.trim())
+        .map_err(|e| format!("Failed to parse scheduled tasks: {}", e))?;
+    let jobs = parsed
+        .get("jobs")
+        .or_else(|| parsed.get("tasks"))
+        .unwrap_or(&parsed);
+    let text =
+        |value: Option<&serde_json::Value>| value.and_then(|v| v.as_str()).map(|s| s.to_string());
+
+    Ok(jobs
+        .as_array()
+        .map(|jobs| {
+            jobs.iter()
+                .filter_map(|job| {
+                    let id = text(job.get("id"))?;
+                    let schedule = job.get("schedule");
+                    let payload = job.get("payload");
+                    let state = job.get("state");
+                    Some(ScheduledTask {
+                        name: text(job.get("name")).unwrap_or_else(|| id.clone()),
+                        schedule: text(schedule.and_then(|s| s.get("expr")))
+                            .or_else(|| text(schedule))
+                            .unwrap_or_default(),
+                        prompt: text(payload.and_then(|p| p.get("message")))
+                            .or_else(|| text(payload.and_then(|p| p.get("text"))))
+                            .unwrap_or_default(),
+                        channel: text(payload.and_then(|p| p.get("channel")))
+                            .or_else(|| text(job.get("channel"))),
+                        enabled: job.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true),
+                        next_run_at_ms: state
+                            .and_then(|s| s.get("nextRunAtMs"))
+                            .and_then(|v| v.as_u64()),
+                        last_run_at_ms: state
+                            .and_then(|s| s.get("lastRunAtMs"))
+                            .and_then(|v| v.as_u64()),
+                        last_status: text(state.and_then(|s| s.get("lastStatus"))),
+                        last_error: text(state.and_then(|s| s.get("lastError"))),
+                        id,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+#[command]
+fn list_scheduled_tasks() -> Result<Vec<ScheduledTask>, String> {
+    let output = shell_command("openclaw cron list --all --json")
+        .or_else(|_| shell_command("openclaw cron list --json"))?;
+    parse_scheduled_tasks(&output)
+}
+
+#[command]
+fn create_scheduled_task(
+    schedule: String,
+    prompt: String,
+    channel: Option<String>,
+    name: Option<String>,
+) -> Result<Vec<ScheduledTask>, String> {
+    validate_cron_expression(&schedule)?;
+    if prompt.trim().is_empty() {
+        return Err("Task prompt cannot be empty.".to_string());
+    }
+    let name = name
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| prompt.chars().take(40).collect());
+
+    let mut args = vec![
+        "cron",
+        "add",
+        "--name",
+        &name,
+        "--cron",
+        schedule.trim(),
+        "--message",
+        &prompt,
+        "--session",
+        "isolated",
+    ];
+    let channel = channel.filter(|c| !c.trim().is_empty());
+    if let Some(channel) = channel.as_deref() {
+        args.extend(["--deliver", "--channel", channel]);
+    }
+    process::run("openclaw", &args)
+        .map_err(|e| format!("Failed to create scheduled task: {}", e))?;
+    list_scheduled_tasks()
+}
+
+#[command]
+fn delete_scheduled_task(id: String) -> Result<Vec<ScheduledTask>, String> {
+    if !is_valid_task_id(&id) {
+        return Err(format!("Invalid task id: {}", id));
+    }
+    process::run("openclaw", &["cron", "rm", &id])
+        .map_err(|e| format!("Failed to delete scheduled task: {}", e))?;
+    list_scheduled_tasks()
+}
+
+#[command]
+fn run_task_now(id: String) -> Result<String, String> {
+    if !is_valid_task_id(&id) {
+        return Err(format!("Invalid task id: {}", id));
+    }
+    process::run("openclaw", &["cron", "run", &id, "--force"])
+        .map_err(|e| format!("Failed to run scheduled task: {}", e))
+}
+
+// Workspace templates
+
+const TEMPLATE_INDEX_TIMEOUT_SECS: u64 = 15;
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct TemplateCatalog {
+    templates: Vec<templates::WorkspaceTemplate>,
+    /// Why the remote index could not be loaded; the bundled templates are still listed.
+    remote_error: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Default)]
+struct TemplateInstallResult {
+    /// False when the template was not applied because of `conflicts`.
+    installed: bool,
+    /// Files that exist with different content and were not listed in `overwrite`.
+    conflicts: Vec<String>,
+    written: Vec<String>,
+    skills: Vec<String>,
+    schedules: Vec<String>,
+    /// Skills and schedules that could not be added; the rest of the template still applies.
+    warnings: Vec<String>,
+}
+
+fn fetch_template_index(url: &str) -> Result<Vec<templates::WorkspaceTemplate>, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(TEMPLATE_INDEX_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let raw = client
+        .get(url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .map_err(|e| format!("Failed to fetch template index: {}", e))?;
+    templates::parse_index(&raw, "remote")
+}
+
+fn workspace_template_catalog(app: &tauri::AppHandle) -> Result<TemplateCatalog, String> {
+    let url = settings::load(&app_data_dir(app)?).workspace_templates_url;
+    let (remote, remote_error) = match url.map(|url| fetch_template_index(&url)) {
+        Some(Ok(remote)) => (remote, None),
+        Some(Err(e)) => (Vec::new(), Some(e)),
+        None => (Vec::new(), None),
+    };
+    Ok(TemplateCatalog {
+        templates: templates::merge(templates::bundled(), remote),
+        remote_error,
+    })
+}
+
+/// Lists the bundled workspace templates plus those from the index in the app settings.
+#[command]
+async fn browse_workspace_templates(app: tauri::AppHandle) -> Result<TemplateCatalog, String> {
+    tauri::async_runtime::spawn_blocking(move || workspace_template_catalog(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn write_template_file(workspace: &str, path: &str, content: &str) -> Result<(), String> {
+    let target = format!("{}/{}", workspace, path);
+    if let Some((dir, _)) = target.rsplit_once('/') {
+        #[cfg(target_os = "windows")]
+        wsl_mkdir_p(dir)?;
+
+        #[cfg(not(target_os = "windows"))]
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir, e))?;
+    }
+    write_local_text_file(&target, content)
+}
+
+fn apply_workspace_template(
+    template: &templates::WorkspaceTemplate,
+    agent_id: Option<&str>,
+    overwrite: Option<&[String]>,
+) -> Result<TemplateInstallResult, String> {
+    let workspace = workspace_dir(agent_id)?;
+    let conflicts = templates::conflicts(template, |path| {
+        read_local_text_file(&format!("{}/{}", workspace, path))
+    });
+    let overwrite = match overwrite {
+        Some(overwrite) => overwrite,
+        None if !conflicts.is_empty() => {
+            return Ok(TemplateInstallResult {
+                conflicts,
+                ..Default::default()
+            })
+        }
+        None => &[],
+    };
+
+    let mut result = TemplateInstallResult {
+        installed: true,
+        ..Default::default()
+    };
+    for (path, content) in &template.files {
+        if conflicts.contains(path) && !overwrite.contains(path) {
+            result.conflicts.push(path.clone());
+            continue;
+        }
+        write_template_file(&workspace, path, content)?;
+        result.written.push(path.clone());
+    }
+    commit_workspace_changes(agent_id, &format!("Apply template {}", template.name));
+
+    for skill in &template.skills {
+        match process::run(
+            "npx",
+            &["clawhub", "install", skill, "--workdir", &workspace],
+        ) {
+            Ok(_) => result.skills.push(skill.clone()),
+            Err(e) => result
+                .warnings
+                .push(format!("Skill {} was not installed: {}", skill, e)),
+        }
+    }
+
+    let existing: Vec<String> = list_scheduled_tasks()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|task| task.name)
+        .collect();
+    for schedule in &template.schedules {
+        if existing.contains(&schedule.name) {
+            result.warnings.push(format!(
+                "A scheduled task named '{}' already exists; it was left as is.",
+                schedule.name
+            ));
+            continue;
+        }
+        let added = validate_cron_expression(&schedule.cron).and_then(|_| {
+            let mut args = vec![
+                "cron",
+                "add",
+                "--name",
+                &schedule.name,
+                "--cron",
+                schedule.cron.trim(),
+                "--message",
+                &schedule.prompt,
+                "--session",
+                "isolated",
+            ];
+            if let Some(agent_id) = agent_id {
+                args.extend(["--agent", agent_id]);
+            }
+            process::run("openclaw", &args).map_err(String::from)
+        });
+        match added {
+            Ok(_) => result.schedules.push(schedule.name.clone()),
+            Err(e) => result.warnings.push(format!(
+                "Scheduled task '{}' was not created: {}",
+                schedule.name, e
+            )),
+        }
+    }
+    Ok(result)
+}
+
+/// Applies template `id` to the workspace of `agent_id` (the main workspace when `None`).
+/// Without `overwrite`, nothing is written if any template file would replace a different
+/// existing file; those paths come back as `conflicts` for the user to decide on. With it,
+/// the listed paths are overwritten and other conflicting files are kept.
+#[command]
+async fn install_workspace_template(
+    app: tauri::AppHandle,
+    id: String,
+    agent_id: Option<String>,
+    overwrite: Option<Vec<String>>,
+) -> Result<TemplateInstallResult, String> {
+    if let Some(agent_id) = agent_id.as_deref() {
+        if !is_valid_session_id(agent_id) {
+            return Err(format!("Invalid agent id: {}", agent_id));
+        }
+    }
+    let _operation = lifecycle::begin_operation("install_workspace_template").await;
+    tauri::async_runtime::spawn_blocking(move || {
+        let catalog = workspace_template_catalog(&app)?;
+        let template = catalog
+            .templates
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| format!("Unknown workspace template '{}'", id))?;
+        apply_workspace_template(template, agent_id.as_deref(), overwrite.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Daily digest
+
+#[derive(serde::Serialize, Clone, Debug, Default)]
+struct DigestStatus {
+    /// `None` while no digest is configured.
+    config: Option<digest::DigestConfig>,
+    /// The cron job with its next/last run; `None` if it was removed outside the app.
+    task: Option<ScheduledTask>,
+}
+
+fn daily_digest_status(app_dir: &Path) -> DigestStatus {
+    let config = digest::load(app_dir);
+    let task = config
+        .as_ref()
+        .and_then(|c| c.job_id.clone())
+        .and_then(|id| {
+            list_scheduled_tasks()
+                .ok()?
+                .into_iter()
+                .find(|task| task.id == id)
+        });
+    DigestStatus { config, task }
+}
+
+/// Creates (or replaces) the cron job that has the agent write a daily summary of
+/// `sections` at `time` and deliver it on `channel`, by default to the paired chat.
+#[command]
+fn configure_daily_digest(
+    app: tauri::AppHandle,
+    time: String,
+    channel: String,
+    sections: Vec<String>,
+    to: Option<String>,
+    timezone: Option<String>,
+) -> Result<DigestStatus, String> {
+    if !is_valid_session_id(&channel) {
+        return Err(format!("Invalid channel: {}", channel));
+    }
+    let mut config = digest::DigestConfig {
+        time: time.trim().to_string(),
+        channel,
+        to: to.map(|t| t.trim().to_string()).filter(|t| !t.is_empty()),
+        sections,
+        timezone: timezone
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty()),
+        job_id: None,
+    };
+    digest::validate(&config)?;
+    let schedule = digest::cron_expression(&config.time)?;
+    let to = match &config.to {
+        Some(to) => to.clone(),
+        None => {
+            let credentials_dir = PathBuf::from(paths::local_path("credentials")?);
+            paired_chats(&credentials_dir, &config.channel)
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("No chat is paired on {} yet.", config.channel))?
+        }
+    };
+
+    let dir = app_data_dir(&app)?;
+    if let Some(old) = digest::load(&dir).and_then(|c| c.job_id) {
+        if is_valid_task_id(&old) {
+            let _ = process::run("openclaw", &["cron", "rm", &old]);
+        }
+    }
+    let prompt = digest::prompt(&config.sections);
+    let mut args = vec![
+        "cron",
+        "add",
+        "--name",
+        digest::JOB_NAME,
+        "--cron",
+        &schedule,
+        "--message",
+        &prompt,
+        "--session",
+        "isolated",
+        "--deliver",
+        "--channel",
+        &config.channel,
+        "--to",
+        &to,
+    ];
+    if let Some(tz) = config.timezone.as_deref() {
+        args.extend(["--tz", tz]);
+    }
+    process::run("openclaw", &args)
+        .map_err(|e| format!("Failed to schedule the daily digest: {}", e))?;
+
+    config.job_id = list_scheduled_tasks()?
+        .into_iter()
+        .rev()
+        .find(|task| task.name == digest::JOB_NAME)
+        .map(|task| task.id);
+    digest::save(&dir, &config)?;
+    Ok(daily_digest_status(&dir))
+}
+
+/// The digest settings and the last/next run of its job.
+#[command]
+fn get_daily_digest(app: tauri::AppHandle) -> Result<DigestStatus, String> {
+    Ok(daily_digest_status(&app_data_dir(&app)?))
+}
+
+#[command]
+fn disable_daily_digest(app: tauri::AppHandle) -> Result<(), String> {
+    let dir = app_data_dir(&app)?;
+    if let Some(id) = digest::load(&dir).and_then(|c| c.job_id) {
+        if is_valid_task_id(&id) {
+            process::run("openclaw", &["cron", "rm", &id])
+                .map_err(|e| format!("Failed to remove the daily digest: {}", e))?;
+        }
+    }
+    digest::remove(&dir)
+}
+
+// Full setup orchestrator
+
+const SETUP_STEPS: &[&str] = &[
+    "prerequisites",
+    "install",
+    "configure",
+    "gateway",
+    "pairing",
+];
+
+/// Typical duration of each setup step in seconds, for progress estimates; Node.js and
+/// OpenClaw installs dominate.
+const SETUP_STEP_EXPECTED_SECS: &[u64] = &[120, 180, 5, 60, 10];
+const _: () = assert!(SETUP_STEP_EXPECTED_SECS.len() == SETUP_STEPS.len());
+
+#[derive(serde::Serialize, Clone, Debug, Default)]
+struct SetupStepState {
+    name: String,
+    status: String,
+    message: Option<String>,
+    /// Catalog code and parameters of `message`, for showing it in the user's language.
+    message_code: Option<messages::Message>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Default)]
+struct SetupRunState {
+    running: bool,
+    /// Key of this run's `task-progress` events; kept when resuming.
+    task_id: String,
+    steps: Vec<SetupStepState>,
+    failed_step: Option<String>,
+    #[serde(skip)]
+    completed: Vec<String>,
+    /// openclaw.json before `configure` ran; `Some(None)` means there was no config yet.
+    #[serde(skip)]
+    config_snapshot: Option<Option<String>>,
+    #[serde(skip)]
+    openclaw_preinstalled: bool,
+}
+
+/// Steps to execute, starting at `resume_from` when retrying a failed step.
+fn setup_steps_to_run(resume_from: Option<&str>) -> Result<Vec<&'static str>, String> {
+    match resume_from {
+        None => Ok(SETUP_STEPS.to_vec()),
+        Some(step) => SETUP_STEPS
+            .iter()
+            .position(|s| *s == step)
+            .map(|index| SETUP_STEPS[index..].to_vec())
+            .ok_or_else(|| messages::text("setup.unknown_step", &[("step", step)])),
+    }
+}
+
+/// Reporter for `step`'s share of the setup run's progress.
+fn setup_step_progress(app: &tauri::AppHandle, step: &str) -> progress::Progress {
+    let task_id = SETUP_RUN.lock().map(|run| run.task_id.clone()).ok();
+    let index = SETUP_STEPS.iter().position(|s| *s == step).unwrap_or(0);
+    let total = SETUP_STEPS.len();
+    progress::Progress::new(app, "setup", task_id)
+        .child(
+            step,
+            progress::fraction(0, 100, index, total),
+            progress::fraction(0, 100, index + 1, total),
+        )
+        .at_step(index, total)
+        .expecting(Duration::from_secs(SETUP_STEP_EXPECTED_SECS[index]))
+}
+
+fn set_setup_step(app: &tauri::AppHandle, step: &str, status: &str, message: Option<String>) {
+    if let Ok(mut run) = SETUP_RUN.lock() {
+        if let Some(state) = run.steps.iter_mut().find(|s| s.name == step) {
+            state.status = status.to_string();
+            state.message_code = message.as_deref().and_then(messages::identify);
+            state.message = message.clone();
+        }
+    }
+    let progress = setup_step_progress(app, step);
+    let message = message.unwrap_or_else(|| status.replace('_', " "));
+    match status {
+        "failed" => progress.error(step, 0, &message),
+        "rolled_back" => progress.warn(step, 0, &message),
+        "done" => progress.info(step, 100, &message),
+        _ => progress.info(step, 0, &message),
+    }
+}
+
+/// Runs `step` off the main thread: the Node.js install is awaited, the other steps run on
+/// the blocking pool.
+async fn run_setup_step(
+    app: &tauri::AppHandle,
+    step: &'static str,
+    config: &AgentConfig,
+) -> Result<String, String> {
+    if step != "prerequisites" {
+        let (app, config) = (app.clone(), config.clone());
+        return tauri::async_runtime::spawn_blocking(move || {
+            run_blocking_setup_step(&app, step, &config)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    let prereqs = tauri::async_runtime::spawn_blocking(check_prerequisites)
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Ok(mut run) = SETUP_RUN.lock() {
+        run.openclaw_preinstalled = prereqs.openclaw_installed;
+    }
+    if prereqs.node_installed {
+        Ok(messages::text("setup.node_installed", &[]))
+    } else {
+        install_local_nodejs().await
+    }
+}
+
+fn run_blocking_setup_step(
+    app: &tauri::AppHandle,
+    step: &str,
+    config: &AgentConfig,
+) -> Result<String, String> {
+    let progress = setup_step_progress(app, step);
+    match step {
+        "install" => {
+            if shell_command("openclaw --version").is_ok() {
+                Ok(messages::text("setup.openclaw_installed", &[]))
+            } else {
+                let _operation = lifecycle::try_begin_operation("install_openclaw")?;
+                install_openclaw_with(
+                    read_install_settings(app).unwrap_or_default(),
+                    None,
+                    &progress,
+                )
+            }
+        }
+        "configure" => {
+            let snapshot = read_local_openclaw_file("openclaw.json").ok();
+            if let Ok(mut run) = SETUP_RUN.lock() {
+                if run.config_snapshot.is_none() {
+                    run.config_snapshot = Some(snapshot);
+                }
+            }
+            configure_agent(config.clone())
+        }
+        "gateway" => {
+            let _operation = lifecycle::try_begin_operation("start_gateway")?;
+            let message = start_local_gateway(&progress)?;
+            notify_gateway_restarted(app);
+            Ok(message)
+        }
+        "pairing" => {
+            if config.telegram_token.as_deref().unwrap_or("").is_empty() {
+                Ok(messages::text("setup.no_channel_to_pair", &[]))
+            } else {
+                generate_pairing_code()
+            }
+        }
+        other => Err(messages::text("setup.unknown_step", &[("step", other)])),
+    }
+}
+
+/// Undoes completed steps in reverse order. Node.js is left installed.
+fn rollback_setup(app: &tauri::AppHandle) -> Vec<String> {
+    let (completed, snapshot, preinstalled) = match SETUP_RUN.lock() {
+        Ok(mut run) => (
+            std::mem::take(&mut run.completed),
+            run.config_snapshot.take(),
+            run.openclaw_preinstalled,
+        ),
+        Err(_) => return vec!["Setup state is poisoned; nothing was rolled back.".to_string()],
+    };
+
+    let mut problems = Vec::new();
+    for step in completed.iter().rev() {
+        let result = match step.as_str() {
+            "gateway" => shell_command("openclaw gateway stop").map(|_| ()),
+            "configure" => match &snapshot {
+                Some(Some(previous)) => write_local_openclaw_file("openclaw.json", previous),
+                Some(None) => {
+                    paths::local_path("openclaw.json").and_then(|path| remove_local_file(&path))
+                }
+                None => Ok(()),
+            },
+            "install" if !preinstalled => shell_command("npm uninstall -g openclaw").map(|_| {
+                process::probe_environment();
+            }),
+            _ => continue,
+        };
+        match result {
+            Ok(_) => set_setup_step(app, step, "rolled_back", None),
+            Err(e) => {
+                set_setup_step(app, step, "failed", Some(e.clone()));
+                problems.push(format!("{}: {}", step, e));
+            }
+        }
+    }
+    problems
+}
+
+/// Runs prerequisites → install → configure → gateway → pairing, emitting `task-progress`
+/// events under `task_id` (generated when omitted). After a failure, call again with
+/// `resume_from` set to the failed step to retry.
+#[command]
+async fn run_full_setup(
+    app: tauri::AppHandle,
+    config: AgentConfig,
+    resume_from: Option<String>,
+    task_id: Option<String>,
+) -> Result<SetupRunState, String> {
+    let steps = setup_steps_to_run(resume_from.as_deref())?;
+    {
+        let mut run = SETUP_RUN
+            .lock()
+            .map_err(|_| "Setup state is poisoned".to_string())?;
+        if run.running {
+            return Err("Setup is already running.".to_string());
+        }
+        if resume_from.is_none() {
+            *run = SetupRunState::default();
+            run.steps = SETUP_STEPS
+                .iter()
+                .map(|name| SetupStepState {
+                    name: name.to_string(),
+                    status: "pending".to_string(),
+                    message: None,
+                    message_code: None,
+                })
+                .collect();
+        }
+        if let Some(task_id) = task_id.filter(|id| !id.trim().is_empty()) {
+            run.task_id = task_id;
+        } else if run.task_id.is_empty() {
+            run.task_id = uuid::Uuid::new_v4().to_string();
+        }
+        run.running = true;
+        run.failed_step = None;
+    }
+    SETUP_ABORT_REQUESTED.store(false, Ordering::Relaxed);
+    let mut attempt = SetupAttempt {
+        started_at: unix_now_secs(),
+        resumed_from: resume_from.clone(),
+        ..Default::default()
+    };
+
+    for step in steps {
+        if SETUP_ABORT_REQUESTED.swap(false, Ordering::Relaxed) {
+            let rollback_app = app.clone();
+            let problems =
+                tauri::async_runtime::spawn_blocking(move || rollback_setup(&rollback_app))
+                    .await
+                    .unwrap_or_else(|e| vec![e.to_string()]);
+            if let Ok(mut run) = SETUP_RUN.lock() {
+                run.running = false;
+            }
+            attempt.outcome = "aborted".to_string();
+            record_setup_attempt(&app, attempt);
+            return Err(if problems.is_empty() {
+                "Setup was aborted and changes were rolled back.".to_string()
+            } else {
+                format!(
+                    "Setup was aborted; rollback had problems: {}",
+                    problems.join("; ")
+                )
+            });
+        }
+
+        set_setup_step(&app, step, "running", None);
+        let started = Instant::now();
+        let result = run_setup_step(&app, step, &config).await;
+        attempt.steps.push(SetupAttemptStep {
+            name: step.to_string(),
+            succeeded: result.is_ok(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            error: result.as_ref().err().cloned(),
+        });
+        match result {
+            Ok(message) => {
+                set_setup_step(&app, step, "done", Some(message));
+                if let Ok(mut run) = SETUP_RUN.lock() {
+                    run.completed.retain(|s| s != step);
+                    run.completed.push(step.to_string());
+                }
+            }
+            Err(e) => {
+                set_setup_step(&app, step, "failed", Some(e.clone()));
+                if let Ok(mut run) = SETUP_RUN.lock() {
+                    run.running = false;
+                    run.failed_step = Some(step.to_string());
+                }
+                attempt.outcome = "failed".to_string();
+                attempt.failure_cause = Some(setup_failure_cause(&e).to_string());
+                record_setup_attempt(&app, attempt);
+                return Err(format!("Setup step '{}' failed: {}", step, e));
+            }
+        }
+    }
+
+    attempt.outcome = "succeeded".to_string();
+    record_setup_attempt(&app, attempt);
+    let mut run = SETUP_RUN
+        .lock()
+        .map_err(|_| "Setup state is poisoned".to_string())?;
+    run.running = false;
+    Ok(run.clone())
+}
+
+/// Aborts the running setup at the next step boundary, or rolls back a failed one now.
+#[command]
+fn abort_full_setup(app: tauri::AppHandle) -> Result<String, String> {
+    let running = SETUP_RUN.lock().map(|run| run.running).unwrap_or(false);
+    if running {
+        SETUP_ABORT_REQUESTED.store(true, Ordering::Relaxed);
+        return Ok("Setup will stop and roll back after the current step.".to_string());
+    }
+    let problems = rollback_setup(&app);
+    if problems.is_empty() {
+        Ok("Partial setup changes were rolled back.".to_string())
+    } else {
+        Err(format!("Rollback had problems: {}", problems.join("; ")))
+    }
+}
+
+#[command]
+fn get_setup_state() -> Result<SetupRunState, String> {
+    SETUP_RUN
+        .lock()
+        .map(|run| run.clone())
+        .map_err(|_| "Setup state is poisoned".to_string())
+}
+
+// Transactional gateway install
+
+const GATEWAY_INSTALL_SNAPSHOT_FILE: &str = "gateway-install-snapshot.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+struct GatewayInstallSnapshot {
+    service_path: String,
+    service: Option<String>,
+    config: Option<String>,
+}
+
+fn local_gateway_service_path() -> Result<String, String> {
+    #[cfg(target_os = "macos")]
+    {
+        let home = dirs::home_dir().ok_or("Could not find home directory")?;
+        Ok(home
+            .join("Library/LaunchAgents/ai.openclaw.gateway.plist")
+            .to_string_lossy()
+            .to_string())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        Ok(format!(
+            "{}/.config/systemd/user/openclaw-gateway.service",
+            wsl_home_dir()?
+        ))
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let home = dirs::home_dir().ok_or("Could not find home directory")?;
+        Ok(home
+            .join(".config/systemd/user/openclaw-gateway.service")
+            .to_string_lossy()
+            .to_string())
+    }
+}
+
+fn read_local_text_file(path: &str) -> Option<String> {
+    #[cfg(target_os = "windows")]
+    let content = wsl_read_file(path).ok();
+
+    #[cfg(not(target_os = "windows"))]
+    let content = fs::read_to_string(path).ok();
+
+    content.filter(|c| !c.trim().is_empty())
+}
+
+fn write_local_text_file(path: &str, content: &str) -> Result<(), String> {
+    let _write = lifecycle::write_guard();
+    #[cfg(target_os = "windows")]
+    {
+        wsl_write_file(path, content)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+}
+
+/// `write_local_text_file` for files holding secrets: readable by the owner only, with the
+/// permissions in place before any content is written.
+fn write_local_private_file(path: &str, content: &str) -> Result<(), String> {
+    let _write = lifecycle::write_guard();
+    #[cfg(target_os = "windows")]
+    {
+        let quoted = shell_single_quote(path);
+        shell_command(&format!(
+            "umask 077 && touch {0} && chmod 600 {0} && printf '%s' {1} > {0}",
+            quoted,
+            shell_single_quote(content)
+        ))
+        .map(|_| ())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        // `mode` only applies to new files.
+        file.set_permissions(fs::Permissions::from_mode(0o600))
+            .and_then(|_| file.write_all(content.as_bytes()))
+            .map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+}
+
+/// Removes `path` on the machine running openclaw (WSL on Windows); a missing file is fine.
+fn remove_local_file(path: &str) -> Result<(), String> {
+    let _write = lifecycle::write_guard();
+    #[cfg(target_os = "windows")]
+    {
+        process::run("rm", &["-f", "--", path]).map(|_| ())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove {}: {}", path, e))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Moves `from` to `to` on the machine running openclaw (WSL on Windows), creating the
+/// destination directory if needed.
+fn rename_local_file(from: &str, to: &str) -> Result<(), String> {
+    let _write = lifecycle::write_guard();
+    let parent = Path::new(to)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    #[cfg(target_os = "windows")]
+    {
+        if !parent.is_empty() {
+            process::run("mkdir", &["-p", "--", &parent])?;
+        }
+        process::run("mv", &["--", from, to]).map(|_| ())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        if !parent.is_empty() {
+            fs::create_dir_all(&parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent, e))?;
+        }
+        fs::rename(from, to).map_err(|e| format!("Failed to move {}: {}", from, e))
+    }
+}
+
+/// Entry names in directory `path` on the machine running openclaw; empty when it is missing.
+fn list_local_dir(path: &str) -> Result<Vec<String>, String> {
+    #[cfg(target_os = "windows")]
+    {
+        let output = process::output("ls", &["-1A", "--", path])?;
+        Ok(if output.success() {
+            output.stdout.lines().map(str::to_string).collect()
+        } else {
+            Vec::new()
+        })
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    match fs::read_dir(path) {
+        Ok(entries) => Ok(entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to list {}: {}", path, e)),
+    }
+}
+
+fn config_gateway_token(config: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(config)
+        .ok()?
+        .get("gateway")?
+        .get("auth")?
+        .get("token")?
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+/// Post-install sanity check: the service exists, the config still parses and the
+/// gateway token (tied to paired devices) survived `install --force`.
+fn gateway_install_problem(
+    snapshot: &GatewayInstallSnapshot,
+    service_after: Option<&str>,
+    config_after: Option<&str>,
+) -> Option<String> {
+    if service_after.is_none() {
+        return Some("Gateway service definition is missing after install.".to_string());
+    }
+    let Some(config_after) = config_after else {
+        return snapshot
+            .config
+            .as_ref()
+            .map(|_| "openclaw.json was removed by the install.".to_string());
+    };
+    if serde_json::from_str::<serde_json::Value>(config_after).is_err() {
+        return Some("openclaw.json is no longer valid JSON after install.".to_string());
+    }
+    let token_before = snapshot.config.as_deref().and_then(config_gateway_token);
+    if token_before.is_some() && token_before != config_gateway_token(config_after) {
+        return Some("Gateway install replaced the existing gateway token.".to_string());
+    }
+    None
+}
+
+fn snapshot_gateway_install() -> Result<GatewayInstallSnapshot, String> {
+    let service_path = local_gateway_service_path()?;
+    let snapshot = GatewayInstallSnapshot {
+        service: read_local_text_file(&service_path),
+        config: read_local_openclaw_file("openclaw.json").ok(),
+        service_path,
+    };
+    let raw = serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string())?;
+    // The config copy holds the gateway token and provider keys.
+    write_local_private_file(&paths::local_path(GATEWAY_INSTALL_SNAPSHOT_FILE)?, &raw)?;
+    Ok(snapshot)
+}
+
+fn reload_gateway_service_definition(service_path: &str) {
+    #[cfg(target_os = "macos")]
+    {
+        let _ =
+            shell_command("launchctl bootout gui/$(id -u)/ai.openclaw.gateway 2>/dev/null || true");
+        let _ = shell_command(&format!(
+            "launchctl bootstrap gui/$(id -u) {} 2>/dev/null || true",
+            shell_single_quote(service_path)
+        ));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = service_path;
+        let _ = shell_command("systemctl --user daemon-reload 2>/dev/null || true");
+    }
+}
+
+fn restore_gateway_install_snapshot(snapshot: &GatewayInstallSnapshot) -> Result<(), String> {
+    let _ = shell_command("openclaw gateway stop");
+    match &snapshot.config {
+        Some(config) => write_local_openclaw_file("openclaw.json", config)?,
+        None => remove_local_file(&paths::local_path("openclaw.json")?)?,
+    }
+    match &snapshot.service {
+        Some(service) => write_local_text_file(&snapshot.service_path, service)?,
+        None => remove_local_file(&snapshot.service_path)?,
+    }
+    reload_gateway_service_definition(&snapshot.service_path);
+    Ok(())
+}
+
+/// Starts the freshly installed gateway and waits for it to listen on `port`.
+fn probe_installed_gateway(port: u16) -> Result<(), String> {
+    run_gateway_cli("openclaw gateway start", port).map_err(|failure| failure.to_string())?;
+    retry::retry(
+        &retry::GATEWAY_STARTUP,
+        |_| probe_local_gateway(port),
+        |_| true,
+        |_, _| {},
+    )
+    .map_err(|e| format!("Gateway did not come up after install: {}", e))
+}
+
+/// Runs `openclaw gateway install --force`, restoring the previous service definition and
+/// config if the install fails, leaves them broken, or (over an existing config) the gateway
+/// no longer comes up. A fresh install has no working gateway to compare against, so it is
+/// not started here. The snapshot is deleted once the install succeeds.
+fn install_gateway_with_rollback(profile: &str) -> Result<String, String> {
+    let snapshot = snapshot_gateway_install()?;
+    let port = read_local_gateway_port();
+    let result = match run_gateway_cli(
+        &format!(
+            "openclaw gateway install --force --profile {}",
+            shell_single_quote(profile)
+        ),
+        port,
+    ) {
+        // System-level service managers need root; ask once instead of failing. Inside WSL
+        // the service is per-user, so elevating there would install it for root instead.
+        Err(GatewayFailure::PermissionDenied { .. }) if cfg!(not(target_os = "windows")) => {
+            let args: Vec<String> = ["gateway", "install", "--force", "--profile", profile]
+                .iter()
+                .map(|a| a.to_string())
+                .collect();
+            elevation::run_elevated("openclaw", &args).map_err(String::from)
+        }
+        other => other.map_err(|failure| failure.to_string()),
+    }
+    .and_then(|output| {
+        let service_after = read_local_text_file(&snapshot.service_path);
+        let config_after = read_local_openclaw_file("openclaw.json").ok();
+        match gateway_install_problem(&snapshot, service_after.as_deref(), config_after.as_deref())
+        {
+            Some(problem) => Err(problem),
+            None => Ok(output),
+        }
+    })
+    .and_then(|output| {
+        // `install --force` rewrites the service without the managed variables.
+        let _ = sync_gateway_env_to_service(&Default::default());
+        if snapshot.config.is_some() {
+            probe_installed_gateway(port)?;
+        }
+        Ok(output)
+    });
+
+    match result {
+        Ok(output) => {
+            let _ = remove_local_file(&paths::local_path(GATEWAY_INSTALL_SNAPSHOT_FILE)?);
+            Ok(output)
+        }
+        Err(e) => match restore_gateway_install_snapshot(&snapshot) {
+            Ok(_) => Err(format!("Gateway install failed and was rolled back: {}", e)),
+            Err(restore_err) => Err(format!(
+                "Gateway install failed ({}) and rollback also failed: {}",
+                e, restore_err
+            )),
+        },
+    }
+}
+
+#[command]
+fn rollback_gateway_install() -> Result<String, String> {
+    let raw = read_local_openclaw_file(GATEWAY_INSTALL_SNAPSHOT_FILE)
+        .map_err(|_| "No gateway install snapshot to roll back to.".to_string())?;
+    let snapshot: GatewayInstallSnapshot = serde_json::from_str(&raw)
+        .map_err(|e| format!("Gateway install snapshot is invalid: {}", e))?;
+    restore_gateway_install_snapshot(&snapshot)?;
+    Ok("Restored the gateway service and config from before the last install.".to_string())
+}
+
+// Gateway service file
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct ServicePathCheck {
+    path: String,
+    exists: bool,
+    /// Detected binary to use instead, when `path` is a missing node/openclaw binary.
+    replacement: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct GatewayServiceReport {
+    path: String,
+    /// `launchd` or `systemd` (also used inside WSL).
+    kind: String,
+    content: Option<String>,
+    program_arguments: Vec<String>,
+    working_directory: Option<String>,
+    paths: Vec<ServicePathCheck>,
+    problems: Vec<String>,
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// `<string>` values of the plist `<array>` following `<key>{key}</key>`.
+fn plist_string_array(content: &str, key: &str) -> Vec<String> {
+    let marker = format!("<key>{}</key>", key);
+    let Some(start) = content.find(&marker) else {
+        return Vec::new();
+    };
+    let rest = &content[start + marker.len()..];
+    let Some(array) = rest
+        .find("<array>")
+        .zip(rest.find("</array>"))
+        .filter(|(open, close)| open < close)
+        .map(|(open, close)| &rest[open..close])
+    else {
+        return Vec::new();
+    };
+    array
+        .split("<string>")
+        .skip(1)
+        .filter_map(|s| {
+            s.split_once("</string>")
+                .map(|(v, _)| xml_unescape(v.trim()))
+        })
+        .collect()
+}
+
+fn plist_string(content: &str, key: &str) -> Option<String> {
+    let marker = format!("<key>{}</key>", key);
+    let rest = &content[content.find(&marker)? + marker.len()..];
+    let value = rest.trim_start().strip_prefix("<string>")?;
+    Some(xml_unescape(value.split_once("</string>")?.0.trim()))
+}
+
+fn systemd_value<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.trim().split_once('=')?;
+        (name.trim() == key).then_some(value.trim())
+    })
+}
+
+/// Splits an `ExecStart=` command line, honouring double quotes.
+fn split_exec_start(value: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+/// Program arguments and working directory from a launchd plist or systemd unit.
+fn parse_gateway_service(kind: &str, content: &str) -> (Vec<String>, Option<String>) {
+    if kind == "launchd" {
+        (
+            plist_string_array(content, "ProgramArguments"),
+            plist_string(content, "WorkingDirectory"),
+        )
+    } else {
+        (
+            systemd_value(content, "ExecStart")
+                .map(|v| split_exec_start(v.trim_start_matches(['-', '@', '+', '!'])))
+                .unwrap_or_default(),
+            systemd_value(content, "WorkingDirectory").map(|s| s.to_string()),
+        )
+    }
+}
+
+fn service_path_replacement(path: &str, env: &process::EnvironmentInfo) -> Option<String> {
+    let detected = match path.rsplit('/').next() {
+        Some("node") => env.node.as_ref(),
+        Some("openclaw") => env.openclaw.as_ref(),
+        _ => None,
+    }?;
+    (detected != path).then(|| detected.clone())
+}
+
+/// Swaps each missing path that has a replacement for the detected binary.
+fn corrected_service_content(content: &str, paths: &[ServicePathCheck]) -> String {
+    paths
+        .iter()
+        .filter(|check| !check.exists)
+        .filter_map(|check| Some((&check.path, check.replacement.as_ref()?)))
+        .fold(content.to_string(), |content, (from, to)| {
+            content.replace(from.as_str(), to)
+        })
+}
+
+fn local_file_exists(path: &str) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        shell_command(&format!("test -e {}", shell_single_quote(path))).is_ok()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        Path::new(path).exists()
+    }
+}
+
+fn inspect_local_gateway_service() -> Result<GatewayServiceReport, String> {
+    let path = local_gateway_service_path()?;
+    let kind = if path.ends_with(".plist") {
+        "launchd"
+    } else {
+        "systemd"
+    };
+    let content = read_local_text_file(&path);
+    let Some(text) = content.as_deref() else {
+        return Ok(GatewayServiceReport {
+            problems: vec![format!(
+                "No gateway service definition at {}. Run the gateway install.",
+                path
+            )],
+            path,
+            kind: kind.to_string(),
+            content: None,
+            program_arguments: Vec::new(),
+            working_directory: None,
+            paths: Vec::new(),
+        });
+    };
+
+    let (program_arguments, working_directory) = parse_gateway_service(kind, text);
+    let env = process::environment_info();
+    let mut problems = Vec::new();
+    if program_arguments.is_empty() {
+        problems.push("The service does not define a program to run.".to_string());
+    }
+    let paths: Vec<ServicePathCheck> = program_arguments
+        .iter()
+        .chain(working_directory.iter())
+        .filter(|arg| arg.starts_with('/'))
+        .map(|path| {
+            let exists = local_file_exists(path);
+            ServicePathCheck {
+                replacement: if exists {
+                    None
+                } else {
+                    service_path_replacement(path, &env)
+                },
+                path: path.clone(),
+                exists,
+            }
+        })
+        .collect();
+    for check in paths.iter().filter(|c| !c.exists) {
+        problems.push(match &check.replacement {
+            Some(replacement) => format!(
+                "{} does not exist; the detected binary is {}.",
+                check.path, replacement
+            ),
+            None => format!("{} does not exist.", check.path),
+        });
+    }
+
+    Ok(GatewayServiceReport {
+        path,
+        kind: kind.to_string(),
+        content,
+        program_arguments,
+        working_directory,
+        paths,
+        problems,
+    })
+}
+
+#[command]
+fn get_gateway_service() -> Result<GatewayServiceReport, String> {
+    inspect_local_gateway_service()
+}
+
+/// Regenerates the service with `gateway install --force`; if paths are still stale (e.g. a
+/// node version manager moved node), rewrites them to the detected binaries and reloads.
+#[command]
+async fn repair_gateway_service() -> Result<GatewayServiceReport, String> {
+    let _operation = lifecycle::begin_operation("repair_gateway_service").await;
+    process::probe_environment();
+    let _ = shell_command("openclaw gateway stop");
+    install_gateway_with_rollback("messaging")?;
+
+    let report = inspect_local_gateway_service()?;
+    if let Some(content) = report.content.as_deref() {
+        let corrected = corrected_service_content(content, &report.paths);
+        if corrected != content {
+            write_local_text_file(&report.path, &corrected)?;
+            reload_gateway_service_definition(&report.path);
+        }
+    }
+    let _ = shell_command("openclaw gateway start");
+    inspect_local_gateway_service()
+}
+
+// Gateway environment
+
+const GATEWAY_ENV_FILE: &str = "clawnetes-gateway-env.json";
+const GATEWAY_ENV_MASK: &str = "••••";
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct GatewayEnvVar {
+    name: String,
+    /// `GATEWAY_ENV_MASK` for secret-looking names.
+    value: String,
+    secret: bool,
+    /// Whether the variable is managed by Clawnetes (only for the running listing).
+    managed: bool,
+}
+
+fn is_valid_env_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn gateway_env_var(name: &str, value: &str, managed: bool) -> GatewayEnvVar {
+    let secret = is_secret_key(name);
+    GatewayEnvVar {
+        name: name.to_string(),
+        value: if secret {
+            GATEWAY_ENV_MASK.to_string()
+        } else {
+            value.to_string()
+        },
+        secret,
+        managed,
+    }
+}
+
+fn read_gateway_env() -> std::collections::BTreeMap<String, String> {
+    read_local_openclaw_file(GATEWAY_ENV_FILE)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn systemd_env_name(line: &str) -> Option<&str> {
+    let value = line.trim().strip_prefix("Environment=")?;
+    value.trim_start_matches('"').split('=').next()
+}
+
+/// Replaces the `Environment=` lines for `managed` names in the `[Service]` section with
+/// `vars`.
+fn inject_systemd_env(
+    content: &str,
+    vars: &std::collections::BTreeMap<String, String>,
+    managed: &std::collections::BTreeSet<String>,
+) -> String {
+    let mut lines: Vec<String> = content
+        .lines()
+        .filter(|line| !systemd_env_name(line).is_some_and(|name| managed.contains(name)))
+        .map(|line| line.to_string())
+        .collect();
+    let env_lines = vars.iter().map(|(name, value)| {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%");
+        format!("Environment=\"{}={}\"", name, escaped)
+    });
+    match lines.iter().position(|line| line.trim() == "[Service]") {
+        Some(index) => {
+            lines.splice(index + 1..index + 1, env_lines);
+        }
+        None => {
+            lines.push("[Service]".to_string());
+            lines.extend(env_lines);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Rewrites the plist's `EnvironmentVariables` dict, keeping unmanaged entries.
+fn inject_launchd_env(
+    content: &str,
+    vars: &std::collections::BTreeMap<String, String>,
+    managed: &std::collections::BTreeSet<String>,
+) -> String {
+    let marker = "<key>EnvironmentVariables</key>";
+    let existing = content.find(marker).and_then(|start| {
+        let end = start + content[start..].find("</dict>")? + "</dict>".len();
+        Some((start, end))
+    });
+    let mut entries: Vec<(String, String)> = Vec::new();
+    if let Some((start, end)) = existing {
+        for pair in content[start + marker.len()..end].split("<key>").skip(1) {
+            let Some((key, rest)) = pair.split_once("</key>") else {
+                continue;
+            };
+            let value = rest
+                .trim_start()
+                .strip_prefix("<string>")
+                .and_then(|v| v.split_once("</string>"))
+                .map(|(v, _)| v.to_string())
+                .unwrap_or_default();
+            entries.push((key.trim().to_string(), value));
+        }
+    }
+    entries.retain(|(key, _)| !managed.contains(&xml_unescape(key)));
+    entries.extend(
+        vars.iter()
+            .map(|(name, value)| (xml_escape(name), xml_escape(value))),
+    );
+
+    let mut block = format!("{}\n    <dict>\n", marker);
+    for (key, value) in &entries {
+        block.push_str(&format!(
+            "      <key>{}</key>\n      <string>{}</string>\n",
+            key, value
+        ));
+    }
+    block.push_str("    </dict>");
+
+    let mut content = content.to_string();
+    match existing {
+        Some((start, end)) if entries.is_empty() => {
+            let start = content[..start].trim_end().len();
+            content.replace_range(start..end, "");
+        }
+        Some((start, end)) => content.replace_range(start..end, &block),
+        None if entries.is_empty() => {}
+        None => {
+            if let Some(index) = content.rfind("</dict>") {
+                content.insert_str(index, &format!("  {}\n  ", block));
+            }
+        }
+    }
+    content
+}
+
+/// Writes the managed variables into the gateway service definition. `removed` names are
+/// dropped from it. A definition carrying secret-looking values is made owner-only, since
+/// LaunchAgent plists and systemd units are world-readable by default. Returns whether the
+/// file changed.
+fn sync_gateway_env_to_service(
+    removed: &std::collections::BTreeSet<String>,
+) -> Result<bool, String> {
+    let path = local_gateway_service_path()?;
+    let Some(content) = read_local_text_file(&path) else {
+        return Ok(false);
+    };
+    let vars = read_gateway_env();
+    let managed: std::collections::BTreeSet<String> = vars
+        .keys()
+        .cloned()
+        .chain(removed.iter().cloned())
+        .collect();
+    let updated = if path.ends_with(".plist") {
+        inject_launchd_env(&content, &vars, &managed)
+    } else {
+        inject_systemd_env(&content, &vars, &managed)
+    };
+    if updated == content {
+        return Ok(false);
+    }
+    if vars.keys().any(|name| is_secret_key(name)) {
+        write_local_private_file(&path, &updated)?;
+    } else {
+        write_local_text_file(&path, &updated)?;
+    }
+    reload_gateway_service_definition(&path);
+    Ok(true)
+}
+
+/// `NAME=value` pairs from `/proc/<pid>/environ` lines or, with `by_token`, from the
+/// space-separated tail of `ps eww` output.
+fn parse_env_listing(output: &str, by_token: bool) -> Vec<(String, String)> {
+    let items: Vec<&str> = if by_token {
+        output.split_whitespace().collect()
+    } else {
+        output.lines().collect()
+    };
+    items
+        .into_iter()
+        .filter_map(|item| item.split_once('='))
+        .filter(|(name, _)| is_valid_env_var_name(name))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[command]
+fn get_gateway_env() -> Vec<GatewayEnvVar> {
+    read_gateway_env()
+        .iter()
+        .map(|(name, value)| gateway_env_var(name, value, true))
+        .collect()
+}
+
+/// Stores `next` as the managed variables and syncs the service definition, dropping names
+/// that were in `previous` only. The gateway picks them up on its next start.
+fn save_gateway_env(
+    previous: &std::collections::BTreeMap<String, String>,
+    next: &std::collections::BTreeMap<String, String>,
+) -> Result<(), String> {
+    let raw = serde_json::to_string_pretty(next).map_err(|e| e.to_string())?;
+    write_local_private_file(&paths::local_path(GATEWAY_ENV_FILE)?, &raw)?;
+
+    let removed = previous
+        .keys()
+        .filter(|name| !next.contains_key(*name))
+        .cloned()
+        .collect();
+    sync_gateway_env_to_service(&removed)?;
+    Ok(())
+}
+
+/// Replaces the managed variables, injects them into the service definition and restarts
+/// the gateway. Values equal to the mask keep their stored value.
+#[command]
+fn set_gateway_env(vars: std::collections::BTreeMap<String, String>) -> Result<String, String> {
+    let _operation = lifecycle::try_begin_operation("set_gateway_env")?;
+    let previous = read_gateway_env();
+    let mut next = std::collections::BTreeMap::new();
+    for (name, value) in vars {
+        let name = name.trim().to_string();
+        if !is_valid_env_var_name(&name) {
+            return Err(format!("Invalid environment variable name: {}", name));
+        }
+        if value.contains(['\n', '\r', '\0']) {
+            return Err(format!("{} must be a single line.", name));
+        }
+        let value = match previous.get(&name) {
+            Some(old) if value == GATEWAY_ENV_MASK => old.clone(),
+            _ => value,
+        };
+        next.insert(name, value);
+    }
+
+    save_gateway_env(&previous, &next)?;
+    restart_local_gateway()?;
+    Ok(format!(
+        "Saved {} environment variable(s) and restarted the gateway.",
+        next.len()
+    ))
+}
+
+/// Environment the running gateway process was actually started with.
+#[command]
+fn get_running_gateway_env() -> Result<Vec<GatewayEnvVar>, String> {
+    let pid = gateway_listener_pid().ok_or("The gateway is not running.")?;
+    #[cfg(target_os = "macos")]
+    let (output, by_token) = (
+        process::run("ps", &["eww", "-o", "command=", "-p", &pid.to_string()])?,
+        true,
+    );
+    #[cfg(not(target_os = "macos"))]
+    let (output, by_token) = (
+        shell_command(&format!("tr '\\0' '\\n' < /proc/{}/environ", pid))?,
+        false,
+    );
+
+    let managed = read_gateway_env();
+    let mut vars: Vec<GatewayEnvVar> = parse_env_listing(&output, by_token)
+        .iter()
+        .map(|(name, value)| gateway_env_var(name, value, managed.contains_key(name)))
+        .collect();
+    vars.sort_by(|a, b| a.name.cmp(&b.name));
+    vars.dedup_by(|a, b| a.name == b.name);
+    Ok(vars)
+}
+
+// Gateway failure classification
+
+/// Why a gateway CLI command failed. Only built for non-zero exits, so benign output such
+/// as "0 errors" on a successful run can never be mistaken for a failure.
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum GatewayFailure {
+    CliNotFound,
+    PermissionDenied {
+        detail: String,
+    },
+    PortInUse {
+        port: u16,
+    },
+    ServiceNotLoaded {
+        detail: String,
+    },
+    InvalidConfig {
+        detail: String,
+    },
+    TimedOut {
+        timeout_secs: u64,
+    },
+    Other {
+        exit_code: Option<i32>,
+        detail: String,
+    },
+}
+
+impl std::fmt::Display for GatewayFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GatewayFailure::CliNotFound => {
+                write!(f, "The openclaw CLI was not found. Reinstall OpenClaw and try again.")
+            }
+            GatewayFailure::PermissionDenied { detail } => {
+                write!(f, "Permission denied while managing the gateway: {}", detail)
+            }
+            GatewayFailure::PortInUse { port } => write!(
+                f,
+                "Port {} is already in use by another process. Stop it or choose another gateway port.",
+                port
+            ),
+            GatewayFailure::ServiceNotLoaded { detail } => write!(
+                f,
+                "The gateway service is not installed or not loaded: {}",
+                detail
+            ),
+            GatewayFailure::InvalidConfig { detail } => {
+                write!(f, "openclaw.json is invalid: {}", detail)
+            }
+            GatewayFailure::TimedOut { timeout_secs } => write!(
+                f,
+                "The gateway command did not finish within {} seconds and was stopped. Try again.",
+                timeout_secs
+            ),
+            GatewayFailure::Other { exit_code, detail } => match exit_code {
+                Some(code) => write!(f, "Gateway command failed with exit code {}: {}", code, detail),
+                None => write!(f, "Gateway command was terminated: {}", detail),
+            },
+        }
+    }
+}
+
+/// Pulls `{ "error": { "code", "message" } }` out of `--json` CLI output, if present.
+fn structured_cli_error(stdout: &str) -> Option<(String, String)> {
+    let value: serde_json::Value = serde_json::from_str(stdout.trim()).ok()?;
+    let error = value.get("error")?;
+    let code = error
+        .get("code")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    let message = error
+        .get("message")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .or_else(|| error.as_str().map(|s| s.to_string()))
+        .unwrap_or_default();
+    Some((code, message))
+}
+
+fn classify_gateway_failure(output: &process::Output, port: u16) -> GatewayFailure {
+    match output.code {
+        Some(127) => return GatewayFailure::CliNotFound,
+        Some(126) => {
+            return GatewayFailure::PermissionDenied {
+                detail: output.stderr.trim().to_string(),
+            }
+        }
+        _ => {}
+    }
+
+    let (code, detail) = structured_cli_error(&output.stdout).unwrap_or_else(|| {
+        let detail = if output.stderr.trim().is_empty() {
+            output.stdout.trim()
+        } else {
+            output.stderr.trim()
+        };
+        (String::new(), detail.to_string())
+    });
+    let haystack = format!("{} {}", code, detail).to_lowercase();
+
+    if haystack.contains("eaddrinuse") || haystack.contains("address already in use") {
+        GatewayFailure::PortInUse { port }
+    } else if haystack.contains("eacces") || haystack.contains("permission denied") {
+        GatewayFailure::PermissionDenied { detail }
+    } else if haystack.contains("could not find service") || haystack.contains("not loaded") {
+        GatewayFailure::ServiceNotLoaded { detail }
+    } else if haystack.contains("config_invalid") || haystack.contains("invalid config") {
+        GatewayFailure::InvalidConfig { detail }
+    } else {
+        GatewayFailure::Other {
+            exit_code: output.code,
+            detail,
+        }
+    }
+}
+
+/// Runs a gateway CLI command, mapping a non-zero exit to a typed failure.
+fn run_gateway_cli(cmd: &str, port: u16) -> Result<String, GatewayFailure> {
+    let output = process::shell(cmd).map_err(|e| match e {
+        process::ProcessError::Timeout { timeout_secs, .. } => {
+            GatewayFailure::TimedOut { timeout_secs }
+        }
+        process::ProcessError::Spawn { detail } => GatewayFailure::Other {
+            exit_code: None,
+            detail,
+        },
+    })?;
+    if output.success() {
+        Ok(output.stdout)
+    } else {
+        Err(classify_gateway_failure(&output, port))
+    }
+}
+
+// Allowlisted CLI passthrough
+
+/// `(subcommand, action, flags)` the UI may run directly, mirroring the app terminal's
+/// `terminal::ALLOWED_COMMANDS`. An empty action means the subcommand takes none, nor any
+/// other positional argument; any argument starting with `-` must be one of the listed flags,
+/// followed by its value for `CLI_VALUE_FLAGS`. Mutating actions such as `config set`,
+/// `plugins install` or `doctor --fix` stay behind dedicated commands.
+const CLI_SUBCOMMAND_ALLOWLIST: &[(&str, &str, &[&str])] = &[
+    ("agents", "list", &["--json"]),
+    ("channels", "list", &["--json"]),
+    ("channels", "status", &["--json", "--probe"]),
+    ("config", "get", &["--json"]),
+    ("cron", "list", &["--all", "--json"]),
+    ("cron", "status", &["--json"]),
+    ("doctor", "", &["--non-interactive"]),
+    ("gateway", "probe", &["--json"]),
+    ("gateway", "status", &["--deep", "--json"]),
+    ("health", "", &["--json"]),
+    ("logs", "", &["--json", "--limit", "--plain"]),
+    ("models", "list", &["--all", "--json"]),
+    ("models", "status", &["--json"]),
+    ("pairing", "list", &["--channel", "--json"]),
+    ("plugins", "list", &["--json"]),
+    ("security", "audit", &["--deep", "--json"]),
+    ("sessions", "", &["--json"]),
+    ("skills", "info", &["--json"]),
+    ("skills", "list", &["--json"]),
+    ("status", "", &["--all", "--deep", "--json"]),
+];
+
+/// Allowlisted flags that take a value, as `--flag value` or `--flag=value`.
+const CLI_VALUE_FLAGS: &[&str] = &["--channel", "--limit"];
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct CliRunOutput {
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+    /// The command was killed at its timeout; the UI offers to retry.
+    timed_out: bool,
+}
+
+/// Builds the argument vector for `openclaw`, rejecting anything outside the allowlist.
+fn cli_subcommand_argv(subcommand: &str, args: &[String]) -> Result<Vec<String>, String> {
+    let action = args.first().map(String::as_str).unwrap_or_default();
+    let (_, action, flags) = CLI_SUBCOMMAND_ALLOWLIST
+        .iter()
+        .find(|(s, a, _)| *s == subcommand && (a.is_empty() || *a == action))
+        .ok_or_else(|| {
+            format!(
+                "'{}' is not an allowed openclaw subcommand",
+                format!("{} {}", subcommand, action).trim_end()
+            )
+        })?;
+    let rest = if action.is_empty() { args } else { &args[1..] };
+    if let Some(bad) = rest.iter().find(|arg| arg.contains('\0')) {
+        return Err(format!("Invalid argument: {:?}", bad));
+    }
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        if !arg.starts_with('-') {
+            // Actions take operands (`config get <key>`); bare subcommands take none.
+            if action.is_empty() {
+                return Err(format!(
+                    "'{}' is not an allowed argument for openclaw {}",
+                    arg, subcommand
+                ));
+            }
+            continue;
+        }
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, _)) => (flag, true),
+            None => (arg.as_str(), false),
+        };
+        let takes_value = CLI_VALUE_FLAGS.contains(&flag);
+        if !flags.contains(&flag) || (inline_value && !takes_value) {
+            return Err(format!(
+                "'{}' is not an allowed flag for openclaw {}",
+                arg, subcommand
+            ));
+        }
+        if takes_value && !inline_value && rest.next().is_none() {
+            return Err(format!("{} needs a value", flag));
+        }
+    }
+    let mut argv = vec![subcommand.to_string()];
+    argv.extend(args.iter().cloned());
+    Ok(argv)
+}
+
+/// Runs `openclaw <subcommand> <args...>` without a shell; arguments reach the process
+/// verbatim, so nothing in them is ever interpreted. `timeout_secs` overrides the default
+/// timeout for long-running subcommands such as `logs`.
+#[command]
+fn run_cli_subcommand(
+    subcommand: String,
+    args: Vec<String>,
+    timeout_secs: Option<u64>,
+) -> Result<CliRunOutput, String> {
+    let argv = cli_subcommand_argv(&subcommand, &args)?;
+    let result = match timeout_secs {
+        Some(secs) => {
+            process::output_with_timeout("openclaw", &argv, Duration::from_secs(secs.max(1)))
+        }
+        None => process::output("openclaw", &argv),
+    };
+    match result {
+        Ok(output) => Ok(CliRunOutput {
+            exit_code: output.code,
+            stdout: output.stdout,
+            stderr: output.stderr,
+            timed_out: false,
+        }),
+        Err(process::ProcessError::Timeout { .. }) => Ok(CliRunOutput {
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            timed_out: true,
+        }),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Cached absolute paths of node/npm/openclaw and the login PATH; `refresh` re-probes.
+#[command]
+fn get_environment_info(refresh: Option<bool>) -> process::EnvironmentInfo {
+    if refresh.unwrap_or(false) {
+        process::probe_environment()
+    } else {
+        process::environment_info()
+    }
+}
+
+// npm install settings (registry, proxy, CA) for installs behind corporate firewalls
+
+const INSTALL_SETTINGS_FILE: &str = "install-settings.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+struct InstallSettings {
+    registry: Option<String>,
+    proxy: Option<String>,
+    /// Path to a PEM bundle, as seen by npm (inside WSL on Windows).
+    ca_file: Option<String>,
+    /// One of `INSTALL_STRATEGIES`; unset means choose automatically.
+    #[serde(default)]
+    preferred_strategy: Option<String>,
+}
+
+fn install_settings_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_dir = app
+        .path_resolver()
+        .app_data_dir()
+        .ok_or("Could not determine app data directory")?;
+    Ok(app_dir.join(INSTALL_SETTINGS_FILE))
+}
+
+fn read_install_settings(app: &tauri::AppHandle) -> Option<InstallSettings> {
+    let raw = fs::read_to_string(install_settings_path(app).ok()?).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn has_network_overrides(settings: &InstallSettings) -> bool {
+    settings.registry.is_some() || settings.proxy.is_some() || settings.ca_file.is_some()
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn validate_install_settings(settings: &InstallSettings) -> Result<(), String> {
+    for (label, value) in [("Registry", &settings.registry), ("Proxy", &settings.proxy)] {
+        if let Some(value) = value {
+            let parsed = url::Url::parse(value)
+                .map_err(|e| format!("{} URL '{}' is invalid: {}", label, value, e))?;
+            if parsed.scheme() != "http" && parsed.scheme() != "https" {
+                return Err(format!("{} URL must start with http:// or https://", label));
+            }
+        }
+    }
+    if let Some(strategy) = &settings.preferred_strategy {
+        if !INSTALL_STRATEGIES.contains(&strategy.as_str()) {
+            return Err(format!("Unknown install strategy: {}", strategy));
+        }
+    }
+    if let Some(ca_file) = &settings.ca_file {
+        if process::run("test", &["-f", ca_file]).is_err() {
+            return Err(format!("CA certificate file not found: {}", ca_file));
+        }
+    }
+    Ok(())
+}
+
+/// npm flags for `settings`; passed per invocation so the user's own `.npmrc` is untouched.
+fn npm_config_args(settings: &InstallSettings) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(registry) = &settings.registry {
+        args.push(format!("--registry={}", registry));
+    }
+    if let Some(proxy) = &settings.proxy {
+        args.push(format!("--proxy={}", proxy));
+        args.push(format!("--https-proxy={}", proxy));
+    }
+    if let Some(ca_file) = &settings.ca_file {
+        args.push(format!("--cafile={}", ca_file));
+    }
+    args
+}
+
+/// `npm ping` with the same flags the install will use, so a wrong proxy or CA fails fast
+/// with a clear message instead of a multi-minute npm retry loop. A short `NETWORK_CHECK`
+/// retry covers a registry that drops the first connection.
+fn check_npm_registry(
+    settings: &InstallSettings,
+    progress: &progress::Progress,
+) -> Result<(), String> {
+    let mut args = vec!["ping".to_string()];
+    args.extend(npm_config_args(settings));
+    retry::retry(
+        &retry::NETWORK_CHECK,
+        |_| {
+            process::output_with_timeout("npm", &args, Duration::from_secs(30))
+                .map_err(String::from)?
+                .into_result()
+                .map(|_| ())
+        },
+        |_| true,
+        |attempt, error| progress.warn("registry", 10, &attempt.describe(error)),
+    )
+    .map_err(|e| {
+        format!(
+            "Could not reach the npm registry {}: {}",
+            settings
+                .registry
+                .as_deref()
+                .unwrap_or("https://registry.npmjs.org/"),
+            e.trim()
+        )
+    })
+}
+
+/// Runs a global npm command; on Windows it needs root inside WSL for /usr/lib/node_modules.
+fn run_npm_global(args: &[String]) -> Result<String, String> {
+    #[cfg(target_os = "windows")]
+    {
+        let quoted: Vec<String> = args.iter().map(|a| shell_single_quote(a)).collect();
+        wsl_root_command(&format!("npm {}", quoted.join(" ")))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    process::run("npm", args)
+}
+
+#[command]
+fn get_install_settings(app: tauri::AppHandle) -> InstallSettings {
+    read_install_settings(&app).unwrap_or_default()
+}
+
+/// Validates and saves npm registry/proxy/CA settings; the registry must be reachable with
+/// them before they are stored. Empty values clear a setting.
+#[command]
+fn set_install_settings(
+    app: tauri::AppHandle,
+    registry: Option<String>,
+    proxy: Option<String>,
+    ca_file: Option<String>,
+    preferred_strategy: Option<String>,
+) -> Result<InstallSettings, String> {
+    let settings = InstallSettings {
+        registry: non_empty(registry),
+        proxy: non_empty(proxy),
+        ca_file: non_empty(ca_file),
+        preferred_strategy: non_empty(preferred_strategy),
+    };
+    validate_install_settings(&settings)?;
+    if has_network_overrides(&settings) {
+        check_npm_registry(
+            &settings,
+            &progress::Progress::new(&app, "install-settings", None),
+        )?;
+    }
+
+    let path = install_settings_path(&app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let raw = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize install settings: {}", e))?;
+    fs::write(&path, raw).map_err(|e| format!("Failed to save install settings: {}", e))?;
+    Ok(settings)
+}
+
+// Offline install from a local npm tarball
+
+/// `C:\Users\me\openclaw.tgz` → `/mnt/c/Users/me/openclaw.tgz`, for handing host paths to
+/// npm inside WSL.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn windows_path_to_wsl(path: &str) -> Option<String> {
+    let mut chars = path.chars();
+    let drive = chars.next().filter(|c| c.is_ascii_alphabetic())?;
+    if chars.next() != Some(':') {
+        return None;
+    }
+    let rest = chars.as_str().replace('\\', "/");
+    Some(format!(
+        "/mnt/{}/{}",
+        drive.to_ascii_lowercase(),
+        rest.trim_start_matches('/')
+    ))
+}
+
+fn verify_sha256(bytes: &[u8], expected: &str) -> Result<(), String> {
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    if actual.eq_ignore_ascii_case(expected.trim()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Checksum mismatch: expected {}, got {}",
+            expected.trim(),
+            actual
+        ))
+    }
+}
+
+/// Installs openclaw from a local `.tgz` (e.g. from `npm pack`) for air-gapped machines.
+/// npm is told to prefer its cache and skip audit/update checks so no registry round trip
+/// is needed when the tarball bundles its dependencies.
+#[command]
+fn install_openclaw_from_file(path: String, sha256: Option<String>) -> Result<String, String> {
+    if !path.ends_with(".tgz") && !path.ends_with(".tar.gz") {
+        return Err("Select an npm package tarball (.tgz).".to_string());
+    }
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if let Some(expected) = sha256.filter(|s| !s.trim().is_empty()) {
+        verify_sha256(&bytes, &expected)?;
+    }
+
+    #[cfg(target_os = "windows")]
+    let npm_path = {
+        ensure_wsl2_installed()?;
+        windows_path_to_wsl(&path)
+            .ok_or_else(|| format!("Cannot map {} into WSL; copy it to a local drive", path))?
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let npm_path = path.clone();
+
+    let args: Vec<String> = [
+        "install",
+        "-g",
+        &npm_path,
+        "--prefer-offline",
+        "--no-audit",
+        "--no-fund",
+        "--no-update-notifier",
+    ]
+    .iter()
+    .map(|a| a.to_string())
+    .collect();
+    run_npm_global(&args)?;
+    process::probe_environment();
+    let version = shell_command("openclaw --version")?;
+    Ok(format!(
+        "OpenClaw {} installed from {}.",
+        version.trim(),
+        path
+    ))
+}
+
+// Recovery from EACCES on global npm installs
+
+const NPM_USER_PREFIX_DIR: &str = ".npm-global";
+
+fn is_npm_permission_error(output: &str) -> bool {
+    let lower = output.to_lowercase();
+    lower.contains("eacces") || (lower.contains("npm") && lower.contains("permission denied"))
+}
+
+/// Replaces npm's EACCES dump with a message pointing at `recover_npm_permissions`.
+fn explain_npm_permission_error(error: String) -> String {
+    if is_npm_permission_error(&error) {
+        format!(
+            "npm cannot write to its global install directory (EACCES). Switch npm to a \
+             user-writable prefix or retry with administrator rights.\n\n{}",
+            error.trim()
+        )
+    } else {
+        error
+    }
+}
+
+/// Recovers from an EACCES global install. `method` is `user_prefix` (point npm at
+/// `~/.npm-global`, whose bin directory the environment probe adds to PATH) or `elevated`
+/// (re-run the install as root via the system password prompt).
+#[command]
+fn recover_npm_permissions(app: tauri::AppHandle, method: String) -> Result<String, String> {
+    if cfg!(target_os = "windows") {
+        return Err("Global npm installs already run as root inside WSL.".to_string());
+    }
+    let settings = read_install_settings(&app).unwrap_or_default();
+    let args = install_backend_args("npm", &settings)?;
+    match method.as_str() {
+        "user_prefix" => {
+            let prefix = dirs::home_dir()
+                .ok_or("Could not find home directory")?
+                .join(NPM_USER_PREFIX_DIR);
+            fs::create_dir_all(&prefix)
+                .map_err(|e| format!("Failed to create {}: {}", prefix.display(), e))?;
+            process::run(
+                "npm",
+                &["config", "set", "prefix", &prefix.to_string_lossy()],
+            )?;
+            process::run("npm", &args)?;
+        }
+        "elevated" => {
+            elevation::run_elevated("npm", &args)?;
+        }
+        other => return Err(format!("Unknown recovery method: {}", other)),
+    }
+    process::probe_environment();
+    shell_command("openclaw --version")?;
+    Ok(match method.as_str() {
+        "user_prefix" => format!(
+            "OpenClaw installed into ~/{}. Add ~/{}/bin to your shell PATH to use it from a terminal.",
+            NPM_USER_PREFIX_DIR, NPM_USER_PREFIX_DIR
+        ),
+        _ => "OpenClaw installed with administrator rights.".to_string(),
+    })
+}
+
+// Storage usage and log rotation
+
+const LOG_ROTATION_INTERVAL_SECS: u64 = 6 * 60 * 60;
+const DEFAULT_LOG_MAX_AGE_DAYS: u64 = 14;
+const DEFAULT_LOG_MAX_SIZE_MB: u64 = 500;
+/// Files written this recently are still open by the gateway; unlinking them would not
+/// free any space until it restarts, so they are rotated by copy-truncate instead.
+const ACTIVE_LOG_GRACE_SECS: u64 = 15 * 60;
+
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+struct StorageUsage {
+    logs_bytes: u64,
+    sessions_bytes: u64,
+    workspace_bytes: u64,
+    /// Everything under `~/.openclaw` plus the gateway's `/tmp/openclaw` logs.
+    total_bytes: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct LogFile {
+    path: String,
+    modified: u64,
+    size: u64,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Default)]
+struct PruneResult {
+    removed_files: Vec<String>,
+    /// Active logs copied aside and truncated; their copies may be in `removed_files`.
+    rotated_files: Vec<String>,
+    freed_bytes: u64,
+}
+
+/// One pruning pass: copy each `rotate` log to its new path and truncate it, then delete
+/// `remove`, which may include copies just made.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct LogPrunePlan {
+    rotate: Vec<(String, String)>,
+    remove: Vec<LogFile>,
+}
+
+/// Sums `du -sk` lines (run from `~/.openclaw`) into categories.
+fn summarize_storage(du_output: &str) -> StorageUsage {
+    let mut usage = StorageUsage::default();
+    for line in du_output.lines() {
+        let mut parts = line.splitn(2, '\t');
+        let (Some(kb), Some(path)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let Ok(bytes) = kb.trim().parse::<u64>().map(|kb| kb * 1024) else {
+            continue;
+        };
+        let path = path.trim().trim_start_matches("./");
+        if path == "." {
+            usage.total_bytes += bytes;
+        } else if path == "logs" {
+            usage.logs_bytes += bytes;
+        } else if path.starts_with("/tmp/openclaw") {
+            // Outside ~/.openclaw, so it is not part of the "." total yet.
+            usage.logs_bytes += bytes;
+            usage.total_bytes += bytes;
+        } else if path.ends_with("/sessions") {
+            usage.sessions_bytes += bytes;
+        } else if path == "workspace" || path.ends_with("/workspace") {
+            usage.workspace_bytes += bytes;
+        }
+    }
+    usage
+}
+
+/// Parses `modified<TAB>size<TAB>path` lines from the log listing script.
+fn parse_log_listing(output: &str) -> Vec<LogFile> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            Some(LogFile {
+                modified: parts.next()?.trim().parse().ok()?,
+                size: parts.next()?.trim().parse().ok()?,
+                path: parts.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Rotates active logs that take more than half of `max_bytes`, then deletes everything
+/// older than `max_age_secs` and the oldest remaining files until the total fits in
+/// `max_bytes`. Active logs themselves are never deleted; their rotated copies can be.
+fn plan_log_pruning(
+    files: &[LogFile],
+    now: u64,
+    max_age_secs: u64,
+    max_bytes: u64,
+) -> LogPrunePlan {
+    let mut plan = LogPrunePlan::default();
+    let mut candidates = Vec::new();
+    for file in files {
+        if now.saturating_sub(file.modified) > ACTIVE_LOG_GRACE_SECS {
+            candidates.push(file.clone());
+        } else if file.size > max_bytes / 2 {
+            let rotated = format!("{}.{}", file.path, now);
+            plan.rotate.push((file.path.clone(), rotated.clone()));
+            candidates.push(LogFile {
+                path: rotated,
+                ..file.clone()
+            });
+        }
+    }
+    candidates.sort_by_key(|f| f.modified);
+
+    let mut remaining: u64 = files.iter().map(|f| f.size).sum();
+    for file in candidates {
+        let expired = now.saturating_sub(file.modified) > max_age_secs;
+        if expired || remaining > max_bytes {
+            remaining -= file.size;
+            plan.remove.push(file);
+        }
+    }
+    plan
+}
+
+fn list_log_files() -> Result<Vec<LogFile>, String> {
+    let listing = shell_command(&format!(
+        "for f in \"{}\"/logs/* /tmp/openclaw/*; do \
+         [ -f \"$f\" ] && printf '%s\\t%s\\t%s\\n' \"$(date -r \"$f\" +%s)\" \
+         \"$(wc -c < \"$f\" | tr -d ' ')\" \"$f\"; done; true",
+        paths::shell_root()
+    ))?;
+    Ok(parse_log_listing(&listing))
+}
+
+fn prune_log_files(max_age_days: u64, max_size_mb: u64) -> Result<PruneResult, String> {
+    let files = list_log_files()?;
+    let plan = plan_log_pruning(
+        &files,
+        unix_now_secs(),
+        max_age_days * 24 * 60 * 60,
+        max_size_mb * 1024 * 1024,
+    );
+    // The gateway appends to its log, so it carries on at the start of the truncated file.
+    for (path, rotated) in &plan.rotate {
+        shell_command(&format!(
+            "cp -p {0} {1} && : > {0}",
+            shell_single_quote(path),
+            shell_single_quote(rotated)
+        ))
+        .map_err(|e| format!("Failed to rotate {}: {}", path, e))?;
+    }
+    let paths: Vec<String> = plan.remove.iter().map(|f| f.path.clone()).collect();
+    if !paths.is_empty() {
+        let mut args = vec!["-f".to_string()];
+        args.extend(paths.iter().cloned());
+        process::run("rm", &args).map_err(|e| format!("Failed to remove old logs: {}", e))?;
+    }
+    Ok(PruneResult {
+        removed_files: paths,
+        rotated_files: plan.rotate.into_iter().map(|(path, _)| path).collect(),
+        freed_bytes: plan.remove.iter().map(|f| f.size).sum(),
+    })
+}
+
+/// Sizes of logs, sessions and workspaces under `~/.openclaw`.
+#[command]
+fn get_storage_usage() -> Result<StorageUsage, String> {
+    let du = shell_command(&format!(
+        "cd \"{}\" 2>/dev/null && \
+         du -sk logs workspace agents/*/sessions agents/*/workspace . 2>/dev/null; \
+         du -sk /tmp/openclaw 2>/dev/null; true",
+        paths::shell_root()
+    ))?;
+    Ok(summarize_storage(&du))
+}
+
+/// Deletes gateway logs older than `older_than_days` and, oldest first, until the logs
+/// total at most `max_size_mb`. A log the gateway is still writing is rotated first.
+#[command]
+fn prune_logs(
+    older_than_days: Option<u64>,
+    max_size_mb: Option<u64>,
+) -> Result<PruneResult, String> {
+    prune_log_files(
+        older_than_days.unwrap_or(DEFAULT_LOG_MAX_AGE_DAYS),
+        max_size_mb.unwrap_or(DEFAULT_LOG_MAX_SIZE_MB),
+    )
+}
+
+/// Prunes logs with the default limits every few hours so a long-running gateway cannot
+/// silently fill the disk.
+fn start_log_rotation() {
+    if LOG_ROTATION_RUNNING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    lifecycle::spawn("log-rotation", || loop {
+        if let Err(e) = prune_log_files(DEFAULT_LOG_MAX_AGE_DAYS, DEFAULT_LOG_MAX_SIZE_MB) {
+            eprintln!("Log rotation failed: {}", e);
+        }
+        if !lifecycle::sleep(Duration::from_secs(LOG_ROTATION_INTERVAL_SECS)) {
+            break;
+        }
+    });
+}
+
+// Gateway log anomalies
+
+const LOG_MONITOR_POLL_SECS: u64 = 10;
+const MAX_DETECTED_ISSUES: usize = 50;
+
+/// Where to resume reading `current`. The first file seen is read from its end so history
+/// from before the monitor started is not replayed; a rotated or truncated file from the
+/// start.
+fn log_read_start(tracked: Option<&(String, u64)>, current: &LogFile) -> u64 {
+    match tracked {
+        None => current.size,
+        Some((path, offset)) if *path == current.path && *offset <= current.size => *offset,
+        Some(_) => 0,
+    }
+}
+
+/// Complete lines of `chunk` and how many bytes they span; a trailing partial line is left
+/// for the next poll.
+fn complete_log_lines(chunk: &str) -> (Vec<&str>, usize) {
+    match chunk.rfind('\n') {
+        Some(end) => (chunk[..end].lines().collect(), end + 1),
+        None => (Vec::new(), 0),
+    }
+}
+
+fn record_detected_issue(app: &tauri::AppHandle, issue: log_monitor::DetectedIssue) {
+    if let Ok(mut issues) = DETECTED_ISSUES.lock() {
+        issues.push_front(issue.clone());
+        issues.truncate(MAX_DETECTED_ISSUES);
+    }
+    let _ = app.emit_all("issue-detected", issue);
+}
+
+/// Tails the newest gateway log, emits an `issue-detected` event for each recognised
+/// problem (see `log_monitor`) and records provider rejections for `get_provider_health`.
+fn start_log_monitor(app: tauri::AppHandle) {
+    if LOG_MONITOR_RUNNING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    lifecycle::spawn("log-monitor", move || {
+        let mut detector = log_monitor::Detector::default();
+        let mut tracked: Option<(String, u64)> = None;
+        while lifecycle::sleep(Duration::from_secs(LOG_MONITOR_POLL_SECS)) {
+            let Some(current) = list_log_files()
+                .ok()
+                .and_then(|files| files.into_iter().max_by_key(|f| f.modified))
+            else {
+                continue;
+            };
+            let start = log_read_start(tracked.as_ref(), &current);
+            let chunk = if start < current.size {
+                shell_command(&format!(
+                    "tail -c +{} {}",
+                    start + 1,
+                    shell_single_quote(&current.path)
+                ))
+                .unwrap_or_default()
+            } else {
+                String::new()
+            };
+            let (lines, consumed) = complete_log_lines(&chunk);
+            for line in lines {
+                let now = unix_now_secs();
+                if let Some(signal) = provider_health::parse_provider_error(line) {
+                    if let Ok(mut tracker) = PROVIDER_HEALTH.lock() {
+                        tracker.apply(&signal, now);
+                    }
+                }
+                if let Some(issue) = detector.observe(line, now) {
+                    record_detected_issue(&app, issue);
+                }
+            }
+            tracked = Some((current.path, start + consumed as u64));
+        }
+        LOG_MONITOR_RUNNING.store(false, Ordering::Relaxed);
+    });
+}
+
+/// Issues found in the gateway log since the app started, newest first.
+#[command]
+fn get_detected_issues() -> Vec<log_monitor::DetectedIssue> {
+    DETECTED_ISSUES
+        .lock()
+        .map(|issues| issues.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[command]
+fn clear_detected_issues() {
+    if let Ok(mut issues) = DETECTED_ISSUES.lock() {
+        issues.clear();
+    }
+}
+
+// Provider health
+
+/// Rate-limit and quota state per auth profile, from provider errors in the gateway log and
+/// openclaw's own cooldowns.
+#[command]
+fn get_provider_health() -> Result<Vec<provider_health::ProviderHealth>, String> {
+    let auth = read_provider_auth_profiles().unwrap_or_else(|_| serde_json::json!({}));
+    let profiles: Vec<String> = auth
+        .get("profiles")
+        .and_then(|p| p.as_object())
+        .map(|p| p.keys().cloned().collect())
+        .unwrap_or_default();
+    let now = unix_now_secs();
+    let mut health = PROVIDER_HEALTH
+        .lock()
+        .map_err(|_| "Provider health state is poisoned".to_string())?
+        .snapshot(&profiles, now);
+    if let Some(stats) = auth.get("usageStats") {
+        for entry in &mut health {
+            if let Some(stats) = stats.get(&entry.profile) {
+                provider_health::apply_usage_stats(entry, stats, now);
+            }
+        }
+    }
+    Ok(health)
+}
+
+// Provider latency
+
+const LATENCY_PROBE_SECS: u64 = 5 * 60;
+const LATENCY_PROBE_TIMEOUT_SECS: u64 = 10;
+
+/// Providers in the primary model and fallback chain, each with a URL to time: the model
+/// list of an endpoint configured under `models.providers`, else the provider's API host.
+fn latency_probe_targets(config: &serde_json::Value) -> Vec<(String, String)> {
+    let model = config.pointer("/agents/defaults/model");
+    let primary = model
+        .and_then(|m| {
+            m.as_str()
+                .or_else(|| m.get("primary").and_then(|p| p.as_str()))
+        })
+        .into_iter();
+    let fallbacks = model
+        .and_then(|m| m.get("fallbacks"))
+        .and_then(|f| f.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|f| f.as_str());
+    let mut targets: Vec<(String, String)> = Vec::new();
+    for model_ref in primary.chain(fallbacks) {
+        let Some((provider, _)) = model_ref.split_once('/') else {
+            continue;
+        };
+        if targets.iter().any(|(p, _)| p == provider) {
+            continue;
+        }
+        let url = match config
+            .pointer(&format!("/models/providers/{}/baseUrl", provider))
+            .and_then(|v| v.as_str())
+        {
+            Some(base_url) => format!("{}/models", base_url.trim_end_matches('/')),
+            None => match provider_api_host(provider) {
+                Some(host) => format!("https://{}/", host),
+                None => continue,
+            },
+        };
+        targets.push((provider.to_string(), url));
+    }
+    targets
+}
+
+/// Round-trip milliseconds for `url`. Any answer below 500 counts: the probe sends no key,
+/// so 401 and 404 still show the provider is up.
+fn probe_latency(client: &reqwest::blocking::Client, url: &str) -> Result<u64, String> {
+    let started = Instant::now();
+    let resp = client
+        .get(url)
+        .send()
+        .map_err(|e| format!("Failed to reach {}: {}", url, e))?;
+    let elapsed = started.elapsed().as_millis() as u64;
+    if resp.status().is_server_error() {
+        return Err(format!("HTTP {}", resp.status().as_u16()));
+    }
+    Ok(elapsed)
+}
+
+/// Probes every configured provider now and then every `LATENCY_PROBE_SECS`.
+fn start_latency_prober() {
+    if LATENCY_PROBER_RUNNING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    lifecycle::spawn("latency-prober", || {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(LATENCY_PROBE_TIMEOUT_SECS))
+            .build();
+        if let Ok(client) = client {
+            loop {
+                let targets = read_local_openclaw_config()
+                    .map(|config| latency_probe_targets(&config))
+                    .unwrap_or_default();
+                for (provider, url) in &targets {
+                    let result = probe_latency(&client, url);
+                    if let Ok(mut tracker) = PROVIDER_LATENCY.lock() {
+                        tracker.record(provider, result, unix_now_secs());
+                    }
+                }
+                if let Ok(mut tracker) = PROVIDER_LATENCY.lock() {
+                    let providers: Vec<String> = targets.into_iter().map(|(p, _)| p).collect();
+                    tracker.retain(&providers);
+                }
+                if !lifecycle::sleep(Duration::from_secs(LATENCY_PROBE_SECS)) {
+                    break;
+                }
+            }
+        }
+        LATENCY_PROBER_RUNNING.store(false, Ordering::Relaxed);
+    });
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct ProviderLatencyReport {
+    providers: Vec<latency::ProviderLatency>,
+    fallbacks: Vec<String>,
+    recommended_fallbacks: Vec<String>,
+}
+
+fn configured_fallbacks(config: &serde_json::Value) -> Vec<String> {
+    config
+        .pointer("/agents/defaults/model/fallbacks")
+        .and_then(|f| f.as_array())
+        .map(|f| {
+            f.iter()
+                .filter_map(|m| m.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn provider_latency_report(config: &serde_json::Value) -> Result<ProviderLatencyReport, String> {
+    let providers = PROVIDER_LATENCY
+        .lock()
+        .map_err(|_| "Provider latency state is poisoned".to_string())?
+        .snapshot();
+    let unhealthy: Vec<String> = get_provider_health()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|h| h.state != "ok")
+        .filter_map(|h| h.profile.split(':').next().map(|p| p.to_string()))
+        .collect();
+    let fallbacks = configured_fallbacks(config);
+    let recommended_fallbacks = latency::recommend_fallbacks(&fallbacks, &providers, &unhealthy);
+    Ok(ProviderLatencyReport {
+        providers,
+        fallbacks,
+        recommended_fallbacks,
+    })
+}
+
+/// Latest latency and availability per configured provider, with the fallback chain
+/// reordered so the fastest providers that are up come first.
+#[command]
+fn get_provider_latency() -> Result<ProviderLatencyReport, String> {
+    provider_latency_report(&read_local_openclaw_config()?)
+}
+
+/// Saves the recommended fallback order and reloads the gateway.
+#[command]
+async fn apply_recommended_fallbacks() -> Result<Vec<String>, String> {
+    let _operation = lifecycle::begin_operation("apply_recommended_fallbacks").await;
+    let (fallbacks, changed) = update_local_openclaw_config(|config| {
+        let report = provider_latency_report(config)?;
+        if report.recommended_fallbacks == report.fallbacks {
+            return Ok((report.fallbacks, false));
+        }
+        config["agents"]["defaults"]["model"]["fallbacks"] =
+            serde_json::json!(report.recommended_fallbacks);
+        Ok((report.recommended_fallbacks, true))
+    })?;
+    if changed {
+        reload_gateway_config().await?;
+    }
+    Ok(fallbacks)
+}
+
+// Workspace history
+
+const WORKSPACE_GIT_PUSH_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_WORKSPACE_LOG_LIMIT: usize = 50;
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+struct WorkspaceCommit {
+    sha: String,
+    timestamp: u64,
+    message: String,
+}
+
+fn workspace_dir(agent_id: Option<&str>) -> Result<String, String> {
+    match agent_id {
+        Some(id) if !is_valid_session_id(id) => Err(format!("Invalid agent id: {}", id)),
+        Some(id) => paths::local_path(&format!("agents/{}/workspace", id)),
+        None => paths::local_path("workspace"),
+    }
+}
+
+/// Runs `git <args>` in `workspace`, without a shell. Never prompts: with `true` as the
+/// askpass program, a remote that needs credentials beyond a stored one fails.
+fn workspace_git(workspace: &str, args: &[&str]) -> Result<String, String> {
+    workspace_git_with_timeout(workspace, args, None)
+}
+
+fn workspace_git_with_timeout(
+    workspace: &str,
+    args: &[&str],
+    timeout: Option<Duration>,
+) -> Result<String, String> {
+    let mut argv = vec!["-C", workspace, "-c", "core.askPass=true"];
+    argv.extend_from_slice(args);
+    match timeout {
+        Some(timeout) => process::output_with_timeout("git", &argv, timeout)?.into_result(),
+        None => process::run("git", &argv),
+    }
+}
+
+fn workspace_has_repo(workspace: &str) -> bool {
+    process::run("test", &["-d", &format!("{}/.git", workspace)]).is_ok()
+}
+
+fn is_commit_sha(sha: &str) -> bool {
+    (7..=40).contains(&sha.len()) && sha.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Parses `git log --format=%H%x1f%at%x1f%s`.
+fn parse_workspace_log(output: &str) -> Vec<WorkspaceCommit> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\u{1f}');
+            Some(WorkspaceCommit {
+                sha: fields.next()?.trim().to_string(),
+                timestamp: fields.next()?.trim().parse().ok()?,
+                message: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+fn try_commit_workspace_changes(workspace: &str, message: &str) -> Result<Option<String>, String> {
+    if !workspace_has_repo(workspace) {
+        return Ok(None);
+    }
+    workspace_git(workspace, &["add", "-A"])?;
+    if workspace_git(workspace, &["status", "--porcelain"])?
+        .trim()
+        .is_empty()
+    {
+        return Ok(None);
+    }
+    workspace_git(workspace, &["commit", "-q", "-m", message])?;
+    let sha = workspace_git(workspace, &["rev-parse", "HEAD"])?
+        .trim()
+        .to_string();
+    if workspace_git(workspace, &["remote", "get-url", "origin"]).is_ok() {
+        let workspace = workspace.to_string();
+        lifecycle::spawn("workspace-push", move || {
+            // A push queued behind another still sends HEAD as of when it runs.
+            let _push = WORKSPACE_PUSH.lock().unwrap_or_else(|e| e.into_inner());
+            let result = workspace_git_with_timeout(
+                &workspace,
+                &["push", "-q", "origin", "HEAD"],
+                Some(Duration::from_secs(WORKSPACE_GIT_PUSH_TIMEOUT_SECS)),
+            );
+            if let Err(e) = result {
+                eprintln!("Workspace push failed: {}", e.trim());
+            }
+        });
+    }
+    Ok(Some(sha))
+}
+
+/// Commits every workspace change with `message` when the workspace has a repo, and pushes
+/// in the background when it has a remote. Failures are logged, never returned: history must
+/// not block a save.
+fn commit_workspace_changes(agent_id: Option<&str>, message: &str) -> Option<String> {
+    let workspace = workspace_dir(agent_id).ok()?;
+    try_commit_workspace_changes(&workspace, message)
+        .map_err(|e| eprintln!("Workspace commit failed: {}", e.trim()))
+        .ok()
+        .flatten()
+}
+
+/// Puts the workspace under git so every save becomes a commit. With `remote_url`, commits
+/// are also pushed there; the remote must accept pushes without a prompt (SSH key or a
+/// stored credential).
+#[command]
+fn init_workspace_repo(
+    agent_id: Option<String>,
+    remote_url: Option<String>,
+) -> Result<String, String> {
+    let _operation = lifecycle::try_begin_operation("init_workspace_repo")?;
+    let workspace = workspace_dir(agent_id.as_deref())?;
+    process::run("mkdir", &["-p", &workspace])?;
+    if !workspace_has_repo(&workspace) {
+        workspace_git(&workspace, &["init", "-q"])?;
+    }
+    if workspace_git(&workspace, &["config", "user.email"]).is_err() {
+        workspace_git(&workspace, &["config", "user.name", "Clawnetes"])?;
+        workspace_git(&workspace, &["config", "user.email", "clawnetes@localhost"])?;
+    }
+    if let Some(url) = remote_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+    {
+        if url.starts_with('-') {
+            return Err(format!("Invalid remote URL: {}", url));
+        }
+        let action = if workspace_git(&workspace, &["remote", "get-url", "origin"]).is_ok() {
+            "set-url"
+        } else {
+            "add"
+        };
+        workspace_git(&workspace, &["remote", action, "origin", url])?;
+    }
+    Ok(
+        match try_commit_workspace_changes(&workspace, "Workspace snapshot")? {
+            Some(sha) => format!("Workspace history enabled at {}", &sha[..7]),
+            None => "Workspace history enabled".to_string(),
+        },
+    )
+}
+
+/// Newest-first workspace commits; empty when history is not enabled.
+#[command]
+fn get_workspace_log(
+    agent_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<WorkspaceCommit>, String> {
+    let workspace = workspace_dir(agent_id.as_deref())?;
+    if !workspace_has_repo(&workspace) {
+        return Ok(Vec::new());
+    }
+    let limit = limit
+        .unwrap_or(DEFAULT_WORKSPACE_LOG_LIMIT)
+        .max(1)
+        .to_string();
+    let output = workspace_git(
+        &workspace,
+        &["log", "-n", &limit, "--format=%H%x1f%at%x1f%s"],
+    )
+    .unwrap_or_default();
+    Ok(parse_workspace_log(&output))
+}
+
+/// Restores the workspace to its state at `sha` as a new commit, so the revert can itself be
+/// undone. Unsaved changes are committed first.
+#[command]
+fn revert_to_commit(agent_id: Option<String>, sha: String) -> Result<String, String> {
+    let _operation = lifecycle::try_begin_operation("revert_to_commit")?;
+    let sha = sha.trim();
+    if !is_commit_sha(sha) {
+        return Err(format!("Invalid commit: {}", sha));
+    }
+    let workspace = workspace_dir(agent_id.as_deref())?;
+    if !workspace_has_repo(&workspace) {
+        return Err("Workspace history is not enabled".to_string());
+    }
+    let target = workspace_git(&workspace, &["log", "-n", "1", "--format=%s", sha])
+        .map_err(|_| format!("Commit {} is not in the workspace history", sha))?;
+    try_commit_workspace_changes(&workspace, "Save changes before revert")?;
+    let source = format!("--source={}", sha);
+    workspace_git(
+        &workspace,
+        &["restore", &source, "--staged", "--worktree", "--", "."],
+    )?;
+    let message = format!("Revert to {}: {}", &sha[..7], target.trim());
+    try_commit_workspace_changes(&workspace, &message)?;
+    Ok(message)
+}
+
+// Cloud backup
+
+const CLOUD_BACKUP_FILE: &str = "cloud-backup.json";
+/// Paths under the root that make up a backup: config and workspaces, not sessions or logs.
+const OPENCLAW_ARCHIVE_SOURCES: &str =
+    "openclaw.json clawnetes-meta.json workspace agents/*/agent agents/*/workspace";
+const CLOUD_BACKUP_KEY_LABEL: &[u8] = b"clawnetes:cloud-backup:v1";
+const CLOUD_BACKUP_CHECK_SECS: u64 = 3600;
+const CLOUD_BACKUP_TIMEOUT_SECS: u64 = 600;
+
+fn default_cloud_backup_interval() -> u64 {
+    24
+}
+
+fn default_cloud_backup_keep() -> usize {
+    7
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+struct CloudBackupConfig {
+    target: backup::TargetConfig,
+    passphrase: String,
+    /// Hours between scheduled uploads; 0 uploads only on request.
+    #[serde(default = "default_cloud_backup_interval")]
+    interval_hours: u64,
+    /// Older backups beyond this many are deleted after each upload; 0 keeps all.
+    #[serde(default = "default_cloud_backup_keep")]
+    keep_last: usize,
+    #[serde(default)]
+    last_upload_at: Option<u64>,
+    #[serde(default)]
+    last_error: Option<String>,
+}
+
+/// `CloudBackupConfig` without the passphrase or target credentials.
+#[derive(serde::Serialize, Clone, Debug, Default)]
+struct CloudBackupStatus {
+    configured: bool,
+    kind: Option<String>,
+    url: Option<String>,
+    interval_hours: u64,
+    keep_last: usize,
+    last_upload_at: Option<u64>,
+    last_error: Option<String>,
+}
+
+impl From<&CloudBackupConfig> for CloudBackupStatus {
+    fn from(config: &CloudBackupConfig) -> Self {
+        let (kind, url) = match &config.target {
+            backup::TargetConfig::WebDav(target) => ("webdav", target.url.clone()),
+        };
+        CloudBackupStatus {
+            configured: true,
+            kind: Some(kind.to_string()),
+            url: Some(url),
+            interval_hours: config.interval_hours,
+            keep_last: config.keep_last,
+            last_upload_at: config.last_upload_at,
+            last_error: config.last_error.clone(),
+        }
+    }
+}
+
+/// The stored settings hold credentials, so they are sealed with a key tied to this machine.
+fn cloud_backup_storage_key() -> Result<[u8; 32], String> {
+    let mut hasher = Sha256::new();
+    hasher.update(CLOUD_BACKUP_KEY_LABEL);
+    hasher.update(get_machine_identifier()?.as_bytes());
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&hasher.finalize()[..32]);
+    Ok(key)
+}
+
+fn load_cloud_backup_config(app: &tauri::AppHandle) -> Option<CloudBackupConfig> {
+    let raw = fs::read_to_string(app_data_dir(app).ok()?.join(CLOUD_BACKUP_FILE)).ok()?;
+    let sealed = BASE64_STANDARD.decode(raw.trim()).ok()?;
+    let plain = backup::open(&cloud_backup_storage_key().ok()?, &sealed).ok()?;
+    serde_json::from_slice(&plain).ok()
+}
+
+fn save_cloud_backup_config(
+    app: &tauri::AppHandle,
+    config: &CloudBackupConfig,
+) -> Result<(), String> {
+    let dir = app_data_dir(app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let plain = serde_json::to_vec(config)
+        .map_err(|e| format!("Failed to serialize cloud backup settings: {}", e))?;
+    let sealed = backup::seal(&cloud_backup_storage_key()?, &plain)?;
+    fs::write(dir.join(CLOUD_BACKUP_FILE), BASE64_STANDARD.encode(sealed))
+        .map_err(|e| format!("Failed to save cloud backup settings: {}", e))
+}
+
+/// Gzipped tar of the config and every agent's workspace, without sessions or logs.
+fn create_openclaw_archive() -> Result<Vec<u8>, String> {
+    let root = paths::shell_root();
+    let cmd = format!(
+        "cd \"{}\" && ls -d {} 2>/dev/null | tar -czf - -T - | base64 | tr -d '\\n'",
+        root, OPENCLAW_ARCHIVE_SOURCES
+    );
+    let encoded =
+        process::shell_with_timeout(&cmd, Duration::from_secs(CLOUD_BACKUP_TIMEOUT_SECS))?
+            .into_result()?;
+    let archive = BASE64_STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("Failed to read backup archive: {}", e))?;
+    if archive.is_empty() {
+        return Err("Nothing to back up; OpenClaw is not configured yet.".to_string());
+    }
+    Ok(archive)
+}
+
+/// Extracts `archive` over the OpenClaw root after saving the current files to
+/// `backups/pre-restore-<time>.tar.gz`.
+fn restore_openclaw_archive(archive: &[u8]) -> Result<(), String> {
+    let staging = paths::local_path(".cloud-restore.b64")?;
+    write_local_text_file(&staging, &BASE64_STANDARD.encode(archive))?;
+    let root = paths::shell_root();
+    let cmd = format!(
+        "cd \"{root}\" && mkdir -p backups && (ls -d {sources} 2>/dev/null | tar -czf backups/pre-restore-{now}.tar.gz -T -) && base64 -d .cloud-restore.b64 | tar -xzf -; status=$?; rm -f .cloud-restore.b64; exit $status",
+        root = root,
+        sources = OPENCLAW_ARCHIVE_SOURCES,
+        now = unix_now_secs()
+    );
+    process::shell_with_timeout(&cmd, Duration::from_secs(CLOUD_BACKUP_TIMEOUT_SECS))?
+        .into_result()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to restore backup: {}", e))
+}
+
+/// Uploads a fresh encrypted archive and prunes old ones. Returns the new backup id.
+fn run_cloud_backup(config: &CloudBackupConfig) -> Result<String, String> {
+    let target = config.target.target();
+    target.prepare()?;
+    let sealed = backup::encrypt_archive(&config.passphrase, &create_openclaw_archive()?)?;
+    let id = backup::backup_id(unix_now_secs());
+    target.upload(&id, &sealed)?;
+    if config.keep_last > 0 {
+        for old in target.list()?.iter().skip(config.keep_last) {
+            if let Err(e) = target.delete(&old.id) {
+                eprintln!("{}", e);
+            }
+        }
+    }
+    Ok(id)
+}
+
+fn record_cloud_backup_result(app: &tauri::AppHandle, result: &Result<String, String>) {
+    if let Some(mut config) = load_cloud_backup_config(app) {
+        match result {
+            Ok(_) => {
+                config.last_upload_at = Some(unix_now_secs());
+                config.last_error = None;
+            }
+            Err(e) => config.last_error = Some(e.clone()),
+        }
+        if let Err(e) = save_cloud_backup_config(app, &config) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+/// Checks hourly whether a scheduled upload is due.
+fn start_cloud_backup_scheduler(app: tauri::AppHandle) {
+    if CLOUD_BACKUP_RUNNING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    lifecycle::spawn("cloud-backup", move || {
+        loop {
+            if let Some(config) = load_cloud_backup_config(&app) {
+                let due = config.interval_hours > 0
+                    && config.last_upload_at.is_none_or(|at| {
+                        unix_now_secs().saturating_sub(at) >= config.interval_hours * 3600
+                    });
+                if due {
+                    let result = run_cloud_backup(&config);
+                    if let Err(e) = &result {
+                        eprintln!("Scheduled cloud backup failed: {}", e);
+                    }
+                    record_cloud_backup_result(&app, &result);
+                }
+            }
+            if !lifecycle::sleep(Duration::from_secs(CLOUD_BACKUP_CHECK_SECS)) {
+                break;
+            }
+        }
+        CLOUD_BACKUP_RUNNING.store(false, Ordering::Relaxed);
+    });
+}
+
+/// Saves the cloud backup target after checking it is reachable. `None` turns backups off.
+#[command]
+async fn set_cloud_backup(
+    app: tauri::AppHandle,
+    target: Option<backup::TargetConfig>,
+    passphrase: Option<String>,
+    interval_hours: Option<u64>,
+    keep_last: Option<usize>,
+) -> Result<CloudBackupStatus, String> {
+    let Some(target) = target else {
+        let path = app_data_dir(&app)?.join(CLOUD_BACKUP_FILE);
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to clear cloud backup settings: {}", e))?;
+        }
+        return Ok(CloudBackupStatus::default());
+    };
+    let previous = load_cloud_backup_config(&app);
+    let passphrase = passphrase
+        .filter(|p| !p.is_empty())
+        .or_else(|| previous.as_ref().map(|c| c.passphrase.clone()))
+        .ok_or("A backup passphrase is required.")?;
+    if passphrase.chars().count() < 8 {
+        return Err("Use a backup passphrase of at least 8 characters.".to_string());
+    }
+    let check = target.clone();
+    tauri::async_runtime::spawn_blocking(move || check.target().prepare())
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let config = CloudBackupConfig {
+        target,
+        passphrase,
+        interval_hours: interval_hours.unwrap_or_else(default_cloud_backup_interval),
+        keep_last: keep_last.unwrap_or_else(default_cloud_backup_keep),
+        last_upload_at: previous.as_ref().and_then(|c| c.last_upload_at),
+        last_error: None,
+    };
+    save_cloud_backup_config(&app, &config)?;
+    start_cloud_backup_scheduler(app.clone());
+    Ok(CloudBackupStatus::from(&config))
+}
+
+#[command]
+fn get_cloud_backup(app: tauri::AppHandle) -> CloudBackupStatus {
+    load_cloud_backup_config(&app)
+        .map(|config| CloudBackupStatus::from(&config))
+        .unwrap_or_default()
+}
+
+/// Uploads a backup now. Returns the new backup id.
+#[command]
+async fn backup_to_remote(app: tauri::AppHandle) -> Result<String, String> {
+    let config = load_cloud_backup_config(&app).ok_or("Cloud backup is not set up.")?;
+    let result = tauri::async_runtime::spawn_blocking(move || run_cloud_backup(&config))
+        .await
+        .map_err(|e| e.to_string())?;
+    record_cloud_backup_result(&app, &result);
+    result
+}
+
+/// Backups on the configured target, newest first.
+#[command]
+async fn list_remote_backups(app: tauri::AppHandle) -> Result<Vec<backup::RemoteBackup>, String> {
+    let config = load_cloud_backup_config(&app).ok_or("Cloud backup is not set up.")?;
+    tauri::async_runtime::spawn_blocking(move || config.target.target().list())
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Downloads and decrypts backup `id` and restores it over the current setup. `passphrase`
+/// defaults to the saved one, so a new machine can restore after entering only the target
+/// and passphrase in `set_cloud_backup`.
+#[command]
+async fn restore_from_remote(
+    app: tauri::AppHandle,
+    id: String,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    let config = load_cloud_backup_config(&app).ok_or("Cloud backup is not set up.")?;
+    let passphrase = passphrase
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| config.passphrase.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        let sealed = config.target.target().download(&id)?;
+        restore_openclaw_archive(&backup::decrypt_archive(&passphrase, &sealed)?)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if let Err(e) = reload_gateway_config().await {
+        eprintln!("Restored backup; gateway reload failed: {}", e);
+    }
+    Ok(())
+}
+
+// Configuration export and import
+
+#[derive(serde::Serialize, Clone, Debug, Default)]
+struct ConfigTransferResult {
+    /// `None` when the file dialog was cancelled.
+    path: Option<String>,
+    /// Machine-specific fields left out of the export, or set for this machine on import.
+    machine_fields: Vec<String>,
+    /// Paths into another machine's home directory that the import rewrote.
+    path_substitutions: Vec<portable::PathSubstitution>,
+}
+
+/// Saves the config and Clawnetes metadata to a file the user picks, in the portable form
+/// of `portable::prepare_config_for_new_machine`, for setting up another machine.
+#[command]
+async fn export_configuration() -> Result<ConfigTransferResult, String> {
+    let config = read_local_openclaw_config()?;
+    let (portable, machine_fields) = portable::prepare_config_for_new_machine(
+        &config,
+        &paths::local_root()?,
+        &paths::local_home()?,
+    );
+    let meta = read_local_openclaw_file(META_FILE)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok());
+    let export = portable::ConfigExport::new(portable, meta, unix_now_secs());
+    let raw = serde_json::to_string_pretty(&export)
+        .map_err(|e| format!("Failed to serialize configuration: {}", e))?;
+    let file_name = format!("clawnetes-config-{}.json", unix_now_secs());
+    let Some(path) = tauri::api::dialog::blocking::FileDialogBuilder::new()
+        .set_title("Export configuration")
+        .set_file_name(&file_name)
+        .add_filter("Configuration", &["json"])
+        .save_file()
+    else {
+        return Ok(ConfigTransferResult::default());
+    };
+    fs::write(&path, raw).map_err(|e| format!("Failed to save configuration: {}", e))?;
+    Ok(ConfigTransferResult {
+        path: Some(path.to_string_lossy().to_string()),
+        machine_fields,
+        ..Default::default()
+    })
+}
+
+/// Replaces the local config with one from `export_configuration` or a plain openclaw.json,
+/// keeping this machine's gateway token and bookkeeping (or generating a token on a fresh
+/// install) and moving paths from the other machine's home to this one, then reloads the
+/// gateway.
+#[command]
+async fn import_configuration() -> Result<ConfigTransferResult, String> {
+    let Some(path) = tauri::api::dialog::blocking::FileDialogBuilder::new()
+        .set_title("Import configuration")
+        .add_filter("Configuration", &["json"])
+        .pick_file()
+    else {
+        return Ok(ConfigTransferResult::default());
+    };
+    let raw = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let export = portable::parse_export(&raw)?;
+    // Only queue behind other operations once there is something to import.
+    let _operation = lifecycle::begin_operation("import_configuration").await;
+    let (root, home) = (paths::local_root()?, paths::local_home()?);
+    let (machine_fields, path_substitutions) = {
+        let _config = lifecycle::config_lock();
+        let local = read_local_openclaw_config().ok();
+        let (mut config, mut machine_fields) =
+            portable::adopt_config(&export.config, &root, &home, local.as_ref());
+        let path_substitutions = portable::translate_paths(&mut config, &root, &home);
+        if config
+            .pointer("/gateway/auth/mode")
+            .and_then(|m| m.as_str())
+            == Some("token")
+            && config.pointer("/gateway/auth/token").is_none()
+        {
+            config_sections::update(
+                &mut config,
+                "gateway.auth.token",
+                serde_json::json!(generate_gateway_token()),
+            )?;
+            machine_fields.push("gateway.auth.token".to_string());
+        }
+        store_local_openclaw_config(&config)?;
+        (machine_fields, path_substitutions)
+    };
+    if let Some(meta) = &export.meta {
+        let raw = serde_json::to_string_pretty(meta).map_err(|e| e.to_string())?;
+        write_local_openclaw_file(META_FILE, &raw)?;
+    }
+    if let Err(e) = reload_gateway_config().await {
+        eprintln!("Imported configuration; gateway reload failed: {}", e);
+    }
+    Ok(ConfigTransferResult {
+        path: Some(path.to_string_lossy().to_string()),
+        machine_fields,
+        path_substitutions,
+    })
+}
+
+// Scheduled local backups
+
+const BACKUP_SCHEDULE_FILE: &str = "backup-schedule.json";
+const LOCAL_BACKUP_EXTENSION: &str = ".tar.gz";
+const BACKUP_SCHEDULE_CHECK_SECS: u64 = 3600;
+/// One hour to 30 days.
+const BACKUP_INTERVAL_HOURS: std::ops::RangeInclusive<u64> = 1..=720;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+struct BackupSchedule {
+    enabled: bool,
+    interval_hours: u64,
+    /// Newest archive of each of this many recent days is kept.
+    keep_daily: usize,
+    /// Newest archive of each of this many recent weeks is kept.
+    keep_weekly: usize,
+    #[serde(default)]
+    last_backup_at: Option<u64>,
+    #[serde(default)]
+    last_error: Option<String>,
+}
+
+impl Default for BackupSchedule {
+    fn default() -> Self {
+        BackupSchedule {
+            enabled: false,
+            interval_hours: 24,
+            keep_daily: 7,
+            keep_weekly: 4,
+            last_backup_at: None,
+            last_error: None,
+        }
+    }
+}
+
+fn backup_schedule_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_data_dir(app)?.join(BACKUP_SCHEDULE_FILE))
+}
+
+fn load_backup_schedule(app: &tauri::AppHandle) -> BackupSchedule {
+    backup_schedule_path(app)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_backup_schedule(app: &tauri::AppHandle, schedule: &BackupSchedule) -> Result<(), String> {
+    let path = backup_schedule_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let raw = serde_json::to_string_pretty(schedule)
+        .map_err(|e| format!("Failed to serialize backup schedule: {}", e))?;
+    fs::write(&path, raw).map_err(|e| format!("Failed to save backup schedule: {}", e))
+}
+
+/// Writes a new archive to `backups/` under the root and applies the retention rules.
+/// Returns the archive name.
+fn create_local_backup(schedule: &BackupSchedule) -> Result<String, String> {
+    let root = paths::shell_root();
+    let name = backup::archive_name(unix_now_secs(), LOCAL_BACKUP_EXTENSION);
+    let cmd = format!(
+        "cd \"{}\" && test -f openclaw.json && mkdir -p backups && ls -d {} 2>/dev/null | tar -czf backups/{} -T -",
+        root, OPENCLAW_ARCHIVE_SOURCES, name
+    );
+    process::shell_with_timeout(&cmd, Duration::from_secs(CLOUD_BACKUP_TIMEOUT_SECS))?
+        .into_result()
+        .map_err(|e| format!("Failed to create backup: {}", e))?;
+
+    let dir = paths::local_path("backups")?;
+    let names = list_local_dir(&dir)?;
+    for old in backup::archives_to_prune(&names, schedule.keep_daily, schedule.keep_weekly) {
+        if let Err(e) = remove_local_file(&format!("{}/{}", dir, old)) {
+            eprintln!("Failed to remove old backup {}: {}", old, e);
+        }
+    }
+    Ok(name)
+}
+
+/// Checks hourly whether a scheduled local backup is due; runs only while the app is open.
+fn start_backup_scheduler(app: tauri::AppHandle) {
+    if BACKUP_SCHEDULER_RUNNING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    lifecycle::spawn("backup-scheduler", move || {
+        loop {
+            let mut schedule = load_backup_schedule(&app);
+            let due = schedule.enabled
+                && schedule.last_backup_at.is_none_or(|at| {
+                    unix_now_secs().saturating_sub(at)
+                        >= schedule.interval_hours.saturating_mul(3600)
+                });
+            if due {
+                match create_local_backup(&schedule) {
+                    Ok(_) => {
+                        schedule.last_backup_at = Some(unix_now_secs());
+                        schedule.last_error = None;
+                    }
+                    Err(e) => {
+                        eprintln!("Scheduled backup failed: {}", e);
+                        schedule.last_error = Some(e);
+                    }
+                }
+                if let Err(e) = save_backup_schedule(&app, &schedule) {
+                    eprintln!("{}", e);
+                }
+            }
+            if !lifecycle::sleep(Duration::from_secs(BACKUP_SCHEDULE_CHECK_SECS)) {
+                break;
+            }
+        }
+        BACKUP_SCHEDULER_RUNNING.store(false, Ordering::Relaxed);
+    });
+}
+
+#[command]
+fn get_backup_schedule(app: tauri::AppHandle) -> BackupSchedule {
+    load_backup_schedule(&app)
+}
+
+/// Updates the schedule; omitted fields keep their current values.
+#[command]
+fn set_backup_schedule(
+    app: tauri::AppHandle,
+    enabled: bool,
+    interval_hours: Option<u64>,
+    keep_daily: Option<usize>,
+    keep_weekly: Option<usize>,
+) -> Result<BackupSchedule, String> {
+    let mut schedule = load_backup_schedule(&app);
+    schedule.enabled = enabled;
+    if let Some(hours) = interval_hours {
+        if !BACKUP_INTERVAL_HOURS.contains(&hours) {
+            return Err(format!(
+                "The backup interval must be between {} and {} hours.",
+                BACKUP_INTERVAL_HOURS.start(),
+                BACKUP_INTERVAL_HOURS.end()
+            ));
+        }
+        schedule.interval_hours = hours;
+    }
+    schedule.keep_daily = keep_daily.unwrap_or(schedule.keep_daily);
+    schedule.keep_weekly = keep_weekly.unwrap_or(schedule.keep_weekly);
+    if schedule.keep_daily == 0 && schedule.keep_weekly == 0 {
+        return Err("Keep at least one daily or weekly backup.".to_string());
+    }
+    save_backup_schedule(&app, &schedule)?;
+    if enabled {
+        start_backup_scheduler(app);
+    }
+    Ok(schedule)
+}
+
+// Activity feed
+
+const ACTIVITY_DAYS: u32 = 7;
+const ACTIVITY_TRANSCRIPT_LINES: usize = 400;
+const ACTIVITY_LOG_LINES: usize = 2000;
+const ACTIVITY_DEFAULT_LIMIT: usize = 50;
+
+/// Events from every agent's transcripts written in the last `ACTIVITY_DAYS` days.
+fn collect_transcript_activity() -> Result<Vec<activity::ActivityEvent>, String> {
+    let output = shell_command(&format!(
+        "cd \"{root}/agents\" 2>/dev/null || exit 0; \
+         for f in */sessions/sessions.json; do [ -f \"$f\" ] && printf '==> %s <==\\n' \"$f\" && cat \"$f\" && echo; done; \
+         find */sessions -maxdepth 1 -name '*.jsonl' -mtime -{days} 2>/dev/null | while read -r f; do \
+         printf '==> %s <==\\n' \"$f\"; tail -n {lines} \"$f\"; echo; done; true",
+        root = paths::shell_root(),
+        days = ACTIVITY_DAYS,
+        lines = ACTIVITY_TRANSCRIPT_LINES
+    ))?;
+    let files = activity::split_files(&output);
+
+    let mut channels: std::collections::HashMap<(String, String), String> =
+        std::collections::HashMap::new();
+    for (path, content) in files.iter().filter(|(p, _)| p.ends_with("/sessions.json")) {
+        let agent = path.split('/').next().unwrap_or_default();
+        let store: serde_json::Value = serde_json::from_str(content).unwrap_or_default();
+        for session in parse_session_list(&store) {
+            if let Some(channel) = session.channel {
+                channels.insert((agent.to_string(), session.session_id), channel);
+            }
+        }
+    }
+
+    let mut events = Vec::new();
+    for (path, content) in &files {
+        let (Some(agent), Some(session_id)) = (
+            path.split('/').next(),
+            path.rsplit('/')
+                .next()
+                .and_then(|f| f.strip_suffix(".jsonl")),
+        ) else {
+            continue;
+        };
+        let channel = channels.get(&(agent.to_string(), session_id.to_string()));
+        events.extend(activity::transcript_events(
+            agent,
+            session_id,
+            channel.map(String::as_str),
+            content,
+        ));
+    }
+    Ok(events)
+}
+
+/// Task runs and errors from the newest gateway log.
+fn collect_log_activity() -> Vec<activity::ActivityEvent> {
+    let Some(current) = list_log_files()
+        .ok()
+        .and_then(|files| files.into_iter().max_by_key(|f| f.modified))
+    else {
+        return Vec::new();
+    };
+    let log = shell_command(&format!(
+        "tail -n {} {}",
+        ACTIVITY_LOG_LINES,
+        shell_single_quote(&current.path)
+    ))
+    .unwrap_or_default();
+    activity::log_events(&log)
+}
+
+/// What the agents have done recently, newest first: messages handled, tool calls, scheduled
+/// task runs and errors. Pass the returned `next_cursor` to page back in time.
+#[command]
+fn get_activity_feed(
+    limit: Option<usize>,
+    cursor: Option<String>,
+) -> Result<activity::ActivityPage, String> {
+    let mut events = collect_transcript_activity()?;
+    events.extend(collect_log_activity());
+    Ok(activity::page(
+        events,
+        limit.unwrap_or(ACTIVITY_DEFAULT_LIMIT).clamp(1, 500),
+        cursor.as_deref(),
+    ))
+}
+
+// Pause agent
+
+const META_FILE: &str = "clawnetes-meta.json";
+
+/// What `pause_agent` switched off, kept in `clawnetes-meta.json` under `paused` so
+/// `resume_agent` restores exactly that.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+struct AgentPauseState {
+    since: u64,
+    channels: Vec<String>,
+    /// Previous `agents.defaults.heartbeat`, if any.
+    heartbeat: Option<serde_json::Value>,
+    /// Previous top-level `cron`, if any.
+    cron: Option<serde_json::Value>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Default)]
+struct AgentPauseStatus {
+    paused: bool,
+    since: Option<u64>,
+    channels: Vec<String>,
+}
+
+impl From<Option<AgentPauseState>> for AgentPauseStatus {
+    fn from(state: Option<AgentPauseState>) -> Self {
+        match state {
+            Some(state) => AgentPauseStatus {
+                paused: true,
+                since: Some(state.since),
+                channels: state.channels,
+            },
+            None => AgentPauseStatus::default(),
+        }
+    }
+}
+
+/// Disables every enabled channel, the heartbeat and cron in `config`. The gateway itself
+/// and its dashboard stay up.
+fn pause_agent_in_config(config: &mut serde_json::Value, now: u64) -> AgentPauseState {
+    let channels: Vec<String> = config
+        .get("channels")
+        .and_then(|c| c.as_object())
+        .map(|channels| {
+            channels
+                .iter()
+                .filter(|(_, entry)| entry.get("enabled").and_then(|v| v.as_bool()) != Some(false))
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    for channel in &channels {
+        let _ = set_channel_enabled_in_config(config, channel, false);
+    }
+    let heartbeat = config.pointer("/agents/defaults/heartbeat").cloned();
+    apply_heartbeat_config(
+        config,
+        &HeartbeatSettings {
+            enabled: false,
+            ..Default::default()
+        },
+    );
+    let cron = config.get("cron").cloned();
+    if let Some(root) = config.as_object_mut() {
+        root.insert("cron".to_string(), serde_json::json!({ "enabled": false }));
+    }
+    AgentPauseState {
+        since: now,
+        channels,
+        heartbeat,
+        cron,
+    }
+}
+
+fn resume_agent_in_config(config: &mut serde_json::Value, state: &AgentPauseState) {
+    for channel in &state.channels {
+        let _ = set_channel_enabled_in_config(config, channel, true);
+    }
+    if let Some(defaults) = config
+        .pointer_mut("/agents/defaults")
+        .and_then(|d| d.as_object_mut())
+    {
+        match &state.heartbeat {
+            Some(heartbeat) => defaults.insert("heartbeat".to_string(), heartbeat.clone()),
+            None => defaults.remove("heartbeat"),
+        };
+    }
+    if let Some(root) = config.as_object_mut() {
+        match &state.cron {
+            Some(cron) => root.insert("cron".to_string(), cron.clone()),
+            None => root.remove("cron"),
+        };
+    }
+}
+
+fn read_clawnetes_meta() -> serde_json::Value {
+    read_local_openclaw_file(META_FILE)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .filter(|meta: &serde_json::Value| meta.is_object())
+        .unwrap_or_else(|| serde_json::json!({}))
+}
+
+fn read_agent_pause_state() -> Option<AgentPauseState> {
+    serde_json::from_value(read_clawnetes_meta().get("paused")?.clone()).ok()
+}
+
+fn write_agent_pause_state(state: Option<&AgentPauseState>) -> Result<(), String> {
+    let mut meta = read_clawnetes_meta();
+    if let Some(meta) = meta.as_object_mut() {
+        match state {
+            Some(state) => meta.insert(
+                "paused".to_string(),
+                serde_json::to_value(state).map_err(|e| e.to_string())?,
+            ),
+            None => meta.remove("paused"),
+        };
+    }
+    let raw = serde_json::to_string_pretty(&meta).map_err(|e| e.to_string())?;
+    write_local_openclaw_file(META_FILE, &raw)
+}
+
+#[command]
+fn get_agent_pause_status() -> AgentPauseStatus {
+    AgentPauseStatus::from(read_agent_pause_state())
+}
+
+/// Stops the agent acting on anything (channels, heartbeat, cron) without stopping the
+/// gateway, so the dashboard stays reachable.
+#[command]
+async fn pause_agent() -> Result<AgentPauseStatus, String> {
+    let (state, paused) = update_local_openclaw_config(|config| {
+        if let Some(state) = read_agent_pause_state() {
+            return Ok((state, false));
+        }
+        let state = pause_agent_in_config(config, unix_now_secs());
+        // Saved first: if the config write fails, resume still knows what to restore.
+        write_agent_pause_state(Some(&state))?;
+        Ok((state, true))
+    })?;
+    if paused {
+        reload_gateway_config().await?;
+    }
+    Ok(AgentPauseStatus::from(Some(state)))
+}
+
+#[command]
+async fn resume_agent() -> Result<AgentPauseStatus, String> {
+    let resumed = update_local_openclaw_config(|config| {
+        let Some(state) = read_agent_pause_state() else {
+            return Ok(false);
+        };
+        resume_agent_in_config(config, &state);
+        Ok(true)
+    })?;
+    if resumed {
+        write_agent_pause_state(None)?;
+        reload_gateway_config().await?;
+    }
+    Ok(AgentPauseStatus::default())
+}
+
+// Safe mode
+
+/// The normal config, set aside while the gateway runs in safe mode.
+const SAFE_MODE_BACKUP_FILE: &str = "openclaw.safe-mode-backup.json";
+
+/// `config` with every channel, plugin, heartbeat and cron job off and tools limited to the
+/// minimal profile with no command execution.
+fn safe_mode_config(config: &serde_json::Value) -> serde_json::Value {
+    let mut safe = config.clone();
+    pause_agent_in_config(&mut safe, 0);
+    if !safe.is_object() {
+        safe = serde_json::json!({});
+    }
+    let root = safe.as_object_mut().unwrap();
+    let plugins = object_entry(root, "plugins");
+    plugins.insert("enabled".to_string(), serde_json::json!(false));
+    if let Some(entries) = plugins.get_mut("entries").and_then(|e| e.as_object_mut()) {
+        for entry in entries.values_mut().filter_map(|e| e.as_object_mut()) {
+            entry.insert("enabled".to_string(), serde_json::json!(false));
+        }
+    }
+    let tools = object_entry(root, "tools");
+    tools.insert("profile".to_string(), serde_json::json!("minimal"));
+    object_entry(tools, "exec").insert("security".to_string(), serde_json::json!("deny"));
+    object_entry(tools, "elevated").insert("enabled".to_string(), serde_json::json!(false));
+    safe
+}
+
+fn safe_mode_active() -> bool {
+    read_local_openclaw_file(SAFE_MODE_BACKUP_FILE).is_ok()
+}
+
+#[command]
+fn get_safe_mode_status() -> bool {
+    safe_mode_active()
+}
+
+/// Restarts the gateway with channels, plugins and tool execution disabled, for debugging a
+/// setup that will not start normally. `exit_safe_mode` puts the normal config back.
+#[command]
+fn start_gateway_safe_mode(
+    app: tauri::AppHandle,
+    task_id: Option<String>,
+) -> Result<String, String> {
+    let progress = progress::Progress::new(&app, "gateway", task_id);
+    let _operation = lifecycle::try_begin_operation("start_gateway_safe_mode")?;
+    {
+        let _config = lifecycle::config_lock();
+        if !safe_mode_active() {
+            let original = read_local_openclaw_file("openclaw.json")?;
+            write_local_openclaw_file(SAFE_MODE_BACKUP_FILE, &original)?;
+        }
+        let original = read_local_openclaw_file(SAFE_MODE_BACKUP_FILE)?;
+        let config: serde_json::Value = serde_json::from_str(&original)
+            .map_err(|e| format!("Failed to parse openclaw.json: {}", e))?;
+        store_local_openclaw_config(&safe_mode_config(&config))?;
+    }
+    let message = start_local_gateway(&progress)?;
+    notify_gateway_restarted(&app);
+    Ok(format!("Safe mode: {}", message))
+}
+
+#[command]
+fn exit_safe_mode(app: tauri::AppHandle, task_id: Option<String>) -> Result<String, String> {
+    let progress = progress::Progress::new(&app, "gateway", task_id);
+    let _operation = lifecycle::try_begin_operation("exit_safe_mode")?;
+    let original = read_local_openclaw_file(SAFE_MODE_BACKUP_FILE)
+        .map_err(|_| "The gateway is not in safe mode.".to_string())?;
+    write_local_openclaw_file("openclaw.json", &original)?;
+    remove_local_file(&paths::local_path(SAFE_MODE_BACKUP_FILE)?)?;
+    let message = start_local_gateway(&progress)?;
+    notify_gateway_restarted(&app);
+    Ok(message)
+}
+
+// Config migrations
+
+#[derive(serde::Serialize, Clone, Debug, Default)]
+struct MigrationReport {
+    /// `meta.lastTouchedVersion` before migrating.
+    from_version: Option<String>,
+    installed_version: Option<String>,
+    needed: bool,
+    steps: Vec<migrations::AppliedMigration>,
+    /// Copy of `openclaw.json` taken before `run_migrations` changed it.
+    backup_path: Option<String>,
+}
+
+fn installed_openclaw_version() -> Option<String> {
+    shell_command("openclaw --version")
+        .ok()
+        .filter(|v| migrations::parse_version(v).is_some())
+        .map(|v| v.trim().to_string())
+}
+
+fn migration_report(
+    config: &serde_json::Value,
+    installed: Option<String>,
+) -> (serde_json::Value, MigrationReport) {
+    let mut migrated = config.clone();
+    let steps = migrations::migrate(&mut migrated, installed.as_deref());
+    let report = MigrationReport {
+        from_version: migrations::config_version(config),
+        installed_version: installed,
+        needed: !steps.is_empty(),
+        steps,
+        backup_path: None,
+    };
+    (migrated, report)
+}
+
+/// Lists the schema migrations `run_migrations` would apply, without changing anything.
+#[command]
+fn check_migrations_needed() -> Result<MigrationReport, String> {
+    let config = read_local_openclaw_config()?;
+    Ok(migration_report(&config, installed_openclaw_version()).1)
+}
+
+/// Backs up `openclaw.json` to `backups/`, applies the pending migrations and reloads the
+/// gateway. Reports every change made.
+#[command]
+async fn run_migrations() -> Result<MigrationReport, String> {
+    let report = update_local_openclaw_config(|config| {
+        let (migrated, mut report) = migration_report(config, installed_openclaw_version());
+        if !report.needed {
+            return Ok(report);
+        }
+        let backup = format!("backups/openclaw.pre-migration-{}.json", unix_now_secs());
+        shell_command(&format!(
+            "mkdir -p \"{root}/backups\" && cp \"{root}/openclaw.json\" \"{root}/{backup}\"",
+            root = paths::shell_root(),
+            backup = backup
+        ))
+        .map_err(|e| format!("Failed to back up openclaw.json: {}", e))?;
+        report.backup_path = Some(paths::local_path(&backup)?);
+        *config = migrated;
+        Ok(report)
+    })?;
+    if !report.needed {
+        return Ok(report);
+    }
+    if let Err(e) = reload_gateway_config().await {
+        eprintln!("Migrated config; gateway reload failed: {}", e);
+    }
+    Ok(report)
+}
+
+// Gateway service status
+
+const SERVICE_STDERR_LINES: usize = 40;
+const SERVICE_QUERY_TIMEOUT_SECS: u64 = 10;
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct GatewayServiceStatus {
+    /// `launchd` or `systemd`.
+    manager: String,
+    definition_path: Option<String>,
+    #[serde(flatten)]
+    service: service_status::ServiceState,
+    /// The service's own recent stderr (launchd) or journal (systemd).
+    stderr_tail: Option<String>,
+    /// What `openclaw gateway status` reports, for comparison.
+    cli_status: Option<String>,
+}
+
+/// Stdout of `cmd` whatever its exit code; the managers exit non-zero for unknown services.
+fn service_query(cmd: &str) -> String {
+    process::shell_with_timeout(cmd, Duration::from_secs(SERVICE_QUERY_TIMEOUT_SECS))
+        .map(|output| output.stdout)
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "macos")]
+fn query_gateway_service(
+    installed: bool,
+) -> (String, service_status::ServiceState, Option<String>) {
+    let printed = service_query(&format!(
+        "launchctl print gui/$(id -u)/{} 2>/dev/null",
+        service_status::LAUNCHD_LABEL
+    ));
+    let service = match service_status::parse_launchctl_print(&printed) {
+        Some(service) => service,
+        None => service_status::ServiceState {
+            state: if installed {
+                "not_loaded"
+            } else {
+                "not_installed"
+            }
+            .to_string(),
+            ..Default::default()
+        },
+    };
+    let stderr = service.stderr_path.as_ref().map(|path| {
+        service_query(&format!(
+            "tail -n {} {} 2>/dev/null",
+            SERVICE_STDERR_LINES,
+            shell_single_quote(path)
+        ))
+    });
+    ("launchd".to_string(), service, stderr)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn query_gateway_service(
+    installed: bool,
+) -> (String, service_status::ServiceState, Option<String>) {
+    let shown = service_query(&format!(
+        "systemctl --user show {} -p LoadState,ActiveState,SubState,MainPID,ExecMainStatus,NRestarts 2>/dev/null",
+        service_status::SYSTEMD_UNIT
+    ));
+    let mut service = service_status::parse_systemctl_show(&shown);
+    if installed && service.state == "not_installed" {
+        // The unit file exists but systemd has not loaded it (no daemon-reload yet).
+        service.state = "not_loaded".to_string();
+    }
+    let journal = service_query(&format!(
+        "journalctl --user -u {} -n {} --no-pager -o cat 2>/dev/null",
+        service_status::SYSTEMD_UNIT,
+        SERVICE_STDERR_LINES
+    ));
+    ("systemd".to_string(), service, Some(journal))
+}
+
+/// Gateway service state from launchd or systemd rather than the CLI: not installed,
+/// installed but not loaded, running, crash-looping or stopped, with the service's own
+/// recent error output.
+#[command]
+fn get_gateway_service_status() -> Result<GatewayServiceStatus, String> {
+    let definition_path = local_gateway_service_path().ok();
+    let installed = definition_path
+        .as_deref()
+        .and_then(read_local_text_file)
+        .is_some();
+    let (manager, service, stderr_tail) = query_gateway_service(installed);
+    Ok(GatewayServiceStatus {
+        manager,
+        definition_path,
+        service,
+        stderr_tail: stderr_tail.filter(|s| !s.trim().is_empty()),
+        cli_status: shell_command("openclaw gateway status")
+            .ok()
+            .map(|s| s.trim().to_string()),
+    })
+}
+
+// Log export
+
+#[derive(serde::Serialize, Clone, Debug, Default)]
+struct LogExportResult {
+    /// Where the export was saved; `None` if the save dialog was cancelled.
+    path: Option<String>,
+    lines: usize,
+    redactions: usize,
+}
+
+/// Every gateway log, oldest file first, as one text.
+fn read_all_gateway_logs() -> Result<String, String> {
+    let mut files = list_log_files()?;
+    files.sort_by_key(|f| f.modified);
+    let mut log = String::new();
+    for file in files {
+        log.push_str(&shell_command(&format!(
+            "cat {}",
+            shell_single_quote(&file.path)
+        ))?);
+        if !log.ends_with('\n') {
+            log.push('\n');
+        }
+    }
+    Ok(log)
+}
+
+/// Writes the gateway log lines within `range` (and from `components`, if given) to a file
+/// the user picks, as `text` or `jsonl`, with tokens and keys redacted. Lets users share one
+/// incident window instead of the whole gateway.log.
+#[command]
+async fn export_logs(
+    range: Option<log_export::LogFilter>,
+    components: Option<Vec<String>>,
+    format: Option<String>,
+) -> Result<LogExportResult, String> {
+    let mut filter = range.unwrap_or_default();
+    filter.components = components.unwrap_or_default();
+    let format = format.unwrap_or_else(|| "text".to_string());
+    let extension = if format == "jsonl" { "jsonl" } else { "log" };
+    let (content, lines, redactions) = tauri::async_runtime::spawn_blocking(move || {
+        log_export::export(&read_all_gateway_logs()?, &filter, &format)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if lines == 0 {
+        return Err("No log lines match the selected time range and components.".to_string());
+    }
+    let file_name = format!("openclaw-logs-{}.{}", unix_now_secs(), extension);
+    let Some(path) = tauri::api::dialog::blocking::FileDialogBuilder::new()
+        .set_title("Export logs")
+        .set_file_name(&file_name)
+        .add_filter("Logs", &[extension])
+        .save_file()
+    else {
+        return Ok(LogExportResult::default());
+    };
+    fs::write(&path, content).map_err(|e| format!("Failed to save logs: {}", e))?;
+    Ok(LogExportResult {
+        path: Some(path.to_string_lossy().to_string()),
+        lines,
+        redactions,
+    })
+}
+
+// App terminal
+
+const TERMINAL_OUTPUT_EVENT: &str = "terminal-output";
+const TERMINAL_READ_CHUNK: usize = 4096;
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct TerminalOutput {
+    session_id: String,
+    data: String,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct TerminalOpened {
+    session_id: String,
+    /// Greeting and first prompt, to write before listening for `terminal-output`.
+    output: String,
+}
+
+struct TerminalCommand {
+    child: Box<dyn portable_pty::Child + Send + Sync>,
+    /// Kept for resizes; the command's output is read from a clone.
+    pty: Box<dyn portable_pty::MasterPty + Send>,
+    stdin: Option<Box<dyn Write + Send>>,
+}
+
+#[derive(Default)]
+struct TerminalSession {
+    cols: u16,
+    rows: u16,
+    editor: terminal::LineEditor,
+    running: Option<TerminalCommand>,
+}
+
+fn emit_terminal_output(app: &tauri::AppHandle, session_id: &str, data: &str) {
+    let _ = app.emit_all(
+        TERMINAL_OUTPUT_EVENT,
+        TerminalOutput {
+            session_id: session_id.to_string(),
+            data: data.to_string(),
+        },
+    );
+}
+
+/// Emits everything `reader` produces, holding back split UTF-8 sequences between reads.
+fn stream_terminal_output(app: &tauri::AppHandle, session_id: &str, mut reader: impl Read) {
+    let mut pending = Vec::new();
+    let mut buf = [0u8; TERMINAL_READ_CHUNK];
+    while let Ok(n) = reader.read(&mut buf) {
+        if n == 0 {
+            break;
+        }
+        pending.extend_from_slice(&buf[..n]);
+        let valid = match std::str::from_utf8(&pending) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => pending.len(),
+        };
+        let text = String::from_utf8_lossy(&pending[..valid]).to_string();
+        pending.drain(..valid);
+        emit_terminal_output(app, session_id, &text);
+    }
+    if !pending.is_empty() {
+        emit_terminal_output(app, session_id, &String::from_utf8_lossy(&pending));
+    }
+}
+
+/// Reaps the session's finished command and shows the prompt again.
+fn finish_terminal_command(app: &tauri::AppHandle, session_id: &str) {
+    let running = TERMINAL_SESSIONS
+        .lock()
+        .ok()
+        .and_then(|mut sessions| sessions.get_mut(session_id)?.running.take());
+    let Some(mut running) = running else {
+        return;
+    };
+    drop(running.stdin.take());
+    let status = match running.child.wait() {
+        Ok(status) if status.success() => String::new(),
+        Ok(status) => format!("[exit {}]\r\n", status.exit_code()),
+        Err(_) => "[stopped]\r\n".to_string(),
+    };
+    emit_terminal_output(app, session_id, &format!("{}{}", status, terminal::PROMPT));
+}
+
+/// Starts the allow-listed `openclaw` command on `line` in a pseudo-terminal of the session's
+/// size and streams its output.
+fn start_terminal_command(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    session: &TerminalSession,
+    line: &str,
+) -> Result<TerminalCommand, String> {
+    let args = terminal::check_command(line)?;
+    let command = process::program_process("openclaw", &args)?;
+    let pair = portable_pty::native_pty_system()
+        .openpty(terminal_size(session))
+        .map_err(|e| format!("Failed to open a terminal: {}", e))?;
+    let child = pair
+        .slave
+        .spawn_command(terminal::pty_command(&command))
+        .map_err(|e| format!("Failed to start openclaw: {}", e))?;
+    // Output ends once the command, the only holder of the slave side, exits.
+    drop(pair.slave);
+    let output = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| format!("Failed to read from the terminal: {}", e))?;
+    let stdin = pair
+        .master
+        .take_writer()
+        .map_err(|e| format!("Failed to write to the terminal: {}", e))?;
+    let (app, session_id) = (app.clone(), session_id.to_string());
+    thread::spawn(move || {
+        stream_terminal_output(&app, &session_id, output);
+        finish_terminal_command(&app, &session_id);
+    });
+    Ok(TerminalCommand {
+        child,
+        pty: pair.master,
+        stdin: Some(stdin),
+    })
+}
+
+fn terminal_size(session: &TerminalSession) -> portable_pty::PtySize {
+    portable_pty::PtySize {
+        rows: session.rows,
+        cols: session.cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+/// Opens a terminal session for `openclaw` commands. Output arrives as `terminal-output`
+/// events carrying the returned `session_id`.
+#[command]
+fn open_terminal(cols: Option<u16>, rows: Option<u16>) -> Result<TerminalOpened, String> {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let session = TerminalSession {
+        cols: cols.unwrap_or(100).max(20),
+        rows: rows.unwrap_or(30).max(5),
+        ..Default::default()
+    };
+    TERMINAL_SESSIONS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(session_id.clone(), session);
+    let output = format!(
+        "OpenClaw terminal. Available commands: {}\r\n{}",
+        terminal::ALLOWED_COMMANDS.join(", "),
+        terminal::PROMPT
+    );
+    Ok(TerminalOpened { session_id, output })
+}
+
+/// Keystrokes from the terminal view: passed to the running command, or edited into the next
+/// command line when none is running.
+#[command]
+fn terminal_input(app: tauri::AppHandle, session_id: String, data: String) -> Result<(), String> {
+    let mut sessions = TERMINAL_SESSIONS.lock().map_err(|e| e.to_string())?;
+    let session = sessions
+        .get_mut(&session_id)
+        .ok_or("This terminal session has been closed.")?;
+    if let Some(running) = &mut session.running {
+        if let Some(stdin) = &mut running.stdin {
+            stdin
+                .write_all(data.as_bytes())
+                .and_then(|_| stdin.flush())
+                .map_err(|e| format!("Failed to send input: {}", e))?;
+        }
+        return Ok(());
+    }
+    let mut events = session.editor.feed(&data).into_iter();
+    while let Some(event) = events.next() {
+        match event {
+            terminal::EditorEvent::Echo(text) => emit_terminal_output(&app, &session_id, &text),
+            terminal::EditorEvent::Line(line) if line.trim().is_empty() => {
+                emit_terminal_output(&app, &session_id, terminal::PROMPT)
+            }
+            terminal::EditorEvent::Line(line) => {
+                match start_terminal_command(&app, &session_id, session, &line) {
+                    Ok(command) => session.running = Some(command),
+                    Err(e) => {
+                        emit_terminal_output(
+                            &app,
+                            &session_id,
+                            &format!("{}\r\n{}", e, terminal::PROMPT),
+                        );
+                        continue;
+                    }
+                }
+                // Anything typed after Enter belongs to the command that just started.
+                let mut rest: String = events
+                    .by_ref()
+                    .filter_map(|e| match e {
+                        terminal::EditorEvent::Line(line) => Some(line + "\n"),
+                        terminal::EditorEvent::Echo(_) => None,
+                    })
+                    .collect();
+                rest.push_str(&session.editor.take_pending());
+                if let Some(stdin) = session.running.as_mut().and_then(|r| r.stdin.as_mut()) {
+                    let _ = stdin.write_all(rest.as_bytes());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Resizes the session; a running command sees the new size straight away.
+#[command]
+fn resize_terminal(session_id: String, cols: u16, rows: u16) -> Result<(), String> {
+    let mut sessions = TERMINAL_SESSIONS.lock().map_err(|e| e.to_string())?;
+    let session = sessions
+        .get_mut(&session_id)
+        .ok_or("This terminal session has been closed.")?;
+    session.cols = cols.max(20);
+    session.rows = rows.max(5);
+    if let Some(running) = &session.running {
+        running
+            .pty
+            .resize(terminal_size(session))
+            .map_err(|e| format!("Failed to resize the terminal: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Ends the session, stopping its command if one is running.
+#[command]
+fn close_terminal(session_id: String) -> Result<(), String> {
+    let session = TERMINAL_SESSIONS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&session_id);
+    if let Some(mut running) = session.and_then(|s| s.running) {
+        let _ = running.child.kill();
+        let _ = running.child.wait();
+    }
+    Ok(())
+}
+
+// Command palette
+
+/// `openclaw agents list`, parsed.
+#[command]
+fn cli_list_agents() -> Result<Vec<cli::AgentEntry>, String> {
+    Ok(cli::agents_list()?)
+}
+
+/// `openclaw sessions`, parsed, most recently updated first.
+#[command]
+fn cli_list_sessions() -> Result<Vec<cli::SessionEntry>, String> {
+    let mut sessions = cli::sessions_list()?;
+    sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    Ok(sessions)
+}
+
+/// `openclaw models list`, parsed.
+#[command]
+fn cli_list_models() -> Result<Vec<cli::ModelEntry>, String> {
+    Ok(cli::models_list()?)
+}
+
+/// `openclaw channels status`, parsed into one entry per channel.
+#[command]
+fn cli_channels_status() -> Result<Vec<cli::ChannelStatus>, String> {
+    Ok(cli::channels_status()?)
+}
+
+// Message catalog
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct MessageCatalog {
+    /// The bundled locale served, e.g. `de` for `de-AT`, or `en` when there is none.
+    locale: String,
+    available: Vec<String>,
+    messages: std::collections::BTreeMap<String, String>,
+}
+
+/// Templates for every backend message code in `locale` (default `en`). Parameters appear
+/// as `{name}`.
+#[command]
+fn get_message_catalog(locale: Option<String>) -> MessageCatalog {
+    let locale = messages::resolve_locale(locale.as_deref().unwrap_or(messages::DEFAULT_LOCALE));
+    MessageCatalog {
+        locale: locale.to_string(),
+        available: messages::locales()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        messages: messages::catalog(locale),
+    }
+}
+
+/// The code and parameters of a backend error or status message, if it comes from the
+/// catalog.
+#[command]
+fn identify_message(text: String) -> Option<messages::Message> {
+    messages::identify(&text)
+}
+
+// Local setup history
+
+const SETUP_HISTORY_FILE: &str = "setup-history.json";
+const MAX_SETUP_HISTORY: usize = 50;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+struct SetupAttemptStep {
+    name: String,
+    succeeded: bool,
+    duration_ms: u64,
+    error: Option<String>,
+}
+
+/// One `run_full_setup` call. Stored only in the app data directory; never uploaded.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+struct SetupAttempt {
+    started_at: u64,
+    resumed_from: Option<String>,
+    /// `succeeded`, `failed` or `aborted`.
+    outcome: String,
+    failure_cause: Option<String>,
+    steps: Vec<SetupAttemptStep>,
+}
+
+fn unix_now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Coarse category for a failed step, so repeated failures can be grouped at a glance.
+fn setup_failure_cause(error: &str) -> &'static str {
+    let lower = error.to_lowercase();
+    if lower.contains("did not finish within") || lower.contains("timed out") {
+        "timeout"
+    } else if elevation::is_permission_error(error) || lower.contains("administrator approval") {
+        "permission"
+    } else if [
+        "could not reach",
+        "enotfound",
+        "etimedout",
+        "econnrefused",
+        "econnreset",
+        "network",
+    ]
+    .iter()
+    .any(|needle| lower.contains(needle))
+    {
+        "network"
+    } else if lower.contains("not installed") || lower.contains("not found") {
+        "missing_dependency"
+    } else if lower.contains("port") && lower.contains("in use") {
+        "port_in_use"
+    } else {
+        "other"
+    }
+}
+
+fn setup_history_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_dir = app
+        .path_resolver()
+        .app_data_dir()
+        .ok_or("Could not determine app data directory")?;
+    Ok(app_dir.join(SETUP_HISTORY_FILE))
+}
+
+fn read_setup_history(app: &tauri::AppHandle) -> Vec<SetupAttempt> {
+    setup_history_path(app)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Appends `attempt`, keeping the newest `MAX_SETUP_HISTORY` entries. Best effort: a
+/// history write must never fail the setup itself.
+fn record_setup_attempt(app: &tauri::AppHandle, attempt: SetupAttempt) {
+    let mut history = read_setup_history(app);
+    history.push(attempt);
+    if history.len() > MAX_SETUP_HISTORY {
+        history.drain(..history.len() - MAX_SETUP_HISTORY);
+    }
+    let Ok(path) = setup_history_path(app) else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(raw) = serde_json::to_string_pretty(&history) {
+        if let Err(e) = fs::write(&path, raw) {
+            eprintln!("Failed to save setup history: {}", e);
+        }
+    }
+}
+
+/// Previous setup attempts, newest first.
+#[command]
+fn get_setup_history(app: tauri::AppHandle) -> Vec<SetupAttempt> {
+    let mut history = read_setup_history(&app);
+    history.reverse();
+    history
+}
+
+// App settings
+
+fn app_data_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Could not determine app data directory".to_string())
+}
+
+#[command]
+fn get_app_settings(app: tauri::AppHandle) -> Result<settings::AppSettings, String> {
+    Ok(settings::load(&app_data_dir(&app)?))
+}
+
+#[command]
+fn set_app_settings(
+    app: tauri::AppHandle,
+    settings: settings::AppSettings,
+) -> Result<settings::AppSettings, String> {
+    let dir = app_data_dir(&app)?;
+    settings::save(&dir, &settings)?;
+    let saved = settings::load(&dir);
+    apply_app_settings(&saved);
+    configure_telemetry(&dir, &saved);
+    if !saved.telemetry_enabled {
+        telemetry::clear_queue(&dir);
+    }
+    Ok(saved)
+}
+
+// Profiles
+
+/// Points `paths` (and the CLI environment) at the active profile's root.
+fn apply_app_settings(settings: &settings::AppSettings) {
+    paths::set_root_setting(profiles::active_root(settings));
+}
+
+#[command]
+fn list_profiles(app: tauri::AppHandle) -> Result<Vec<profiles::ProfileInfo>, String> {
+    let settings = settings::load(&app_data_dir(&app)?);
+    let default_port = if settings.active_profile == profiles::DEFAULT_PROFILE {
+        read_local_gateway_port()
+    } else {
+        profiles::DEFAULT_GATEWAY_PORT
+    };
+    Ok(profiles::list(&settings, default_port))
+}
+
+/// Creates an isolated profile with its own root and gateway port. The root is seeded with
+/// an `openclaw.json` carrying the port so its gateway does not collide with the others.
+#[command]
+fn create_profile(app: tauri::AppHandle, name: String) -> Result<profiles::Profile, String> {
+    let dir = app_data_dir(&app)?;
+    let mut settings = settings::load(&dir);
+    let profile = profiles::add_profile(&mut settings, name.trim())?;
+
+    let root = paths::expand_root(&profile.root)?;
+    #[cfg(target_os = "windows")]
+    wsl_mkdir_p(&root)?;
+    #[cfg(not(target_os = "windows"))]
+    fs::create_dir_all(&root).map_err(|e| format!("Failed to create {}: {}", root, e))?;
+
+    let config_path = format!("{}/openclaw.json", root);
+    let existing = {
+        #[cfg(target_os = "windows")]
+        {
+            wsl_read_file(&config_path).unwrap_or_default()
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            fs::read_to_string(&config_path).unwrap_or_default()
+        }
+    };
+    if existing.trim().is_empty() {
+        let config = serde_json::json!({ "gateway": { "port": profile.port } });
+        let raw = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        write_local_text_file(&config_path, &raw)?;
+    }
+
+    settings::save(&dir, &settings)?;
+    Ok(profile)
+}
+
+/// Makes `name` the active profile; every later command operates on its root.
+#[command]
+fn switch_profile(
+    app: tauri::AppHandle,
+    name: String,
+) -> Result<Vec<profiles::ProfileInfo>, String> {
+    let dir = app_data_dir(&app)?;
+    let mut settings = settings::load(&dir);
+    if name != profiles::DEFAULT_PROFILE && profiles::find_profile(&settings, &name).is_none() {
+        return Err(format!("Unknown profile '{}'", name));
+    }
+    settings.active_profile = name;
+    settings::save(&dir, &settings)?;
+    apply_app_settings(&settings);
+    list_profiles(app)
+}
+
+// WSL2 Helper Functions
+
+#[cfg(target_os = "windows")]
+fn check_wsl2_installed() -> bool {
+    let output = Command::new("powershell")
+        .args(["-Command", "wsl -l -v 2>$null; exit $LASTEXITCODE"])
+        .output();
+
+    output.map(|o| o.status.success()).unwrap_or(false)
+}
+
+/// Poll WSL Ubuntu until it responds, with a configurable timeout.
+/// Used after installing WSL or before running commands that need WSL to be ready.
+#[cfg(target_os = "windows")]
+fn wait_for_wsl_ready(timeout_secs: u64) -> Result<(), String> {
+    let start = std::time::Instant::now();
+    let timeout = Duration::from_secs(timeout_secs);
+    while start.elapsed() < timeout {
+        let output = Command::new("wsl")
+            .args(["-d", "Ubuntu", "-u", "root", "--", "echo", "ready"])
+            .output();
+        if let Ok(o) = output {
+            if o.status.success() {
+                let stdout = String::from_utf8_lossy(&o.stdout);
+                if stdout.trim() == "ready" {
+                    return Ok(());
+                }
+            }
+        }
+        thread::sleep(Duration::from_secs(3));
+    }
+    Err(format!(
+        "WSL Ubuntu not ready after {} seconds",
+        timeout_secs
+    ))
+}
+
+#[cfg(target_os = "windows")]
+fn ensure_wsl2_installed() -> Result<(), String> {
+    // Check if WSL2 is already installed
+    if check_wsl2_installed() {
+        return Ok(());
+    }
+
+    // Install WSL2 using elevated PowerShell (triggers UAC admin prompt)
+    // Start-Process -Verb RunAs launches the command with admin privileges,
+    // showing the user a UAC confirmation dialog they can click to approve.
+    // -Wait ensures we block until the elevated process completes.
+    let install_args: Vec<String> = ["--install", "--distribution", "Ubuntu"]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    if let Err(e) = elevation::run_elevated("wsl.exe", &install_args) {
+        // Check if user declined the UAC prompt
+        let declined = match &e {
+            elevation::ElevationError::Declined => true,
+            elevation::ElevationError::Failed(stderr) => elevation::is_permission_error(stderr),
+        };
+        if declined {
+            return Err(messages::text("wsl.admin_required", &[]));
+        }
+        return Err(format!(
+            "WSL2 installation failed. Please ensure virtualization is enabled in BIOS. Error: {}",
+            e
+        ));
+    }
+
+    // Wait for WSL Ubuntu to become responsive (first-time init can be slow)
+    wait_for_wsl_ready(90).map_err(|e| {
+        format!("WSL2 was installed but Ubuntu is not responding. You may need to restart your computer. Error: {}", e)
+    })?;
+
+    // Verify WSL2 is now available
+    if !check_wsl2_installed() {
+        return Err(messages::text("wsl.restart_required", &[]));
+    }
+
+    // Configure Ubuntu with a default user non-interactively.
+    // Without this, Ubuntu prompts for username/password on first launch.
+
+    // Create a non-root user 'openclaw' with a password, for general use
+    let user_setup = Command::new("wsl")
+        .args(["-d", "Ubuntu", "-u", "root", "--", "/bin/bash", "-c",
+            "id openclaw >/dev/null 2>&1 || (useradd -m -s /bin/bash openclaw && echo 'openclaw:openclaw' | chpasswd && usermod -aG sudo openclaw)"
+        ])
+        .output()
+        .map_err(|e| format!("Failed to create openclaw user: {}", e))?;
+
+    if !user_setup.status.success() {
+        let stderr = String::from_utf8_lossy(&user_setup.stderr);
+        eprintln!(
+            "Warning: user setup returned error (may be harmless if user exists): {}",
+            stderr
+        );
+    }
+
+    // Write /etc/wsl.conf to set default user (more reliable than `ubuntu config --default-user`)
+    let wsl_conf = Command::new("wsl")
+        .args([
+            "-d",
+            "Ubuntu",
+            "-u",
+            "root",
+            "--",
+            "/bin/bash",
+            "-c",
+            "printf '[user]\\ndefault=openclaw\\n' > /etc/wsl.conf",
+        ])
+        .output()
+        .map_err(|e| format!("Failed to write /etc/wsl.conf: {}", e))?;
+
+    if !wsl_conf.status.success() {
+        let stderr = String::from_utf8_lossy(&wsl_conf.stderr);
+        eprintln!("Warning: failed to write wsl.conf: {}", stderr);
+    }
+
+    // Terminate Ubuntu so wsl.conf takes effect on next launch
+    let _ = Command::new("wsl").args(["--terminate", "Ubuntu"]).output();
+
+    thread::sleep(Duration::from_secs(2));
+
+    // Wait for Ubuntu to come back with the new default user
+    wait_for_wsl_ready(30).map_err(|e| {
+        format!(
+            "WSL Ubuntu failed to restart after user configuration: {}",
+            e
+        )
+    })?;
+
+    Ok(())
+}
+
+/// Run a command as root inside WSL (for apt-get, system setup, etc.)
+/// This avoids the sudo password prompt by using `wsl -u root` directly.
+#[cfg(target_os = "windows")]
+fn wsl_root_command(cmd: &str) -> Result<String, String> {
+    let mut command = Command::new("wsl");
+    command.args([
+        "-d",
+        "Ubuntu",
+        "--user",
+        "root",
+        "--",
+        "/bin/bash",
+        "-c",
+        &process::with_child_env(cmd),
+    ]);
+    let output = process::spawn(command, cmd)?;
+
+    if output.success() {
+        Ok(output.stdout)
+    } else {
+        Err(format!("{}\n{}", output.stdout, output.stderr))
+    }
+}
+
+// --- WSL filesystem helpers (Windows only) ---
+// On Windows, openclaw runs inside WSL but Tauri runs natively.
+// dirs::home_dir() returns C:\Users\... but we need /home/user/... inside WSL.
+
+#[cfg(target_os = "windows")]
+fn wsl_home_dir() -> Result<String, String> {
+    shell_command("echo $HOME").map(|s| s.trim().to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn wsl_write_file(path: &str, content: &str) -> Result<(), String> {
+    // Escape single quotes for safe shell embedding: ' -> '\''
+    let escaped = content.replace('\'', "'\\''");
+    let cmd = format!("printf '%s' '{}' > \"{}\"", escaped, path);
+    shell_command(&cmd)?;
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn wsl_read_file(path: &str) -> Result<String, String> {
+    shell_command(&format!("cat \"{}\" 2>/dev/null", path))
+}
+
+#[cfg(target_os = "windows")]
+fn wsl_mkdir_p(path: &str) -> Result<(), String> {
+    shell_command(&format!("mkdir -p \"{}\"", path))?;
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn wsl_list_dirs(base_path: &str) -> Vec<String> {
+    let mut dirs_found = Vec::new();
+    if let Ok(output) = shell_command(&format!("ls -1 -F \"{}\" 2>/dev/null", base_path)) {
+        for line in output.lines() {
+            if line.trim().ends_with('/') {
+                dirs_found.push(line.trim().trim_matches('/').to_string());
+            }
+        }
+    }
+    dirs_found
+}
+
+#[cfg(target_os = "windows")]
+fn wsl_remove_dir(path: &str) -> Result<(), String> {
+    let cmd = if path.starts_with("~/") {
+        format!("rm -rf \"$HOME/{}\"", &path[2..])
+    } else {
+        format!("rm -rf \"{}\"", path)
+    };
+    shell_command(&cmd)?;
+    Ok(())
+}
+
+fn shell_command(cmd: &str) -> Result<String, String> {
+    process::shell(cmd)?.into_result()
+}
+
+/// Registered gateways are checked through the config they are running: a DM policy other
+/// than `pairing`, or an approved sender in `allowFrom`, counts as linked.
+#[command]
+async fn check_pairing_status(
+    app: tauri::AppHandle,
+    remote: Option<RemoteInfo>,
+    gateway_id: Option<String>,
+) -> Result<bool, String> {
+    if let Some(client) = registered_gateway_client(&app, gateway_id.as_deref())? {
+        let snapshot = client.config_snapshot().await?;
+        let config = snapshot
+            .get("config")
+            .or_else(|| snapshot.get("parsed"))
+            .cloned()
+            .unwrap_or_default();
+        if let Some(policy) = extract_telegram_dm_policy_from_config(&config) {
+            if telegram_pairing_status_from_dm_policy(&policy) {
+                return Ok(true);
+            }
+        }
+        return Ok(config
+            .pointer("/channels/telegram/allowFrom")
+            .and_then(|v| v.as_array())
+            .is_some_and(|entries| !entries.is_empty()));
+    }
+    if let Some(r) = remote {
+        let sess = connect_ssh(&r)?;
+        let os_type = execute_ssh(&sess, "uname -s")?.trim().to_string();
+        let prefix = get_env_prefix(&os_type);
+
+        if let Some(policy) = read_telegram_dm_policy_via_cli(|cmd| execute_ssh(&sess, &format!("{}{}", prefix, cmd)))? {
+            if telegram_pairing_status_from_dm_policy(&policy) {
+                return Ok(true);
+            }
+        }
+
+        if let Some(policy) =
+            read_telegram_dm_policy_from_config_str(&execute_ssh(&sess, "cat ~/.openclaw/openclaw.json")?)
+        {
+            if telegram_pairing_status_from_dm_policy(&policy) {
+                return Ok(true);
+            }
+        }
+
+        return telegram_allow_from_is_linked_remote(&sess);
+    }
+
+    if let Some(policy) = read_telegram_dm_policy_via_cli(shell_command)? {
+        if telegram_pairing_status_from_dm_policy(&policy) {
+            return Ok(true);
+        }
+    }
+
+    if let Ok(config_str) = read_local_openclaw_file("openclaw.json") {
+        if let Some(policy) = read_telegram_dm_policy_from_config_str(&config_str) {
+            if telegram_pairing_status_from_dm_policy(&policy) {
+                return Ok(true);
+            }
+        }
+    }
+
+    let credentials_dir = PathBuf::from(paths::local_path("credentials")?);
+    Ok(telegram_allow_from_is_linked_local(&credentials_dir))
+}
+
+fn telegram_pairing_status_from_dm_policy(policy: &str) -> bool {
+    policy.trim().trim_matches('"') != "pairing"
+}
+
+fn parse_config_string_output(output: &str) -> Option<String> {
+    let value = output.trim().trim_matches('"');
+    if value.is_empty() || value == "null" || value == "undefined" {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+fn extract_telegram_dm_policy_from_config(config: &serde_json::Value) -> Option<String> {
+    config
+        .get("channels")
+        .and_then(|c| c.get("telegram"))
+        .and_then(|t| t.get("accounts"))
+        .and_then(|a| a.get("default"))
+        .and_then(|m| m.get("dmPolicy"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .or_else(|| {
+            config
+                .get("channels")
+                .and_then(|c| c.get("telegram"))
+                .and_then(|t| t.get("dmPolicy"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        })
+}
+
+fn read_telegram_dm_policy_from_config_str(config_str: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(config_str)
+        .ok()
+        .and_then(|config| extract_telegram_dm_policy_from_config(&config))
+}
+
+fn read_telegram_dm_policy_via_cli<F>(mut run_command: F) -> Result<Option<String>, String>
+where
+    F: FnMut(&str) -> Result<String, String>,
+{
+    for cmd in [
+        "openclaw config get channels.telegram.accounts.default.dmPolicy",
+        "openclaw config get channels.telegram.dmPolicy",
+    ] {
+        match run_command(cmd) {
+            Ok(output) => {
+                if let Some(policy) = parse_config_string_output(&output) {
+                    return Ok(Some(policy));
+                }
+            }
+            Err(_) => continue,
+        }
+    }
+
+    Ok(None)
+}
+
+fn telegram_allow_from_entries_from_str(content: &str) -> Option<usize> {
+    serde_json::from_str::<serde_json::Value>(content)
+        .ok()
+        .and_then(|json| json.get("allowFrom").and_then(|value| value.as_array()).map(|items| items.len()))
+}
+
+fn is_telegram_allow_from_filename(name: &str) -> bool {
+    name.starts_with("telegram") && name.ends_with("-allowFrom.json")
+}
+
+fn telegram_allow_from_is_linked_local(credentials_dir: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(credentials_dir) else {
+        return false;
+    };
+
+    entries.flatten().any(|entry| {
+        let path = entry.path();
+        let Some(name) = path.file_name().and_then(|value| value.to_str()) else {
+            return false;
+        };
+        if !path.is_file() || !is_telegram_allow_from_filename(name) {
+            return false;
+        }
+
+        fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| telegram_allow_from_entries_from_str(&content))
+            .map(|count| count > 0)
+            .unwrap_or(false)
+    })
+}
+
+fn telegram_allow_from_is_linked_remote(sess: &Session) -> Result<bool, String> {
+    let files = execute_ssh(
+        sess,
+        "find \"$HOME/.openclaw/credentials\" -maxdepth 1 -type f -name 'telegram*-allowFrom.json' 2>/dev/null",
+    )?;
+
+    for file in files.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let content = execute_ssh(sess, &format!("cat {}", shell_single_quote(file)))?;
+        if telegram_allow_from_entries_from_str(&content).unwrap_or(0) > 0 {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+fn whatsapp_session_is_linked(session_dir: &Path) -> bool {
+    if !session_dir.exists() {
+        return false;
+    }
+
+    let mut stack = vec![session_dir.to_path_buf()];
+    while let Some(path) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&path) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let child = entry.path();
+            if child.is_file() {
+                return true;
+            }
+            if child.is_dir() {
+                stack.push(child);
+            }
+        }
+    }
+
+    false
+}
+
+fn check_whatsapp_link_status(remote: Option<RemoteInfo>) -> Result<bool, String> {
+    if let Some(r) = remote {
+        let sess = connect_ssh(&r)?;
+        let output = execute_ssh(
+            &sess,
+            "if [ -d \"$HOME/.openclaw/credentials/whatsapp/default\" ] && find \"$HOME/.openclaw/credentials/whatsapp/default\" -type f 2>/dev/null | grep -q .; then printf linked; else printf unlinked; fi",
+        )?;
+        Ok(output.trim() == "linked")
+    } else {
+        let session_dir = PathBuf::from(paths::local_path("credentials/whatsapp/default")?);
+        Ok(whatsapp_session_is_linked(&session_dir))
+    }
+}
+
+#[command]
+async fn check_messaging_link_status(
+    app: tauri::AppHandle,
+    channel: String,
+    remote: Option<RemoteInfo>,
+) -> Result<bool, String> {
+    match channel.as_str() {
+        "telegram" => check_pairing_status(app, remote, None).await,
+        "whatsapp" => check_whatsapp_link_status(remote),
+        "none" => Ok(true),
+        _ => Err(format!("Unsupported messaging channel: {}", channel)),
+    }
+}
+
+#[command]
+async fn get_current_config(remote: Option<RemoteInfo>) -> Result<CurrentConfig, String> {
+    // Helper to extract values from markdown
+    fn extract_md_value(content: &str, key: &str) -> String {
+        let pattern = format!("**{}:**", key);
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+            // Look for the pattern **Key:** in the line
+            if let Some(pattern_pos) = trimmed.find(&pattern) {
+                // Extract everything after the pattern
+                let value_start = pattern_pos + pattern.len();
+                let value = &trimmed[value_start..];
+                return value.trim().to_string();
+            }
+        }
+        String::new()
+    }
+
+    // Establish session ONCE if remote
+    let session = if let Some(ref r) = remote {
+        Some(connect_ssh(r)?)
+    } else {
+        None
+    };
+
+    // Resolve the OpenClaw root (absolute) to avoid '~' ambiguity
+    let openclaw_root = if let Some(sess) = &session {
+        let remote_home = execute_ssh(sess, "echo $HOME")
+            .map_err(|e| format!("Failed to get remote home: {}", e))?;
+        format!("{}/.openclaw", remote_home.trim())
+    } else {
+        // On Windows, openclaw runs inside WSL — this is a WSL path, not a Windows one
+        paths::local_root()?
+    };
+
+    // Helper to read file content (using absolute paths)
+    let read_file_content = |path: &str| -> String {
+        if let Some(sess) = &session {
+            // Remote read
+            execute_ssh(sess, &format!("cat \"{}\"", path)).unwrap_or_default()
+        } else {
+            // On Windows, read from WSL filesystem
+            #[cfg(target_os = "windows")]
+            {
+                wsl_read_file(path).unwrap_or_default()
+            }
+            #[cfg(not(target_os = "windows"))]
+            {
+                fs::read_to_string(path).unwrap_or_default()
+            }
+        }
+    };
+
+    // Helper to list directories (used for skills)
+    let list_directories = |base_path: &str| -> Vec<String> {
+        let mut dirs_found = Vec::new();
+        if let Some(sess) = &session {
+            // Remote: use ls -F to mark directories with /
+            if let Ok(output) = execute_ssh(sess, &format!("ls -1 -F \"{}\"", base_path)) {
+                for line in output.lines() {
+                    if line.trim().ends_with('/') {
+                        dirs_found.push(line.trim().trim_matches('/').to_string());
+                    }
+                }
+            }
+        } else {
+            // On Windows, list dirs inside WSL filesystem
+            #[cfg(target_os = "windows")]
+            {
+                dirs_found = wsl_list_dirs(base_path);
+            }
+            #[cfg(not(target_os = "windows"))]
+            {
+                let path = std::path::Path::new(base_path);
+                if let Ok(entries) = fs::read_dir(path) {
+                    for entry in entries.flatten() {
+                        if let Ok(ft) = entry.file_type() {
+                            if ft.is_dir() {
+                                if let Ok(name) = entry.file_name().into_string() {
+                                    dirs_found.push(name);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        dirs_found
+    };
+
+    // Fetch Main Config Files
+    let openclaw_json_str = read_file_content(&format!("{}/openclaw.json", openclaw_root));
+    let auth_profiles_str = read_file_content(&format!(
+        "{}/agents/main/agent/auth-profiles.json",
+        openclaw_root
+    ));
+    let identity_str = read_file_content(&format!("{}/workspace/IDENTITY.md", openclaw_root));
+    let user_str = read_file_content(&format!("{}/workspace/USER.md", openclaw_root));
+    let soul_str = read_file_content(&format!("{}/workspace/SOUL.md", openclaw_root));
+
+    if openclaw_json_str.is_empty() {
+        return Err("Configuration not found (openclaw.json is empty or missing)".to_string());
+    }
+
+    let oc_config: serde_json::Value = serde_json::from_str(&openclaw_json_str)
+        .map_err(|e| format!("Failed to parse openclaw.json: {}", e))?;
+    let auth_config: serde_json::Value =
+        serde_json::from_str(&auth_profiles_str).unwrap_or(serde_json::json!({}));
+    let empty_json = serde_json::json!({});
+
+    // Gateway Config
+    let gateway = oc_config.get("gateway").unwrap_or(&empty_json);
+    let gateway_port = gateway
+        .get("port")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(18789) as u16;
+    let gateway_bind = gateway
+        .get("bind")
+        .and_then(|v| v.as_str())
+        .unwrap_or("loopback")
+        .to_string();
+    let gateway_auth_mode = gateway
+        .get("auth")
+        .and_then(|a| a.get("mode"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("token")
+        .to_string();
+    let tailscale_mode = gateway
+        .get("tailscale")
+        .and_then(|t| t.get("mode"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("off")
+        .to_string();
+
+    // Agent Config (Defaults / Main)
+    let defaults = oc_config
+        .get("agents")
+        .and_then(|a| a.get("defaults"))
+        .unwrap_or(&empty_json);
+    let model_primary_raw = defaults
+        .get("model")
+        .and_then(|m| m.get("primary"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("anthropic/claude-opus-4-6")
+        .to_string();
+    let model_primary = normalize_model_ref_for_ui(&model_primary_raw);
+    let fallback_models_raw: Vec<String> = defaults
+        .get("model")
+        .and_then(|m| m.get("fallbacks"))
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    let fallback_models: Vec<String> = fallback_models_raw
+        .iter()
+        .map(|model| normalize_model_ref_for_ui(model))
+        .collect();
+
+    // Auth & Provider (Main)
+    let base_provider = model_primary_raw
+        .split('/')
+        .next()
+        .map(normalize_provider_for_ui)
+        .unwrap_or_else(|| "anthropic".to_string());
+    let main_provider_auth = resolve_provider_auth_data(&base_provider, &auth_config)
+        .unwrap_or_else(|| default_provider_auth(&base_provider, "", "token", None));
+    let profile = main_provider_auth
+        .profile
+        .clone()
+        .unwrap_or(serde_json::json!({}));
+    let provider = base_provider.clone();
+    let api_key = main_provider_auth.token.clone();
+    let auth_method = main_provider_auth.auth_method.clone();
+
+    // Markdown Extraction (Main)
+    let agent_name = extract_md_value(&identity_str, "Name");
+    let agent_vibe = extract_md_value(&identity_str, "Vibe");
+    let agent_emoji = extract_md_value(&identity_str, "Emoji");
+    let user_name = extract_md_value(&user_str, "Name");
+
+    // Telegram
+    let telegram_token = oc_config
+        .get("channels")
+        .and_then(|c| c.get("telegram"))
+        .and_then(|t| t.get("accounts"))
+        .and_then(|a| a.get("default"))
+        .and_then(|m| m.get("botToken"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    // Skills (Main)
+    // We look in <root>/workspace/skills
+    let skills = list_directories(&format!("{}/workspace/skills", openclaw_root));
+
+    let mut referenced_providers = std::collections::BTreeSet::new();
+    referenced_providers.insert(base_provider.clone());
+    for model in &fallback_models_raw {
+        if let Some(p) = model.split('/').next() {
+            referenced_providers.insert(normalize_provider_for_ui(p));
+        }
+    }
+
+    // Advanced Settings
+    let sandbox_mode = defaults
+        .get("sandbox")
+        .and_then(|s| s.get("mode"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("full")
+        .to_string();
+    let mapped_sandbox = if sandbox_mode == "all" {
+        "full"
+    } else if sandbox_mode == "non-main" {
+        "partial"
+    } else if sandbox_mode == "off" {
+        "none"
+    } else {
+        &sandbox_mode
+    };
+
+    let tools = oc_config.get("tools").unwrap_or(&empty_json);
+    let tools_profile = tools
+        .get("profile")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let allowed_tools: Vec<String> = tools
+        .get("allow")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    let denied_tools: Vec<String> = tools
+        .get("deny")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    let tools_mode = if tools_profile.as_deref() == Some("full") && denied_tools.is_empty() {
+        "all"
+    } else if !allowed_tools.is_empty() || tools_profile.is_some() {
+        "allowlist"
+    } else if !denied_tools.is_empty() {
+        "denylist"
+    } else {
+        "all"
+    };
+
+    let mut provider_auths = std::collections::HashMap::new();
+    for referenced_provider in &referenced_providers {
+        if let Some(auth) = resolve_provider_auth_data(referenced_provider, &auth_config) {
+            provider_auths.insert(referenced_provider.clone(), auth);
+        }
+    }
+
+    let fallbacks = fallback_models.clone();
+
+    let heartbeat = defaults.get("heartbeat").unwrap_or(&empty_json);
+    let heartbeat_mode = if heartbeat.get("enabled") == Some(&serde_json::json!(false)) {
+        "never".to_string()
+    } else if let Some(mode) = heartbeat.get("mode").and_then(|v| v.as_str()) {
+        mode.to_string()
+    } else if let Some(every) = heartbeat.get("every").and_then(|v| v.as_str()) {
+        every.to_string()
+    } else {
+        "1h".to_string()
+    };
+    let idle_timeout = heartbeat
+        .get("timeout")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(3600000);
+
+    // Multi-agent
+    let empty_vec = vec![];
+    let agent_list = oc_config
+        .get("agents")
+        .and_then(|a| a.get("list"))
+        .and_then(|v| v.as_array())
+        .unwrap_or(&empty_vec);
+    let enable_multi_agent = agent_list.len() > 1;
+    let mut agent_configs = Vec::new();
+
+    if enable_multi_agent {
+        for agent_val in agent_list {
+            let aid = agent_val
+                .get("id")
+                .and_then(|s| s.as_str())
+                .unwrap_or("")
+                .to_string();
+            if aid.is_empty() || aid == "main" {
+                continue;
+            }
+
+            // Basic info from openclaw.json
+            let mut name = agent_val
+                .get("name")
+                .and_then(|s| s.as_str())
+                .unwrap_or("Agent")
+                .to_string();
+
+            // Robust Model Extraction: Handle nested {primary: "..."} or simple string "..."
+            let amodel_raw = if let Some(m_obj) = agent_val.get("model").and_then(|m| m.as_object())
+            {
+                m_obj
+                    .get("primary")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("")
+                    .to_string()
+            } else if let Some(m_str) = agent_val.get("model").and_then(|s| s.as_str()) {
+                m_str.to_string()
+            } else {
+                "".to_string()
+            };
+            let amodel = normalize_model_ref_for_ui(&amodel_raw);
+
+            let afallbacks_raw: Vec<String> = agent_val
+                .get("model")
+                .and_then(|m| {
+                    if m.is_object() {
+                        m.get("fallbacks")
+                    } else {
+                        None
+                    }
+                })
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default();
+            let afallbacks: Vec<String> = afallbacks_raw
+                .iter()
+                .map(|model| normalize_model_ref_for_ui(model))
+                .collect();
+
+            // Read Agent Files (Absolute Paths)
+            let agent_workspace_base = format!("{}/agents/{}/workspace", openclaw_root, aid);
+
+            let aid_md = read_file_content(&format!("{}/IDENTITY.md", agent_workspace_base));
+            let au_md = read_file_content(&format!("{}/USER.md", agent_workspace_base));
+            let as_md = read_file_content(&format!("{}/SOUL.md", agent_workspace_base));
+
+            // Extract Metadata
+            let extracted_name = extract_md_value(&aid_md, "Name");
+            if !extracted_name.is_empty() {
+                name = extracted_name;
+            } // Identity MD overrides config name
+
+            let avibe = extract_md_value(&aid_md, "Vibe");
+            let aemoji = extract_md_value(&aid_md, "Emoji");
+
+            // Extract Skills for this agent
+            let askills = list_directories(&format!("{}/skills", agent_workspace_base));
+            let askills_opt = if askills.is_empty() {
+                None
+            } else {
+                Some(askills)
+            };
+
+            // Read additional md files for sub-agent
+            let a_tools_md_s = read_file_content(&format!("{}/TOOLS.md", agent_workspace_base));
+            let a_tools_md = if a_tools_md_s.is_empty() {
+                None
+            } else {
+                Some(a_tools_md_s)
+            };
+            let a_agents_md_s = read_file_content(&format!("{}/AGENTS.md", agent_workspace_base));
+            let a_agents_md = if a_agents_md_s.is_empty() {
+                None
+            } else {
+                Some(a_agents_md_s)
+            };
+            let a_heartbeat_md_s =
+                read_file_content(&format!("{}/HEARTBEAT.md", agent_workspace_base));
+            let a_heartbeat_md = if a_heartbeat_md_s.is_empty() {
+                None
+            } else {
+                Some(a_heartbeat_md_s)
+            };
+            let a_memory_md_s = read_file_content(&format!("{}/MEMORY.md", agent_workspace_base));
+            let a_memory_md = if a_memory_md_s.is_empty() {
+                None
+            } else {
+                Some(a_memory_md_s)
+            };
+
+            agent_configs.push(AgentData {
+                id: aid,
+                name,
+                model: amodel,
+                fallback_models: Some(afallbacks),
+                skills: askills_opt,
+                vibe: if avibe.is_empty() { None } else { Some(avibe) },
+                emoji: Some(aemoji),
+                identity_md: Some(aid_md),
+                user_md: Some(au_md),
+                soul_md: Some(as_md),
+                tools_md: a_tools_md,
+                agents_md: a_agents_md,
+                heartbeat_md: a_heartbeat_md,
+                memory_md: a_memory_md,
+                subagents: None,
+                tools: agent_val
+                    .get("tools")
+                    .and_then(|value| serde_json::from_value(value.clone()).ok()),
+            });
+            if let Some(agent_provider) =
+                agent_configs.last().and_then(|a| a.model.split('/').next())
+            {
+                referenced_providers.insert(normalize_provider_for_ui(agent_provider));
+            }
+            if let Some(agent_fallbacks) =
+                agent_configs.last().and_then(|a| a.fallback_models.clone())
+            {
+                for fallback in agent_fallbacks {
+                    if let Some(fallback_provider) = fallback.split('/').next() {
+                        referenced_providers.insert(normalize_provider_for_ui(fallback_provider));
+                    }
+                }
+            }
+        }
+    }
+
+    // Check Pairing Status
+    let credentials_dir = PathBuf::from(&openclaw_root).join("credentials");
+    let is_paired = extract_telegram_dm_policy_from_config(&oc_config)
+        .map(|policy| telegram_pairing_status_from_dm_policy(&policy))
+        .unwrap_or(false)
+        || telegram_allow_from_is_linked_local(&credentials_dir);
+
+    // Read additional workspace markdown files
+    let tools_md_s = read_file_content(&format!("{}/workspace/TOOLS.md", openclaw_root));
+    let tools_md_str = if tools_md_s.is_empty() {
+        None
+    } else {
+        Some(tools_md_s)
+    };
+    let agents_md_s = read_file_content(&format!("{}/workspace/AGENTS.md", openclaw_root));
+    let agents_md_str = if agents_md_s.is_empty() {
+        None
+    } else {
+        Some(agents_md_s)
+    };
+    let heartbeat_md_s =
+        read_file_content(&format!("{}/workspace/HEARTBEAT.md", openclaw_root));
+    let heartbeat_md_str = if heartbeat_md_s.is_empty() {
+        None
+    } else {
+        Some(heartbeat_md_s)
+    };
+    let memory_md_s = read_file_content(&format!("{}/workspace/MEMORY.md", openclaw_root));
+    let memory_md_str = if memory_md_s.is_empty() {
+        None
+    } else {
+        Some(memory_md_s)
+    };
+
+    // Check memory enabled (memoryFlush is an object: { enabled: bool })
+    let memory_enabled = defaults
+        .get("compaction")
+        .and_then(|c| c.get("memoryFlush"))
+        .and_then(|v| {
+            // Handle both old format (bare bool) and new format ({ enabled: bool })
+            if v.is_boolean() {
+                v.as_bool()
+            } else {
+                v.get("enabled").and_then(|e| e.as_bool())
+            }
+        })
+        .unwrap_or(false);
+
+    // Read Clawnetes metadata from separate file
+    let meta_str = read_file_content(&format!("{}/clawnetes-meta.json", openclaw_root));
+    let meta: serde_json::Value = serde_json::from_str(&meta_str).unwrap_or(serde_json::json!({}));
+
+    // Read cron jobs from metadata
+    let cron_jobs: Option<Vec<CronJobConfig>> = meta
+        .get("cron_jobs")
+        .and_then(|c| serde_json::from_value(c.clone()).ok());
+
+    // Read agent type from metadata (NOT from openclaw.json)
+    let agent_type = meta
+        .get("agent_type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("custom")
+        .to_string();
+
+    // Read WhatsApp config from openclaw.json
+    let whatsapp_enabled = oc_config
+        .get("plugins")
+        .and_then(|p| p.get("entries"))
+        .and_then(|e| e.get("whatsapp"))
+        .and_then(|w| w.get("enabled"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let whatsapp_dm_policy = oc_config
+        .get("channels")
+        .and_then(|c| c.get("whatsapp"))
+        .and_then(|w| w.get("accounts"))
+        .and_then(|a| a.get("default"))
+        .and_then(|m| m.get("dmPolicy"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let thinking_level = defaults
+        .get("thinkingDefault")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let azure = (provider == AZURE_PROVIDER)
+        .then(|| azure_config_from_profile(&profile))
+        .flatten();
+
+    Ok(CurrentConfig {
+        provider,
+        api_key,
+        auth_method,
+        model: model_primary,
+        user_name,
+        agent_name,
+        agent_vibe,
+        agent_emoji,
+        agent_type,
+        telegram_token,
+        gateway_port,
+        gateway_bind,
+        gateway_auth_mode,
+        tailscale_mode,
+        node_manager: "npm".to_string(),
+        skills,
+        service_keys: std::collections::HashMap::new(),
+        provider_auths,
+        sandbox_mode: mapped_sandbox.to_string(),
+        tools_mode: tools_mode.to_string(),
+        tools_profile,
+        allowed_tools,
+        denied_tools,
+        fallback_models: fallbacks,
+        heartbeat_mode,
+        idle_timeout_ms: idle_timeout,
+        identity_md: identity_str,
+        user_md: user_str,
+        soul_md: soul_str,
+        tools_md: tools_md_str,
+        agents_md: agents_md_str,
+        heartbeat_md: heartbeat_md_str,
+        memory_md: memory_md_str,
+        memory_enabled,
+        enable_multi_agent,
+        agent_configs,
+        is_paired,
+        cron_jobs,
+        local_base_url: profile
+            .get("baseUrl")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        base_url: oc_config
+            .pointer(&format!("/models/providers/{}/baseUrl", CUSTOM_PROVIDER))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        azure,
+        bedrock: oc_config
+            .pointer(&format!("/models/providers/{}", bedrock::PROVIDER))
+            .map(|_| bedrock::BedrockConfig {
+                region: oc_config
+                    .pointer("/env/vars/AWS_REGION")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                profile: oc_config
+                    .pointer("/env/vars/AWS_PROFILE")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string()),
+                access_key_id: None,
+                secret_access_key: None,
+                session_token: None,
+            }),
+        thinking_level,
+        whatsapp_enabled: Some(whatsapp_enabled),
+        whatsapp_dm_policy,
+        whatsapp_phone_number: oc_config
+            .get("channels")
+            .and_then(|c| c.get("whatsapp"))
+            .and_then(|w| w.get("allowFrom"))
+            .and_then(|a| a.as_array())
+            .and_then(|a| a.first())
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+    })
+}
+
+#[command]
+fn has_saved_license(app: tauri::AppHandle) -> Result<bool, String> {
+    Ok(read_saved_license(&app)?.is_some())
+}
+
+#[command]
+fn verify_and_store_license(app: tauri::AppHandle, key: String) -> Result<bool, String> {
+    let trimmed = key.trim();
+    if trimmed.is_empty() {
+        return Err("License key is required.".to_string());
+    }
+
+    verify_license_with_gumroad(trimmed)?;
+    write_saved_license(&app, trimmed)?;
+    Ok(true)
+}
+
+#[command]
+async fn install_local_nodejs() -> Result<String, String> {
+    let result = install_nodejs();
+    // brew/nvm change the login PATH, so the cached environment is stale now.
+    process::probe_environment();
+    result
+}
+
+fn install_nodejs() -> Result<String, String> {
+    #[cfg(target_os = "windows")]
+    {
+        // On Windows: install WSL2 first, then Node.js inside WSL2
+        ensure_wsl2_installed()?;
+        // Ensure WSL is responsive before running apt commands
+        wait_for_wsl_ready(30)
+            .map_err(|e| format!("WSL not ready for Node.js installation: {}", e))?;
+        // Use wsl_root_command to run as root directly (avoids sudo password prompt)
+        wsl_root_command("curl -fsSL https://deb.nodesource.com/setup_22.x | bash -")
+            .map_err(|e| format!("Failed to add NodeSource repository: {}", e))?;
+        wsl_root_command("apt-get install -y nodejs")
+            .map_err(|e| format!("Failed to install Node.js in WSL2: {}", e))?;
+        return Ok("Node.js installed successfully in WSL2.".to_string());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        // 1. Try brew (macOS standard)
+        if shell_command("brew --version").is_ok() {
+            return shell_command("brew install node");
+        }
+
+        // 2. Try nvm (via curl) - Fallback for macOS without brew or Linux
+        let install_nvm_cmd =
+            "curl -o- https://raw.githubusercontent.com/nvm-sh/nvm/v0.40.1/install.sh | bash";
+        let install_node_cmd = "export NVM_DIR=\"$HOME/.nvm\"; \
+            [ -s \"$NVM_DIR/nvm.sh\" ] && \\. \"$NVM_DIR/nvm.sh\"; \
+            nvm install node && nvm use node && nvm alias default node";
+
+        let nvm_result = shell_command(install_nvm_cmd)
+            .map_err(|e| format!("Failed to install nvm: {}", e))
+            .and_then(|_| {
+                shell_command(install_node_cmd)
+                    .map_err(|e| format!("Failed to install Node.js via nvm: {}", e))
+            });
+
+        // 3. Linux without a usable nvm: the distro package, installed as root via pkexec.
+        match nvm_result {
+            Ok(output) => Ok(output),
+            Err(nvm_err) if cfg!(target_os = "linux") => install_nodejs_with_system_packages()
+                .map_err(|e| format!("{}\nSystem package install also failed: {}", nvm_err, e)),
+            Err(nvm_err) => Err(nvm_err),
+        }
+    }
+}
+
+const NODE_SYSTEM_PACKAGE_MANAGERS: &[&str] = &["apt-get", "dnf", "zypper", "pacman"];
+
+fn install_nodejs_with_system_packages() -> Result<String, String> {
+    let manager = NODE_SYSTEM_PACKAGE_MANAGERS
+        .iter()
+        .find(|pm| process::resolve(pm).is_ok())
+        .ok_or("No supported system package manager found.")?;
+    let args: Vec<String> = match *manager {
+        "pacman" => vec!["-S", "--noconfirm", "nodejs", "npm"],
+        "zypper" => vec!["--non-interactive", "install", "nodejs", "npm"],
+        _ => vec!["install", "-y", "nodejs", "npm"],
+    }
+    .into_iter()
+    .map(|a| a.to_string())
+    .collect();
+    elevation::run_elevated(manager, &args).map_err(String::from)
+}
+
+#[command]
+fn get_ollama_models(remote: Option<RemoteInfo>) -> Result<Vec<String>, String> {
+    if let Some(r) = remote {
+        // Remote: SSH exec curl to hit Ollama API on the remote host
+        let sess = connect_ssh(&r).map_err(|e| format!("SSH connect failed: {}", e))?;
+        let output = execute_ssh(
+            &sess,
+            "curl -sf http://localhost:11434/api/tags 2>/dev/null || echo '{}'",
+        );
+        match output {
+            Ok(json_str) => {
+                let val: serde_json::Value =
+                    serde_json::from_str(&json_str).unwrap_or(serde_json::json!({}));
+                Ok(parse_local_models("ollama", &val))
+            }
+            Err(_) => Ok(vec![]),
+        }
+    } else {
+        // Local: use reqwest blocking
+        match reqwest::blocking::get("http://localhost:11434/api/tags") {
+            Ok(resp) => {
+                let json: serde_json::Value = resp.json().unwrap_or(serde_json::json!({}));
+                Ok(parse_local_models("ollama", &json))
+            }
+            Err(_) => Ok(vec![]),
+        }
+    }
+}
+
+#[command]
+fn get_lmstudio_models(
+    base_url: Option<String>,
+    remote: Option<RemoteInfo>,
+) -> Result<Vec<String>, String> {
+    let url_base = base_url.as_deref().unwrap_or("http://localhost:1234");
+    let models_url = format!("{}/v1/models", url_base);
+
+    if let Some(r) = remote {
+        let sess = connect_ssh(&r).map_err(|e| format!("SSH connect failed: {}", e))?;
+        let output = execute_ssh(
+            &sess,
+            &format!("curl -sf {}/v1/models 2>/dev/null || echo '{{}}'", url_base),
+        );
+        match output {
+            Ok(json_str) => {
+                let val: serde_json::Value =
+                    serde_json::from_str(&json_str).unwrap_or(serde_json::json!({}));
+                Ok(parse_local_models("lmstudio", &val))
+            }
+            Err(_) => Ok(vec![]),
+        }
+    } else {
+        match reqwest::blocking::get(&models_url) {
+            Ok(resp) => {
+                let json: serde_json::Value = resp.json().unwrap_or(serde_json::json!({}));
+                Ok(parse_local_models("lmstudio", &json))
+            }
+            Err(_) => Ok(vec![]),
+        }
+    }
+}
+
+#[command]
+fn validate_openclaw_config(
+    remote: Option<RemoteInfo>,
+    is_wsl: Option<bool>,
+) -> Result<String, String> {
+    if let Some(r) = remote {
+        let sess = connect_ssh(&r).map_err(|e| format!("SSH connect failed: {}", e))?;
+        let os_type = execute_ssh(&sess, "uname -s")
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        let prefix = get_env_prefix(&os_type);
+        execute_ssh(&sess, &format!("{}openclaw config validate 2>&1", prefix))
+    } else if is_wsl.unwrap_or(false) {
+        shell_command("wsl -- openclaw config validate 2>&1")
+    } else {
+        shell_command("openclaw config validate 2>&1")
+    }
+}
+
+/// Reads `gateway.auth.token` from the openclaw.json on the target host.
+fn read_gateway_auth_token(remote: Option<&RemoteInfo>) -> Result<Option<String>, String> {
+    let config = if let Some(r) = remote {
+        let sess = connect_ssh(r)?;
+        let home = execute_ssh(&sess, "echo $HOME").unwrap_or_default();
+        let json_str = execute_ssh(
+            &sess,
+            &format!("cat {}/.openclaw/openclaw.json", home.trim()),
+        )
+        .unwrap_or_default();
+        serde_json::from_str::<serde_json::Value>(&json_str).unwrap_or(serde_json::json!({}))
+    } else {
+        read_local_openclaw_config().unwrap_or(serde_json::json!({}))
+    };
+    Ok(config
+        .get("gateway")
+        .and_then(|g| g.get("auth"))
+        .and_then(|a| a.get("token"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string()))
+}
+
+#[command]
+async fn start_whatsapp_login(
+    gateway_port: u16,
+    remote: Option<RemoteInfo>,
+) -> Result<String, String> {
+    // Remote gateways are reached through the SSH tunnel on the same local port.
+    let token = read_gateway_auth_token(remote.as_ref())?;
+    let client = gateway_client::GatewayClient::new(gateway_port, token);
+
+    // On the very first connection to a fresh gateway the handshake answers NOT_PAIRED and
+    // the gateway auto-approves the device; connect() retries until that completes.
+    let mut connection = client.connect().await?;
+    let payload = connection
+        .request(
+            "web.login.start",
+            serde_json::json!({ "timeoutMs": 30000, "force": true }),
+            gateway_client::DEFAULT_RPC_TIMEOUT,
+        )
+        .await?;
+
+    match payload.get("qrDataUrl").and_then(|v| v.as_str()) {
+        Some(qr) => Ok(qr.to_string()),
+        // ok:true but no qrDataUrl — already linked or unexpected format
+        None => Err("Gateway returned ok but no QR code (already linked?)".to_string()),
+    }
+}
+
+#[command]
+async fn wait_whatsapp_login(
+    gateway_port: u16,
+    remote: Option<RemoteInfo>,
+) -> Result<bool, String> {
+    let token = read_gateway_auth_token(remote.as_ref())?;
+    let client = gateway_client::GatewayClient::new(gateway_port, token);
+
+    let mut connection = client.connect().await?;
+    let result = connection
+        .request(
+            "web.login.wait",
+            serde_json::json!({ "timeoutMs": 120000 }),
+            Duration::from_secs(130),
+        )
+        .await;
+
+    match result {
+        Ok(payload) => Ok(payload
+            .get("connected")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)),
+        Err(gateway_client::RpcError::TimedOut) => Err("WhatsApp login wait timed out".to_string()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[command]
+async fn wipe_whatsapp_session() -> Result<(), String> {
+    let session_dir = paths::local_path("credentials/whatsapp/default")?;
+    if std::path::Path::new(&session_dir).exists() {
+        std::fs::remove_dir_all(&session_dir)
+            .map_err(|e| format!("Failed to delete whatsapp session: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Check if WhatsApp creds are saved by calling web.login.start WITHOUT force.
+/// If creds exist, OpenClaw returns ok:true with no qrDataUrl ("already linked").
+#[command]
+async fn check_whatsapp_linked(gateway_port: u16) -> Result<bool, String> {
+    let token = read_gateway_auth_token(None)?;
+    let client = gateway_client::GatewayClient::new(gateway_port, token);
+
+    // NOT_PAIRED from the connect handshake means WhatsApp is definitively
+    // not linked — return false rather than an error.
+    let mut connection = match client.connect_once().await? {
+        Some(connection) => connection,
+        None => return Ok(false),
+    };
+
+    let result = connection
+        .request(
+            "web.login.start",
+            serde_json::json!({ "timeoutMs": 10000 }),
+            Duration::from_secs(15),
+        )
+        .await;
+
+    match result {
+        // If no qrDataUrl → creds exist, already linked
+        Ok(payload) => Ok(payload.get("qrDataUrl").and_then(|v| v.as_str()).is_none()),
+        Err(gateway_client::RpcError::Transport(e)) => Err(format!("WebSocket error: {}", e)),
+        Err(_) => Ok(false),
+    }
+}
+
+#[command]
+async fn restart_openclaw_gateway(
+    app: tauri::AppHandle,
+    remote: Option<RemoteInfo>,
+) -> Result<(), String> {
+    let _operation = lifecycle::begin_operation("restart_openclaw_gateway").await;
+    if let Some(r) = remote {
+        let sess = connect_ssh(&r)?;
+        let nvm_prefix = get_env_prefix(&execute_ssh(&sess, "uname -s")?.trim().to_string());
+        execute_ssh(&sess, &format!("{}openclaw gateway restart", nvm_prefix))?;
+    } else {
+        // 'openclaw gateway restart' uses launchctl kickstart which fails with
+        // "Operation not permitted" from Tauri's subprocess context.
+        // Use the same stop → bootstrap → start pattern as start_gateway() instead.
+        let _ = shell_command("openclaw gateway stop");
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+
+        #[cfg(target_os = "macos")]
+        if let Some(home) = dirs::home_dir() {
+            let plist = home.join("Library/LaunchAgents/ai.openclaw.gateway.plist");
+            if plist.exists() {
+                let _ = shell_command(&format!(
+                    "launchctl bootstrap gui/$(id -u) \"{}\"",
+                    plist.to_string_lossy()
+                ));
+            }
+        }
+
+        shell_command("openclaw gateway start")
+            .map_err(|e| format!("Gateway restart failed: {}", e))?;
+    }
+    // Wait for gateway to fully start before returning
+    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    notify_gateway_restarted(&app);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match headless::parse_args(&args) {
+        Ok(Some(command)) => std::process::exit(headless::run(command)),
+        Ok(None) => {}
+        Err(e) => {
+            let usage = args
+                .first()
+                .and_then(|command| headless::command_usage(command));
+            eprintln!("{}\n\n{}", e, usage.as_deref().unwrap_or(headless::USAGE));
+            std::process::exit(2);
+        }
+    }
+
+    tauri::Builder::default()
+        .manage(lifecycle::state())
+        .setup(|app| {
+            install_panic_hook();
+            if let Ok(dir) = app_data_dir(&app.handle()) {
+                let settings = settings::load(&dir);
+                apply_app_settings(&settings);
+                configure_telemetry(&dir, &settings);
+                *CRASH_LOG_DIR.lock().unwrap() = Some(dir.clone());
+                OBSERVER_MODE.store(observer::load(&dir).enabled, Ordering::Relaxed);
+                if let Some(port) = settings.health_endpoint_port {
+                    if let Err(e) = start_health_endpoint_server(port) {
+                        eprintln!("{}", e);
+                    }
+                }
+            }
+            // Resolve node/npm/openclaw once in the background so the first command
+            // does not pay for a login shell.
+            std::thread::spawn(process::probe_environment);
+            start_budget_monitor(app.handle());
+            start_log_rotation();
+            start_log_monitor(app.handle());
+            start_gateway_event_bridge(app.handle());
+            start_latency_prober();
+            start_cloud_backup_scheduler(app.handle());
+            start_backup_scheduler(app.handle());
+            start_telemetry_uploader();
+            metrics::start_sampler();
+            Ok(())
+        })
+        .invoke_handler(guard_commands(tauri::generate_handler![
+            install_local_nodejs,
+            check_prerequisites,
+            install_openclaw,
+            get_install_strategies,
+            configure_agent,
+            start_gateway,
+            initialize_agent_sessions,
+            generate_pairing_code,
+            get_dashboard_url,
+            approve_pairing,
+            close_app,
+            install_skill,
+            install_remote_skill,
+            start_provider_auth,
+            get_openclaw_version,
+            uninstall_openclaw,
+            run_doctor_repair,
+            run_security_audit_fix,
+            read_workspace_files,
+            save_workspace_files,
+            create_custom_skill,
+            test_ssh_connection,
+            setup_remote_openclaw,
+            start_ssh_tunnel,
+            stop_ssh_tunnel,
+            check_remote_prerequisites,
+            get_remote_openclaw_version,
+            run_remote_doctor_repair,
+            run_remote_security_audit_fix,
+            uninstall_remote_openclaw,
+            update_remote_openclaw,
+            get_remote_gateway_token,
+            verify_tunnel_connectivity,
+            get_current_config,
+            has_saved_license,
+            verify_and_store_license,
+            check_pairing_status,
+            check_messaging_link_status,
+            get_ollama_models,
+            get_lmstudio_models,
+            validate_openclaw_config,
+            start_whatsapp_login,
+            wait_whatsapp_login,
+            wipe_whatsapp_session,
+            check_whatsapp_linked,
+            restart_openclaw_gateway,
+            set_gateway_exposure,
+            start_dashboard_proxy,
+            stop_dashboard_proxy,
+            open_dashboard_window,
+            set_gateway_supervision,
+            get_gateway_supervision_status,
+            get_gateway_status,
+            send_test_message,
+            send_test_message_streaming,
+            list_sessions,
+            get_session,
+            delete_session,
+            archive_session,
+            get_usage_summary,
+            set_budget,
+            get_budget_status,
+            list_plugins,
+            enable_plugin,
+            disable_plugin,
+            install_plugin,
+            get_tool_permissions,
+            set_tool_permissions,
+            list_scheduled_tasks,
+            create_scheduled_task,
+            delete_scheduled_task,
+            run_task_now,
+            run_full_setup,
+            abort_full_setup,
+            get_setup_state,
+            rollback_gateway_install,
+            run_cli_subcommand,
+            get_environment_info,
+            get_install_settings,
+            set_install_settings,
+            install_openclaw_from_file,
+            recover_npm_permissions,
+            get_storage_usage,
+            prune_logs,
+            get_setup_history,
+            get_app_settings,
+            set_app_settings,
+            list_profiles,
+            create_profile,
+            switch_profile,
+            generate_identity_files,
+            suggest_config_fix,
+            apply_config_fix,
+            restart_gateway_and_verify,
+            watch_config,
+            merge_config_changes,
+            get_config_section,
+            update_config_section,
+            get_model_params,
+            set_model_params,
+            get_subagent_settings,
+            set_subagent_settings,
+            get_heartbeat_settings,
+            set_heartbeat_settings,
+            get_heartbeat_status,
+            get_messaging_policy,
+            set_messaging_policy,
+            list_telegram_accounts,
+            add_telegram_account,
+            rename_telegram_account,
+            remove_telegram_account,
+            list_telegram_groups,
+            set_telegram_group_access,
+            get_telegram_transport,
+            set_telegram_transport,
+            test_email_credentials,
+            configure_email_channel,
+            configure_twilio_sms,
+            set_channel_enabled,
+            get_metrics,
+            check_connectivity,
+            apply_setup_manifest,
+            check_config_drift,
+            apply_drift_fixes,
+            deploy_remote_gateway,
+            deploy_gateway_container,
+            container_status,
+            container_logs,
+            get_gateway_service,
+            repair_gateway_service,
+            get_gateway_env,
+            set_gateway_env,
+            get_running_gateway_env,
+            get_health_endpoint,
+            set_health_endpoint,
+            get_pairing_qr,
+            run_smoke_tests,
+            get_background_tasks,
+            get_active_operation,
+            get_detected_issues,
+            clear_detected_issues,
+            get_provider_health,
+            get_routing_rules,
+            set_routing_rules,
+            detect_local_model_servers,
+            configure_local_provider,
+            validate_custom_endpoint,
+            validate_azure_openai,
+            validate_bedrock,
+            get_provider_latency,
+            apply_recommended_fallbacks,
+            init_workspace_repo,
+            get_workspace_log,
+            revert_to_commit,
+            set_cloud_backup,
+            get_cloud_backup,
+            backup_to_remote,
+            list_remote_backups,
+            restore_from_remote,
+            get_activity_feed,
+            get_agent_pause_status,
+            pause_agent,
+            resume_agent,
+            get_safe_mode_status,
+            start_gateway_safe_mode,
+            exit_safe_mode,
+            check_migrations_needed,
+            run_migrations,
+            get_gateway_service_status,
+            export_logs,
+            open_terminal,
+            terminal_input,
+            resize_terminal,
+            close_terminal,
+            cli_list_agents,
+            cli_list_sessions,
+            cli_list_models,
+            cli_channels_status,
+            get_message_catalog,
+            identify_message,
+            get_backup_schedule,
+            set_backup_schedule,
+            get_telemetry_status,
+            set_telemetry_enabled,
+            audit_security,
+            apply_security_fix,
+            harden_permissions,
+            get_observer_mode,
+            set_observer_mode,
+            list_gateways,
+            add_gateway,
+            remove_gateway,
+            test_gateway,
+            get_gateway_logs,
+            broadcast_message,
+            configure_daily_digest,
+            get_daily_digest,
+            disable_daily_digest,
+            list_mcp_servers,
+            add_mcp_server,
+            remove_mcp_server,
+            test_mcp_server,
+            setup_browser_tools,
+            export_configuration,
+            import_configuration,
+            browse_workspace_templates,
+            install_workspace_template
+        ]))
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                lifecycle::shutdown();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_agent_config_deserialization() {
+        let json_data = r#"
+        {
+            "provider": "anthropic",
+            "api_key": "sk-test-123",
+            "model": "anthropic/claude-opus-4-6",
+            "user_name": "Test User",
+            "agent_name": "Test Agent",
+            "agents": [
+                {
+                    "id": "agent-1",
+                    "name": "SubAgent 1",
+                    "model": "openai/gpt-4o",
+                    "emoji": "🤖"
+                }
+            ]
+        }
+        "#;
+
+        let config: AgentConfig =
+            serde_json::from_str(json_data).expect("Failed to deserialize AgentConfig");
+
+        assert_eq!(config.provider, "anthropic");
+        assert_eq!(config.api_key, "sk-test-123");
+        assert_eq!(config.model, "anthropic/claude-opus-4-6");
+        assert_eq!(config.user_name, "Test User");
+
+        let agents = config.agents.expect("Agents list should be present");
+        assert_eq!(agents.len(), 1);
+        assert_eq!(agents[0].name, "SubAgent 1");
+        assert_eq!(agents[0].emoji, Some("🤖".to_string()));
+    }
+
+    #[test]
+    fn test_apply_agent_overrides_serializes_explicit_tools_and_subagents() {
+        let agent = AgentData {
+            id: "data-analysis".to_string(),
+            name: "Data Analysis".to_string(),
+            model: "anthropic/claude-sonnet-4-6".to_string(),
+            fallback_models: None,
+            skills: None,
+            vibe: None,
+            emoji: None,
+            identity_md: None,
+            user_md: None,
+            soul_md: None,
+            tools_md: None,
+            agents_md: None,
+            heartbeat_md: None,
+            memory_md: None,
+            subagents: Some(SubagentConfig {
+                allow_agents: vec!["reporting".to_string()],
+            }),
+            tools: Some(AgentToolsConfig {
+                profile: Some("coding".to_string()),
+                allow: Some(vec!["browser".to_string(), "web_search".to_string()]),
+                deny: Some(vec!["subagents".to_string()]),
+                elevated: Some(ElevatedToolConfig { enabled: true }),
+            }),
+        };
+        let mut agent_obj = serde_json::json!({
+            "id": agent.id,
+            "name": agent.name
+        });
+
+        apply_agent_overrides(&mut agent_obj, &agent);
+
+        assert_eq!(
+            agent_obj
+                .get("tools")
+                .and_then(|tools| tools.get("profile"))
+                .and_then(|value| value.as_str()),
+            Some("coding")
+        );
+        assert_eq!(
+            agent_obj
+                .get("tools")
+                .and_then(|tools| tools.get("allow"))
+                .and_then(|value| value.as_array())
+                .map(|values| values.len()),
+            Some(2)
+        );
+        assert!(agent_obj
+            .get("tools")
+            .and_then(|tools| tools.get("agentToAgent"))
+            .is_none());
+        assert_eq!(
+            agent_obj
+                .get("subagents")
+                .and_then(|subagents| subagents.get("allowAgents"))
+                .and_then(|value| value.as_array())
+                .map(|values| values.len()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_apply_agent_overrides_omits_missing_nested_agent_config() {
+        let agent = AgentData {
+            id: "reporting".to_string(),
+            name: "Reporting".to_string(),
+            model: "anthropic/claude-sonnet-4-6".to_string(),
+            fallback_models: None,
+            skills: None,
+            vibe: None,
+            emoji: None,
+            identity_md: None,
+            user_md: None,
+            soul_md: None,
+            tools_md: None,
+            agents_md: None,
+            heartbeat_md: None,
+            memory_md: None,
+            subagents: None,
+            tools: None,
+        };
+        let mut agent_obj = serde_json::json!({
+            "id": agent.id,
+            "name": agent.name
+        });
+
+        apply_agent_overrides(&mut agent_obj, &agent);
+
+        assert!(agent_obj.get("tools").is_none());
+        assert!(agent_obj.get("subagents").is_none());
+    }
+
+    #[test]
+    fn test_agent_tools_config_omits_empty_optional_fields() {
+        let tools = AgentToolsConfig {
+            profile: Some("minimal".to_string()),
+            allow: None,
+            deny: None,
+            elevated: None,
+        };
+
+        let serialized = serde_json::to_value(&tools).expect("tool config should serialize");
+
+        assert_eq!(
+            serialized.get("profile").and_then(|value| value.as_str()),
+            Some("minimal")
+        );
+        assert!(serialized.get("allow").is_none());
+        assert!(serialized.get("deny").is_none());
+        assert!(serialized.get("elevated").is_none());
+        assert!(serialized.get("agentToAgent").is_none());
+    }
+
+    #[test]
+    fn test_build_agent_session_init_command_uses_hello_message() {
+        assert_eq!(
+            build_agent_session_init_command("data-analysis"),
+            "openclaw agent --agent data-analysis --message \"hello\" 2>/dev/null || true"
+        );
+    }
+
+    #[test]
+    fn test_build_auth_profiles_doc_preserves_oauth_profile_shape() {
+        let mut provider_auths = std::collections::HashMap::new();
+        provider_auths.insert(
+            "openai".to_string(),
+            ProviderAuthData {
+                auth_method: "openai-codex".to_string(),
+                token: "".to_string(),
+                profile_key: Some("openai-codex:default".to_string()),
+                profile: Some(serde_json::json!({
+                    "type": "oauth",
+                    "provider": "openai-codex",
+                    "access": "access-token",
+                    "refresh": "refresh-token"
+                })),
+                oauth_provider_id: Some("openai-codex".to_string()),
+            },
+        );
+
+        let doc = build_auth_profiles_doc(&provider_auths, None, None, "openai");
+        let profile = doc
+            .get("profiles")
+            .and_then(|p| p.get("openai-codex:default"))
+            .unwrap();
+        assert_eq!(profile.get("type").and_then(|v| v.as_str()), Some("oauth"));
+        assert_eq!(
+            profile.get("refresh").and_then(|v| v.as_str()),
+            Some("refresh-token")
+        );
+        assert_eq!(
+            doc.get("lastGood")
+                .and_then(|v| v.get("openai"))
+                .and_then(|v| v.as_str()),
+            Some("openai-codex:default")
+        );
+    }
+
+    #[test]
+    fn test_resolve_provider_auth_data_uses_last_good_for_oauth_provider() {
+        let auth_config = serde_json::json!({
+            "profiles": {
+                "openai-codex:default": {
+                    "type": "oauth",
+                    "provider": "openai-codex",
+                    "access": "access-token"
+                }
+            },
+            "lastGood": {
+                "openai": "openai-codex:default"
+            }
+        });
+
+        let resolved = resolve_provider_auth_data("openai", &auth_config)
+            .expect("provider auth should resolve");
+        assert_eq!(
+            resolved.profile_key.as_deref(),
+            Some("openai-codex:default")
+        );
+        assert_eq!(resolved.auth_method, "openai-codex");
+        assert_eq!(resolved.token, "access-token");
+        assert_eq!(resolved.oauth_provider_id.as_deref(), Some("openai-codex"));
+    }
+
+    #[test]
+    fn test_resolve_provider_auth_data_prefers_usable_oauth_over_empty_last_good() {
+        let auth_config = serde_json::json!({
+            "profiles": {
+                "openai:default": {
+                    "type": "oauth",
+                    "provider": "openai",
+                    "access": ""
+                },
+                "openai-codex:default": {
+                    "type": "oauth",
+                    "provider": "openai-codex",
+                    "access": "real-access-token"
+                }
+            },
+            "lastGood": {
+                "openai": "openai:default"
+            }
+        });
+
+        let resolved = resolve_provider_auth_data("openai", &auth_config)
+            .expect("provider auth should resolve");
+        assert_eq!(
+            resolved.profile_key.as_deref(),
+            Some("openai-codex:default")
+        );
+        assert_eq!(resolved.token, "real-access-token");
+        assert_eq!(resolved.oauth_provider_id.as_deref(), Some("openai-codex"));
+    }
+
+    #[test]
+    fn test_resolve_provider_auth_data_maps_anthropic_oauth_to_setup_token() {
+        let auth_config = serde_json::json!({
+            "profiles": {
+                "anthropic:default": {
+                    "type": "oauth",
+                    "provider": "anthropic",
+                    "access": "anthropic-access"
+                }
+            },
+            "lastGood": {
+                "anthropic": "anthropic:default"
+            }
+        });
+
+        let resolved = resolve_provider_auth_data("anthropic", &auth_config)
+            .expect("provider auth should resolve");
+        assert_eq!(resolved.auth_method, "setup-token");
+        assert_eq!(resolved.token, "anthropic-access");
+    }
+
+    #[test]
+    fn test_build_terminal_runner_command_writes_marker_file() {
+        let command = "openclaw models auth login --provider 'openai-codex'";
+        let runner = build_terminal_runner_command(command, "/tmp/clawnetes-oauth.exit");
+
+        assert!(runner.contains("openclaw models auth login"));
+        assert!(runner.contains("auth_exit_code=$?"));
+        assert!(runner.contains("printf '%s' \"$auth_exit_code\" > '/tmp/clawnetes-oauth.exit'"));
+        assert!(runner.ends_with("exit $auth_exit_code"));
+    }
+
+    #[test]
+    fn test_build_provider_auth_command_uses_plugin_login_for_gemini_cli() {
+        assert_eq!(
+            build_provider_auth_command("google", "google-gemini-cli", "google-gemini-cli"),
+            "openclaw models auth login --provider 'google-gemini-cli'"
+        );
+    }
+
+    #[test]
+    fn test_required_plugin_for_oauth_provider_id_maps_gemini_cli() {
+        assert_eq!(
+            required_plugin_for_oauth_provider_id("google-gemini-cli"),
+            Some("google-gemini-cli-auth")
+        );
+        assert_eq!(required_plugin_for_oauth_provider_id("openai-codex"), None);
+    }
+
+    #[test]
+    fn test_decorate_oauth_launch_error_adds_gemini_guidance() {
+        let message = decorate_oauth_launch_error(
+            "google-gemini-cli",
+            "OpenClaw auth exited with status 1.".to_string(),
+        );
+
+        assert!(message.contains("OpenClaw auth exited with status 1."));
+        assert!(message.contains("GOOGLE_CLOUD_PROJECT"));
+        assert!(message.contains("Gemini API Key"));
+    }
+
+    #[test]
+    fn test_collect_required_plugin_ids_includes_gemini_oauth_and_skill_once() {
+        let mut provider_auths = std::collections::HashMap::new();
+        provider_auths.insert(
+            "google".to_string(),
+            ProviderAuthData {
+                auth_method: "google-gemini-cli".to_string(),
+                token: String::new(),
+                profile_key: None,
+                profile: None,
+                oauth_provider_id: Some("google-gemini-cli".to_string()),
+            },
+        );
+
+        let skills = vec!["gemini".to_string()];
+        let required = collect_required_plugin_ids(&provider_auths, Some(&skills));
+
+        assert_eq!(required, vec!["google-gemini-cli-auth".to_string()]);
+    }
+
+    #[test]
+    fn test_collect_required_plugin_ids_ignores_plain_google_token_auth() {
+        let mut provider_auths = std::collections::HashMap::new();
+        provider_auths.insert(
+            "google".to_string(),
+            ProviderAuthData {
+                auth_method: "token".to_string(),
+                token: "test-token".to_string(),
+                profile_key: None,
+                profile: None,
+                oauth_provider_id: None,
+            },
+        );
+
+        let required = collect_required_plugin_ids(&provider_auths, None);
+
+        assert!(required.is_empty());
+    }
+
+    #[test]
+    fn test_merge_enabled_plugin_entries_adds_required_plugin_entry() {
+        let mut config = serde_json::json!({});
+        merge_enabled_plugin_entries(&mut config, &["google-gemini-cli-auth".to_string()]);
+
+        assert_eq!(
+            config
+                .get("plugins")
+                .and_then(|plugins| plugins.get("entries"))
+                .and_then(|entries| entries.get("google-gemini-cli-auth"))
+                .and_then(|plugin| plugin.get("enabled"))
+                .and_then(|enabled| enabled.as_bool()),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_build_provider_auth_command_uses_plugin_login_for_codex() {
+        assert_eq!(
+            build_provider_auth_command("openai", "openai-codex", "openai-codex"),
+            "openclaw models auth login --provider 'openai-codex'"
+        );
+    }
+
+    #[test]
+    fn test_build_unix_terminal_script_uses_login_shell_on_macos() {
+        let script = build_unix_terminal_script(
+            TerminalPlatform::Macos,
+            "openclaw models auth login --provider 'openai-codex'",
+            "/tmp/clawnetes-oauth.exit",
+        );
+
+        assert!(script.starts_with("#!/bin/zsh -l"));
+        assert!(script.contains("openclaw models auth login --provider 'openai-codex'"));
+        assert!(script.contains("auth_exit_code=$?"));
+        assert!(script.contains("printf '%s' \"$auth_exit_code\" > '/tmp/clawnetes-oauth.exit'"));
+        assert!(!script.contains("\nstatus=$?\n"));
+    }
+
+    #[test]
+    fn test_build_macos_terminal_launch_uses_terminal_app() {
+        let plan = build_macos_terminal_launch("/tmp/openclaw-auth.command");
+
+        assert_eq!(plan.program, "open");
+        assert_eq!(
+            plan.args,
+            vec!["-a", "Terminal", "/tmp/openclaw-auth.command"]
+        );
+    }
+
+    #[test]
+    fn test_build_linux_terminal_launches_include_common_emulators() {
+        let plans = build_linux_terminal_launches("/tmp/openclaw-auth.sh");
+
+        assert_eq!(
+            plans.first().map(|plan| plan.program.as_str()),
+            Some("x-terminal-emulator")
+        );
+        assert!(plans.iter().any(|plan| plan.program == "gnome-terminal"));
+        assert!(plans.iter().any(|plan| plan.program == "xterm"));
+    }
+
+    #[test]
+    fn test_build_windows_terminal_launches_include_tty_capable_launchers() {
+        let plans =
+            build_windows_terminal_launches("openclaw models auth login --provider 'openai-codex'");
+
+        assert_eq!(
+            plans.first().map(|plan| plan.program.as_str()),
+            Some("wt.exe")
+        );
+        assert!(plans.iter().any(|plan| plan.program == "cmd.exe"));
+        assert!(plans[0].args.contains(&"wsl.exe".to_string()));
+        assert!(plans[0].args.contains(&"/bin/bash".to_string()));
+    }
+
+    #[test]
+    fn test_oauth_callback_port_mapping() {
+        assert_eq!(oauth_callback_port("openai-codex"), Some(1455));
+        assert_eq!(oauth_callback_port("google-gemini-cli"), Some(8085));
+        assert_eq!(oauth_callback_port("anthropic"), None);
+    }
+
+    #[test]
+    fn test_normalize_auth_mode_maps_oauth_variants() {
+        assert_eq!(normalize_auth_mode("openai-codex"), "oauth");
+        assert_eq!(normalize_auth_mode("claude-cli"), "token");
+        assert_eq!(normalize_auth_mode("setup-token"), "token");
+        assert_eq!(normalize_auth_mode("token"), "token");
+    }
+
+    #[test]
+    fn test_normalize_provider_for_ui_maps_openai_codex_and_google_vertex() {
+        assert_eq!(normalize_provider_for_ui("openai-codex"), "openai");
+        assert_eq!(normalize_provider_for_ui("google-vertex"), "google");
+        assert_eq!(normalize_provider_for_ui("openai"), "openai");
+    }
+
+    #[test]
+    fn test_normalize_model_ref_for_ui_maps_openai_codex_namespace() {
+        assert_eq!(
+            normalize_model_ref_for_ui("openai-codex/gpt-5.4"),
+            "openai/gpt-5.4"
+        );
+        assert_eq!(
+            normalize_model_ref_for_ui("anthropic/claude-opus-4-6"),
+            "anthropic/claude-opus-4-6"
+        );
+    }
+
+    #[test]
+    fn test_apply_model_provider_auth_maps_openai_models_for_codex_oauth() {
+        let mut provider_auths = std::collections::HashMap::new();
+        provider_auths.insert(
+            "openai".to_string(),
+            ProviderAuthData {
+                auth_method: "openai-codex".to_string(),
+                token: "".to_string(),
+                profile_key: Some("openai-codex:default".to_string()),
+                profile: Some(serde_json::json!({
+                    "provider": "openai-codex",
+                    "type": "oauth"
+                })),
+                oauth_provider_id: Some("openai-codex".to_string()),
+            },
+        );
+
+        assert_eq!(
+            apply_model_provider_auth("openai/gpt-5.4", &provider_auths),
+            "openai-codex/gpt-5.4"
+        );
+        assert_eq!(
+            auth_provider_id_for_config(
+                "openai",
+                provider_auths.get("openai").unwrap(),
+                &provider_auths
+            ),
+            "openai-codex"
+        );
+    }
+
+    #[test]
+    fn test_build_effective_models_catalog_uses_effective_namespace_only() {
+        let models = build_effective_models_catalog(
+            "openai-codex/gpt-5.4",
+            &["openai-codex/gpt-5.4-mini".to_string()],
+        );
+
+        assert!(models.contains_key("openai-codex/gpt-5.4"));
+        assert!(models.contains_key("openai-codex/gpt-5.4-mini"));
+        assert!(!models.contains_key("openai/gpt-5.4"));
+    }
+
+    #[test]
+    fn test_parse_lsof_listener_info_parses_multiple_records() {
+        let parsed = parse_lsof_listener_info("p62370\ncopenclaw-models\np70001\ncnode\n");
+
+        assert_eq!(
+            parsed,
+            vec![
+                PortListenerInfo {
+                    pid: 62370,
+                    command: "openclaw-models".to_string()
+                },
+                PortListenerInfo {
+                    pid: 70001,
+                    command: "node".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_openclaw_listener_only_matches_openclaw_processes() {
+        assert!(is_openclaw_listener(&PortListenerInfo {
+            pid: 1,
+            command: "openclaw-models".to_string()
+        }));
+        assert!(is_openclaw_listener(&PortListenerInfo {
+            pid: 2,
+            command: "OpenClaw".to_string()
+        }));
+        assert!(!is_openclaw_listener(&PortListenerInfo {
+            pid: 3,
+            command: "node".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_gateway_config_includes_mode_local() {
+        // The gateway config MUST include "mode": "local" to prevent
+        // "Gateway start blocked: set gateway.mode=local (current: unset)" error
+        let gateway_token = "test-token-123";
+        let gateway_auth_mode = "token";
+        let tailscale_mode = "off";
+        let gateway_port = 18789;
+        let gateway_bind = "127.0.0.1";
+
+        let config_val = serde_json::json!({
+            "gateway": {
+                "mode": "local",
+                "port": gateway_port,
+                "bind": gateway_bind,
+                "auth": { "mode": gateway_auth_mode, "token": gateway_token },
+                "tailscale": { "mode": tailscale_mode, "resetOnExit": false }
+            }
+        });
+
+        let gateway = config_val.get("gateway").expect("gateway key must exist");
+        assert_eq!(
+            gateway.get("mode").and_then(|v| v.as_str()),
+            Some("local"),
+            "gateway.mode must be set to 'local' to prevent startup failure"
+        );
+        assert_eq!(gateway.get("port").and_then(|v| v.as_u64()), Some(18789));
+        assert_eq!(
+            gateway
+                .get("auth")
+                .and_then(|a| a.get("token"))
+                .and_then(|t| t.as_str()),
+            Some("test-token-123")
+        );
+    }
+
+    #[test]
+    fn test_gateway_startup_command_sequence() {
+        // Verify the correct command sequence for gateway startup on Windows/WSL:
+        // 1. gateway install --force
+        // 2. gateway stop (prevent crash-loop before config is written)
+        // 3. ... config is written ...
+        // 4. systemctl reset-failed (recover from any crash-loop)
+        // 5. gateway stop
+        // 6. gateway start
+
+        let nvm_prefix = "source ~/.nvm/nvm.sh && ";
+
+        // Commands after install (prevent crash-loop)
+        let install_cmd = format!("{}openclaw gateway install --force", nvm_prefix);
+        let stop_after_install_cmd = format!("{}openclaw gateway stop || true", nvm_prefix);
+
+        // Commands before start (recover from crash-loop)
+        let reset_failed_cmd =
+            "systemctl --user reset-failed openclaw-gateway.service 2>/dev/null || true";
+        let stop_before_start_cmd = format!("{}openclaw gateway stop || true", nvm_prefix);
+        let start_cmd = format!("{}openclaw gateway start", nvm_prefix);
+
+        // Verify install is followed by stop
+        assert!(install_cmd.contains("gateway install --force"));
+        assert!(stop_after_install_cmd.contains("gateway stop"));
+
+        // Verify start sequence includes reset-failed
+        assert!(reset_failed_cmd.contains("reset-failed"));
+        assert!(reset_failed_cmd.contains("openclaw-gateway.service"));
+
+        // Verify stop comes before start
+        assert!(stop_before_start_cmd.contains("gateway stop"));
+        assert!(start_cmd.contains("gateway start"));
+        assert!(!start_cmd.contains("stop"));
+    }
+
+    #[test]
+    fn test_gateway_config_preserves_auth_token() {
+        // When reconfiguring, existing gateway auth token should be preserved
+        let existing_config = serde_json::json!({
+            "gateway": {
+                "mode": "local",
+                "auth": { "mode": "token", "token": "existing-secret-token" }
+            }
+        });
+
+        let token = existing_config
+            .get("gateway")
+            .and_then(|g| g.get("auth"))
+            .and_then(|a| a.get("token"))
+            .and_then(|t| t.as_str());
+
+        assert_eq!(
+            token,
+            Some("existing-secret-token"),
+            "Gateway auth token must be preserved during reconfiguration"
+        );
+    }
+
+    #[test]
+    fn test_parse_gateway_token_cli_output_rejects_empty_and_nullish_values() {
+        assert_eq!(
+            parse_gateway_token_cli_output("token-123\n"),
+            Some("token-123".to_string())
+        );
+        assert_eq!(
+            parse_gateway_token_cli_output("\"token-123\""),
+            Some("token-123".to_string())
+        );
+        assert_eq!(parse_gateway_token_cli_output(""), None);
+        assert_eq!(parse_gateway_token_cli_output("null"), None);
+        assert_eq!(parse_gateway_token_cli_output("undefined"), None);
+    }
+
+    #[test]
+    fn test_telegram_pairing_status_from_dm_policy() {
+        assert!(telegram_pairing_status_from_dm_policy("\"allowlist\""));
+        assert!(telegram_pairing_status_from_dm_policy("open"));
+        assert!(telegram_pairing_status_from_dm_policy("\"open\""));
+        assert!(!telegram_pairing_status_from_dm_policy("pairing"));
+        assert!(!telegram_pairing_status_from_dm_policy("\"pairing\""));
+    }
+
+    #[test]
+    fn test_extract_telegram_dm_policy_from_config_supports_default_and_legacy_layouts() {
+        let account_scoped = serde_json::json!({
+            "channels": {
+                "telegram": {
+                    "accounts": {
+                        "default": {
+                            "dmPolicy": "open"
+                        }
+                    }
+                }
+            }
+        });
+        assert_eq!(
+            extract_telegram_dm_policy_from_config(&account_scoped),
+            Some("open".to_string())
+        );
+
+        let legacy = serde_json::json!({
+            "channels": {
+                "telegram": {
+                    "dmPolicy": "pairing"
+                }
+            }
+        });
+        assert_eq!(
+            extract_telegram_dm_policy_from_config(&legacy),
+            Some("pairing".to_string())
+        );
+    }
+
+    #[test]
+    fn test_read_telegram_dm_policy_from_config_str_fallback_detects_linked_state() {
+        let config = r#"{
+            "channels": {
+                "telegram": {
+                    "dmPolicy": "open"
+                }
+            }
+        }"#;
+
+        let policy = read_telegram_dm_policy_from_config_str(config);
+        assert_eq!(policy, Some("open".to_string()));
+        assert!(telegram_pairing_status_from_dm_policy(policy.as_deref().unwrap()));
+    }
+
+    #[test]
+    fn test_read_telegram_dm_policy_via_cli_tries_legacy_after_primary_failure() {
+        let mut commands = Vec::new();
+        let policy = read_telegram_dm_policy_via_cli(|cmd| {
+            commands.push(cmd.to_string());
+            match cmd {
+                "openclaw config get channels.telegram.accounts.default.dmPolicy" => {
+                    Err("missing".to_string())
+                }
+                "openclaw config get channels.telegram.dmPolicy" => Ok("\"open\"".to_string()),
+                _ => Err("unexpected command".to_string()),
+            }
+        })
+        .expect("cli fallback succeeds");
+
+        assert_eq!(policy, Some("open".to_string()));
+        assert_eq!(
+            commands,
+            vec![
+                "openclaw config get channels.telegram.accounts.default.dmPolicy".to_string(),
+                "openclaw config get channels.telegram.dmPolicy".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_telegram_allow_from_entries_from_str_detects_approved_senders() {
+        let content = r#"{
+            "version": 1,
+            "allowFrom": ["5162540072"]
+        }"#;
+        assert_eq!(telegram_allow_from_entries_from_str(content), Some(1));
+
+        let empty = r#"{
+            "version": 1,
+            "allowFrom": []
+        }"#;
+        assert_eq!(telegram_allow_from_entries_from_str(empty), Some(0));
+    }
+
+    #[test]
+    fn test_telegram_allow_from_is_linked_local_detects_account_file() {
+        let temp_dir = std::env::temp_dir()
+            .join(format!("clawnetes-telegram-allowfrom-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&temp_dir).expect("create temp telegram credentials dir");
+        fs::write(
+            temp_dir.join("telegram-default-allowFrom.json"),
+            r#"{"version":1,"allowFrom":["5162540072"]}"#,
+        )
+        .expect("write allowFrom file");
+
+        assert!(telegram_allow_from_is_linked_local(&temp_dir));
+
+        let _ = fs::remove_dir_all(&temp_dir);
     }
-}
 
-#[command]
-fn validate_openclaw_config(
-    remote: Option<RemoteInfo>,
-    is_wsl: Option<bool>,
-) -> Result<String, String> {
-    if let Some(r) = remote {
-        let sess = connect_ssh(&r).map_err(|e| format!("SSH connect failed: {}", e))?;
-        let os_type = execute_ssh(&sess, "uname -s")
-            .unwrap_or_default()
-            .trim()
-            .to_string();
-        let prefix = get_env_prefix(&os_type);
-        execute_ssh(&sess, &format!("{}openclaw config validate 2>&1", prefix))
-    } else if is_wsl.unwrap_or(false) {
-        shell_command("wsl -- openclaw config validate 2>&1")
-    } else {
-        shell_command("openclaw config validate 2>&1")
+    #[test]
+    fn test_whatsapp_session_is_linked_detects_nested_files() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("clawnetes-whatsapp-test-{}", uuid::Uuid::new_v4()));
+        let nested = temp_dir.join("nested");
+        fs::create_dir_all(&nested).expect("create temp whatsapp dir");
+        fs::write(nested.join("session.json"), "{}").expect("write temp whatsapp file");
+
+        assert!(whatsapp_session_is_linked(&temp_dir));
+
+        let _ = fs::remove_dir_all(&temp_dir);
     }
-}
 
-#[command]
-async fn start_whatsapp_login(
-    gateway_port: u16,
-    remote: Option<RemoteInfo>,
-) -> Result<String, String> {
-    use futures_util::{SinkExt, StreamExt};
-    use tokio_tungstenite::connect_async;
-    use tokio_tungstenite::tungstenite::protocol::Message;
+    #[test]
+    fn test_whatsapp_session_is_linked_false_for_missing_or_empty_dir() {
+        let missing_dir =
+            std::env::temp_dir().join(format!("clawnetes-whatsapp-test-{}", uuid::Uuid::new_v4()));
+        assert!(!whatsapp_session_is_linked(&missing_dir));
 
-    // Read auth token from the correct host (remote via SSH, or local filesystem).
-    let auth_token: Option<String> = if let Some(ref r) = remote {
-        let sess = connect_ssh(r)?;
-        let home = execute_ssh(&sess, "echo $HOME").unwrap_or_default();
-        let home = home.trim();
-        let json_str = execute_ssh(&sess, &format!("cat {}/.openclaw/openclaw.json", home))
-            .unwrap_or_default();
-        serde_json::from_str::<serde_json::Value>(&json_str)
-            .ok()
-            .and_then(|c| {
-                c.get("gateway")
-                    .and_then(|g| g.get("auth"))
-                    .and_then(|a| a.get("token"))
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            })
-    } else {
-        let home_dir = dirs::home_dir()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_default();
-        let openclaw_json_str =
-            std::fs::read_to_string(format!("{}/.openclaw/openclaw.json", home_dir))
-                .unwrap_or_default();
-        serde_json::from_str::<serde_json::Value>(&openclaw_json_str)
-            .ok()
-            .and_then(|c| {
-                c.get("gateway")
-                    .and_then(|g| g.get("auth"))
-                    .and_then(|a| a.get("token"))
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            })
-    };
+        fs::create_dir_all(&missing_dir).expect("create empty whatsapp dir");
+        assert!(!whatsapp_session_is_linked(&missing_dir));
 
-    // On the very first connection to a fresh gateway the gateway responds NOT_PAIRED to the
-    // connect handshake, then immediately auto-approves the new client device.  The connection
-    // is closed right after that response, so any RPC sent on it is lost.  We retry once (after
-    // a short pause) so the second connection sees the now-approved device and gets ok:true.
-    let url = format!("ws://127.0.0.1:{}", gateway_port);
-    // The gateway auto-approves new client devices asynchronously; from the logs this takes
-    // up to ~30 seconds.  We retry with a 10-second pause so we don't exhaust retries before
-    // approval completes.  5 attempts × 10 s = up to 40 s total wait, well above the observed
-    // ~30 s worst case.
-    let max_attempts: u8 = 5;
-    for attempt in 0..max_attempts {
-        if attempt > 0 {
-            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
-        }
+        let _ = fs::remove_dir_all(&missing_dir);
+    }
 
-        let (mut ws_stream, _) = connect_async(&url)
-            .await
-            .map_err(|e| format!("WebSocket connect failed: {}", e))?;
-
-        let connect_req_id = uuid::Uuid::new_v4().to_string();
-        let mut connect_msg = serde_json::json!({
-            "type": "req",
-            "id": connect_req_id,
-            "method": "connect",
-            "params": {
-                "client": {
-                    "id": "gateway-client",
-                    "version": "1.0",
-                    "platform": std::env::consts::OS,
-                    "mode": "backend"
-                },
-                "minProtocol": 3,
-                "maxProtocol": 3,
-                "role": "operator",
-                "scopes": ["operator.admin"]
-            }
-        });
-        if let Some(ref token) = auth_token {
-            if let Some(params) = connect_msg
-                .get_mut("params")
-                .and_then(|p| p.as_object_mut())
-            {
-                params.insert("auth".to_string(), serde_json::json!({ "token": token }));
-            }
-        }
+    #[test]
+    fn test_parse_dashboard_url_cli_output_finds_url_amid_other_output() {
+        let output = "Doctor warnings...\nDashboard URL: http://127.0.0.1:18789/#token=abc123\nCopied to clipboard.\n";
+        assert_eq!(
+            parse_dashboard_url_cli_output(output),
+            Some("http://127.0.0.1:18789/#token=abc123".to_string())
+        );
+        assert_eq!(parse_dashboard_url_cli_output("no dashboard line"), None);
+    }
 
-        ws_stream
-            .send(Message::Text(connect_msg.to_string()))
-            .await
-            .map_err(|e| format!("WebSocket send connect failed: {}", e))?;
-
-        let mut handshake_ok = false;
-        let mut needs_reconnect = false;
-        while let Some(msg) = ws_stream.next().await {
-            match msg {
-                Ok(Message::Text(text)) => {
-                    let val: serde_json::Value =
-                        serde_json::from_str(&text).unwrap_or(serde_json::json!({}));
-                    if val.get("id").and_then(|v| v.as_str()) == Some(&connect_req_id) {
-                        if val.get("ok").and_then(|v| v.as_bool()) == Some(true) {
-                            handshake_ok = true;
-                            break;
-                        } else {
-                            // NOT_PAIRED / device-required: gateway has started async approval of
-                            // this client device.  Close and retry after a pause so the approval
-                            // can complete before the next attempt.
-                            let error_code = val
-                                .get("error")
-                                .and_then(|e| e.get("code"))
-                                .and_then(|c| c.as_str())
-                                .unwrap_or("");
-                            let detail_code = val
-                                .get("error")
-                                .and_then(|e| e.get("details"))
-                                .and_then(|d| d.get("code"))
-                                .and_then(|c| c.as_str())
-                                .unwrap_or("");
-                            if error_code == "NOT_PAIRED"
-                                || detail_code == "DEVICE_IDENTITY_REQUIRED"
-                            {
-                                needs_reconnect = true;
-                                break;
-                            }
-                            return Err(format!("Gateway connect handshake failed: {}", text));
-                        }
+    #[test]
+    fn test_rebuild_models_catalog_replaces_stale_openai_entry_during_merge() {
+        let mut config_json = serde_json::json!({
+            "agents": {
+                "defaults": {
+                    "models": {
+                        "openai/gpt-5.4": {}
                     }
                 }
-                Ok(Message::Close(_)) => break,
-                Err(e) => return Err(format!("WebSocket error during handshake: {}", e)),
-                _ => {}
             }
-        }
+        });
 
-        if needs_reconnect {
-            continue;
-        }
-        if !handshake_ok {
-            return Err("Gateway connect handshake timed out".to_string());
+        let effective_primary_model = "openai-codex/gpt-5.4".to_string();
+        let effective_fallback_models = vec!["openai-codex/gpt-5.4-mini".to_string()];
+
+        if let Some(defaults) = config_json
+            .get_mut("agents")
+            .and_then(|a| a.get_mut("defaults"))
+            .and_then(|d| d.as_object_mut())
+        {
+            defaults.insert(
+                "models".to_string(),
+                serde_json::Value::Object(build_effective_models_catalog(
+                    &effective_primary_model,
+                    &effective_fallback_models,
+                )),
+            );
         }
 
-        // Handshake succeeded — request QR code.
-        let request_id = uuid::Uuid::new_v4().to_string();
-        let rpc_msg = serde_json::json!({
-            "type": "req",
-            "id": request_id,
-            "method": "web.login.start",
-            "params": { "timeoutMs": 30000, "force": true }
-        });
+        let models = config_json
+            .get("agents")
+            .and_then(|a| a.get("defaults"))
+            .and_then(|d| d.get("models"))
+            .and_then(|m| m.as_object())
+            .expect("models object");
 
-        ws_stream
-            .send(Message::Text(rpc_msg.to_string()))
-            .await
-            .map_err(|e| format!("WebSocket send failed: {}", e))?;
-
-        while let Some(msg) = ws_stream.next().await {
-            match msg {
-                Ok(Message::Text(text)) => {
-                    let val: serde_json::Value =
-                        serde_json::from_str(&text).unwrap_or(serde_json::json!({}));
-                    if val.get("id").and_then(|v| v.as_str()) == Some(&request_id) {
-                        if val.get("ok").and_then(|v| v.as_bool()).unwrap_or(false) {
-                            if let Some(qr) = val
-                                .get("payload")
-                                .and_then(|r| r.get("qrDataUrl"))
-                                .and_then(|v| v.as_str())
-                            {
-                                return Ok(qr.to_string());
-                            }
-                            // ok:true but no qrDataUrl — already linked or unexpected format
-                            return Err(
-                                "Gateway returned ok but no QR code (already linked?)".to_string()
-                            );
-                        } else if let Some(err) = val.get("error") {
-                            return Err(format!("Gateway error: {}", err));
-                        }
-                    }
+        assert!(models.contains_key("openai-codex/gpt-5.4"));
+        assert!(models.contains_key("openai-codex/gpt-5.4-mini"));
+        assert!(!models.contains_key("openai/gpt-5.4"));
+    }
+
+    #[test]
+    fn test_extract_gateway_token_from_config_reads_gateway_auth_token() {
+        let config = serde_json::json!({
+            "gateway": {
+                "auth": {
+                    "token": "config-token-456"
                 }
-                Ok(Message::Close(_)) => break,
-                Err(e) => return Err(format!("WebSocket error: {}", e)),
-                _ => {}
             }
-        }
+        });
 
-        // No QR received on this attempt; if retries remain, try again.
+        assert_eq!(
+            extract_gateway_token_from_config(&config.to_string(), "config").unwrap(),
+            "config-token-456"
+        );
     }
 
-    Err("No QR code received from gateway after retries".to_string())
-}
-
-#[command]
-async fn wait_whatsapp_login(
-    gateway_port: u16,
-    remote: Option<RemoteInfo>,
-) -> Result<bool, String> {
-    use futures_util::{SinkExt, StreamExt};
-    use tokio_tungstenite::connect_async;
-    use tokio_tungstenite::tungstenite::protocol::Message;
-
-    let auth_token: Option<String> = if let Some(ref r) = remote {
-        let sess = connect_ssh(r)?;
-        let home = execute_ssh(&sess, "echo $HOME").unwrap_or_default();
-        let home = home.trim();
-        let json_str = execute_ssh(&sess, &format!("cat {}/.openclaw/openclaw.json", home))
-            .unwrap_or_default();
-        serde_json::from_str::<serde_json::Value>(&json_str)
-            .ok()
-            .and_then(|c| {
-                c.get("gateway")
-                    .and_then(|g| g.get("auth"))
-                    .and_then(|a| a.get("token"))
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            })
-    } else {
-        let home_dir = dirs::home_dir()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_default();
-        let openclaw_json_str =
-            std::fs::read_to_string(format!("{}/.openclaw/openclaw.json", home_dir))
-                .unwrap_or_default();
-        serde_json::from_str::<serde_json::Value>(&openclaw_json_str)
-            .ok()
-            .and_then(|c| {
-                c.get("gateway")
-                    .and_then(|g| g.get("auth"))
-                    .and_then(|a| a.get("token"))
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            })
-    };
+    #[test]
+    fn test_wsl_root_command_uses_explicit_distro() {
+        // wsl_root_command should use `-d Ubuntu` for robustness
+        // Verify the expected argument structure
+        let cmd = "echo hello";
+        let expected_args = vec![
+            "-d",
+            "Ubuntu",
+            "--user",
+            "root",
+            "--",
+            "/bin/bash",
+            "-c",
+            cmd,
+        ];
+        assert_eq!(expected_args[0], "-d");
+        assert_eq!(expected_args[1], "Ubuntu");
+        assert_eq!(expected_args[2], "--user");
+        assert_eq!(expected_args[3], "root");
+        assert_eq!(expected_args[7], cmd);
+    }
 
-    let url = format!("ws://127.0.0.1:{}", gateway_port);
-    let max_attempts: u8 = 5;
-    for attempt in 0..max_attempts {
-        if attempt > 0 {
-            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
-        }
+    #[test]
+    fn test_wsl_conf_content_format() {
+        // The wsl.conf written to set default user must follow INI format
+        let expected_content = "[user]\ndefault=openclaw\n";
+        assert!(expected_content.starts_with("[user]"));
+        assert!(expected_content.contains("default=openclaw"));
+        // Verify it's valid INI-style (section header + key=value)
+        let lines: Vec<&str> = expected_content.trim().lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "[user]");
+        assert_eq!(lines[1], "default=openclaw");
+    }
 
-        let (mut ws_stream, _) = connect_async(&url)
-            .await
-            .map_err(|e| format!("WebSocket connect failed: {}", e))?;
-
-        let connect_req_id = uuid::Uuid::new_v4().to_string();
-        let mut connect_msg = serde_json::json!({
-            "type": "req",
-            "id": connect_req_id,
-            "method": "connect",
-            "params": {
-                "client": {
-                    "id": "gateway-client",
-                    "version": "1.0",
-                    "platform": std::env::consts::OS,
-                    "mode": "backend"
-                },
-                "minProtocol": 3,
-                "maxProtocol": 3,
-                "role": "operator",
-                "scopes": ["operator.admin"]
-            }
-        });
-        if let Some(ref token) = auth_token {
-            if let Some(params) = connect_msg
-                .get_mut("params")
-                .and_then(|p| p.as_object_mut())
-            {
-                params.insert("auth".to_string(), serde_json::json!({ "token": token }));
-            }
-        }
+    #[test]
+    fn test_wsl_user_setup_command_structure() {
+        // The user setup command should create user, set password, and add to sudo group
+        let user_cmd = "id openclaw >/dev/null 2>&1 || (useradd -m -s /bin/bash openclaw && echo 'openclaw:openclaw' | chpasswd && usermod -aG sudo openclaw)";
+        // Checks for existing user first
+        assert!(user_cmd.contains("id openclaw"));
+        // Creates with home dir and bash shell
+        assert!(user_cmd.contains("useradd -m -s /bin/bash openclaw"));
+        // Sets password
+        assert!(user_cmd.contains("chpasswd"));
+        // Adds to sudo group
+        assert!(user_cmd.contains("usermod -aG sudo openclaw"));
+    }
 
-        ws_stream
-            .send(Message::Text(connect_msg.to_string()))
-            .await
-            .map_err(|e| format!("WebSocket send connect failed: {}", e))?;
-
-        let mut handshake_ok = false;
-        let mut needs_reconnect = false;
-        while let Some(msg) = ws_stream.next().await {
-            match msg {
-                Ok(Message::Text(text)) => {
-                    let val: serde_json::Value =
-                        serde_json::from_str(&text).unwrap_or(serde_json::json!({}));
-                    if val.get("id").and_then(|v| v.as_str()) == Some(&connect_req_id) {
-                        if val.get("ok").and_then(|v| v.as_bool()) == Some(true) {
-                            handshake_ok = true;
-                            break;
-                        } else {
-                            let error_code = val
-                                .get("error")
-                                .and_then(|e| e.get("code"))
-                                .and_then(|c| c.as_str())
-                                .unwrap_or("");
-                            let detail_code = val
-                                .get("error")
-                                .and_then(|e| e.get("details"))
-                                .and_then(|d| d.get("code"))
-                                .and_then(|c| c.as_str())
-                                .unwrap_or("");
-                            if error_code == "NOT_PAIRED"
-                                || detail_code == "DEVICE_IDENTITY_REQUIRED"
-                            {
-                                needs_reconnect = true;
-                                break;
-                            }
-                            return Err(format!("Gateway connect handshake failed: {}", text));
-                        }
-                    }
-                }
-                Ok(Message::Close(_)) => break,
-                Err(e) => return Err(format!("WebSocket error during handshake: {}", e)),
-                _ => {}
-            }
-        }
+    #[test]
+    fn test_wait_for_wsl_ready_command_args() {
+        // wait_for_wsl_ready should use explicit distro and root user
+        let expected_args = ["-d", "Ubuntu", "-u", "root", "--", "echo", "ready"];
+        assert_eq!(expected_args[0], "-d");
+        assert_eq!(expected_args[1], "Ubuntu");
+        assert_eq!(expected_args[2], "-u");
+        assert_eq!(expected_args[3], "root");
+        assert_eq!(expected_args[6], "ready");
+    }
 
-        if needs_reconnect {
-            continue;
-        }
-        if !handshake_ok {
-            return Err("Gateway connect handshake timed out".to_string());
-        }
+    #[test]
+    fn test_wsl_write_file_escapes_single_quotes() {
+        // wsl_write_file uses printf '%s' '...' > file, so single quotes must be escaped
+        let content = "it's a test with 'quotes' inside";
+        let escaped = content.replace('\'', "'\\''");
+        let cmd = format!("printf '%s' '{}' > \"{}\"", escaped, "/tmp/test.txt");
 
-        let request_id = uuid::Uuid::new_v4().to_string();
-        let rpc_msg = serde_json::json!({
-            "type": "req",
-            "id": request_id,
-            "method": "web.login.wait",
-            "params": { "timeoutMs": 120000 }
-        });
+        // Verify the escaped content doesn't have unmatched quotes
+        assert!(cmd.contains("it'\\''s a test with '\\''quotes'\\'' inside"));
+        // Verify the command targets the right file
+        assert!(cmd.contains("/tmp/test.txt"));
+    }
 
-        ws_stream
-            .send(Message::Text(rpc_msg.to_string()))
-            .await
-            .map_err(|e| format!("WebSocket send failed: {}", e))?;
-
-        let result = tokio::time::timeout(std::time::Duration::from_secs(130), async {
-            while let Some(msg) = ws_stream.next().await {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        let val: serde_json::Value =
-                            serde_json::from_str(&text).unwrap_or(serde_json::json!({}));
-                        if val.get("id").and_then(|v| v.as_str()) == Some(&request_id) {
-                            if val.get("ok").and_then(|v| v.as_bool()).unwrap_or(false) {
-                                let connected = val
-                                    .get("payload")
-                                    .and_then(|r| r.get("connected"))
-                                    .and_then(|v| v.as_bool())
-                                    .unwrap_or(false);
-                                return Ok(connected);
-                            } else if let Some(err) = val.get("error") {
-                                return Err(format!("Gateway error: {}", err));
-                            }
-                        }
-                    }
-                    Ok(Message::Close(_)) => break,
-                    Err(e) => return Err(format!("WebSocket error: {}", e)),
-                    _ => {}
-                }
-            }
-            Ok(false)
-        })
-        .await;
+    #[test]
+    fn test_wsl_write_file_handles_json_content() {
+        // JSON content often has no single quotes, but may have special chars
+        let content = r#"{"gateway":{"mode":"local","auth":{"token":"abc123"}}}"#;
+        let escaped = content.replace('\'', "'\\''");
+        // JSON typically has no single quotes, so escaped should equal original
+        assert_eq!(escaped, content);
+    }
 
-        return match result {
-            Ok(r) => r,
-            Err(_) => Err("WhatsApp login wait timed out".to_string()),
-        };
+    #[test]
+    fn test_wsl_home_dir_command_structure() {
+        // wsl_home_dir calls shell_command("echo $HOME") which on Windows
+        // routes through wsl -- /bin/bash -c "echo $HOME"
+        // Verify the command string is correct
+        let cmd = "echo $HOME";
+        assert_eq!(cmd, "echo $HOME");
+        // The result should be trimmed (no trailing newline)
+        let simulated_output = "/home/testuser\n";
+        assert_eq!(simulated_output.trim(), "/home/testuser");
     }
 
-    Err("Gateway connect handshake failed after retries".to_string())
-}
-#[command]
-async fn wipe_whatsapp_session() -> Result<(), String> {
-    let home_dir = dirs::home_dir()
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_default();
-    let session_dir = format!("{}/.openclaw/credentials/whatsapp/default", home_dir);
-    if std::path::Path::new(&session_dir).exists() {
-        std::fs::remove_dir_all(&session_dir)
-            .map_err(|e| format!("Failed to delete whatsapp session: {}", e))?;
+    #[test]
+    fn test_wsl_read_file_command_structure() {
+        // wsl_read_file calls shell_command("cat \"path\" 2>/dev/null")
+        let path = "/home/user/.openclaw/openclaw.json";
+        let cmd = format!("cat \"{}\" 2>/dev/null", path);
+        assert!(cmd.contains("cat"));
+        assert!(cmd.contains(path));
+        assert!(cmd.contains("2>/dev/null"), "stderr should be suppressed");
     }
-    Ok(())
-}
 
-/// Check if WhatsApp creds are saved by calling web.login.start WITHOUT force.
-/// If creds exist, OpenClaw returns ok:true with no qrDataUrl ("already linked").
-#[command]
-async fn check_whatsapp_linked(gateway_port: u16) -> Result<bool, String> {
-    use futures_util::{SinkExt, StreamExt};
-    use tokio_tungstenite::connect_async;
-    use tokio_tungstenite::tungstenite::protocol::Message;
+    #[test]
+    fn test_configure_agent_uses_string_paths() {
+        // On all platforms, configure_agent now uses String paths (not PathBuf)
+        // so that on Windows the WSL home (/home/user) is used instead of C:\Users\user
+        let home = "/home/testuser";
+        let openclaw_root = format!("{}/.openclaw", home);
+        let workspace = format!("{}/workspace", openclaw_root);
+        let agents_dir = format!("{}/agents/main/agent", openclaw_root);
 
-    let url = format!("ws://127.0.0.1:{}", gateway_port);
-    let (mut ws_stream, _) = connect_async(&url)
-        .await
-        .map_err(|e| format!("WebSocket connect failed: {}", e))?;
-
-    // Connect handshake
-    let connect_req_id = uuid::Uuid::new_v4().to_string();
-    let mut connect_msg = serde_json::json!({
-        "type": "req",
-        "id": connect_req_id,
-        "method": "connect",
-        "params": {
-            "client": {
-                "id": "gateway-client",
-                "version": "1.0",
-                "platform": std::env::consts::OS,
-                "mode": "backend"
-            },
-            "minProtocol": 3,
-            "maxProtocol": 3,
-            "role": "operator",
-            "scopes": ["operator.admin"]
-        }
-    });
+        assert_eq!(openclaw_root, "/home/testuser/.openclaw");
+        assert_eq!(workspace, "/home/testuser/.openclaw/workspace");
+        assert_eq!(agents_dir, "/home/testuser/.openclaw/agents/main/agent");
 
-    let home_dir = dirs::home_dir()
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_default();
-    let openclaw_json_str =
-        std::fs::read_to_string(format!("{}/.openclaw/openclaw.json", home_dir))
-            .unwrap_or_default();
-    if let Ok(oc_config) = serde_json::from_str::<serde_json::Value>(&openclaw_json_str) {
-        if let Some(token) = oc_config
-            .get("gateway")
-            .and_then(|g| g.get("auth"))
-            .and_then(|a| a.get("token"))
-            .and_then(|v| v.as_str())
-        {
-            if let Some(params) = connect_msg
-                .get_mut("params")
-                .and_then(|p| p.as_object_mut())
-            {
-                params.insert("auth".to_string(), serde_json::json!({ "token": token }));
-            }
-        }
+        // Verify these are Unix-style paths (not Windows backslashes)
+        assert!(!openclaw_root.contains('\\'));
+        assert!(!workspace.contains('\\'));
     }
 
-    ws_stream
-        .send(Message::Text(connect_msg.to_string()))
-        .await
-        .map_err(|e| format!("WebSocket send connect failed: {}", e))?;
+    #[test]
+    fn test_wsl_remove_dir_command_structure() {
+        // wsl_remove_dir should use rm -rf with the path and expand ~/ to $HOME/
+        let path = "~/.openclaw";
+        let cmd = if path.starts_with("~/") {
+            format!("rm -rf \"$HOME/{}\"", &path[2..])
+        } else {
+            format!("rm -rf \"{}\"", path)
+        };
+        assert!(cmd.contains("rm -rf"));
+        assert!(cmd.contains("$HOME/.openclaw"));
+    }
 
-    // Wait for handshake response
-    while let Some(msg) = ws_stream.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                let val: serde_json::Value =
-                    serde_json::from_str(&text).unwrap_or(serde_json::json!({}));
-                if val.get("id").and_then(|v| v.as_str()) == Some(&connect_req_id) {
-                    if val.get("ok").and_then(|v| v.as_bool()) == Some(true) {
-                        break;
-                    } else {
-                        // NOT_PAIRED from the connect handshake means WhatsApp is definitively
-                        // not linked — return false rather than an error.
-                        let error_code = val
-                            .get("error")
-                            .and_then(|e| e.get("code"))
-                            .and_then(|c| c.as_str())
-                            .unwrap_or("");
-                        let detail_code = val
-                            .get("error")
-                            .and_then(|e| e.get("details"))
-                            .and_then(|d| d.get("code"))
-                            .and_then(|c| c.as_str())
-                            .unwrap_or("");
-                        if error_code == "NOT_PAIRED" || detail_code == "DEVICE_IDENTITY_REQUIRED" {
-                            return Ok(false);
-                        }
-                        return Err(format!("Gateway handshake failed: {}", text));
-                    }
-                }
+    #[test]
+    fn test_validate_license_response_accepts_clean_purchase() {
+        let response = serde_json::json!({
+            "success": true,
+            "purchase": {
+                "refunded": false,
+                "chargebacked": false
             }
-            Ok(Message::Close(_)) => return Err("WebSocket closed during handshake".to_string()),
-            Err(e) => return Err(format!("WebSocket error: {}", e)),
-            _ => {}
-        }
-    }
+        });
 
-    // Call web.login.start WITHOUT force — if creds exist, returns "already linked" with no QR
-    let request_id = uuid::Uuid::new_v4().to_string();
-    let rpc_msg = serde_json::json!({
-        "type": "req",
-        "id": request_id,
-        "method": "web.login.start",
-        "params": { "timeoutMs": 10000 }
-    });
+        assert!(validate_license_response(&response).is_ok());
+    }
 
-    ws_stream
-        .send(Message::Text(rpc_msg.to_string()))
-        .await
-        .map_err(|e| format!("WebSocket send failed: {}", e))?;
-
-    let timeout = tokio::time::timeout(std::time::Duration::from_secs(15), async {
-        while let Some(msg) = ws_stream.next().await {
-            match msg {
-                Ok(Message::Text(text)) => {
-                    let val: serde_json::Value =
-                        serde_json::from_str(&text).unwrap_or(serde_json::json!({}));
-                    if val.get("id").and_then(|v| v.as_str()) == Some(&request_id) {
-                        if val.get("ok").and_then(|v| v.as_bool()).unwrap_or(false) {
-                            // If no qrDataUrl → creds exist, already linked
-                            let has_qr = val
-                                .get("payload")
-                                .and_then(|r| r.get("qrDataUrl"))
-                                .and_then(|v| v.as_str())
-                                .is_some();
-                            return Ok(!has_qr);
-                        } else {
-                            return Ok(false);
-                        }
-                    }
-                }
-                Ok(Message::Close(_)) => break,
-                Err(e) => return Err(format!("WebSocket error: {}", e)),
-                _ => {}
+    #[test]
+    fn test_validate_license_response_rejects_refunded_purchase() {
+        let response = serde_json::json!({
+            "success": true,
+            "purchase": {
+                "refunded": true,
+                "chargebacked": false
             }
-        }
-        Ok(false)
-    })
-    .await;
+        });
 
-    match timeout {
-        Ok(result) => result,
-        Err(_) => Ok(false),
+        assert_eq!(
+            validate_license_response(&response).unwrap_err(),
+            "License has been refunded."
+        );
     }
-}
-
-#[command]
-async fn restart_openclaw_gateway(remote: Option<RemoteInfo>) -> Result<(), String> {
-    if let Some(r) = remote {
-        let sess = connect_ssh(&r)?;
-        let nvm_prefix = get_env_prefix(&execute_ssh(&sess, "uname -s")?.trim().to_string());
-        execute_ssh(&sess, &format!("{}openclaw gateway restart", nvm_prefix))?;
-    } else {
-        // 'openclaw gateway restart' uses launchctl kickstart which fails with
-        // "Operation not permitted" from Tauri's subprocess context.
-        // Use the same stop → bootstrap → start pattern as start_gateway() instead.
-        let _ = shell_command("openclaw gateway stop");
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
 
-        #[cfg(target_os = "macos")]
-        if let Some(home) = dirs::home_dir() {
-            let plist = home.join("Library/LaunchAgents/ai.openclaw.gateway.plist");
-            if plist.exists() {
-                let _ = shell_command(&format!(
-                    "launchctl bootstrap gui/$(id -u) \"{}\"",
-                    plist.to_string_lossy()
-                ));
+    #[test]
+    fn test_validate_license_response_rejects_chargebacked_purchase() {
+        let response = serde_json::json!({
+            "success": true,
+            "purchase": {
+                "refunded": false,
+                "chargebacked": true
             }
-        }
+        });
 
-        shell_command("openclaw gateway start")
-            .map_err(|e| format!("Gateway restart failed: {}", e))?;
+        assert_eq!(
+            validate_license_response(&response).unwrap_err(),
+            "License has been chargebacked."
+        );
     }
-    // Wait for gateway to fully start before returning
-    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
-    Ok(())
-}
 
-fn main() {
-    tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![
-            install_local_nodejs,
-            check_prerequisites,
-            install_openclaw,
-            configure_agent,
-            start_gateway,
-            initialize_agent_sessions,
-            generate_pairing_code,
-            get_dashboard_url,
-            approve_pairing,
-            close_app,
-            install_skill,
-            install_remote_skill,
-            start_provider_auth,
-            get_openclaw_version,
-            uninstall_openclaw,
-            run_doctor_repair,
-            run_security_audit_fix,
-            read_workspace_files,
-            save_workspace_files,
-            create_custom_skill,
-            test_ssh_connection,
-            setup_remote_openclaw,
-            start_ssh_tunnel,
-            stop_ssh_tunnel,
-            check_remote_prerequisites,
-            get_remote_openclaw_version,
-            run_remote_doctor_repair,
-            run_remote_security_audit_fix,
-            uninstall_remote_openclaw,
-            update_remote_openclaw,
-            get_remote_gateway_token,
-            verify_tunnel_connectivity,
-            get_current_config,
-            has_saved_license,
-            verify_and_store_license,
-            check_pairing_status,
-            check_messaging_link_status,
-            get_ollama_models,
-            get_lmstudio_models,
-            validate_openclaw_config,
-            start_whatsapp_login,
-            wait_whatsapp_login,
-            wipe_whatsapp_session,
-            check_whatsapp_linked,
-            restart_openclaw_gateway
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
+    #[test]
+    fn test_validate_license_response_rejects_unsuccessful_response() {
+        let response = serde_json::json!({
+            "success": false
+        });
+
+        assert_eq!(
+            validate_license_response(&response).unwrap_err(),
+            "Invalid license key."
+        );
+    }
+
+    #[test]
+    fn test_encrypt_saved_license_round_trip() {
+        let key = derive_license_encryption_key("machine-a");
+        let encrypted =
+            encrypt_saved_license_value("LICENSE-123", &key).expect("license should encrypt");
+        let decrypted =
+            decrypt_saved_license_value(&encrypted, &key).expect("license should decrypt");
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
+        assert_eq!(decrypted, "LICENSE-123");
+    }
 
     #[test]
-    fn test_agent_config_deserialization() {
-        let json_data = r#"
-        {
-            "provider": "anthropic",
-            "api_key": "sk-test-123",
-            "model": "anthropic/claude-opus-4-6",
-            "user_name": "Test User",
-            "agent_name": "Test Agent",
-            "agents": [
-                {
-                    "id": "agent-1",
-                    "name": "SubAgent 1",
-                    "model": "openai/gpt-4o",
-                    "emoji": "🤖"
-                }
-            ]
-        }
-        "#;
+    fn test_saved_license_decrypt_fails_with_different_machine_key() {
+        let key = derive_license_encryption_key("machine-a");
+        let wrong_key = derive_license_encryption_key("machine-b");
+        let encrypted =
+            encrypt_saved_license_value("LICENSE-123", &key).expect("license should encrypt");
 
-        let config: AgentConfig =
-            serde_json::from_str(json_data).expect("Failed to deserialize AgentConfig");
+        assert_eq!(
+            decrypt_saved_license_value(&encrypted, &wrong_key).unwrap_err(),
+            "Saved license cannot be decrypted on this machine."
+        );
+    }
 
-        assert_eq!(config.provider, "anthropic");
-        assert_eq!(config.api_key, "sk-test-123");
-        assert_eq!(config.model, "anthropic/claude-opus-4-6");
-        assert_eq!(config.user_name, "Test User");
+    #[test]
+    fn test_parse_windows_machine_guid_extracts_value() {
+        let output = "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft\\Cryptography\r\n    MachineGuid    REG_SZ    1234-5678\r\n";
+        assert_eq!(
+            parse_windows_machine_guid(output).as_deref(),
+            Some("1234-5678")
+        );
+    }
 
-        let agents = config.agents.expect("Agents list should be present");
-        assert_eq!(agents.len(), 1);
-        assert_eq!(agents[0].name, "SubAgent 1");
-        assert_eq!(agents[0].emoji, Some("🤖".to_string()));
+    #[test]
+    fn test_read_first_nonempty_file_prefers_first_nonempty_candidate() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("clawnetes-license-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&temp_dir).expect("temp dir should be created");
+        let first = temp_dir.join("first");
+        let second = temp_dir.join("second");
+        fs::write(&first, "   ").expect("first file should be written");
+        fs::write(&second, "machine-id-123\n").expect("second file should be written");
+
+        let result = read_first_nonempty_file(&[first.clone(), second.clone()])
+            .expect("machine id lookup should succeed");
+
+        assert_eq!(result.as_deref(), Some("machine-id-123"));
+
+        let _ = fs::remove_file(first);
+        let _ = fs::remove_file(second);
+        let _ = fs::remove_dir(temp_dir);
     }
 
     #[test]
-    fn test_apply_agent_overrides_serializes_explicit_tools_and_subagents() {
-        let agent = AgentData {
-            id: "data-analysis".to_string(),
-            name: "Data Analysis".to_string(),
-            model: "anthropic/claude-sonnet-4-6".to_string(),
-            fallback_models: None,
-            skills: None,
-            vibe: None,
-            emoji: None,
-            identity_md: None,
-            user_md: None,
-            soul_md: None,
-            tools_md: None,
-            agents_md: None,
-            heartbeat_md: None,
-            memory_md: None,
-            subagents: Some(SubagentConfig {
-                allow_agents: vec!["reporting".to_string()],
-            }),
-            tools: Some(AgentToolsConfig {
-                profile: Some("coding".to_string()),
-                allow: Some(vec!["browser".to_string(), "web_search".to_string()]),
-                deny: Some(vec!["subagents".to_string()]),
-                elevated: Some(ElevatedToolConfig { enabled: true }),
-            }),
-        };
-        let mut agent_obj = serde_json::json!({
-            "id": agent.id,
-            "name": agent.name
+    fn test_decrypt_saved_license_rejects_corrupt_payload() {
+        let key = derive_license_encryption_key("machine-a");
+        assert!(decrypt_saved_license_value(
+            "{\"version\":1,\"nonce\":\"bad\",\"ciphertext\":\"bad\"}",
+            &key
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_gateway_bind_for_exposure_maps_modes() {
+        assert_eq!(
+            gateway_bind_for_exposure("loopback"),
+            Ok(("loopback", None))
+        );
+        assert_eq!(gateway_bind_for_exposure("lan"), Ok(("lan", None)));
+        assert_eq!(
+            gateway_bind_for_exposure("all"),
+            Ok(("custom", Some("0.0.0.0")))
+        );
+        assert!(gateway_bind_for_exposure("public").is_err());
+    }
+
+    #[test]
+    fn test_apply_gateway_exposure_clears_custom_host_when_leaving_all_mode() {
+        let mut config = serde_json::json!({
+            "gateway": { "port": 18789, "bind": "custom", "customBindHost": "0.0.0.0" }
         });
+        apply_gateway_exposure(&mut config, "loopback", None);
+        assert_eq!(config["gateway"]["bind"], "loopback");
+        assert!(config["gateway"].get("customBindHost").is_none());
+        assert_eq!(config["gateway"]["port"], 18789);
+    }
 
-        apply_agent_overrides(&mut agent_obj, &agent);
+    #[test]
+    fn test_gateway_exposure_warnings_flag_weak_auth_for_non_loopback() {
+        assert!(gateway_exposure_warnings("loopback", "none", None).is_empty());
+        assert!(gateway_exposure_warnings("lan", "none", None)[0].contains("auth is disabled"));
+        assert!(gateway_exposure_warnings("lan", "token", Some("short"))[0].contains("24"));
+        assert!(gateway_exposure_warnings("lan", "token", Some(&"a".repeat(32))).is_empty());
+        assert_eq!(
+            gateway_exposure_warnings("all", "token", Some(&"a".repeat(32))).len(),
+            1
+        );
+    }
 
+    #[test]
+    fn test_parse_firewall_state_outputs() {
         assert_eq!(
-            agent_obj
-                .get("tools")
-                .and_then(|tools| tools.get("profile"))
-                .and_then(|value| value.as_str()),
-            Some("coding")
+            parse_macos_firewall_state("Firewall is enabled. (State = 1)"),
+            Some(true)
         );
         assert_eq!(
-            agent_obj
-                .get("tools")
-                .and_then(|tools| tools.get("allow"))
-                .and_then(|value| value.as_array())
-                .map(|values| values.len()),
-            Some(2)
+            parse_macos_firewall_state("Firewall is disabled. (State = 0)"),
+            Some(false)
         );
-        assert!(agent_obj
-            .get("tools")
-            .and_then(|tools| tools.get("agentToAgent"))
-            .is_none());
         assert_eq!(
-            agent_obj
-                .get("subagents")
-                .and_then(|subagents| subagents.get("allowAgents"))
-                .and_then(|value| value.as_array())
-                .map(|values| values.len()),
-            Some(1)
+            parse_ufw_state("Status: active\n\nTo Action From"),
+            Some(true)
+        );
+        assert_eq!(parse_ufw_state("Status: inactive\n"), Some(false));
+        assert_eq!(parse_ufw_state(""), None);
+        assert_eq!(
+            parse_netsh_firewall_state(
+                "Domain Profile Settings:\nState                                 OFF\nPrivate Profile Settings:\nState                                 ON\n"
+            ),
+            Some(true)
         );
+        assert_eq!(parse_netsh_firewall_state("nothing here"), None);
     }
 
     #[test]
-    fn test_apply_agent_overrides_omits_missing_nested_agent_config() {
-        let agent = AgentData {
-            id: "reporting".to_string(),
-            name: "Reporting".to_string(),
-            model: "anthropic/claude-sonnet-4-6".to_string(),
-            fallback_models: None,
-            skills: None,
-            vibe: None,
-            emoji: None,
-            identity_md: None,
-            user_md: None,
-            soul_md: None,
-            tools_md: None,
-            agents_md: None,
-            heartbeat_md: None,
-            memory_md: None,
-            subagents: None,
-            tools: None,
-        };
-        let mut agent_obj = serde_json::json!({
-            "id": agent.id,
-            "name": agent.name
-        });
+    fn test_rewrite_dashboard_request_head_injects_token_and_closes_plain_requests() {
+        let head = "GET /assets/app.js HTTP/1.1\r\nHost: 127.0.0.1:51000\r\nAuthorization: Bearer stale\r\nConnection: keep-alive\r\nAccept: */*\r\n\r\n";
+        let (rewritten, is_upgrade) = rewrite_dashboard_request_head(head, "tok-123", 18789);
+
+        assert!(!is_upgrade);
+        assert!(rewritten.starts_with("GET /assets/app.js HTTP/1.1\r\nHost: 127.0.0.1:18789\r\n"));
+        assert!(rewritten.contains("Authorization: Bearer tok-123\r\n"));
+        assert!(!rewritten.contains("stale"));
+        assert!(!rewritten.contains("keep-alive"));
+        assert!(rewritten.contains("Accept: */*\r\n"));
+        assert!(rewritten.ends_with("Connection: close\r\n\r\n"));
+    }
 
-        apply_agent_overrides(&mut agent_obj, &agent);
+    #[test]
+    fn test_rewrite_dashboard_request_head_drops_proxy_session_cookie() {
+        let head = "GET / HTTP/1.1\r\nHost: 127.0.0.1:51000\r\nOrigin: http://127.0.0.1:51000\r\nCookie: theme=dark; clawnetes_dashboard=s3cret\r\n\r\n";
+        let (rewritten, _) = rewrite_dashboard_request_head(head, "tok", 18789);
 
-        assert!(agent_obj.get("tools").is_none());
-        assert!(agent_obj.get("subagents").is_none());
+        assert!(rewritten.contains("Cookie: theme=dark\r\n"));
+        assert!(!rewritten.contains("s3cret"));
     }
 
     #[test]
-    fn test_agent_tools_config_omits_empty_optional_fields() {
-        let tools = AgentToolsConfig {
-            profile: Some("minimal".to_string()),
-            allow: None,
-            deny: None,
-            elevated: None,
-        };
+    fn test_check_dashboard_request_requires_own_host_and_session() {
+        let request =
+            |host: &str, extra: &str| format!("GET / HTTP/1.1\r\nHost: {}\r\n{}\r\n", host, extra);
+        let check = |head: &str| check_dashboard_request(head, 51000, "s3cret");
 
-        let serialized = serde_json::to_value(&tools).expect("tool config should serialize");
+        assert_eq!(
+            check(&request(
+                "127.0.0.1:51000",
+                "Cookie: clawnetes_dashboard=s3cret\r\n"
+            )),
+            DashboardProxyDecision::Forward
+        );
+        assert_eq!(
+            check("GET /__clawnetes/login?key=s3cret HTTP/1.1\r\nHost: 127.0.0.1:51000\r\n\r\n"),
+            DashboardProxyDecision::Login
+        );
+        assert!(matches!(
+            check("GET /__clawnetes/login?key=guess HTTP/1.1\r\nHost: 127.0.0.1:51000\r\n\r\n"),
+            DashboardProxyDecision::Reject(_)
+        ));
+        assert!(matches!(
+            check(&request("127.0.0.1:51000", "")),
+            DashboardProxyDecision::Reject(_)
+        ));
+        // DNS rebinding: right address, foreign name.
+        assert!(matches!(
+            check(&request(
+                "evil.example:51000",
+                "Cookie: clawnetes_dashboard=s3cret\r\n"
+            )),
+            DashboardProxyDecision::Reject(_)
+        ));
+        assert!(matches!(
+            check(&request(
+                "127.0.0.1:51000",
+                "Origin: http://evil.example\r\nCookie: clawnetes_dashboard=s3cret\r\n"
+            )),
+            DashboardProxyDecision::Reject(_)
+        ));
+    }
+
+    #[test]
+    fn test_rewrite_dashboard_request_head_preserves_websocket_upgrade() {
+        let head = "GET / HTTP/1.1\r\nHost: 127.0.0.1:51000\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Key: abc\r\n\r\n";
+        let (rewritten, is_upgrade) = rewrite_dashboard_request_head(head, "tok", 18789);
+
+        assert!(is_upgrade);
+        assert!(rewritten.contains("Connection: Upgrade\r\n"));
+        assert!(rewritten.contains("Upgrade: websocket\r\n"));
+        assert!(!rewritten.contains("Connection: close"));
+    }
 
+    #[test]
+    fn test_gateway_status_indicates_stopped() {
+        assert!(gateway_status_indicates_stopped("Runtime: not running"));
+        assert!(gateway_status_indicates_stopped(
+            "Service: stopped (last exit 1)"
+        ));
+        assert!(gateway_status_indicates_stopped(""));
+        assert!(!gateway_status_indicates_stopped(
+            "Runtime: running (pid 4242)"
+        ));
+        assert!(!gateway_status_indicates_stopped("Gateway is starting"));
+    }
+
+    #[test]
+    fn test_extract_gateway_exit_reason_picks_exit_line() {
+        let status =
+            "Service: LaunchAgent (loaded)\nRuntime: stopped\nLast exit status: 1 (EADDRINUSE)\n";
         assert_eq!(
-            serialized.get("profile").and_then(|value| value.as_str()),
-            Some("minimal")
+            extract_gateway_exit_reason(status),
+            Some("Last exit status: 1 (EADDRINUSE)".to_string())
         );
-        assert!(serialized.get("allow").is_none());
-        assert!(serialized.get("deny").is_none());
-        assert!(serialized.get("elevated").is_none());
-        assert!(serialized.get("agentToAgent").is_none());
+        assert_eq!(extract_gateway_exit_reason("Runtime: stopped"), None);
+    }
+
+    #[test]
+    fn test_supervisor_backoff_delay_grows_and_caps() {
+        assert_eq!(supervisor_backoff_delay(5, 1), Duration::from_secs(5));
+        assert_eq!(supervisor_backoff_delay(5, 2), Duration::from_secs(10));
+        assert_eq!(supervisor_backoff_delay(5, 3), Duration::from_secs(20));
+        assert_eq!(supervisor_backoff_delay(120, 10), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_parse_gateway_status_text_extracts_fields() {
+        let output = "Service: LaunchAgent (loaded)\nGateway: bind=loopback (127.0.0.1), port=18789 (service args)\nRuntime: running (pid 4242, state active)\nUptime: 2h 13m\nChannels: telegram, whatsapp\nClients: 3 connected\n";
+        let status = parse_gateway_status_text(output);
+
+        assert!(status.running);
+        assert_eq!(status.pid, Some(4242));
+        assert_eq!(status.port, Some(18789));
+        assert_eq!(status.uptime.as_deref(), Some("2h 13m"));
+        assert_eq!(status.active_channels, vec!["telegram", "whatsapp"]);
+        assert_eq!(status.connected_clients, Some(3));
+    }
+
+    #[test]
+    fn test_parse_gateway_status_text_detects_stopped_runtime() {
+        let status = parse_gateway_status_text("Service: systemd (enabled)\nRuntime: stopped\n");
+        assert!(!status.running);
+        assert_eq!(status.pid, None);
+        assert!(status.active_channels.is_empty());
+    }
+
+    #[test]
+    fn test_parse_gateway_status_output_prefers_json() {
+        let output = r#"{
+            "runtime": { "status": "running", "pid": 99, "uptime": 3600 },
+            "port": 18789,
+            "version": "2026.3.1",
+            "channels": { "telegram": { "connected": true }, "whatsapp": { "connected": false } },
+            "clients": [{ "id": "a" }, { "id": "b" }]
+        }"#;
+        let status = parse_gateway_status_output(output);
+
+        assert!(status.running);
+        assert_eq!(status.pid, Some(99));
+        assert_eq!(status.uptime.as_deref(), Some("3600s"));
+        assert_eq!(status.port, Some(18789));
+        assert_eq!(status.version.as_deref(), Some("2026.3.1"));
+        assert_eq!(status.active_channels, vec!["telegram"]);
+        assert_eq!(status.connected_clients, Some(2));
+    }
+
+    #[test]
+    fn test_chat_message_text_joins_text_blocks() {
+        let message = serde_json::json!({
+            "role": "assistant",
+            "content": [
+                { "type": "text", "text": "Hello" },
+                { "type": "tool_use", "name": "x" },
+                { "type": "text", "text": " there" }
+            ]
+        });
+        assert_eq!(chat_message_text(&message).as_deref(), Some("Hello there"));
+        assert_eq!(
+            chat_message_text(&serde_json::json!("plain")).as_deref(),
+            Some("plain")
+        );
+    }
+
+    #[test]
+    fn test_chat_stream_suffix_returns_new_text_only() {
+        assert_eq!(chat_stream_suffix("Hel", "Hello"), "lo");
+        assert_eq!(chat_stream_suffix("", "Hi"), "Hi");
+        assert_eq!(chat_stream_suffix("abc", "xyz"), "xyz");
+    }
+
+    #[test]
+    fn test_parse_session_list_reads_store_map_and_gateway_payload() {
+        let store = serde_json::json!({
+            "agent:main:main": { "sessionId": "abc", "updatedAt": 10, "lastChannel": "telegram" },
+            "agent:main:other": { "sessionId": "def", "updatedAt": 20 },
+            "broken": { "updatedAt": 30 }
+        });
+        let sessions = parse_session_list(&store);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].session_id, "def");
+        assert_eq!(sessions[1].channel.as_deref(), Some("telegram"));
+
+        let payload = serde_json::json!({
+            "sessions": [{ "key": "agent:main:main", "sessionId": "abc", "displayName": "Main" }]
+        });
+        let sessions = parse_session_list(&payload);
+        assert_eq!(sessions[0].key, "agent:main:main");
+        assert_eq!(sessions[0].display_name.as_deref(), Some("Main"));
+    }
+
+    #[test]
+    fn test_paginate_transcript_skips_non_message_lines() {
+        let content = [
+            r#"{"type":"session","id":"abc"}"#,
+            r#"{"type":"message","timestamp":"t1","message":{"role":"user","content":[{"type":"text","text":"hi"}]}}"#,
+            r#"{"type":"message","message":{"role":"assistant","content":[{"type":"text","text":"hello"}]}}"#,
+            "not json",
+        ]
+        .join("\n");
+        let (total, entries) = paginate_transcript(&content, 1, 10);
+        assert_eq!(total, 2);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].role, "assistant");
+        assert_eq!(entries[0].text, "hello");
+    }
+
+    #[test]
+    fn test_is_valid_session_id_rejects_paths() {
+        assert!(is_valid_session_id("3f2a-bc_1"));
+        assert!(!is_valid_session_id("../openclaw"));
+        assert!(!is_valid_session_id(""));
+    }
+
+    #[test]
+    fn test_civil_date_from_unix_days() {
+        assert_eq!(civil_date_from_unix_days(0), "1970-01-01");
+        assert_eq!(civil_date_from_unix_days(19_723), "2024-01-01");
+        assert_eq!(civil_date_from_unix_days(19_782), "2024-02-29");
     }
 
     #[test]
-    fn test_build_agent_session_init_command_uses_hello_message() {
-        assert_eq!(
-            build_agent_session_init_command("data-analysis"),
-            "openclaw agent --agent data-analysis --message \"hello\" 2>/dev/null || true"
-        );
+    fn test_usage_range_days() {
+        assert_eq!(usage_range_days("all"), Ok(None));
+        assert_eq!(usage_range_days("today"), Ok(Some(0)));
+        assert_eq!(usage_range_days("7d"), Ok(Some(6)));
+        assert!(usage_range_days("fortnight").is_err());
     }
 
     #[test]
-    fn test_build_auth_profiles_doc_preserves_oauth_profile_shape() {
-        let mut provider_auths = std::collections::HashMap::new();
-        provider_auths.insert(
-            "openai".to_string(),
-            ProviderAuthData {
-                auth_method: "openai-codex".to_string(),
-                token: "".to_string(),
-                profile_key: Some("openai-codex:default".to_string()),
-                profile: Some(serde_json::json!({
-                    "type": "oauth",
-                    "provider": "openai-codex",
-                    "access": "access-token",
-                    "refresh": "refresh-token"
-                })),
-                oauth_provider_id: Some("openai-codex".to_string()),
-            },
-        );
+    fn test_aggregate_usage_groups_by_day_and_model() {
+        let line = |ts: &str, model: &str| {
+            format!(
+                r#"{{"type":"message","timestamp":"{}","message":{{"role":"assistant","provider":"anthropic","model":"{}","usage":{{"input":1000000,"output":100000,"cost":{{"total":9.9}}}}}}}}"#,
+                ts, model
+            )
+        };
+        let records: Vec<UsageRecord> = [
+            line("2024-05-01T10:00:00Z", "claude-sonnet-4-5"),
+            line("2024-05-01T11:00:00Z", "claude-sonnet-4-5"),
+            line("2024-05-02T09:00:00Z", "mystery-model"),
+            line("2024-04-30T09:00:00Z", "claude-sonnet-4-5"),
+            r#"{"type":"message","message":{"role":"user"}}"#.to_string(),
+        ]
+        .iter()
+        .filter_map(|l| parse_usage_record(l))
+        .collect();
+        assert_eq!(records.len(), 4);
 
-        let doc = build_auth_profiles_doc(&provider_auths, None, None, "openai");
-        let profile = doc
-            .get("profiles")
-            .and_then(|p| p.get("openai-codex:default"))
-            .unwrap();
-        assert_eq!(profile.get("type").and_then(|v| v.as_str()), Some("oauth"));
+        let buckets = aggregate_usage(&records, Some("2024-05-01"));
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].requests, 2);
+        assert!((buckets[0].estimated_cost_usd - 9.0).abs() < 1e-9);
+        assert!((buckets[1].estimated_cost_usd - 9.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_crossed_budget_thresholds_skips_already_alerted() {
+        let settings = BudgetSettings {
+            monthly_usd: 20.0,
+            alert_thresholds: vec![100.0, 50.0, 80.0],
+            pause_gateway_at_limit: false,
+        };
         assert_eq!(
-            profile.get("refresh").and_then(|v| v.as_str()),
-            Some("refresh-token")
+            crossed_budget_thresholds(&settings, 5.0, &[]),
+            Vec::<f64>::new()
         );
         assert_eq!(
-            doc.get("lastGood")
-                .and_then(|v| v.get("openai"))
-                .and_then(|v| v.as_str()),
-            Some("openai-codex:default")
+            crossed_budget_thresholds(&settings, 17.0, &[]),
+            vec![50.0, 80.0]
+        );
+        assert_eq!(
+            crossed_budget_thresholds(&settings, 21.0, &[50.0, 80.0]),
+            vec![100.0]
         );
     }
 
     #[test]
-    fn test_resolve_provider_auth_data_uses_last_good_for_oauth_provider() {
-        let auth_config = serde_json::json!({
-            "profiles": {
-                "openai-codex:default": {
-                    "type": "oauth",
-                    "provider": "openai-codex",
-                    "access": "access-token"
-                }
-            },
-            "lastGood": {
-                "openai": "openai-codex:default"
-            }
-        });
+    fn test_roll_budget_month_lifts_pause() {
+        let mut status = BudgetStatus {
+            month: "2026-09".to_string(),
+            spent_usd: 21.0,
+            alerted_thresholds: vec![50.0, 80.0, 100.0],
+            paused: true,
+            ..Default::default()
+        };
+        assert!(!roll_budget_month(&mut status, "2026-09"));
+        assert!(status.paused);
+        assert_eq!(status.alerted_thresholds.len(), 3);
+
+        assert!(roll_budget_month(&mut status, "2026-10"));
+        assert_eq!(status.month, "2026-10");
+        assert!(!status.paused);
+        assert!(status.alerted_thresholds.is_empty());
+    }
 
-        let resolved = resolve_provider_auth_data("openai", &auth_config)
-            .expect("provider auth should resolve");
-        assert_eq!(
-            resolved.profile_key.as_deref(),
-            Some("openai-codex:default")
-        );
-        assert_eq!(resolved.auth_method, "openai-codex");
-        assert_eq!(resolved.token, "access-token");
-        assert_eq!(resolved.oauth_provider_id.as_deref(), Some("openai-codex"));
+    #[test]
+    fn test_validate_budget_settings() {
+        let mut settings = BudgetSettings {
+            monthly_usd: 10.0,
+            alert_thresholds: vec![80.0],
+            pause_gateway_at_limit: true,
+        };
+        assert!(validate_budget_settings(&settings).is_ok());
+        settings.alert_thresholds.push(150.0);
+        assert!(validate_budget_settings(&settings).is_err());
+        settings.alert_thresholds.pop();
+        settings.monthly_usd = -1.0;
+        assert!(validate_budget_settings(&settings).is_err());
     }
 
     #[test]
-    fn test_resolve_provider_auth_data_prefers_usable_oauth_over_empty_last_good() {
-        let auth_config = serde_json::json!({
-            "profiles": {
-                "openai:default": {
-                    "type": "oauth",
-                    "provider": "openai",
-                    "access": ""
-                },
-                "openai-codex:default": {
-                    "type": "oauth",
-                    "provider": "openai-codex",
-                    "access": "real-access-token"
-                }
-            },
-            "lastGood": {
-                "openai": "openai:default"
-            }
+    fn test_plugin_infos_merges_config_entries() {
+        let output = r#"{"plugins":[
+            {"id":"telegram","name":"Telegram","status":"loaded","origin":"bundled"},
+            {"id":"whatsapp","status":"disabled","origin":"bundled"},
+            {"id":"voice-call","status":"loaded","origin":"global","version":"1.2.0"}
+        ]}"#;
+        let config = serde_json::json!({
+            "plugins": { "entries": { "whatsapp": { "enabled": true }, "voice-call": { "enabled": false } } }
         });
+        let list: cli::PluginList = cli::parse_json_output(output).unwrap();
+        let plugins = plugin_infos(&list, &config);
+        assert_eq!(plugins.len(), 3);
+        assert!(plugins[0].enabled && !plugins[0].installed);
+        assert_eq!(plugins[1].name, "whatsapp");
+        assert!(plugins[1].enabled);
+        assert!(plugins[2].installed && !plugins[2].enabled);
+    }
 
-        let resolved = resolve_provider_auth_data("openai", &auth_config)
-            .expect("provider auth should resolve");
+    #[test]
+    fn test_set_plugin_enabled_in_config_preserves_entry_settings() {
+        let mut config = serde_json::json!({
+            "plugins": { "entries": { "voice-call": { "enabled": true, "config": { "provider": "twilio" } } } }
+        });
+        set_plugin_enabled_in_config(&mut config, "voice-call", false);
+        set_plugin_enabled_in_config(&mut config, "matrix", true);
+        assert_eq!(config["plugins"]["entries"]["voice-call"]["enabled"], false);
         assert_eq!(
-            resolved.profile_key.as_deref(),
-            Some("openai-codex:default")
+            config["plugins"]["entries"]["voice-call"]["config"]["provider"],
+            "twilio"
         );
-        assert_eq!(resolved.token, "real-access-token");
-        assert_eq!(resolved.oauth_provider_id.as_deref(), Some("openai-codex"));
+        assert_eq!(config["plugins"]["entries"]["matrix"]["enabled"], true);
+
+        let mut empty = serde_json::json!({});
+        set_plugin_enabled_in_config(&mut empty, "telegram", true);
+        assert_eq!(empty["plugins"]["entries"]["telegram"]["enabled"], true);
     }
 
     #[test]
-    fn test_resolve_provider_auth_data_maps_anthropic_oauth_to_setup_token() {
-        let auth_config = serde_json::json!({
-            "profiles": {
-                "anthropic:default": {
-                    "type": "oauth",
-                    "provider": "anthropic",
-                    "access": "anthropic-access"
-                }
+    fn test_is_valid_plugin_spec() {
+        assert!(is_valid_plugin_spec("@openclaw/voice-call"));
+        assert!(!is_valid_plugin_spec("--force"));
+        assert!(!is_valid_plugin_spec("x; rm -rf ~"));
+    }
+
+    #[test]
+    fn test_read_tool_permissions_from_config() {
+        let config = serde_json::json!({
+            "tools": {
+                "profile": "coding",
+                "deny": ["browser"],
+                "exec": { "ask": "on-miss" },
+                "elevated": { "enabled": true }
             },
-            "lastGood": {
-                "anthropic": "anthropic:default"
-            }
+            "agents": { "defaults": { "sandbox": { "mode": "non-main", "workspaceAccess": "ro" } } },
+            "skills": { "entries": { "weather": { "enabled": false }, "github": {} } }
         });
+        let permissions = read_tool_permissions(&config);
+        assert_eq!(permissions.profile.as_deref(), Some("coding"));
+        assert_eq!(permissions.deny, vec!["browser".to_string()]);
+        assert_eq!(permissions.exec_ask.as_deref(), Some("on-miss"));
+        assert!(permissions.elevated_enabled);
+        assert_eq!(permissions.sandbox_mode.as_deref(), Some("non-main"));
+        assert_eq!(permissions.skills[0].name, "github");
+        assert!(permissions.skills[0].enabled);
+        assert!(!permissions.skills[1].enabled);
+    }
 
-        let resolved = resolve_provider_auth_data("anthropic", &auth_config)
-            .expect("provider auth should resolve");
-        assert_eq!(resolved.auth_method, "setup-token");
-        assert_eq!(resolved.token, "anthropic-access");
+    #[test]
+    fn test_apply_tool_permissions_round_trips_and_keeps_other_keys() {
+        let mut config = serde_json::json!({
+            "tools": { "web": { "search": { "enabled": true } }, "exec": { "ask": "always" } },
+            "agents": { "defaults": { "model": { "primary": "x" } } }
+        });
+        let permissions = ToolPermissions {
+            profile: Some("messaging".to_string()),
+            allow: vec!["read".to_string()],
+            sandbox_mode: Some("all".to_string()),
+            skills: vec![SkillPermission {
+                name: "weather".to_string(),
+                enabled: false,
+            }],
+            ..Default::default()
+        };
+        apply_tool_permissions(&mut config, &permissions);
+        assert_eq!(read_tool_permissions(&config), permissions);
+        assert_eq!(config["tools"]["web"]["search"]["enabled"], true);
+        assert!(config["tools"].get("exec").is_none());
+        assert!(config["tools"].get("elevated").is_none());
+        assert_eq!(config["agents"]["defaults"]["model"]["primary"], "x");
     }
 
     #[test]
-    fn test_build_terminal_runner_command_writes_marker_file() {
-        let command = "openclaw models auth login --provider 'openai-codex'";
-        let runner = build_terminal_runner_command(command, "/tmp/clawnetes-oauth.exit");
+    fn test_validate_tool_permissions() {
+        let mut permissions = ToolPermissions {
+            exec_ask: Some("sometimes".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_tool_permissions(&permissions).is_err());
+        permissions.exec_ask = Some("always".to_string());
+        assert!(validate_tool_permissions(&permissions).is_ok());
+        permissions.allow = vec!["exec".to_string()];
+        permissions.deny = vec!["exec".to_string()];
+        assert!(validate_tool_permissions(&permissions).is_err());
+    }
 
-        assert!(runner.contains("openclaw models auth login"));
-        assert!(runner.contains("auth_exit_code=$?"));
-        assert!(runner.contains("printf '%s' \"$auth_exit_code\" > '/tmp/clawnetes-oauth.exit'"));
-        assert!(runner.ends_with("exit $auth_exit_code"));
+    #[test]
+    fn test_validate_cron_expression_accepts_common_forms() {
+        for expr in [
+            "0 9 * * *",
+            "*/15 * * * *",
+            "0 8-18/2 * * mon-fri",
+            "30 6 1,15 jan,jul 0",
+            "@daily",
+        ] {
+            assert!(validate_cron_expression(expr).is_ok(), "{}", expr);
+        }
     }
 
     #[test]
-    fn test_build_provider_auth_command_uses_plugin_login_for_gemini_cli() {
+    fn test_validate_cron_expression_rejects_bad_input() {
+        assert!(validate_cron_expression("0 9 * *").is_err());
+        assert!(validate_cron_expression("60 * * * *").is_err());
+        assert!(validate_cron_expression("0 18-8 * * *").is_err());
+        assert!(validate_cron_expression("*/0 * * * *").is_err());
+        assert!(validate_cron_expression("0 0 0 * *").is_err());
+        assert!(validate_cron_expression("@sometimes").is_err());
+    }
+
+    #[test]
+    fn test_parse_scheduled_tasks() {
+        let output = r#"{"jobs":[{"id":"job-1","name":"Morning brief","enabled":false,
+            "schedule":{"kind":"cron","expr":"0 8 * * *"},
+            "payload":{"kind":"agentTurn","message":"Summarize my inbox","channel":"telegram"},
+            "state":{"nextRunAtMs":1700000000000,"lastStatus":"error","lastError":"rate limited"}}]}"#;
+        let tasks = parse_scheduled_tasks(output).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].schedule, "0 8 * * *");
+        assert_eq!(tasks[0].prompt, "Summarize my inbox");
+        assert_eq!(tasks[0].channel.as_deref(), Some("telegram"));
+        assert!(!tasks[0].enabled);
+        assert_eq!(tasks[0].next_run_at_ms, Some(1_700_000_000_000));
+        assert_eq!(tasks[0].last_status.as_deref(), Some("error"));
+        assert_eq!(tasks[0].last_error.as_deref(), Some("rate limited"));
+    }
+
+    #[test]
+    fn test_setup_steps_to_run_resumes_from_failed_step() {
+        assert_eq!(setup_steps_to_run(None).unwrap().len(), SETUP_STEPS.len());
         assert_eq!(
-            build_provider_auth_command("google", "google-gemini-cli", "google-gemini-cli"),
-            "openclaw models auth login --provider 'google-gemini-cli'"
+            setup_steps_to_run(Some("gateway")).unwrap(),
+            vec!["gateway", "pairing"]
         );
+        assert!(setup_steps_to_run(Some("reboot")).is_err());
     }
 
     #[test]
-    fn test_required_plugin_for_oauth_provider_id_maps_gemini_cli() {
+    fn test_gateway_install_problem_detects_broken_installs() {
+        let snapshot = GatewayInstallSnapshot {
+            service_path: "/tmp/openclaw-gateway.service".to_string(),
+            service: None,
+            config: Some(r#"{"gateway":{"auth":{"token":"abc"}}}"#.to_string()),
+        };
+        let good = r#"{"gateway":{"auth":{"token":"abc"},"port":18789}}"#;
         assert_eq!(
-            required_plugin_for_oauth_provider_id("google-gemini-cli"),
-            Some("google-gemini-cli-auth")
+            gateway_install_problem(&snapshot, Some("[Unit]"), Some(good)),
+            None
         );
-        assert_eq!(required_plugin_for_oauth_provider_id("openai-codex"), None);
+        assert!(gateway_install_problem(&snapshot, None, Some(good)).is_some());
+        assert!(gateway_install_problem(&snapshot, Some("[Unit]"), Some("{broken")).is_some());
+        assert!(gateway_install_problem(&snapshot, Some("[Unit]"), None).is_some());
+        assert!(gateway_install_problem(
+            &snapshot,
+            Some("[Unit]"),
+            Some(r#"{"gateway":{"auth":{"token":"new"}}}"#)
+        )
+        .is_some());
     }
 
     #[test]
-    fn test_decorate_oauth_launch_error_adds_gemini_guidance() {
-        let message = decorate_oauth_launch_error(
-            "google-gemini-cli",
-            "OpenClaw auth exited with status 1.".to_string(),
+    fn test_classify_gateway_failure_uses_exit_code_and_structured_output() {
+        let output = |code: i32, stdout: &str, stderr: &str| process::Output {
+            code: Some(code),
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+        };
+        assert_eq!(
+            classify_gateway_failure(&output(127, "", "zsh: command not found: openclaw"), 18789),
+            GatewayFailure::CliNotFound
+        );
+        assert_eq!(
+            classify_gateway_failure(
+                &output(
+                    1,
+                    r#"{"ok":false,"error":{"code":"EADDRINUSE","message":"busy"}}"#,
+                    ""
+                ),
+                18789
+            ),
+            GatewayFailure::PortInUse { port: 18789 }
         );
+        assert!(matches!(
+            classify_gateway_failure(
+                &output(1, "", "Could not find service ai.openclaw.gateway"),
+                18789
+            ),
+            GatewayFailure::ServiceNotLoaded { .. }
+        ));
+        assert_eq!(
+            classify_gateway_failure(&output(3, "boom", ""), 18789),
+            GatewayFailure::Other {
+                exit_code: Some(3),
+                detail: "boom".to_string()
+            }
+        );
+    }
 
-        assert!(message.contains("OpenClaw auth exited with status 1."));
-        assert!(message.contains("GOOGLE_CLOUD_PROJECT"));
-        assert!(message.contains("Gemini API Key"));
+    #[test]
+    fn test_successful_output_mentioning_errors_is_not_a_failure() {
+        let output = process::Output {
+            code: Some(0),
+            stdout: "Gateway started. 0 errors, 0 failed checks.".to_string(),
+            stderr: String::new(),
+        };
+        assert!(output.success());
     }
 
     #[test]
-    fn test_collect_required_plugin_ids_includes_gemini_oauth_and_skill_once() {
-        let mut provider_auths = std::collections::HashMap::new();
-        provider_auths.insert(
-            "google".to_string(),
-            ProviderAuthData {
-                auth_method: "google-gemini-cli".to_string(),
-                token: String::new(),
-                profile_key: None,
-                profile: None,
-                oauth_provider_id: Some("google-gemini-cli".to_string()),
-            },
+    fn test_command_error_recognises_timeouts() {
+        #[cfg(unix)]
+        {
+            let started = Instant::now();
+            let err =
+                process::output_with_timeout("/bin/sleep", &["7"], Duration::from_millis(200))
+                    .err()
+                    .unwrap();
+            let message = format!("Failed to restart the gateway: {}", err);
+            match command_error("restart_gateway", started, message.clone()) {
+                crash::CommandError::App(crash::AppError::Timeout {
+                    command, process, ..
+                }) => {
+                    assert_eq!(command, "restart_gateway");
+                    assert_eq!(process, "/bin/sleep 7");
+                }
+                other => panic!("expected a timeout, got {:?}", other),
+            }
+            assert_eq!(
+                command_error("restart_gateway", Instant::now(), message.clone()),
+                crash::CommandError::Message(message)
+            );
+        }
+    }
+
+    #[test]
+    fn test_cli_subcommand_argv_enforces_allowlist() {
+        let args = vec!["status".to_string(), "--json".to_string()];
+        assert_eq!(
+            cli_subcommand_argv("gateway", &args).unwrap(),
+            vec!["gateway", "status", "--json"]
+        );
+        assert!(cli_subcommand_argv("uninstall", &[]).is_err());
+        assert!(cli_subcommand_argv("status; rm -rf ~", &[]).is_err());
+        let set = vec!["set".to_string(), "gateway.mode".to_string()];
+        assert!(cli_subcommand_argv("config", &set).is_err());
+        let fix = vec!["--fix".to_string()];
+        assert!(cli_subcommand_argv("doctor", &fix).is_err());
+        let config = vec!["--config=/tmp/evil.json".to_string()];
+        assert!(cli_subcommand_argv("status", &config).is_err());
+        let limit = vec!["--limit=50".to_string()];
+        assert!(cli_subcommand_argv("logs", &limit).is_ok());
+        let argv = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+        assert!(cli_subcommand_argv("logs", &argv(&["--limit", "50", "--json"])).is_ok());
+        assert!(cli_subcommand_argv("logs", &argv(&["--limit"])).is_err());
+        assert!(cli_subcommand_argv("status", &argv(&["--json=1"])).is_err());
+        // Subcommands without an action take no positional arguments.
+        for args in [
+            &["$(reboot)"][..],
+            &["--json", "extra"],
+            &["reset", "--all"],
+        ] {
+            assert!(cli_subcommand_argv("status", &argv(args)).is_err());
+            assert!(cli_subcommand_argv("logs", &argv(args)).is_err());
+        }
+        // Shell metacharacters are passed through as literal arguments, never interpreted.
+        let literal = vec!["get".to_string(), "$(reboot)".to_string()];
+        assert_eq!(
+            cli_subcommand_argv("config", &literal).unwrap()[2],
+            "$(reboot)"
         );
+    }
 
-        let skills = vec!["gemini".to_string()];
-        let required = collect_required_plugin_ids(&provider_auths, Some(&skills));
+    #[test]
+    fn test_npm_config_args_from_install_settings() {
+        assert!(npm_config_args(&InstallSettings::default()).is_empty());
+        let settings = InstallSettings {
+            registry: Some("https://npm.corp.example/".to_string()),
+            proxy: Some("http://proxy:3128".to_string()),
+            ca_file: Some("/etc/ssl/corp.pem".to_string()),
+            preferred_strategy: None,
+        };
+        assert_eq!(
+            npm_config_args(&settings),
+            vec![
+                "--registry=https://npm.corp.example/",
+                "--proxy=http://proxy:3128",
+                "--https-proxy=http://proxy:3128",
+                "--cafile=/etc/ssl/corp.pem",
+            ]
+        );
+    }
 
-        assert_eq!(required, vec!["google-gemini-cli-auth".to_string()]);
+    #[test]
+    fn test_validate_install_settings_rejects_bad_urls() {
+        let bad_scheme = InstallSettings {
+            registry: Some("ftp://npm.example".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_install_settings(&bad_scheme).is_err());
+        let bad_proxy = InstallSettings {
+            proxy: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_install_settings(&bad_proxy).is_err());
     }
 
     #[test]
-    fn test_collect_required_plugin_ids_ignores_plain_google_token_auth() {
-        let mut provider_auths = std::collections::HashMap::new();
-        provider_auths.insert(
-            "google".to_string(),
-            ProviderAuthData {
-                auth_method: "token".to_string(),
-                token: "test-token".to_string(),
-                profile_key: None,
-                profile: None,
-                oauth_provider_id: None,
-            },
+    fn test_windows_path_to_wsl() {
+        assert_eq!(
+            windows_path_to_wsl(r"C:\Users\me\openclaw-1.2.0.tgz").as_deref(),
+            Some("/mnt/c/Users/me/openclaw-1.2.0.tgz")
         );
+        assert_eq!(windows_path_to_wsl(r"\\server\share\a.tgz"), None);
+        assert_eq!(windows_path_to_wsl("/home/me/a.tgz"), None);
+    }
 
-        let required = collect_required_plugin_ids(&provider_auths, None);
-
-        assert!(required.is_empty());
+    #[test]
+    fn test_verify_sha256() {
+        let sha_of_abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert!(verify_sha256(b"abc", sha_of_abc).is_ok());
+        assert!(verify_sha256(b"abc", &sha_of_abc.to_uppercase()).is_ok());
+        assert!(verify_sha256(b"abd", sha_of_abc).is_err());
     }
 
     #[test]
-    fn test_merge_enabled_plugin_entries_adds_required_plugin_entry() {
-        let mut config = serde_json::json!({});
-        merge_enabled_plugin_entries(&mut config, &["google-gemini-cli-auth".to_string()]);
+    fn test_install_backend_args() {
+        let settings = InstallSettings {
+            registry: Some("https://npm.corp.example/".to_string()),
+            proxy: Some("http://proxy:3128".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            install_backend_args("bun", &settings).unwrap(),
+            vec![
+                "add",
+                "-g",
+                "openclaw",
+                "--registry=https://npm.corp.example/"
+            ]
+        );
+        assert_eq!(
+            install_backend_args("npm", &InstallSettings::default()).unwrap(),
+            vec!["install", "-g", "openclaw"]
+        );
+        assert_eq!(
+            install_backend_args("pnpm", &settings).unwrap().len(),
+            3 + npm_config_args(&settings).len()
+        );
+        assert!(install_backend_args("yarn", &settings).is_err());
+    }
 
+    #[test]
+    fn test_choose_install_strategy() {
         assert_eq!(
-            config
-                .get("plugins")
-                .and_then(|plugins| plugins.get("entries"))
-                .and_then(|entries| entries.get("google-gemini-cli-auth"))
-                .and_then(|plugin| plugin.get("enabled"))
-                .and_then(|enabled| enabled.as_bool()),
-            Some(true)
+            choose_install_strategy(&["npm", "brew"], true),
+            Some("brew")
+        );
+        assert_eq!(
+            choose_install_strategy(&["brew", "npm"], false),
+            Some("npm")
         );
+        assert_eq!(
+            choose_install_strategy(&["bun", "pnpm"], false),
+            Some("pnpm")
+        );
+        assert_eq!(choose_install_strategy(&[], true), None);
     }
 
     #[test]
-    fn test_build_provider_auth_command_uses_plugin_login_for_codex() {
+    fn test_npm_permission_error_detection() {
+        let eacces = "npm ERR! code EACCES\nnpm ERR! syscall mkdir\nnpm ERR! path /usr/local/lib/node_modules/openclaw";
+        assert!(is_npm_permission_error(eacces));
+        assert!(explain_npm_permission_error(eacces.to_string()).contains("user-writable prefix"));
+        assert!(!is_npm_permission_error("npm ERR! code E404"));
+        assert_eq!(explain_npm_permission_error("boom".to_string()), "boom");
+    }
+
+    #[test]
+    fn test_summarize_storage() {
+        let du = "2048\tlogs\n10\tworkspace\n4\tagents/main/sessions\n6\tagents/ops/workspace\n3000\t.\n100\t/tmp/openclaw\n";
         assert_eq!(
-            build_provider_auth_command("openai", "openai-codex", "openai-codex"),
-            "openclaw models auth login --provider 'openai-codex'"
+            summarize_storage(du),
+            StorageUsage {
+                logs_bytes: 2148 * 1024,
+                sessions_bytes: 4 * 1024,
+                workspace_bytes: 16 * 1024,
+                total_bytes: 3100 * 1024,
+            }
         );
     }
 
     #[test]
-    fn test_build_unix_terminal_script_uses_login_shell_on_macos() {
-        let script = build_unix_terminal_script(
-            TerminalPlatform::Macos,
-            "openclaw models auth login --provider 'openai-codex'",
-            "/tmp/clawnetes-oauth.exit",
+    fn test_plan_log_pruning_by_age_then_size() {
+        let day = 24 * 60 * 60;
+        let now = 100 * day;
+        let file = |path: &str, age: u64, size: u64| LogFile {
+            path: path.to_string(),
+            modified: now - age,
+            size,
+        };
+        let files = vec![
+            file("old.log", 30 * day, 10),
+            file("mid.log", 5 * day, 60),
+            file("new.log", 2 * day, 50),
+            file("active.log", 60, 500),
+        ];
+        let names = |plan: &LogPrunePlan| -> Vec<String> {
+            plan.remove.iter().map(|f| f.path.clone()).collect()
+        };
+        let plan = plan_log_pruning(&files, now, 14 * day, 1000);
+        assert_eq!(names(&plan), vec!["old.log"]);
+        assert!(plan.rotate.is_empty());
+
+        // The active file is rotated instead of removed, and its copy counts towards the limit.
+        let rotated = format!("active.log.{}", now);
+        let plan = plan_log_pruning(&files, now, 14 * day, 100);
+        assert_eq!(
+            plan.rotate,
+            vec![("active.log".to_string(), rotated.clone())]
+        );
+        assert_eq!(
+            names(&plan),
+            vec!["old.log", "mid.log", "new.log", rotated.as_str()]
         );
 
-        assert!(script.starts_with("#!/bin/zsh -l"));
-        assert!(script.contains("openclaw models auth login --provider 'openai-codex'"));
-        assert!(script.contains("auth_exit_code=$?"));
-        assert!(script.contains("printf '%s' \"$auth_exit_code\" > '/tmp/clawnetes-oauth.exit'"));
-        assert!(!script.contains("\nstatus=$?\n"));
+        // A rotated copy that fits is kept.
+        let plan = plan_log_pruning(&files, now, 14 * day, 900);
+        assert_eq!(plan.rotate.len(), 1);
+        assert_eq!(names(&plan), vec!["old.log"]);
     }
 
     #[test]
-    fn test_build_macos_terminal_launch_uses_terminal_app() {
-        let plan = build_macos_terminal_launch("/tmp/openclaw-auth.command");
+    fn test_parse_log_listing() {
+        let files =
+            parse_log_listing("1700000000\t42\t/tmp/openclaw/openclaw-2025-01-01.log\ngarbage\n");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].size, 42);
+        assert_eq!(files[0].path, "/tmp/openclaw/openclaw-2025-01-01.log");
+    }
 
-        assert_eq!(plan.program, "open");
+    #[test]
+    fn test_setup_failure_cause() {
         assert_eq!(
-            plan.args,
-            vec!["-a", "Terminal", "/tmp/openclaw-auth.command"]
+            setup_failure_cause("`npm install` did not finish within 900 seconds and was stopped."),
+            "timeout"
+        );
+        assert_eq!(setup_failure_cause("npm ERR! code EACCES"), "permission");
+        assert_eq!(
+            setup_failure_cause("Could not reach the npm registry https://registry.npmjs.org/"),
+            "network"
+        );
+        assert_eq!(
+            setup_failure_cause("npm is not installed or not on PATH"),
+            "missing_dependency"
         );
+        assert_eq!(setup_failure_cause("something odd"), "other");
     }
 
     #[test]
-    fn test_build_linux_terminal_launches_include_common_emulators() {
-        let plans = build_linux_terminal_launches("/tmp/openclaw-auth.sh");
+    fn test_build_identity_files_from_persona_spec() {
+        let spec = PersonaSpec {
+            name: "Nova".to_string(),
+            user_name: Some("Sam".to_string()),
+            tone: Some("playful".to_string()),
+            verbosity: Some("concise".to_string()),
+            boundaries: Some(vec![
+                "Never send email without asking".to_string(),
+                " ".to_string(),
+            ]),
+            specialties: Some(vec!["travel planning".to_string()]),
+            emoji_usage: Some("none".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_persona_spec(&spec).is_ok());
 
-        assert_eq!(
-            plans.first().map(|plan| plan.program.as_str()),
-            Some("x-terminal-emulator")
-        );
-        assert!(plans.iter().any(|plan| plan.program == "gnome-terminal"));
-        assert!(plans.iter().any(|plan| plan.program == "xterm"));
+        let identity = build_identity_md(&spec);
+        assert!(identity.contains("- **Name:** Nova"));
+        assert!(identity.contains("- **Vibe:** playful, concise"));
+
+        let soul = build_soul_md(&spec);
+        assert!(soul.starts_with("# SOUL.md\n## Mission\nServe Sam."));
+        assert!(soul.contains("- Do not use emoji."));
+        assert!(soul.contains("## Boundaries\n- Never send email without asking"));
+        assert!(!soul.contains("- \n"));
     }
 
     #[test]
-    fn test_build_windows_terminal_launches_include_tty_capable_launchers() {
-        let plans =
-            build_windows_terminal_launches("openclaw models auth login --provider 'openai-codex'");
+    fn test_validate_persona_spec_rejects_unknown_values() {
+        let spec = PersonaSpec {
+            name: "Nova".to_string(),
+            verbosity: Some("rambling".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_persona_spec(&spec).is_err());
+        assert!(validate_persona_spec(&PersonaSpec::default()).is_err());
+    }
 
+    #[test]
+    fn test_strip_markdown_fence() {
         assert_eq!(
-            plans.first().map(|plan| plan.program.as_str()),
-            Some("wt.exe")
+            strip_markdown_fence("```markdown\n# SOUL.md\nHi\n```"),
+            "# SOUL.md\nHi"
         );
-        assert!(plans.iter().any(|plan| plan.program == "cmd.exe"));
-        assert!(plans[0].args.contains(&"wsl.exe".to_string()));
-        assert!(plans[0].args.contains(&"/bin/bash".to_string()));
+        assert_eq!(strip_markdown_fence("  # SOUL.md  "), "# SOUL.md");
     }
 
     #[test]
-    fn test_oauth_callback_port_mapping() {
-        assert_eq!(oauth_callback_port("openai-codex"), Some(1455));
-        assert_eq!(oauth_callback_port("google-gemini-cli"), Some(8085));
-        assert_eq!(oauth_callback_port("anthropic"), None);
+    fn test_redact_and_restore_config_secrets() {
+        let raw = "{\n  \"gateway\": {\n    \"auth\": { \"mode\": \"token\" },\n    \"token\": \"s3cret\",\n  },\n  \"botToken\": \"123:abc\",\n  \"envKey\": \"${TG_TOKEN}\"\n}";
+        let (redacted, secrets) = redact_config_secrets(raw);
+        assert_eq!(secrets, vec!["s3cret".to_string(), "123:abc".to_string()]);
+        assert!(!redacted.contains("s3cret"));
+        assert!(redacted.contains("\"token\": \"__REDACTED_1__\","));
+        assert!(redacted.contains("${TG_TOKEN}"));
+        assert_eq!(restore_config_secrets(&redacted, &secrets), raw);
     }
 
     #[test]
-    fn test_normalize_auth_mode_maps_oauth_variants() {
-        assert_eq!(normalize_auth_mode("openai-codex"), "oauth");
-        assert_eq!(normalize_auth_mode("claude-cli"), "token");
-        assert_eq!(normalize_auth_mode("setup-token"), "token");
-        assert_eq!(normalize_auth_mode("token"), "token");
+    fn test_redact_config_secrets_on_a_single_line() {
+        let raw = r#"{"gateway":{"auth":{"mode":"token","token":"s3cret"}},"channels":{"telegram":{"botToken":"12:a\"b","dmPolicy":"pairing"}},models:{apiKey:'sk-1',name:"key"}}"#;
+        let (redacted, secrets) = redact_config_secrets(raw);
+        assert_eq!(
+            secrets,
+            vec![
+                "s3cret".to_string(),
+                r#"12:a\"b"#.to_string(),
+                "sk-1".to_string()
+            ]
+        );
+        assert_eq!(
+            redacted,
+            r#"{"gateway":{"auth":{"mode":"token","token":"__REDACTED_1__"}},"channels":{"telegram":{"botToken":"__REDACTED_2__","dmPolicy":"pairing"}},models:{apiKey:'__REDACTED_3__',name:"key"}}"#
+        );
+        assert_eq!(restore_config_secrets(&redacted, &secrets), raw);
     }
 
     #[test]
-    fn test_normalize_provider_for_ui_maps_openai_codex_and_google_vertex() {
-        assert_eq!(normalize_provider_for_ui("openai-codex"), "openai");
-        assert_eq!(normalize_provider_for_ui("google-vertex"), "google");
-        assert_eq!(normalize_provider_for_ui("openai"), "openai");
+    fn test_config_line_diff() {
+        let diff = config_line_diff("{\n  \"a\": 1,\n}", "{\n  \"a\": 1\n}");
+        assert_eq!(diff, "  {\n-   \"a\": 1,\n+   \"a\": 1\n  }");
     }
 
     #[test]
-    fn test_normalize_model_ref_for_ui_maps_openai_codex_namespace() {
+    fn test_running_config_matches() {
+        let disk = r#"{"gateway":{"port":18789}}"#;
+        let hash = format!("{:x}", Sha256::digest(disk.as_bytes()));
         assert_eq!(
-            normalize_model_ref_for_ui("openai-codex/gpt-5.4"),
-            "openai/gpt-5.4"
+            running_config_matches(
+                &serde_json::json!({ "hash": hash.to_uppercase() }),
+                disk,
+                &hash
+            ),
+            Some(true)
         );
         assert_eq!(
-            normalize_model_ref_for_ui("anthropic/claude-opus-4-6"),
-            "anthropic/claude-opus-4-6"
+            running_config_matches(
+                &serde_json::json!({ "hash": "stale", "config": { "gateway": { "port": 18789 } } }),
+                disk,
+                &hash
+            ),
+            Some(true)
+        );
+        assert_eq!(
+            running_config_matches(&serde_json::json!({ "hash": "stale" }), disk, &hash),
+            Some(false)
+        );
+        assert_eq!(
+            running_config_matches(&serde_json::json!({}), disk, &hash),
+            None
         );
     }
 
     #[test]
-    fn test_apply_model_provider_auth_maps_openai_models_for_codex_oauth() {
-        let mut provider_auths = std::collections::HashMap::new();
-        provider_auths.insert(
-            "openai".to_string(),
-            ProviderAuthData {
-                auth_method: "openai-codex".to_string(),
-                token: "".to_string(),
-                profile_key: Some("openai-codex:default".to_string()),
-                profile: Some(serde_json::json!({
-                    "provider": "openai-codex",
-                    "type": "oauth"
-                })),
-                oauth_provider_id: Some("openai-codex".to_string()),
-            },
+    fn test_merge_json_three_way() {
+        let base = serde_json::json!({
+            "gateway": { "port": 18789, "bind": "loopback" },
+            "agents": { "defaults": { "model": "a" } },
+            "old": true
+        });
+        let ours = serde_json::json!({
+            "gateway": { "port": 18790, "bind": "loopback" },
+            "agents": { "defaults": { "model": "b" } },
+            "old": true
+        });
+        let theirs = serde_json::json!({
+            "gateway": { "port": 18789, "bind": "lan" },
+            "agents": { "defaults": { "model": "c" } },
+            "plugins": { "entries": {} }
+        });
+        let mut conflicts = Vec::new();
+        let merged = merge_json_three_way(Some(&base), &ours, &theirs, "", &mut conflicts);
+        assert_eq!(
+            merged["gateway"],
+            serde_json::json!({ "port": 18790, "bind": "lan" })
         );
+        assert_eq!(merged["agents"]["defaults"]["model"], "b");
+        assert!(merged.get("old").is_none());
+        assert!(merged.get("plugins").is_some());
+        assert_eq!(conflicts, vec!["agents.defaults.model".to_string()]);
+    }
+
+    #[test]
+    fn test_validate_model_params_ranges_per_provider() {
+        let params = ModelParams {
+            temperature: Some(1.5),
+            ..Default::default()
+        };
+        assert!(validate_model_params("openai/gpt-5", &params).is_ok());
+        assert!(validate_model_params("anthropic/claude-sonnet-4-5", &params).is_err());
+        assert!(validate_model_params("no-provider", &ModelParams::default()).is_err());
 
+        let params = ModelParams {
+            max_tokens: Some(0),
+            ..Default::default()
+        };
+        assert!(validate_model_params("openai/gpt-5", &params).is_err());
+
+        let params = ModelParams {
+            reasoning_effort: Some("minimal".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_model_params("openai/gpt-5", &params).is_ok());
+        assert!(validate_model_params("google/gemini-2.5-pro", &params).is_err());
+    }
+
+    #[test]
+    fn test_apply_and_read_model_params() {
+        let model = "anthropic/claude-sonnet-4-5";
+        let mut config = serde_json::json!({
+            "agents": { "defaults": { "models": { model: { "alias": "sonnet", "params": { "topP": 0.9, "maxTokens": 10 } } } } }
+        });
+        let params = ModelParams {
+            temperature: Some(0.4),
+            max_tokens: None,
+            reasoning_effort: Some("high".to_string()),
+            compaction_mode: Some("safeguard".to_string()),
+        };
+        apply_model_params(&mut config, model, &params);
+        let entry = &config["agents"]["defaults"]["models"][model];
+        assert_eq!(entry["alias"], "sonnet");
+        assert_eq!(entry["params"]["topP"], 0.9);
+        assert!(entry["params"].get("maxTokens").is_none());
         assert_eq!(
-            apply_model_provider_auth("openai/gpt-5.4", &provider_auths),
-            "openai-codex/gpt-5.4"
+            config["agents"]["defaults"]["compaction"]["mode"],
+            "safeguard"
         );
+        assert_eq!(read_model_params(&config, model), params);
+    }
+
+    #[test]
+    fn test_apply_and_read_subagent_config() {
+        let mut config = serde_json::json!({
+            "agents": { "defaults": { "subagents": { "maxConcurrent": 8, "archiveAfterMinutes": 60 } } }
+        });
+        assert_eq!(read_subagent_config(&config), (8, Vec::new()));
+
+        let models = vec![
+            "openai/gpt-5-mini".to_string(),
+            "anthropic/claude-haiku-4-5".to_string(),
+        ];
+        apply_subagent_config(&mut config, 3, &models);
         assert_eq!(
-            auth_provider_id_for_config(
-                "openai",
-                provider_auths.get("openai").unwrap(),
-                &provider_auths
-            ),
-            "openai-codex"
+            config["agents"]["defaults"]["subagents"]["archiveAfterMinutes"],
+            60
         );
+        assert_eq!(read_subagent_config(&config), (3, models));
+
+        apply_subagent_config(&mut config, 3, &[]);
+        assert!(config["agents"]["defaults"]["subagents"]
+            .get("model")
+            .is_none());
     }
 
     #[test]
-    fn test_build_effective_models_catalog_uses_effective_namespace_only() {
-        let models = build_effective_models_catalog(
-            "openai-codex/gpt-5.4",
-            &["openai-codex/gpt-5.4-mini".to_string()],
-        );
+    fn test_validate_subagent_settings() {
+        let mut settings = SubagentSettings {
+            max_concurrent: 4,
+            ..Default::default()
+        };
+        assert!(validate_subagent_settings(&settings).is_ok());
+        settings.budget_caps.insert("research".to_string(), 0.0);
+        assert!(validate_subagent_settings(&settings).is_err());
+        settings.budget_caps.clear();
+        settings.max_concurrent = 0;
+        assert!(validate_subagent_settings(&settings).is_err());
+    }
 
-        assert!(models.contains_key("openai-codex/gpt-5.4"));
-        assert!(models.contains_key("openai-codex/gpt-5.4-mini"));
-        assert!(!models.contains_key("openai/gpt-5.4"));
+    #[test]
+    fn test_is_valid_heartbeat_interval() {
+        assert!(is_valid_heartbeat_interval("30m"));
+        assert!(is_valid_heartbeat_interval("2h"));
+        assert!(!is_valid_heartbeat_interval("0m"));
+        assert!(!is_valid_heartbeat_interval("m"));
+        assert!(!is_valid_heartbeat_interval("1.5h"));
+        assert!(!is_valid_heartbeat_interval(""));
     }
 
     #[test]
-    fn test_parse_lsof_listener_info_parses_multiple_records() {
-        let parsed = parse_lsof_listener_info("p62370\ncopenclaw-models\np70001\ncnode\n");
+    fn test_apply_and_read_heartbeat_config() {
+        let mut config = serde_json::json!({});
+        let settings = HeartbeatSettings {
+            enabled: true,
+            every: "30m".to_string(),
+            prompt: Some("Check the inbox".to_string()),
+            heartbeat_md: None,
+        };
+        apply_heartbeat_config(&mut config, &settings);
+        assert_eq!(
+            read_heartbeat_config(&config),
+            (true, "30m".to_string(), Some("Check the inbox".to_string()))
+        );
 
+        apply_heartbeat_config(&mut config, &HeartbeatSettings::default());
         assert_eq!(
-            parsed,
-            vec![
-                PortListenerInfo {
-                    pid: 62370,
-                    command: "openclaw-models".to_string()
-                },
-                PortListenerInfo {
-                    pid: 70001,
-                    command: "node".to_string()
-                }
-            ]
+            config["agents"]["defaults"]["heartbeat"],
+            serde_json::json!({ "enabled": false })
         );
+        assert!(!read_heartbeat_config(&config).0);
     }
 
     #[test]
-    fn test_is_openclaw_listener_only_matches_openclaw_processes() {
-        assert!(is_openclaw_listener(&PortListenerInfo {
-            pid: 1,
-            command: "openclaw-models".to_string()
-        }));
-        assert!(is_openclaw_listener(&PortListenerInfo {
-            pid: 2,
-            command: "OpenClaw".to_string()
-        }));
-        assert!(!is_openclaw_listener(&PortListenerInfo {
-            pid: 3,
-            command: "node".to_string()
-        }));
+    fn test_parse_heartbeat_log_line() {
+        let run = parse_heartbeat_log_line(
+            r#"{"0":"heartbeat: run completed","_meta":{"date":"2025-03-01T10:00:00.000Z"}}"#,
+        )
+        .unwrap();
+        assert_eq!(run.outcome, "ran");
+        assert_eq!(run.time.as_deref(), Some("2025-03-01T10:00:00.000Z"));
+        assert_eq!(
+            parse_heartbeat_log_line(r#"{"msg":"Heartbeat failed: timeout","time":"t"}"#)
+                .unwrap()
+                .outcome,
+            "failed"
+        );
+        assert!(parse_heartbeat_log_line(r#"{"msg":"gateway ready"}"#).is_none());
+        assert!(parse_heartbeat_log_line("heartbeat plain text").is_none());
     }
 
     #[test]
-    fn test_gateway_config_includes_mode_local() {
-        // The gateway config MUST include "mode": "local" to prevent
-        // "Gateway start blocked: set gateway.mode=local (current: unset)" error
-        let gateway_token = "test-token-123";
-        let gateway_auth_mode = "token";
-        let tailscale_mode = "off";
-        let gateway_port = 18789;
-        let gateway_bind = "127.0.0.1";
-
-        let config_val = serde_json::json!({
-            "gateway": {
-                "mode": "local",
-                "port": gateway_port,
-                "bind": gateway_bind,
-                "auth": { "mode": gateway_auth_mode, "token": gateway_token },
-                "tailscale": { "mode": tailscale_mode, "resetOnExit": false }
+    fn test_apply_and_read_messaging_policy() {
+        let mut config = serde_json::json!({
+            "messages": { "ackReactionScope": "group-mentions" },
+            "channels": {
+                "telegram": { "accounts": { "default": { "botToken": "t", "dmPolicy": "pairing" } } },
+                "whatsapp": { "enabled": true, "dmPolicy": "open", "allowFrom": ["*"] }
             }
         });
+        let mut policy = read_messaging_policy(&config);
+        assert_eq!(
+            policy.channels["telegram"].dm_policy.as_deref(),
+            Some("pairing")
+        );
 
-        let gateway = config_val.get("gateway").expect("gateway key must exist");
+        policy.ack_reaction_scope = "all".to_string();
+        policy.channels.insert(
+            "whatsapp".to_string(),
+            ChannelMessagingPolicy {
+                dm_policy: Some("closed".to_string()),
+                group_policy: Some("allowlist".to_string()),
+                group_allow_from: vec!["+15550001111".to_string()],
+            },
+        );
+        policy
+            .channels
+            .insert("discord".to_string(), ChannelMessagingPolicy::default());
+        assert!(validate_messaging_policy(&policy).is_ok());
+        apply_messaging_policy(&mut config, &policy);
+
+        assert_eq!(config["messages"]["ackReactionScope"], "all");
+        assert_eq!(config["channels"]["whatsapp"]["dmPolicy"], "disabled");
+        assert!(config["channels"]["whatsapp"].get("allowFrom").is_none());
         assert_eq!(
-            gateway.get("mode").and_then(|v| v.as_str()),
-            Some("local"),
-            "gateway.mode must be set to 'local' to prevent startup failure"
+            config["channels"]["telegram"]["accounts"]["default"]["botToken"],
+            "t"
         );
-        assert_eq!(gateway.get("port").and_then(|v| v.as_u64()), Some(18789));
+        assert!(config["channels"].get("discord").is_none());
         assert_eq!(
-            gateway
-                .get("auth")
-                .and_then(|a| a.get("token"))
-                .and_then(|t| t.as_str()),
-            Some("test-token-123")
+            read_messaging_policy(&config).channels["whatsapp"],
+            policy.channels["whatsapp"]
         );
     }
 
     #[test]
-    fn test_gateway_startup_command_sequence() {
-        // Verify the correct command sequence for gateway startup on Windows/WSL:
-        // 1. gateway install --force
-        // 2. gateway stop (prevent crash-loop before config is written)
-        // 3. ... config is written ...
-        // 4. systemctl reset-failed (recover from any crash-loop)
-        // 5. gateway stop
-        // 6. gateway start
-
-        let nvm_prefix = "source ~/.nvm/nvm.sh && ";
-
-        // Commands after install (prevent crash-loop)
-        let install_cmd = format!("{}openclaw gateway install --force", nvm_prefix);
-        let stop_after_install_cmd = format!("{}openclaw gateway stop || true", nvm_prefix);
-
-        // Commands before start (recover from crash-loop)
-        let reset_failed_cmd =
-            "systemctl --user reset-failed openclaw-gateway.service 2>/dev/null || true";
-        let stop_before_start_cmd = format!("{}openclaw gateway stop || true", nvm_prefix);
-        let start_cmd = format!("{}openclaw gateway start", nvm_prefix);
-
-        // Verify install is followed by stop
-        assert!(install_cmd.contains("gateway install --force"));
-        assert!(stop_after_install_cmd.contains("gateway stop"));
-
-        // Verify start sequence includes reset-failed
-        assert!(reset_failed_cmd.contains("reset-failed"));
-        assert!(reset_failed_cmd.contains("openclaw-gateway.service"));
-
-        // Verify stop comes before start
-        assert!(stop_before_start_cmd.contains("gateway stop"));
-        assert!(start_cmd.contains("gateway start"));
-        assert!(!start_cmd.contains("stop"));
+    fn test_validate_messaging_policy() {
+        let mut policy = MessagingPolicy {
+            ack_reaction_scope: "everything".to_string(),
+            ..Default::default()
+        };
+        assert!(validate_messaging_policy(&policy).is_err());
+        policy.ack_reaction_scope = "direct".to_string();
+        policy.channels.insert(
+            "telegram".to_string(),
+            ChannelMessagingPolicy {
+                group_policy: Some("allowlist".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(validate_messaging_policy(&policy).is_err());
     }
 
     #[test]
-    fn test_gateway_config_preserves_auth_token() {
-        // When reconfiguring, existing gateway auth token should be preserved
-        let existing_config = serde_json::json!({
-            "gateway": {
-                "mode": "local",
-                "auth": { "mode": "token", "token": "existing-secret-token" }
-            }
+    fn test_telegram_account_add_rename_remove() {
+        let token = "123456:ABCdefGHIjklMNOpqrSTUvwx";
+        let mut config = serde_json::json!({
+            "channels": { "telegram": { "accounts": { "default": { "botToken": "1:x", "name": "Primary Bot" } } } }
         });
-
-        let token = existing_config
-            .get("gateway")
-            .and_then(|g| g.get("auth"))
-            .and_then(|a| a.get("token"))
-            .and_then(|t| t.as_str());
-
+        add_telegram_account_to_config(&mut config, "work", token, "Work Bot", "open").unwrap();
+        assert!(
+            add_telegram_account_to_config(&mut config, "work", token, "Again", "pairing").is_err()
+        );
+        assert!(
+            add_telegram_account_to_config(&mut config, "other", token, "Dup", "pairing").is_err()
+        );
+        assert!(
+            add_telegram_account_to_config(&mut config, "bad", "not-a-token", "X", "pairing")
+                .is_err()
+        );
         assert_eq!(
-            token,
-            Some("existing-secret-token"),
-            "Gateway auth token must be preserved during reconfiguration"
+            config["channels"]["telegram"]["accounts"]["work"]["allowFrom"],
+            serde_json::json!(["*"])
         );
+
+        rename_telegram_account_in_config(&mut config, "work", " Office ").unwrap();
+        let accounts = telegram_accounts(&config);
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[1].name.as_deref(), Some("Office"));
+        assert!(accounts[1].has_token);
+
+        remove_telegram_account_from_config(&mut config, "work").unwrap();
+        remove_telegram_account_from_config(&mut config, "default").unwrap();
+        assert!(config["channels"].get("telegram").is_none());
     }
 
     #[test]
-    fn test_parse_gateway_token_cli_output_rejects_empty_and_nullish_values() {
+    fn test_telegram_group_id_from_session_key() {
         assert_eq!(
-            parse_gateway_token_cli_output("token-123\n"),
-            Some("token-123".to_string())
+            telegram_group_id_from_session_key("agent:main:telegram:group:-1001234:topic:7"),
+            Some("-1001234".to_string())
         );
         assert_eq!(
-            parse_gateway_token_cli_output("\"token-123\""),
-            Some("token-123".to_string())
+            telegram_group_id_from_session_key("agent:main:telegram:dm:42"),
+            None
         );
-        assert_eq!(parse_gateway_token_cli_output(""), None);
-        assert_eq!(parse_gateway_token_cli_output("null"), None);
-        assert_eq!(parse_gateway_token_cli_output("undefined"), None);
-    }
-
-    #[test]
-    fn test_telegram_pairing_status_from_dm_policy() {
-        assert!(telegram_pairing_status_from_dm_policy("\"allowlist\""));
-        assert!(telegram_pairing_status_from_dm_policy("open"));
-        assert!(telegram_pairing_status_from_dm_policy("\"open\""));
-        assert!(!telegram_pairing_status_from_dm_policy("pairing"));
-        assert!(!telegram_pairing_status_from_dm_policy("\"pairing\""));
+        assert_eq!(telegram_group_id_from_session_key("agent:main:main"), None);
     }
 
     #[test]
-    fn test_extract_telegram_dm_policy_from_config_supports_default_and_legacy_layouts() {
-        let account_scoped = serde_json::json!({
-            "channels": {
-                "telegram": {
-                    "accounts": {
-                        "default": {
-                            "dmPolicy": "open"
-                        }
-                    }
-                }
-            }
+    fn test_telegram_groups_approve_and_deny() {
+        let mut config = serde_json::json!({
+            "channels": { "telegram": { "accounts": { "default": { "botToken": "t" } } } }
         });
+        let session = |key: &str, name: &str| SessionSummary {
+            key: key.to_string(),
+            session_id: "s".to_string(),
+            updated_at: None,
+            channel: Some("telegram".to_string()),
+            display_name: Some(name.to_string()),
+            archived: false,
+        };
+        let sessions = vec![
+            session("agent:main:telegram:group:-100", "Family"),
+            session("agent:main:telegram:group:-200", "Work"),
+        ];
+        set_telegram_group_in_config(&mut config, "-100", true, None, Some("Family")).unwrap();
+        set_telegram_group_in_config(&mut config, "-300", false, None, None).unwrap();
+        assert!(set_telegram_group_in_config(&mut config, "abc", true, None, None).is_err());
+
+        let groups = telegram_groups(&config, &sessions);
+        let status: Vec<(&str, &str)> = groups
+            .iter()
+            .map(|g| (g.chat_id.as_str(), g.status.as_str()))
+            .collect();
         assert_eq!(
-            extract_telegram_dm_policy_from_config(&account_scoped),
-            Some("open".to_string())
+            status,
+            vec![
+                ("-100", "approved"),
+                ("-200", "pending"),
+                ("-300", "denied")
+            ]
         );
-
-        let legacy = serde_json::json!({
-            "channels": {
-                "telegram": {
-                    "dmPolicy": "pairing"
-                }
-            }
-        });
+        assert_eq!(groups[1].title.as_deref(), Some("Work"));
         assert_eq!(
-            extract_telegram_dm_policy_from_config(&legacy),
-            Some("pairing".to_string())
+            config["channels"]["telegram"]["groups"]["-100"]["requireMention"],
+            true
         );
     }
 
     #[test]
-    fn test_read_telegram_dm_policy_from_config_str_fallback_detects_linked_state() {
-        let config = r#"{
-            "channels": {
-                "telegram": {
-                    "dmPolicy": "open"
-                }
-            }
-        }"#;
-
-        let policy = read_telegram_dm_policy_from_config_str(config);
-        assert_eq!(policy, Some("open".to_string()));
-        assert!(telegram_pairing_status_from_dm_policy(policy.as_deref().unwrap()));
+    fn test_telegram_transport_round_trip() {
+        let mut config = serde_json::json!({
+            "channels": { "telegram": { "accounts": { "default": { "botToken": "t" } } } }
+        });
+        assert_eq!(read_telegram_transport(&config).mode, "polling");
+        apply_telegram_transport(&mut config, Some(("https://bot.example.com/tg", "s3cret")))
+            .unwrap();
+        let transport = read_telegram_transport(&config);
+        assert_eq!(transport.mode, "webhook");
+        assert_eq!(
+            transport.webhook_url.as_deref(),
+            Some("https://bot.example.com/tg")
+        );
+        apply_telegram_transport(&mut config, None).unwrap();
+        assert!(config["channels"]["telegram"]
+            .get("webhookSecret")
+            .is_none());
+        assert!(apply_telegram_transport(&mut serde_json::json!({}), None).is_err());
     }
 
     #[test]
-    fn test_read_telegram_dm_policy_via_cli_tries_legacy_after_primary_failure() {
-        let mut commands = Vec::new();
-        let policy = read_telegram_dm_policy_via_cli(|cmd| {
-            commands.push(cmd.to_string());
-            match cmd {
-                "openclaw config get channels.telegram.accounts.default.dmPolicy" => {
-                    Err("missing".to_string())
-                }
-                "openclaw config get channels.telegram.dmPolicy" => Ok("\"open\"".to_string()),
-                _ => Err("unexpected command".to_string()),
-            }
-        })
-        .expect("cli fallback succeeds");
-
-        assert_eq!(policy, Some("open".to_string()));
+    fn test_webhook_url_validation_and_info() {
+        assert!(validate_webhook_url("https://bot.example.com/tg").is_ok());
+        assert!(validate_webhook_url("http://bot.example.com/tg").is_err());
+        assert!(validate_webhook_url("https://localhost:8787/tg").is_err());
         assert_eq!(
-            commands,
-            vec![
-                "openclaw config get channels.telegram.accounts.default.dmPolicy".to_string(),
-                "openclaw config get channels.telegram.dmPolicy".to_string(),
-            ]
+            tailscale_webhook_url(
+                &serde_json::json!({ "Self": { "DNSName": "box.tail1.ts.net." } })
+            ),
+            Some("https://box.tail1.ts.net/telegram-webhook".to_string())
         );
+
+        let info = serde_json::json!({ "ok": true, "result": { "url": "https://a.example/tg" } });
+        assert!(check_webhook_info(&info, Some("https://a.example/tg")).is_ok());
+        assert!(check_webhook_info(&info, None).is_err());
+        let failing = serde_json::json!({ "result": { "url": "https://a.example/tg", "last_error_message": "Connection refused" } });
+        assert!(check_webhook_info(&failing, Some("https://a.example/tg")).is_err());
     }
 
     #[test]
-    fn test_telegram_allow_from_entries_from_str_detects_approved_senders() {
-        let content = r#"{
-            "version": 1,
-            "allowFrom": ["5162540072"]
-        }"#;
-        assert_eq!(telegram_allow_from_entries_from_str(content), Some(1));
+    fn test_apply_email_channel() {
+        let server = |host: &str, port: u16| email::MailServer {
+            host: host.to_string(),
+            port,
+            user: "agent@example.com".to_string(),
+            password: "app-password".to_string(),
+        };
+        let mut email_config = EmailChannelConfig {
+            address: "agent@example.com".to_string(),
+            imap: server("imap.example.com", 993),
+            smtp: server("smtp.example.com", 587),
+            allow_from: vec!["me@example.com".to_string()],
+        };
+        assert!(validate_email_channel(&email_config).is_ok());
 
-        let empty = r#"{
-            "version": 1,
-            "allowFrom": []
-        }"#;
-        assert_eq!(telegram_allow_from_entries_from_str(empty), Some(0));
+        let mut config = serde_json::json!({ "channels": { "telegram": { "enabled": true } } });
+        apply_email_channel(&mut config, &email_config);
+        assert_eq!(config["channels"]["telegram"]["enabled"], true);
+        assert_eq!(config["channels"]["email"]["smtp"]["port"], 587);
+        assert_eq!(config["channels"]["email"]["dmPolicy"], "allowlist");
+        assert_eq!(config["plugins"]["entries"]["email"]["enabled"], true);
+
+        email_config.address = "not-an-address".to_string();
+        assert!(validate_email_channel(&email_config).is_err());
     }
 
     #[test]
-    fn test_telegram_allow_from_is_linked_local_detects_account_file() {
-        let temp_dir = std::env::temp_dir()
-            .join(format!("clawnetes-telegram-allowfrom-{}", uuid::Uuid::new_v4()));
-        fs::create_dir_all(&temp_dir).expect("create temp telegram credentials dir");
-        fs::write(
-            temp_dir.join("telegram-default-allowFrom.json"),
-            r#"{"version":1,"allowFrom":["5162540072"]}"#,
-        )
-        .expect("write allowFrom file");
+    fn test_twilio_config_validation_and_apply() {
+        let mut twilio = TwilioConfig {
+            account_sid: format!("AC{}", "0123456789abcdef".repeat(2)),
+            auth_token: "fedcba9876543210".repeat(2),
+            phone_number: "+15551234567".to_string(),
+        };
+        assert!(validate_twilio_config(&twilio).is_ok());
 
-        assert!(telegram_allow_from_is_linked_local(&temp_dir));
+        let listing =
+            serde_json::json!({ "incoming_phone_numbers": [{ "phone_number": "+15551234567" }] });
+        assert!(twilio_number_listed(&listing, "+15551234567"));
+        assert!(!twilio_number_listed(&listing, "+15557654321"));
 
-        let _ = fs::remove_dir_all(&temp_dir);
+        let mut config = serde_json::json!({});
+        apply_twilio_sms_channel(&mut config, &twilio);
+        assert_eq!(config["channels"]["sms"]["fromNumber"], "+15551234567");
+        assert_eq!(config["plugins"]["entries"]["sms"]["enabled"], true);
+
+        twilio.phone_number = "5551234567".to_string();
+        assert!(validate_twilio_config(&twilio).is_err());
+        twilio.phone_number = "+15551234567".to_string();
+        twilio.account_sid = "SK123".to_string();
+        assert!(validate_twilio_config(&twilio).is_err());
     }
 
     #[test]
-    fn test_whatsapp_session_is_linked_detects_nested_files() {
-        let temp_dir =
-            std::env::temp_dir().join(format!("clawnetes-whatsapp-test-{}", uuid::Uuid::new_v4()));
-        let nested = temp_dir.join("nested");
-        fs::create_dir_all(&nested).expect("create temp whatsapp dir");
-        fs::write(nested.join("session.json"), "{}").expect("write temp whatsapp file");
+    fn test_set_channel_enabled_and_status() {
+        let mut config = serde_json::json!({
+            "channels": { "telegram": { "accounts": { "default": { "botToken": "t" } } } },
+            "plugins": { "entries": { "telegram": { "enabled": true } } }
+        });
+        set_channel_enabled_in_config(&mut config, "telegram", false).unwrap();
+        assert_eq!(config["channels"]["telegram"]["enabled"], false);
+        assert_eq!(
+            config["channels"]["telegram"]["accounts"]["default"]["enabled"],
+            false
+        );
+        assert_eq!(config["plugins"]["entries"]["telegram"]["enabled"], false);
+        assert!(set_channel_enabled_in_config(&mut config, "discord", true).is_err());
 
-        assert!(whatsapp_session_is_linked(&temp_dir));
+        let status = serde_json::json!({
+            "channels": { "whatsapp": { "running": true } },
+            "channelAccounts": { "telegram": [{ "accountId": "default", "running": false, "connected": true }] }
+        });
+        assert_eq!(channel_running_from_status(&status, "telegram"), Some(true));
+        assert_eq!(channel_running_from_status(&status, "whatsapp"), Some(true));
+        assert_eq!(channel_running_from_status(&status, "sms"), Some(false));
+        assert_eq!(
+            channel_running_from_status(&serde_json::json!({}), "sms"),
+            None
+        );
+    }
 
-        let _ = fs::remove_dir_all(&temp_dir);
+    #[test]
+    fn test_parse_resource_outputs() {
+        let df = "Filesystem 1024-blocks Used Available Capacity Mounted on\n/dev/disk3s5 971350180 500000000 1048576 90% /System/Volumes/Data\n";
+        assert_eq!(parse_df_available_kb(df), Some(1048576));
+        let meminfo = "MemTotal:       16318480 kB\nMemFree:         1203340 kB\nMemAvailable:    8123456 kB\n";
+        assert_eq!(parse_meminfo_available_kb(meminfo), Some(8123456));
+        let vm_stat = "Mach Virtual Memory Statistics: (page size of 16384 bytes)\nPages free:                               1000.\nPages active:                             9999.\nPages inactive:                           2000.\nPages speculative:                         100.\n";
+        assert_eq!(parse_vm_stat_available_kb(vm_stat), Some(3100 * 16));
     }
 
     #[test]
-    fn test_whatsapp_session_is_linked_false_for_missing_or_empty_dir() {
-        let missing_dir =
-            std::env::temp_dir().join(format!("clawnetes-whatsapp-test-{}", uuid::Uuid::new_v4()));
-        assert!(!whatsapp_session_is_linked(&missing_dir));
+    fn test_resource_warnings() {
+        let mut check = ResourceCheck {
+            free_disk_mb: Some(50_000),
+            available_memory_mb: Some(8_000),
+            on_battery: Some(false),
+            low_power_mode: None,
+            warnings: Vec::new(),
+        };
+        assert!(resource_warnings(&check).is_empty());
+        check.free_disk_mb = Some(500);
+        check.on_battery = Some(true);
+        assert_eq!(resource_warnings(&check).len(), 2);
+    }
 
-        fs::create_dir_all(&missing_dir).expect("create empty whatsapp dir");
-        assert!(!whatsapp_session_is_linked(&missing_dir));
+    #[test]
+    fn test_diagnose_connectivity() {
+        let check =
+            |name: &str, dns_ok: bool, https_ok: bool, http_status: Option<u16>| EndpointCheck {
+                name: name.to_string(),
+                host: format!("{}.example", name),
+                dns_ok,
+                https_ok,
+                http_status,
+                error: None,
+                clock_skew_secs: None,
+                tls_interceptor: None,
+            };
+        let status = |checks: &[EndpointCheck], proxy: bool| diagnose_connectivity(checks, proxy).0;
 
-        let _ = fs::remove_dir_all(&missing_dir);
+        let ok = [
+            check("npm", true, true, Some(200)),
+            check("provider", true, true, Some(404)),
+        ];
+        assert_eq!(status(&ok, false), "ok");
+        let offline = [
+            check("npm", false, false, None),
+            check("provider", false, false, None),
+        ];
+        assert_eq!(status(&offline, false), "no_internet");
+        assert_eq!(status(&offline, true), "proxy_required");
+        let firewalled = [
+            check("npm", true, false, None),
+            check("provider", true, false, None),
+        ];
+        assert_eq!(status(&firewalled, false), "proxy_required");
+        let blocked = [
+            check("npm", true, true, Some(200)),
+            check("provider", true, false, None),
+        ];
+        assert_eq!(status(&blocked, false), "provider_blocked");
+        let auth = [
+            check("npm", true, false, Some(407)),
+            check("telegram", true, true, Some(200)),
+        ];
+        assert_eq!(status(&auth, false), "proxy_required");
     }
 
     #[test]
-    fn test_parse_dashboard_url_cli_output_finds_url_amid_other_output() {
-        let output = "Doctor warnings...\nDashboard URL: http://127.0.0.1:18789/#token=abc123\nCopied to clipboard.\n";
+    fn test_clock_and_tls_checks() {
         assert_eq!(
-            parse_dashboard_url_cli_output(output),
-            Some("http://127.0.0.1:18789/#token=abc123".to_string())
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784111777)
         );
-        assert_eq!(parse_dashboard_url_cli_output("no dashboard line"), None);
-    }
+        assert_eq!(parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        assert_eq!(parse_http_date("yesterday"), None);
 
-    #[test]
-    fn test_rebuild_models_catalog_replaces_stale_openai_entry_during_merge() {
-        let mut config_json = serde_json::json!({
-            "agents": {
-                "defaults": {
-                    "models": {
-                        "openai/gpt-5.4": {}
-                    }
-                }
-            }
-        });
+        assert_eq!(
+            detect_tls_interceptor(b"\x30\x82CN=Zscaler Intermediate Root CA"),
+            Some("Zscaler")
+        );
+        assert_eq!(detect_tls_interceptor(b"\x30\x82O=DigiCert Inc"), None);
+
+        assert!(clock_and_tls_warnings(Some(30), None).is_empty());
+        let warnings = clock_and_tls_warnings(Some(-600), Some("Netskope"));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("10 minutes ahead of"));
+        assert!(warnings[1].contains("(by Netskope)"));
+        let warnings = clock_and_tls_warnings(None, Some(UNKNOWN_TLS_INTERCEPTOR));
+        assert!(warnings[0].starts_with(
+            "HTTPS certificate not issued by a public CA: traffic is being inspected."
+        ));
 
-        let effective_primary_model = "openai-codex/gpt-5.4".to_string();
-        let effective_fallback_models = vec!["openai-codex/gpt-5.4-mini".to_string()];
+        let untrusted = std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "invalid peer certificate: UnknownIssuer",
+        );
+        assert!(is_untrusted_certificate_error(&untrusted));
+        let refused = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+        assert!(!is_untrusted_certificate_error(&refused));
+    }
 
-        if let Some(defaults) = config_json
-            .get_mut("agents")
-            .and_then(|a| a.get_mut("defaults"))
-            .and_then(|d| d.as_object_mut())
-        {
-            defaults.insert(
-                "models".to_string(),
-                serde_json::Value::Object(build_effective_models_catalog(
-                    &effective_primary_model,
-                    &effective_fallback_models,
-                )),
-            );
-        }
+    #[test]
+    fn test_setup_manifest_converges_idempotently() {
+        let manifest = parse_setup_manifest(
+            "team.json",
+            r#"{
+                "providers": { "anthropic": { "apiKeyEnv": "TEAM_KEY" } },
+                "model": { "primary": "anthropic/claude-sonnet-4-5", "fallbacks": ["openai/gpt-5"] },
+                "channels": { "telegram": { "dmPolicy": "allowlist" } },
+                "plugins": ["telegram"],
+                "workspace": { "files": { "AGENTS.md": "Be concise." } }
+            }"#,
+        )
+        .unwrap();
 
-        let models = config_json
-            .get("agents")
-            .and_then(|a| a.get("defaults"))
-            .and_then(|d| d.get("models"))
-            .and_then(|m| m.as_object())
-            .expect("models object");
+        let mut config = serde_json::json!({ "channels": { "telegram": { "botToken": "t" } } });
+        let changed = apply_manifest_to_config(&mut config, &manifest);
+        assert_eq!(
+            changed,
+            vec!["model", "channels.telegram", "plugins.telegram"]
+        );
+        assert_eq!(config["channels"]["telegram"]["botToken"], "t");
+        assert!(apply_manifest_to_config(&mut config, &manifest).is_empty());
+
+        let env = |var: &str| (var == "TEAM_KEY").then(|| "sk-ant-test".to_string());
+        let mut doc = serde_json::json!({});
+        let auth = apply_manifest_to_auth_profiles(&mut doc, &manifest.providers, &env).unwrap();
+        assert_eq!(auth, vec!["auth.anthropic"]);
+        assert_eq!(doc["lastGood"]["anthropic"], "anthropic:default");
+        assert!(
+            apply_manifest_to_auth_profiles(&mut doc, &manifest.providers, &env)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(apply_manifest_to_auth_profiles(&mut doc, &manifest.providers, &|_| None).is_err());
+    }
 
-        assert!(models.contains_key("openai-codex/gpt-5.4"));
-        assert!(models.contains_key("openai-codex/gpt-5.4-mini"));
-        assert!(!models.contains_key("openai/gpt-5.4"));
+    #[test]
+    fn test_parse_setup_manifest_rejects_bad_input() {
+        assert!(parse_setup_manifest("m.yaml", "model: x").is_err());
+        assert!(parse_setup_manifest("m.json", r#"{ "model": { "primary": "gpt" } }"#).is_err());
+        assert!(parse_setup_manifest(
+            "m.json",
+            r#"{ "workspace": { "files": { "../x.md": "" } } }"#
+        )
+        .is_err());
+        assert!(parse_setup_manifest("m.json", r#"{ "unknown": true }"#).is_err());
     }
 
     #[test]
-    fn test_extract_gateway_token_from_config_reads_gateway_auth_token() {
-        let config = serde_json::json!({
-            "gateway": {
-                "auth": {
-                    "token": "config-token-456"
-                }
-            }
+    fn test_config_drift_and_fix() {
+        let expected = serde_json::json!({
+            "gateway": { "port": 18789, "auth": { "token": "secret-a" } },
+            "agents": { "defaults": { "models": { "openai/gpt-5.4": { "alias": "gpt" } } } },
+            "meta": { "lastTouchedAt": "a" }
+        });
+        let mut actual = serde_json::json!({
+            "gateway": { "port": 18800, "auth": { "token": "secret-b" } },
+            "agents": { "defaults": { "models": {} } },
+            "tools": { "profile": "full" },
+            "meta": { "lastTouchedAt": "b" }
         });
+        let drift = config_drift("openclaw.json", &expected, &actual);
+        let paths: Vec<&str> = drift.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/agents/defaults/models",
+                "/agents/defaults/models/openai~1gpt-5.4/alias",
+                "/gateway/auth/token",
+                "/gateway/port",
+                "/tools/profile"
+            ]
+        );
+        assert_eq!(drift[2].expected, Some(serde_json::json!("••••")));
 
+        for entry in &drift {
+            set_json_pointer(
+                &mut actual,
+                &entry.path,
+                expected.pointer(&entry.path).cloned(),
+            );
+        }
+        assert!(config_drift("openclaw.json", &expected, &actual).is_empty());
         assert_eq!(
-            extract_gateway_token_from_config(&config.to_string(), "config").unwrap(),
-            "config-token-456"
+            actual["agents"]["defaults"]["models"]["openai/gpt-5.4"]["alias"],
+            "gpt"
         );
     }
 
     #[test]
-    fn test_wsl_root_command_uses_explicit_distro() {
-        // wsl_root_command should use `-d Ubuntu` for robustness
-        // Verify the expected argument structure
-        let cmd = "echo hello";
-        let expected_args = vec![
-            "-d",
-            "Ubuntu",
-            "--user",
-            "root",
-            "--",
-            "/bin/bash",
-            "-c",
-            cmd,
-        ];
-        assert_eq!(expected_args[0], "-d");
-        assert_eq!(expected_args[1], "Ubuntu");
-        assert_eq!(expected_args[2], "--user");
-        assert_eq!(expected_args[3], "root");
-        assert_eq!(expected_args[7], cmd);
+    fn test_remote_deploy_progress() {
+        assert_eq!(remote_deploy_percent("connect", false), 0);
+        assert_eq!(remote_deploy_percent("setup", false), 50);
+        assert_eq!(remote_deploy_percent("verify", true), 100);
+
+        let check = PrereqCheck {
+            node_installed: true,
+            docker_running: true,
+            openclaw_installed: false,
+            package_managers: vec!["npm".to_string()],
+            resources: ResourceCheck {
+                warnings: vec!["Less than 1 GB of free disk space".to_string()],
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            remote_prerequisite_summary(&check),
+            "Node.js found; openclaw missing (will install); Less than 1 GB of free disk space"
+        );
     }
 
-    #[test]
-    fn test_wsl_conf_content_format() {
-        // The wsl.conf written to set default user must follow INI format
-        let expected_content = "[user]\ndefault=openclaw\n";
-        assert!(expected_content.starts_with("[user]"));
-        assert!(expected_content.contains("default=openclaw"));
-        // Verify it's valid INI-style (section header + key=value)
-        let lines: Vec<&str> = expected_content.trim().lines().collect();
-        assert_eq!(lines.len(), 2);
-        assert_eq!(lines[0], "[user]");
-        assert_eq!(lines[1], "default=openclaw");
+    #[test]
+    fn test_container_env_vars() {
+        let profiles = serde_json::json!({
+            "profiles": {
+                "anthropic:default": { "type": "token", "provider": "anthropic", "token": "sk-ant" },
+                "lmstudio:default": { "type": "token", "provider": "lmstudio", "token": "dummy-token" },
+                "openai-codex:default": { "type": "oauth", "provider": "openai-codex", "access": "x" }
+            }
+        });
+        let vars = container_env_vars("gw-token", &profiles).unwrap();
+        assert_eq!(
+            render_container_env_file(&vars),
+            "OPENCLAW_GATEWAY_TOKEN=gw-token\nANTHROPIC_API_KEY=sk-ant\n"
+        );
+        assert!(container_env_vars("bad\ntoken", &profiles).is_err());
+        assert_eq!(provider_env_var("google-vertex"), "GOOGLE_VERTEX_API_KEY");
     }
 
     #[test]
-    fn test_wsl_user_setup_command_structure() {
-        // The user setup command should create user, set password, and add to sudo group
-        let user_cmd = "id openclaw >/dev/null 2>&1 || (useradd -m -s /bin/bash openclaw && echo 'openclaw:openclaw' | chpasswd && usermod -aG sudo openclaw)";
-        // Checks for existing user first
-        assert!(user_cmd.contains("id openclaw"));
-        // Creates with home dir and bash shell
-        assert!(user_cmd.contains("useradd -m -s /bin/bash openclaw"));
-        // Sets password
-        assert!(user_cmd.contains("chpasswd"));
-        // Adds to sudo group
-        assert!(user_cmd.contains("usermod -aG sudo openclaw"));
+    fn test_container_compose_and_state() {
+        let compose = render_compose_file("/home/me/.openclaw", 18789);
+        assert!(compose.contains("\"127.0.0.1:18789:18789\""));
+        assert!(compose.contains("\"/home/me/.openclaw:/home/me/.openclaw\""));
+        assert!(compose.contains("OPENCLAW_CONFIG_PATH: \"/home/me/.openclaw/openclaw.json\""));
+        assert!(compose.contains("env_file: clawnetes-container.env"));
+
+        assert_eq!(
+            parse_container_state("running|Up 5 minutes\n"),
+            Some(("running".to_string(), "Up 5 minutes".to_string()))
+        );
+        assert_eq!(parse_container_state("\n"), None);
     }
 
     #[test]
-    fn test_wait_for_wsl_ready_command_args() {
-        // wait_for_wsl_ready should use explicit distro and root user
-        let expected_args = ["-d", "Ubuntu", "-u", "root", "--", "echo", "ready"];
-        assert_eq!(expected_args[0], "-d");
-        assert_eq!(expected_args[1], "Ubuntu");
-        assert_eq!(expected_args[2], "-u");
-        assert_eq!(expected_args[3], "root");
-        assert_eq!(expected_args[6], "ready");
+    fn test_parse_gateway_service() {
+        let plist = r#"<plist><dict>
+  <key>ProgramArguments</key>
+  <array>
+    <string>/Users/me/.nvm/versions/node/v20.1.0/bin/node</string>
+    <string>/Users/me/.nvm/versions/node/v20.1.0/lib/node_modules/openclaw/dist/index.js</string>
+    <string>gateway</string>
+  </array>
+  <key>WorkingDirectory</key>
+  <string>/Users/me/.openclaw</string>
+</dict></plist>"#;
+        let (args, cwd) = parse_gateway_service("launchd", plist);
+        assert_eq!(args.len(), 3);
+        assert_eq!(args[2], "gateway");
+        assert_eq!(cwd.as_deref(), Some("/Users/me/.openclaw"));
+
+        let unit =
+            "[Service]\nExecStart=\"/usr/bin/node\" /opt/openclaw/index.js gateway --port 18789\n";
+        let (args, cwd) = parse_gateway_service("systemd", unit);
+        assert_eq!(args[0], "/usr/bin/node");
+        assert_eq!(args.len(), 5);
+        assert_eq!(cwd, None);
     }
 
     #[test]
-    fn test_wsl_write_file_escapes_single_quotes() {
-        // wsl_write_file uses printf '%s' '...' > file, so single quotes must be escaped
-        let content = "it's a test with 'quotes' inside";
-        let escaped = content.replace('\'', "'\\''");
-        let cmd = format!("printf '%s' '{}' > \"{}\"", escaped, "/tmp/test.txt");
+    fn test_corrected_service_content() {
+        let env = process::EnvironmentInfo {
+            shell_path: None,
+            node: Some("/opt/homebrew/bin/node".to_string()),
+            npm: None,
+            openclaw: None,
+            bun: None,
+            pnpm: None,
+            probed_at: 0,
+        };
+        let stale = "/Users/me/.nvm/versions/node/v20.1.0/bin/node";
+        let paths = vec![
+            ServicePathCheck {
+                path: stale.to_string(),
+                exists: false,
+                replacement: service_path_replacement(stale, &env),
+            },
+            ServicePathCheck {
+                path: "/missing/index.js".to_string(),
+                exists: false,
+                replacement: service_path_replacement("/missing/index.js", &env),
+            },
+        ];
+        assert_eq!(
+            corrected_service_content(&format!("<string>{}</string>", stale), &paths),
+            "<string>/opt/homebrew/bin/node</string>"
+        );
+        assert_eq!(paths[1].replacement, None);
+    }
 
-        // Verify the escaped content doesn't have unmatched quotes
-        assert!(cmd.contains("it'\\''s a test with '\\''quotes'\\'' inside"));
-        // Verify the command targets the right file
-        assert!(cmd.contains("/tmp/test.txt"));
+    #[test]
+    fn test_inject_gateway_env() {
+        let vars: std::collections::BTreeMap<String, String> =
+            [("OPENCLAW_LOG_LEVEL".to_string(), "debug".to_string())].into();
+        let managed: std::collections::BTreeSet<String> =
+            ["OPENCLAW_LOG_LEVEL".to_string(), "OLD_VAR".to_string()].into();
+
+        let unit = "[Unit]\nDescription=OpenClaw\n[Service]\nEnvironment=\"OLD_VAR=1\"\nEnvironment=HOME=/home/me\nExecStart=/usr/bin/node gateway\n";
+        let updated = inject_systemd_env(unit, &vars, &managed);
+        assert!(updated.contains("[Service]\nEnvironment=\"OPENCLAW_LOG_LEVEL=debug\"\n"));
+        assert!(updated.contains("Environment=HOME=/home/me"));
+        assert!(!updated.contains("OLD_VAR"));
+
+        let plist = "<plist><dict>\n    <key>Label</key>\n    <string>ai.openclaw.gateway</string>\n    <key>EnvironmentVariables</key>\n    <dict>\n      <key>HOME</key>\n      <string>/Users/me</string>\n      <key>OLD_VAR</key>\n      <string>1</string>\n    </dict>\n  </dict></plist>";
+        let updated = inject_launchd_env(plist, &vars, &managed);
+        assert_eq!(
+            updated.matches("<key>EnvironmentVariables</key>").count(),
+            1
+        );
+        assert!(updated.contains("<key>HOME</key>\n      <string>/Users/me</string>"));
+        assert!(updated.contains("<key>OPENCLAW_LOG_LEVEL</key>\n      <string>debug</string>"));
+        assert!(!updated.contains("OLD_VAR"));
+        assert_eq!(inject_launchd_env(&updated, &vars, &managed), updated);
     }
 
     #[test]
-    fn test_wsl_write_file_handles_json_content() {
-        // JSON content often has no single quotes, but may have special chars
-        let content = r#"{"gateway":{"mode":"local","auth":{"token":"abc123"}}}"#;
-        let escaped = content.replace('\'', "'\\''");
-        // JSON typically has no single quotes, so escaped should equal original
-        assert_eq!(escaped, content);
+    fn test_parse_env_listing() {
+        assert_eq!(
+            parse_env_listing(
+                "HOME=/home/me\nOPENCLAW_GATEWAY_TOKEN=abc=\nnot a var\n",
+                false
+            ),
+            vec![
+                ("HOME".to_string(), "/home/me".to_string()),
+                ("OPENCLAW_GATEWAY_TOKEN".to_string(), "abc=".to_string())
+            ]
+        );
+        let vars = parse_env_listing("/usr/local/bin/node index.js gateway HOME=/Users/me", true);
+        assert_eq!(vars, vec![("HOME".to_string(), "/Users/me".to_string())]);
+        assert!(!is_valid_env_var_name("1BAD"));
+        assert_eq!(
+            gateway_env_var("OPENCLAW_GATEWAY_TOKEN", "abc", true).value,
+            GATEWAY_ENV_MASK
+        );
     }
 
     #[test]
-    fn test_wsl_home_dir_command_structure() {
-        // wsl_home_dir calls shell_command("echo $HOME") which on Windows
-        // routes through wsl -- /bin/bash -c "echo $HOME"
-        // Verify the command string is correct
-        let cmd = "echo $HOME";
-        assert_eq!(cmd, "echo $HOME");
-        // The result should be trimmed (no trailing newline)
-        let simulated_output = "/home/testuser\n";
-        assert_eq!(simulated_output.trim(), "/home/testuser");
+    fn test_health_endpoint_response() {
+        let (status, _, body) = health_endpoint_response("/health", 18789, false, (None, None));
+        assert_eq!(status, 503);
+        assert!(body.contains("\"status\":\"down\""));
+
+        let (status, content_type, body) =
+            health_endpoint_response("/metrics?x=1", 18789, true, (Some(2.5), Some(1024)));
+        assert_eq!(status, 200);
+        assert!(content_type.starts_with("text/plain"));
+        assert!(body.contains("openclaw_gateway_up 1\n"));
+        assert!(body.contains("openclaw_gateway_cpu_percent 2.5\n"));
+        assert!(body.contains("openclaw_gateway_memory_bytes 1048576\n"));
+
+        assert_eq!(
+            health_endpoint_response("/nope", 1, true, (None, None)).0,
+            404
+        );
     }
 
     #[test]
-    fn test_wsl_read_file_command_structure() {
-        // wsl_read_file calls shell_command("cat \"path\" 2>/dev/null")
-        let path = "/home/user/.openclaw/openclaw.json";
-        let cmd = format!("cat \"{}\" 2>/dev/null", path);
-        assert!(cmd.contains("cat"));
-        assert!(cmd.contains(path));
-        assert!(cmd.contains("2>/dev/null"), "stderr should be suppressed");
+    fn test_telegram_deep_link() {
+        assert_eq!(
+            telegram_deep_link("@my_claw_bot", TELEGRAM_PAIRING_START),
+            "https://t.me/my_claw_bot?start=pair"
+        );
     }
 
     #[test]
-    fn test_configure_agent_uses_string_paths() {
-        // On all platforms, configure_agent now uses String paths (not PathBuf)
-        // so that on Windows the WSL home (/home/user) is used instead of C:\Users\user
-        let home = "/home/testuser";
-        let openclaw_root = format!("{}/.openclaw", home);
-        let workspace = format!("{}/workspace", openclaw_root);
-        let agents_dir = format!("{}/agents/main/agent", openclaw_root);
+    fn test_smoke_test_helpers() {
+        let config = serde_json::json!({
+            "agents": { "defaults": { "model": { "primary": "openrouter/anthropic/claude-sonnet-4" } } }
+        });
+        assert_eq!(
+            primary_model(&config),
+            Some((
+                "openrouter".to_string(),
+                "anthropic/claude-sonnet-4".to_string()
+            ))
+        );
+        assert_eq!(primary_model(&serde_json::json!({})), None);
 
-        assert_eq!(openclaw_root, "/home/testuser/.openclaw");
-        assert_eq!(workspace, "/home/testuser/.openclaw/workspace");
-        assert_eq!(agents_dir, "/home/testuser/.openclaw/agents/main/agent");
+        assert_eq!(smoke_result("cli", Ok(None)).status, "skip");
+        let failed = smoke_result("gateway", Err("down".to_string()));
+        assert_eq!(
+            (failed.status.as_str(), failed.message.as_str()),
+            ("fail", "down")
+        );
 
-        // Verify these are Unix-style paths (not Windows backslashes)
-        assert!(!openclaw_root.contains('\\'));
-        assert!(!workspace.contains('\\'));
+        let client = reqwest::Client::new();
+        assert!(completion_probe_request(&client, "groq", "llama", "k").is_some());
+        assert!(completion_probe_request(&client, "ollama", "llama3", "k").is_none());
     }
 
     #[test]
-    fn test_wsl_remove_dir_command_structure() {
-        // wsl_remove_dir should use rm -rf with the path and expand ~/ to $HOME/
-        let path = "~/.openclaw";
-        let cmd = if path.starts_with("~/") {
-            format!("rm -rf \"$HOME/{}\"", &path[2..])
-        } else {
-            format!("rm -rf \"{}\"", path)
+    fn test_log_read_start_and_complete_lines() {
+        let file = |path: &str, size| LogFile {
+            path: path.to_string(),
+            modified: 0,
+            size,
         };
-        assert!(cmd.contains("rm -rf"));
-        assert!(cmd.contains("$HOME/.openclaw"));
+        let tracked = ("/tmp/openclaw/a.log".to_string(), 100);
+        assert_eq!(log_read_start(None, &file("/tmp/openclaw/a.log", 500)), 500);
+        assert_eq!(
+            log_read_start(Some(&tracked), &file("/tmp/openclaw/a.log", 500)),
+            100
+        );
+        assert_eq!(
+            log_read_start(Some(&tracked), &file("/tmp/openclaw/a.log", 40)),
+            0
+        );
+        assert_eq!(
+            log_read_start(Some(&tracked), &file("/tmp/openclaw/b.log", 500)),
+            0
+        );
+
+        let (lines, consumed) = complete_log_lines("one\ntwo\nthr");
+        assert_eq!(lines, vec!["one", "two"]);
+        assert_eq!(consumed, 8);
+        assert_eq!(complete_log_lines("partial"), (Vec::new(), 0));
+    }
+
+    fn routing_rule(task: &str, agent: Option<&str>, primary: &str) -> RoutingRule {
+        RoutingRule {
+            task: task.to_string(),
+            agent: agent.map(|a| a.to_string()),
+            primary: primary.to_string(),
+            fallbacks: Vec::new(),
+        }
     }
 
     #[test]
-    fn test_validate_license_response_accepts_clean_purchase() {
-        let response = serde_json::json!({
-            "success": true,
-            "purchase": {
-                "refunded": false,
-                "chargebacked": false
-            }
+    fn test_validate_routing_rules_checks_providers() {
+        let config = serde_json::json!({
+            "agents": { "list": [{ "id": "coder" }] },
+            "models": { "providers": { "ollama": {} } }
         });
+        let auth =
+            serde_json::json!({ "profiles": { "anthropic:default": {}, "openai:default": {} } });
+        let providers = configured_model_providers(&config, &auth);
+        let valid = vec![
+            routing_rule("subagents", None, "ollama/llama3.2"),
+            routing_rule("agent", Some("coder"), "anthropic/claude-opus-4-6"),
+        ];
+        assert!(validate_routing_rules(&valid, &config, &providers).is_ok());
 
-        assert!(validate_license_response(&response).is_ok());
+        let unknown_provider = vec![routing_rule("image", None, "google/gemini-2.5-pro")];
+        assert!(
+            validate_routing_rules(&unknown_provider, &config, &providers)
+                .unwrap_err()
+                .contains("no configured profile")
+        );
+        let unknown_agent = vec![routing_rule("agent", Some("writer"), "openai/gpt-5.4")];
+        assert!(validate_routing_rules(&unknown_agent, &config, &providers).is_err());
+        let duplicate = vec![
+            routing_rule("image", None, "openai/gpt-5.4"),
+            routing_rule("image", None, "openai/gpt-5.4-mini"),
+        ];
+        assert!(validate_routing_rules(&duplicate, &config, &providers).is_err());
     }
 
     #[test]
-    fn test_validate_license_response_rejects_refunded_purchase() {
-        let response = serde_json::json!({
-            "success": true,
-            "purchase": {
-                "refunded": true,
-                "chargebacked": false
+    fn test_apply_and_read_routing_rules() {
+        let mut config = serde_json::json!({
+            "agents": {
+                "defaults": {
+                    "model": { "primary": "anthropic/claude-opus-4-6" },
+                    "imageModel": { "primary": "openai/gpt-5.4" },
+                    "heartbeat": { "every": "30m" },
+                    "models": { "anthropic/claude-opus-4-6": {} }
+                },
+                "list": [{ "id": "main" }, { "id": "coder", "model": "openai/gpt-5.4" }]
             }
         });
+        let mut subagents = routing_rule("subagents", None, "openai/gpt-5.4-mini");
+        subagents.fallbacks = vec!["anthropic/claude-haiku-4-5".to_string()];
+        let rules = vec![
+            subagents,
+            routing_rule("heartbeat", None, "anthropic/claude-haiku-4-5"),
+            routing_rule("agent", Some("main"), "anthropic/claude-opus-4-6"),
+        ];
+        apply_routing_rules(&mut config, &rules);
+
+        let defaults = &config["agents"]["defaults"];
+        assert_eq!(defaults["model"]["primary"], "anthropic/claude-opus-4-6");
+        assert!(defaults.get("imageModel").is_none());
+        assert_eq!(defaults["heartbeat"]["every"], "30m");
+        assert_eq!(defaults["heartbeat"]["model"], "anthropic/claude-haiku-4-5");
+        assert!(defaults["models"].get("openai/gpt-5.4-mini").is_some());
+        assert!(config["agents"]["list"][1].get("model").is_none());
+
+        let read = read_routing_rules(&config);
+        assert_eq!(read[0].task, "default");
+        assert_eq!(&read[1..], &rules[..]);
+    }
 
+    #[test]
+    fn test_parse_local_models_and_apply_local_provider() {
+        let tags =
+            serde_json::json!({ "models": [{ "name": "llama3.2:3b" }, { "name": "qwen3:8b" }] });
         assert_eq!(
-            validate_license_response(&response).unwrap_err(),
-            "License has been refunded."
+            parse_local_models("ollama", &tags),
+            vec!["llama3.2:3b", "qwen3:8b"]
+        );
+        let listing = serde_json::json!({ "data": [{ "id": "qwen2.5-7b-instruct" }] });
+        assert_eq!(
+            parse_local_models("lmstudio", &listing),
+            vec!["qwen2.5-7b-instruct"]
         );
+
+        let server = LocalModelServer {
+            kind: "ollama".to_string(),
+            base_url: local_server_root("http://127.0.0.1:11434/v1/").to_string(),
+            running: true,
+            models: vec!["llama3.2:3b".to_string()],
+        };
+        let mut config = serde_json::json!({
+            "models": { "mode": "merge", "providers": { "lmstudio": { "baseUrl": "x" } } },
+            "agents": { "defaults": {
+                "model": { "primary": "anthropic/claude-opus-4-6", "fallbacks": ["openai/gpt-5.4"] },
+                "models": { "anthropic/claude-opus-4-6": {} }
+            } }
+        });
+        apply_local_provider(&mut config, &server, Some("llama3.2:3b"));
+        let provider = &config["models"]["providers"]["ollama"];
+        assert_eq!(provider["baseUrl"], "http://127.0.0.1:11434/v1");
+        assert_eq!(provider["models"][0]["id"], "llama3.2:3b");
+        assert_eq!(config["models"]["providers"]["lmstudio"]["baseUrl"], "x");
+        let model = &config["agents"]["defaults"]["model"];
+        assert_eq!(model["primary"], "ollama/llama3.2:3b");
+        assert_eq!(model["fallbacks"][0], "openai/gpt-5.4");
+        assert!(config["agents"]["defaults"]["models"]
+            .get("ollama/llama3.2:3b")
+            .is_some());
     }
 
     #[test]
-    fn test_validate_license_response_rejects_chargebacked_purchase() {
-        let response = serde_json::json!({
-            "success": true,
-            "purchase": {
-                "refunded": false,
-                "chargebacked": true
+    fn test_custom_endpoint_models_section() {
+        let config: AgentConfig = serde_json::from_value(serde_json::json!({
+            "provider": "custom",
+            "api_key": "sk-proxy",
+            "model": "custom/llama-3.3-70b",
+            "user_name": "Test User",
+            "agent_name": "Test Agent",
+            "fallback_models": ["custom/qwen3-32b", "openai/gpt-5.4"],
+            "base_url": "https://llm.corp.example/v1/"
+        }))
+        .unwrap();
+        let models = provider_models_section(&config).unwrap().unwrap();
+        let provider = &models["providers"]["custom"];
+        assert_eq!(provider["baseUrl"], "https://llm.corp.example/v1");
+        assert_eq!(provider["apiKey"], "sk-proxy");
+        let ids: Vec<&str> = provider["models"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["llama-3.3-70b", "qwen3-32b"]);
+        assert_eq!(
+            provider_base_url(&config).map(String::as_str),
+            Some("https://llm.corp.example/v1/")
+        );
+        assert!(custom_endpoint_url("llm.corp.example").is_err());
+    }
+
+    #[test]
+    fn test_azure_models_section_and_profile() {
+        let config: AgentConfig = serde_json::from_value(serde_json::json!({
+            "provider": "azure",
+            "api_key": "azure-key",
+            "model": "azure/gpt-4o-prod",
+            "user_name": "Test User",
+            "agent_name": "Test Agent",
+            "azure": {
+                "endpoint": "https://contoso.openai.azure.com/openai/",
+                "deployment": "gpt-4o-prod",
+                "api_version": null
             }
-        });
+        }))
+        .unwrap();
+        let models = provider_models_section(&config).unwrap().unwrap();
+        let provider = &models["providers"]["azure"];
+        assert_eq!(
+            provider["baseUrl"],
+            "https://contoso.openai.azure.com/openai/v1"
+        );
+        assert_eq!(provider["headers"]["api-key"], "azure-key");
+        assert_eq!(provider["models"][0]["id"], "gpt-4o-prod");
+
+        let auths = get_provider_auth_map(&config);
+        let profile = auths["azure"].profile.clone().unwrap();
+        assert_eq!(profile["baseUrl"], "https://contoso.openai.azure.com");
+        assert_eq!(profile["apiVersion"], AZURE_DEFAULT_API_VERSION);
+        let azure = azure_config_from_profile(&profile).unwrap();
+        assert_eq!(azure.deployment, "gpt-4o-prod");
+        assert!(azure_endpoint("http://contoso.openai.azure.com").is_err());
+    }
 
+    #[test]
+    fn test_bedrock_models_section_uses_aws_profile() {
+        let config: AgentConfig = serde_json::from_value(serde_json::json!({
+            "provider": "amazon-bedrock",
+            "api_key": "",
+            "model": "amazon-bedrock/us.anthropic.claude-sonnet-4-5-20250929-v1:0",
+            "user_name": "Test User",
+            "agent_name": "Test Agent",
+            "bedrock": {
+                "region": "us-west-2",
+                "access_key_id": "AKIAEXAMPLE",
+                "secret_access_key": "secret"
+            }
+        }))
+        .unwrap();
+        let mut config_json = serde_json::json!({ "env": { "vars": { "OTHER": "1" } } });
+        apply_provider_models(&config, &mut config_json).unwrap();
+        let provider = &config_json["models"]["providers"]["amazon-bedrock"];
         assert_eq!(
-            validate_license_response(&response).unwrap_err(),
-            "License has been chargebacked."
+            provider["baseUrl"],
+            "https://bedrock-runtime.us-west-2.amazonaws.com"
+        );
+        assert_eq!(provider["auth"], "aws-sdk");
+        assert!(provider.get("apiKey").is_none());
+        assert_eq!(
+            provider["models"][0]["id"],
+            "us.anthropic.claude-sonnet-4-5-20250929-v1:0"
         );
+        let vars = &config_json["env"]["vars"];
+        assert_eq!(vars["AWS_PROFILE"], bedrock::CREDENTIALS_PROFILE);
+        assert_eq!(vars["AWS_REGION"], "us-west-2");
+        assert_eq!(vars["OTHER"], "1");
+        assert!(!get_provider_auth_map(&config).contains_key("amazon-bedrock"));
     }
 
     #[test]
-    fn test_validate_license_response_rejects_unsuccessful_response() {
-        let response = serde_json::json!({
-            "success": false
+    fn test_latency_probe_targets() {
+        let config = serde_json::json!({
+            "models": { "providers": { "ollama": { "baseUrl": "http://127.0.0.1:11434/v1/" } } },
+            "agents": { "defaults": { "model": {
+                "primary": "anthropic/claude-opus-4-6",
+                "fallbacks": ["anthropic/claude-sonnet-4-6", "ollama/llama3.2:3b", "unknown/x"]
+            } } }
         });
-
         assert_eq!(
-            validate_license_response(&response).unwrap_err(),
-            "Invalid license key."
+            latency_probe_targets(&config),
+            vec![
+                (
+                    "anthropic".to_string(),
+                    "https://api.anthropic.com/".to_string()
+                ),
+                (
+                    "ollama".to_string(),
+                    "http://127.0.0.1:11434/v1/models".to_string()
+                ),
+            ]
         );
     }
 
     #[test]
-    fn test_encrypt_saved_license_round_trip() {
-        let key = derive_license_encryption_key("machine-a");
-        let encrypted =
-            encrypt_saved_license_value("LICENSE-123", &key).expect("license should encrypt");
-        let decrypted =
-            decrypt_saved_license_value(&encrypted, &key).expect("license should decrypt");
-
-        assert_eq!(decrypted, "LICENSE-123");
+    fn test_is_cli_operand() {
+        assert!(is_cli_operand("weather"));
+        assert!(is_cli_operand("ABCD1234"));
+        assert!(!is_cli_operand("--registry=https://evil.example"));
+        assert!(!is_cli_operand("a b"));
+        assert!(!is_cli_operand(""));
     }
 
     #[test]
-    fn test_saved_license_decrypt_fails_with_different_machine_key() {
-        let key = derive_license_encryption_key("machine-a");
-        let wrong_key = derive_license_encryption_key("machine-b");
-        let encrypted =
-            encrypt_saved_license_value("LICENSE-123", &key).expect("license should encrypt");
-
-        assert_eq!(
-            decrypt_saved_license_value(&encrypted, &wrong_key).unwrap_err(),
-            "Saved license cannot be decrypted on this machine."
-        );
+    fn test_workspace_dir_rejects_invalid_agent_ids() {
+        assert!(workspace_dir(Some("../../etc")).is_err());
+        assert!(workspace_dir(Some("")).is_err());
     }
 
     #[test]
-    fn test_parse_windows_machine_guid_extracts_value() {
-        let output = "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft\\Cryptography\r\n    MachineGuid    REG_SZ    1234-5678\r\n";
-        assert_eq!(
-            parse_windows_machine_guid(output).as_deref(),
-            Some("1234-5678")
-        );
+    fn test_parse_workspace_log() {
+        let output = "a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2\u{1f}1760000000\u{1f}Add skill weather\n\
+                      0123456789abcdef0123456789abcdef01234567\u{1f}1759990000\u{1f}Update identity, user and soul files\n";
+        let commits = parse_workspace_log(output);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].timestamp, 1_760_000_000);
+        assert_eq!(commits[0].message, "Add skill weather");
+        assert!(is_commit_sha(&commits[1].sha));
+        assert!(is_commit_sha("a1b2c3d"));
+        assert!(!is_commit_sha("HEAD~1"));
+        assert!(!is_commit_sha("a1b2"));
     }
 
     #[test]
-    fn test_read_first_nonempty_file_prefers_first_nonempty_candidate() {
+    fn test_commit_workspace_changes_passes_message_as_argv() {
         let temp_dir =
-            std::env::temp_dir().join(format!("clawnetes-license-test-{}", uuid::Uuid::new_v4()));
+            std::env::temp_dir().join(format!("clawnetes-workspace-test-{}", uuid::Uuid::new_v4()));
         fs::create_dir_all(&temp_dir).expect("temp dir should be created");
-        let first = temp_dir.join("first");
-        let second = temp_dir.join("second");
-        fs::write(&first, "   ").expect("first file should be written");
-        fs::write(&second, "machine-id-123\n").expect("second file should be written");
+        let workspace = temp_dir.to_string_lossy().to_string();
+        workspace_git(&workspace, &["init", "-q"]).expect("git init should succeed");
+        workspace_git(&workspace, &["config", "user.name", "Test"]).unwrap();
+        workspace_git(&workspace, &["config", "user.email", "test@localhost"]).unwrap();
+        assert!(workspace_has_repo(&workspace));
+        fs::write(temp_dir.join("SOUL.md"), "soul").expect("file should be written");
+
+        let message = "it's $(touch pwned) `touch pwned`";
+        let sha = try_commit_workspace_changes(&workspace, message)
+            .expect("commit should succeed")
+            .expect("changes should be committed");
+
+        assert!(is_commit_sha(&sha));
+        let log = workspace_git(&workspace, &["log", "-n", "1", "--format=%s"]).unwrap();
+        assert_eq!(log.trim(), message);
+        assert!(!temp_dir.join("pwned").exists());
+        assert_eq!(try_commit_workspace_changes(&workspace, "no-op"), Ok(None));
+
+        let _ = fs::remove_dir_all(temp_dir);
+    }
 
-        let result = read_first_nonempty_file(&[first.clone(), second.clone()])
-            .expect("machine id lookup should succeed");
+    #[test]
+    fn test_cloud_backup_status_hides_secrets() {
+        let config: CloudBackupConfig = serde_json::from_value(serde_json::json!({
+            "target": {
+                "kind": "webdav",
+                "url": "https://dav.example.com/openclaw",
+                "username": "me",
+                "password": "dav-secret"
+            },
+            "passphrase": "backup-secret"
+        }))
+        .unwrap();
+        assert_eq!(config.interval_hours, 24);
+        assert_eq!(config.keep_last, 7);
+        let status = serde_json::to_string(&CloudBackupStatus::from(&config)).unwrap();
+        assert!(status.contains("\"kind\":\"webdav\""));
+        assert!(!status.contains("secret"));
+    }
 
-        assert_eq!(result.as_deref(), Some("machine-id-123"));
+    #[test]
+    fn test_pause_and_resume_agent_config() {
+        let original = serde_json::json!({
+            "channels": {
+                "telegram": { "enabled": true, "botToken": "t" },
+                "discord": { "enabled": false }
+            },
+            "agents": { "defaults": { "heartbeat": { "every": "30m" } } },
+            "gateway": { "port": 18789 }
+        });
+        let mut config = original.clone();
+        let state = pause_agent_in_config(&mut config, 100);
+        assert_eq!(state.channels, vec!["telegram".to_string()]);
+        assert_eq!(config["channels"]["telegram"]["enabled"], false);
+        assert_eq!(config["agents"]["defaults"]["heartbeat"]["enabled"], false);
+        assert_eq!(config["cron"]["enabled"], false);
+
+        resume_agent_in_config(&mut config, &state);
+        assert_eq!(config["channels"], original["channels"]);
+        assert_eq!(config["agents"], original["agents"]);
+        assert!(config.get("cron").is_none());
+    }
 
-        let _ = fs::remove_file(first);
-        let _ = fs::remove_file(second);
-        let _ = fs::remove_dir(temp_dir);
+    #[test]
+    fn test_safe_mode_config() {
+        let config = serde_json::json!({
+            "channels": { "telegram": { "enabled": true } },
+            "plugins": { "entries": { "voice-call": { "enabled": true } } },
+            "tools": { "profile": "full", "exec": { "ask": "off" } }
+        });
+        let safe = safe_mode_config(&config);
+        assert_eq!(safe["channels"]["telegram"]["enabled"], false);
+        assert_eq!(safe["plugins"]["enabled"], false);
+        assert_eq!(safe["plugins"]["entries"]["voice-call"]["enabled"], false);
+        assert_eq!(safe["tools"]["profile"], "minimal");
+        assert_eq!(safe["tools"]["exec"]["security"], "deny");
+        assert_eq!(safe["tools"]["exec"]["ask"], "off");
+        assert_eq!(safe["cron"]["enabled"], false);
     }
 
     #[test]
-    fn test_decrypt_saved_license_rejects_corrupt_payload() {
-        let key = derive_license_encryption_key("machine-a");
-        assert!(decrypt_saved_license_value(
-            "{\"version\":1,\"nonce\":\"bad\",\"ciphertext\":\"bad\"}",
-            &key
+    fn test_backup_schedule_defaults() {
+        let schedule: BackupSchedule = serde_json::from_str(
+            r#"{"enabled":true,"interval_hours":12,"keep_daily":3,"keep_weekly":2}"#,
         )
-        .is_err());
+        .unwrap();
+        assert_eq!(schedule.last_backup_at, None);
+        assert_eq!(BackupSchedule::default().interval_hours, 24);
+        assert!(!BackupSchedule::default().enabled);
+    }
+
+    #[test]
+    fn test_paired_chats_reads_channel_allowlists() {
+        let dir = std::env::temp_dir().join(format!("clawsetup-paired-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("telegram-allowFrom.json"),
+            r#"{"version":1,"allowFrom":["123456789",42]}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("whatsapp-allowFrom.json"),
+            r#"{"allowFrom":["+15551234567"]}"#,
+        )
+        .unwrap();
+        assert_eq!(paired_chats(&dir, "telegram"), ["123456789", "42"]);
+        assert!(paired_chats(&dir, "discord").is_empty());
+        let _ = fs::remove_dir_all(&dir);
     }
 }
@@ -0,0 +1,259 @@
+//! MCP tool servers for the agents, kept in the `mcp.servers` section of `openclaw.json`.
+//!
+//! A server is either a local command speaking MCP over stdio, configured with `env`, or a
+//! remote streamable-HTTP endpoint, configured with `headers`. Values that look like
+//! credentials never reach the config file: the entry refers to them as
+//! `${MCP_<SERVER>_<NAME>}`, a variable stored with the other managed gateway variables in the
+//! owner-only `clawnetes-gateway-env.json` and exported to the gateway service.
+//! Servers are tested with the JSON-RPC `initialize` handshake.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub const SECTION: &str = "mcp.servers";
+pub const PROTOCOL_VERSION: &str = "2025-06-18";
+const SECRET_MARKERS: &[&str] = &["KEY", "TOKEN", "SECRET", "PASSWORD", "AUTH", "CREDENTIAL"];
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct McpServer {
+    pub name: String,
+    /// Executable for stdio servers, e.g. `npx`.
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    /// Endpoint for streamable-HTTP servers.
+    pub url: Option<String>,
+    /// Plain environment variables (stdio) or headers (HTTP); secrets are listed in
+    /// `secret_env` instead.
+    pub env: BTreeMap<String, String>,
+    /// Names of entries whose values are in the gateway environment.
+    pub secret_env: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Handshake {
+    pub server_name: Option<String>,
+    pub server_version: Option<String>,
+    pub protocol_version: Option<String>,
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub fn is_secret_name(var: &str) -> bool {
+    let upper = var.to_ascii_uppercase();
+    SECRET_MARKERS.iter().any(|marker| upper.contains(marker))
+}
+
+/// Gateway environment variable holding secret `var` of `server`.
+pub fn secret_env_name(server: &str, var: &str) -> String {
+    format!("MCP_{}_{}", server, var)
+        .to_ascii_uppercase()
+        .replace('-', "_")
+}
+
+/// The `mcp.servers.<name>` entry for `server` and the secrets it references, as
+/// `(name, value)`. `env` holds every variable or header, secret or not.
+pub fn config_entry(
+    server: &McpServer,
+    env: &BTreeMap<String, String>,
+) -> Result<(serde_json::Value, Vec<(String, String)>), String> {
+    if !is_valid_name(&server.name) {
+        return Err(format!("Invalid MCP server name: {}", server.name));
+    }
+    let mut entry = serde_json::Map::new();
+    let env_key = match (&server.command, &server.url) {
+        (Some(command), None) if !command.trim().is_empty() => {
+            entry.insert("command".to_string(), serde_json::json!(command.trim()));
+            entry.insert("args".to_string(), serde_json::json!(server.args));
+            "env"
+        }
+        (None, Some(url)) if url.starts_with("https://") || url.starts_with("http://") => {
+            entry.insert("url".to_string(), serde_json::json!(url.trim()));
+            "headers"
+        }
+        (None, Some(url)) => return Err(format!("MCP server URL '{}' must be http(s)", url)),
+        _ => return Err("Give an MCP server either a command or a URL.".to_string()),
+    };
+    let mut vars = serde_json::Map::new();
+    let mut secrets = Vec::new();
+    for (name, value) in env {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!("Invalid {} name: {}", env_key, name));
+        }
+        if value.contains(['\n', '\r', '\0']) {
+            return Err(format!("{} must be a single line.", name));
+        }
+        if is_secret_name(name) {
+            let env_name = secret_env_name(&server.name, name);
+            vars.insert(
+                name.clone(),
+                serde_json::json!(format!("${{{}}}", env_name)),
+            );
+            secrets.push((name.clone(), value.clone()));
+        } else {
+            vars.insert(name.clone(), serde_json::json!(value));
+        }
+    }
+    if !vars.is_empty() {
+        entry.insert(env_key.to_string(), serde_json::Value::Object(vars));
+    }
+    Ok((serde_json::Value::Object(entry), secrets))
+}
+
+/// Servers configured in `config`.
+pub fn list(config: &serde_json::Value) -> Vec<McpServer> {
+    let Some(servers) = config.pointer("/mcp/servers").and_then(|s| s.as_object()) else {
+        return Vec::new();
+    };
+    servers
+        .iter()
+        .map(|(name, entry)| {
+            let text = |key: &str| entry.get(key).and_then(|v| v.as_str()).map(str::to_string);
+            let mut server = McpServer {
+                name: name.clone(),
+                command: text("command"),
+                url: text("url"),
+                args: entry
+                    .get("args")
+                    .and_then(|a| a.as_array())
+                    .map(|args| {
+                        args.iter()
+                            .filter_map(|a| a.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                ..Default::default()
+            };
+            let env = entry.get("env").or_else(|| entry.get("headers"));
+            if let Some(env) = env.and_then(|e| e.as_object()) {
+                for (var, value) in env {
+                    let value = value.as_str().unwrap_or_default();
+                    if value == format!("${{{}}}", secret_env_name(name, var)) {
+                        server.secret_env.push(var.clone());
+                    } else {
+                        server.env.insert(var.clone(), value.to_string());
+                    }
+                }
+            }
+            server
+        })
+        .collect()
+}
+
+pub fn initialize_request(id: u64) -> serde_json::Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "clawnetes", "version": env!("CARGO_PKG_VERSION") }
+        }
+    })
+}
+
+/// The JSON-RPC message in `body`, which streamable-HTTP servers may wrap in SSE `data:` lines.
+pub fn parse_message(body: &str) -> Option<serde_json::Value> {
+    let body = body.trim();
+    if let Ok(value) = serde_json::from_str(body) {
+        return Some(value);
+    }
+    body.lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .find_map(|data| serde_json::from_str(data.trim()).ok())
+}
+
+/// Server details from an `initialize` response, or its error message.
+pub fn parse_handshake(response: &serde_json::Value) -> Result<Handshake, String> {
+    if let Some(error) = response.get("error") {
+        return Err(error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| error.to_string()));
+    }
+    let result = response
+        .get("result")
+        .ok_or("The server did not answer the MCP handshake.")?;
+    let text =
+        |value: Option<&serde_json::Value>| value.and_then(|v| v.as_str()).map(str::to_string);
+    Ok(Handshake {
+        server_name: text(result.pointer("/serverInfo/name")),
+        server_version: text(result.pointer("/serverInfo/version")),
+        protocol_version: text(result.get("protocolVersion")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_entry_keeps_secrets_out_of_the_config() {
+        let server = McpServer {
+            name: "github".to_string(),
+            command: Some("npx".to_string()),
+            args: vec![
+                "-y".to_string(),
+                "@modelcontextprotocol/server-github".to_string(),
+            ],
+            ..Default::default()
+        };
+        let env = BTreeMap::from([
+            ("GITHUB_TOKEN".to_string(), "ghp_secret".to_string()),
+            ("LOG_LEVEL".to_string(), "info".to_string()),
+        ]);
+        let (entry, secrets) = config_entry(&server, &env).unwrap();
+        assert_eq!(entry["env"]["GITHUB_TOKEN"], "${MCP_GITHUB_GITHUB_TOKEN}");
+        assert_eq!(entry["env"]["LOG_LEVEL"], "info");
+        assert_eq!(
+            secrets,
+            [("GITHUB_TOKEN".to_string(), "ghp_secret".to_string())]
+        );
+
+        let config = serde_json::json!({ "mcp": { "servers": { "github": entry } } });
+        let listed = list(&config);
+        assert_eq!(listed[0].args, server.args);
+        assert_eq!(listed[0].secret_env, ["GITHUB_TOKEN"]);
+        assert_eq!(
+            listed[0].env.get("LOG_LEVEL").map(String::as_str),
+            Some("info")
+        );
+
+        let mut remote = McpServer {
+            name: "docs".to_string(),
+            url: Some("https://docs.example/mcp".to_string()),
+            ..Default::default()
+        };
+        let headers = BTreeMap::from([("Authorization".to_string(), "Bearer x".to_string())]);
+        let (entry, _) = config_entry(&remote, &headers).unwrap();
+        assert_eq!(
+            entry["headers"]["Authorization"],
+            "${MCP_DOCS_AUTHORIZATION}"
+        );
+        remote.url = Some("ftp://docs".to_string());
+        assert!(config_entry(&remote, &BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_parse_handshake_from_json_or_sse() {
+        let sse = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"protocolVersion\":\"2025-06-18\",\"serverInfo\":{\"name\":\"docs\",\"version\":\"1.2.0\"}}}\n\n";
+        let handshake = parse_handshake(&parse_message(sse).unwrap()).unwrap();
+        assert_eq!(handshake.server_name.as_deref(), Some("docs"));
+        assert_eq!(handshake.protocol_version.as_deref(), Some("2025-06-18"));
+        let error = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32600,"message":"bad request"}}"#;
+        assert_eq!(
+            parse_handshake(&parse_message(error).unwrap()),
+            Err("bad request".to_string())
+        );
+    }
+}
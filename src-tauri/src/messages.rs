@@ -0,0 +1,166 @@
+//! Message catalog for user-facing backend text.
+//!
+//! Messages are identified by stable codes (`gateway.not_running`) whose templates live in
+//! `locales/<lang>.json`, compiled into the binary. Backend errors stay plain English strings
+//! rendered from the `en` catalog, so every existing caller keeps working; `identify` maps such
+//! a string back to its code and parameters, which lets the frontend show it in any locale.
+//! Parameters are written `{name}` in templates.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.json")),
+    ("de", include_str!("../locales/de.json")),
+    ("es", include_str!("../locales/es.json")),
+];
+
+lazy_static! {
+    static ref ENGLISH: BTreeMap<String, String> = parse(CATALOGS[0].1);
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Message {
+    pub code: String,
+    pub params: BTreeMap<String, String>,
+}
+
+fn parse(json: &str) -> BTreeMap<String, String> {
+    serde_json::from_str(json).unwrap_or_default()
+}
+
+pub fn locales() -> Vec<&'static str> {
+    CATALOGS.iter().map(|(locale, _)| *locale).collect()
+}
+
+/// The bundled locale for `requested` (`de-AT` → `de`), or the default.
+pub fn resolve_locale(requested: &str) -> &'static str {
+    let language = requested
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    CATALOGS
+        .iter()
+        .map(|(locale, _)| *locale)
+        .find(|locale| *locale == language)
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// Every template in `locale`, with English filling any the translation lacks.
+pub fn catalog(locale: &str) -> BTreeMap<String, String> {
+    let locale = resolve_locale(locale);
+    let mut messages = ENGLISH.clone();
+    if let Some((_, json)) = CATALOGS.iter().find(|(l, _)| *l == locale) {
+        messages.extend(parse(json));
+    }
+    messages
+}
+
+pub fn render(template: &str, params: &[(&str, &str)]) -> String {
+    params
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// English text for `code`; the code itself if the catalog has no such entry.
+pub fn text(code: &str, params: &[(&str, &str)]) -> String {
+    ENGLISH
+        .get(code)
+        .map(|template| render(template, params))
+        .unwrap_or_else(|| code.to_string())
+}
+
+/// Literal runs and `{param}` names of `template`, alternating and starting with a literal.
+fn split_template(template: &str) -> (Vec<&str>, Vec<&str>) {
+    let mut literals = Vec::new();
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some((literal, after)) = rest.split_once('{') {
+        let Some((name, after)) = after.split_once('}') else {
+            break;
+        };
+        literals.push(literal);
+        names.push(name);
+        rest = after;
+    }
+    literals.push(rest);
+    (literals, names)
+}
+
+fn match_template(template: &str, text: &str) -> Option<BTreeMap<String, String>> {
+    let (literals, names) = split_template(template);
+    let mut rest = text.strip_prefix(literals[0])?;
+    let mut params = BTreeMap::new();
+    for (name, literal) in names.iter().zip(&literals[1..]) {
+        let end = if literal.is_empty() {
+            rest.len()
+        } else {
+            rest.find(literal)?
+        };
+        params.insert(name.to_string(), rest[..end].to_string());
+        rest = &rest[end + literal.len()..];
+    }
+    rest.is_empty().then_some(params)
+}
+
+/// The code and parameters of English backend text produced by `text`.
+pub fn identify(text: &str) -> Option<Message> {
+    let text = text.trim();
+    ENGLISH.iter().find_map(|(code, template)| {
+        Some(Message {
+            code: code.clone(),
+            params: match_template(template, text)?,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogs_match_english() {
+        for (locale, json) in CATALOGS {
+            let translated = parse(json);
+            assert_eq!(translated.len(), ENGLISH.len(), "{} is incomplete", locale);
+            for (code, template) in &translated {
+                let english = ENGLISH.get(code).expect("code missing from en");
+                assert_eq!(
+                    split_template(template).1,
+                    split_template(english).1,
+                    "{}: {}",
+                    locale,
+                    code
+                );
+            }
+        }
+        assert_eq!(resolve_locale("de-AT"), "de");
+        assert_eq!(resolve_locale("pt_BR"), "en");
+    }
+
+    #[test]
+    fn test_text_round_trips_through_identify() {
+        let error = text(
+            "process.timeout",
+            &[("command", "openclaw doctor"), ("seconds", "180")],
+        );
+        assert_eq!(
+            error,
+            "`openclaw doctor` did not finish within 180 seconds and was stopped. Try again."
+        );
+        let message = identify(&error).unwrap();
+        assert_eq!(message.code, "process.timeout");
+        assert_eq!(message.params["command"], "openclaw doctor");
+        assert_eq!(message.params["seconds"], "180");
+        assert_eq!(
+            identify("Unknown setup step: pairing").unwrap().params["step"],
+            "pairing"
+        );
+        assert_eq!(identify("Something else went wrong"), None);
+    }
+}
@@ -0,0 +1,320 @@
+//! Rolling gateway metrics for the dashboard charts.
+//!
+//! A background thread samples the gateway process's CPU and resident memory and the
+//! transcript messages written since the last sample every `SAMPLE_SECS`, keeping
+//! `WINDOW_SECS` of history in memory. Figures come from `ps`, which behaves the same on macOS,
+//! Linux and inside WSL, so no system-info crate is needed. Its `%cpu` is a lifetime average on
+//! Linux, so CPU is derived from the change in accumulated CPU time between samples instead.
+//! Transcripts are read from where the previous sample stopped. `query` buckets the window for
+//! charting.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const SAMPLE_SECS: u64 = 30;
+const WINDOW_SECS: u64 = 24 * 60 * 60;
+
+lazy_static! {
+    static ref SAMPLES: Mutex<VecDeque<Sample>> = Mutex::new(VecDeque::new());
+    static ref SAMPLER_RUNNING: AtomicBool = AtomicBool::new(false);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Sample {
+    timestamp: u64,
+    cpu_percent: Option<f64>,
+    rss_kb: Option<u64>,
+    /// Messages added since the previous sample.
+    messages: u64,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct MetricPoint {
+    /// Start of the bucket, unix seconds.
+    pub timestamp: u64,
+    /// Average over the bucket; `None` when the gateway was not running.
+    pub cpu_percent: Option<f64>,
+    /// Peak over the bucket.
+    pub rss_mb: Option<f64>,
+    pub messages: u64,
+}
+
+/// Seconds in a `ps` CPU time: `[DD-]HH:MM:SS` on Linux, `M:SS.cc` on macOS.
+fn parse_cpu_time(field: &str) -> Option<f64> {
+    let (days, clock) = match field.split_once('-') {
+        Some((days, clock)) => (days.parse::<f64>().ok()?, clock),
+        None => (0.0, field),
+    };
+    let secs = clock.split(':').try_fold(0.0, |total, part| {
+        Some(total * 60.0 + part.replace(',', ".").parse::<f64>().ok()?)
+    })?;
+    Some(days * 86400.0 + secs)
+}
+
+/// CPU seconds and RSS as printed by `ps -o time=,rss=`.
+fn parse_ps_line(line: &str) -> Option<(f64, u64)> {
+    let mut fields = line.split_whitespace();
+    let cpu = parse_cpu_time(fields.next()?)?;
+    let rss = fields.next()?.parse().ok()?;
+    Some((cpu, rss))
+}
+
+/// Average CPU percent between two readings of accumulated CPU seconds.
+fn cpu_percent(previous: f64, current: f64, elapsed_secs: f64) -> Option<f64> {
+    (elapsed_secs > 0.0 && current >= previous).then(|| (current - previous) / elapsed_secs * 100.0)
+}
+
+/// Messages in the complete lines of `text`, and the bytes those lines take up; a trailing
+/// partial line is left for the next read.
+fn count_complete_messages(text: &str) -> (u64, u64) {
+    let complete = text.rfind('\n').map_or(0, |i| i + 1);
+    let messages = text[..complete]
+        .lines()
+        .filter(|line| line.contains("\"type\":\"message\""))
+        .count();
+    (messages as u64, complete as u64)
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn process_usage(pid: i32) -> Option<(f64, u64)> {
+    crate::process::run("ps", &["-o", "time=,rss=", "-p", &pid.to_string()])
+        .ok()
+        .and_then(|out| parse_ps_line(out.trim()))
+}
+
+fn transcript_files() -> Vec<String> {
+    let Ok(root) = crate::paths::local_root() else {
+        return Vec::new();
+    };
+    let agents = format!("{}/agents", root);
+    crate::list_local_dir(&agents)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|agent| {
+            let sessions = format!("{}/{}/sessions", agents, agent);
+            crate::list_local_dir(&sessions)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|name| name.ends_with(".jsonl"))
+                .map(move |name| format!("{}/{}", sessions, name))
+        })
+        .collect()
+}
+
+/// Contents of `path` from byte `offset`, or from the start when the file is now shorter
+/// (rewritten); returns where the text begins.
+#[cfg(not(target_os = "windows"))]
+fn read_from(path: &str, offset: u64) -> Option<(u64, String)> {
+    use std::io::{Read, Seek, SeekFrom};
+    let mut file = std::fs::File::open(path).ok()?;
+    let start = if file.metadata().ok()?.len() < offset {
+        0
+    } else {
+        offset
+    };
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    Some((start, String::from_utf8_lossy(&bytes).to_string()))
+}
+
+#[cfg(target_os = "windows")]
+fn read_from(path: &str, offset: u64) -> Option<(u64, String)> {
+    let output = crate::shell_command(&format!(
+        "wc -c < {0} && tail -c +{1} {0}",
+        crate::shell_single_quote(path),
+        offset + 1
+    ))
+    .ok()?;
+    let (len, text) = output.split_once('\n')?;
+    if len.trim().parse::<u64>().ok()? < offset {
+        return read_from(path, 0);
+    }
+    Some((offset, text.to_string()))
+}
+
+/// Read position in each transcript, so a sample only reads what was appended since.
+#[derive(Default)]
+struct TranscriptCounter {
+    offsets: HashMap<String, u64>,
+    primed: bool,
+}
+
+impl TranscriptCounter {
+    /// Messages appended since the previous call; the first call only records where each
+    /// transcript ends.
+    fn new_messages(&mut self) -> u64 {
+        let mut total = 0;
+        let mut offsets = HashMap::new();
+        for path in transcript_files() {
+            let offset = self.offsets.get(&path).copied().unwrap_or(0);
+            let Some((start, text)) = read_from(&path, offset) else {
+                offsets.insert(path, offset);
+                continue;
+            };
+            let (messages, consumed) = count_complete_messages(&text);
+            if self.primed {
+                total += messages;
+            }
+            offsets.insert(path, start + consumed);
+        }
+        self.offsets = offsets;
+        self.primed = true;
+        total
+    }
+}
+
+fn record(sample: Sample) {
+    if let Ok(mut samples) = SAMPLES.lock() {
+        let cutoff = sample.timestamp.saturating_sub(WINDOW_SECS);
+        while samples.front().is_some_and(|s| s.timestamp < cutoff) {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+}
+
+fn run_sampler() {
+    let mut transcripts = TranscriptCounter::default();
+    // Gateway pid, its CPU seconds and when they were read.
+    let mut last_cpu: Option<(i32, f64, std::time::Instant)> = None;
+    loop {
+        let pid = crate::gateway_listener_pid();
+        let usage = pid.and_then(|pid| Some((pid, process_usage(pid)?)));
+        let now = std::time::Instant::now();
+        let cpu = match (last_cpu, usage) {
+            (Some((last_pid, last_secs, at)), Some((pid, (secs, _)))) if last_pid == pid => {
+                cpu_percent(last_secs, secs, now.duration_since(at).as_secs_f64())
+            }
+            _ => None,
+        };
+        last_cpu = usage.map(|(pid, (secs, _))| (pid, secs, now));
+        record(Sample {
+            timestamp: now_secs(),
+            cpu_percent: cpu,
+            rss_kb: usage.map(|(_, (_, rss))| rss),
+            messages: transcripts.new_messages(),
+        });
+        if !crate::lifecycle::sleep(Duration::from_secs(SAMPLE_SECS)) {
+            break;
+        }
+    }
+}
+
+pub fn start_sampler() {
+    if !SAMPLER_RUNNING.swap(true, Ordering::Relaxed) {
+        crate::lifecycle::spawn("metrics-sampler", run_sampler);
+    }
+}
+
+/// Buckets samples from the last `range_secs` into `resolution_secs` points, oldest first.
+/// Empty buckets are omitted.
+fn bucket_samples(
+    samples: &[Sample],
+    now: u64,
+    range_secs: u64,
+    resolution_secs: u64,
+) -> Vec<MetricPoint> {
+    let resolution = resolution_secs.max(SAMPLE_SECS);
+    let start = now.saturating_sub(range_secs);
+    let mut points: Vec<MetricPoint> = Vec::new();
+    let mut cpu_sum = 0.0;
+    let mut cpu_count = 0;
+    for sample in samples.iter().filter(|s| s.timestamp >= start) {
+        let bucket = start + (sample.timestamp - start) / resolution * resolution;
+        if points.last().map(|p| p.timestamp) != Some(bucket) {
+            cpu_sum = 0.0;
+            cpu_count = 0;
+            points.push(MetricPoint {
+                timestamp: bucket,
+                cpu_percent: None,
+                rss_mb: None,
+                messages: 0,
+            });
+        }
+        let Some(point) = points.last_mut() else {
+            continue;
+        };
+        if let Some(cpu) = sample.cpu_percent {
+            cpu_sum += cpu;
+            cpu_count += 1;
+            point.cpu_percent = Some(cpu_sum / cpu_count as f64);
+        }
+        if let Some(rss) = sample.rss_kb {
+            let mb = rss as f64 / 1024.0;
+            point.rss_mb = Some(point.rss_mb.map_or(mb, |m: f64| m.max(mb)));
+        }
+        point.messages += sample.messages;
+    }
+    points
+}
+
+/// CPU percent and resident memory in KiB from the most recent sample.
+pub fn latest_usage() -> (Option<f64>, Option<u64>) {
+    SAMPLES
+        .lock()
+        .ok()
+        .and_then(|s| s.back().map(|sample| (sample.cpu_percent, sample.rss_kb)))
+        .unwrap_or((None, None))
+}
+
+pub fn query(range_secs: u64, resolution_secs: u64) -> Vec<MetricPoint> {
+    let samples: Vec<Sample> = SAMPLES
+        .lock()
+        .map(|s| s.iter().cloned().collect())
+        .unwrap_or_default();
+    bucket_samples(&samples, now_secs(), range_secs, resolution_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ps_line() {
+        assert_eq!(parse_ps_line(" 00:01:30 204800"), Some((90.0, 204800)));
+        assert_eq!(parse_ps_line("1-00:00:01 1024"), Some((86401.0, 1024)));
+        assert_eq!(parse_ps_line("0:01.50 1024"), Some((1.5, 1024)));
+        assert_eq!(parse_ps_line(""), None);
+        assert_eq!(cpu_percent(10.0, 13.0, 30.0), Some(10.0));
+        assert_eq!(cpu_percent(10.0, 2.0, 30.0), None);
+    }
+
+    #[test]
+    fn test_count_complete_messages() {
+        let text = "{\"type\":\"message\"}\n{\"type\":\"tool\"}\n{\"type\":\"message\"";
+        assert_eq!(count_complete_messages(text), (1, 35));
+        assert_eq!(count_complete_messages(""), (0, 0));
+    }
+
+    #[test]
+    fn test_bucket_samples() {
+        let sample = |timestamp, cpu, rss, messages| Sample {
+            timestamp,
+            cpu_percent: cpu,
+            rss_kb: rss,
+            messages,
+        };
+        let samples = vec![
+            sample(100, Some(50.0), Some(1024), 1),
+            sample(970, Some(10.0), Some(2048), 2),
+            sample(1000, Some(30.0), Some(1024), 3),
+            sample(1090, None, None, 0),
+        ];
+        let points = bucket_samples(&samples, 1200, 600, 60);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].timestamp, 960);
+        assert_eq!(points[0].cpu_percent, Some(20.0));
+        assert_eq!(points[0].rss_mb, Some(2.0));
+        assert_eq!(points[0].messages, 5);
+        assert_eq!(points[1].cpu_percent, None);
+    }
+}
@@ -0,0 +1,249 @@
+//! Ordered `openclaw.json` schema migrations.
+//!
+//! Each step names the OpenClaw version whose schema it targets. A step runs when the config
+//! was last written by an older version (`meta.lastTouchedVersion`, or any version when that is
+//! missing) and the installed OpenClaw is at least the step's version, so a config is never
+//! moved to a layout the installed gateway cannot read. Steps are idempotent and report each
+//! change they make; a step with nothing to change is left out of the report.
+
+use serde_json::{Map, Value};
+
+pub struct Migration {
+    pub id: &'static str,
+    pub version: &'static str,
+    pub description: &'static str,
+    apply: fn(&mut Value) -> Vec<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct AppliedMigration {
+    pub id: String,
+    pub version: String,
+    pub description: String,
+    pub changes: Vec<String>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        id: "agent-to-agents-defaults",
+        version: "2026.1.0",
+        description: "Move the single-agent `agent` section to `agents.defaults`",
+        apply: agent_to_agents_defaults,
+    },
+    Migration {
+        id: "gateway-auth-token",
+        version: "2026.1.0",
+        description: "Move `gateway.token` to `gateway.auth.token`",
+        apply: gateway_auth_token,
+    },
+    Migration {
+        id: "telegram-default-account",
+        version: "2026.2.0",
+        description: "Move Telegram bot settings into `channels.telegram.accounts.default`",
+        apply: telegram_default_account,
+    },
+];
+
+/// Numeric parts of the first version-looking word, e.g. `openclaw v2026.2.8-beta` → 2026.2.8.
+pub fn parse_version(text: &str) -> Option<Vec<u32>> {
+    text.split_whitespace().find_map(|word| {
+        let word = word.trim_start_matches('v');
+        let core = word.split(['-', '+']).next()?;
+        let parts: Vec<u32> = core
+            .split('.')
+            .map(|p| p.parse().ok())
+            .collect::<Option<_>>()?;
+        (parts.len() >= 2).then_some(parts)
+    })
+}
+
+pub fn config_version(config: &Value) -> Option<String> {
+    config
+        .pointer("/meta/lastTouchedVersion")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+}
+
+/// Steps that apply to `config` when upgrading to `installed` (no upper bound if unknown).
+pub fn pending(config: &Value, installed: Option<&str>) -> Vec<&'static Migration> {
+    let from = config_version(config).as_deref().and_then(parse_version);
+    let to = installed.and_then(parse_version);
+    MIGRATIONS
+        .iter()
+        .filter(|m| {
+            let version = parse_version(m.version);
+            from.as_ref()
+                .is_none_or(|from| version.as_ref() > Some(from))
+                && to.as_ref().is_none_or(|to| version.as_ref() <= Some(to))
+        })
+        .collect()
+}
+
+/// Applies the pending steps to `config` in order and returns those that changed something.
+/// Records `installed` as `meta.lastTouchedVersion` when anything changed.
+pub fn migrate(config: &mut Value, installed: Option<&str>) -> Vec<AppliedMigration> {
+    let applied: Vec<AppliedMigration> = pending(config, installed)
+        .into_iter()
+        .filter_map(|step| {
+            let changes = (step.apply)(config);
+            (!changes.is_empty()).then(|| AppliedMigration {
+                id: step.id.to_string(),
+                version: step.version.to_string(),
+                description: step.description.to_string(),
+                changes,
+            })
+        })
+        .collect();
+    if applied.is_empty() {
+        return applied;
+    }
+    if let (Some(version), Some(root)) = (installed, config.as_object_mut()) {
+        let meta = root
+            .entry("meta")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(meta) = meta.as_object_mut() {
+            meta.insert("lastTouchedVersion".to_string(), Value::from(version));
+        }
+    }
+    applied
+}
+
+fn object<'a>(parent: &'a mut Map<String, Value>, key: &str) -> &'a mut Map<String, Value> {
+    let child = parent
+        .entry(key.to_string())
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !child.is_object() {
+        *child = Value::Object(Map::new());
+    }
+    child.as_object_mut().unwrap()
+}
+
+/// Moves every key of `from` into `to` that `to` does not already have.
+fn merge_missing(from: Map<String, Value>, to: &mut Map<String, Value>, path: &str) -> Vec<String> {
+    let mut changes = Vec::new();
+    for (key, value) in from {
+        if to.contains_key(&key) {
+            changes.push(format!(
+                "Dropped {}.{} (already set in the new location)",
+                path, key
+            ));
+        } else {
+            changes.push(format!("Moved {}.{}", path, key));
+            to.insert(key, value);
+        }
+    }
+    changes
+}
+
+fn agent_to_agents_defaults(config: &mut Value) -> Vec<String> {
+    let Some(root) = config.as_object_mut() else {
+        return Vec::new();
+    };
+    let Some(Value::Object(agent)) = root.remove("agent") else {
+        return Vec::new();
+    };
+    let defaults = object(object(root, "agents"), "defaults");
+    merge_missing(agent, defaults, "agent")
+}
+
+fn gateway_auth_token(config: &mut Value) -> Vec<String> {
+    let Some(gateway) = config.get_mut("gateway").and_then(|g| g.as_object_mut()) else {
+        return Vec::new();
+    };
+    let Some(token) = gateway.remove("token") else {
+        return Vec::new();
+    };
+    let auth = object(gateway, "auth");
+    if auth.contains_key("token") {
+        return vec!["Dropped gateway.token (gateway.auth.token is already set)".to_string()];
+    }
+    auth.insert("token".to_string(), token);
+    vec!["Moved gateway.token to gateway.auth.token".to_string()]
+}
+
+/// Keys that describe the bot account rather than the channel as a whole.
+const TELEGRAM_ACCOUNT_KEYS: &[&str] = &[
+    "botToken",
+    "tokenFile",
+    "name",
+    "dmPolicy",
+    "allowFrom",
+    "groupPolicy",
+    "groupAllowFrom",
+    "groups",
+];
+
+fn telegram_default_account(config: &mut Value) -> Vec<String> {
+    let Some(telegram) = config
+        .pointer_mut("/channels/telegram")
+        .and_then(|t| t.as_object_mut())
+    else {
+        return Vec::new();
+    };
+    if !telegram.contains_key("botToken") && !telegram.contains_key("tokenFile") {
+        return Vec::new();
+    }
+    let moved: Map<String, Value> = TELEGRAM_ACCOUNT_KEYS
+        .iter()
+        .filter_map(|key| Some((key.to_string(), telegram.remove(*key)?)))
+        .collect();
+    let default = object(object(telegram, "accounts"), "default");
+    merge_missing(moved, default, "channels.telegram")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_legacy_config() {
+        let mut config = serde_json::json!({
+            "meta": { "lastTouchedVersion": "2025.12.1" },
+            "agent": { "model": "anthropic/claude-sonnet-4-5", "workspace": "~/w" },
+            "agents": { "defaults": { "workspace": "~/.openclaw/workspace" } },
+            "gateway": { "port": 18789, "token": "abc" },
+            "channels": { "telegram": { "enabled": true, "botToken": "123:x", "dmPolicy": "pairing" } }
+        });
+        assert_eq!(pending(&config, Some("openclaw 2026.1.5")).len(), 2);
+
+        let applied = migrate(&mut config, Some("2026.2.8"));
+        let ids: Vec<&str> = applied.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "agent-to-agents-defaults",
+                "gateway-auth-token",
+                "telegram-default-account"
+            ]
+        );
+        assert!(config.get("agent").is_none());
+        assert_eq!(
+            config["agents"]["defaults"]["model"],
+            "anthropic/claude-sonnet-4-5"
+        );
+        assert_eq!(
+            config["agents"]["defaults"]["workspace"],
+            "~/.openclaw/workspace"
+        );
+        assert_eq!(config["gateway"]["auth"]["token"], "abc");
+        assert_eq!(config["channels"]["telegram"]["enabled"], true);
+        assert_eq!(
+            config["channels"]["telegram"]["accounts"]["default"]["botToken"],
+            "123:x"
+        );
+        assert_eq!(config["meta"]["lastTouchedVersion"], "2026.2.8");
+
+        assert!(pending(&config, Some("2026.2.8")).is_empty());
+        assert!(migrate(&mut config, None).is_empty());
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v2026.2.8"), Some(vec![2026, 2, 8]));
+        assert_eq!(
+            parse_version("openclaw 2026.3.0-beta.1"),
+            Some(vec![2026, 3, 0])
+        );
+        assert_eq!(parse_version("unknown"), None);
+    }
+}
@@ -0,0 +1,144 @@
+//! Read-only observer mode.
+//!
+//! While the mode is on, the invoke handler rejects every command outside `ALLOWED_COMMANDS`
+//! (status, logs, sessions and usage views plus what the app needs to render), so a shared or
+//! family agent can be watched without risking configuration changes. Leaving the mode can be
+//! protected with a passcode, kept as a salted SHA-256 hash in `observer.json`.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+const OBSERVER_FILE: &str = "observer.json";
+
+/// Commands that only read state.
+pub const ALLOWED_COMMANDS: &[&str] = &[
+    "browse_workspace_templates",
+    "check_messaging_link_status",
+    "check_pairing_status",
+    "cli_channels_status",
+    "cli_list_agents",
+    "cli_list_models",
+    "cli_list_sessions",
+    "close_app",
+    "container_logs",
+    "container_status",
+    "export_logs",
+    "get_active_operation",
+    "get_activity_feed",
+    "get_agent_pause_status",
+    "get_app_settings",
+    "get_background_tasks",
+    "get_budget_status",
+    "get_daily_digest",
+    "get_detected_issues",
+    "get_environment_info",
+    "get_gateway_logs",
+    "get_gateway_service",
+    "get_gateway_service_status",
+    "get_gateway_status",
+    "get_gateway_supervision_status",
+    "get_heartbeat_status",
+    "get_message_catalog",
+    "get_metrics",
+    "get_observer_mode",
+    "get_openclaw_version",
+    "get_provider_health",
+    "get_provider_latency",
+    "get_safe_mode_status",
+    "get_session",
+    "get_setup_state",
+    "get_storage_usage",
+    "get_usage_summary",
+    "identify_message",
+    "list_gateways",
+    "list_mcp_servers",
+    "list_sessions",
+    "set_observer_mode",
+    "test_gateway",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ObserverState {
+    pub enabled: bool,
+    pub passcode_salt: Option<String>,
+    pub passcode_hash: Option<String>,
+}
+
+pub fn is_allowed(command: &str) -> bool {
+    ALLOWED_COMMANDS.contains(&command)
+}
+
+pub fn hash_passcode(salt: &str, passcode: &str) -> String {
+    Sha256::digest(format!("{}:{}", salt, passcode).as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+impl ObserverState {
+    /// Turns the mode on, protecting it with `passcode` when one is given.
+    pub fn enable(&mut self, passcode: Option<&str>) {
+        self.enabled = true;
+        let passcode = passcode.map(str::trim).filter(|p| !p.is_empty());
+        self.passcode_salt = passcode.map(|_| uuid::Uuid::new_v4().simple().to_string());
+        self.passcode_hash = passcode
+            .zip(self.passcode_salt.as_deref())
+            .map(|(passcode, salt)| hash_passcode(salt, passcode));
+    }
+
+    /// Turns the mode off if `passcode` matches the one it was enabled with.
+    pub fn disable(&mut self, passcode: Option<&str>) -> Result<(), String> {
+        if let (Some(salt), Some(hash)) = (&self.passcode_salt, &self.passcode_hash) {
+            let given = passcode.map(str::trim).unwrap_or_default();
+            if hash_passcode(salt, given) != *hash {
+                return Err("Wrong passcode; observer mode stays on.".to_string());
+            }
+        }
+        *self = ObserverState::default();
+        Ok(())
+    }
+}
+
+pub fn load(app_dir: &Path) -> ObserverState {
+    fs::read_to_string(app_dir.join(OBSERVER_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(app_dir: &Path, state: &ObserverState) -> Result<(), String> {
+    fs::create_dir_all(app_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let raw = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize observer mode: {}", e))?;
+    fs::write(app_dir.join(OBSERVER_FILE), raw)
+        .map_err(|e| format!("Failed to save observer mode: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passcode_guards_leaving_observer_mode() {
+        let mut state = ObserverState::default();
+        state.enable(Some("1234"));
+        assert!(state.enabled);
+        assert!(state.disable(None).is_err());
+        assert!(state.disable(Some("4321")).is_err());
+        assert!(state.enabled);
+        state.disable(Some(" 1234 ")).unwrap();
+        assert_eq!(state, ObserverState::default());
+
+        state.enable(Some(""));
+        assert_eq!(state.passcode_hash, None);
+        state.disable(None).unwrap();
+
+        assert!(is_allowed("get_gateway_status"));
+        assert!(!is_allowed("set_app_settings"));
+        assert!(ALLOWED_COMMANDS.windows(2).all(|w| w[0] < w[1]));
+    }
+}
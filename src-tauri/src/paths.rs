@@ -0,0 +1,175 @@
+//! Location of the local OpenClaw root (`~/.openclaw` by default).
+//!
+//! The root comes from, in order: the active profile / `openclaw_root` app setting (see
+//! `profiles::active_root`), `OPENCLAW_STATE_DIR`,
+//! `$OPENCLAW_HOME/.openclaw`, and finally `~/.openclaw`. Every local file access and
+//! shell snippet goes through here, and a non-default root is exported to the `openclaw`
+//! CLI so both sides agree on where config and state live. Remote (SSH) hosts keep using
+//! their own `~/.openclaw`.
+
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref ROOT_SETTING: Mutex<Option<String>> = Mutex::new(None);
+}
+
+const DEFAULT_DIR: &str = ".openclaw";
+
+/// Root directory for `home`, applying the setting/env overrides. `~/` prefixes in an
+/// override are expanded against `home`.
+fn root_for_home(
+    home: &str,
+    setting: Option<&str>,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> String {
+    let expand = |path: String| match path.strip_prefix("~/") {
+        Some(rest) => format!("{}/{}", home.trim_end_matches('/'), rest),
+        None => path,
+    };
+    let non_empty = |value: Option<String>| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    if let Some(root) = non_empty(setting.map(|s| s.to_string())) {
+        return expand(root).trim_end_matches('/').to_string();
+    }
+    if let Some(root) = non_empty(env("OPENCLAW_STATE_DIR")) {
+        return expand(root).trim_end_matches('/').to_string();
+    }
+    if let Some(home_override) = non_empty(env("OPENCLAW_HOME")) {
+        return format!(
+            "{}/{}",
+            expand(home_override).trim_end_matches('/'),
+            DEFAULT_DIR
+        );
+    }
+    format!("{}/{}", home.trim_end_matches('/'), DEFAULT_DIR)
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+/// Applies the root from app settings and exports the resulting root to CLI children.
+pub fn set_root_setting(root: Option<String>) {
+    if let Ok(mut setting) = ROOT_SETTING.lock() {
+        *setting = root.filter(|r| !r.trim().is_empty());
+    }
+    if !is_custom_root() {
+        crate::process::set_child_env(Vec::new());
+        return;
+    }
+    let env = match local_root() {
+        Ok(root) => vec![
+            ("OPENCLAW_STATE_DIR".to_string(), root.clone()),
+            (
+                "OPENCLAW_CONFIG_PATH".to_string(),
+                format!("{}/openclaw.json", root),
+            ),
+        ],
+        _ => Vec::new(),
+    };
+    crate::process::set_child_env(env);
+}
+
+fn root_setting() -> Option<String> {
+    ROOT_SETTING.lock().ok().and_then(|s| s.clone())
+}
+
+/// True when the root is anything other than `~/.openclaw`.
+pub fn is_custom_root() -> bool {
+    root_setting().is_some()
+        || env_var("OPENCLAW_STATE_DIR").is_some_and(|v| !v.trim().is_empty())
+        || env_var("OPENCLAW_HOME").is_some_and(|v| !v.trim().is_empty())
+}
+
+pub fn local_home() -> Result<String, String> {
+    #[cfg(target_os = "windows")]
+    {
+        crate::wsl_home_dir().map(|h| h.trim().to_string())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        dirs::home_dir()
+            .map(|h| h.to_string_lossy().to_string())
+            .ok_or_else(|| "Could not find home directory".to_string())
+    }
+}
+
+/// Absolute OpenClaw root on the machine running openclaw (a WSL path on Windows).
+pub fn local_root() -> Result<String, String> {
+    let home = local_home()?;
+    Ok(root_for_home(&home, root_setting().as_deref(), &env_var))
+}
+
+/// Absolute form of a configured root such as `~/.openclaw-lab`.
+pub fn expand_root(root: &str) -> Result<String, String> {
+    let home = local_home()?;
+    Ok(root_for_home(&home, Some(root), &|_| None))
+}
+
+/// `relative` under the local root.
+pub fn local_path(relative: &str) -> Result<String, String> {
+    Ok(format!(
+        "{}/{}",
+        local_root()?,
+        relative.trim_start_matches('/')
+    ))
+}
+
+/// The root as a shell word fragment for use inside double quotes, e.g.
+/// `format!("cat \"{}/openclaw.json\"", paths::shell_root())`. Stays `$HOME/.openclaw`
+/// for the default root so commands need no home lookup.
+pub fn shell_root() -> String {
+    if !is_custom_root() {
+        return format!("$HOME/{}", DEFAULT_DIR);
+    }
+    match local_root() {
+        Ok(root) => root
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('$', "\\$")
+            .replace('`', "\\`"),
+        Err(_) => format!("$HOME/{}", DEFAULT_DIR),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_for_home_precedence() {
+        let none = |_: &str| None;
+        assert_eq!(root_for_home("/home/me", None, &none), "/home/me/.openclaw");
+        assert_eq!(
+            root_for_home("/home/me", Some("~/claw-state/"), &none),
+            "/home/me/claw-state"
+        );
+
+        let env = |name: &str| match name {
+            "OPENCLAW_HOME" => Some("/srv/agent".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            root_for_home("/home/me", None, &env),
+            "/srv/agent/.openclaw"
+        );
+        assert_eq!(
+            root_for_home("/home/me", Some("/data/oc"), &env),
+            "/data/oc"
+        );
+
+        let state_env = |name: &str| match name {
+            "OPENCLAW_STATE_DIR" => Some("/var/lib/openclaw".to_string()),
+            "OPENCLAW_HOME" => Some("/srv/agent".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            root_for_home("/home/me", None, &state_env),
+            "/var/lib/openclaw"
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! Moving a configuration to another machine.
+//!
+//! `prepare_config_for_new_machine` turns `openclaw.json` into a portable form for export:
+//! paths under the OpenClaw root and the home directory become `ROOT_PLACEHOLDER` and
+//! `HOME_PLACEHOLDER`, and the `MACHINE_FIELDS` that tie it to this machine's gateway are
+//! dropped. `adopt_config` reverses this on import, filling the placeholders in for the local
+//! machine and taking the dropped fields from the config being replaced, and
+//! `translate_paths` moves any remaining paths from the other machine's home directory (as
+//! found in a plain `openclaw.json` copied across) to this one. Device identity and
+//! pairings live outside `openclaw.json` and are never exported, so the new machine pairs as a
+//! new device.
+
+use serde::{Deserialize, Serialize};
+
+pub const FORMAT: &str = "clawnetes-config";
+pub const VERSION: u32 = 1;
+pub const ROOT_PLACEHOLDER: &str = "<openclaw-root>";
+pub const HOME_PLACEHOLDER: &str = "<home>";
+
+/// Dotted paths of fields that belong to one machine: the gateway's auth token (devices are
+/// paired against it), its bind address, openclaw's own bookkeeping and the local browser.
+pub const MACHINE_FIELDS: &[&str] = &[
+    "gateway.auth.token",
+    "gateway.customBindHost",
+    "meta",
+    "wizard",
+    "browser.executablePath",
+];
+
+/// Contents of an exported configuration file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConfigExport {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: u64,
+    /// `macos`, `linux` or `windows`.
+    pub platform: String,
+    pub config: serde_json::Value,
+    /// `clawnetes-meta.json`, when there is one.
+    #[serde(default)]
+    pub meta: Option<serde_json::Value>,
+}
+
+impl ConfigExport {
+    pub fn new(config: serde_json::Value, meta: Option<serde_json::Value>, now: u64) -> Self {
+        ConfigExport {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            exported_at: now,
+            platform: std::env::consts::OS.to_string(),
+            config,
+            meta,
+        }
+    }
+}
+
+/// A path rewritten by `translate_paths`, with `field` as a dotted path like
+/// `agents.list[0].workspace`.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PathSubstitution {
+    pub field: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// An export from `ConfigExport`, or a plain `openclaw.json` copied from another machine.
+pub fn parse_export(raw: &str) -> Result<ConfigExport, String> {
+    let value: serde_json::Value = serde_json::from_str(raw)
+        .map_err(|e| format!("Not a Clawnetes configuration export: {}", e))?;
+    if value.is_object() && value.get("format").is_none() {
+        return Ok(ConfigExport {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            exported_at: 0,
+            platform: String::new(),
+            config: value,
+            meta: None,
+        });
+    }
+    let export: ConfigExport = serde_json::from_value(value)
+        .map_err(|e| format!("Not a Clawnetes configuration export: {}", e))?;
+    if export.format != FORMAT {
+        return Err("Not a Clawnetes configuration export.".to_string());
+    }
+    if export.version > VERSION {
+        return Err(format!(
+            "This export was made by a newer Clawnetes (format version {}). Update the app to import it.",
+            export.version
+        ));
+    }
+    if !export.config.is_object() {
+        return Err("The export does not contain a configuration.".to_string());
+    }
+    Ok(export)
+}
+
+/// `value` with `prefix` replaced by `with`, if it is `prefix` or a path below it.
+pub fn replace_prefix(value: &str, prefix: &str, with: &str) -> Option<String> {
+    if prefix.is_empty() {
+        return None;
+    }
+    let rest = value.strip_prefix(prefix)?;
+    (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", with, rest))
+}
+
+/// Applies `rewrite` to every string in `value`, keeping the original where it returns `None`.
+pub fn rewrite_strings(
+    value: &mut serde_json::Value,
+    rewrite: &mut dyn FnMut(&str) -> Option<String>,
+) {
+    match value {
+        serde_json::Value::String(text) => {
+            if let Some(rewritten) = rewrite(text) {
+                *text = rewritten;
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                rewrite_strings(item, rewrite);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for item in map.values_mut() {
+                rewrite_strings(item, rewrite);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn pointer(path: &str) -> String {
+    format!("/{}", path.replace('.', "/"))
+}
+
+fn remove_field(config: &mut serde_json::Value, path: &str) -> bool {
+    let (parent, key) = match path.rsplit_once('.') {
+        Some((parent, key)) => (config.pointer_mut(&pointer(parent)), key),
+        None => (Some(config), path),
+    };
+    parent
+        .and_then(|p| p.as_object_mut())
+        .is_some_and(|p| p.remove(key).is_some())
+}
+
+fn set_field(config: &mut serde_json::Value, path: &str, value: serde_json::Value) {
+    let mut current = config;
+    let mut segments = path.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        let Some(map) = current.as_object_mut() else {
+            return;
+        };
+        if segments.peek().is_none() {
+            map.insert(segment.to_string(), value);
+            return;
+        }
+        current = map.entry(segment).or_insert_with(|| serde_json::json!({}));
+    }
+}
+
+/// Portable copy of `config` and the `MACHINE_FIELDS` that were dropped from it.
+pub fn prepare_config_for_new_machine(
+    config: &serde_json::Value,
+    root: &str,
+    home: &str,
+) -> (serde_json::Value, Vec<String>) {
+    let mut portable = config.clone();
+    let stripped: Vec<String> = MACHINE_FIELDS
+        .iter()
+        .filter(|path| remove_field(&mut portable, path))
+        .map(|path| path.to_string())
+        .collect();
+    rewrite_strings(&mut portable, &mut |text| {
+        replace_prefix(text, root, ROOT_PLACEHOLDER)
+            .or_else(|| replace_prefix(text, home, HOME_PLACEHOLDER))
+    });
+    (portable, stripped)
+}
+
+/// `portable` set up for this machine, and the `MACHINE_FIELDS` taken from `local`, the
+/// config it replaces.
+pub fn adopt_config(
+    portable: &serde_json::Value,
+    root: &str,
+    home: &str,
+    local: Option<&serde_json::Value>,
+) -> (serde_json::Value, Vec<String>) {
+    let mut config = portable.clone();
+    rewrite_strings(&mut config, &mut |text| {
+        replace_prefix(text, ROOT_PLACEHOLDER, root)
+            .or_else(|| replace_prefix(text, HOME_PLACEHOLDER, home))
+    });
+    let mut kept = Vec::new();
+    for path in MACHINE_FIELDS {
+        if let Some(value) = local.and_then(|l| l.pointer(&pointer(path))) {
+            set_field(&mut config, path, value.clone());
+            kept.push(path.to_string());
+        }
+    }
+    (config, kept)
+}
+
+/// Splits an absolute path into its home directory and the rest, for homes under `/Users`,
+/// `/home`, `/root` or a Windows `X:\Users`.
+fn split_home(path: &str) -> Option<(&str, &str)> {
+    if path == "/root" || path.starts_with("/root/") {
+        return Some(path.split_at(5));
+    }
+    let users = if path.starts_with("/Users/") {
+        7
+    } else if path.starts_with("/home/") {
+        6
+    } else if matches!(path.get(1..9), Some(":\\Users\\" | ":/Users/")) {
+        9
+    } else {
+        return None;
+    };
+    let name = path[users..]
+        .find(['/', '\\'])
+        .unwrap_or(path.len() - users);
+    (name > 0).then(|| path.split_at(users + name))
+}
+
+/// `path` moved from another machine's home to `home`, with its OpenClaw root (`~/.openclaw`)
+/// mapped to `root`. `None` for paths that are not in a home directory or already local.
+pub fn translate_path(path: &str, root: &str, home: &str) -> Option<String> {
+    let (foreign, rest) = split_home(path)?;
+    if foreign == home {
+        return None;
+    }
+    let rest = rest.replace('\\', "/");
+    Some(match replace_prefix(&rest, "/.openclaw", root) {
+        Some(under_root) => under_root,
+        None => format!("{}{}", home, rest),
+    })
+}
+
+/// Rewrites every path in `config` that points into another machine's home directory.
+pub fn translate_paths(
+    config: &mut serde_json::Value,
+    root: &str,
+    home: &str,
+) -> Vec<PathSubstitution> {
+    fn walk(
+        value: &mut serde_json::Value,
+        field: String,
+        root: &str,
+        home: &str,
+        out: &mut Vec<PathSubstitution>,
+    ) {
+        match value {
+            serde_json::Value::String(text) => {
+                if let Some(to) = translate_path(text, root, home) {
+                    let from = std::mem::replace(text, to.clone());
+                    out.push(PathSubstitution { field, from, to });
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for (i, item) in items.iter_mut().enumerate() {
+                    walk(item, format!("{}[{}]", field, i), root, home, out);
+                }
+            }
+            serde_json::Value::Object(map) => {
+                for (key, item) in map.iter_mut() {
+                    let field = if field.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", field, key)
+                    };
+                    walk(item, field, root, home, out);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut substitutions = Vec::new();
+    walk(config, String::new(), root, home, &mut substitutions);
+    substitutions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_round_trips_between_machines() {
+        let config = serde_json::json!({
+            "agents": {
+                "defaults": { "workspace": "/Users/alice/.openclaw/workspace" },
+                "list": [{ "id": "main", "agentDir": "/Users/alice/.openclaw/agents/main/agent" }]
+            },
+            "skills": { "load": { "extraDirs": ["/Users/alice/skills", "/Users/alicexyz"] } },
+            "gateway": { "port": 18789, "auth": { "mode": "token", "token": "old" } },
+            "meta": { "lastTouchedVersion": "2026.1.1" }
+        });
+        let (portable, stripped) =
+            prepare_config_for_new_machine(&config, "/Users/alice/.openclaw", "/Users/alice");
+        assert_eq!(stripped, ["gateway.auth.token", "meta"]);
+        assert_eq!(
+            portable["agents"]["defaults"]["workspace"],
+            "<openclaw-root>/workspace"
+        );
+        assert_eq!(
+            portable["skills"]["load"]["extraDirs"],
+            serde_json::json!(["<home>/skills", "/Users/alicexyz"])
+        );
+        assert!(portable.get("meta").is_none());
+
+        let export = ConfigExport::new(portable, None, 1);
+        let parsed = parse_export(&serde_json::to_string(&export).unwrap()).unwrap();
+        let local = serde_json::json!({ "gateway": { "auth": { "token": "local" } } });
+        let (adopted, kept) = adopt_config(
+            &parsed.config,
+            "/home/bob/.openclaw",
+            "/home/bob",
+            Some(&local),
+        );
+        assert_eq!(kept, ["gateway.auth.token"]);
+        assert_eq!(adopted["gateway"]["auth"]["token"], "local");
+        assert_eq!(adopted["gateway"]["auth"]["mode"], "token");
+        assert_eq!(
+            adopted["agents"]["list"][0]["agentDir"],
+            "/home/bob/.openclaw/agents/main/agent"
+        );
+        assert!(parse_export(r#"{"format":"other","version":1}"#).is_err());
+    }
+
+    #[test]
+    fn test_translate_paths_from_another_home() {
+        let mut config = serde_json::json!({
+            "agents": {
+                "defaults": { "workspace": "/Users/alice/.openclaw/workspace" },
+                "list": [{ "id": "main", "workspace": "C:\\Users\\Alice\\projects\\bot" }]
+            },
+            "models": { "providers": { "local": { "path": "/home/bob/models/llama.gguf" } } },
+            "tools": { "exec": { "cwd": "/opt/shared" } }
+        });
+        let substitutions = translate_paths(&mut config, "/home/bob/.openclaw", "/home/bob");
+        assert_eq!(
+            config["agents"]["defaults"]["workspace"],
+            "/home/bob/.openclaw/workspace"
+        );
+        assert_eq!(
+            substitutions[1],
+            PathSubstitution {
+                field: "agents.list[0].workspace".to_string(),
+                from: "C:\\Users\\Alice\\projects\\bot".to_string(),
+                to: "/home/bob/projects/bot".to_string(),
+            }
+        );
+        assert_eq!(substitutions.len(), 2);
+        assert_eq!(
+            translate_path("/root/.openclaw", "/srv/claw", "/home/bob").as_deref(),
+            Some("/srv/claw")
+        );
+        assert_eq!(translate_path("/Users/", "/r", "/h"), None);
+    }
+}
@@ -0,0 +1,687 @@
+//! Spawning external programs.
+//!
+//! `run`/`output` pass arguments straight to the child with `Command::args`, so user input
+//! (pairing codes, file names, model names) is never seen by a shell. `shell` remains for
+//! fixed command lines that genuinely need shell features such as pipes or `||`.
+//!
+//! `probe_environment` runs once at startup and records the login shell's PATH and the
+//! absolute paths of node/npm/openclaw, after which plain commands skip the login shell.
+//!
+//! Every child runs under a timeout (see `default_timeout`) and is killed when it expires,
+//! so a hung `openclaw` or npm process can no longer freeze the invoking command. A command
+//! that fails because of such a timeout rejects with `AppError::Timeout` (see
+//! `timeouts_since`), so the UI can offer to retry it.
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    static ref RESOLVED_PROGRAMS: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+    static ref ENVIRONMENT: Mutex<Option<EnvironmentInfo>> = Mutex::new(None);
+    static ref CHILD_ENV: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+    static ref RECENT_TIMEOUTS: Mutex<VecDeque<(Instant, ProcessError)>> =
+        Mutex::new(VecDeque::new());
+}
+
+const PROBED_PROGRAMS: [&str; 5] = ["node", "npm", "openclaw", "bun", "pnpm"];
+/// Global bin directories of bun, pnpm and the user-level npm prefix, which are not always
+/// on the shell profile's PATH. Appended to PATH during the probe when they exist.
+const GLOBAL_BIN_DIRS: &str = "\"$HOME/.bun/bin\" \"$PNPM_HOME\" \"$HOME/Library/pnpm\" \
+     \"$HOME/.local/share/pnpm\" \"$HOME/.npm-global/bin\"";
+const SHELL_SYNTAX: &str = "|&;<>()$`'\"\\*?~{}[]#=!\n";
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Gateway start/stop, doctor and plugin/skill installs routinely take a minute or more.
+const SLOW_TIMEOUT: Duration = Duration::from_secs(3 * 60);
+/// Package manager installs download Node.js or the whole openclaw dependency tree.
+pub const INSTALL_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How many timeouts `timeouts_since` remembers.
+const MAX_RECENT_TIMEOUTS: usize = 16;
+
+const INSTALL_COMMANDS: &[&str] = &[
+    "npm install",
+    "npm uninstall",
+    "npm update",
+    "apt-get",
+    "brew install",
+    "nvm install",
+    "install.sh",
+];
+const SLOW_COMMANDS: &[&str] = &[
+    "openclaw gateway",
+    "openclaw doctor",
+    "openclaw security",
+    "openclaw plugins install",
+    "openclaw update",
+    "clawhub install",
+];
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProcessError {
+    /// The program could not be found or started.
+    Spawn { detail: String },
+    /// The child was still running at the deadline and has been killed.
+    Timeout { command: String, timeout_secs: u64 },
+}
+
+impl std::fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessError::Spawn { detail } => write!(f, "{}", detail),
+            ProcessError::Timeout {
+                command,
+                timeout_secs,
+            } => write!(
+                f,
+                "{}",
+                crate::messages::text(
+                    "process.timeout",
+                    &[("command", command), ("seconds", &timeout_secs.to_string())],
+                )
+            ),
+        }
+    }
+}
+
+/// The `Timeout` error for a killed child, remembered for `timeouts_since`.
+fn timed_out(command: String, timeout: Duration) -> ProcessError {
+    let err = ProcessError::Timeout {
+        command,
+        timeout_secs: timeout.as_secs(),
+    };
+    if let Ok(mut recent) = RECENT_TIMEOUTS.lock() {
+        recent.push_front((Instant::now(), err.clone()));
+        recent.truncate(MAX_RECENT_TIMEOUTS);
+    }
+    err
+}
+
+/// Children killed at their timeout since `since`, newest first. Errors reach the frontend
+/// as text, so this lets the command layer recognise a timeout in them.
+pub fn timeouts_since(since: Instant) -> Vec<ProcessError> {
+    RECENT_TIMEOUTS
+        .lock()
+        .map(|recent| {
+            recent
+                .iter()
+                .take_while(|(at, _)| *at >= since)
+                .map(|(_, err)| err.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl From<ProcessError> for String {
+    fn from(err: ProcessError) -> Self {
+        err.to_string()
+    }
+}
+
+#[derive(Clone, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentInfo {
+    /// PATH as seen by the login shell; applied to directly spawned children.
+    pub shell_path: Option<String>,
+    pub node: Option<String>,
+    pub npm: Option<String>,
+    pub openclaw: Option<String>,
+    pub bun: Option<String>,
+    pub pnpm: Option<String>,
+    pub probed_at: u64,
+}
+
+/// Raw result of a child process, for callers that need the exit code.
+pub struct Output {
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Output {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// `Ok(stdout)` on success, otherwise the most useful error text available.
+    pub fn into_result(self) -> Result<String, String> {
+        if self.success() {
+            Ok(self.stdout)
+        } else if !self.stderr.is_empty() {
+            Err(self.stderr)
+        } else if !self.stdout.is_empty() {
+            Err(self.stdout) // sometimes error messages are in stdout
+        } else {
+            Err(format!(
+                "Command failed with exit code: {}",
+                self.code.unwrap_or(-1)
+            ))
+        }
+    }
+}
+
+/// Timeout for a command line when the caller does not pick one.
+pub fn default_timeout(command_line: &str) -> Duration {
+    if INSTALL_COMMANDS.iter().any(|c| command_line.contains(c)) {
+        INSTALL_TIMEOUT
+    } else if SLOW_COMMANDS.iter().any(|c| command_line.contains(c)) {
+        SLOW_TIMEOUT
+    } else {
+        DEFAULT_TIMEOUT
+    }
+}
+
+/// Short label for timeout messages: the program and its subcommand, never user input.
+fn command_label(command_line: &str) -> String {
+    command_line
+        .split_whitespace()
+        .take(2)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn read_in_background<R: Read + Send + 'static>(
+    pipe: Option<R>,
+) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).to_string()
+    })
+}
+
+/// Runs `cmd` to completion, killing it once `timeout` has elapsed.
+pub fn spawn_with_timeout(
+    mut cmd: Command,
+    label: String,
+    timeout: Duration,
+) -> Result<Output, ProcessError> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ProcessError::Spawn {
+            detail: format!("Failed to execute command: {}", e),
+        })?;
+    // Drain both pipes concurrently so a chatty child cannot block on a full pipe.
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(timed_out(label, timeout));
+            }
+            Ok(None) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                return Err(ProcessError::Spawn {
+                    detail: format!("Failed to wait for command: {}", e),
+                })
+            }
+        }
+    };
+
+    Ok(Output {
+        code: status.code(),
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Like `spawn_with_timeout`, but calls `on_line` with each stdout/stderr line as it arrives,
+/// for installs whose own progress output is worth showing.
+pub fn spawn_streaming<F: FnMut(&str)>(
+    mut cmd: Command,
+    label: String,
+    timeout: Duration,
+    mut on_line: F,
+) -> Result<Output, ProcessError> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ProcessError::Spawn {
+            detail: format!("Failed to execute command: {}", e),
+        })?;
+    let (tx, rx) = std::sync::mpsc::channel::<(bool, String)>();
+    let forward = |pipe: Option<Box<dyn Read + Send>>, is_stderr: bool| {
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            let Some(pipe) = pipe else { return };
+            for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                if tx.send((is_stderr, line)).is_err() {
+                    break;
+                }
+            }
+        });
+    };
+    forward(child.stdout.take().map(|p| Box::new(p) as _), false);
+    forward(child.stderr.take().map(|p| Box::new(p) as _), true);
+    drop(tx);
+
+    let deadline = Instant::now() + timeout;
+    let (mut stdout, mut stderr) = (String::new(), String::new());
+    loop {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok((is_stderr, line)) => {
+                on_line(&line);
+                let buffer = if is_stderr { &mut stderr } else { &mut stdout };
+                buffer.push_str(&line);
+                buffer.push('\n');
+            }
+            // Both pipes closed: the child has exited or is about to.
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(timed_out(label, timeout));
+        }
+    }
+    let status = child.wait().map_err(|e| ProcessError::Spawn {
+        detail: format!("Failed to wait for command: {}", e),
+    })?;
+    Ok(Output {
+        code: status.code(),
+        stdout,
+        stderr,
+    })
+}
+
+/// Runs a prepared `Command` under the default timeout for `command_line`, for callers
+/// that need spawn options `run`/`shell` do not cover (e.g. `wsl --user root`).
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn spawn(cmd: Command, command_line: &str) -> Result<Output, ProcessError> {
+    spawn_with_timeout(
+        cmd,
+        command_label(command_line),
+        default_timeout(command_line),
+    )
+}
+
+/// Splits `cmd` into argv when it is a plain `program arg...` line whose program has
+/// already been resolved, so it can be spawned without a shell.
+fn direct_argv(cmd: &str) -> Option<Vec<&str>> {
+    if cmd.chars().any(|c| SHELL_SYNTAX.contains(c)) {
+        return None;
+    }
+    let argv: Vec<&str> = cmd.split_whitespace().collect();
+    let program = argv.first()?;
+    let cached = RESOLVED_PROGRAMS
+        .lock()
+        .map(|cache| cache.contains_key(*program))
+        .unwrap_or(false);
+    if cached {
+        Some(argv)
+    } else {
+        None
+    }
+}
+
+/// Runs a fixed command line through the platform shell (login zsh on macOS, WSL bash on
+/// Windows). Never interpolate user input into `cmd`; use `run` instead. Simple command
+/// lines for already-resolved programs are spawned directly.
+pub fn shell(cmd: &str) -> Result<Output, ProcessError> {
+    shell_with_timeout(cmd, default_timeout(cmd))
+}
+
+pub fn shell_with_timeout(cmd: &str, timeout: Duration) -> Result<Output, ProcessError> {
+    if let Some(argv) = direct_argv(cmd) {
+        return output_with_timeout(argv[0], &argv[1..], timeout);
+    }
+    spawn_shell(cmd, timeout)
+}
+
+fn spawn_shell(cmd: &str, timeout: Duration) -> Result<Output, ProcessError> {
+    spawn_with_timeout(shell_process(cmd), command_label(cmd), timeout)
+}
+
+/// The platform shell running `cmd`, with the child environment and probed PATH applied,
+/// for callers that manage the child themselves (e.g. the interactive terminal).
+pub fn shell_process(cmd: &str) -> Command {
+    #[cfg(target_os = "macos")]
+    let (shell, args) = ("/bin/zsh", vec!["-l", "-c"]);
+
+    #[cfg(target_os = "windows")]
+    let (shell, args) = ("wsl", vec!["--", "/bin/bash", "-c"]);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let (shell, args) = ("sh", vec!["-c"]);
+
+    // WSL does not inherit the Windows environment, so variables are exported in-line.
+    #[cfg(target_os = "windows")]
+    let cmd = &with_child_env(cmd);
+
+    let mut command = Command::new(shell);
+    command.args(&args).arg(cmd);
+    #[cfg(not(target_os = "windows"))]
+    command.envs(child_env());
+    // Carries probed bun/pnpm bin directories into the shell; macOS path_helper keeps
+    // inherited entries. WSL does not inherit the Windows PATH, so it is left alone there.
+    #[cfg(not(target_os = "windows"))]
+    if let Some(path) = shell_path() {
+        command.env("PATH", path);
+    }
+    command
+}
+
+fn is_bare_program_name(program: &str) -> bool {
+    !program.is_empty()
+        && program
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Absolute path for a bare program name, looked up once through the login shell so that
+/// nvm/Homebrew PATH entries apply even when the app was launched from the Dock.
+pub fn resolve(program: &str) -> Result<String, String> {
+    if !is_bare_program_name(program) {
+        return Ok(program.to_string());
+    }
+    if let Some(path) = RESOLVED_PROGRAMS
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(program).cloned())
+    {
+        return Ok(path);
+    }
+    let path = spawn_shell(&format!("command -v {}", program), PROBE_TIMEOUT)?
+        .into_result()
+        .map(|path| path.trim().to_string())
+        .ok()
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| format!("{} is not installed or not on PATH", program))?;
+    if let Ok(mut cache) = RESOLVED_PROGRAMS.lock() {
+        cache.insert(program.to_string(), path.clone());
+    }
+    Ok(path)
+}
+
+/// Parses the probe script output: the PATH line followed by one `command -v` line per
+/// entry of `PROBED_PROGRAMS` (blank when missing).
+fn parse_probe_output(stdout: &str, probed_at: u64) -> EnvironmentInfo {
+    let mut lines = stdout.lines().map(|l| l.trim().to_string());
+    let mut next = || lines.next().filter(|l| !l.is_empty());
+    EnvironmentInfo {
+        shell_path: next(),
+        node: next(),
+        npm: next(),
+        openclaw: next(),
+        bun: next(),
+        pnpm: next(),
+        probed_at,
+    }
+}
+
+fn probe_script() -> String {
+    let extend_path = format!(
+        "for d in {}; do if [ -n \"$d\" ] && [ -d \"$d\" ]; then \
+         case \":$PATH:\" in *\":$d:\"*) ;; *) PATH=\"$PATH:$d\" ;; esac; fi; done",
+        GLOBAL_BIN_DIRS
+    );
+    [extend_path, "printf '%s\\n' \"$PATH\"".to_string()]
+        .into_iter()
+        .chain(
+            PROBED_PROGRAMS
+                .iter()
+                .map(|p| format!("(command -v {} || echo)", p)),
+        )
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Resolves node/npm/openclaw (plus bun/pnpm) and the login PATH with a single shell
+/// invocation and caches the result. Called at startup and again after installs change
+/// what is on PATH.
+pub fn probe_environment() -> EnvironmentInfo {
+    let script = probe_script();
+    let probed_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let stdout = spawn_shell(&script, PROBE_TIMEOUT)
+        .map(|out| out.stdout)
+        .unwrap_or_default();
+    let info = parse_probe_output(&stdout, probed_at);
+
+    if let Ok(mut cache) = RESOLVED_PROGRAMS.lock() {
+        cache.clear();
+        let found = [&info.node, &info.npm, &info.openclaw, &info.bun, &info.pnpm];
+        for (program, path) in PROBED_PROGRAMS.iter().zip(found) {
+            if let Some(path) = path {
+                cache.insert(program.to_string(), path.clone());
+            }
+        }
+    }
+    if let Ok(mut env) = ENVIRONMENT.lock() {
+        *env = Some(info.clone());
+    }
+    info
+}
+
+/// The cached probe result, probing first if startup has not done so yet.
+pub fn environment_info() -> EnvironmentInfo {
+    let cached = ENVIRONMENT.lock().ok().and_then(|env| env.clone());
+    cached.unwrap_or_else(probe_environment)
+}
+
+/// Extra environment variables for every child, e.g. a custom OpenClaw root (see
+/// `paths::set_root_setting`). Replaces the previous set.
+pub fn set_child_env(vars: Vec<(String, String)>) {
+    if let Ok(mut env) = CHILD_ENV.lock() {
+        *env = vars;
+    }
+}
+
+fn child_env() -> Vec<(String, String)> {
+    CHILD_ENV.lock().map(|env| env.clone()).unwrap_or_default()
+}
+
+/// `export A='x'; export B='y'; ` for the WSL shell.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn export_prefix(vars: &[(String, String)]) -> String {
+    vars.iter()
+        .map(|(name, value)| format!("export {}={}; ", name, crate::shell_single_quote(value)))
+        .collect()
+}
+
+/// Prefixes a shell command line with exports of the child environment, for shells that
+/// do not inherit it (WSL).
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn with_child_env(cmd: &str) -> String {
+    format!("{}{}", export_prefix(&child_env()), cmd)
+}
+
+fn shell_path() -> Option<String> {
+    ENVIRONMENT
+        .lock()
+        .ok()
+        .and_then(|env| env.as_ref().and_then(|e| e.shell_path.clone()))
+}
+
+/// Spawns `program` with `args` and no shell in between.
+pub fn output<S: AsRef<str>>(program: &str, args: &[S]) -> Result<Output, ProcessError> {
+    let args: Vec<&str> = args.iter().map(|a| a.as_ref()).collect();
+    let timeout = default_timeout(&format!("{} {}", program, args.join(" ")));
+    output_with_timeout(program, &args, timeout)
+}
+
+pub fn output_with_timeout<S: AsRef<str>>(
+    program: &str,
+    args: &[S],
+    timeout: Duration,
+) -> Result<Output, ProcessError> {
+    let args: Vec<&str> = args.iter().map(|a| a.as_ref()).collect();
+    let command =
+        program_process(program, &args).map_err(|detail| ProcessError::Spawn { detail })?;
+    let label = command_label(&format!("{} {}", program, args.join(" ")));
+    spawn_with_timeout(command, label, timeout)
+}
+
+/// `program args` with the probed PATH and child environment applied, spawned without a
+/// shell, for callers that manage the child themselves (e.g. the interactive terminal).
+pub fn program_process<S: AsRef<str>>(program: &str, args: &[S]) -> Result<Command, String> {
+    let args: Vec<&str> = args.iter().map(|a| a.as_ref()).collect();
+    let resolved = resolve(program)?;
+    // npm and openclaw are `#!/usr/bin/env node` scripts, so the child needs the login
+    // PATH (nvm, Homebrew) rather than the minimal one the app was launched with.
+    let path = shell_path();
+
+    #[cfg(target_os = "windows")]
+    let command = {
+        let mut cmd = Command::new("wsl");
+        cmd.arg("--exec");
+        let env = child_env();
+        if path.is_some() || !env.is_empty() {
+            cmd.arg("/usr/bin/env");
+        }
+        if let Some(path) = path {
+            cmd.arg(format!("PATH={}", path));
+        }
+        cmd.args(
+            env.iter()
+                .map(|(name, value)| format!("{}={}", name, value)),
+        );
+        cmd.arg(&resolved).args(args);
+        cmd
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let command = {
+        let mut cmd = Command::new(&resolved);
+        if let Some(path) = path {
+            cmd.env("PATH", path);
+        }
+        cmd.envs(child_env()).args(args);
+        cmd
+    };
+
+    Ok(command)
+}
+
+pub fn run<S: AsRef<str>>(program: &str, args: &[S]) -> Result<String, String> {
+    output(program, args)?.into_result()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_result_prefers_stderr_then_stdout() {
+        let out = |code, stdout: &str, stderr: &str| Output {
+            code,
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+        };
+        assert_eq!(
+            out(Some(0), "ok", "warn").into_result(),
+            Ok("ok".to_string())
+        );
+        assert_eq!(
+            out(Some(1), "out", "err").into_result(),
+            Err("err".to_string())
+        );
+        assert_eq!(
+            out(Some(1), "out", "").into_result(),
+            Err("out".to_string())
+        );
+        assert_eq!(
+            out(None, "", "").into_result(),
+            Err("Command failed with exit code: -1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_is_bare_program_name() {
+        assert!(is_bare_program_name("openclaw"));
+        assert!(!is_bare_program_name("/usr/local/bin/openclaw"));
+        assert!(!is_bare_program_name("openclaw; reboot"));
+    }
+
+    #[test]
+    fn test_parse_probe_output() {
+        let info = parse_probe_output(
+            "/usr/local/bin:/usr/bin\n/usr/local/bin/node\n/usr/local/bin/npm\n\n/home/me/.bun/bin/bun\n\n",
+            7,
+        );
+        assert_eq!(info.shell_path.as_deref(), Some("/usr/local/bin:/usr/bin"));
+        assert_eq!(info.node.as_deref(), Some("/usr/local/bin/node"));
+        assert_eq!(info.npm.as_deref(), Some("/usr/local/bin/npm"));
+        assert_eq!(info.openclaw, None);
+        assert_eq!(info.bun.as_deref(), Some("/home/me/.bun/bin/bun"));
+        assert_eq!(info.pnpm, None);
+        assert_eq!(info.probed_at, 7);
+    }
+
+    #[test]
+    fn test_direct_argv_rejects_shell_syntax() {
+        assert_eq!(direct_argv("openclaw doctor --fix || true"), None);
+        assert_eq!(direct_argv("openclaw config validate 2>&1"), None);
+        assert_eq!(direct_argv("echo $HOME"), None);
+    }
+
+    #[test]
+    fn test_default_timeout_by_command() {
+        assert_eq!(default_timeout("npm install -g openclaw"), INSTALL_TIMEOUT);
+        assert_eq!(default_timeout("openclaw gateway restart"), SLOW_TIMEOUT);
+        assert_eq!(default_timeout("openclaw --version"), DEFAULT_TIMEOUT);
+        assert_eq!(command_label("openclaw cron rm secret-id"), "openclaw cron");
+    }
+
+    #[test]
+    fn test_slow_child_is_killed_at_timeout() {
+        #[cfg(unix)]
+        {
+            let started = Instant::now();
+            let err = output_with_timeout("/bin/sleep", &["5"], Duration::from_millis(200))
+                .err()
+                .unwrap();
+            assert!(started.elapsed() < Duration::from_secs(4));
+            assert_eq!(
+                err,
+                ProcessError::Timeout {
+                    command: "/bin/sleep 5".to_string(),
+                    timeout_secs: 0,
+                }
+            );
+            assert!(timeouts_since(started).contains(&err));
+        }
+    }
+
+    #[test]
+    fn test_export_prefix_quotes_values() {
+        let vars = vec![(
+            "OPENCLAW_STATE_DIR".to_string(),
+            "/data/it's here".to_string(),
+        )];
+        assert_eq!(
+            export_prefix(&vars),
+            "export OPENCLAW_STATE_DIR='/data/it'\\''s here'; "
+        );
+    }
+
+    #[test]
+    fn test_run_passes_arguments_verbatim() {
+        #[cfg(unix)]
+        {
+            let out = run("/bin/echo", &["$(whoami)", "a;b"]).unwrap();
+            assert_eq!(out.trim(), "$(whoami) a;b");
+        }
+    }
+}
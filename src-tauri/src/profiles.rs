@@ -0,0 +1,140 @@
+//! Named OpenClaw profiles, each with its own root directory and gateway port, so an
+//! experimental agent can run next to the everyday one.
+//!
+//! The `default` profile is implicit and uses the regular root (`openclaw_root` setting or
+//! `~/.openclaw`). Other profiles live at `~/.openclaw-<name>`, the same layout as
+//! `openclaw --profile <name>`. Switching profiles re-points `paths`, so every command
+//! operates on the active one.
+
+use crate::settings::AppSettings;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PROFILE: &str = "default";
+pub const DEFAULT_GATEWAY_PORT: u16 = 18789;
+/// Gateways use a few consecutive ports each (gateway, browser control, canvas), so
+/// profile ports are spaced out rather than adjacent.
+const PORT_SPACING: u16 = 20;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub root: String,
+    pub port: u16,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ProfileInfo {
+    pub name: String,
+    /// `None` for the default profile, which follows the regular root.
+    pub root: Option<String>,
+    pub port: u16,
+    pub active: bool,
+}
+
+pub fn validate_profile_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 32
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!(
+            "Invalid profile name '{}': use up to 32 lowercase letters, digits, '-' or '_'.",
+            name
+        ));
+    }
+    if name == DEFAULT_PROFILE {
+        return Err("The default profile already exists.".to_string());
+    }
+    Ok(())
+}
+
+fn next_port(settings: &AppSettings) -> u16 {
+    settings
+        .profiles
+        .iter()
+        .map(|p| p.port)
+        .chain(std::iter::once(DEFAULT_GATEWAY_PORT))
+        .max()
+        .unwrap_or(DEFAULT_GATEWAY_PORT)
+        + PORT_SPACING
+}
+
+/// Adds a profile named `name` to `settings` and returns it.
+pub fn add_profile(settings: &mut AppSettings, name: &str) -> Result<Profile, String> {
+    validate_profile_name(name)?;
+    if settings.profiles.iter().any(|p| p.name == name) {
+        return Err(format!("Profile '{}' already exists.", name));
+    }
+    let profile = Profile {
+        name: name.to_string(),
+        root: format!("~/.openclaw-{}", name),
+        port: next_port(settings),
+    };
+    settings.profiles.push(profile.clone());
+    Ok(profile)
+}
+
+pub fn find_profile<'a>(settings: &'a AppSettings, name: &str) -> Option<&'a Profile> {
+    settings.profiles.iter().find(|p| p.name == name)
+}
+
+/// Root override for the active profile: its own root, or `openclaw_root` for `default`.
+pub fn active_root(settings: &AppSettings) -> Option<String> {
+    match find_profile(settings, &settings.active_profile) {
+        Some(profile) => Some(profile.root.clone()),
+        None => settings.openclaw_root.clone(),
+    }
+}
+
+pub fn list(settings: &AppSettings, default_port: u16) -> Vec<ProfileInfo> {
+    let active_is_known = find_profile(settings, &settings.active_profile).is_some();
+    std::iter::once(ProfileInfo {
+        name: DEFAULT_PROFILE.to_string(),
+        root: None,
+        port: default_port,
+        active: !active_is_known,
+    })
+    .chain(settings.profiles.iter().map(|p| ProfileInfo {
+        name: p.name.clone(),
+        root: Some(p.root.clone()),
+        port: p.port,
+        active: active_is_known && p.name == settings.active_profile,
+    }))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_profile_assigns_root_and_spaced_ports() {
+        let mut settings = AppSettings::default();
+        let lab = add_profile(&mut settings, "lab").unwrap();
+        assert_eq!(lab.root, "~/.openclaw-lab");
+        assert_eq!(lab.port, DEFAULT_GATEWAY_PORT + PORT_SPACING);
+        let next = add_profile(&mut settings, "lab-2").unwrap();
+        assert_eq!(next.port, DEFAULT_GATEWAY_PORT + 2 * PORT_SPACING);
+        assert!(add_profile(&mut settings, "lab").is_err());
+        assert!(add_profile(&mut settings, "default").is_err());
+        assert!(add_profile(&mut settings, "../etc").is_err());
+    }
+
+    #[test]
+    fn test_active_root_and_list() {
+        let mut settings = AppSettings {
+            openclaw_root: Some("/data/openclaw".to_string()),
+            ..Default::default()
+        };
+        add_profile(&mut settings, "lab").unwrap();
+        assert_eq!(active_root(&settings), Some("/data/openclaw".to_string()));
+
+        settings.active_profile = "lab".to_string();
+        assert_eq!(active_root(&settings), Some("~/.openclaw-lab".to_string()));
+        let listed = list(&settings, DEFAULT_GATEWAY_PORT);
+        assert_eq!(listed.len(), 2);
+        assert!(!listed[0].active);
+        assert!(listed[1].active);
+    }
+}
@@ -0,0 +1,304 @@
+//! Progress reporting for long-running commands.
+//!
+//! Install, gateway start, the setup orchestrator and remote deployment all report through a
+//! `Progress`, which the UI follows on a single `task-progress` channel keyed by task id.
+//! A caller may pass its own task id so it can subscribe before the command starts. Steps
+//! nested inside a larger task use `child`, which maps their 0–100 onto a slice of the
+//! parent's range. Headless runs print the same updates to stderr.
+//!
+//! For assistive technology every event also carries a dotted `phase` id, the step's position
+//! when the reporter knows it, elapsed and estimated remaining seconds, and a ready-made
+//! `announcement` sentence for a live region.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const TASK_PROGRESS_EVENT: &str = "task-progress";
+const MIN_PERCENT_TO_EXTRAPOLATE: u64 = 5;
+const MIN_SECS_TO_EXTRAPOLATE: u64 = 2;
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct TaskProgress {
+    pub task_id: String,
+    pub task: String,
+    pub step: String,
+    pub percent: u8,
+    pub message: String,
+    /// `info`, `warn` or `error`.
+    pub level: String,
+    /// Task path plus step, e.g. `setup.install.download`.
+    pub phase: String,
+    /// 1-based position of the current step and the number of steps, when known.
+    pub step_index: Option<usize>,
+    pub step_count: Option<usize>,
+    pub elapsed_secs: u64,
+    /// Estimated seconds until this reporter's work is done.
+    pub remaining_secs: Option<u64>,
+    /// E.g. "Installing OpenClaw, step 2 of 5, about 40 seconds remaining".
+    pub announcement: String,
+}
+
+type Sink = Arc<dyn Fn(&TaskProgress) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Progress {
+    task_id: String,
+    task: String,
+    /// Dotted names of this task and the tasks it is nested in.
+    path: String,
+    /// Slice of the task's 0–100 that this reporter's own 0–100 maps onto.
+    range: (u8, u8),
+    /// Fixed position among sibling steps, set with `at_step`.
+    position: Option<(usize, usize)>,
+    /// Step names whose position is looked up per event, set with `with_steps`.
+    steps: &'static [&'static str],
+    started: Instant,
+    /// Typical duration, used for the estimate until progress gives a better one.
+    expected: Option<Duration>,
+    sink: Sink,
+}
+
+impl Progress {
+    /// Reports `task` as `task-progress` events on `app`; errors also go to opt-in reporting.
+    pub fn new(app: &tauri::AppHandle, task: &str, task_id: Option<String>) -> Self {
+        use tauri::Manager;
+        let app = app.clone();
+        Self::with_sink(
+            task,
+            task_id,
+            Arc::new(move |event: &TaskProgress| {
+                if event.level == "error" {
+                    crate::report_command_failure(&event.task, &event.message);
+                }
+                let _ = app.emit_all(TASK_PROGRESS_EVENT, event);
+            }),
+        )
+    }
+
+    /// Prints each update as `[task] percent% message` on stderr.
+    pub fn stderr(task: &str) -> Self {
+        Self::with_sink(
+            task,
+            None,
+            Arc::new(|event: &TaskProgress| {
+                eprintln!("[{}] {:>3}% {}", event.task, event.percent, event.message);
+            }),
+        )
+    }
+
+    fn with_sink(task: &str, task_id: Option<String>, sink: Sink) -> Self {
+        Progress {
+            task_id: task_id
+                .filter(|id| !id.trim().is_empty())
+                .unwrap_or_else(new_task_id),
+            task: task.to_string(),
+            path: task.to_string(),
+            range: (0, 100),
+            position: None,
+            steps: &[],
+            started: Instant::now(),
+            expected: None,
+            sink,
+        }
+    }
+
+    /// Reporter for a sub-task covering `start..=end` percent of this one.
+    pub fn child(&self, task: &str, start: u8, end: u8) -> Self {
+        Progress {
+            task_id: self.task_id.clone(),
+            task: task.to_string(),
+            path: format!("{}.{}", self.path, task),
+            range: (self.scale(start), self.scale(end)),
+            position: None,
+            steps: &[],
+            started: Instant::now(),
+            expected: None,
+            sink: self.sink.clone(),
+        }
+    }
+
+    /// Marks this reporter as step `index` (0-based) of `count`.
+    pub fn at_step(mut self, index: usize, count: usize) -> Self {
+        self.position = Some((index + 1, count));
+        self
+    }
+
+    /// Reports the position of each event's step within `steps`.
+    pub fn with_steps(mut self, steps: &'static [&'static str]) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Typical duration of this reporter's work.
+    pub fn expecting(mut self, duration: Duration) -> Self {
+        self.expected = Some(duration);
+        self
+    }
+
+    pub fn info(&self, step: &str, percent: u8, message: &str) {
+        self.report("info", step, percent, message);
+    }
+
+    pub fn warn(&self, step: &str, percent: u8, message: &str) {
+        self.report("warn", step, percent, message);
+    }
+
+    pub fn error(&self, step: &str, percent: u8, message: &str) {
+        self.report("error", step, percent, message);
+    }
+
+    fn scale(&self, percent: u8) -> u8 {
+        let (start, end) = self.range;
+        let span = end.saturating_sub(start) as u32;
+        start + (span * percent.min(100) as u32 / 100) as u8
+    }
+
+    fn report(&self, level: &str, step: &str, percent: u8, message: &str) {
+        let position = self.position.or_else(|| {
+            let index = self.steps.iter().position(|s| *s == step)?;
+            Some((index + 1, self.steps.len()))
+        });
+        let elapsed = self.started.elapsed().as_secs();
+        let remaining = estimate_remaining(elapsed, percent, self.expected.map(|d| d.as_secs()));
+        (self.sink)(&TaskProgress {
+            task_id: self.task_id.clone(),
+            task: self.task.clone(),
+            step: step.to_string(),
+            percent: self.scale(percent),
+            message: message.to_string(),
+            level: level.to_string(),
+            phase: format!("{}.{}", self.path, step),
+            step_index: position.map(|(index, _)| index),
+            step_count: position.map(|(_, count)| count),
+            elapsed_secs: elapsed,
+            remaining_secs: remaining,
+            announcement: announcement(message, position, remaining),
+        });
+    }
+}
+
+/// Percent after `done` of `total` equal parts between `start` and `end`.
+pub fn fraction(start: u8, end: u8, done: usize, total: usize) -> u8 {
+    if total == 0 {
+        return end;
+    }
+    let span = end.saturating_sub(start) as usize;
+    start + (span * done.min(total) / total) as u8
+}
+
+/// Seconds left, extrapolated from `percent` done after `elapsed_secs`, or `expected_secs`
+/// minus the time spent until there is enough progress to extrapolate from.
+pub fn estimate_remaining(
+    elapsed_secs: u64,
+    percent: u8,
+    expected_secs: Option<u64>,
+) -> Option<u64> {
+    let percent = u64::from(percent.min(100));
+    if percent >= 100 {
+        return Some(0);
+    }
+    if percent >= MIN_PERCENT_TO_EXTRAPOLATE && elapsed_secs >= MIN_SECS_TO_EXTRAPOLATE {
+        return Some(elapsed_secs * (100 - percent) / percent);
+    }
+    expected_secs.map(|expected| expected.saturating_sub(elapsed_secs))
+}
+
+fn describe_remaining(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("about {} seconds remaining", secs.div_ceil(5) * 5),
+        60..=119 => "about a minute remaining".to_string(),
+        _ => format!("about {} minutes remaining", secs.div_ceil(60)),
+    }
+}
+
+/// `message`, then the step position and remaining time when known, as one sentence.
+pub fn announcement(
+    message: &str,
+    position: Option<(usize, usize)>,
+    remaining_secs: Option<u64>,
+) -> String {
+    let mut parts = vec![message.trim().trim_end_matches(['.', '…']).to_string()];
+    if let Some((index, count)) = position {
+        parts.push(format!("step {} of {}", index, count));
+    }
+    if let Some(secs) = remaining_secs.filter(|secs| *secs > 0) {
+        parts.push(describe_remaining(secs));
+    }
+    parts.retain(|part| !part.is_empty());
+    parts.join(", ")
+}
+
+fn new_task_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_child_progress_maps_onto_parent_range() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let sink: Sink = Arc::new(move |event: &TaskProgress| {
+            recorded.lock().unwrap().push(event.clone());
+        });
+        let setup = Progress::with_sink("setup", Some("run-1".to_string()), sink);
+        let install = setup.child("install", 20, 40);
+        install.info("download", 0, "Starting");
+        install.warn("download", 50, "Slow mirror");
+        install.info("download", 100, "Done");
+
+        let events = events.lock().unwrap();
+        let percents: Vec<u8> = events.iter().map(|e| e.percent).collect();
+        assert_eq!(percents, vec![20, 30, 40]);
+        assert!(events
+            .iter()
+            .all(|e| e.task_id == "run-1" && e.task == "install"));
+        assert_eq!(events[1].level, "warn");
+        assert_eq!(fraction(60, 90, 2, 8), 67);
+        assert_eq!(fraction(60, 90, 9, 8), 90);
+        assert_eq!(events[0].phase, "setup.install.download");
+        assert_eq!(events[0].step_index, None);
+    }
+
+    #[test]
+    fn test_announcement_with_position_and_estimate() {
+        assert_eq!(estimate_remaining(20, 50, Some(300)), Some(20));
+        assert_eq!(estimate_remaining(1, 2, Some(90)), Some(89));
+        assert_eq!(estimate_remaining(1, 2, None), None);
+        assert_eq!(estimate_remaining(30, 100, None), Some(0));
+        assert_eq!(
+            announcement("Installing OpenClaw...", Some((2, 5)), Some(38)),
+            "Installing OpenClaw, step 2 of 5, about 40 seconds remaining"
+        );
+        assert_eq!(
+            announcement("Waiting for the gateway", None, Some(150)),
+            "Waiting for the gateway, about 3 minutes remaining"
+        );
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let sink: Sink = Arc::new(move |event: &TaskProgress| {
+            recorded.lock().unwrap().push(event.clone());
+        });
+        let deploy =
+            Progress::with_sink("remote-deploy", None, sink).with_steps(&["connect", "setup"]);
+        deploy.info("setup", 50, "Running setup");
+        deploy
+            .child("setup", 50, 100)
+            .at_step(1, 4)
+            .info("install", 0, "Installing");
+        let events = events.lock().unwrap();
+        assert_eq!(
+            (events[0].step_index, events[0].step_count),
+            (Some(2), Some(2))
+        );
+        assert_eq!(
+            (events[1].step_index, events[1].step_count),
+            (Some(2), Some(4))
+        );
+        assert_eq!(events[1].phase, "remote-deploy.setup.install");
+    }
+}
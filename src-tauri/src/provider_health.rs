@@ -0,0 +1,302 @@
+//! Per-profile rate-limit and quota state for model providers.
+//!
+//! Two sources feed it: error payloads the gateway logs when a provider rejects a request
+//! (`parse_provider_error`, fed by the log monitor), and the cooldowns openclaw records in
+//! auth-profiles.json under `usageStats` when present. Log lines do not name the auth
+//! profile, so they are attributed to `<provider>:default`. A rate limit clears once its
+//! reset time passes; other states clear after `STALE_SECS` without a repeat.
+
+use std::collections::BTreeMap;
+
+pub const STALE_SECS: u64 = 30 * 60;
+/// Assumed rate-limit window when the provider gives no reset time.
+const DEFAULT_RATE_LIMIT_SECS: u64 = 60;
+const MAX_MESSAGE_CHARS: usize = 200;
+
+const PROVIDERS: &[(&str, &str)] = &[
+    ("anthropic", "anthropic"),
+    ("openrouter", "openrouter"),
+    ("openai", "openai"),
+    ("gemini", "google"),
+    ("google", "google"),
+    ("xai", "xai"),
+    ("mistral", "mistral"),
+    ("groq", "groq"),
+    ("deepseek", "deepseek"),
+];
+
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ProviderHealth {
+    pub profile: String,
+    /// `ok`, `rate_limited`, `out_of_quota` or `auth_failed`.
+    pub state: String,
+    /// Unix seconds when the current rate limit is expected to lift.
+    pub reset_at: Option<u64>,
+    pub remaining_requests: Option<u64>,
+    pub remaining_tokens: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<u64>,
+    pub error_count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProviderSignal {
+    pub provider: String,
+    pub state: &'static str,
+    pub retry_after_secs: Option<u64>,
+    pub remaining_requests: Option<u64>,
+    pub remaining_tokens: Option<u64>,
+    pub message: String,
+}
+
+/// Seconds given after `key`, as in "retry-after: 20", "try again in 1m30s" or "retry in 450ms".
+fn duration_after(lower: &str, key: &str) -> Option<u64> {
+    let rest = lower[lower.find(key)? + key.len()..]
+        .trim_start_matches(|c: char| c == ':' || c == '"' || c == '=' || c.is_whitespace());
+    let mut total = 0.0;
+    let mut number = String::new();
+    let mut chars = rest.chars().peekable();
+    let mut parsed = false;
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let value: f64 = match number.parse() {
+            Ok(value) => value,
+            Err(_) => break,
+        };
+        number.clear();
+        parsed = true;
+        total += match c {
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                value / 1000.0
+            }
+            'm' => value * 60.0,
+            'h' => value * 3600.0,
+            's' => value,
+            _ => {
+                total += value;
+                break;
+            }
+        };
+    }
+    if let Ok(value) = number.parse::<f64>() {
+        total += value;
+        parsed = true;
+    }
+    parsed.then(|| total.ceil() as u64)
+}
+
+fn number_after(lower: &str, key: &str) -> Option<u64> {
+    let rest = &lower[lower.find(key)? + key.len()..];
+    let digits: String = rest
+        .trim_start_matches(|c: char| !c.is_ascii_digit())
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+fn provider_of(line: &str, lower: &str) -> Option<String> {
+    let from_json = serde_json::from_str::<serde_json::Value>(line.trim())
+        .ok()
+        .and_then(|entry| {
+            entry
+                .get("provider")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_lowercase())
+        });
+    let text = from_json.as_deref().unwrap_or(lower);
+    PROVIDERS
+        .iter()
+        .find(|(needle, _)| text.contains(needle))
+        .map(|(_, provider)| provider.to_string())
+}
+
+/// Rate-limit, quota or auth rejection from a provider, if `line` records one.
+pub fn parse_provider_error(line: &str) -> Option<ProviderSignal> {
+    let state = match crate::log_monitor::classify_line(line)? {
+        "rate_limit" => "rate_limited",
+        "out_of_credit" => "out_of_quota",
+        _ => "auth_failed",
+    };
+    let lower = line.to_lowercase();
+    let provider = provider_of(line, &lower)?;
+    let retry_after_secs = ["retry-after", "retry_after", "try again in", "retry in"]
+        .iter()
+        .find_map(|key| duration_after(&lower, key));
+    Some(ProviderSignal {
+        provider,
+        state,
+        retry_after_secs,
+        remaining_requests: number_after(&lower, "remaining-requests"),
+        remaining_tokens: number_after(&lower, "remaining-tokens"),
+        message: line.trim().chars().take(MAX_MESSAGE_CHARS).collect(),
+    })
+}
+
+#[derive(Default)]
+pub struct Tracker {
+    profiles: BTreeMap<String, ProviderHealth>,
+}
+
+impl Tracker {
+    pub fn apply(&mut self, signal: &ProviderSignal, now: u64) {
+        let profile = format!("{}:default", signal.provider);
+        let health = self
+            .profiles
+            .entry(profile.clone())
+            .or_insert_with(|| ProviderHealth {
+                profile,
+                ..Default::default()
+            });
+        health.state = signal.state.to_string();
+        health.reset_at = (signal.state == "rate_limited")
+            .then(|| now + signal.retry_after_secs.unwrap_or(DEFAULT_RATE_LIMIT_SECS));
+        health.remaining_requests = signal.remaining_requests.or(health.remaining_requests);
+        health.remaining_tokens = signal.remaining_tokens.or(health.remaining_tokens);
+        health.last_error = Some(signal.message.clone());
+        health.last_error_at = Some(now);
+        health.error_count += 1;
+    }
+
+    /// State of `profiles` plus any other profile seen in the log, with expired states
+    /// cleared.
+    pub fn snapshot(&self, profiles: &[String], now: u64) -> Vec<ProviderHealth> {
+        let mut all: BTreeMap<String, ProviderHealth> = profiles
+            .iter()
+            .map(|profile| {
+                (
+                    profile.clone(),
+                    ProviderHealth {
+                        profile: profile.clone(),
+                        state: "ok".to_string(),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect();
+        for (profile, health) in &self.profiles {
+            let mut health = health.clone();
+            let expired = match health.state.as_str() {
+                "rate_limited" => health.reset_at.is_some_and(|reset| reset <= now),
+                _ => health
+                    .last_error_at
+                    .is_some_and(|at| now.saturating_sub(at) > STALE_SECS),
+            };
+            if expired {
+                health.state = "ok".to_string();
+                health.reset_at = None;
+            }
+            all.insert(profile.clone(), health);
+        }
+        all.into_values().collect()
+    }
+}
+
+fn unix_secs(value: &serde_json::Value) -> Option<u64> {
+    let raw = value.as_u64()?;
+    // openclaw stores epoch milliseconds.
+    Some(if raw > 10_000_000_000 {
+        raw / 1000
+    } else {
+        raw
+    })
+}
+
+/// Applies openclaw's own cooldown bookkeeping for `health.profile` from auth-profiles.json.
+pub fn apply_usage_stats(health: &mut ProviderHealth, stats: &serde_json::Value, now: u64) {
+    if let Some(count) = stats.get("errorCount").and_then(|v| v.as_u64()) {
+        health.error_count = health.error_count.max(count as u32);
+    }
+    if let Some(until) = stats
+        .get("disabledUntil")
+        .and_then(unix_secs)
+        .filter(|until| *until > now)
+    {
+        let reason = stats
+            .get("disabledReason")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        health.state = match reason {
+            "billing" => "out_of_quota",
+            "auth" => "auth_failed",
+            _ => "rate_limited",
+        }
+        .to_string();
+        health.reset_at = Some(until);
+    } else if let Some(until) = stats
+        .get("cooldownUntil")
+        .and_then(unix_secs)
+        .filter(|until| *until > now)
+    {
+        if health.state == "ok" {
+            health.state = "rate_limited".to_string();
+        }
+        health.reset_at = Some(health.reset_at.map_or(until, |reset| reset.max(until)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_provider_error() {
+        let signal = parse_provider_error(
+            r#"{"provider":"anthropic","msg":"429 rate_limit_error","retry-after":"20","x-ratelimit-remaining-tokens":"0"}"#,
+        )
+        .unwrap();
+        assert_eq!(signal.provider, "anthropic");
+        assert_eq!(signal.state, "rate_limited");
+        assert_eq!(signal.retry_after_secs, Some(20));
+        assert_eq!(signal.remaining_tokens, Some(0));
+
+        let signal = parse_provider_error(
+            "openai error 429: Rate limit reached. Please try again in 1m30s.",
+        )
+        .unwrap();
+        assert_eq!(signal.retry_after_secs, Some(90));
+        assert_eq!(
+            parse_provider_error("gemini: You exceeded your current quota")
+                .unwrap()
+                .state,
+            "out_of_quota"
+        );
+        assert!(parse_provider_error("429 from an unknown upstream").is_none());
+        assert_eq!(duration_after("retry in 450ms", "retry in"), Some(1));
+    }
+
+    #[test]
+    fn test_tracker_clears_expired_rate_limits() {
+        let mut tracker = Tracker::default();
+        let signal =
+            parse_provider_error("anthropic 429 too many requests, retry-after: 30").unwrap();
+        tracker.apply(&signal, 1000);
+        let profiles = vec![
+            "anthropic:default".to_string(),
+            "openai:default".to_string(),
+        ];
+
+        let health = tracker.snapshot(&profiles, 1010);
+        assert_eq!(health[0].state, "rate_limited");
+        assert_eq!(health[0].reset_at, Some(1030));
+        assert_eq!(health[1].state, "ok");
+        assert_eq!(tracker.snapshot(&profiles, 1031)[0].state, "ok");
+
+        let mut health = ProviderHealth {
+            profile: "openai:default".to_string(),
+            state: "ok".to_string(),
+            ..Default::default()
+        };
+        let stats = serde_json::json!({
+            "disabledUntil": 1_700_000_600_000u64,
+            "disabledReason": "billing",
+        });
+        apply_usage_stats(&mut health, &stats, 1_700_000_000);
+        assert_eq!(health.state, "out_of_quota");
+        assert_eq!(health.reset_at, Some(1_700_000_600));
+    }
+}
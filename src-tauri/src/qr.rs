@@ -0,0 +1,418 @@
+//! Minimal QR code encoder for pairing links.
+//!
+//! Byte mode, error correction level M, versions 1–10 (up to 213 bytes), which covers deep
+//! links comfortably. The mask is chosen with the standard penalty rules. Output is SVG so the
+//! frontend can show it in an `<img>` without an image library.
+
+const MAX_VERSION: usize = 10;
+/// Error correction codewords per block at level M, by version.
+const ECC_PER_BLOCK: [usize; MAX_VERSION] = [10, 16, 26, 18, 24, 16, 18, 22, 22, 26];
+const BLOCKS: [usize; MAX_VERSION] = [1, 1, 1, 2, 2, 4, 4, 4, 5, 5];
+/// Total codewords, by version.
+const TOTAL_CODEWORDS: [usize; MAX_VERSION] = [26, 44, 70, 100, 134, 172, 196, 242, 292, 346];
+/// Format bits for level M.
+const ECL_M_BITS: u32 = 0;
+
+pub struct QrCode {
+    pub size: usize,
+    modules: Vec<bool>,
+    is_function: Vec<bool>,
+}
+
+fn bit(value: u32, i: u32) -> bool {
+    (value >> i) & 1 != 0
+}
+
+fn data_codewords(version: usize) -> usize {
+    TOTAL_CODEWORDS[version - 1] - ECC_PER_BLOCK[version - 1] * BLOCKS[version - 1]
+}
+
+fn char_count_bits(version: usize) -> usize {
+    if version < 10 {
+        8
+    } else {
+        16
+    }
+}
+
+fn rs_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u8 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1D);
+        z ^= ((y >> i) & 1) * x;
+    }
+    z
+}
+
+fn rs_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root: u8 = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = rs_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = rs_multiply(root, 0x02);
+    }
+    result
+}
+
+fn rs_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for b in data {
+        let factor = b ^ result.remove(0);
+        result.push(0);
+        for (x, y) in result.iter_mut().zip(divisor) {
+            *x ^= rs_multiply(*y, factor);
+        }
+    }
+    result
+}
+
+/// Mode indicator, length, payload, terminator and pad bytes.
+fn encode_data(data: &[u8], version: usize) -> Vec<u8> {
+    let mut bits: Vec<bool> = Vec::new();
+    let mut push = |value: u32, len: usize| {
+        for i in (0..len).rev() {
+            bits.push(bit(value, i as u32));
+        }
+    };
+    push(0b0100, 4);
+    push(data.len() as u32, char_count_bits(version));
+    for b in data {
+        push(u32::from(*b), 8);
+    }
+    let capacity = data_codewords(version) * 8;
+    let terminator = (capacity - bits.len()).min(4);
+    bits.resize((bits.len() + terminator).div_ceil(8) * 8, false);
+    let mut bytes: Vec<u8> = bits
+        .chunks(8)
+        .map(|chunk| chunk.iter().fold(0u8, |acc, b| (acc << 1) | u8::from(*b)))
+        .collect();
+    for pad in [0xEC, 0x11].iter().cycle() {
+        if bytes.len() >= data_codewords(version) {
+            break;
+        }
+        bytes.push(*pad);
+    }
+    bytes
+}
+
+/// Splits into blocks, appends error correction and interleaves.
+fn add_ecc_and_interleave(data: &[u8], version: usize) -> Vec<u8> {
+    let num_blocks = BLOCKS[version - 1];
+    let ecc_len = ECC_PER_BLOCK[version - 1];
+    let raw = TOTAL_CODEWORDS[version - 1];
+    let num_short_blocks = num_blocks - raw % num_blocks;
+    let short_block_len = raw / num_blocks;
+    let divisor = rs_divisor(ecc_len);
+
+    let mut blocks: Vec<Vec<u8>> = Vec::new();
+    let mut k = 0;
+    for i in 0..num_blocks {
+        let len = short_block_len - ecc_len + usize::from(i >= num_short_blocks);
+        let mut block = data[k..k + len].to_vec();
+        k += len;
+        let ecc = rs_remainder(&block, &divisor);
+        if i < num_short_blocks {
+            block.push(0);
+        }
+        block.extend(ecc);
+        blocks.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw);
+    for i in 0..blocks[0].len() {
+        for (j, block) in blocks.iter().enumerate() {
+            if i != short_block_len - ecc_len || j >= num_short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+fn alignment_positions(version: usize, size: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = (version * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    let mut positions: Vec<usize> = (0..count - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+impl QrCode {
+    /// Encodes `data` in the smallest version that fits.
+    pub fn encode(data: &[u8]) -> Result<QrCode, String> {
+        let version = (1..=MAX_VERSION)
+            .find(|v| 4 + char_count_bits(*v) + data.len() * 8 <= data_codewords(*v) * 8)
+            .ok_or_else(|| format!("{} bytes is too long for a QR code here.", data.len()))?;
+        let size = version * 4 + 17;
+        let mut qr = QrCode {
+            size,
+            modules: vec![false; size * size],
+            is_function: vec![false; size * size],
+        };
+        qr.draw_function_patterns(version);
+        let codewords = add_ecc_and_interleave(&encode_data(data, version), version);
+        qr.draw_codewords(&codewords);
+
+        let mut best = (u32::MAX, 0);
+        for mask in 0..8 {
+            qr.apply_mask(mask);
+            qr.draw_format_bits(mask);
+            let penalty = qr.penalty();
+            if penalty < best.0 {
+                best = (penalty, mask);
+            }
+            qr.apply_mask(mask);
+        }
+        qr.apply_mask(best.1);
+        qr.draw_format_bits(best.1);
+        Ok(qr)
+    }
+
+    pub fn module(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.is_function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+        for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4i32..=4 {
+                for dx in -4i32..=4 {
+                    let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                    if (0..size as i32).contains(&x) && (0..size as i32).contains(&y) {
+                        let dist = dx.abs().max(dy.abs());
+                        self.set_function(x as usize, y as usize, dist != 2 && dist != 4);
+                    }
+                }
+            }
+        }
+        let positions = alignment_positions(version, size);
+        let n = positions.len();
+        for i in 0..n {
+            for j in 0..n {
+                if [(0, 0), (0, n - 1), (n - 1, 0)].contains(&(i, j)) {
+                    continue;
+                }
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        let dist = dx.abs().max(dy.abs());
+                        self.set_function(
+                            (positions[i] as i32 + dx) as usize,
+                            (positions[j] as i32 + dy) as usize,
+                            dist != 1,
+                        );
+                    }
+                }
+            }
+        }
+        // Reserve the format areas; the real bits are drawn once the mask is known.
+        self.draw_format_bits(0);
+        if version >= 7 {
+            let mut rem = version as u32;
+            for _ in 0..12 {
+                rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+            }
+            let bits = ((version as u32) << 12) | rem;
+            for i in 0..18 {
+                let a = size - 11 + i % 3;
+                let b = i / 3;
+                self.set_function(a, b, bit(bits, i as u32));
+                self.set_function(b, a, bit(bits, i as u32));
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        let size = self.size;
+        let data = (ECL_M_BITS << 3) | mask;
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = ((data << 10) | rem) ^ 0x5412;
+
+        for i in 0..6 {
+            self.set_function(8, i, bit(bits, i as u32));
+        }
+        self.set_function(8, 7, bit(bits, 6));
+        self.set_function(8, 8, bit(bits, 7));
+        self.set_function(7, 8, bit(bits, 8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(bits, i as u32));
+        }
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(bits, i as u32));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(bits, i as u32));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let size = self.size as i32;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..size {
+                for j in 0..2 {
+                    let x = (right - j) as usize;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { size - 1 - vert } else { vert } as usize;
+                    if !self.is_function[y * self.size + x] && i < data.len() * 8 {
+                        self.modules[y * self.size + x] =
+                            bit(u32::from(data[i >> 3]), 7 - (i as u32 & 7));
+                        i += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                if invert && !self.is_function[index] {
+                    self.modules[index] = !self.modules[index];
+                }
+            }
+        }
+    }
+
+    /// Penalty score from the four standard rules; lower is easier to scan.
+    fn penalty(&self) -> u32 {
+        let size = self.size;
+        let mut penalty = 0;
+        let finder_like = [
+            true, false, true, true, true, false, true, false, false, false, false,
+        ];
+        for horizontal in [true, false] {
+            for a in 0..size {
+                let line: Vec<bool> = (0..size)
+                    .map(|b| {
+                        if horizontal {
+                            self.module(b, a)
+                        } else {
+                            self.module(a, b)
+                        }
+                    })
+                    .collect();
+                let mut run = 1;
+                for b in 1..=size {
+                    if b < size && line[b] == line[b - 1] {
+                        run += 1;
+                        continue;
+                    }
+                    if run >= 5 {
+                        penalty += 3 + (run - 5) as u32;
+                    }
+                    run = 1;
+                }
+                for window in line.windows(finder_like.len()) {
+                    if window.iter().eq(finder_like.iter())
+                        || window.iter().eq(finder_like.iter().rev())
+                    {
+                        penalty += 40;
+                    }
+                }
+            }
+        }
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.module(x, y);
+                if color == self.module(x + 1, y)
+                    && color == self.module(x, y + 1)
+                    && color == self.module(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+        let total = (size * size) as i64;
+        let dark = self.modules.iter().filter(|m| **m).count() as i64;
+        let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+        penalty + (k * 10) as u32
+    }
+
+    /// Black-on-white SVG with the standard four-module quiet zone.
+    pub fn to_svg(&self) -> String {
+        let border = 4;
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.module(x, y) {
+                    path.push_str(&format!("M{},{}h1v1h-1z", x + border, y + border));
+                }
+            }
+        }
+        let dim = self.size + border * 2;
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {dim} {dim}\" shape-rendering=\"crispEdges\"><rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/><path d=\"{path}\" fill=\"#000000\"/></svg>",
+            dim = dim,
+            path = path
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reed_solomon_matches_reference() {
+        // "HELLO WORLD" at 1-M, the usual worked example.
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        assert_eq!(
+            rs_remainder(&data, &rs_divisor(10)),
+            vec![196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+        );
+    }
+
+    #[test]
+    fn test_encode_layout() {
+        let qr = QrCode::encode(b"https://t.me/example_bot?start=pair").unwrap();
+        // 35 bytes needs version 3 at level M.
+        assert_eq!(qr.size, 29);
+        // Finder pattern corners and the always-dark module.
+        assert!(qr.module(0, 0) && qr.module(qr.size - 1, 0) && qr.module(0, qr.size - 1));
+        assert!(!qr.module(7, 7));
+        assert!(qr.module(8, qr.size - 8));
+        assert!(qr.to_svg().starts_with("<svg"));
+        assert!(QrCode::encode(&[b'x'; 300]).is_err());
+    }
+}
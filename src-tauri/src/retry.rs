@@ -0,0 +1,258 @@
+//! Retry with exponential backoff and jitter for flaky operations.
+//!
+//! Each kind of operation has a named `RetryPolicy`. `retry` and `retry_async` call the
+//! operation until it succeeds, the policy's attempts run out or `should_retry` rejects the
+//! error (a wrong API key will not start working on the third try). `on_retry` hears about
+//! each failed attempt before the wait, so callers can surface it as progress. Waits end
+//! early when the app shuts down.
+
+use rand::Rng;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Total tries, including the first.
+    pub attempts: usize,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: u32,
+    /// Each delay is randomised by up to this fraction either way.
+    pub jitter: f64,
+}
+
+/// Gateway coming up after `openclaw gateway start`: a steady poll, no backoff.
+pub const GATEWAY_STARTUP: RetryPolicy = RetryPolicy {
+    attempts: 8,
+    initial_delay: Duration::from_secs(3),
+    max_delay: Duration::from_secs(3),
+    multiplier: 1,
+    jitter: 0.0,
+};
+
+/// Registry pings and other reachability checks.
+pub const NETWORK_CHECK: RetryPolicy = RetryPolicy {
+    attempts: 3,
+    initial_delay: Duration::from_secs(1),
+    max_delay: Duration::from_secs(8),
+    multiplier: 2,
+    jitter: 0.2,
+};
+
+/// Read-only openclaw CLI queries, which fail while the gateway restarts.
+pub const CLI: RetryPolicy = RetryPolicy {
+    attempts: 3,
+    initial_delay: Duration::from_millis(500),
+    max_delay: Duration::from_secs(4),
+    multiplier: 2,
+    jitter: 0.2,
+};
+
+/// Provider and bot credential checks; rate limits and 5xx deserve a longer pause.
+pub const PROVIDER_VALIDATION: RetryPolicy = RetryPolicy {
+    attempts: 3,
+    initial_delay: Duration::from_secs(2),
+    max_delay: Duration::from_secs(10),
+    multiplier: 3,
+    jitter: 0.25,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attempt {
+    /// 1-based number of the attempt that just failed.
+    pub number: usize,
+    pub of: usize,
+    /// Wait before the next attempt.
+    pub delay: Duration,
+}
+
+impl Attempt {
+    pub fn describe(&self, error: &str) -> String {
+        format!(
+            "{} (attempt {}/{}, retrying in {:.1}s)",
+            error.trim(),
+            self.number,
+            self.of,
+            self.delay.as_secs_f64()
+        )
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before attempt `number + 1`, without jitter.
+    pub fn base_delay(&self, number: usize) -> Duration {
+        let factor = (self.multiplier.max(1) as u64)
+            .checked_pow(number.saturating_sub(1) as u32)
+            .unwrap_or(u64::MAX);
+        let millis = (self.initial_delay.as_millis() as u64).saturating_mul(factor);
+        Duration::from_millis(millis).min(self.max_delay)
+    }
+
+    pub fn delay(&self, number: usize) -> Duration {
+        let base = self.base_delay(number);
+        if self.jitter <= 0.0 {
+            return base;
+        }
+        let spread = rand::thread_rng().gen_range(-self.jitter..=self.jitter);
+        base.mul_f64(1.0 + spread)
+    }
+}
+
+/// Network failures, timeouts, HTTP 429 and 5xx. Matches the messages this app builds
+/// around reqwest and CLI errors.
+pub fn is_transient(error: &str) -> bool {
+    let lower = error.to_lowercase();
+    [
+        "failed to reach",
+        "timed out",
+        "timeout",
+        "connection refused",
+        "econnrefused",
+        "econnreset",
+        "eai_again",
+        "temporarily unavailable",
+        "http 429",
+        "http 500",
+        "http 502",
+        "http 503",
+        "http 504",
+    ]
+    .iter()
+    .any(|needle| lower.contains(needle))
+}
+
+pub fn retry<T, E, F, S, R>(
+    policy: &RetryPolicy,
+    mut operation: F,
+    should_retry: S,
+    mut on_retry: R,
+) -> Result<T, E>
+where
+    F: FnMut(usize) -> Result<T, E>,
+    S: Fn(&E) -> bool,
+    R: FnMut(&Attempt, &E),
+{
+    let mut number = 1;
+    loop {
+        match operation(number) {
+            Ok(value) => return Ok(value),
+            Err(e) if number >= policy.attempts || !should_retry(&e) => return Err(e),
+            Err(e) => {
+                let attempt = Attempt {
+                    number,
+                    of: policy.attempts,
+                    delay: policy.delay(number),
+                };
+                on_retry(&attempt, &e);
+                if !crate::lifecycle::sleep(attempt.delay) {
+                    return Err(e);
+                }
+            }
+        }
+        number += 1;
+    }
+}
+
+pub async fn retry_async<T, E, F, Fut, S, R>(
+    policy: &RetryPolicy,
+    mut operation: F,
+    should_retry: S,
+    mut on_retry: R,
+) -> Result<T, E>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+    S: Fn(&E) -> bool,
+    R: FnMut(&Attempt, &E),
+{
+    let mut shutdown = crate::lifecycle::subscribe();
+    let mut number = 1;
+    loop {
+        match operation(number).await {
+            Ok(value) => return Ok(value),
+            Err(e) if number >= policy.attempts || !should_retry(&e) => return Err(e),
+            Err(e) => {
+                let attempt = Attempt {
+                    number,
+                    of: policy.attempts,
+                    delay: policy.delay(number),
+                };
+                on_retry(&attempt, &e);
+                tokio::select! {
+                    _ = tokio::time::sleep(attempt.delay) => {}
+                    _ = shutdown.recv() => return Err(e),
+                }
+            }
+        }
+        number += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAST: RetryPolicy = RetryPolicy {
+        attempts: 4,
+        initial_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(3),
+        multiplier: 2,
+        jitter: 0.5,
+    };
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        assert_eq!(NETWORK_CHECK.base_delay(1), Duration::from_secs(1));
+        assert_eq!(NETWORK_CHECK.base_delay(3), Duration::from_secs(4));
+        assert_eq!(NETWORK_CHECK.base_delay(9), Duration::from_secs(8));
+        assert_eq!(GATEWAY_STARTUP.delay(5), Duration::from_secs(3));
+        for _ in 0..50 {
+            let delay = PROVIDER_VALIDATION.delay(1);
+            assert!(delay >= Duration::from_millis(1500) && delay <= Duration::from_millis(2500));
+        }
+    }
+
+    #[test]
+    fn test_retry_stops_on_success_or_permanent_error() {
+        let mut seen = Vec::new();
+        let result: Result<usize, String> = retry(
+            &FAST,
+            |n| {
+                if n < 3 {
+                    Err("HTTP 503".to_string())
+                } else {
+                    Ok(n)
+                }
+            },
+            |e| is_transient(e),
+            |attempt, _| seen.push(attempt.number),
+        );
+        assert_eq!(result, Ok(3));
+        assert_eq!(seen, vec![1, 2]);
+
+        let mut calls = 0;
+        let result: Result<(), String> = retry(
+            &FAST,
+            |_| {
+                calls += 1;
+                Err("HTTP 401: invalid key".to_string())
+            },
+            |e| is_transient(e),
+            |_, _| {},
+        );
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let result: Result<(), String> = retry(
+            &FAST,
+            |_| {
+                calls += 1;
+                Err("timed out".to_string())
+            },
+            |e| is_transient(e),
+            |_, _| {},
+        );
+        assert!(result.is_err());
+        assert_eq!(calls, FAST.attempts);
+    }
+}
@@ -0,0 +1,352 @@
+//! Audit of `openclaw.json` and the secret files next to it for weak settings.
+//!
+//! Each finding carries a severity that is deducted from a 100-point score and, where the app
+//! can repair it, the id of a fix the UI offers as a single button (`apply_security_fix`).
+//! `harden_script` restricts the OpenClaw root to its owner: 0700 directories, 0600 secrets
+//! and logs.
+
+use serde::Serialize;
+
+/// Files under the OpenClaw root that hold keys or tokens, as shell globs.
+pub const SECRET_FILES: &[&str] = &[
+    "openclaw.json",
+    ".env",
+    "agents/*/agent/auth-profiles.json",
+    "credentials/*",
+];
+/// Directories under the OpenClaw root only the owner should be able to enter.
+pub const PRIVATE_DIRS: &[&str] = &[".", "agents", "credentials", "logs", "workspace"];
+/// Gateway log directory outside the root.
+pub const TMP_LOG_DIR: &str = "/tmp/openclaw";
+pub const MIN_TOKEN_LEN: usize = 24;
+const WEAK_TOKENS: &[&str] = &[
+    "admin",
+    "changeme",
+    "change-me",
+    "default",
+    "openclaw",
+    "password",
+    "secret",
+    "test",
+    "token",
+];
+/// Fewer distinct characters than this marks a token as trivially guessable.
+const MIN_DISTINCT_CHARS: usize = 8;
+
+pub const FIX_ENABLE_TOKEN_AUTH: &str = "enable_token_auth";
+pub const FIX_REGENERATE_TOKEN: &str = "regenerate_token";
+pub const FIX_RESTRICT_PERMISSIONS: &str = "restrict_permissions";
+/// Followed by `:<channel>`.
+pub const FIX_DM_PAIRING: &str = "dm_pairing";
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Finding {
+    pub id: String,
+    /// `high`, `medium` or `low`.
+    pub severity: String,
+    pub title: String,
+    pub detail: String,
+    /// Id to pass to `apply_security_fix`, when the app can repair this.
+    pub fix: Option<String>,
+    pub fix_label: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Report {
+    /// 100 minus the severity weight of every finding, floored at 0.
+    pub score: u8,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PermissionChange {
+    pub path: String,
+    /// Octal modes, e.g. `644` → `600`.
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileMode {
+    /// Relative to the OpenClaw root.
+    pub path: String,
+    pub mode: u32,
+}
+
+fn weight(severity: &str) -> u8 {
+    match severity {
+        "high" => 30,
+        "medium" => 15,
+        _ => 5,
+    }
+}
+
+fn finding(
+    id: &str,
+    severity: &str,
+    title: &str,
+    detail: String,
+    fix: Option<(String, &str)>,
+) -> Finding {
+    Finding {
+        id: id.to_string(),
+        severity: severity.to_string(),
+        title: title.to_string(),
+        detail,
+        fix_label: fix.as_ref().map(|(_, label)| label.to_string()),
+        fix: fix.map(|(id, _)| id),
+    }
+}
+
+/// Why `token` is weak, if it is.
+pub fn token_weakness(token: Option<&str>) -> Option<String> {
+    let token = token.map(str::trim).unwrap_or_default();
+    if token.is_empty() {
+        return Some("No gateway token is set.".to_string());
+    }
+    if WEAK_TOKENS
+        .iter()
+        .any(|weak| token.eq_ignore_ascii_case(weak))
+    {
+        return Some("The gateway token is a well-known default value.".to_string());
+    }
+    let mut distinct: Vec<char> = token.chars().collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.len() < MIN_DISTINCT_CHARS {
+        return Some(
+            "The gateway token repeats too few characters to be hard to guess.".to_string(),
+        );
+    }
+    if token.len() < MIN_TOKEN_LEN {
+        return Some(format!(
+            "The gateway token is {} characters long; use at least {}.",
+            token.len(),
+            MIN_TOKEN_LEN
+        ));
+    }
+    None
+}
+
+/// `<octal mode> <path>` lines, as printed by `stat -c '%a %n'` or `stat -f '%Lp %N'`.
+pub fn parse_stat_output(output: &str) -> Vec<FileMode> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (mode, path) = line.trim().split_once(' ')?;
+            Some(FileMode {
+                path: path.trim_start_matches("./").to_string(),
+                mode: u32::from_str_radix(mode, 8).ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Shell script, run from anywhere, that sets 0700 on the private directories (and
+/// `workspace` when it lives elsewhere) and 0600 on secret files and logs. `root` is a
+/// fragment for double quotes. Prints each change as `<old> <new> <path>`.
+pub fn harden_script(root: &str, workspace: Option<&str>) -> String {
+    let mut script = format!(
+        "cd \"{}\" 2>/dev/null || exit 0; \
+         h() {{ m=$(stat -c %a \"$2\" 2>/dev/null || stat -f %Lp \"$2\"); \
+         [ \"$m\" != \"$1\" ] && chmod \"$1\" \"$2\" 2>/dev/null && echo \"$m $1 $2\"; }}; ",
+        root
+    );
+    let dirs = PRIVATE_DIRS
+        .iter()
+        .map(|dir| dir.to_string())
+        .chain(workspace.map(crate::shell_single_quote))
+        .chain([TMP_LOG_DIR.to_string()]);
+    for dir in dirs {
+        script.push_str(&format!("[ -d {0} ] && h 700 {0}; ", dir));
+    }
+    script.push_str(&format!(
+        "for f in {} logs/* {}/*; do [ -f \"$f\" ] && h 600 \"$f\"; done; true",
+        SECRET_FILES.join(" "),
+        TMP_LOG_DIR
+    ));
+    script
+}
+
+/// Changes printed by `harden_script`.
+pub fn parse_permission_changes(output: &str) -> Vec<PermissionChange> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.trim().splitn(3, ' ');
+            let from = parts.next()?;
+            let to = parts.next()?;
+            let path = parts.next()?;
+            Some(PermissionChange {
+                path: path.to_string(),
+                from: from.to_string(),
+                to: to.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn audit(config: &serde_json::Value, files: &[FileMode]) -> Report {
+    let mut findings = Vec::new();
+    let gateway = config.get("gateway");
+    let text =
+        |value: Option<&serde_json::Value>| value.and_then(|v| v.as_str()).map(str::to_string);
+    let bind = text(gateway.and_then(|g| g.get("bind"))).unwrap_or_else(|| "loopback".to_string());
+    let exposed = bind != "loopback";
+    let auth = gateway.and_then(|g| g.get("auth"));
+    let auth_mode = text(auth.and_then(|a| a.get("mode"))).unwrap_or_else(|| "token".to_string());
+
+    if auth_mode == "none" || auth_mode == "off" {
+        if exposed {
+            findings.push(finding(
+                "gateway_auth_disabled",
+                "high",
+                "Gateway auth is off on a network-facing bind",
+                format!(
+                    "The gateway listens on '{}' without authentication, so anyone who can reach it can control your agents.",
+                    bind
+                ),
+                Some((FIX_ENABLE_TOKEN_AUTH.to_string(), "Require a token")),
+            ));
+        }
+    } else if auth_mode == "token" {
+        if let Some(weakness) = token_weakness(text(auth.and_then(|a| a.get("token"))).as_deref()) {
+            findings.push(finding(
+                "gateway_token_weak",
+                if exposed { "high" } else { "medium" },
+                "Weak gateway token",
+                weakness,
+                Some((FIX_REGENERATE_TOKEN.to_string(), "Generate a strong token")),
+            ));
+        }
+    }
+
+    let loose: Vec<&FileMode> = files.iter().filter(|f| f.mode & 0o077 != 0).collect();
+    if !loose.is_empty() {
+        let listed: Vec<String> = loose
+            .iter()
+            .map(|f| format!("{} ({:o})", f.path, f.mode & 0o777))
+            .collect();
+        let readable = loose.iter().any(|f| f.mode & 0o044 != 0);
+        findings.push(finding(
+            "secret_files_exposed",
+            if readable { "high" } else { "medium" },
+            if readable {
+                "Secret files are readable by other users"
+            } else {
+                "Secret files are writable by other users"
+            },
+            format!(
+                "Other accounts on this machine can access: {}.",
+                listed.join(", ")
+            ),
+            Some((
+                FIX_RESTRICT_PERMISSIONS.to_string(),
+                "Restrict to your user",
+            )),
+        ));
+    }
+
+    if let Some(channels) = config.get("channels").and_then(|c| c.as_object()) {
+        for (name, channel) in channels {
+            if channel.get("dmPolicy").and_then(|p| p.as_str()) == Some("open") {
+                findings.push(finding(
+                    &format!("dm_policy_open:{}", name),
+                    "medium",
+                    "Anyone can message your agent",
+                    format!(
+                        "The {} channel accepts direct messages from anyone. Pairing only lets in people you approve.",
+                        name
+                    ),
+                    Some((format!("{}:{}", FIX_DM_PAIRING, name), "Switch to pairing")),
+                ));
+            }
+        }
+    }
+
+    let penalty: u32 = findings
+        .iter()
+        .map(|f| u32::from(weight(&f.severity)))
+        .sum();
+    Report {
+        score: 100u32.saturating_sub(penalty) as u8,
+        findings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_scores_weak_settings() {
+        let config = serde_json::json!({
+            "gateway": { "bind": "lan", "auth": { "mode": "token", "token": "changeme" } },
+            "channels": {
+                "telegram": { "dmPolicy": "open" },
+                "discord": { "dmPolicy": "pairing" }
+            }
+        });
+        let files =
+            parse_stat_output("600 openclaw.json\n644 ./agents/main/agent/auth-profiles.json\n");
+        assert_eq!(files[1].path, "agents/main/agent/auth-profiles.json");
+        let report = audit(&config, &files);
+        let ids: Vec<&str> = report.findings.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "gateway_token_weak",
+                "secret_files_exposed",
+                "dm_policy_open:telegram"
+            ]
+        );
+        assert_eq!(report.findings[0].severity, "high");
+        assert_eq!(
+            report.findings[2].fix.as_deref(),
+            Some("dm_pairing:telegram")
+        );
+        assert_eq!(report.score, 25);
+
+        let config = serde_json::json!({
+            "gateway": { "bind": "loopback", "auth": { "mode": "none" } }
+        });
+        assert_eq!(audit(&config, &[]).score, 100);
+    }
+
+    #[test]
+    fn test_harden_script() {
+        let script = harden_script("$HOME/.openclaw", Some("/srv/agent work"));
+        assert!(script.starts_with("cd \"$HOME/.openclaw\" 2>/dev/null || exit 0;"));
+        assert!(script.contains("[ -d credentials ] && h 700 credentials;"));
+        assert!(script.contains("[ -d '/srv/agent work' ] && h 700 '/srv/agent work';"));
+        assert!(script.contains(
+            "for f in openclaw.json .env agents/*/agent/auth-profiles.json credentials/* logs/* /tmp/openclaw/*;"
+        ));
+        assert_eq!(
+            parse_permission_changes("644 600 openclaw.json\n755 700 /srv/agent work\n"),
+            [
+                PermissionChange {
+                    path: "openclaw.json".to_string(),
+                    from: "644".to_string(),
+                    to: "600".to_string(),
+                },
+                PermissionChange {
+                    path: "/srv/agent work".to_string(),
+                    from: "755".to_string(),
+                    to: "700".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_token_weakness() {
+        assert!(token_weakness(None).is_some());
+        assert!(token_weakness(Some("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa")).is_some());
+        assert!(token_weakness(Some("short-but-varied1")).is_some());
+        assert_eq!(
+            token_weakness(Some("Xk29fQm3LrT8vBn1Yp6Hs4Dw7Gc0Ja5E")),
+            None
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Gateway service state straight from the service manager.
+//!
+//! `openclaw gateway status` reports what the CLI believes; these parsers read launchd
+//! (`launchctl print`) and systemd (`systemctl --user show`) directly, so the app can tell a
+//! missing service from one that is installed but not loaded, and a running gateway from one
+//! the manager keeps restarting. On Windows the gateway is a systemd user unit inside WSL.
+
+pub const LAUNCHD_LABEL: &str = "ai.openclaw.gateway";
+pub const SYSTEMD_UNIT: &str = "openclaw-gateway.service";
+
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ServiceState {
+    /// `not_installed`, `not_loaded`, `running`, `crashing` or `stopped`.
+    pub state: String,
+    pub pid: Option<u32>,
+    pub last_exit_code: Option<i32>,
+    /// Starts (launchd) or automatic restarts (systemd) the manager has recorded.
+    pub restarts: Option<u32>,
+    /// Where launchd sends the gateway's stderr, if configured.
+    pub stderr_path: Option<String>,
+    /// The manager's own state words, e.g. `active/running` or `spawn scheduled`.
+    pub detail: String,
+}
+
+/// `launchctl print gui/<uid>/ai.openclaw.gateway` output, or `None` when launchd does not
+/// know the service (installed but not loaded, if the plist exists).
+pub fn parse_launchctl_print(output: &str) -> Option<ServiceState> {
+    let field = |name: &str| {
+        output.lines().find_map(|line| {
+            let (key, value) = line.trim().split_once(" = ")?;
+            (key == name).then(|| value.trim().to_string())
+        })
+    };
+    let detail = field("state")?;
+    let pid = field("pid").and_then(|v| v.parse().ok());
+    let last_exit_code = field("last exit code").and_then(|v| v.parse().ok());
+    let restarts = field("runs").and_then(|v| v.parse().ok());
+    let state = if detail == "running" && pid.is_some() {
+        "running".to_string()
+    } else if last_exit_code.is_some_and(|code: i32| code != 0) || detail.contains("spawn") {
+        "crashing".to_string()
+    } else {
+        "stopped".to_string()
+    };
+    Some(ServiceState {
+        state,
+        pid,
+        last_exit_code,
+        restarts,
+        stderr_path: field("stderr path"),
+        detail,
+    })
+}
+
+/// `systemctl --user show openclaw-gateway.service` output (`Key=Value` lines).
+pub fn parse_systemctl_show(output: &str) -> ServiceState {
+    let field = |name: &str| {
+        output.lines().find_map(|line| {
+            let (key, value) = line.trim().split_once('=')?;
+            (key == name).then(|| value.trim().to_string())
+        })
+    };
+    let load = field("LoadState").unwrap_or_default();
+    let active = field("ActiveState").unwrap_or_default();
+    let sub = field("SubState").unwrap_or_default();
+    let pid = field("MainPID")
+        .and_then(|v| v.parse().ok())
+        .filter(|pid| *pid != 0);
+    let last_exit_code = field("ExecMainStatus").and_then(|v| v.parse().ok());
+    let state = match (load.as_str(), active.as_str(), sub.as_str()) {
+        ("not-found", _, _) | ("", _, _) => "not_installed".to_string(),
+        (_, "active", "running") => "running".to_string(),
+        (_, "failed", _) | (_, "activating", "auto-restart") => "crashing".to_string(),
+        (_, "activating", _) if last_exit_code.is_some_and(|code: i32| code != 0) => {
+            "crashing".to_string()
+        }
+        _ => "stopped".to_string(),
+    };
+    ServiceState {
+        state,
+        pid,
+        last_exit_code,
+        restarts: field("NRestarts").and_then(|v| v.parse().ok()),
+        stderr_path: None,
+        detail: format!("{}/{}", active, sub),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_launchctl_print() {
+        let running = "gui/501/ai.openclaw.gateway = {\n\tactive count = 1\n\tpath = /Users/me/Library/LaunchAgents/ai.openclaw.gateway.plist\n\tstate = running\n\n\tprogram = /usr/local/bin/node\n\tstderr path = /Users/me/.openclaw/logs/gateway.err.log\n\truns = 1\n\tpid = 4242\n\tlast exit code = (never exited)\n}";
+        let status = parse_launchctl_print(running).unwrap();
+        assert_eq!(status.state, "running");
+        assert_eq!(status.pid, Some(4242));
+        assert_eq!(status.last_exit_code, None);
+        assert_eq!(
+            status.stderr_path.as_deref(),
+            Some("/Users/me/.openclaw/logs/gateway.err.log")
+        );
+
+        let crashing = "gui/501/ai.openclaw.gateway = {\n\tstate = spawn scheduled\n\truns = 14\n\tlast exit code = 1\n}";
+        let status = parse_launchctl_print(crashing).unwrap();
+        assert_eq!(status.state, "crashing");
+        assert_eq!(status.restarts, Some(14));
+        assert!(parse_launchctl_print(
+            "Could not find service \"ai.openclaw.gateway\" in domain for port"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_parse_systemctl_show() {
+        let status = parse_systemctl_show(
+            "LoadState=loaded\nActiveState=activating\nSubState=auto-restart\nMainPID=0\nExecMainStatus=1\nNRestarts=7\n",
+        );
+        assert_eq!(status.state, "crashing");
+        assert_eq!(status.pid, None);
+        assert_eq!(status.restarts, Some(7));
+        assert_eq!(
+            parse_systemctl_show(
+                "LoadState=loaded\nActiveState=active\nSubState=running\nMainPID=99\n"
+            )
+            .state,
+            "running"
+        );
+        assert_eq!(
+            parse_systemctl_show("LoadState=not-found\nActiveState=inactive\n").state,
+            "not_installed"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! The app's own preferences (theme, last-used provider, notifications, default workspace),
+//! stored as `settings.json` in the app data directory.
+//!
+//! The file carries a `version`; `migrate` upgrades older layouts step by step on load so
+//! fields can be renamed or restructured without losing what users already chose.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SETTINGS_FILE: &str = "settings.json";
+pub const CURRENT_VERSION: u32 = 1;
+const THEMES: &[&str] = &["system", "light", "dark"];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct NotificationPrefs {
+    pub budget_alerts: bool,
+    pub gateway_down: bool,
+    pub setup_finished: bool,
+}
+
+impl Default for NotificationPrefs {
+    fn default() -> Self {
+        NotificationPrefs {
+            budget_alerts: true,
+            gateway_down: true,
+            setup_finished: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AppSettings {
+    pub version: u32,
+    /// One of `THEMES`.
+    pub theme: String,
+    pub last_provider: Option<String>,
+    pub notifications: NotificationPrefs,
+    pub default_workspace_path: Option<String>,
+    /// Overrides the OpenClaw root (`~/.openclaw`); see `paths`.
+    pub openclaw_root: Option<String>,
+    /// Extra profiles besides the implicit `default` one; see `profiles`.
+    pub profiles: Vec<crate::profiles::Profile>,
+    pub active_profile: String,
+    /// Port of the localhost health/metrics endpoint; `None` keeps it off.
+    pub health_endpoint_port: Option<u16>,
+    /// Opt-in crash and error reporting; see `telemetry`.
+    pub telemetry_enabled: bool,
+    /// Sentry DSN or report URL; `None` uses the endpoint built into the app, if any.
+    pub telemetry_endpoint: Option<String>,
+    /// Extra workspace template index fetched on top of the bundled one; see `templates`.
+    pub workspace_templates_url: Option<String>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            version: CURRENT_VERSION,
+            theme: "system".to_string(),
+            last_provider: None,
+            notifications: NotificationPrefs::default(),
+            default_workspace_path: None,
+            openclaw_root: None,
+            profiles: Vec::new(),
+            active_profile: crate::profiles::DEFAULT_PROFILE.to_string(),
+            health_endpoint_port: None,
+            telemetry_enabled: false,
+            telemetry_endpoint: None,
+            workspace_templates_url: None,
+        }
+    }
+}
+
+fn settings_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(SETTINGS_FILE)
+}
+
+/// Upgrades a stored settings document to `CURRENT_VERSION`. Each version bump adds an
+/// `if version < N` step that rewrites the previous layout; a document without a version
+/// predates versioning and is treated as version 0.
+pub fn migrate(mut value: serde_json::Value) -> serde_json::Value {
+    let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if version < u64::from(CURRENT_VERSION) {
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("version".to_string(), serde_json::json!(CURRENT_VERSION));
+        }
+    }
+    value
+}
+
+pub fn validate(settings: &AppSettings) -> Result<(), String> {
+    if !THEMES.contains(&settings.theme.as_str()) {
+        return Err(format!(
+            "Unknown theme '{}'; expected one of {}",
+            settings.theme,
+            THEMES.join(", ")
+        ));
+    }
+    if let Some(path) = &settings.default_workspace_path {
+        if path.trim().is_empty() {
+            return Err("Default workspace path cannot be empty.".to_string());
+        }
+    }
+    if settings.active_profile != crate::profiles::DEFAULT_PROFILE
+        && crate::profiles::find_profile(settings, &settings.active_profile).is_none()
+    {
+        return Err(format!("Unknown profile '{}'", settings.active_profile));
+    }
+    if let Some(root) = &settings.openclaw_root {
+        if !(root.starts_with('/') || root.starts_with("~/")) {
+            return Err(format!(
+                "OpenClaw root '{}' must be an absolute path or start with ~/",
+                root
+            ));
+        }
+    }
+    if settings.health_endpoint_port == Some(0) {
+        return Err("Health endpoint port must be between 1 and 65535.".to_string());
+    }
+    if let Some(endpoint) = &settings.telemetry_endpoint {
+        if !(endpoint.starts_with("https://") || endpoint.starts_with("http://")) {
+            return Err(format!(
+                "Error reporting endpoint '{}' must be an http(s) URL",
+                endpoint
+            ));
+        }
+    }
+    if let Some(url) = &settings.workspace_templates_url {
+        if !url.starts_with("https://") {
+            return Err(format!(
+                "Workspace template index '{}' must be an https URL",
+                url
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Stored settings, migrated to the current version; defaults when missing or unreadable.
+pub fn load(app_dir: &Path) -> AppSettings {
+    fs::read_to_string(settings_path(app_dir))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .map(migrate)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(app_dir: &Path, settings: &AppSettings) -> Result<(), String> {
+    validate(settings)?;
+    fs::create_dir_all(app_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let mut settings = settings.clone();
+    settings.version = CURRENT_VERSION;
+    let raw = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(settings_path(app_dir), raw).map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_unversioned_document_keeps_values() {
+        let migrated = migrate(serde_json::json!({
+            "theme": "dark",
+            "notifications": { "gateway_down": false }
+        }));
+        let settings: AppSettings = serde_json::from_value(migrated).unwrap();
+        assert_eq!(settings.version, CURRENT_VERSION);
+        assert_eq!(settings.theme, "dark");
+        assert!(!settings.notifications.gateway_down);
+        assert!(settings.notifications.budget_alerts);
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_theme() {
+        let mut settings = AppSettings::default();
+        assert!(validate(&settings).is_ok());
+        settings.theme = "neon".to_string();
+        assert!(validate(&settings).is_err());
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("clawsetup-settings-{}", std::process::id()));
+        let settings = AppSettings {
+            last_provider: Some("anthropic".to_string()),
+            ..Default::default()
+        };
+        save(&dir, &settings).unwrap();
+        assert_eq!(load(&dir), settings);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,258 @@
+//! Opt-in crash and error reporting.
+//!
+//! Nothing is recorded unless the user turns reporting on. Panics and failed long-running
+//! commands become `Report`s with secrets and the home directory scrubbed, appended to a queue
+//! file in the app data directory and uploaded in the background, so reports made offline are
+//! sent once the endpoint is reachable again. The endpoint is either a Sentry DSN (sentry.io,
+//! self-hosted Sentry or GlitchTip) or any URL that accepts the report as a JSON POST.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const QUEUE_FILE: &str = "telemetry-queue.jsonl";
+pub const MAX_QUEUED: usize = 100;
+const MAX_MESSAGE_CHARS: usize = 2000;
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+lazy_static! {
+    static ref QUEUE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Report {
+    /// 32 hex digits, as Sentry expects for event ids.
+    pub id: String,
+    pub timestamp: u64,
+    /// `panic` or `command_error`.
+    pub kind: String,
+    /// The failing task, or the source location of a panic.
+    pub source: String,
+    pub message: String,
+    pub app_version: String,
+    pub os: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SentryDsn {
+    pub store_url: String,
+    pub public_key: String,
+}
+
+/// `text` without the home directory path or anything that looks like a secret, truncated.
+pub fn scrub(text: &str, home: Option<&str>) -> String {
+    let text = match home.filter(|home| home.len() > 1) {
+        Some(home) => text.replace(home, "~"),
+        None => text.to_string(),
+    };
+    let (text, _) = crate::log_export::redact_line(&text);
+    text.chars().take(MAX_MESSAGE_CHARS).collect()
+}
+
+pub fn new_report(kind: &str, source: &str, message: &str, home: Option<&str>) -> Report {
+    Report {
+        id: uuid::Uuid::new_v4().simple().to_string(),
+        timestamp: crate::unix_now_secs(),
+        kind: kind.to_string(),
+        source: scrub(source, home),
+        message: scrub(message, home),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+    }
+}
+
+/// `https://<key>@<host>/<project>` → the project's store endpoint and public key.
+pub fn parse_dsn(dsn: &str) -> Option<SentryDsn> {
+    let url = url::Url::parse(dsn.trim()).ok()?;
+    let public_key = url.username();
+    let host = url.host_str()?;
+    let path = url.path().trim_end_matches('/');
+    let (prefix, project) = path.rsplit_once('/')?;
+    if public_key.is_empty() || project.is_empty() {
+        return None;
+    }
+    let port = url.port().map(|p| format!(":{}", p)).unwrap_or_default();
+    Some(SentryDsn {
+        store_url: format!(
+            "{}://{}{}{}/api/{}/store/",
+            url.scheme(),
+            host,
+            port,
+            prefix,
+            project
+        ),
+        public_key: public_key.to_string(),
+    })
+}
+
+pub fn sentry_event(report: &Report) -> serde_json::Value {
+    serde_json::json!({
+        "event_id": report.id,
+        "timestamp": report.timestamp,
+        "platform": "native",
+        "level": if report.kind == "panic" { "fatal" } else { "error" },
+        "logger": report.kind,
+        "transaction": report.source,
+        "message": { "formatted": report.message },
+        "release": format!("clawnetes@{}", report.app_version),
+        "tags": { "os": report.os, "kind": report.kind },
+    })
+}
+
+fn queue_path(dir: &Path) -> PathBuf {
+    dir.join(QUEUE_FILE)
+}
+
+fn read_queue_unlocked(dir: &Path) -> Vec<Report> {
+    fs::read_to_string(queue_path(dir))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn write_queue_unlocked(dir: &Path, reports: &[Report]) -> Result<(), String> {
+    if reports.is_empty() {
+        let _ = fs::remove_file(queue_path(dir));
+        return Ok(());
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let lines: Vec<String> = reports
+        .iter()
+        .filter_map(|r| serde_json::to_string(r).ok())
+        .collect();
+    fs::write(queue_path(dir), lines.join("\n") + "\n")
+        .map_err(|e| format!("Failed to save telemetry queue: {}", e))
+}
+
+pub fn queued(dir: &Path) -> usize {
+    let _lock = QUEUE_LOCK.lock();
+    read_queue_unlocked(dir).len()
+}
+
+/// Appends `report`, dropping the oldest reports beyond `MAX_QUEUED`.
+pub fn enqueue(dir: &Path, report: Report) -> Result<(), String> {
+    let _lock = QUEUE_LOCK.lock();
+    let mut reports = read_queue_unlocked(dir);
+    reports.push(report);
+    let excess = reports.len().saturating_sub(MAX_QUEUED);
+    reports.drain(..excess);
+    write_queue_unlocked(dir, &reports)
+}
+
+pub fn clear_queue(dir: &Path) {
+    let _lock = QUEUE_LOCK.lock();
+    let _ = fs::remove_file(queue_path(dir));
+}
+
+fn send(client: &reqwest::blocking::Client, endpoint: &str, report: &Report) -> Result<(), String> {
+    let request = match parse_dsn(endpoint) {
+        Some(dsn) => client
+            .post(&dsn.store_url)
+            .header(
+                "X-Sentry-Auth",
+                format!(
+                    "Sentry sentry_version=7, sentry_key={}, sentry_client=clawnetes/{}",
+                    dsn.public_key, report.app_version
+                ),
+            )
+            .json(&sentry_event(report)),
+        None => client.post(endpoint).json(report),
+    };
+    let response = request.send().map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("The endpoint answered {}", response.status()))
+    }
+}
+
+/// Uploads queued reports oldest first, stopping at the first failure so the rest stay queued.
+/// Returns how many were sent.
+pub fn flush(dir: &Path, endpoint: &str) -> Result<usize, String> {
+    let pending = {
+        let _lock = QUEUE_LOCK.lock();
+        read_queue_unlocked(dir)
+    };
+    if pending.is_empty() {
+        return Ok(0);
+    }
+    let client = reqwest::blocking::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut sent = Vec::new();
+    let mut failure = None;
+    for report in &pending {
+        match send(&client, endpoint, report) {
+            Ok(()) => sent.push(report.id.clone()),
+            Err(e) => {
+                failure = Some(e);
+                break;
+            }
+        }
+    }
+    // Reports queued during the upload are kept.
+    let _lock = QUEUE_LOCK.lock();
+    let remaining: Vec<Report> = read_queue_unlocked(dir)
+        .into_iter()
+        .filter(|r| !sent.contains(&r.id))
+        .collect();
+    write_queue_unlocked(dir, &remaining)?;
+    match failure {
+        Some(e) => Err(format!(
+            "Sent {} of {} error reports: {}",
+            sent.len(),
+            pending.len(),
+            e
+        )),
+        None => Ok(sent.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dsn() {
+        let dsn = parse_dsn("https://abc123@o42.ingest.sentry.io/4505").unwrap();
+        assert_eq!(
+            dsn.store_url,
+            "https://o42.ingest.sentry.io/api/4505/store/"
+        );
+        assert_eq!(dsn.public_key, "abc123");
+        assert_eq!(
+            parse_dsn("http://key@sentry.internal:9000/sub/7")
+                .unwrap()
+                .store_url,
+            "http://sentry.internal:9000/sub/api/7/store/"
+        );
+        assert_eq!(parse_dsn("https://reports.example.com/ingest"), None);
+    }
+
+    #[test]
+    fn test_queue_scrubs_and_caps_reports() {
+        let dir = std::env::temp_dir().join(format!("clawsetup-telemetry-{}", std::process::id()));
+        let report = new_report(
+            "command_error",
+            "gateway",
+            "Failed to read /home/me/.openclaw/openclaw.json: token=abc123",
+            Some("/home/me"),
+        );
+        assert_eq!(
+            report.message,
+            "Failed to read ~/.openclaw/openclaw.json: token=[REDACTED]"
+        );
+        assert_eq!(report.id.len(), 32);
+        for _ in 0..MAX_QUEUED + 5 {
+            enqueue(&dir, report.clone()).unwrap();
+        }
+        assert_eq!(queued(&dir), MAX_QUEUED);
+        clear_queue(&dir);
+        assert_eq!(queued(&dir), 0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
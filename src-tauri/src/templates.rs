@@ -0,0 +1,165 @@
+//! Workspace templates: ready-made personas an agent can start from.
+//!
+//! A template bundles workspace files (identity, soul, notes), ClawHub skills and scheduled
+//! prompts. The curated index ships with the app in `templates/workspace-templates.json`; a
+//! remote index set in the app settings can add more, but never replaces a bundled template.
+//! Installing compares the template's files with the agent's workspace first, so existing
+//! files are only overwritten when the user says so.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const BUNDLED_INDEX: &str = include_str!("../templates/workspace-templates.json");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TemplateSchedule {
+    pub name: String,
+    /// Five-field cron expression.
+    pub cron: String,
+    pub prompt: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WorkspaceTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Workspace-relative path, e.g. `IDENTITY.md`, to file content.
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    /// ClawHub skill names.
+    #[serde(default)]
+    pub skills: Vec<String>,
+    #[serde(default)]
+    pub schedules: Vec<TemplateSchedule>,
+    /// `bundled` or `remote`.
+    #[serde(default)]
+    pub source: String,
+}
+
+#[derive(Deserialize)]
+struct TemplateIndex {
+    templates: Vec<WorkspaceTemplate>,
+}
+
+pub fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// A relative path that stays inside the workspace and out of its `.git`.
+pub fn is_safe_path(path: &str) -> bool {
+    !path.is_empty()
+        && !path.starts_with('/')
+        && !path.contains('\\')
+        && path
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != ".." && part != ".git")
+}
+
+fn validate(template: &WorkspaceTemplate) -> Result<(), String> {
+    if !is_valid_id(&template.id) {
+        return Err(format!("Invalid template id '{}'", template.id));
+    }
+    if let Some(path) = template.files.keys().find(|path| !is_safe_path(path)) {
+        return Err(format!(
+            "Template '{}' writes outside the workspace: {}",
+            template.id, path
+        ));
+    }
+    if let Some(skill) = template
+        .skills
+        .iter()
+        .find(|s| s.is_empty() || s.starts_with('-') || s.contains(char::is_whitespace))
+    {
+        return Err(format!(
+            "Template '{}' has an invalid skill name '{}'",
+            template.id, skill
+        ));
+    }
+    Ok(())
+}
+
+/// The templates in index JSON `raw`, tagged with `source`.
+pub fn parse_index(raw: &str, source: &str) -> Result<Vec<WorkspaceTemplate>, String> {
+    let index: TemplateIndex =
+        serde_json::from_str(raw).map_err(|e| format!("Invalid template index: {}", e))?;
+    index
+        .templates
+        .into_iter()
+        .map(|mut template| {
+            validate(&template)?;
+            template.source = source.to_string();
+            Ok(template)
+        })
+        .collect()
+}
+
+pub fn bundled() -> Vec<WorkspaceTemplate> {
+    parse_index(BUNDLED_INDEX, "bundled").unwrap_or_default()
+}
+
+/// `bundled` followed by the `remote` templates whose ids are not already taken.
+pub fn merge(
+    mut bundled: Vec<WorkspaceTemplate>,
+    remote: Vec<WorkspaceTemplate>,
+) -> Vec<WorkspaceTemplate> {
+    for template in remote {
+        if !bundled.iter().any(|t| t.id == template.id) {
+            bundled.push(template);
+        }
+    }
+    bundled
+}
+
+/// Paths of `template` files that exist in the workspace with different content, given
+/// `existing(path)`, the current content of a workspace file.
+pub fn conflicts(
+    template: &WorkspaceTemplate,
+    existing: impl Fn(&str) -> Option<String>,
+) -> Vec<String> {
+    template
+        .files
+        .iter()
+        .filter(|(path, content)| existing(path).is_some_and(|current| current != **content))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_index_and_conflicts() {
+        let templates = bundled();
+        assert!(!templates.is_empty());
+        assert!(templates.iter().all(|t| t.source == "bundled"));
+
+        let remote = parse_index(
+            r#"{"templates":[
+                {"id":"research-assistant","name":"Other","description":""},
+                {"id":"chef","name":"Chef","description":"Plans meals","files":{"IDENTITY.md":"Chef","SOUL.md":"Cook"}}
+            ]}"#,
+            "remote",
+        )
+        .unwrap();
+        let merged = merge(templates.clone(), remote);
+        assert_eq!(merged.len(), templates.len() + 1);
+        let chef = merged.last().unwrap();
+        assert_eq!(chef.source, "remote");
+        let existing = |path: &str| match path {
+            "IDENTITY.md" => Some("Someone else".to_string()),
+            "SOUL.md" => Some("Cook".to_string()),
+            _ => None,
+        };
+        assert_eq!(conflicts(chef, existing), ["IDENTITY.md"]);
+
+        let escaping = r#"{"templates":[{"id":"x","name":"X","description":"","files":{"../openclaw.json":"{}"}}]}"#;
+        assert!(parse_index(escaping, "remote").is_err());
+        assert!(!is_safe_path("skills/../../x"));
+        assert!(is_safe_path("skills/notes/SKILL.md"));
+    }
+}
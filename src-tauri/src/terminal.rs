@@ -0,0 +1,214 @@
+//! In-app `openclaw` terminal.
+//!
+//! The app keeps the line editor itself and only starts a process once a full command line is
+//! entered, so nothing but the allow-listed `openclaw` subcommands below can run. Each command
+//! runs on a pseudo-terminal from `portable_pty` (ConPTY under WSL), which gives it colours,
+//! progress bars, prompts and Ctrl-C, and takes resizes while it runs.
+
+/// `openclaw` subcommands (matched as leading words) the terminal will run.
+pub const ALLOWED_COMMANDS: &[&str] = &[
+    "--help",
+    "--version",
+    "agents list",
+    "channels list",
+    "channels status",
+    "config get",
+    "cron list",
+    "cron status",
+    "doctor",
+    "gateway probe",
+    "gateway status",
+    "health",
+    "help",
+    "logs",
+    "memory search",
+    "memory status",
+    "models list",
+    "models status",
+    "pairing approve",
+    "pairing list",
+    "plugins list",
+    "security audit",
+    "sessions",
+    "skills info",
+    "skills list",
+    "status",
+];
+
+pub const PROMPT: &str = "openclaw> ";
+
+/// Splits a command line into words, honouring single quotes, double quotes and backslashes.
+pub fn split_command_line(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                let escaped = chars.next().ok_or("Unfinished escape at end of line")?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err("Unterminated quote".to_string());
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// The `openclaw` arguments for `line`, or why it may not run here.
+pub fn check_command(line: &str) -> Result<Vec<String>, String> {
+    let mut args = split_command_line(line)?;
+    if args.first().map(String::as_str) == Some("openclaw") {
+        args.remove(0);
+    }
+    let allowed = ALLOWED_COMMANDS.iter().any(|command| {
+        let words: Vec<&str> = command.split(' ').collect();
+        args.len() >= words.len() && args.iter().zip(&words).all(|(a, w)| a == w)
+    });
+    if allowed {
+        Ok(args)
+    } else {
+        Err(format!(
+            "Not available in the app terminal: {}",
+            args.first().map(String::as_str).unwrap_or_default()
+        ))
+    }
+}
+
+/// `command` as a `portable_pty` command: same program, arguments, environment and directory.
+pub fn pty_command(command: &std::process::Command) -> portable_pty::CommandBuilder {
+    let mut builder = portable_pty::CommandBuilder::new(command.get_program());
+    builder.args(command.get_args());
+    for (name, value) in command.get_envs() {
+        match value {
+            Some(value) => builder.env(name, value),
+            None => builder.env_remove(name),
+        }
+    }
+    if let Some(dir) = command.get_current_dir() {
+        builder.cwd(dir);
+    }
+    builder
+}
+
+/// What the user typed while no command was running.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditorEvent {
+    /// Text to echo back to the terminal.
+    Echo(String),
+    /// A finished line (Enter), or an empty one after Ctrl-C.
+    Line(String),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LineEditor {
+    buffer: String,
+}
+
+impl LineEditor {
+    /// Applies raw terminal input: printable characters, Backspace, Enter and Ctrl-C.
+    pub fn feed(&mut self, input: &str) -> Vec<EditorEvent> {
+        let mut events = Vec::new();
+        let mut echo = String::new();
+        for c in input.chars() {
+            match c {
+                '\r' | '\n' => {
+                    echo.push_str("\r\n");
+                    events.push(EditorEvent::Echo(std::mem::take(&mut echo)));
+                    events.push(EditorEvent::Line(std::mem::take(&mut self.buffer)));
+                }
+                '\u{3}' => {
+                    echo.push_str("^C\r\n");
+                    events.push(EditorEvent::Echo(std::mem::take(&mut echo)));
+                    self.buffer.clear();
+                    events.push(EditorEvent::Line(String::new()));
+                }
+                '\u{7f}' | '\u{8}' if self.buffer.pop().is_some() => {
+                    echo.push_str("\u{8} \u{8}");
+                }
+                c if !c.is_control() => {
+                    self.buffer.push(c);
+                    echo.push(c);
+                }
+                _ => {}
+            }
+        }
+        if !echo.is_empty() {
+            events.push(EditorEvent::Echo(echo));
+        }
+        events
+    }
+
+    /// Clears and returns the unfinished line.
+    pub fn take_pending(&mut self) -> String {
+        std::mem::take(&mut self.buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_command() {
+        assert_eq!(
+            check_command("openclaw config get 'agents.defaults.model'").unwrap(),
+            ["config", "get", "agents.defaults.model"]
+        );
+        assert_eq!(
+            check_command("status --deep").unwrap(),
+            ["status", "--deep"]
+        );
+        assert!(check_command("config set gateway.mode remote").is_err());
+        assert!(check_command("gateway").is_err());
+        assert!(check_command("status; rm -rf ~").is_err());
+        assert!(check_command("logs \"unterminated").is_err());
+        assert_eq!(
+            split_command_line(r#"a "b c" d\ e ''"#).unwrap(),
+            ["a", "b c", "d e", ""]
+        );
+        let mut command = std::process::Command::new("/usr/bin/openclaw");
+        command
+            .args(["logs", "--follow"])
+            .env("PATH", "/opt/bin")
+            .env_remove("NO_COLOR");
+        let builder = pty_command(&command);
+        assert_eq!(
+            builder.get_argv(),
+            &["/usr/bin/openclaw", "logs", "--follow"].map(std::ffi::OsString::from)
+        );
+        assert_eq!(builder.get_env("PATH"), Some("/opt/bin".as_ref()));
+        assert_eq!(builder.get_env("NO_COLOR"), None);
+    }
+
+    #[test]
+    fn test_line_editor() {
+        let mut editor = LineEditor::default();
+        assert_eq!(editor.feed("stat"), [EditorEvent::Echo("stat".to_string())]);
+        assert_eq!(
+            editor.feed("x\u{7f}us\r"),
+            [
+                EditorEvent::Echo("x\u{8} \u{8}us\r\n".to_string()),
+                EditorEvent::Line("status".to_string()),
+            ]
+        );
+        assert_eq!(
+            editor.feed("abc\u{3}"),
+            [
+                EditorEvent::Echo("abc^C\r\n".to_string()),
+                EditorEvent::Line(String::new()),
+            ]
+        );
+    }
+}